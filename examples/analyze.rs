@@ -0,0 +1,29 @@
+// File Purpose: moscope as a library, `cargo run --example analyze -- <BINARY>`
+
+use std::error::Error;
+use moscope::macho::analysis;
+use moscope::reporting::macho::ReportOptions;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let path = std::env::args().nth(1).ok_or("usage: analyze <BINARY>")?;
+    let data = std::fs::read(&path)?;
+
+    // Same report `moscope <BINARY> --format json` prints, just as a struct
+    let report = analysis::analyze(&data, &ReportOptions::default())?;
+
+    for arch in &report.architectures {
+        let symbols = arch.symbols.as_ref().map_or(0, Vec::len);
+        let dylibs = arch.dylibs.as_ref().map_or(0, Vec::len);
+        println!(
+            "{} ({}) {}: {} symbols, {} dylibs, uuid {}",
+            arch.cpu_type,
+            arch.cpu_subtype,
+            arch.filetype,
+            symbols,
+            dylibs,
+            arch.uuid.as_deref().unwrap_or("-"),
+        );
+    }
+
+    Ok(())
+}
@@ -2,4 +2,8 @@
 // https://google.github.io/comprehensive-rust/comprehensive-rust.pdf
 //      As of 01/15/26, located in: VII Day 4: Morning > Testing > Other Types of Testing
 pub mod macho;
-pub mod reporting;
\ No newline at end of file
+pub mod reporting;
+
+// In-memory Mach-O builder for round-trip tests, see src/test_util.rs
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
\ No newline at end of file
@@ -0,0 +1,685 @@
+// File Purpose: Parse one architecture slice end to end (what the default report is built from)
+
+use std::collections::HashMap;
+use std::error::Error;
+use crate::macho::build_version::{self, BuildVersion};
+use crate::macho::codesign::{self, ParsedCodeDirectory};
+use crate::macho::constants::*;
+use crate::macho::diagnostics::Diagnostic;
+use crate::macho::dyld::{self, Fixup};
+use crate::macho::dylibs::{self, ParsedDylib};
+use crate::macho::data_in_code::{self, DataInCodeEntry};
+use crate::macho::encryption::{self, EncryptionInfo};
+use crate::macho::error::MoscopeError;
+use crate::macho::entry_point::{self, EntryPoint};
+use crate::macho::fat::{self, FatArch};
+use crate::macho::fileset::{self, FilesetEntry};
+use crate::macho::hash;
+use crate::macho::header::{MachOSlice, ParsedMachOHeader};
+use crate::macho::load_commands::{self, LinkEditDataCommand, LoadCommand, MinVersion, SymsegCommand};
+use crate::macho::memory_image::{self, MachOMemoryImage};
+use crate::macho::notes::{self, Note};
+use crate::macho::parse::MachOFile;
+use crate::macho::rpaths::{self, ParsedRPath};
+use crate::macho::sections::SectionKind;
+use crate::macho::segments::{self, ParsedSegment};
+use crate::macho::source_version;
+use crate::macho::sub_images::{self, ParsedSubImage};
+use crate::macho::symtab::{self, ParsedString, ParsedSymbol};
+use crate::macho::utils::{byte_array_to_string, PhaseTimer};
+use crate::macho::uuid::{self, ParsedUuid};
+use crate::reporting::macho::{ArchitectureReport, MachOReport, ReportOptions, build_architecture_report};
+
+/*
+The per-slice pipeline, in the order the pieces depend on each other:
+
+    header + load commands
+        --> segments, dylibs, rpaths, symtab / dysymtab / dyld_info, linkedit_data
+        --> symbols (+ indirect symbol resolution, section names)
+        --> strings (VM image, or file offsets with --no-vm-image)
+        --> fixups
+        --> diagnostics, then the output filters (dedup, sort, truncate)
+
+main runs this once per selected slice (through parse::MachOFile); tests and library users
+get the exact same ParsedSlice the CLI reports on. analyze() is the whole thing for every
+slice of a file, bytes in, MachOReport out, for anyone using moscope as a library.
+*/
+
+// The CLI switches that change what gets parsed (the --no-* output switches live in ReportOptions)
+#[derive(Debug, Clone)]
+pub struct SliceOptions {
+    pub no_vm_image: bool,
+    pub min_string_length: usize,
+    pub section_min_lengths: Option<HashMap<String, usize>>,
+    pub string_sections: Option<Vec<String>>, // only these sections
+    pub skip_sections: Option<Vec<String>>,
+    pub string_pattern: Option<String>,
+    pub ascii_only: bool,
+    pub raw_strings: bool,
+    pub dedup_rpaths: bool,
+    pub max_strings: Option<usize>,
+    pub debug_symbols_only: bool,
+    pub include_debug_symbols: bool,
+    pub max_symbols: Option<usize>,
+}
+
+impl Default for SliceOptions {
+    fn default() -> Self {
+        SliceOptions {
+            no_vm_image: false,
+            min_string_length: 4, // same as --min-string-length
+            section_min_lengths: None,
+            string_sections: None,
+            skip_sections: None,
+            string_pattern: None,
+            ascii_only: false,
+            raw_strings: false,
+            dedup_rpaths: false,
+            max_strings: None,
+            debug_symbols_only: false,
+            include_debug_symbols: false,
+            max_symbols: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ParsedSlice {
+    pub slice: MachOSlice,
+    pub header: ParsedMachOHeader,
+    pub load_commands: Vec<LoadCommand>,
+    pub linkedit_data: Vec<LinkEditDataCommand>,
+    pub segments: Vec<ParsedSegment>,
+    pub dylibs: Vec<ParsedDylib>,
+    pub rpaths: Vec<ParsedRPath>,
+    pub dyld_environment: Vec<String>, // every LC_DYLD_ENVIRONMENT, in load command order
+    pub symbols: Vec<ParsedSymbol>,
+    pub strings: Vec<ParsedString>,
+    pub fixups: Vec<Fixup>,
+    pub diagnostics: Vec<Diagnostic>,
+    pub uuid: Option<ParsedUuid>,
+    pub code_directories: Vec<ParsedCodeDirectory>, // empty when unsigned
+    pub symseg: Option<SymsegCommand>, // LC_SYMSEG, very old binaries only
+    pub symtab_stats: Option<symtab::SymtabStats>, // None without LC_SYMTAB
+    pub stripped: bool,                            // no symbols and no export trie
+    pub build_version: Option<BuildVersion>,       // LC_BUILD_VERSION, older binaries use LC_VERSION_MIN_* instead
+    pub min_version: Option<MinVersion>,           // that LC_VERSION_MIN_*, some binaries carry both
+    pub entry_point: Option<EntryPoint>,           // LC_MAIN, or LC_UNIXTHREAD's pc, executables only
+    pub source_version: Option<String>,            // LC_SOURCE_VERSION, already formatted A.B.C.D.E
+    pub target_triple: Option<String>,             // LC_TARGET_TRIPLE, recent linkers only
+    pub data_in_code: Vec<DataInCodeEntry>,        // empty without LC_DATA_IN_CODE (or with an empty one)
+    pub dyld_info: Option<dyld::DyldInfoCommand>,  // LC_DYLD_INFO(_ONLY), chained fixup binaries have none
+    pub bound_symbols: Vec<dyld::BoundSymbol>,     // decoded from dyld_info's bind opcodes, empty without one
+    pub encryption: Option<EncryptionInfo>,        // LC_ENCRYPTION_INFO(_64), App Store / FairPlay binaries
+    pub notes: Vec<Note>,                          // every LC_NOTE in load command order, mostly core files
+    pub fileset_entries: Vec<FilesetEntry>,        // MH_FILESET members (kernel caches), in load command order
+    pub sub_images: Vec<ParsedSubImage>,           // LC_SUB_FRAMEWORK / CLIENT / UMBRELLA / LIBRARY, umbrella frameworks
+    pub timings: PhaseTimer,           // how long each parsing phase took, for --timings
+}
+
+impl ParsedSlice {
+    // fat_align is left for the caller, only the fat header knows it
+    pub fn build_report(&self, data: &[u8], opts: &ReportOptions) -> Result<ArchitectureReport, Box<dyn Error>> {
+        let (cputype, cpusubtype) = self.header.header.cpu();
+        let mut report = build_architecture_report(
+            cputype,
+            cpusubtype,
+            &self.header.header,
+            &self.load_commands,
+            &self.linkedit_data,
+            &self.segments,
+            &self.dylibs,
+            &self.rpaths,
+            &self.symbols,
+            &self.strings,
+            &self.fixups,
+            &self.diagnostics,
+            Some(hash::slice_sha256(data, &self.slice)?),
+            load_commands::find_prebind_cksum(data, &self.load_commands, self.header.kind.is_be()).ok().flatten(), // legacy, never worth failing the report over
+            opts,
+        );
+        if opts.dump_unknown_load_commands && let Some(reports) = report.load_commands.as_mut() {
+            for (lc_report, lc) in reports.iter_mut().zip(&self.load_commands) {
+                lc_report.body = load_commands::unknown_load_command_body(data, lc);
+            }
+        }
+        report.symseg = self.symseg.as_ref().map(SymsegCommand::build_report);
+        report.symtab_stats = self.symtab_stats.as_ref().map(symtab::SymtabStats::build_report);
+        report.stripped = self.stripped;
+        report.uuid = self.uuid.as_ref().map(ParsedUuid::to_canonical);
+        report.build_version = self.build_version.as_ref().map(BuildVersion::build_report);
+        report.min_version = self.min_version.as_ref().map(MinVersion::build_report);
+        report.entry_point = self.entry_point.as_ref().map(EntryPoint::build_report);
+        report.source_version = self.source_version.clone();
+        report.target_triple = self.target_triple.clone();
+        report.dyld_environment = self.dyld_environment.clone();
+        report.dyld_info = self.dyld_info.as_ref().map(dyld::DyldInfoCommand::build_report);
+        report.encryption = self.encryption.as_ref().map(EncryptionInfo::build_report);
+        report.encrypted = self.encryption.is_some_and(|info| info.is_encrypted());
+        if opts.include_dylibs && !self.sub_images.is_empty() {
+            report.sub_images = Some(self.sub_images.iter().map(ParsedSubImage::build_report).collect());
+        }
+        if !self.fileset_entries.is_empty() {
+            report.fileset_entries = Some(self.fileset_entries.iter().map(FilesetEntry::build_report).collect());
+        }
+        if !self.notes.is_empty() {
+            report.notes = Some(self.notes.iter().map(|note| note.build_report(data, self.slice.offset)).collect());
+        }
+        if opts.include_fixups && !self.bound_symbols.is_empty() {
+            report.bound_symbols = Some(self.bound_symbols.iter().map(|bind| bind.build_report(&self.segments, &self.dylibs)).collect());
+        }
+        if !self.data_in_code.is_empty() {
+            report.data_in_code = Some(self.data_in_code.iter().map(|entry| entry.build_report(&self.segments)).collect());
+        }
+        if !self.code_directories.is_empty() {
+            report.code_signature = Some(codesign::build_code_signature_report(&report.cpu_type, &report.cpu_subtype, &self.code_directories, &[]));
+        }
+        Ok(report)
+    }
+}
+
+pub fn parse_slice(data: &[u8], slice: &MachOSlice, opts: &SliceOptions) -> Result<ParsedSlice, MoscopeError> {
+    let mut timer = PhaseTimer::new();
+
+    // Read Mach-O header and load command table for this slice
+    let (thin_header, load_commands_vec) = load_commands::read_slice_load_commands(data, slice)?;
+    let is_be = thin_header.kind.is_be();
+    timer.lap("header + load commands");
+
+    let parsed_segments = segments::parse_segments(data, &load_commands_vec, is_be)?;
+    timer.lap("segments");
+    let mut parsed_dylibs = Vec::new();
+    let mut parsed_rpaths = Vec::new();
+    let mut dyld_environment = Vec::new();
+    let mut parsed_symbols: Vec<ParsedSymbol> = Vec::new();
+    let mut parsed_strings = Vec::new();
+    let mut parsed_fixups: Vec<Fixup> = Vec::new();
+    let mut parsed_linkedit_data = Vec::new();
+    let mut slice_diagnostics = Vec::new();
+
+    // LC_SYMTAB doesn't contain symbols it just declares info
+    // So we need to keep track of it so we can get all the symbols
+    let mut symtab_cmd: Option<symtab::SymtabCommand> = None;
+    let mut dysymtab_cmd: Option<symtab::DYSymtabCommand> = None;
+    let mut dyldinfo_cmd: Option<dyld::DyldInfoCommand> = None;
+    let mut symseg_cmd: Option<SymsegCommand> = None;
+    let mut build_version = None;
+    let mut min_version = None;
+    let mut entry = None;
+    let mut source_version = None;
+    let mut target_triple = None;
+    let mut encryption: Option<EncryptionInfo> = None;
+    let mut parsed_notes = Vec::new();
+    let mut parsed_sub_images = Vec::new();
+    let mut fileset_entries = Vec::new();
+
+    for lc in &load_commands_vec {
+        let base_cmd = lc.cmd & !LC_REQ_DYLD;
+
+        match base_cmd {
+            LC_ID_DYLIB
+            | LC_LOAD_DYLIB
+            | LC_LOAD_WEAK_DYLIB
+            | LC_REEXPORT_DYLIB
+            | LC_LAZY_LOAD_DYLIB
+            | LC_LOAD_UPWARD_DYLIB => {
+                parsed_dylibs.push(dylibs::parse_dylib(data, lc, is_be)?);
+            }
+            LC_RPATH => {
+                parsed_rpaths.push(rpaths::parse_rpath(data, lc, is_be)?);
+            }
+            LC_FILESET_ENTRY => match fileset::parse_fileset_entry(data, lc, is_be) {
+                Ok(entry) => fileset_entries.push(entry),
+                Err(e) => slice_diagnostics.push(Diagnostic::error("fileset-entry-malformed", e.to_string())),
+            },
+            LC_DYLD_ENVIRONMENT => {
+                dyld_environment.push(rpaths::parse_dyld_environment(data, lc, is_be)?);
+            }
+            LC_SUB_FRAMEWORK | LC_SUB_CLIENT | LC_SUB_UMBRELLA | LC_SUB_LIBRARY => {
+                parsed_sub_images.push(sub_images::parse_sub_image(data, lc, is_be)?);
+            }
+            LC_SYMTAB => {
+                symtab_cmd = Some(symtab::read_symtab_command(data, lc, is_be)?);
+            }
+            LC_DYSYMTAB => {
+                dysymtab_cmd = Some(symtab::read_dysymtab_command(data, lc, is_be)?);
+            }
+            LC_DYLD_INFO => {
+                dyldinfo_cmd = Some(dyld::read_dyld_info_command(data, lc, is_be)?);
+            }
+            LC_SYMSEG => {
+                symseg_cmd = Some(load_commands::parse_symseg(data, lc, is_be)?);
+            }
+            // Only informational, a bad one is a diagnostic and not a reason to stop
+            LC_BUILD_VERSION => match build_version::parse_build_version(data, lc, is_be) {
+                Ok(bv) => build_version = Some(bv),
+                Err(e) => slice_diagnostics.push(Diagnostic::error("build-version-malformed", e.to_string())),
+            },
+            LC_MAIN | LC_UNIXTHREAD => {
+                let (cputype, _) = thin_header.header.cpu();
+                let parsed = match base_cmd {
+                    LC_MAIN => entry_point::parse_main(data, lc, is_be, &parsed_segments),
+                    _ => entry_point::parse_unixthread(data, lc, is_be, cputype, &parsed_segments),
+                };
+                match parsed {
+                    // LC_MAIN wins if someone put both in
+                    Ok(found) if entry.is_none() || base_cmd == LC_MAIN => {
+                        slice_diagnostics.extend(entry_point::stack_size_diagnostic(&found));
+                        entry = Some(found);
+                    }
+                    Ok(_) => {}
+                    Err(e) => slice_diagnostics.push(Diagnostic::error("entry-point-malformed", e.to_string())),
+                }
+            }
+            LC_SOURCE_VERSION => match source_version::parse_source_version(data, lc, is_be) {
+                Ok(version) => source_version = Some(version),
+                Err(e) => slice_diagnostics.push(Diagnostic::error("source-version-malformed", e.to_string())),
+            },
+            LC_TARGET_TRIPLE => match build_version::parse_target_triple(data, lc, is_be) {
+                Ok(triple) => target_triple = Some(triple),
+                Err(e) => slice_diagnostics.push(Diagnostic::error("target-triple-malformed", e.to_string())),
+            },
+            LC_ENCRYPTION_INFO | LC_ENCRYPTION_INFO_64 => match encryption::parse_encryption_info(data, lc, is_be) {
+                Ok(info) => encryption = Some(info),
+                Err(e) => slice_diagnostics.push(Diagnostic::error("encryption-info-malformed", e.to_string())),
+            },
+            LC_NOTE => match notes::parse_note(data, lc, is_be) {
+                Ok(note) => parsed_notes.push(note),
+                Err(e) => slice_diagnostics.push(Diagnostic::error("note-malformed", e.to_string())),
+            },
+            LC_VERSION_MIN_MACOSX | LC_VERSION_MIN_IPHONEOS | LC_VERSION_MIN_TVOS | LC_VERSION_MIN_WATCHOS => {
+                match load_commands::parse_version_min(data, lc, is_be) {
+                    Ok(min) => min_version = Some(min),
+                    Err(e) => slice_diagnostics.push(Diagnostic::error("version-min-malformed", e.to_string())),
+                }
+            }
+
+            // Everything shaped like linkedit_data_command (code signature, function starts, atom info, ...)
+            _ if load_commands::is_linkedit_data_command(lc.cmd) => {
+                parsed_linkedit_data.extend(load_commands::parse_linkedit_data_or_diagnose(data, lc, is_be, &mut slice_diagnostics));
+            }
+            _ => {}
+        }
+    }
+
+    timer.lap("dylibs, rpaths, linkedit commands");
+
+    // now we take a look @ our symtab_cmd and parse symbols
+    let mut symtab_stats = None;
+    if let Some(symtab) = symtab_cmd {
+        symtab_stats = Some(symtab::symtab_stats(data, &symtab, slice.offset, thin_header.kind.is_64(), is_be)?);
+        parsed_symbols = symtab::parse_symbols(data, &symtab, slice.offset, thin_header.kind.is_64(), is_be)?;
+    }
+
+    // now for indirect symbols ingestion
+    let indirect_symbols = match &dysymtab_cmd {
+        Some(dysym) => Some(symtab::parse_indirect_symbols(data, dysym, slice.offset, is_be)?),
+        None => None,
+    };
+
+    timer.lap("symbols");
+
+    // Strings extraction using the vm addressing instead of file offsets
+    //      because our file offsets method fails for dyld extracted binaries
+
+    // Build VM image once per slice, unless --no-vm-image asked for plain file offsets
+    // Shared cache dylibs always get one, their section offsets point into the cache file
+    let use_vm_image = !opts.no_vm_image || (thin_header.header.flags() & MH_DYLIB_IN_CACHE) != 0;
+    let vm_image = use_vm_image.then(|| MachOMemoryImage::new(&parsed_segments, data, slice.offset));
+    timer.lap("vm image");
+
+    // Before building report grab the strings
+    // Iterate only __cstring sections; each byte is scanned once
+    // Real cost of this is not O(n^3) like I thought but it's actually roughly O(C + B + K)
+    // C = total number of sections across all segments
+    // B = total bytes scanned in __cstring
+    // K = number of extracted strings
+    let mut section_index = 0;
+    for segment in &parsed_segments {
+        for section in &segment.sections {
+            section_index += 1; // 1-based across all segments, same numbering as n_sect
+
+            // Check if we should skip this section
+            if let Some(ref skip) = opts.skip_sections {
+                let sectname = byte_array_to_string(&section.sectname);
+                if skip.contains(&sectname) {
+                    continue;
+                }
+            }
+
+            // Check if we should only process specific sections
+            if let Some(ref only) = opts.string_sections {
+                let sectname = byte_array_to_string(&section.sectname);
+                if !only.contains(&sectname) {
+                    continue;
+                }
+            }
+
+            // Other C string literal sections (selectors, class names, ...) only when asked for by name
+            let sectname = byte_array_to_string(&section.sectname);
+            let requested = (section.flags & SECTION_TYPE) == S_CSTRING_LITERALS
+                && opts.section_min_lengths.as_ref().is_some_and(|m| m.contains_key(&sectname));
+            let min_len = symtab::min_length_for(&sectname, opts.section_min_lengths.as_ref(), opts.min_string_length);
+
+            // UTF-16 CFString literals, never printable ASCII so --ascii-only drops them wholesale
+            let is_ustring = section.sectname == SECT_USTRING && !opts.ascii_only;
+
+            // Ciphertext only ever yields noise, say why the section has no strings instead
+            let wants_strings = (section.kind == SectionKind::CString || requested || is_ustring) && section.size > 0;
+            if wants_strings && let Some(info) = encryption.filter(|info| info.covers(section.offset as u64, section.size)) {
+                slice_diagnostics.push(Diagnostic::warning(
+                    "strings-skipped-encrypted",
+                    format!("{},{} lies in the encrypted range (cryptid {}), its strings were not extracted", byte_array_to_string(&section.segname), sectname, info.crypt_id),
+                ));
+            } else if wants_strings {
+                let sec_bytes = match &vm_image {
+                    Some(image) => image.read_section(section),
+                    None => memory_image::read_section_from_file(data, section, slice.offset),
+                };
+                if let Some(sec_bytes) = sec_bytes {
+                    // Use filtered extraction if pattern provided, otherwise normal
+                    let extracted_strings = if let Some(ref pattern) = opts.string_pattern {
+                        let filtered = if is_ustring {
+                            symtab::filter_strings(symtab::extract_utf16le_strings(sec_bytes, 1), pattern)
+                        } else {
+                            symtab::extract_filtered_strings(sec_bytes, pattern, opts.ascii_only)
+                        };
+                        match filtered {
+                            Ok(strings) => strings,
+                            Err(e) => {
+                                eprintln!("Invalid regex pattern '{}': {}", pattern, e);
+                                Vec::new()
+                            }
+                        }
+                    } else if is_ustring {
+                        symtab::extract_utf16le_strings(sec_bytes, min_len)
+                    } else {
+                        symtab::extract_string_entries(sec_bytes, min_len, opts.ascii_only)
+                    };
+
+                    // Attach section info to string
+                    for s in extracted_strings {
+                        if s.value.is_empty() { continue; }
+                        parsed_strings.push(ParsedString {
+                            value: s.value,
+                            encoding: s.encoding,
+                            raw: opts.raw_strings.then_some(s.raw),
+                            segname: segment.segname,
+                            sectname: section.sectname,
+                            section_index,
+                            offset: s.offset as u64,
+                        });
+                    }
+                }
+            }
+
+            // If this section uses indirect symbols
+            if let Some(indirect) = &indirect_symbols && section.kind.uses_indirect_symbols() {
+                let start = section.reserved1 as usize;
+                let entry_size = if section.reserved2 != 0 {
+                    section.reserved2 as usize
+                } else {
+                    8 // arm64 defualt pointer/stub size
+                };
+
+                let count = (section.size as usize) / entry_size;
+
+                if start >= indirect.len() {
+                    continue; // section is bogus? metadata incorrect?
+                }
+
+                // Alright we have some new bounds checking here
+                // When testing on our sample binaries, nothing was wrong
+                // But one real binary on my mac panicked with:
+                //      index out of bounds: the len is 2349 but the index is 2349
+                // count --> What the section claims it needs (derived, anyway)
+                // max_count --> how many entries actually exist from `start` to the end of the indirect table
+                // safe_count --> the smaller of the two
+                let max_count = indirect.len() - start;
+                let safe_count = count.min(max_count);
+                for i in 0..safe_count {
+                    let raw = indirect[start + i];
+
+                    let flags = raw & (INDIRECT_SYMBOL_ABS | INDIRECT_SYMBOL_LOCAL);
+                    if flags != 0 {
+                        continue;
+                    }
+
+                    let indirect_index = (raw & !(INDIRECT_SYMBOL_ABS | INDIRECT_SYMBOL_LOCAL)) as usize;
+
+                    if indirect_index >= parsed_symbols.len() {
+                        continue;
+                    }
+
+                    let sym = &mut parsed_symbols[indirect_index];
+
+                    sym.indirect_sect = Some(byte_array_to_string(&section.sectname));
+                    sym.segname = Some(byte_array_to_string(&section.segname));
+                    sym.indirect_addr = Some(section.addr + (i as u64) * entry_size as u64); // now the undefined symbols can have an address like otool -Iv
+
+                    if sym.kind == symtab::SymbolKind::Undefined && sym.is_external {
+                        sym.kind = match byte_array_to_string(&section.sectname).as_str() {
+                            "__la_symbol_ptr" => symtab::SymbolKind::Lazy,
+                            "__stubs"         => symtab::SymbolKind::Stub,
+                            "__got"           => symtab::SymbolKind::Got,
+                            _                 => sym.kind,
+                        };
+                    }
+                }
+            }
+        }
+    }
+
+    let mut global_sect_index: u8 = 1;
+    // Put the section data into the hashmap
+    timer.lap("strings + indirect symbols");
+
+    let mut section_map = HashMap::new();
+    for segment in &parsed_segments {
+        for section in &segment.sections {
+            section_map.insert(global_sect_index, (
+                byte_array_to_string(&segment.segname),
+                byte_array_to_string(&section.sectname),
+            ));
+            global_sect_index += 1;
+        }
+    }
+
+    // Use the hashmap to map symbols to the segments/sections they live in
+    // I am using the hashmap because the other way I first thought was going to be quadratic time complexity
+    // This should be closer to linear
+    for sym in &mut parsed_symbols {
+        if let Some(idx) = sym.section.map(|s| s.0) && let Some((segname, sectname)) = section_map.get(&idx) {
+            sym.segname = Some(segname.clone());   // String
+            sym.sectname = Some(sectname.clone()); // String
+        }
+    }
+
+    // Library ordinals only mean something in a two-level namespace image, a flat one would
+    // have every import pinned to whatever garbage sits in n_desc's high byte
+    if thin_header.header.flags() & MH_TWOLEVEL != 0 {
+        for sym in &mut parsed_symbols {
+            sym.library = sym.resolve_library(&parsed_dylibs);
+        }
+    }
+
+    timer.lap("symbol sections");
+
+    // Apply fixups for this slice
+    if let Some(dyldinfo) = &dyldinfo_cmd {
+        // Fixups are resolved by VM address, so they still need the image even with --no-vm-image
+        let fixup_image = match vm_image {
+            Some(image) => image,
+            None => MachOMemoryImage::new(&parsed_segments, data, slice.offset),
+        };
+        parsed_fixups = Fixup::parse(
+            dyldinfo,
+            &parsed_segments,
+            0, // slide
+            &fixup_image,
+            data,
+            slice.offset,
+            thin_header.kind.is_64(),
+        )?;
+    }
+
+    timer.lap("fixups");
+
+    // Structural sanity checks, these never stop the report from being produced
+    slice_diagnostics.extend(segments::validate_section_bounds(&parsed_segments));
+    slice_diagnostics.extend(segments::validate_section_layout(&parsed_segments));
+    slice_diagnostics.extend(segments::validate_pagezero(&parsed_segments, &thin_header.header));
+    let (cputype, _) = thin_header.header.cpu();
+    slice_diagnostics.extend(segments::validate_segment_alignment(&parsed_segments, &thin_header.header, segments::page_size(cputype)));
+    slice_diagnostics.extend(rpaths::validate_rpaths(&parsed_rpaths));
+    if let Some(symseg) = &symseg_cmd {
+        slice_diagnostics.extend(load_commands::validate_symseg(symseg, hash::slice_bytes(data, slice)?.len() as u64));
+    }
+
+    if opts.dedup_rpaths {
+        rpaths::dedup_rpaths(&mut parsed_rpaths);
+    }
+
+    // Pin the order down before anything is cut, --max-strings keeps the same N every run
+    symtab::sort_strings(&mut parsed_strings);
+
+    // Before building the architecture report, apply max limit if specified
+    if let Some(max) = opts.max_strings {
+        parsed_strings.truncate(max);
+    }
+
+    if opts.debug_symbols_only {  // Keep only debug symbols
+        parsed_symbols.retain(|sym| sym.is_debug);
+    } else if !opts.include_debug_symbols {  // Take out debug symbols
+        parsed_symbols.retain(|sym| !sym.is_debug);
+    }
+
+    if let Some(limit) = opts.max_symbols {
+        parsed_symbols.truncate(limit);
+    }
+
+    // A malformed LC_UUID shouldn't sink the whole report, --uuid is where it's a hard error
+    let uuid = match uuid::find_uuid(data, &load_commands_vec) {
+        Ok(uuid) => uuid,
+        Err(e) => {
+            slice_diagnostics.push(Diagnostic::error("uuid-malformed", e.to_string()));
+            None
+        }
+    };
+
+    // Same for the signature, a broken SuperBlob is worth a diagnostic, not an aborted report
+    let mut code_directories = Vec::new();
+    if let Some(cmd) = parsed_linkedit_data.iter().find(|ld| ld.cmd == LC_CODE_SIGNATURE) {
+        match codesign::read_code_signature(data, slice.offset, cmd) {
+            Ok(directories) => code_directories = directories,
+            Err(e) => slice_diagnostics.push(Diagnostic::error("code-signature-malformed", e.to_string())),
+        }
+    }
+
+    let mut data_in_code = Vec::new();
+    if let Some(cmd) = parsed_linkedit_data.iter().find(|ld| ld.cmd == LC_DATA_IN_CODE) {
+        match data_in_code::parse_data_in_code(data, cmd, slice.offset, is_be) {
+            Ok(entries) => data_in_code = entries,
+            Err(e) => slice_diagnostics.push(Diagnostic::error("data-in-code-malformed", e.to_string())),
+        }
+    }
+
+    // The import list stripped binaries still have, no indirect symbol table needed
+    let mut bound_symbols = Vec::new();
+    if let Some(info) = dyldinfo_cmd.as_ref().filter(|info| info.bind_size > 0) {
+        match dyld::parse_bound_symbols(data, slice.offset, info, &parsed_segments, thin_header.kind.is_64()) {
+            Ok(binds) => bound_symbols = binds,
+            Err(e) => slice_diagnostics.push(Diagnostic::error("bind-opcodes-malformed", e.to_string())),
+        }
+    }
+
+    let has_export_trie = parsed_linkedit_data.iter().any(|ld| ld.cmd == LC_DYLD_EXPORTS_TRIE && ld.datasize > 0)
+        || dyldinfo_cmd.as_ref().is_some_and(|info| info.export_size > 0);
+    let stripped = symtab::is_stripped(symtab_stats.as_ref(), has_export_trie);
+
+    timer.lap("checks, uuid, code signature, data in code, binds");
+
+    Ok(ParsedSlice {
+        slice: *slice,
+        header: thin_header,
+        load_commands: load_commands_vec,
+        linkedit_data: parsed_linkedit_data,
+        segments: parsed_segments,
+        dylibs: parsed_dylibs,
+        rpaths: parsed_rpaths,
+        dyld_environment,
+        symbols: parsed_symbols,
+        strings: parsed_strings,
+        fixups: parsed_fixups,
+        diagnostics: slice_diagnostics,
+        uuid,
+        code_directories,
+        symseg: symseg_cmd,
+        symtab_stats,
+        stripped,
+        build_version,
+        min_version,
+        entry_point: entry,
+        source_version,
+        target_triple,
+        dyld_info: dyldinfo_cmd,
+        bound_symbols,
+        encryption,
+        notes: parsed_notes,
+        sub_images: parsed_sub_images,
+        fileset_entries,
+        data_in_code,
+        timings: timer,
+    })
+}
+
+
+// fat_archs of a universal binary, None for a thin one
+pub fn detect_fat_archs(data: &[u8]) -> Result<Option<Vec<FatArch>>, Box<dyn Error>> {
+    // A fat magic with a nonsense header is reported as such instead of retrying it as a thin binary
+    match fat::read_fat_header(data) {
+        Ok(fat_hdr) => Ok(Some(fat::read_fat_archs(data, &fat_hdr)?)),
+        Err(e) if fat::has_fat_magic(data) => Err(e.into()),
+        Err(_) => Ok(None),
+    }
+}
+
+// Every slice of the file, the CLI's fast paths, diff and verify always cover all of them
+pub fn all_slices(fat_archs: Option<&[FatArch]>) -> Vec<MachOSlice> {
+    match fat_archs {
+        Some(archs) => archs.iter().map(|arch| MachOSlice { offset: arch.offset(), size: Some(arch.size()) }).collect(),
+        None => vec![MachOSlice { offset: 0, size: None }],
+    }
+}
+
+// Parse the given slices and put the full MachOReport together, cross-slice checks included
+pub fn analyze_slices(
+    data: &[u8],
+    fat_archs: Option<&[FatArch]>,
+    slices: Vec<MachOSlice>,
+    slice_opts: &SliceOptions,
+    report_opts: &ReportOptions,
+) -> Result<(Vec<ParsedSlice>, MachOReport), Box<dyn Error>> {
+    let mut file = MachOFile::parse_slices(data, fat_archs.map(<[FatArch]>::to_vec), slices, slice_opts)?;
+
+    // MachOFile::report() one slice at a time, so --timings can show each slice's report build
+    let mut architecture_reports = Vec::new();
+    for i in 0..file.slices.len() {
+        architecture_reports.push(file.slice_report(&file.slices[i], data, report_opts)?);
+        file.slices[i].timings.lap("report build");
+    }
+    let macho_report = file.report_from(architecture_reports, report_opts);
+    Ok((file.slices, macho_report))
+}
+
+/*
+The library entry point: Mach-O (thin or fat) bytes in, the report the CLI prints out.
+Every slice is analyzed with the default SliceOptions, nothing is printed. Errors are the
+MoscopeError MachOFile::parse gives, BadMagic for anything that isn't a Mach-O.
+
+    let data = std::fs::read("/bin/ls")?;
+    let report = moscope::macho::analysis::analyze(&data, &ReportOptions::default())?;
+*/
+pub fn analyze(data: &[u8], opts: &ReportOptions) -> Result<MachOReport, MoscopeError> {
+    MachOFile::parse(data)?.report(data, opts)
+}
@@ -0,0 +1,330 @@
+// File Purpose: What platform / OS version / SDK was this built for? (LC_BUILD_VERSION)
+
+use std::cmp::Ordering;
+use std::error::Error;
+use std::io::{self, Write};
+use colored::Colorize;
+use crate::macho::constants::*;
+use crate::macho::load_commands::{self, LoadCommand};
+use crate::macho::utils;
+use crate::reporting::build_version::{BuildToolReport, BuildVersionReport};
+
+/*
+build_version_command memory layout (from loader.h)
++-----------------------------+
+| cmd (u32)                   |  LC_BUILD_VERSION
+| cmdsize (u32)               |  24 + ntools * 8
+| platform (u32)              |  PLATFORM_*
+| minos (u32)                 |  X.Y.Z encoded in nibbles xxxx.yy.zz
+| sdk (u32)                   |  X.Y.Z encoded in nibbles xxxx.yy.zz
+| ntools (u32)                |  number of build_tool_version entries that follow
++-----------------------------+
+| tool (u32)                  |  TOOL_*        \
+| version (u32)               |  X.Y.Z         / repeated ntools times
++-----------------------------+
+*/
+
+pub const BUILD_VERSION_COMMAND_SIZE: usize = 24;
+pub const BUILD_TOOL_VERSION_SIZE: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildVersion {
+    pub platform: String,
+    pub minos: String,
+    pub sdk: String,
+    pub tools: Vec<(String, String)>, // (tool name, version)
+}
+
+impl BuildVersion {
+    pub fn build_report(&self) -> BuildVersionReport {
+        BuildVersionReport {
+            platform: self.platform.clone(),
+            minos: self.minos.clone(),
+            sdk: self.sdk.clone(),
+            tools: self.tools.iter().map(|(tool, version)| BuildToolReport { tool: tool.clone(), version: version.clone() }).collect(),
+        }
+    }
+}
+
+
+pub fn platform_name(platform: u32) -> String {
+    match platform {
+        PLATFORM_UNKNOWN            => "unknown",
+        PLATFORM_ANY                => "any",
+        PLATFORM_MACOS              => "macOS",
+        PLATFORM_IOS                => "iOS",
+        PLATFORM_TVOS               => "tvOS",
+        PLATFORM_WATCHOS            => "watchOS",
+        PLATFORM_BRIDGEOS           => "bridgeOS",
+        PLATFORM_MACCATALYST        => "macCatalyst",
+        PLATFORM_IOSSIMULATOR       => "iOSSimulator",
+        PLATFORM_TVOSSIMULATOR      => "tvOSSimulator",
+        PLATFORM_WATCHOSSIMULATOR   => "watchOSSimulator",
+        PLATFORM_DRIVERKIT          => "DriverKit",
+        PLATFORM_VISIONOS           => "visionOS",
+        PLATFORM_VISIONOSSIMULATOR  => "visionOSSimulator",
+        PLATFORM_FIRMWARE           => "firmware",
+        PLATFORM_SEPOS              => "sepOS",
+        // New platforms show up every couple of years, don't fail on them
+        _ => return format!("Platform({})", platform),
+    }.to_string()
+}
+
+pub fn tool_name(tool: u32) -> String {
+    match tool {
+        TOOL_CLANG              => "clang",
+        TOOL_SWIFT              => "swift",
+        TOOL_LD                 => "ld",
+        TOOL_LLD                => "lld",
+        TOOL_METAL              => "metal",
+        TOOL_AIRLLD             => "airlld",
+        TOOL_AIRNT              => "airnt",
+        TOOL_AIRNT_PLUGIN       => "airnt-plugin",
+        TOOL_AIRPACK            => "airpack",
+        TOOL_GPUARCHIVER        => "gpuarchiver",
+        TOOL_METAL_FRAMEWORK    => "metal-framework",
+        _ => return format!("Tool({})", tool),
+    }.to_string()
+}
+
+
+// xxxx.yy.zz nibble packing --> "X.Y.Z"
+pub fn decode_version(packed: u32) -> String {
+    format!("{}.{}.{}", packed >> 16, (packed >> 8) & 0xff, packed & 0xff)
+}
+
+// "14", "14.2" or "14.2.1" --> (14, 2, 1), missing components are 0
+pub fn parse_version(version: &str) -> Option<(u32, u32, u32)> {
+    let mut parts = [0u32; 3];
+
+    for (i, part) in version.trim().split('.').enumerate() {
+        if i == 3 {
+            return None;
+        }
+        parts[i] = part.parse().ok()?;
+    }
+
+    Some((parts[0], parts[1], parts[2]))
+}
+
+pub fn compare_versions(a: &str, b: &str) -> Option<Ordering> {
+    Some(parse_version(a)?.cmp(&parse_version(b)?))
+}
+
+
+pub fn parse_build_version(data: &[u8], lc: &LoadCommand, is_be: bool) -> Result<BuildVersion, Box<dyn Error>> {
+    let base = lc.offset as usize;
+    let end = base + lc.cmdsize as usize;
+
+    if (lc.cmdsize as usize) < BUILD_VERSION_COMMAND_SIZE || end > data.len() {
+        return Err("LC_BUILD_VERSION exceeds file bounds".into());
+    }
+
+    let platform: u32 = utils::bytes_to(is_be, &data[base + 8..])?; // skip cmd & cmdsize
+    let minos: u32 = utils::bytes_to(is_be, &data[base + 12..])?;
+    let sdk: u32 = utils::bytes_to(is_be, &data[base + 16..])?;
+    let ntools: u32 = utils::bytes_to(is_be, &data[base + 20..])?;
+
+    // ntools drives how many build_tool_version entries follow, they have to fit inside cmdsize
+    let tools_size = (ntools as usize).checked_mul(BUILD_TOOL_VERSION_SIZE).ok_or("LC_BUILD_VERSION ntools overflow")?;
+    if BUILD_VERSION_COMMAND_SIZE + tools_size > lc.cmdsize as usize {
+        return Err(format!("LC_BUILD_VERSION declares {} tools but cmdsize is only {}", ntools, lc.cmdsize).into());
+    }
+
+    let mut tools = Vec::with_capacity(ntools as usize);
+    let mut tool_offset = base + BUILD_VERSION_COMMAND_SIZE;
+    for _ in 0..ntools {
+        let tool: u32 = utils::bytes_to(is_be, &data[tool_offset..])?;
+        let version: u32 = utils::bytes_to(is_be, &data[tool_offset + 4..])?;
+        tools.push((tool_name(tool), decode_version(version)));
+        tool_offset += BUILD_TOOL_VERSION_SIZE;
+    }
+
+    Ok(BuildVersion {
+        platform: platform_name(platform),
+        minos: decode_version(minos),
+        sdk: decode_version(sdk),
+        tools,
+    })
+}
+
+pub fn find_build_version(data: &[u8], load_commands: &[LoadCommand], is_be: bool) -> Result<Option<BuildVersion>, Box<dyn Error>> {
+    match load_commands.iter().find(|lc| lc.cmd == LC_BUILD_VERSION) {
+        Some(lc) => Ok(Some(parse_build_version(data, lc, is_be)?)),
+        None => Ok(None),
+    }
+}
+
+// LC_TARGET_TRIPLE: one lc_str at +8 holding the clang triple, "arm64-apple-macos14.0". Only
+// recent linkers emit it, older binaries just don't have one.
+pub fn parse_target_triple(data: &[u8], lc: &LoadCommand, is_be: bool) -> Result<String, Box<dyn Error>> {
+    Ok(load_commands::read_lc_str(data, lc, 8, is_be)?)
+}
+
+
+pub fn write_build_version(w: &mut dyn Write, report: &BuildVersionReport) -> io::Result<()> {
+    writeln!(w, "{} {} (min {}, SDK {})", "Build Version:".yellow().bold(), report.platform, report.minos, report.sdk)?;
+    for tool in &report.tools {
+        writeln!(w, "  {:<14} {}", tool.tool, tool.version)?;
+    }
+    Ok(())
+}
+
+pub fn write_target_triple(w: &mut dyn Write, triple: &str) -> io::Result<()> {
+    writeln!(w, "{} {}", "Target Triple:".yellow().bold(), triple)?;
+    Ok(())
+}
+
+
+/*
+CI guard rails for --require-platform / --require-min-os
+    platform --> case-insensitive match against platform_name() ("macos" == "macOS")
+    min os   --> the binary's minos must be <= the required version,
+                 i.e. it must still run on the oldest OS we claim to support
+Each returned string is one human readable failure with actual vs expected values.
+*/
+pub fn check_requirements(build_version: Option<&BuildVersion>, require_platform: Option<&str>, require_min_os: Option<&str>) -> Vec<String> {
+    let mut failures = Vec::new();
+
+    if require_platform.is_none() && require_min_os.is_none() {
+        return failures;
+    }
+
+    let Some(bv) = build_version else {
+        failures.push("no LC_BUILD_VERSION load command present".to_string());
+        return failures;
+    };
+
+    if let Some(expected) = require_platform
+        && !bv.platform.eq_ignore_ascii_case(expected)
+    {
+        failures.push(format!("platform is {} (expected {})", bv.platform, expected));
+    }
+
+    if let Some(expected) = require_min_os {
+        match compare_versions(&bv.minos, expected) {
+            Some(Ordering::Greater) => {
+                failures.push(format!("minimum OS is {} (expected {} or lower)", bv.minos, expected));
+            }
+            Some(_) => {}
+            None => failures.push(format!("cannot compare minimum OS {} against '{}'", bv.minos, expected)),
+        }
+    }
+
+    failures
+}
+
+
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_version_command(platform: u32, minos: u32, sdk: u32, tools: &[(u32, u32)]) -> Vec<u8> {
+        let cmdsize = (BUILD_VERSION_COMMAND_SIZE + tools.len() * BUILD_TOOL_VERSION_SIZE) as u32;
+        let mut data = Vec::new();
+        for value in [LC_BUILD_VERSION, cmdsize, platform, minos, sdk, tools.len() as u32] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        for (tool, version) in tools {
+            data.extend_from_slice(&tool.to_le_bytes());
+            data.extend_from_slice(&version.to_le_bytes());
+        }
+        data
+    }
+
+    fn lc_for(data: &[u8]) -> LoadCommand {
+        LoadCommand { cmd: LC_BUILD_VERSION, cmdsize: data.len() as u32, offset: 0 }
+    }
+
+    #[test]
+    fn parses_platform_versions_and_tools() {
+        let data = build_version_command(PLATFORM_MACOS, 0x000E_0000, 0x000E_0201, &[(TOOL_LD, 0x0469_0200)]);
+
+        let bv = parse_build_version(&data, &lc_for(&data), false).unwrap();
+
+        assert_eq!(bv.platform, "macOS");
+        assert_eq!(bv.minos, "14.0.0");
+        assert_eq!(bv.sdk, "14.2.1");
+        assert_eq!(bv.tools, vec![("ld".to_string(), "1129.2.0".to_string())]);
+    }
+
+    #[test]
+    fn report_keeps_every_tool() {
+        let data = build_version_command(PLATFORM_IOSSIMULATOR, 0x0011_0000, 0x0011_0200, &[(TOOL_CLANG, 0x05DC_0000), (TOOL_LD, 0x0469_0200)]);
+        let report = parse_build_version(&data, &lc_for(&data), false).unwrap().build_report();
+
+        assert_eq!(report.platform, "iOSSimulator");
+        assert_eq!(report.sdk, "17.2.0");
+        assert_eq!(report.tools, vec![
+            BuildToolReport { tool: "clang".to_string(), version: "1500.0.0".to_string() },
+            BuildToolReport { tool: "ld".to_string(), version: "1129.2.0".to_string() },
+        ]);
+    }
+
+    #[test]
+    fn unknown_platform_is_not_an_error() {
+        let data = build_version_command(99, 0x000E_0000, 0x000E_0000, &[]);
+        let bv = parse_build_version(&data, &lc_for(&data), false).unwrap();
+
+        assert_eq!(bv.platform, "Platform(99)");
+    }
+
+    #[test]
+    fn ntools_larger_than_cmdsize_is_rejected() {
+        let mut data = build_version_command(PLATFORM_MACOS, 0, 0, &[]);
+        data[20..24].copy_from_slice(&4u32.to_le_bytes()); // claim 4 tools with no room for them
+
+        assert!(parse_build_version(&data, &lc_for(&data), false).is_err());
+    }
+
+    #[test]
+    fn target_triple_is_read_from_its_lc_str() {
+        let mut data = Vec::new();
+        for value in [LC_TARGET_TRIPLE, 40, 12] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(b"arm64-apple-macos14.0\0");
+        data.resize(40, 0);
+        let lc = LoadCommand { cmd: LC_TARGET_TRIPLE, cmdsize: 40, offset: 0 };
+
+        assert_eq!(parse_target_triple(&data, &lc, false).unwrap(), "arm64-apple-macos14.0");
+        // offset past cmdsize, and a cmdsize that runs off the file
+        assert!(parse_target_triple(&data, &LoadCommand { cmdsize: 12, ..lc }, false).is_err());
+        assert!(parse_target_triple(&data[..32], &lc, false).is_err());
+    }
+
+    #[test]
+    fn compare_versions_pads_missing_components() {
+        assert_eq!(compare_versions("14.0.0", "14"), Some(Ordering::Equal));
+        assert_eq!(compare_versions("13.5.0", "14.0"), Some(Ordering::Less));
+        assert_eq!(compare_versions("14.0.1", "14.0"), Some(Ordering::Greater));
+        assert_eq!(compare_versions("14.0.0", "fourteen"), None);
+    }
+
+    #[test]
+    fn check_requirements_reports_actual_vs_expected() {
+        let bv = BuildVersion {
+            platform: "iOS".to_string(),
+            minos: "17.0.0".to_string(),
+            sdk: "17.2.0".to_string(),
+            tools: Vec::new(),
+        };
+
+        assert!(check_requirements(Some(&bv), Some("ios"), Some("17.0")).is_empty());
+
+        let failures = check_requirements(Some(&bv), Some("macOS"), Some("16.4"));
+        assert_eq!(failures, vec![
+            "platform is iOS (expected macOS)".to_string(),
+            "minimum OS is 17.0.0 (expected 16.4 or lower)".to_string(),
+        ]);
+
+        assert_eq!(check_requirements(None, Some("macOS"), None).len(), 1);
+        assert!(check_requirements(None, None, None).is_empty());
+    }
+}
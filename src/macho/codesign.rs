@@ -0,0 +1,687 @@
+// File Purpose: Who signed this? The LC_CODE_SIGNATURE blob (SuperBlob + CodeDirectories)
+
+use std::error::Error;
+use std::io::{self, Write};
+use colored::Colorize;
+use crate::macho::constants::*;
+use crate::macho::diagnostics::{self, Diagnostic};
+use crate::macho::hash;
+use crate::macho::load_commands::LinkEditDataCommand;
+use crate::reporting::codesign::{CodeDirectoryCheckReport, CodeDirectoryReport, CodeSignatureReport, PageMismatchReport, SignatureCheckReport};
+
+
+/*
+LC_CODE_SIGNATURE points (dataoff/datasize, slice relative) at an embedded signature SuperBlob.
+Unlike the rest of the Mach-O everything in here is BIG endian, whatever the slice is:
+
+    SuperBlob
+    +---------------------------+
+    | magic  0xfade0cc0         |
+    | length                    |  whole superblob
+    | count                     |
+    | BlobIndex[count]          |  { type (slot), offset from the superblob start }
+    +---------------------------+
+    | CodeDirectory   slot 0    |  the primary one (SHA-1 on older signatures, SHA-256 now)
+    | Requirements    slot 2    |
+    | Entitlements    slot 5    |
+    | CodeDirectory   0x1000+   |  alternates, one per extra hash type
+    | CMS signature   0x10000   |
+    +---------------------------+
+
+    CodeDirectory (the fields moscope reads)
+    +----------+------------------------------------------------------+
+    | 0x00     | magic 0xfade0c02                                     |
+    | 0x04     | length of this blob                                  |
+    | 0x08     | version                                              |
+    | 0x0c     | flags                                                |
+    | 0x10     | hashOffset     --> slot hashes                       |
+    | 0x14     | identOffset    --> "com.example.tool\0"              |
+    | 0x18     | nSpecialSlots                                        |
+    | 0x1c     | nCodeSlots                                           |
+    | 0x20     | codeLimit      bytes of the file the page hashes cover |
+    | 0x24     | hashSize, hashType, platform, pageSize (log2), u8s   |
+    | 0x28     | spare2                                               |
+    | 0x2c     | scatterOffset  (version >= 0x20100)                  |
+    | 0x30     | teamOffset     (version >= 0x20200) --> "ABCDE12345\0" |
+    +----------+------------------------------------------------------+
+
+The cdhash is the hash of the CodeDirectory blob itself (hashType's algorithm, cut to 20 bytes).
+With several CodeDirectories the system, and `codesign -dvvv`'s CDHash, goes with the strongest one.
+*/
+const CODE_DIRECTORY_MIN_SIZE: usize = 0x28;
+const CS_SUPPORTSTEAMID: u32 = 0x20200;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedCodeDirectory {
+    pub slot: u32,
+    pub version: u32,
+    pub flags: u32,
+    pub hash_type: u8,
+    pub hash_size: u8,
+    pub page_size: u32, // bytes, 0 = one hash over the whole codeLimit
+    pub code_limit: u32,
+    pub n_code_slots: u32,
+    pub n_special_slots: u32,
+    pub identifier: String,
+    pub team_id: Option<String>, // ad-hoc and pre-2014 signatures have none
+    pub cdhash: Option<Vec<u8>>, // None for a hash type we can't compute
+    pub code_hashes: Vec<Vec<u8>>, // nCodeSlots hashes at hashOffset, one per page of the first codeLimit bytes
+}
+
+impl ParsedCodeDirectory {
+    // Bytes each code slot covers, a page size of 0 means one slot over the whole codeLimit
+    pub fn page_bytes(&self) -> u64 {
+        match self.page_size {
+            0 => (self.code_limit as u64).max(1),
+            size => size as u64,
+        }
+    }
+
+    // How many code slots codeLimit calls for, the last page may be a short one
+    pub fn expected_code_slots(&self) -> u64 {
+        (self.code_limit as u64).div_ceil(self.page_bytes())
+    }
+
+    pub fn build_report(&self) -> CodeDirectoryReport {
+        CodeDirectoryReport {
+            slot: self.slot,
+            version: format!("0x{:x}", self.version),
+            flags: cs_flags_to_names(self.flags),
+            hash_type: hash_type_name(self.hash_type).to_string(),
+            page_size: self.page_size,
+            code_limit: self.code_limit,
+            n_code_slots: self.n_code_slots,
+            n_special_slots: self.n_special_slots,
+            team_id: self.team_id.clone(),
+            cdhash: self.cdhash.as_deref().map(hash::hex),
+        }
+    }
+}
+
+pub struct CsFlag {
+    pub mask: u32,
+    pub name: &'static str,
+}
+
+pub const CS_FLAGS: &[CsFlag] = &[
+    CsFlag { mask: CS_VALID, name: "VALID" },
+    CsFlag { mask: CS_ADHOC, name: "ADHOC" },
+    CsFlag { mask: CS_GET_TASK_ALLOW, name: "GET_TASK_ALLOW" },
+    CsFlag { mask: CS_INSTALLER, name: "INSTALLER" },
+    CsFlag { mask: CS_FORCED_LV, name: "FORCED_LV" },
+    CsFlag { mask: CS_INVALID_ALLOWED, name: "INVALID_ALLOWED" },
+    CsFlag { mask: CS_HARD, name: "HARD" },
+    CsFlag { mask: CS_KILL, name: "KILL" },
+    CsFlag { mask: CS_CHECK_EXPIRATION, name: "CHECK_EXPIRATION" },
+    CsFlag { mask: CS_RESTRICT, name: "RESTRICT" },
+    CsFlag { mask: CS_ENFORCEMENT, name: "ENFORCEMENT" },
+    CsFlag { mask: CS_REQUIRE_LV, name: "REQUIRE_LV" },
+    CsFlag { mask: CS_ENTITLEMENTS_VALIDATED, name: "ENTITLEMENTS_VALIDATED" },
+    CsFlag { mask: CS_NVRAM_UNRESTRICTED, name: "NVRAM_UNRESTRICTED" },
+    CsFlag { mask: CS_RUNTIME, name: "RUNTIME" },
+    CsFlag { mask: CS_LINKER_SIGNED, name: "LINKER_SIGNED" },
+    CsFlag { mask: CS_PLATFORM_BINARY, name: "PLATFORM_BINARY" },
+];
+
+// Same idea as header::parse_flags, for the CodeDirectory flags word
+pub fn cs_flags_to_names(flags: u32) -> Vec<String> {
+    CS_FLAGS.iter().filter(|f| flags & f.mask != 0).map(|f| f.name.to_string()).collect()
+}
+
+pub fn hash_type_name(hash_type: u8) -> &'static str {
+    match hash_type {
+        CS_HASHTYPE_SHA1 => "sha1",
+        CS_HASHTYPE_SHA256 => "sha256",
+        CS_HASHTYPE_SHA256_TRUNCATED => "sha256-truncated",
+        CS_HASHTYPE_SHA384 => "sha384",
+        _ => "unknown",
+    }
+}
+
+// Ranking the system uses to pick the CodeDirectory that identifies the code
+fn hash_type_strength(hash_type: u8) -> u8 {
+    match hash_type {
+        CS_HASHTYPE_SHA384 => 4,
+        CS_HASHTYPE_SHA256 => 3,
+        CS_HASHTYPE_SHA256_TRUNCATED => 2,
+        CS_HASHTYPE_SHA1 => 1,
+        _ => 0,
+    }
+}
+
+fn be_u32(blob: &[u8], at: usize) -> Result<u32, Box<dyn Error>> {
+    let bytes = blob.get(at..at + 4).ok_or_else(|| format!("code signature: read at 0x{:x} past the end of the blob", at))?;
+    Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+// NUL terminated string at `offset` inside the CodeDirectory, None if it doesn't end inside the blob
+fn read_cd_string(cd: &[u8], offset: usize) -> Option<String> {
+    let rest = cd.get(offset..)?;
+    let end = rest.iter().position(|&b| b == 0)?;
+    Some(String::from_utf8_lossy(&rest[..end]).to_string())
+}
+
+fn parse_code_directory(cd: &[u8], slot: u32) -> Result<ParsedCodeDirectory, Box<dyn Error>> {
+    if cd.len() < CODE_DIRECTORY_MIN_SIZE {
+        return Err(format!("CodeDirectory in slot 0x{:x} is only {} bytes", slot, cd.len()).into());
+    }
+
+    let ident_offset = be_u32(cd, 0x14)? as usize;
+    let identifier = read_cd_string(cd, ident_offset)
+        .ok_or_else(|| format!("CodeDirectory identifier offset 0x{:x} is outside the blob", ident_offset))?;
+
+    // teamOffset only exists from 0x20200 on, 0 there means "no team" (ad-hoc)
+    let version = be_u32(cd, 0x08)?;
+    let team_id = match version >= CS_SUPPORTSTEAMID {
+        true => match be_u32(cd, 0x30)? as usize {
+            0 => None,
+            team_offset => Some(read_cd_string(cd, team_offset)
+                .ok_or_else(|| format!("CodeDirectory team offset 0x{:x} is outside the blob", team_offset))?),
+        },
+        false => None,
+    };
+
+    let hash_type = cd[0x25];
+    let hash_size = cd[0x24];
+    let page_shift = cd[0x27];
+
+    // Code slots sit at hashOffset upwards (the special slots are the negative indexes below it)
+    let hash_offset = be_u32(cd, 0x10)? as usize;
+    let n_code_slots = be_u32(cd, 0x1c)?;
+    let code_hashes = (n_code_slots as usize).checked_mul(hash_size as usize)
+        .and_then(|len| hash_offset.checked_add(len))
+        .and_then(|end| cd.get(hash_offset..end))
+        .ok_or_else(|| format!("CodeDirectory in slot 0x{:x}: {} code slots at 0x{:x} run past the blob", slot, n_code_slots, hash_offset))?
+        .chunks(hash_size.max(1) as usize)
+        .map(|h| h.to_vec())
+        .collect();
+
+    Ok(ParsedCodeDirectory {
+        slot,
+        version,
+        flags: be_u32(cd, 0x0c)?,
+        hash_type,
+        hash_size,
+        page_size: if page_shift == 0 { 0 } else { 1u32.checked_shl(page_shift as u32).unwrap_or(0) },
+        code_limit: be_u32(cd, 0x20)?,
+        n_code_slots,
+        n_special_slots: be_u32(cd, 0x18)?,
+        identifier,
+        team_id,
+        cdhash: hash::cs_digest(hash_type, cd).map(|mut digest| {
+            digest.truncate(CS_CDHASH_LEN);
+            digest
+        }),
+        code_hashes,
+    })
+}
+
+// Every CodeDirectory in the SuperBlob, primary first, then the alternates in slot order
+pub fn parse_code_signature(blob: &[u8]) -> Result<Vec<ParsedCodeDirectory>, Box<dyn Error>> {
+    if be_u32(blob, 0)? != CSMAGIC_EMBEDDED_SIGNATURE {
+        return Err(format!("code signature: bad SuperBlob magic 0x{:08x}", be_u32(blob, 0)?).into());
+    }
+
+    let length = (be_u32(blob, 4)? as usize).min(blob.len());
+    let blob = &blob[..length];
+    let count = be_u32(blob, 8)? as usize;
+    if 12 + count.saturating_mul(8) > blob.len() {
+        return Err(format!("code signature: {} blob indexes don't fit in {} bytes", count, blob.len()).into());
+    }
+
+    let mut directories = Vec::new();
+    for i in 0..count {
+        let slot = be_u32(blob, 12 + i * 8)?;
+        let offset = be_u32(blob, 16 + i * 8)? as usize;
+
+        let is_code_directory = slot == CSSLOT_CODEDIRECTORY
+            || (CSSLOT_ALTERNATE_CODEDIRECTORIES..CSSLOT_ALTERNATE_CODEDIRECTORIES + CSSLOT_ALTERNATE_CODEDIRECTORY_MAX).contains(&slot);
+        if !is_code_directory {
+            continue;
+        }
+
+        if be_u32(blob, offset)? != CSMAGIC_CODEDIRECTORY {
+            return Err(format!("code signature: slot 0x{:x} is not a CodeDirectory", slot).into());
+        }
+        let cd_len = be_u32(blob, offset + 4)? as usize;
+        let cd = offset.checked_add(cd_len).and_then(|end| blob.get(offset..end))
+            .ok_or_else(|| format!("code signature: CodeDirectory in slot 0x{:x} runs past the SuperBlob", slot))?;
+        directories.push(parse_code_directory(cd, slot)?);
+    }
+
+    directories.sort_by_key(|cd| cd.slot);
+    Ok(directories)
+}
+
+// The CodeDirectories of a slice, from its already parsed LC_CODE_SIGNATURE (dataoff is slice relative)
+pub fn read_code_signature(data: &[u8], slice_offset: u64, cmd: &LinkEditDataCommand) -> Result<Vec<ParsedCodeDirectory>, Box<dyn Error>> {
+    let start = slice_offset + cmd.dataoff as u64;
+    let blob = data.get(start as usize..(start + cmd.datasize as u64) as usize)
+        .ok_or_else(|| format!("code signature ({:#x}, {} bytes) runs past EOF", cmd.dataoff, cmd.datasize))?;
+    parse_code_signature(blob)
+}
+
+pub fn best_code_directory(directories: &[ParsedCodeDirectory]) -> Option<&ParsedCodeDirectory> {
+    // max_by_key keeps the last of equals, rev() so a tie goes to the primary
+    directories.iter().rev().max_by_key(|cd| hash_type_strength(cd.hash_type))
+}
+
+pub fn build_code_signature_report(cpu_type: &str, cpu_subtype: &str, directories: &[ParsedCodeDirectory], diagnostics: &[Diagnostic]) -> CodeSignatureReport {
+    let best = best_code_directory(directories);
+    let flags = best.map_or(0, |cd| cd.flags);
+    CodeSignatureReport {
+        cpu_type: cpu_type.to_string(),
+        cpu_subtype: cpu_subtype.to_string(),
+        signed: !directories.is_empty(),
+        identifier: best.map(|cd| cd.identifier.clone()),
+        team_id: best.and_then(|cd| cd.team_id.clone()),
+        hash_type: best.map(|cd| hash_type_name(cd.hash_type).to_string()),
+        cdhash: best.and_then(|cd| cd.cdhash.as_deref()).map(hash::hex),
+        flags: cs_flags_to_names(flags),
+        platform_binary: flags & CS_PLATFORM_BINARY != 0,
+        hardened_runtime: flags & CS_RUNTIME != 0,
+        library_validation: flags & CS_REQUIRE_LV != 0,
+        adhoc: flags & CS_ADHOC != 0,
+        get_task_allow: flags & CS_GET_TASK_ALLOW != 0,
+        code_directories: directories.iter().map(|cd| cd.build_report()).collect(),
+        diagnostics: diagnostics.iter().map(|d| d.build_report()).collect(),
+    }
+}
+
+/*
+--check-signature: the code slots are what the kernel checks page by page as it maps the binary in.
+
+    slice bytes   0          pageSize     2*pageSize              codeLimit
+                  +------------+------------+-------- ... ----------+
+                  |   page 0   |   page 1   |                       |  signature blob lives past here
+                  +------------+------------+-------- ... ----------+
+                        |            |
+    code slot i = hashType(page i) cut to hashSize, the last page stops short at codeLimit
+
+codeLimit ends right where the signature starts, so the load commands, __TEXT, __DATA and most of
+__LINKEDIT are covered, a patched byte anywhere in there shows up as its page not matching.
+*/
+
+// Every code slot of one CodeDirectory recomputed over `slice` (the thin Mach-O's bytes)
+pub fn check_code_directory(slice: &[u8], cd: &ParsedCodeDirectory) -> CodeDirectoryCheckReport {
+    let code_limit = cd.code_limit as u64;
+    let page_size = cd.page_bytes();
+
+    let mut mismatches = Vec::new();
+    let mut pages_checked = 0;
+    let supported = hash::cs_digest(cd.hash_type, &[]).is_some();
+    if supported {
+        for (page, expected) in cd.code_hashes.iter().enumerate() {
+            let offset = page as u64 * page_size;
+            let end = (offset + page_size).min(code_limit);
+            pages_checked += 1;
+
+            // A page past EOF (truncated file) or past codeLimit can't match anything
+            let actual = slice.get(offset as usize..end.max(offset) as usize)
+                .filter(|_| offset < code_limit)
+                .and_then(|bytes| hash::cs_digest(cd.hash_type, bytes))
+                .map(|mut digest| {
+                    digest.truncate(cd.hash_size as usize);
+                    digest
+                });
+            if actual.as_deref() != Some(expected.as_slice()) {
+                mismatches.push(PageMismatchReport {
+                    page: page as u32,
+                    offset,
+                    expected: hash::hex(expected),
+                    actual: actual.as_deref().map(hash::hex),
+                });
+            }
+        }
+    }
+
+    CodeDirectoryCheckReport {
+        slot: cd.slot,
+        hash_type: hash_type_name(cd.hash_type).to_string(),
+        page_size: cd.page_size,
+        code_limit: cd.code_limit,
+        pages_checked,
+        valid: supported && mismatches.is_empty() && cd.code_hashes.len() as u64 == cd.expected_code_slots(),
+        mismatches,
+    }
+}
+
+pub fn build_signature_check_report(cpu_type: &str, cpu_subtype: &str, slice: &[u8], directories: &[ParsedCodeDirectory], diagnostics: &[Diagnostic]) -> SignatureCheckReport {
+    let mut diagnostics = diagnostics.to_vec();
+    for cd in directories {
+        if hash::cs_digest(cd.hash_type, &[]).is_none() {
+            diagnostics.push(Diagnostic::warning(
+                "code-signature-unverifiable",
+                format!("CodeDirectory in slot 0x{:x} uses hash type {}, its pages can't be checked", cd.slot, cd.hash_type),
+            ));
+        }
+
+        let expected_pages = cd.expected_code_slots();
+        if cd.code_hashes.len() as u64 != expected_pages {
+            diagnostics.push(Diagnostic::error(
+                "code-signature-slot-count",
+                format!("CodeDirectory in slot 0x{:x} has {} code slots, codeLimit 0x{:x} needs {}", cd.slot, cd.code_hashes.len(), cd.code_limit, expected_pages),
+            ));
+        }
+        if cd.code_limit as usize > slice.len() {
+            diagnostics.push(Diagnostic::error(
+                "code-signature-truncated",
+                format!("codeLimit 0x{:x} is past the end of the slice (0x{:x} bytes)", cd.code_limit, slice.len()),
+            ));
+        }
+    }
+
+    let code_directories: Vec<_> = directories.iter().map(|cd| check_code_directory(slice, cd)).collect();
+    SignatureCheckReport {
+        cpu_type: cpu_type.to_string(),
+        cpu_subtype: cpu_subtype.to_string(),
+        signed: !directories.is_empty(),
+        valid: !code_directories.is_empty() && code_directories.iter().all(|cd| cd.valid),
+        code_directories,
+        diagnostics: diagnostics.iter().map(|d| d.build_report()).collect(),
+    }
+}
+
+pub fn write_code_signature_summary(w: &mut dyn Write, report: &CodeSignatureReport) -> io::Result<()> {
+    writeln!(w)?;
+    writeln!(w, "{} ({})", "Code Signature".green().bold(), report.cpu_subtype)?;
+    writeln!(w, "----------------------------------------")?;
+
+    if !report.signed {
+        writeln!(w, "Not signed (no LC_CODE_SIGNATURE)")?;
+        diagnostics::write_titled_diagnostic_reports(w, "Diagnostics", &report.diagnostics)?;
+        return Ok(());
+    }
+
+    writeln!(w, "{} {}", "Identifier :".yellow().bold(), report.identifier.as_deref().unwrap_or("-"))?;
+    writeln!(w, "{} {}", "Team ID    :".yellow().bold(), report.team_id.as_deref().unwrap_or("- (ad-hoc or unsigned)"))?;
+    writeln!(w, "{} {}", "Hash type  :".yellow().bold(), report.hash_type.as_deref().unwrap_or("-"))?;
+    writeln!(w, "{} {}", "CDHash     :".yellow().bold(), report.cdhash.as_deref().unwrap_or("-"))?;
+    writeln!(w, "{} {}", "Flags      :".yellow().bold(), if report.flags.is_empty() { "none".to_string() } else { report.flags.join(", ") })?;
+    writeln!(w, "{} {}", "Runtime    :".yellow().bold(), if report.hardened_runtime { "hardened" } else { "not hardened" })?;
+    writeln!(w, "{} {}", "Lib. valid.:".yellow().bold(), if report.library_validation { "required" } else { "not required" })?;
+    if report.get_task_allow {
+        writeln!(w, "{} get-task-allow is set, any process can attach a debugger", "Warning    :".red().bold())?;
+    }
+
+    if report.code_directories.len() > 1 {
+        for cd in &report.code_directories {
+            writeln!(w, "  {} {} {}", format!("slot 0x{:04x}", cd.slot).dimmed(), cd.hash_type, cd.cdhash.as_deref().unwrap_or("-"))?;
+        }
+    }
+    diagnostics::write_titled_diagnostic_reports(w, "Diagnostics", &report.diagnostics)?;
+    Ok(())
+}
+
+pub fn write_signature_check(w: &mut dyn Write, report: &SignatureCheckReport) -> io::Result<()> {
+    writeln!(w)?;
+    writeln!(w, "{} ({})", "Signature Check".green().bold(), report.cpu_subtype)?;
+    writeln!(w, "----------------------------------------")?;
+
+    if !report.signed {
+        writeln!(w, "Not signed (no LC_CODE_SIGNATURE), nothing to check")?;
+        diagnostics::write_titled_diagnostic_reports(w, "Diagnostics", &report.diagnostics)?;
+        return Ok(());
+    }
+
+    let result = match report.valid {
+        true => "valid, every code page matches its hash".green().bold(),
+        false => "INVALID, the code does not match the signature".red().bold(),
+    };
+    writeln!(w, "{} {}", "Result     :".yellow().bold(), result)?;
+
+    for cd in &report.code_directories {
+        writeln!(
+            w,
+            "  {} {:<16} {}/{} pages match",
+            format!("slot 0x{:04x}", cd.slot).dimmed(),
+            cd.hash_type,
+            cd.pages_checked - cd.mismatches.len() as u32,
+            cd.pages_checked
+        )?;
+        for page in &cd.mismatches {
+            writeln!(
+                w,
+                "    {} {} expected {} got {}",
+                format!("page {:<5}", page.page).red(),
+                format!("0x{:08x}", page.offset).cyan(),
+                page.expected,
+                page.actual.as_deref().unwrap_or("- (past EOF)")
+            )?;
+        }
+    }
+    diagnostics::write_titled_diagnostic_reports(w, "Diagnostics", &report.diagnostics)?;
+    Ok(())
+}
+
+
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Minimal CodeDirectory: fixed header, identifier right after it, no slot hashes
+    fn code_directory(hash_type: u8, identifier: &str) -> Vec<u8> {
+        code_directory_with_team(hash_type, identifier, None)
+    }
+
+    // version 0x20400 header runs to 0x58, the strings go right after it
+    fn code_directory_with_team(hash_type: u8, identifier: &str, team: Option<&str>) -> Vec<u8> {
+        let ident_offset = 0x58u32;
+        let team_offset = team.map_or(0, |_| ident_offset + identifier.len() as u32 + 1);
+        let mut cd = Vec::new();
+        cd.extend_from_slice(&CSMAGIC_CODEDIRECTORY.to_be_bytes());
+        cd.extend_from_slice(&0u32.to_be_bytes()); // length, patched below
+        cd.extend_from_slice(&0x20400u32.to_be_bytes()); // version
+        cd.extend_from_slice(&0u32.to_be_bytes()); // flags
+        cd.extend_from_slice(&0u32.to_be_bytes()); // hashOffset
+        cd.extend_from_slice(&ident_offset.to_be_bytes());
+        cd.extend_from_slice(&0u32.to_be_bytes()); // nSpecialSlots
+        cd.extend_from_slice(&1u32.to_be_bytes()); // nCodeSlots
+        cd.extend_from_slice(&0x4000u32.to_be_bytes()); // codeLimit
+        cd.extend_from_slice(&[32, hash_type, 0, 12]); // hashSize, hashType, platform, pageSize
+        cd.extend_from_slice(&0u32.to_be_bytes()); // spare2
+        cd.extend_from_slice(&0u32.to_be_bytes()); // scatterOffset
+        cd.extend_from_slice(&team_offset.to_be_bytes());
+        cd.resize(ident_offset as usize, 0); // spare3, codeLimit64, execSeg*, runtime, ...
+        cd.extend_from_slice(identifier.as_bytes());
+        cd.push(0);
+        if let Some(team) = team {
+            cd.extend_from_slice(team.as_bytes());
+            cd.push(0);
+        }
+        let len = cd.len() as u32;
+        cd[4..8].copy_from_slice(&len.to_be_bytes());
+        cd
+    }
+
+    // A CodeDirectory that really signs `code`: 0x1000 pages, slot hashes appended after the identifier
+    fn code_directory_over(code: &[u8]) -> Vec<u8> {
+        let mut cd = code_directory(CS_HASHTYPE_SHA256, "com.example.tool");
+        let hash_offset = cd.len() as u32;
+        let pages: Vec<&[u8]> = code.chunks(0x1000).collect();
+        cd[0x10..0x14].copy_from_slice(&hash_offset.to_be_bytes());
+        cd[0x1c..0x20].copy_from_slice(&(pages.len() as u32).to_be_bytes());
+        cd[0x20..0x24].copy_from_slice(&(code.len() as u32).to_be_bytes());
+        for page in pages {
+            cd.extend(hash::cs_digest(CS_HASHTYPE_SHA256, page).unwrap());
+        }
+        let len = cd.len() as u32;
+        cd[4..8].copy_from_slice(&len.to_be_bytes());
+        cd
+    }
+
+    fn superblob(blobs: &[(u32, Vec<u8>)]) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&CSMAGIC_EMBEDDED_SIGNATURE.to_be_bytes());
+        out.extend_from_slice(&0u32.to_be_bytes());
+        out.extend_from_slice(&(blobs.len() as u32).to_be_bytes());
+        let mut offset = 12 + blobs.len() * 8;
+        for (slot, blob) in blobs {
+            out.extend_from_slice(&slot.to_be_bytes());
+            out.extend_from_slice(&(offset as u32).to_be_bytes());
+            offset += blob.len();
+        }
+        for (_, blob) in blobs {
+            out.extend_from_slice(blob);
+        }
+        let len = out.len() as u32;
+        out[4..8].copy_from_slice(&len.to_be_bytes());
+        out
+    }
+
+    #[test]
+    fn cdhash_is_the_truncated_hash_of_the_code_directory() {
+        let cd = code_directory(CS_HASHTYPE_SHA256, "com.example.tool");
+        let directories = parse_code_signature(&superblob(&[(CSSLOT_CODEDIRECTORY, cd.clone())])).unwrap();
+
+        assert_eq!(directories.len(), 1);
+        assert_eq!(directories[0].identifier, "com.example.tool");
+        assert_eq!(directories[0].page_size, 0x1000);
+        assert_eq!(directories[0].cdhash.as_deref(), Some(&hash::cs_digest(CS_HASHTYPE_SHA256, &cd).unwrap()[..20]));
+    }
+
+    #[test]
+    fn strongest_code_directory_wins() {
+        let sha1_cd = code_directory(CS_HASHTYPE_SHA1, "com.example.tool");
+        let sha256_cd = code_directory(CS_HASHTYPE_SHA256, "com.example.tool");
+        let blob = superblob(&[
+            (CSSLOT_CODEDIRECTORY, sha1_cd.clone()),
+            (2, vec![0xfa, 0xde, 0x0c, 0x01, 0, 0, 0, 12, 0, 0, 0, 0]), // requirements, skipped
+            (CSSLOT_ALTERNATE_CODEDIRECTORIES, sha256_cd.clone()),
+        ]);
+
+        let directories = parse_code_signature(&blob).unwrap();
+        let report = build_code_signature_report("ARM64", "arm64", &directories, &[]);
+
+        assert!(report.signed);
+        assert_eq!(report.code_directories.len(), 2);
+        assert_eq!(report.hash_type.as_deref(), Some("sha256"));
+        assert_eq!(report.cdhash, Some(hash::hex(&hash::cs_digest(CS_HASHTYPE_SHA256, &sha256_cd).unwrap()[..20])));
+        assert_eq!(report.code_directories[0].cdhash, Some(hash::hex(&hash::sha1(&sha1_cd))));
+    }
+
+    #[test]
+    fn malformed_signatures_are_rejected() {
+        assert!(parse_code_signature(&[0u8; 12]).is_err());
+
+        let mut cd = code_directory(CS_HASHTYPE_SHA256, "x");
+        cd[0x14..0x18].copy_from_slice(&0xffffu32.to_be_bytes()); // identifier past the end
+        assert!(parse_code_signature(&superblob(&[(CSSLOT_CODEDIRECTORY, cd)])).is_err());
+
+        let mut blob = superblob(&[(CSSLOT_CODEDIRECTORY, code_directory(CS_HASHTYPE_SHA256, "x"))]);
+        blob[8..12].copy_from_slice(&1000u32.to_be_bytes()); // count way past the blob
+        assert!(parse_code_signature(&blob).is_err());
+    }
+
+    #[test]
+    fn team_id_is_read_from_team_offset() {
+        let cd = code_directory_with_team(CS_HASHTYPE_SHA256, "com.example.tool", Some("ABCDE12345"));
+        let directories = parse_code_signature(&superblob(&[(CSSLOT_CODEDIRECTORY, cd.clone())])).unwrap();
+        assert_eq!(directories[0].team_id.as_deref(), Some("ABCDE12345"));
+        assert_eq!(build_code_signature_report("ARM64", "arm64", &directories, &[]).team_id.as_deref(), Some("ABCDE12345"));
+
+        // ad-hoc: teamOffset 0
+        let directories = parse_code_signature(&superblob(&[(CSSLOT_CODEDIRECTORY, code_directory(CS_HASHTYPE_SHA256, "x"))])).unwrap();
+        assert_eq!(directories[0].team_id, None);
+
+        // before 0x20200 the field isn't there, whatever the bytes say
+        let mut old = cd.clone();
+        old[8..12].copy_from_slice(&0x20100u32.to_be_bytes());
+        assert_eq!(parse_code_signature(&superblob(&[(CSSLOT_CODEDIRECTORY, old)])).unwrap()[0].team_id, None);
+
+        // pointing outside the CodeDirectory
+        let mut bad = cd;
+        bad[0x30..0x34].copy_from_slice(&0x1000u32.to_be_bytes());
+        assert!(parse_code_signature(&superblob(&[(CSSLOT_CODEDIRECTORY, bad)])).is_err());
+    }
+
+    #[test]
+    fn combined_cs_flags_are_decoded() {
+        let flags = CS_ADHOC | CS_RUNTIME | CS_REQUIRE_LV | CS_LINKER_SIGNED;
+        assert_eq!(cs_flags_to_names(flags), vec!["ADHOC", "REQUIRE_LV", "RUNTIME", "LINKER_SIGNED"]);
+        assert!(cs_flags_to_names(0).is_empty());
+
+        let mut cd = code_directory(CS_HASHTYPE_SHA256, "com.example.tool");
+        cd[0x0c..0x10].copy_from_slice(&(flags | CS_GET_TASK_ALLOW).to_be_bytes());
+        let directories = parse_code_signature(&superblob(&[(CSSLOT_CODEDIRECTORY, cd)])).unwrap();
+        let report = build_code_signature_report("ARM64", "arm64", &directories, &[]);
+
+        assert!(report.adhoc && report.hardened_runtime && report.library_validation && report.get_task_allow);
+        assert!(!report.platform_binary);
+        assert_eq!(report.flags, report.code_directories[0].flags);
+
+        let unsigned = build_code_signature_report("ARM64", "arm64", &[], &[]);
+        assert!(unsigned.flags.is_empty() && !unsigned.hardened_runtime && !unsigned.adhoc);
+    }
+
+    #[test]
+    fn code_slots_are_read_from_hash_offset() {
+        let code: Vec<u8> = (0..0x2800u32).map(|i| i as u8).collect();
+        let directories = parse_code_signature(&superblob(&[(CSSLOT_CODEDIRECTORY, code_directory_over(&code))])).unwrap();
+
+        assert_eq!(directories[0].code_hashes.len(), 3);
+        assert_eq!(directories[0].expected_code_slots(), 3);
+        assert_eq!(directories[0].code_hashes[2], hash::cs_digest(CS_HASHTYPE_SHA256, &code[0x2000..]).unwrap());
+
+        // nCodeSlots running past the end of the CodeDirectory
+        let mut bad = code_directory_over(&code);
+        bad[0x1c..0x20].copy_from_slice(&100u32.to_be_bytes());
+        assert!(parse_code_signature(&superblob(&[(CSSLOT_CODEDIRECTORY, bad)])).is_err());
+    }
+
+    #[test]
+    fn untouched_code_passes_the_check() {
+        let code: Vec<u8> = (0..0x2800u32).map(|i| (i * 7) as u8).collect();
+        let directories = parse_code_signature(&superblob(&[(CSSLOT_CODEDIRECTORY, code_directory_over(&code))])).unwrap();
+        let report = build_signature_check_report("ARM64", "arm64", &code, &directories, &[]);
+
+        assert!(report.signed && report.valid);
+        assert_eq!(report.code_directories[0].pages_checked, 3);
+        assert!(report.code_directories[0].mismatches.is_empty());
+        assert!(report.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn a_patched_byte_fails_its_page() {
+        let code = vec![0u8; 0x3000];
+        let directories = parse_code_signature(&superblob(&[(CSSLOT_CODEDIRECTORY, code_directory_over(&code))])).unwrap();
+
+        let mut patched = code.clone();
+        patched[0x1234] = 0xc3;
+        let report = build_signature_check_report("ARM64", "arm64", &patched, &directories, &[]);
+
+        assert!(!report.valid);
+        let mismatches = &report.code_directories[0].mismatches;
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!((mismatches[0].page, mismatches[0].offset), (1, 0x1000));
+        assert_eq!(mismatches[0].expected, hash::hex(&directories[0].code_hashes[1]));
+        assert_eq!(mismatches[0].actual, Some(hash::hex(&hash::cs_digest(CS_HASHTYPE_SHA256, &patched[0x1000..0x2000]).unwrap())));
+    }
+
+    #[test]
+    fn truncated_code_and_unknown_hash_types_are_invalid() {
+        let code = vec![0x41u8; 0x2000];
+        let directories = parse_code_signature(&superblob(&[(CSSLOT_CODEDIRECTORY, code_directory_over(&code))])).unwrap();
+
+        // The last page is gone, nothing to hash
+        let report = build_signature_check_report("ARM64", "arm64", &code[..0x1800], &directories, &[]);
+        assert!(!report.valid);
+        assert_eq!(report.code_directories[0].mismatches[0].actual, None);
+        assert!(report.diagnostics.iter().any(|d| d.code == "code-signature-truncated"));
+
+        let mut unknown = directories[0].clone();
+        unknown.hash_type = 0x7f;
+        let report = build_signature_check_report("ARM64", "arm64", &code, &[unknown], &[]);
+        assert!(!report.valid);
+        assert_eq!(report.code_directories[0].pages_checked, 0);
+        assert!(report.diagnostics.iter().any(|d| d.code == "code-signature-unverifiable"));
+
+        assert!(!build_signature_check_report("ARM64", "arm64", &code, &[], &[]).valid);
+    }
+}
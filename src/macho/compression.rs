@@ -0,0 +1,287 @@
+// File Purpose: Unwrap gzip/xz compressed inputs so the rest of the parser only ever sees raw Mach-O bytes
+// Release artifacts frequently ship binaries as .gz/.xz, this saves a manual decompress step
+
+use std::error::Error;
+use crate::macho::constants;
+
+/*
+Magic numbers (first bytes of the file):
+    gzip --> 1f 8b
+    xz   --> fd 37 7a 58 5a 00   ("\xfd7zXZ\0")
+
+The decoders are behind the `compression` cargo feature so the default build stays dependency-light.
+Without the feature we still detect the magic and tell the user how to get support instead of
+failing later with a confusing "Not a valid Mach-O binary".
+*/
+
+pub const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+pub const XZ_MAGIC: [u8; 6] = [0xfd, 0x37, 0x7a, 0x58, 0x5a, 0x00];
+
+// A few hundred bytes of gzip can inflate to gigabytes, stop well before that eats all memory.
+// Still larger than any real universal binary.
+pub const MAX_DECOMPRESSED_SIZE: u64 = 4 * 1024 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Xz,
+}
+
+impl Compression {
+    pub fn name(self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Xz => "xz",
+        }
+    }
+}
+
+pub fn detect_compression(data: &[u8]) -> Option<Compression> {
+    if data.starts_with(&GZIP_MAGIC) {
+        Some(Compression::Gzip)
+    } else if data.starts_with(&XZ_MAGIC) {
+        Some(Compression::Xz)
+    } else {
+        None
+    }
+}
+
+pub fn has_macho_or_fat_magic(data: &[u8]) -> bool {
+    if data.len() < 4 {
+        return false;
+    }
+    let magic: [u8; 4] = [data[0], data[1], data[2], data[3]];
+    matches!(
+        magic,
+        constants::MH_MAGIC | constants::MH_CIGAM | constants::MH_MAGIC_64 | constants::MH_CIGAM_64
+            | constants::FAT_MAGIC | constants::FAT_CIGAM | constants::FAT_MAGIC_64 | constants::FAT_CIGAM_64
+    )
+}
+
+// Returns the input untouched unless it's compressed, in which case the decompressed bytes are returned
+pub fn maybe_decompress(data: Vec<u8>) -> Result<Vec<u8>, Box<dyn Error>> {
+    let Some(kind) = detect_compression(&data) else {
+        return Ok(data);
+    };
+
+    let decompressed = decompress(&data, kind, MAX_DECOMPRESSED_SIZE)?;
+
+    if !has_macho_or_fat_magic(&decompressed) {
+        return Err(format!("decompressed {} input is not a Mach-O or fat binary", kind.name()).into());
+    }
+
+    Ok(decompressed)
+}
+
+#[cfg(feature = "compression")]
+fn decompress(data: &[u8], kind: Compression, limit: u64) -> Result<Vec<u8>, Box<dyn Error>> {
+    use std::io::Read;
+
+    let mut out = Vec::new();
+    match kind {
+        Compression::Gzip => {
+            // One byte past the limit is enough to know the output doesn't fit
+            flate2::read::MultiGzDecoder::new(data)
+                .take(limit + 1)
+                .read_to_end(&mut out)
+                .map_err(|e| format!("failed to decompress gzip input: {}", e))?;
+        }
+        Compression::Xz => {
+            // lzma-rs only writes into a sink, so the sink is what refuses to grow
+            let mut sink = LimitedWriter { out: &mut out, limit };
+            lzma_rs::xz_decompress(&mut std::io::BufReader::new(data), &mut sink)
+                .map_err(|e| format!("failed to decompress xz input: {}", e))?;
+        }
+    }
+
+    if out.len() as u64 > limit {
+        return Err(format!("decompressed {} input is larger than the {} byte limit", kind.name(), limit).into());
+    }
+    Ok(out)
+}
+
+#[cfg(feature = "compression")]
+struct LimitedWriter<'a> {
+    out: &'a mut Vec<u8>,
+    limit: u64,
+}
+
+#[cfg(feature = "compression")]
+impl std::io::Write for LimitedWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if (self.out.len() + buf.len()) as u64 > self.limit {
+            return Err(std::io::Error::other(format!("output is larger than the {} byte limit", self.limit)));
+        }
+        self.out.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/*
+The first `len` bytes a compressed stream unwraps to, without unwrapping the rest: the --recursive
+walk only needs to know whether a .gz / .xz holds a Mach-O before reading it in full. None when
+the stream is broken, or without the `compression` feature (nothing can be peeked at then).
+*/
+#[cfg(feature = "compression")]
+pub fn decompressed_prefix(reader: impl std::io::Read, kind: Compression, len: usize) -> Option<Vec<u8>> {
+    use std::io::Read;
+
+    let mut out = Vec::new();
+    match kind {
+        Compression::Gzip => {
+            flate2::read::MultiGzDecoder::new(reader).take(len as u64).read_to_end(&mut out).ok()?;
+        }
+        Compression::Xz => {
+            // the sink stops the decoder once it has `len` bytes, that "error" is the point
+            let mut sink = PrefixWriter { out: &mut out, len };
+            let _ = lzma_rs::xz_decompress(&mut std::io::BufReader::new(reader), &mut sink);
+        }
+    }
+    (out.len() == len).then_some(out)
+}
+
+#[cfg(feature = "compression")]
+struct PrefixWriter<'a> {
+    out: &'a mut Vec<u8>,
+    len: usize,
+}
+
+#[cfg(feature = "compression")]
+impl std::io::Write for PrefixWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let wanted = self.len - self.out.len();
+        self.out.extend_from_slice(&buf[..buf.len().min(wanted)]);
+        if self.out.len() == self.len {
+            return Err(std::io::Error::other("prefix complete"));
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "compression"))]
+pub fn decompressed_prefix(_reader: impl std::io::Read, _kind: Compression, _len: usize) -> Option<Vec<u8>> {
+    None
+}
+
+#[cfg(not(feature = "compression"))]
+fn decompress(_data: &[u8], kind: Compression, _limit: u64) -> Result<Vec<u8>, Box<dyn Error>> {
+    Err(format!(
+        "input is {}-compressed; rebuild moscope with `--features compression` or decompress it first",
+        kind.name()
+    ).into())
+}
+
+
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fake_macho() -> Vec<u8> {
+        let mut data = constants::MH_CIGAM_64.to_vec();
+        data.extend_from_slice(&[0u8; 28]);
+        data
+    }
+
+    #[test]
+    fn uncompressed_input_is_passed_through() {
+        let data = fake_macho();
+        assert_eq!(maybe_decompress(data.clone()).unwrap(), data);
+    }
+
+    #[test]
+    fn detects_gzip_and_xz_magic() {
+        assert_eq!(detect_compression(&[0x1f, 0x8b, 0x08, 0x00]), Some(Compression::Gzip));
+        assert_eq!(detect_compression(&XZ_MAGIC), Some(Compression::Xz));
+        assert_eq!(detect_compression(&fake_macho()), None);
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn gzip_input_is_decompressed() {
+        use std::io::Write;
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&fake_macho()).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        assert_eq!(maybe_decompress(compressed).unwrap(), fake_macho());
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn xz_input_is_decompressed() {
+        let mut compressed = Vec::new();
+        lzma_rs::xz_compress(&mut std::io::BufReader::new(fake_macho().as_slice()), &mut compressed).unwrap();
+
+        assert_eq!(maybe_decompress(compressed).unwrap(), fake_macho());
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn decompressed_non_macho_is_rejected() {
+        use std::io::Write;
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(b"definitely not a mach-o").unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        assert!(maybe_decompress(compressed).is_err());
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn only_a_prefix_is_unwrapped_for_a_peek() {
+        use std::io::{Read, Write};
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&fake_macho()).unwrap();
+        let gz = encoder.finish().unwrap();
+        assert_eq!(decompressed_prefix(gz.as_slice(), Compression::Gzip, 4).unwrap(), constants::MH_CIGAM_64);
+
+        // 1 MiB of zeros, the peek stops after the first few
+        let mut xz = Vec::new();
+        lzma_rs::xz_compress(&mut std::io::BufReader::new(std::io::repeat(0).take(1 << 20)), &mut xz).unwrap();
+        assert_eq!(decompressed_prefix(xz.as_slice(), Compression::Xz, 4).unwrap(), [0; 4]);
+
+        assert!(decompressed_prefix(&gz[..8], Compression::Gzip, 4).is_none());
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn oversized_output_is_rejected() {
+        use std::io::Write;
+
+        // 64 KiB of zeros squeezes down to a few hundred bytes either way
+        let big = vec![0u8; 64 * 1024];
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&big).unwrap();
+        let gz = encoder.finish().unwrap();
+        let mut xz = Vec::new();
+        lzma_rs::xz_compress(&mut std::io::BufReader::new(big.as_slice()), &mut xz).unwrap();
+
+        assert!(decompress(&gz, Compression::Gzip, 1024).is_err());
+        assert!(decompress(&xz, Compression::Xz, 1024).is_err());
+        assert_eq!(decompress(&gz, Compression::Gzip, big.len() as u64).unwrap().len(), big.len());
+        assert_eq!(decompress(&xz, Compression::Xz, big.len() as u64).unwrap().len(), big.len());
+    }
+
+    #[cfg(not(feature = "compression"))]
+    #[test]
+    fn compressed_input_errors_without_feature() {
+        assert!(maybe_decompress(vec![0x1f, 0x8b, 0x08, 0x00]).is_err());
+    }
+}
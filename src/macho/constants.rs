@@ -93,7 +93,7 @@ pub const CPU_TYPE_ARM64: i32  = CPU_TYPE_ARM | CPU_ARCH_ABI64;
 // ------------------------------------------------------------
 // CPU subtype masks
 // ------------------------------------------------------------
-/// Mask for extracting the subtype capability bits
+// Mask for extracting the subtype capability bits
 
 // pub const CPU_SUBTYPE_MASK: i32 = 0xff00_0000;
 // This mask value ^ exceeds i32 value  
@@ -245,6 +245,12 @@ pub const S_DTRACE_DOF: u32                 = 0x0F; // section contains DTrace O
 pub const S_LAZY_DYLUB_SYMBOL_POINTERS: u32 = 0x10; // section with only lazy symbol pointers to lazy loaded dylibs
 
 // section types to support thread local variables
+pub const S_THREAD_LOCAL_REGULAR: u32                   = 0x11; // template of initial values for TLVs
+pub const S_THREAD_LOCAL_ZEROFILL: u32                  = 0x12; // template of initial values for TLVs
+pub const S_THREAD_LOCAL_VARIABLES: u32                 = 0x13; // TLV descriptors
+pub const S_THREAD_LOCAL_VARIABLE_POINTERS: u32         = 0x14; // pointers to TLV descriptors
+pub const S_THREAD_LOCAL_INIT_FUNCTION_POINTERS: u32    = 0x15; // functions to call to initialize TLV values
+
 pub const SECTION_ATTRIBUTES_USR: u32       = 0xFF000000; // User setable attributes
 pub const S_ATTR_PURE_INSTRUCTIONS: u32     = 0x80000000; // section contains only true machine instructions
 pub const S_ATTR_NO_TOC: u32                = 0x40000000; // section contains coalesced symbols that are not to be in a ranlib table of contents
@@ -374,6 +380,12 @@ pub const SECT_CSTRING: [u8; 16] = [
     0, 0, 0, 0, 0, 0, 0
 ];
 
+// os_log() format strings, ld64 emits it as S_CSTRING_LITERALS but older toolchains left it S_REGULAR
+pub const SECT_OSLOGSTRING: [u8; 16] = [
+    b'_', b'_', b'o', b's', b'l', b'o', b'g', b's', b't', b'r', b'i', b'n', b'g',
+    0, 0, 0
+];
+
 pub const SECT_OBJC_METHNAME: [u8; 16] = [
     b'_', b'_', b'o', b'b', b'j', b'c', b'_', b'm', b'e', b't', b'h', b'n', b'a', b'm', b'e',
     0
@@ -467,6 +479,19 @@ pub const SECT_OBJC_PROTLIST: [u8; 16] = [
     b'_', b'_', b'o', b'b', b'j', b'c', b'_', b'p', b'r', b'o', b't', b'l', b'i', b's', b't', 0
 ];
 
+// Current toolchains spell it out, __objc_protlist above is the older name
+pub const SECT_OBJC_PROTOLIST: [u8; 16] = [
+    b'_', b'_', b'o', b'b', b'j', b'c', b'_', b'p', b'r', b'o', b't', b'o', b'l', b'i', b's', b't'
+];
+
+pub const SECT_OBJC_PROTOREFS: [u8; 16] = [
+    b'_', b'_', b'o', b'b', b'j', b'c', b'_', b'p', b'r', b'o', b't', b'o', b'r', b'e', b'f', b's'
+];
+
+pub const SECT_OBJC_CATLIST: [u8; 16] = [
+    b'_', b'_', b'o', b'b', b'j', b'c', b'_', b'c', b'a', b't', b'l', b'i', b's', b't', 0, 0
+];
+
 pub const SECT_OBJC_IVAR: [u8; 16] = [
     b'_', b'_', b'o', b'b', b'j', b'c', b'_', b'i', b'v', b'a', b'r',
     0, 0, 0, 0, 0
@@ -574,6 +599,39 @@ pub const N_INDR: u8    = 0x0A; // indirect
 pub const NO_SECT: u8   = 0x0e; // sumbol is not in any section
 pub const MAX_SECT: u8  = 0xFF; // 1 thru 255 inclusive
 
+// STAB n_type values (from stab.h), only meaningful when (n_type & N_STAB) != 0
+// The whole byte is the stab type, it is NOT split into the usual PEXT/TYPE/EXT bitfields
+pub const N_GSYM: u8    = 0x20; // global symbol: name,,NO_SECT,type,0
+pub const N_FNAME: u8   = 0x22; // procedure name (f77 kludge): name,,NO_SECT,0,0
+pub const N_FUN: u8     = 0x24; // procedure: name,,n_sect,linenumber,address
+pub const N_STSYM: u8   = 0x26; // static symbol: name,,n_sect,type,address
+pub const N_LCSYM: u8   = 0x28; // .lcomm symbol: name,,n_sect,type,address
+pub const N_BNSYM: u8   = 0x2e; // begin nsect sym: 0,,n_sect,0,address
+pub const N_AST: u8     = 0x32; // AST file path: name,,NO_SECT,0,0
+pub const N_OPT: u8     = 0x3c; // emitted with gcc2_compiled and in gcc source
+pub const N_RSYM: u8    = 0x40; // register sym: name,,NO_SECT,type,register
+pub const N_SLINE: u8   = 0x44; // src line: 0,,n_sect,linenumber,address
+pub const N_ENSYM: u8   = 0x4e; // end nsect sym: 0,,n_sect,0,address
+pub const N_SSYM: u8    = 0x60; // structure elt: name,,NO_SECT,type,struct_offset
+pub const N_SO: u8      = 0x64; // source file name: name,,n_sect,0,address
+pub const N_OSO: u8     = 0x66; // object file name: name,,0,0,st_mtime
+pub const N_LSYM: u8    = 0x80; // local sym: name,,NO_SECT,type,offset
+pub const N_BINCL: u8   = 0x82; // include file beginning: name,,NO_SECT,0,sum
+pub const N_SOL: u8     = 0x84; // #included file name: name,,n_sect,0,address
+pub const N_PARAMS: u8  = 0x86; // compiler parameters: name,,NO_SECT,0,0
+pub const N_VERSION: u8 = 0x88; // compiler version: name,,NO_SECT,0,0
+pub const N_OLEVEL: u8  = 0x8A; // compiler -O level: name,,NO_SECT,0,0
+pub const N_PSYM: u8    = 0xa0; // parameter: name,,NO_SECT,type,offset
+pub const N_EINCL: u8   = 0xa2; // include file end: name,,NO_SECT,0,0
+pub const N_ENTRY: u8   = 0xa4; // alternate entry: name,,n_sect,linenumber,address
+pub const N_LBRAC: u8   = 0xc0; // left bracket: 0,,NO_SECT,nesting level,address
+pub const N_EXCL: u8    = 0xc2; // deleted include file: name,,NO_SECT,0,sum
+pub const N_RBRAC: u8   = 0xe0; // right bracket: 0,,NO_SECT,nesting level,address
+pub const N_BCOMM: u8   = 0xe2; // begin common: name,,NO_SECT,0,0
+pub const N_ECOMM: u8   = 0xe4; // end common: name,,n_sect,0,0
+pub const N_ECOML: u8   = 0xe8; // end common (local name): 0,,n_sect,0,address
+pub const N_LENG: u8    = 0xfe; // second stab entry with length information
+
 pub const INDIRECT_SYMBOL_LOCAL: u32    = 0x80000000; // slot corresponds to a local symbol, not external symbol
 pub const INDIRECT_SYMBOL_ABS: u32      = 0x40000000; // slot is absolute, doesn't reference any symbol
 
@@ -655,6 +713,43 @@ pub const LC_FUNCTION_VARIANTS: u32         = 0x37; // used with linkedit_data_c
 pub const LC_FUNCTION_VARIANT_FIXED: u32    = 0x38; // used with linkedit_data_command
 pub const LC_TARGET_TRIPLE: u32             = 0x39; // target triple used to compile
 
+// Known values for the platform field of LC_BUILD_VERSION (from loader.h)
+pub const PLATFORM_UNKNOWN: u32             = 0;
+pub const PLATFORM_ANY: u32                 = 0xFFFFFFFF;
+pub const PLATFORM_MACOS: u32               = 1;
+pub const PLATFORM_IOS: u32                 = 2;
+pub const PLATFORM_TVOS: u32                = 3;
+pub const PLATFORM_WATCHOS: u32             = 4;
+pub const PLATFORM_BRIDGEOS: u32            = 5;
+pub const PLATFORM_MACCATALYST: u32         = 6;
+pub const PLATFORM_IOSSIMULATOR: u32        = 7;
+pub const PLATFORM_TVOSSIMULATOR: u32       = 8;
+pub const PLATFORM_WATCHOSSIMULATOR: u32    = 9;
+pub const PLATFORM_DRIVERKIT: u32           = 10;
+pub const PLATFORM_VISIONOS: u32            = 11;
+pub const PLATFORM_VISIONOSSIMULATOR: u32   = 12;
+pub const PLATFORM_FIRMWARE: u32            = 13;
+pub const PLATFORM_SEPOS: u32               = 14;
+
+// Known values for the tool field of build_tool_version
+pub const TOOL_CLANG: u32                   = 1;
+pub const TOOL_SWIFT: u32                   = 2;
+pub const TOOL_LD: u32                      = 3;
+pub const TOOL_LLD: u32                     = 4;
+pub const TOOL_METAL: u32                   = 1024;
+pub const TOOL_AIRLLD: u32                  = 1025;
+pub const TOOL_AIRNT: u32                   = 1026;
+pub const TOOL_AIRNT_PLUGIN: u32            = 1027;
+pub const TOOL_AIRPACK: u32                 = 1028;
+pub const TOOL_GPUARCHIVER: u32             = 1031;
+pub const TOOL_METAL_FRAMEWORK: u32         = 1032;
+
+// thread_command flavors LC_UNIXTHREAD entry points are read from (mach/*/thread_status.h)
+pub const X86_THREAD_STATE32: u32           = 1;  // i386, 16 u32 registers, eip is the 11th
+pub const X86_THREAD_STATE64: u32           = 4;  // x86_64, 21 u64 registers, rip is the 17th
+pub const ARM_THREAD_STATE: u32             = 1;  // arm, r0-r12 sp lr pc cpsr
+pub const ARM_THREAD_STATE64: u32           = 6;  // arm64, x0-x28 fp lr sp pc cpsr
+
 
 
 
@@ -687,6 +782,12 @@ pub const BIND_SPECIAL_DYLIB_MAIN_EXECUTABLE: i32                       = -1;
 pub const BIND_SPECIAL_DYLIB_FLAT_LOOKUP: i32                           = -2;
 pub const BIND_SPECIAL_DYLIB_WEAK_LOOKUP: i32                           = -3;
 
+// LIBRARY ORDINALS (high byte of an undefined nlist's n_desc, two-level namespace only)
+pub const SELF_LIBRARY_ORDINAL: u8                                     = 0x00;
+pub const MAX_LIBRARY_ORDINAL: u8                                      = 0xfd;
+pub const DYNAMIC_LOOKUP_ORDINAL: u8                                   = 0xfe;
+pub const EXECUTABLE_ORDINAL: u8                                       = 0xff;
+
 pub const BIND_SYMBOL_FLAGS_WEAK_IMPORT: u8                            = 0x01;
 pub const BIND_SYMBOL_FLAGS_NON_WEAK_DEFINITION: u8                    = 0x08;
 
@@ -717,6 +818,40 @@ pub const EXPORT_SYMBOL_FLAGS_WEAK_DEFINITION: u8                      = 0x04;
 pub const EXPORT_SYMBOL_FLAGS_REEXPORT: u8                             = 0x08;
 pub const EXPORT_SYMBOL_FLAGS_STUB_AND_RESOLVER: u8                    = 0x10;
 pub const EXPORT_SYMBOL_FLAGS_STATIC_RESOLVER: u8                      = 0x20;
+// DATA IN CODE (data_in_code_entry.kind)
+pub const DICE_KIND_DATA: u16                                          = 0x0001;
+pub const DICE_KIND_JUMP_TABLE8: u16                                   = 0x0002;
+pub const DICE_KIND_JUMP_TABLE16: u16                                  = 0x0003;
+pub const DICE_KIND_JUMP_TABLE32: u16                                  = 0x0004;
+pub const DICE_KIND_ABS_JUMP_TABLE32: u16                              = 0x0005;
+// CODE SIGNING (from the xnu cs_blobs.h, every field in these blobs is big endian)
+pub const CSMAGIC_EMBEDDED_SIGNATURE: u32                              = 0xfade0cc0;
+pub const CSMAGIC_CODEDIRECTORY: u32                                   = 0xfade0c02;
+pub const CSSLOT_CODEDIRECTORY: u32                                    = 0x0000;
+pub const CSSLOT_ALTERNATE_CODEDIRECTORIES: u32                        = 0x1000;
+pub const CSSLOT_ALTERNATE_CODEDIRECTORY_MAX: u32                      = 5;
+pub const CS_HASHTYPE_SHA1: u8                                         = 1;
+pub const CS_HASHTYPE_SHA256: u8                                       = 2;
+pub const CS_HASHTYPE_SHA256_TRUNCATED: u8                             = 3;
+pub const CS_HASHTYPE_SHA384: u8                                       = 4;
+pub const CS_CDHASH_LEN: usize                                         = 20; // every cdhash is cut to this, whatever the hash type
+pub const CS_VALID: u32                                               = 0x00000001;
+pub const CS_ADHOC: u32                                               = 0x00000002;
+pub const CS_GET_TASK_ALLOW: u32                                      = 0x00000004;
+pub const CS_INSTALLER: u32                                           = 0x00000008;
+pub const CS_FORCED_LV: u32                                           = 0x00000010;
+pub const CS_INVALID_ALLOWED: u32                                     = 0x00000020;
+pub const CS_HARD: u32                                                = 0x00000100;
+pub const CS_KILL: u32                                                = 0x00000200;
+pub const CS_CHECK_EXPIRATION: u32                                    = 0x00000400;
+pub const CS_RESTRICT: u32                                            = 0x00000800;
+pub const CS_ENFORCEMENT: u32                                         = 0x00001000;
+pub const CS_REQUIRE_LV: u32                                          = 0x00002000; // library validation
+pub const CS_ENTITLEMENTS_VALIDATED: u32                              = 0x00004000;
+pub const CS_NVRAM_UNRESTRICTED: u32                                  = 0x00008000;
+pub const CS_RUNTIME: u32                                             = 0x00010000; // hardened runtime
+pub const CS_LINKER_SIGNED: u32                                       = 0x00020000;
+pub const CS_PLATFORM_BINARY: u32                                     = 0x04000000;
 
 
 
@@ -825,6 +960,141 @@ pub fn filetype_name(filetype: u32) -> &'static str {
 }
 
 
+// Bare MH_* name, for places that need a single token (--identify) rather than the description above
+pub fn filetype_short_name(filetype: u32) -> &'static str {
+    match filetype {
+        MH_OBJECT        => "MH_OBJECT",
+        MH_EXECUTE       => "MH_EXECUTE",
+        MH_FVMLIB        => "MH_FVMLIB",
+        MH_CORE          => "MH_CORE",
+        MH_PRELOAD       => "MH_PRELOAD",
+        MH_DYLIB         => "MH_DYLIB",
+        MH_DYLINKER      => "MH_DYLINKER",
+        MH_BUNDLE        => "MH_BUNDLE",
+        MH_DYLIB_STUB    => "MH_DYLIB_STUB",
+        MH_DSYM          => "MH_DSYM",
+        MH_KEXT_BUNDLE   => "MH_KEXT_BUNDLE",
+        MH_FILESET       => "MH_FILESET",
+        _ => "MH_UNKNOWN",
+    }
+}
+
+// The -arch spelling lipo/nm/clang use (arm64e, x86_64, armv7s, i386, ...)
+pub fn arch_name(cputype: i32, cpusubtype: i32) -> &'static str {
+    let subtype = cpusubtype & !CPU_SUBTYPE_MASK;
+
+    match cputype {
+        CPU_TYPE_ARM64 => {
+            if (cpusubtype & CPU_SUBTYPE_PTRAUTH_ABI) != 0 || subtype == CPU_SUBTYPE_ARM64E {
+                "arm64e"
+            } else {
+                "arm64"
+            }
+        }
+        CPU_TYPE_ARM => match subtype {
+            CPU_SUBTYPE_ARM_V6  => "armv6",
+            CPU_SUBTYPE_ARM_V7  => "armv7",
+            CPU_SUBTYPE_ARM_V7S => "armv7s",
+            CPU_SUBTYPE_ARM_V7K => "armv7k",
+            CPU_SUBTYPE_ARM_V8  => "armv8",
+            _ => "arm",
+        },
+        CPU_TYPE_X86_64 => "x86_64",
+        CPU_TYPE_X86 => "i386",
+        CPU_TYPE_POWERPC => "ppc",
+        _ => "unknown",
+    }
+}
+
+
+pub fn stab_type_name(n_type: u8) -> &'static str {
+    // Caller is expected to have already checked (n_type & N_STAB) != 0
+    match n_type {
+        N_GSYM    => "N_GSYM",
+        N_FNAME   => "N_FNAME",
+        N_FUN     => "N_FUN",
+        N_STSYM   => "N_STSYM",
+        N_LCSYM   => "N_LCSYM",
+        N_BNSYM   => "N_BNSYM",
+        N_AST     => "N_AST",
+        N_OPT     => "N_OPT",
+        N_RSYM    => "N_RSYM",
+        N_SLINE   => "N_SLINE",
+        N_ENSYM   => "N_ENSYM",
+        N_SSYM    => "N_SSYM",
+        N_SO      => "N_SO",
+        N_OSO     => "N_OSO",
+        N_LSYM    => "N_LSYM",
+        N_BINCL   => "N_BINCL",
+        N_SOL     => "N_SOL",
+        N_PARAMS  => "N_PARAMS",
+        N_VERSION => "N_VERSION",
+        N_OLEVEL  => "N_OLEVEL",
+        N_PSYM    => "N_PSYM",
+        N_EINCL   => "N_EINCL",
+        N_ENTRY   => "N_ENTRY",
+        N_LBRAC   => "N_LBRAC",
+        N_EXCL    => "N_EXCL",
+        N_RBRAC   => "N_RBRAC",
+        N_BCOMM   => "N_BCOMM",
+        N_ECOMM   => "N_ECOMM",
+        N_ECOML   => "N_ECOML",
+        N_LENG    => "N_LENG",
+        _ => "Unknown STAB",
+    }
+}
+
+
+pub fn section_type_name(flags: u32) -> &'static str {
+    // Low byte of section flags, mutually exclusive
+    match flags & SECTION_TYPE {
+        S_REGULAR                               => "S_REGULAR",
+        S_ZEROFILL                              => "S_ZEROFILL",
+        S_CSTRING_LITERALS                      => "S_CSTRING_LITERALS",
+        S_4BYTE_LITERALS                        => "S_4BYTE_LITERALS",
+        S_8BYTE_LITERALS                        => "S_8BYTE_LITERALS",
+        S_LITERAL_POINTERS                      => "S_LITERAL_POINTERS",
+        S_NON_LAZY_SYMBOL_POINTERS              => "S_NON_LAZY_SYMBOL_POINTERS",
+        S_LAZY_SYMBOL_POINTERS                  => "S_LAZY_SYMBOL_POINTERS",
+        S_SYMBOL_STUBS                          => "S_SYMBOL_STUBS",
+        S_MOD_INIT_FUNC_POINTERS                => "S_MOD_INIT_FUNC_POINTERS",
+        S_MOD_TERM_FUNC_POINTERS                => "S_MOD_TERM_FUNC_POINTERS",
+        S_COALESCED                             => "S_COALESCED",
+        S_GB_ZEROFILL                           => "S_GB_ZEROFILL",
+        S_INTERPOSING                           => "S_INTERPOSING",
+        S_16BYTE_LITERALS                       => "S_16BYTE_LITERALS",
+        S_DTRACE_DOF                            => "S_DTRACE_DOF",
+        S_LAZY_DYLUB_SYMBOL_POINTERS            => "S_LAZY_DYLIB_SYMBOL_POINTERS",
+        S_THREAD_LOCAL_REGULAR                  => "S_THREAD_LOCAL_REGULAR",
+        S_THREAD_LOCAL_ZEROFILL                 => "S_THREAD_LOCAL_ZEROFILL",
+        S_THREAD_LOCAL_VARIABLES                => "S_THREAD_LOCAL_VARIABLES",
+        S_THREAD_LOCAL_VARIABLE_POINTERS        => "S_THREAD_LOCAL_VARIABLE_POINTERS",
+        S_THREAD_LOCAL_INIT_FUNCTION_POINTERS   => "S_THREAD_LOCAL_INIT_FUNCTION_POINTERS",
+        _ => "Unknown Section Type",
+    }
+}
+
+pub fn section_attribute_names(flags: u32) -> Vec<&'static str> {
+    // Upper 24 bits of section flags, any combination can be set
+    const ATTRIBUTES: [(u32, &str); 10] = [
+        (S_ATTR_PURE_INSTRUCTIONS,   "S_ATTR_PURE_INSTRUCTIONS"),
+        (S_ATTR_NO_TOC,              "S_ATTR_NO_TOC"),
+        (S_ATTR_STRIP_STATIC_SYMS,   "S_ATTR_STRIP_STATIC_SYMS"),
+        (S_ATTR_NO_DEAD_STRIP,       "S_ATTR_NO_DEAD_STRIP"),
+        (S_ATTR_LIVE_SUPPORT,        "S_ATTR_LIVE_SUPPORT"),
+        (S_ATTR_SELF_MODIFYING_CODE, "S_ATTR_SELF_MODIFYING_CODE"),
+        (S_ATTR_DEBUG,               "S_ATTR_DEBUG"),
+        (S_ATTR_SOME_INSTRUCTIONS,   "S_ATTR_SOME_INSTRUCTIONS"),
+        (S_ATTR_EXT_RELOC,           "S_ATTR_EXT_RELOC"),
+        (S_ATTR_LOC_RELOC,           "S_ATTR_LOC_RELOC"),
+    ];
+
+    ATTRIBUTES
+        .iter()
+        .filter(|(bit, _)| flags & bit != 0)
+        .map(|(_, name)| *name)
+        .collect()
+}
 
 /*
 ============================
@@ -965,4 +1235,45 @@ mod tests {
             "Unknown File Type"
         );
     }
+
+    // stab_type_name() tests
+    #[test]
+    fn stab_type_name_decodes_common_entries() {
+        assert_eq!(stab_type_name(N_SO), "N_SO");
+        assert_eq!(stab_type_name(N_OSO), "N_OSO");
+        assert_eq!(stab_type_name(N_FUN), "N_FUN");
+        assert_eq!(stab_type_name(N_SLINE), "N_SLINE");
+    }
+
+    #[test]
+    fn stab_type_name_unknown() {
+        assert_eq!(stab_type_name(0xf0), "Unknown STAB");
+    }
+
+    // section_type_name() / section_attribute_names() tests
+    #[test]
+    fn section_flags_split_into_type_and_attributes() {
+        let flags = S_SYMBOL_STUBS | S_ATTR_PURE_INSTRUCTIONS | S_ATTR_SOME_INSTRUCTIONS;
+
+        assert_eq!(section_type_name(flags), "S_SYMBOL_STUBS");
+        assert_eq!(section_attribute_names(flags), vec!["S_ATTR_PURE_INSTRUCTIONS", "S_ATTR_SOME_INSTRUCTIONS"]);
+    }
+
+    #[test]
+    fn section_type_name_unknown() {
+        assert_eq!(section_type_name(0xff), "Unknown Section Type");
+        assert!(section_attribute_names(S_REGULAR).is_empty());
+    }
+
+    // arch_name() / filetype_short_name() tests
+    #[test]
+    fn arch_name_uses_lipo_spelling() {
+        assert_eq!(arch_name(CPU_TYPE_ARM64, CPU_SUBTYPE_ARM64_ALL), "arm64");
+        assert_eq!(arch_name(CPU_TYPE_ARM64, CPU_SUBTYPE_ARM64E | CPU_SUBTYPE_PTRAUTH_ABI), "arm64e");
+        assert_eq!(arch_name(CPU_TYPE_X86_64, CPU_SUBTYPE_X86_ALL), "x86_64");
+        assert_eq!(arch_name(CPU_TYPE_X86, CPU_SUBTYPE_X86_ALL), "i386");
+        assert_eq!(arch_name(CPU_TYPE_ARM, CPU_SUBTYPE_ARM_V7S), "armv7s");
+        assert_eq!(filetype_short_name(MH_EXECUTE), "MH_EXECUTE");
+        assert_eq!(filetype_short_name(0xFFFF), "MH_UNKNOWN");
+    }
 }
@@ -0,0 +1,183 @@
+// File Purpose: LC_DATA_IN_CODE, the ranges of __text that are really data (jump tables, literal pools)
+
+use std::error::Error;
+use std::io::{self, Write};
+use colored::Colorize;
+use crate::macho::constants::*;
+use crate::macho::load_commands::LinkEditDataCommand;
+use crate::macho::segments::ParsedSegment;
+use crate::macho::utils;
+use crate::reporting::data_in_code::DataInCodeEntryReport;
+
+
+/*
+LC_DATA_IN_CODE is a linkedit_data_command, the payload is a flat array of (from loader.h):
+
+    data_in_code_entry
+    +-----------------------------+
+    | offset (u32)                |  file offset of the data, from the mach_header (slice relative)
+    | length (u16)                |  number of bytes of data
+    | kind (u16)                  |  DICE_KIND_*
+    +-----------------------------+
+
+    DICE_KIND_DATA              0x1  plain data / literal pool
+    DICE_KIND_JUMP_TABLE8       0x2  jump table of 1 byte entries
+    DICE_KIND_JUMP_TABLE16      0x3  ... 2 byte entries
+    DICE_KIND_JUMP_TABLE32      0x4  ... 4 byte entries
+    DICE_KIND_ABS_JUMP_TABLE32  0x5  4 byte absolute addresses
+
+Mostly arm/arm64 (the assembler drops these between instructions), x86 binaries usually carry an
+empty one. A disassembler that walks __text blindly decodes these ranges as garbage instructions.
+*/
+
+pub const DATA_IN_CODE_ENTRY_SIZE: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataInCodeEntry {
+    pub offset: u32,
+    pub length: u16,
+    pub kind: u16,
+}
+
+impl DataInCodeEntry {
+    pub fn kind_str(&self) -> String {
+        match self.kind {
+            DICE_KIND_DATA => "DATA".to_string(),
+            DICE_KIND_JUMP_TABLE8 => "JUMP_TABLE8".to_string(),
+            DICE_KIND_JUMP_TABLE16 => "JUMP_TABLE16".to_string(),
+            DICE_KIND_JUMP_TABLE32 => "JUMP_TABLE32".to_string(),
+            DICE_KIND_ABS_JUMP_TABLE32 => "ABS_JUMP_TABLE32".to_string(),
+            other => format!("UNKNOWN(0x{:x})", other),
+        }
+    }
+
+    pub fn build_report(&self, segments: &[ParsedSegment]) -> DataInCodeEntryReport {
+        let offset = self.offset as u64;
+        let vm_addr = segments
+            .iter()
+            .find(|seg| seg.filesize > 0 && offset >= seg.fileoff && offset - seg.fileoff < seg.filesize)
+            .map(|seg| seg.vmaddr + (offset - seg.fileoff));
+
+        DataInCodeEntryReport {
+            offset: self.offset,
+            vm_addr,
+            length: self.length,
+            kind: self.kind_str(),
+        }
+    }
+}
+
+// datasize / 8 entries, a datasize that isn't a multiple of 8 or runs off the file is malformed
+pub fn parse_data_in_code(data: &[u8], linkedit: &LinkEditDataCommand, slice_offset: u64, is_be: bool) -> Result<Vec<DataInCodeEntry>, Box<dyn Error>> {
+    if !(linkedit.datasize as usize).is_multiple_of(DATA_IN_CODE_ENTRY_SIZE) {
+        return Err(format!("LC_DATA_IN_CODE datasize {} is not a multiple of {}", linkedit.datasize, DATA_IN_CODE_ENTRY_SIZE).into());
+    }
+
+    let start = slice_offset as usize + linkedit.dataoff as usize;
+    let end = start + linkedit.datasize as usize;
+    if end > data.len() {
+        return Err(format!("LC_DATA_IN_CODE data (0x{:x}..0x{:x}) exceeds file bounds", start, end).into());
+    }
+
+    data[start..end]
+        .chunks_exact(DATA_IN_CODE_ENTRY_SIZE)
+        .map(|entry| {
+            Ok(DataInCodeEntry {
+                offset: utils::bytes_to(is_be, &entry[0..4])?,
+                length: utils::bytes_to(is_be, &entry[4..6])?,
+                kind: utils::bytes_to(is_be, &entry[6..8])?,
+            })
+        })
+        .collect()
+}
+
+pub fn write_data_in_code(w: &mut dyn Write, entries: &[DataInCodeEntryReport]) -> io::Result<()> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(w)?;
+    writeln!(w, "{}", "Data In Code".green().bold())?;
+    writeln!(w, "----------------------------------------")?;
+    writeln!(w, "{:<12} {:<20} {:<8} Kind", "Offset", "Address", "Length")?;
+    for entry in entries {
+        writeln!(
+            w,
+            "{:<12} {:<20} {:<8} {}",
+            format!("0x{:x}", entry.offset),
+            entry.vm_addr.map(|a| format!("0x{:016x}", a)).unwrap_or_else(|| "-".to_string()),
+            entry.length,
+            entry.kind.cyan()
+        )?;
+    }
+    writeln!(w, "----------------------------------------")?;
+    Ok(())
+}
+
+
+/*
+============================
+          UNIT TESTS
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry_bytes(offset: u32, length: u16, kind: u16) -> Vec<u8> {
+        let mut bytes = offset.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&length.to_le_bytes());
+        bytes.extend_from_slice(&kind.to_le_bytes());
+        bytes
+    }
+
+    fn linkedit(dataoff: u32, datasize: u32) -> LinkEditDataCommand {
+        LinkEditDataCommand { cmd: LC_DATA_IN_CODE, dataoff, datasize }
+    }
+
+    #[test]
+    fn entries_are_read_and_classified() {
+        let mut data = vec![0u8; 0x10];
+        data.extend(entry_bytes(0x3f00, 8, DICE_KIND_DATA));
+        data.extend(entry_bytes(0x3f40, 16, DICE_KIND_JUMP_TABLE32));
+        data.extend(entry_bytes(0x3f80, 4, 0x42));
+
+        let entries = parse_data_in_code(&data, &linkedit(0x10, 24), 0, false).unwrap();
+        assert_eq!(entries[1], DataInCodeEntry { offset: 0x3f40, length: 16, kind: DICE_KIND_JUMP_TABLE32 });
+        assert_eq!(
+            entries.iter().map(|e| e.kind_str()).collect::<Vec<_>>(),
+            ["DATA", "JUMP_TABLE32", "UNKNOWN(0x42)"]
+        );
+    }
+
+    #[test]
+    fn offsets_map_through_their_segment() {
+        let text = ParsedSegment {
+            segname: SEG_TEXT,
+            vmaddr: 0x1_0000_0000,
+            vmsize: 0x4000,
+            fileoff: 0,
+            filesize: 0x4000,
+            maxprot: 5,
+            initprot: 5,
+            flags: 0,
+            sections: Vec::new(),
+        };
+        let entry = DataInCodeEntry { offset: 0x3f00, length: 4, kind: DICE_KIND_ABS_JUMP_TABLE32 };
+
+        let report = entry.build_report(&[text]);
+        assert_eq!(report.vm_addr, Some(0x1_0000_3f00));
+        assert_eq!(report.kind, "ABS_JUMP_TABLE32");
+        assert_eq!(entry.build_report(&[]).vm_addr, None);
+    }
+
+    #[test]
+    fn malformed_payloads_are_errors() {
+        let data = entry_bytes(0x3f00, 8, DICE_KIND_DATA);
+
+        assert!(parse_data_in_code(&data, &linkedit(0, 6), 0, false).is_err());
+        assert!(parse_data_in_code(&data, &linkedit(0, 16), 0, false).is_err());
+        assert!(parse_data_in_code(&data, &linkedit(0, 0), 0, false).unwrap().is_empty());
+    }
+}
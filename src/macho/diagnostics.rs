@@ -0,0 +1,78 @@
+// File Purpose: Structural problems found while parsing that aren't fatal enough to bail on
+
+use std::io::{self, Write};
+use colored::Colorize;
+use crate::reporting::diagnostics::DiagnosticReport;
+
+/*
+The parsers are deliberately fault tolerant, a malformed-but-loadable binary should
+still produce a report. Anything odd we notice along the way (sections escaping their
+segment, weird rpaths, ...) gets recorded here instead of being silently ignored.
+
+    Warning --> unusual, may be intentional or toolchain specific
+    Error   --> structurally inconsistent, corruption or crafted confusion
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: &'static str, // short stable identifier, e.g. "section-outside-segment-vm"
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn warning(code: &'static str, message: String) -> Self {
+        Diagnostic { severity: Severity::Warning, code, message }
+    }
+
+    pub fn error(code: &'static str, message: String) -> Self {
+        Diagnostic { severity: Severity::Error, code, message }
+    }
+
+    pub fn build_report(&self) -> DiagnosticReport {
+        DiagnosticReport {
+            severity: self.severity.as_str().to_string(),
+            code: self.code.to_string(),
+            message: self.message.clone(),
+        }
+    }
+}
+
+
+pub fn write_diagnostics_summary(w: &mut dyn Write, diagnostics: &[DiagnosticReport]) -> io::Result<()> {
+    write_titled_diagnostic_reports(w, "Diagnostics", diagnostics)
+}
+
+pub fn write_titled_diagnostic_reports(w: &mut dyn Write, title: &str, diagnostics: &[DiagnosticReport]) -> io::Result<()> {
+    if diagnostics.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(w)?;
+    writeln!(w, "{}", title.green().bold())?;
+    writeln!(w, "----------------------------------------")?;
+
+    for d in diagnostics {
+        let label = format!("{:<8}", d.severity);
+        let severity = if d.severity == Severity::Error.as_str() { label.red().bold() } else { label.yellow().bold() };
+        writeln!(w, "{} [{}] {}", severity, d.code, d.message)?;
+    }
+
+    writeln!(w, "----------------------------------------")?;
+    Ok(())
+}
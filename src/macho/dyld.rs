@@ -21,19 +21,20 @@
 
 */
 use std::error::Error;
-use std::mem;
+use std::io::{self, Write};
 use colored::Colorize;
 use crate::macho::constants::*;
 use crate::macho::memory_image::MachOMemoryImage;
 use crate::macho::segments::ParsedSegment;
-use crate::macho::utils::{byte_array_to_string, read_sleb, read_uleb};
-use crate::macho::{sections::ParsedSection, symtab::ParsedSymbol};
-use crate::reporting::dyld::FixupReport;
+use crate::macho::load_commands::{self, LoadCommand};
+use crate::macho::utils::{byte_array_to_string, bytes_to, read_sleb, read_uleb};
+use crate::macho::dylibs::{self, ParsedDylib};
+use crate::reporting::dyld::{BoundSymbolReport, DyldInfoReport, FixupReport};
 
 
 // from mach-o/loader.h
 #[derive(Debug, Clone, Copy)]
-pub struct DYLDInfoCommand {
+pub struct DyldInfoCommand {
     pub cmd: u32,                   // LC_DYLD_INFO or LC_DYLD_INFO_ONLY
     pub cmdsize: u32,               // sizeof(struct dyld_info_command)
     // Adjust for ASLR
@@ -56,12 +57,49 @@ pub struct DYLDInfoCommand {
     pub export_size: u32,           // size of lazy binding info
 }
 
-#[derive(Debug, Clone)]
-pub struct ThreadedBindEntry {
-    dylib_ordinal: i32,
-    symbol_name: String,
-    bind_type: u8,
-    addend: i64
+impl DyldInfoCommand {
+    pub fn build_report(&self) -> DyldInfoReport {
+        DyldInfoReport {
+            cmd: load_commands::load_command_name(self.cmd).to_string(),
+            rebase_off: self.rebase_off,
+            rebase_size: self.rebase_size,
+            bind_off: self.bind_off,
+            bind_size: self.bind_size,
+            weak_bind_off: self.weak_bind_off,
+            weak_bind_size: self.weak_bind_size,
+            lazy_bind_off: self.lazy_bind_off,
+            lazy_bind_size: self.lazy_bind_size,
+            export_off: self.export_off,
+            export_size: self.export_size,
+        }
+    }
+}
+
+pub const DYLD_INFO_COMMAND_SIZE: u32 = 48;
+
+pub fn read_dyld_info_command(data: &[u8], lc: &LoadCommand, is_be: bool) -> Result<DyldInfoCommand, Box<dyn Error>> {
+    let name = load_commands::load_command_name(lc.cmd);
+    if lc.cmdsize != DYLD_INFO_COMMAND_SIZE {
+        return Err(format!("{} has cmdsize {} (expected {})", name, lc.cmdsize, DYLD_INFO_COMMAND_SIZE).into());
+    }
+
+    let off = lc.offset as usize;
+    let fields = data.get(off + 8 .. off + DYLD_INFO_COMMAND_SIZE as usize).ok_or_else(|| format!("{} exceeds file bounds", name))?;
+    let field = |i: usize| bytes_to::<u32>(is_be, &fields[i * 4 .. i * 4 + 4]);
+    Ok(DyldInfoCommand {
+        cmd: lc.cmd,
+        cmdsize: lc.cmdsize,
+        rebase_off: field(0)?,
+        rebase_size: field(1)?,
+        bind_off: field(2)?,
+        bind_size: field(3)?,
+        weak_bind_off: field(4)?,
+        weak_bind_size: field(5)?,
+        lazy_bind_off: field(6)?,
+        lazy_bind_size: field(7)?,
+        export_off: field(8)?,
+        export_size: field(9)?,
+    })
 }
 
 #[derive(Debug, Clone)]
@@ -123,55 +161,77 @@ impl Fixup  {
         }
     }
 
+    // rebase_off / bind_off are slice relative, `slice_offset` is where this slice starts in `data`
     pub fn parse(
-        dyld_info: &DYLDInfoCommand,
+        dyld_info: &DyldInfoCommand,
         segments: &[ParsedSegment],
-        symbols: &[ParsedSymbol],
         slide: u64, // ASLR slide
         memory: &MachOMemoryImage,
         data: &[u8], // mach-o bytes
+        slice_offset: u64,
+        is_64: bool,
     ) -> Result<Vec<Fixup>, Box<dyn Error>> {
         let mut fixups = Vec::new();
+        let pointer_size: u64 = if is_64 { 8 } else { 4 };
 
         // parse each type of classic fixup 
-        Self::parse_rebase(dyld_info, segments, slide, data, &mut fixups)?;
-        Self::parse_bind(dyld_info, segments, symbols, slide, data, memory, &mut fixups)?;
-        
+        let rebase_stream = opcode_stream(data, slice_offset, dyld_info.rebase_off, dyld_info.rebase_size, "rebase")?;
+        Self::parse_rebase(rebase_stream, segments, slide, pointer_size, &mut fixups)?;
+
+        // Binds come out of the same walk as the import list, threaded ones followed through the image
+        let bind_stream = opcode_stream(data, slice_offset, dyld_info.bind_off, dyld_info.bind_size, "bind")?;
+        walk_bind_opcodes(bind_stream, segments, pointer_size, Some(memory), |bind| {
+            fixups.push(Fixup::Bind {
+                addr: segments[bind.segment_index as usize].vmaddr + bind.segment_offset + slide,
+                symbol: bind.symbol_name,
+                addend: bind.addend,
+            });
+            Ok(())
+        })?;
+
         Ok(fixups)
     }
 
     fn parse_rebase(
-        dyld_info: &DYLDInfoCommand,
+        stream: &[u8],
         segments: &[ParsedSegment],
         slide: u64, // ASLR slide
-        data: &[u8], // mach-o bytes
+        pointer_size: u64,
         fixups: &mut Vec<Fixup>
     ) -> Result<(), Box<dyn Error>> {
-        let start = dyld_info.rebase_off as usize;
-        let end = start + dyld_info.rebase_size as usize;
-        let stream = &data[start..end];
-
         let mut cursor = 0;
+        let mut segment: Option<&ParsedSegment> = None;
         let mut address: u64 = 0;
-        let mut type_ = 0;
+        let mut _rebase_type = 0;
 
         // https://github.com/apple-opensource/dyld/blob/master/dyld3/MachOAnalyzer.cpp#L1444
         // Apple's dyld/dyld3/MachOAnalyzer::forEachRebase handles the rebasing
-        
+
+        // Every rebase has to land inside the segment set by SET_SEGMENT_AND_OFFSET, which also
+        // stops a huge ULEB count from looping (and allocating) forever
+        let mut rebase_here = |segment: Option<&ParsedSegment>, address: u64| -> Result<(), Box<dyn Error>> {
+            let seg = segment.ok_or("rebase opcode before any segment was set")?;
+            if address < seg.vmaddr || address - seg.vmaddr >= seg.vmsize {
+                return Err(format!("rebase at 0x{:x} is outside {}", address, byte_array_to_string(&seg.segname)).into());
+            }
+            fixups.push(Fixup::Rebase { addr: address + slide });
+            Ok(())
+        };
 
         while cursor < stream.len() {
             let opcode = stream[cursor];
             cursor += 1;
             match opcode & REBASE_OPCODE_MASK {
                 REBASE_OPCODE_SET_TYPE_IMM => { // 0x10
-                    type_ = opcode & REBASE_IMMEDIATE_MASK; // SET_TYPE_IMM
+                    _rebase_type = opcode & REBASE_IMMEDIATE_MASK; // SET_TYPE_IMM
                 }
                 REBASE_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB => { // 0x20
                     let seg_index = (opcode & REBASE_IMMEDIATE_MASK) as usize;
                     
                     let offset = read_uleb(stream, &mut cursor)?;
-                    let seg = &segments[seg_index];
-                    address = seg.vmaddr + offset;
+                    let seg = segments.get(seg_index).ok_or_else(|| format!("rebase opcodes reference segment {} of {}", seg_index, segments.len()))?;
+                    address = seg.vmaddr.wrapping_add(offset);
+                    segment = Some(seg);
                 }
 
                 REBASE_OPCODE_ADD_ADDR_ULEB => { // 0x30
@@ -184,29 +244,29 @@ impl Fixup  {
 
                 REBASE_OPCODE_ADD_ADDR_IMM_SCALED => { // 0x40
                     let scale = (opcode & REBASE_IMMEDIATE_MASK) as u64;
-                    address += scale * 8; // 8 --> scale * pointer size
+                    address = address.wrapping_add(scale * pointer_size);
                 }
                 REBASE_OPCODE_DO_REBASE_IMM_TIMES => { // 0x50
                     let count = (opcode & REBASE_IMMEDIATE_MASK) as u64;
                     for _ in 0..count {
-                        fixups.push(Fixup::Rebase { addr: address + slide });
-                        address += 8; // 64 bit pointer size
+                        rebase_here(segment, address)?;
+                        address = address.wrapping_add(pointer_size);
                     }
                 }
                 REBASE_OPCODE_DO_REBASE_ULEB_TIMES => { // 0x60
                     
                     let count = read_uleb(stream, &mut cursor)?;
                     for _ in 0..count {
-                        fixups.push(Fixup::Rebase { addr: address + slide });
-                        address += 8;
+                        rebase_here(segment, address)?;
+                        address = address.wrapping_add(pointer_size);
                     }
                 }
 
                 REBASE_OPCODE_DO_REBASE_ADD_ADDR_ULEB => { // 0x70
-                    fixups.push(Fixup::Rebase { addr: address + slide });
+                    rebase_here(segment, address)?;
                     
                     let skip = read_uleb(stream, &mut cursor)?;
-                    address = address.checked_add(skip + 8)
+                    address = address.checked_add(skip + pointer_size)
                         .ok_or("address overflow during DO_REBASE_ADD_ADDR_ULEB")?;
                 }
 
@@ -216,8 +276,8 @@ impl Fixup  {
                     
                     let skip = read_uleb(stream, &mut cursor)?;
                     for _ in 0..count {
-                        fixups.push(Fixup::Rebase { addr: address + slide });
-                        address = address.checked_add(skip + 8)
+                        rebase_here(segment, address)?;
+                        address = address.checked_add(skip.saturating_add(pointer_size))
                             .ok_or("address overflow during DO_REBASE_ULEB_TIMES_SKIPPING_ULEB")?;
                     }
                 }
@@ -234,315 +294,487 @@ impl Fixup  {
 
         Ok(())
     }
+}
 
+// One of LC_DYLD_INFO's opcode tables, `off` is slice relative
+fn opcode_stream<'a>(data: &'a [u8], slice_offset: u64, off: u32, size: u32, name: &str) -> Result<&'a [u8], Box<dyn Error>> {
+    let start = slice_offset + off as u64;
+    let end = start + size as u64;
+    data.get(start as usize..end as usize)
+        .ok_or_else(|| format!("{} opcodes (0x{:x}..0x{:x}) exceed file bounds", name, start, end).into())
+}
 
-    fn parse_bind(
-        dyld_info: &DYLDInfoCommand,
-        segments: &[ParsedSegment],
-        symbols: &[ParsedSymbol],
-        slide: u64, 
-        data: &[u8], 
-        memory: &MachOMemoryImage,
-        fixups: &mut Vec<Fixup>
-    ) -> Result<(), Box<dyn Error>> {
-
-        let start = dyld_info.bind_off as usize;
-        let end = start + dyld_info.bind_size as usize;
-        let stream = &data[start..end];
-
-        let mut cursor = 0;
-        
-        let mut segment_index: usize = 0;
-        let mut segment_offset: u64 = 0;
-        let mut symbol_name: Option<String> = None;
-        let mut addend: i64 = 0;
-        let mut bind_type: u8 = BIND_TYPE_POINTER; // 1
-        let mut dylib_ordinal: i32 = 1;
-
-        let mut threaded = false;
-        let mut threaded_table_size: usize = 0;
-        let mut threaded_bind_table: Vec<ThreadedBindEntry> = Vec::new();
-
-        while cursor < stream.len() {
-            let opcode = stream[cursor];
-            cursor += 1;
-
-            
-
-            match opcode & BIND_OPCODE_MASK {
-
-                BIND_OPCODE_SET_DYLIB_ORDINAL_IMM => {
-                    // This opcode tells which dylib a symbol comes from
-                    dylib_ordinal = (opcode & BIND_IMMEDIATE_MASK) as i32;
-                }
-
-                BIND_OPCODE_SET_DYLIB_ORDINAL_ULEB => {
-                    // Ordinal is encoded as a ULEB128 immediately after the opcode
-                    
-                    dylib_ordinal = read_uleb(stream, &mut cursor)? as i32;
-                }
-
-                BIND_OPCODE_SET_DYLIB_SPECIAL_IMM => {
-                    // The value is a 4 bit signed immediate:
-                    // 0 --> self
-                    // -1 --> MAIN_EXECUTABLE
-                    // -2 --> FLAT_LOOKUP
-                    // -3 --> WEAK_LOOKUP
-                    let imm = (opcode & BIND_IMMEDIATE_MASK) as i8;
-                    dylib_ordinal = match imm {
-                        0 => BIND_SPECIAL_DYLIB_SELF,
-                        1 => BIND_SPECIAL_DYLIB_MAIN_EXECUTABLE,
-                        2 => BIND_SPECIAL_DYLIB_FLAT_LOOKUP,
-                        3 => BIND_SPECIAL_DYLIB_WEAK_LOOKUP,
-                        _ => return Err(format!("Invalid special dyld immediate {imm}").into())
-                    };
-                }
 
-                BIND_OPCODE_SET_SYMBOL_TRAILING_FLAGS_IMM => {
-                    let mut name_bytes: Vec<u8> = Vec::new();
-                    while cursor < stream.len() && stream[cursor] != 0 {
-                        name_bytes.push(stream[cursor]);
-                        cursor += 1;
-                    }
-                    cursor += 1; // null byte terminating name
-                    symbol_name = Some(String::from_utf8(name_bytes)?);
-                }
+/*
+One DO_BIND* out of the non-lazy bind stream, kept in the stream's own terms (segment index +
+offset, raw ordinal) so it can be decoded without a VM image. Fixup::parse gets its binds out of
+the same walk_bind_opcodes, this is the import list for when all you want is names.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoundSymbol {
+    pub segment_index: u8,
+    pub segment_offset: u64,
+    pub dylib_ordinal: i32, // > 0 dependency ordinal, <= 0 one of BIND_SPECIAL_DYLIB_*
+    pub symbol_name: String,
+    pub bind_type: u8,      // BIND_TYPE_*
+    pub addend: i64,
+}
 
-                BIND_OPCODE_SET_TYPE_IMM => {
-                    bind_type = opcode & BIND_IMMEDIATE_MASK; 
-                }
+impl BoundSymbol {
+    pub fn build_report(&self, segments: &[ParsedSegment], dylibs: &[ParsedDylib]) -> BoundSymbolReport {
+        let segment = segments.get(self.segment_index as usize);
+        BoundSymbolReport {
+            segment: segment.map(|seg| byte_array_to_string(&seg.segname)),
+            segment_offset: self.segment_offset,
+            addr: segment.map(|seg| seg.vmaddr + self.segment_offset),
+            dylib_ordinal: self.dylib_ordinal,
+            library: bind_ordinal_library(dylibs, self.dylib_ordinal),
+            symbol: self.symbol_name.clone(),
+            bind_type: bind_type_str(self.bind_type),
+            addend: self.addend,
+        }
+    }
+}
 
-                BIND_OPCODE_SET_ADDEND_SLEB => {
-                    addend = read_sleb(stream, &mut cursor)?;
-                }
+pub fn bind_type_str(bind_type: u8) -> String {
+    match bind_type {
+        BIND_TYPE_POINTER => "POINTER".to_string(),
+        BIND_TYPE_TEXT_ABSOLUTE32 => "TEXT_ABSOLUTE32".to_string(),
+        BIND_TYPE_TEXT_PCREL32 => "TEXT_PCREL32".to_string(),
+        other => format!("UNKNOWN({})", other),
+    }
+}
 
-                BIND_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB => {
-                    segment_index = (opcode & BIND_IMMEDIATE_MASK) as usize;
-                    
-                    segment_offset = read_uleb(stream, &mut cursor)?;
-                }
+// Bind ordinals are signed where the nlist ones are a byte, the specials don't line up with those
+pub fn bind_ordinal_library(dylibs: &[ParsedDylib], ordinal: i32) -> String {
+    match ordinal {
+        BIND_SPECIAL_DYLIB_SELF => "<self>".to_string(),
+        BIND_SPECIAL_DYLIB_MAIN_EXECUTABLE => "<main executable>".to_string(),
+        BIND_SPECIAL_DYLIB_FLAT_LOOKUP => "<flat lookup>".to_string(),
+        BIND_SPECIAL_DYLIB_WEAK_LOOKUP => "<weak lookup>".to_string(),
+        ordinal => match dylibs::dylib_for_ordinal(dylibs, ordinal as u64) {
+            Some(dylib) => dylib.path.clone(),
+            None => format!("<invalid ordinal {}>", ordinal),
+        },
+    }
+}
 
-                BIND_OPCODE_ADD_ADDR_ULEB => {
-                    
-                    let raw = read_uleb(stream, &mut cursor)?;
-                    let delta = raw as i64; // interpret as signed
-                    segment_offset = (segment_offset as i64)
-                        .checked_add(delta)
-                        .ok_or("segment_offset overflow during ADD_ADDR_ULEB")? as u64;
+/*
+The bind_off/bind_size opcode stream, interpreted the way dyld's forEachBind does:
+    SET_* opcodes update the running state (ordinal, name, type, addend, segment + offset)
+    DO_BIND* records one bind at the current location then advances past it (plus any skip)
+Every bind goes to `on_bind`, and has to land inside its segment: a bind past the segment's
+vmsize is an error, so a DO_BIND_ULEB_TIMES with a count near 2^64 stops at the segment's end
+instead of looping forever.
+
+Threaded binds (BIND_OPCODE_THREADED) switch the DO_BIND*s to filling an ordinal table, each
+THREADED_APPLY then follows a chain of pointers through the image, every link one bind from that
+table. Without an image to follow them through they're an error rather than a silently short list.
+*/
+pub fn walk_bind_opcodes(
+    stream: &[u8],
+    segments: &[ParsedSegment],
+    pointer_size: u64,
+    memory: Option<&MachOMemoryImage>,
+    mut on_bind: impl FnMut(BoundSymbol) -> Result<(), Box<dyn Error>>,
+) -> Result<(), Box<dyn Error>> {
+    let mut cursor = 0;
+    let mut segment_index: u8 = 0;
+    let mut segment_offset: u64 = 0;
+    let mut symbol_name: Option<String> = None;
+    let mut addend: i64 = 0;
+    let mut bind_type: u8 = BIND_TYPE_POINTER;
+    let mut dylib_ordinal: i32 = 0;
+
+    let mut threaded = false;
+    let mut threaded_table_size: usize = 0;
+    let mut threaded_bind_table: Vec<BoundSymbol> = Vec::new();
+
+    let segment_size = |segment_index: u8| -> Result<u64, Box<dyn Error>> {
+        segments
+            .get(segment_index as usize)
+            .map(|seg| seg.vmsize)
+            .ok_or_else(|| format!("bind opcodes reference segment {} of {}", segment_index, segments.len()).into())
+    };
+
+    while cursor < stream.len() {
+        let opcode = stream[cursor];
+        let immediate = opcode & BIND_IMMEDIATE_MASK;
+        cursor += 1;
+
+        // Records the current state, a DO_BIND before any SET_SYMBOL is malformed
+        let mut bind_here = |segment_offset: u64| -> Result<(), Box<dyn Error>> {
+            let name = symbol_name.clone().ok_or("bind opcode before any symbol name was set")?;
+            let bind = BoundSymbol { segment_index, segment_offset, dylib_ordinal, symbol_name: name, bind_type, addend };
+            if threaded {
+                // only the ordinal table entry matters, the location comes from THREADED_APPLY
+                if threaded_bind_table.len() >= threaded_table_size {
+                    return Err("Threaded bind table overflow".into());
                 }
+                threaded_bind_table.push(bind);
+                return Ok(());
+            }
+            let size = segment_size(segment_index)?;
+            if segment_offset >= size {
+                return Err(format!("bind at segment {} offset 0x{:x} is past the segment's end (vmsize 0x{:x})", segment_index, segment_offset, size).into());
+            }
+            on_bind(bind)
+        };
 
-                BIND_OPCODE_DO_BIND => {
-                    let seg = &segments[segment_index];
-                    let addr = seg.vmaddr + segment_offset;
-
-                    if threaded {
-                        if threaded_bind_table.len() >= threaded_table_size {
-                            return Err("Threaded bind table overflow".into());
-                        }
-
-                        threaded_bind_table.push(ThreadedBindEntry {
-                            dylib_ordinal,
-                            symbol_name: symbol_name.clone().unwrap_or_default(),
-                            bind_type,
-                            addend,
-                        });
-                    } else {
-                        if let Some(name) = &symbol_name {
-                            fixups.push(Fixup::Bind {
-                                addr: addr + slide,
-                                symbol: name.clone(),
-                                addend,
-                            });
-                        }
-                    }
-
-                    segment_offset += 8;
+        match opcode & BIND_OPCODE_MASK {
+            BIND_OPCODE_DONE => {
+                // ld pads with DONE between symbols in some streams, only the end of the stream stops us
+            }
+            BIND_OPCODE_SET_DYLIB_ORDINAL_IMM => dylib_ordinal = immediate as i32,
+            BIND_OPCODE_SET_DYLIB_ORDINAL_ULEB => dylib_ordinal = read_uleb(stream, &mut cursor)? as i32,
+            BIND_OPCODE_SET_DYLIB_SPECIAL_IMM => {
+                // 4 bit signed immediate: 0x0 self, 0xf main executable, 0xe flat, 0xd weak
+                dylib_ordinal = if immediate == 0 { 0 } else { (immediate | BIND_OPCODE_MASK) as i8 as i32 };
+            }
+            BIND_OPCODE_SET_SYMBOL_TRAILING_FLAGS_IMM => {
+                let len = stream[cursor..].iter().position(|&b| b == 0).ok_or("unterminated symbol name in bind opcodes")?;
+                symbol_name = Some(String::from_utf8_lossy(&stream[cursor..cursor + len]).to_string());
+                cursor += len + 1;
+            }
+            BIND_OPCODE_SET_TYPE_IMM => bind_type = immediate,
+            BIND_OPCODE_SET_ADDEND_SLEB => addend = read_sleb(stream, &mut cursor)?,
+            BIND_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB => {
+                segment_index = immediate;
+                segment_offset = read_uleb(stream, &mut cursor)?;
+            }
+            BIND_OPCODE_ADD_ADDR_ULEB => {
+                // Negative deltas are encoded as huge ULEBs, wrapping is the intended arithmetic
+                segment_offset = segment_offset.wrapping_add(read_uleb(stream, &mut cursor)?);
+            }
+            BIND_OPCODE_DO_BIND => {
+                bind_here(segment_offset)?;
+                segment_offset = segment_offset.wrapping_add(pointer_size);
+            }
+            BIND_OPCODE_DO_BIND_ADD_ADDR_ULEB => {
+                bind_here(segment_offset)?;
+                let skip = read_uleb(stream, &mut cursor)?;
+                segment_offset = segment_offset.wrapping_add(pointer_size).wrapping_add(skip);
+            }
+            BIND_OPCODE_DO_BIND_ADD_ADDR_IMM_SCALED => {
+                bind_here(segment_offset)?;
+                segment_offset = segment_offset.wrapping_add(pointer_size + immediate as u64 * pointer_size);
+            }
+            BIND_OPCODE_DO_BIND_ULEB_TIMES_SKIPPING_ULEB => {
+                let count = read_uleb(stream, &mut cursor)?;
+                let skip = read_uleb(stream, &mut cursor)?;
+                // each bind moves at least a pointer forward, more than fit in the segment can't be right
+                if !threaded && count > segment_size(segment_index)?.div_ceil(pointer_size) {
+                    return Err(format!("bind count {} doesn't fit in segment {}", count, segment_index).into());
                 }
-
-                BIND_OPCODE_DO_BIND_ADD_ADDR_ULEB => {
-                    
-                    let skip = read_uleb(stream, &mut cursor)?;
-                    let seg = &segments[segment_index];
-                    let addr = seg.vmaddr + segment_offset;
-
-                    if let Some(name) = &symbol_name {
-                        fixups.push(Fixup::Bind {
-                            addr: addr + slide,
-                            symbol: name.clone(),
-                            addend,
-                        });
-                    }
-
-                    segment_offset += 8 + skip;
+                for _ in 0..count {
+                    bind_here(segment_offset)?;
+                    segment_offset = segment_offset.wrapping_add(pointer_size).wrapping_add(skip);
                 }
-
-                BIND_OPCODE_DO_BIND_ADD_ADDR_IMM_SCALED => {
-                    let skip = (opcode & BIND_IMMEDIATE_MASK) as u64 * 8;
-                    let seg = &segments[segment_index];
-                    let addr = seg.vmaddr + segment_offset;
-
-                    if let Some(name) = &symbol_name {
-                        fixups.push(Fixup::Bind {
-                            addr: addr + slide,
-                            symbol: name.clone(),
-                            addend,
-                        });
+            }
+            BIND_OPCODE_THREADED => {
+                // what is threaded bind:
+                //  instead of encoding every DO_BIND, it sets up a threaded chain of pointers that dyld can follow
+                //  not to be confused with multithreading
+                // sub opcodes:
+                //      BIND_SUBOPCODE_THREADED_SET_BIND_ORDINAL_TABLE_SIZE_ULEB = 0x00
+                //          sets the size of the bind ordinal table
+                //      BIND_SUBOPCODE_THREADED_APPLY = 0x01
+                //          applies the threaded binds using the table
+                let memory = memory.ok_or("threaded binds can't be listed without following the image's pointer chains")?;
+                threaded = true;
+
+                match immediate {
+                    BIND_SUBOPCODE_THREADED_SET_BIND_ORDINAL_TABLE_SIZE_ULEB => {
+                        threaded_table_size = read_uleb(stream, &mut cursor)? as usize;
+                        // the table fills one DO_BIND at a time, a bogus size shouldn't reserve memory up front
+                        threaded_bind_table = Vec::new();
                     }
+                    BIND_SUBOPCODE_THREADED_APPLY => {
+                        let seg_vmaddr = segments.get(segment_index as usize).map(|seg| seg.vmaddr).ok_or("threaded bind in a segment that doesn't exist")?;
+                        let size = segment_size(segment_index)?;
+
+                        // Each pointer in memory contains the bind ordinal index and next pointer delta
+                        // bits 0..15   --> ordinal index
+                        // bits 16..31  --> next delta in 8 byte units
+                        // rest         --> flags
+                        loop {
+                            if segment_offset >= size {
+                                return Err(format!("threaded bind chain runs past the end of segment {}", segment_index).into());
+                            }
+                            let raw = memory.read_u64(seg_vmaddr + segment_offset)
+                                .ok_or("Invalid VM read during threaded bind")?;
 
-                    segment_offset += 8 + skip;
-                }
+                            // non arm64 layout, no ptr auth for right now
+                            let ordinal_index = (raw & 0xFFFF) as usize;
+                            let delta = (raw >> 16) & 0xFFFF;
 
-                BIND_OPCODE_DO_BIND_ULEB_TIMES_SKIPPING_ULEB => {
-                    
-                    let count = read_uleb(stream, &mut cursor)?;
-                    
-                    let skip = read_uleb(stream, &mut cursor)?;
-
-                    let seg = &segments[segment_index];
-                    let mut addr = seg.vmaddr + segment_offset;
+                            let entry = threaded_bind_table.get(ordinal_index).ok_or("Threaded ordinal out of bounds")?;
+                            on_bind(BoundSymbol { segment_index, segment_offset, ..entry.clone() })?;
 
-                    for _ in 0..count {
-                        if let Some(name) = &symbol_name {
-                            fixups.push(Fixup::Bind {
-                                addr: addr + slide,
-                                symbol: name.clone(),
-                                addend,
-                            });
+                            if delta == 0 {
+                                break;
+                            }
+                            segment_offset += delta * 8;
                         }
-                        addr += 8 + skip;
                     }
-
-                    segment_offset = addr - seg.vmaddr;
+                    _ => return Err("Invalid threaded subopcode".into()),
                 }
+            }
+            _ => return Err(format!("unknown bind opcode 0x{:02x} at bind_off+0x{:x}", opcode, cursor - 1).into()),
+        }
+    }
 
-                BIND_OPCODE_THREADED => {
-                    // what is threaded bind:
-                    //  instead of encoding every DO_BIND, it sets up a threaded chain of pointers that dyld can follow
-                    //  not to be confused with multithreading 
-
-                    // 0xD0 (BIND_OPCODE_THREADED) is a prefix opcode that switches dyld into "threaded bind mode"
-                    // sub opcodes:
-                    //      BIND_SUBOPCODE_THREADED_SET_BIND_ORDINAL_TABLE_SIZE_ULEB = 0x00
-                    //          sets the size of the bind ordinal table
-                    //      BIND_SUBOPCODE_THREADED_APPLY = 0x01
-                    //          applies the threaded binds using the table
-                    // lower nibble (retrieved via BIND_IMMEDIATE_MASK) indicates thread type
-                    //      1 --> threaded by seg/offset
-                    //      2 --> threaded by absolute pointer
-
-                    threaded = true;
-
-                    let subopcode = opcode & BIND_IMMEDIATE_MASK;
-
-                    match subopcode {
-
-                        BIND_SUBOPCODE_THREADED_SET_BIND_ORDINAL_TABLE_SIZE_ULEB => {
-                            
-                            let size = read_uleb(stream, &mut cursor)? as usize;
-                            threaded_table_size = size;
-                            threaded_bind_table = Vec::with_capacity(size);
-                        }
-
-                        BIND_SUBOPCODE_THREADED_APPLY => {
-                            let seg = &segments[segment_index];
-                            let mut addr = seg.vmaddr + segment_offset;
+    Ok(())
+}
 
-                            // Each pointer in memory contains the bind ordinal index and next pointer delta
-                            // bits 0..15   --> ordinal index
-                            // bits 16..31  --> next delta in 8 byte units
-                            // rest         --> flags
+// The import list: every non-lazy bind, no image needed (so no threaded binds)
+pub fn parse_bound_symbols(data: &[u8], slice_offset: u64, dyld_info: &DyldInfoCommand, segments: &[ParsedSegment], is_64: bool) -> Result<Vec<BoundSymbol>, Box<dyn Error>> {
+    let stream = opcode_stream(data, slice_offset, dyld_info.bind_off, dyld_info.bind_size, "bind")?;
+    let pointer_size: u64 = if is_64 { 8 } else { 4 };
 
-                            loop {
-                                let raw = memory.read_u64(addr)
-                                    .ok_or("Invalid VM read during threaded bind")?;
+    let mut binds = Vec::new();
+    walk_bind_opcodes(stream, segments, pointer_size, None, |bind| {
+        binds.push(bind);
+        Ok(())
+    })?;
+    Ok(binds)
+}
 
-                                // non arm64 layout, no ptr auth for right now
-                                let ordinal_index = (raw & 0xFFFF) as usize;
-                                let delta = ((raw >> 16) & 0xFFFF) as u64;
+pub fn write_bound_symbols(w: &mut dyn Write, binds: &[BoundSymbolReport]) -> io::Result<()> {
+    if binds.is_empty() {
+        return Ok(());
+    }
 
-                                if ordinal_index >= threaded_bind_table.len() {
-                                    return Err("Threaded ordinal out of bounds".into());
-                                }
+    writeln!(w)?;
+    writeln!(w, "{}", "Bound Symbols".green().bold())?;
+    writeln!(w, "----------------------------------------")?;
+    writeln!(w, "{:<20} {:<16} {:<40} Library", "Address", "Segment", "Symbol")?;
+    for bind in binds {
+        let symbol = match bind.addend {
+            0 => bind.symbol.clone(),
+            addend => format!("{}{:+}", bind.symbol, addend),
+        };
+        writeln!(
+            w,
+            "{:<20} {:<16} {:<40} {}",
+            bind.addr.map(|a| format!("0x{:016x}", a)).unwrap_or_else(|| "-".to_string()),
+            bind.segment.as_deref().unwrap_or("?"),
+            symbol,
+            bind.library.dimmed()
+        )?;
+    }
+    writeln!(w, "----------------------------------------")?;
+    Ok(())
+}
 
-                                let entry = &threaded_bind_table[ordinal_index];
+// The five tables LC_DYLD_INFO points at, an empty one (size 0) is normal, e.g. no weak binds
+pub fn write_dyld_info(w: &mut dyn Write, info: &DyldInfoReport) -> io::Result<()> {
+    let tables = [
+        ("rebase", info.rebase_off, info.rebase_size),
+        ("bind", info.bind_off, info.bind_size),
+        ("weak_bind", info.weak_bind_off, info.weak_bind_size),
+        ("lazy_bind", info.lazy_bind_off, info.lazy_bind_size),
+        ("export", info.export_off, info.export_size),
+    ];
+
+    writeln!(w, "{} ({})", "Dyld Info".green().bold(), info.cmd)?;
+    writeln!(w, "----------------------------------------")?;
+    for (name, off, size) in tables {
+        let size_str = match size {
+            0 => "empty".dimmed().to_string(),
+            size => format!("{:#x}", size),
+        };
+        writeln!(w, " - {:<12} off=0x{:08x} size={}", name, off, size_str)?;
+    }
+    writeln!(w, "----------------------------------------")?;
+    writeln!(w)?;
+    Ok(())
+}
 
-                                fixups.push(Fixup::Bind {
-                                    addr: addr + slide,
-                                    symbol: entry.symbol_name.clone(),
-                                    addend: entry.addend,
-                                });
+pub fn write_fixups_summary(w: &mut dyn Write, fixups: &[FixupReport]) -> io::Result<()> {
+    if fixups.is_empty() {
+        return Ok(());
+    }
 
-                                if delta == 0 {
-                                    break;
-                                }
+    writeln!(w)?;
+    writeln!(w, "{}", "Fixups".green().bold())?;
+    writeln!(w, "--------------------------------------------------------------------------------")?;
+    writeln!(
+        w,
+        "{:<18} {:<12} {:<30} {:<12}",
+        "Address", "Type", "Symbol", "Addend"
+    )?;
+    writeln!(w, "--------------------------------------------------------------------------------")?;
 
-                                addr += delta * 8;
-                            }
+    for f in fixups {
+        let addr_str = format!("0x{:016x}", f.addr);
+        let kind = f.kind.yellow();
+        match (&f.symbol, f.addend) {
+            (Some(symbol), Some(addend)) => {
+                let sym = symbol.magenta();
+                let add = format!("{}", addend).cyan();
+                writeln!(w, "{:<18} {:<12} {:<30} {:<12}", addr_str, kind, sym, add)?;
+            }
+            // Rebases have no symbol or addend
+            _ => writeln!(w, "{:<18} {:<12} {:<30} {:<12}", addr_str, kind, "", "")?,
+        }
+    }
 
-                            segment_offset = addr - seg.vmaddr;
-                        }
+    writeln!(w, "--------------------------------------------------------------------------------")?;
+    Ok(())
+}
 
-                        _ => return Err("Invalid threaded subopcode".into())
-                    }
-                }
 
-                BIND_OPCODE_DONE => break,
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
 
-                _ => {
-                    return Err(format!("Unknown bind opcode 0x:{:02x}", opcode).into());
-                }
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Only the bind table matters to parse_bound_symbols
+    fn bind_only(bind_size: usize) -> DyldInfoCommand {
+        DyldInfoCommand {
+            cmd: LC_DYLD_INFO_ONLY,
+            cmdsize: DYLD_INFO_COMMAND_SIZE,
+            rebase_off: 0,
+            rebase_size: 0,
+            bind_off: 0,
+            bind_size: bind_size as u32,
+            weak_bind_off: 0,
+            weak_bind_size: 0,
+            lazy_bind_off: 0,
+            lazy_bind_size: 0,
+            export_off: 0,
+            export_size: 0,
         }
+    }
 
-        Ok(())
+    // __TEXT, __DATA_CONST, __DATA, a page each
+    fn segments() -> Vec<ParsedSegment> {
+        (0..3u64)
+            .map(|i| ParsedSegment {
+                segname: [0; 16],
+                vmaddr: 0x1000 * (i + 1),
+                vmsize: 0x1000,
+                fileoff: 0,
+                filesize: 0,
+                maxprot: 3,
+                initprot: 3,
+                flags: 0,
+                sections: Vec::new(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn bind_state_machine_follows_each_do_bind_form() {
+        let mut stream = vec![
+            BIND_OPCODE_SET_DYLIB_ORDINAL_IMM | 1,
+            BIND_OPCODE_SET_SYMBOL_TRAILING_FLAGS_IMM,
+        ];
+        stream.extend_from_slice(b"_printf\0");
+        stream.extend_from_slice(&[
+            BIND_OPCODE_SET_TYPE_IMM | BIND_TYPE_POINTER,
+            BIND_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB | 2, 0x10,
+            BIND_OPCODE_DO_BIND,                                 // 0x10
+            BIND_OPCODE_DO_BIND_ADD_ADDR_ULEB, 0x08,             // 0x18, then skip one slot
+            BIND_OPCODE_DO_BIND_ADD_ADDR_IMM_SCALED | 1,         // 0x28, then skip one slot
+            BIND_OPCODE_DO_BIND_ULEB_TIMES_SKIPPING_ULEB, 2, 0,  // 0x38, 0x40
+            BIND_OPCODE_SET_DYLIB_SPECIAL_IMM | 0x0f,            // main executable
+            BIND_OPCODE_SET_ADDEND_SLEB, 0x7c,                   // -4
+            BIND_OPCODE_ADD_ADDR_ULEB, 0x08,
+            BIND_OPCODE_SET_SYMBOL_TRAILING_FLAGS_IMM,
+        ]);
+        stream.extend_from_slice(b"_environ\0");
+        stream.extend_from_slice(&[BIND_OPCODE_DO_BIND, BIND_OPCODE_DONE]);
+
+        let binds = parse_bound_symbols(&stream, 0, &bind_only(stream.len()), &segments(), true).unwrap();
+        let offsets: Vec<u64> = binds.iter().map(|b| b.segment_offset).collect();
+        assert_eq!(offsets, [0x10, 0x18, 0x28, 0x38, 0x40, 0x50]);
+        assert!(binds[..5].iter().all(|b| b.symbol_name == "_printf" && b.dylib_ordinal == 1 && b.segment_index == 2 && b.addend == 0));
+        assert_eq!(binds[5].symbol_name, "_environ");
+        assert_eq!(binds[5].dylib_ordinal, BIND_SPECIAL_DYLIB_MAIN_EXECUTABLE);
+        assert_eq!(binds[5].addend, -4);
     }
-}
 
+    #[test]
+    fn do_bind_without_a_symbol_is_an_error() {
+        let stream = [BIND_OPCODE_SET_DYLIB_ORDINAL_IMM | 1, BIND_OPCODE_DO_BIND];
+        assert!(parse_bound_symbols(&stream, 0, &bind_only(stream.len()), &segments(), true).is_err());
+        assert!(parse_bound_symbols(&stream, 0, &bind_only(stream.len() + 1), &segments(), true).is_err());
+    }
 
-pub fn print_fixups_summary(fixups: &[Fixup]) {
-    if fixups.is_empty() {
-        return;
+    #[test]
+    fn huge_bind_counts_stop_at_the_segment_end() {
+        let mut stream = vec![BIND_OPCODE_SET_SYMBOL_TRAILING_FLAGS_IMM, b'a', 0, BIND_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB | 2, 0];
+        // count ~2^64, skip 0
+        stream.extend_from_slice(&[BIND_OPCODE_DO_BIND_ULEB_TIMES_SKIPPING_ULEB, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01, 0x00]);
+        let err = parse_bound_symbols(&stream, 0, &bind_only(stream.len()), &segments(), true).unwrap_err();
+        assert!(err.to_string().contains("doesn't fit"), "{}", err);
+
+        // a count that fits the check but a skip that walks off the end
+        let stream = [BIND_OPCODE_SET_SYMBOL_TRAILING_FLAGS_IMM, b'a', 0, BIND_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB | 2, 0, BIND_OPCODE_DO_BIND_ULEB_TIMES_SKIPPING_ULEB, 0x20, 0x80, 0x02];
+        let err = parse_bound_symbols(&stream, 0, &bind_only(stream.len()), &segments(), true).unwrap_err();
+        assert!(err.to_string().contains("past the segment's end"), "{}", err);
+
+        // and a segment the file doesn't have
+        let stream = [BIND_OPCODE_SET_SYMBOL_TRAILING_FLAGS_IMM, b'a', 0, BIND_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB | 9, 0, BIND_OPCODE_DO_BIND];
+        assert!(parse_bound_symbols(&stream, 0, &bind_only(stream.len()), &segments(), true).is_err());
     }
 
-    println!();
-    println!("{}", "Fixups".green().bold());
-    println!("--------------------------------------------------------------------------------");
-    println!(
-        "{:<18} {:<12} {:<30} {:<12}",
-        "Address", "Type", "Symbol", "Addend"
-    );
-    println!("--------------------------------------------------------------------------------");
+    #[test]
+    fn fixups_read_their_opcodes_relative_to_the_slice() {
+        // rebase: __DATA+0x10, twice. bind: _printf at __DATA_CONST+0x8
+        let rebase = [REBASE_OPCODE_SET_TYPE_IMM | 1, REBASE_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB | 2, 0x10, REBASE_OPCODE_DO_REBASE_IMM_TIMES | 2, REBASE_OPCODE_DONE];
+        let mut bind = vec![BIND_OPCODE_SET_DYLIB_ORDINAL_IMM | 1, BIND_OPCODE_SET_SYMBOL_TRAILING_FLAGS_IMM];
+        bind.extend_from_slice(b"_printf\0");
+        bind.extend_from_slice(&[BIND_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB | 1, 0x08, BIND_OPCODE_DO_BIND, BIND_OPCODE_DONE]);
+
+        // the slice starts 0x4000 into the file, whatever sits before it must not be read
+        let slice_offset = 0x4000u64;
+        let mut data = vec![0xffu8; slice_offset as usize];
+        data.extend_from_slice(&rebase);
+        data.extend_from_slice(&bind);
+        let info = DyldInfoCommand {
+            rebase_off: 0,
+            rebase_size: rebase.len() as u32,
+            bind_off: rebase.len() as u32,
+            ..bind_only(bind.len())
+        };
+
+        let segs = segments();
+        let image = MachOMemoryImage::new(&segs, &data, slice_offset);
+        let fixups = Fixup::parse(&info, &segs, 0, &image, &data, slice_offset, true).unwrap();
+        let reports: Vec<(String, u64)> = fixups.iter().map(|f| f.build_report()).map(|r| (r.kind, r.addr)).collect();
+        assert_eq!(reports, [("rebase".into(), 0x3010), ("rebase".into(), 0x3018), ("bind".into(), 0x2008)]);
+
+        // the binds are the import list's, same walk
+        let binds = parse_bound_symbols(&data, slice_offset, &info, &segs, true).unwrap();
+        assert_eq!((binds[0].segment_index, binds[0].segment_offset, binds[0].symbol_name.as_str()), (1, 0x08, "_printf"));
+
+        // and a rebase count that runs past __DATA is an error, not a few billion fixups
+        let runaway = [REBASE_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB | 2, 0, REBASE_OPCODE_DO_REBASE_ULEB_TIMES, 0xff, 0xff, 0xff, 0xff, 0x0f];
+        let info = DyldInfoCommand { rebase_off: 0, rebase_size: runaway.len() as u32, ..bind_only(0) };
+        assert!(Fixup::parse(&info, &segs, 0, &image, &runaway, 0, true).is_err());
+    }
 
-    for f in fixups {
-        match f {
-            Fixup::Rebase { addr } => {
-                let addr_str = format!("0x{:016x}", addr);
-                let kind = "rebase".yellow();
-                println!("{:<18} {:<12} {:<30} {:<12}", addr_str, kind, "", "");
-            }
-            Fixup::Bind { addr, symbol, addend } => {
-                let addr_str = format!("0x{:016x}", addr);
-                let kind = "bind".yellow();
-                let sym = symbol.magenta();
-                let add = format!("{}", addend).cyan();
-                println!("{:<18} {:<12} {:<30} {:<12}", addr_str, kind, sym, add);
-            }
-            Fixup::WeakBind { addr, symbol, addend } => {
-                let addr_str = format!("0x{:016x}", addr);
-                let kind = "weak_bind".yellow();
-                let sym = symbol.magenta();
-                let add = format!("{}", addend).cyan();
-                println!("{:<18} {:<12} {:<30} {:<12}", addr_str, kind, sym, add);
-            }
-            Fixup::LazyBind { addr, symbol, addend } => {
-                let addr_str = format!("0x{:016x}", addr);
-                let kind = "lazy_bind".yellow();
-                let sym = symbol.magenta();
-                let add = format!("{}", addend).cyan();
-                println!("{:<18} {:<12} {:<30} {:<12}", addr_str, kind, sym, add);
-            }
+    #[test]
+    fn dyld_info_command_size_is_checked() {
+        let mut data = Vec::new();
+        for field in [LC_DYLD_INFO_ONLY, DYLD_INFO_COMMAND_SIZE, 0x3000, 0x8, 0x3008, 0xd8, 0, 0, 0x3110, 0x2b0, 0x33c0, 0x40] {
+            data.extend_from_slice(&field.to_le_bytes());
         }
+        let lc = LoadCommand { cmd: LC_DYLD_INFO_ONLY, cmdsize: DYLD_INFO_COMMAND_SIZE, offset: 0 };
+        let info = read_dyld_info_command(&data, &lc, false).unwrap();
+        assert_eq!((info.bind_off, info.bind_size, info.export_size), (0x3008, 0xd8, 0x40));
+
+        // a short command would read the next one's bytes, one at the end of the buffer used to panic
+        assert!(read_dyld_info_command(&data, &LoadCommand { cmdsize: 24, ..lc }, false).is_err());
+        assert!(read_dyld_info_command(&data[..40], &lc, false).is_err());
+        assert!(read_dyld_info_command(&data, &LoadCommand { offset: 16, ..lc }, false).is_err());
     }
-
-    println!("--------------------------------------------------------------------------------");
-}
\ No newline at end of file
+}
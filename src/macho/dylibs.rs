@@ -2,12 +2,13 @@
 
 // From mach-o's loader.h 
 
-use std::error::Error;
-use crate::macho::constants::{LC_ID_DYLIB, LC_LAZY_LOAD_DYLIB, LC_LOAD_DYLIB, LC_LOAD_UPWARD_DYLIB, LC_LOAD_WEAK_DYLIB, LC_REEXPORT_DYLIB};
-use crate::macho::load_commands::LoadCommand;
-use crate::reporting::dylibs::DylibReport;
+use std::io::{self, Write};
+use crate::macho::constants::{DYNAMIC_LOOKUP_ORDINAL, EXECUTABLE_ORDINAL, SELF_LIBRARY_ORDINAL, LC_ID_DYLIB, LC_LAZY_LOAD_DYLIB, LC_LOAD_DYLIB, LC_LOAD_UPWARD_DYLIB, LC_LOAD_WEAK_DYLIB, LC_REEXPORT_DYLIB};
+use crate::macho::error::MoscopeError;
+use crate::macho::load_commands::{self, LoadCommand};
+use crate::reporting::dylibs::{DylibOrdinalReport, DylibReport};
 use crate::macho::utils;
-use colored::Colorize;
+use colored::{ColoredString, Colorize};
 
 /*
 
@@ -33,6 +34,29 @@ pub enum DylibKind {
     Upward,
     Unknown,
 }
+
+impl DylibKind {
+    // Shared by the text summary and the JSON "kind" field, part of the report contract
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DylibKind::Id => "ID",
+            DylibKind::Load => "LOAD",
+            DylibKind::Weak => "WEAK",
+            DylibKind::Reexport => "REEXPORT",
+            DylibKind::Lazy => "LAZY",
+            DylibKind::Upward => "UPWARD",
+            DylibKind::Unknown => "UNKNOWN",
+        }
+    }
+}
+
+// Text summary color for the report's "kind" string
+fn colored_kind(kind: &str) -> ColoredString {
+    if kind == DylibKind::Unknown.as_str() { kind.red().bold() } else { kind.yellow().bold() }
+}
+// cmd, cmdsize and the four dylib fields, the name follows
+pub const DYLIB_COMMAND_SIZE: u32 = 24;
+
 // dylib fields:
     /* 
         The `name`` is an lc_str in loader.h so we gotta look at `lc_str`` in loader.h
@@ -62,73 +86,38 @@ pub struct ParsedDylib {
 }
 
 impl ParsedDylib {
-    pub fn build_report(&self, json: bool) -> DylibReport {
+    pub fn build_report(&self) -> DylibReport {
         DylibReport { 
             path: self.path.clone(), 
+            normalized_path: normalize_install_name(&self.path),
             timestamp: self.timestamp, 
             current_version: self.current_version, 
             compatibility_version: self.compatibility_version, 
-            kind: if json { self.kind_plain() } else { self.kind_colored() },
-            load_command: self.source_lc.build_report(json), 
+            kind: self.kind.as_str().to_string(),
+            load_command: self.source_lc.build_report(), 
         }
     }
-
-    fn kind_plain(&self) -> String {
-        match self.kind {
-            DylibKind::Id => "ID",
-            DylibKind::Load => "LOAD",
-            DylibKind::Weak => "WEAK",
-            DylibKind::Reexport => "REEXPORT",
-            DylibKind::Lazy => "LAZY",
-            DylibKind::Upward => "UPWARD",
-            DylibKind::Unknown => "UNKNOWN",
-        }.to_string()
-    }
-
-    fn kind_colored(&self) -> String {
-       match self.kind {
-            DylibKind::Id => "ID".yellow().bold(),
-            DylibKind::Load => "LOAD".yellow().bold(),
-            DylibKind::Weak => "WEAK".yellow().bold(),
-            DylibKind::Reexport => "REEXPORT".yellow().bold(),
-            DylibKind::Lazy => "LAZY".yellow().bold(),
-            DylibKind::Upward => "UPWARD".yellow().bold(),
-            DylibKind::Unknown => "UNKNOWN".red().bold(),
-        }.to_string()
-    }
 }
 
 
-pub fn parse_dylib(data: &[u8], lc: &LoadCommand, is_be: bool) -> Result<ParsedDylib, Box<dyn Error>> {
+pub fn parse_dylib(data: &[u8], lc: &LoadCommand, is_be: bool) -> Result<ParsedDylib, MoscopeError> {
     // Good ol' bounds checking 
     let base = lc.offset as usize;
     let end = base + lc.cmdsize as usize;
 
+    if lc.cmdsize < DYLIB_COMMAND_SIZE {
+        return Err(MoscopeError::InvalidCmdSize { cmd: lc.cmd, cmdsize: lc.cmdsize });
+    }
     if end > data.len() {
-        return Err("dylib load command exceeds file bounds".into());
+        return Err(MoscopeError::OutOfBounds { offset: lc.offset, len: lc.cmdsize as u64 });
     }
 
-    let name_offset: u32 = utils::bytes_to(is_be, &data[base + 8..])?; // start at plus 8 to skip cmd & cmdsize
     let timestamp: u32 = utils::bytes_to(is_be, &data[base + 12..])?;
     let current_version: u32 = utils::bytes_to(is_be, &data[base + 16..])?;
     let compat_version: u32 = utils::bytes_to(is_be, &data[base + 20..])?;
 
-    let string_start = base + name_offset as usize;
-    let string_end = base + lc.cmdsize as usize; // Thankfully the cmdsize is given so we know the max size of the string
-
-    
-    if string_start >= string_end || string_end > data.len() {
-        return Err("Invalid dylib name offset".into());
-    }
-
-    let string_bytes = &data[string_start..string_end];
-
-    let first_null_byte = match string_bytes.iter().position(|&byte| byte == 0) {
-        Some(pos) => pos,
-        None => return Err("Unterminated dylib name string".into()),
-    };
-
-    let path = String::from_utf8_lossy(&string_bytes[..first_null_byte]).to_string();
+    // name is an lc_str right after cmd & cmdsize, cmdsize bounds the string
+    let path = load_commands::read_lc_str(data, lc, 8, is_be)?;
 
     let kind = match lc.cmd {
         LC_ID_DYLIB => DylibKind::Id,
@@ -141,31 +130,213 @@ pub fn parse_dylib(data: &[u8], lc: &LoadCommand, is_be: bool) -> Result<ParsedD
     };
 
     Ok(ParsedDylib {
-        path: path,
-        timestamp: timestamp,
-        current_version: current_version,
+        path,
+        timestamp,
+        current_version,
         compatibility_version: compat_version,
-        kind: kind,
+        kind,
         source_lc: *lc,
     })
 }
 
-pub fn print_dylibs_summary(dylibs: &Vec<ParsedDylib>) {
-    println!("{}", "\nDynamic Libraries".green().bold());
-    println!("----------------------------------------");
+// current_version / compatibility_version are packed xxxx.yy.zz
+pub fn format_dylib_version(version: u32) -> String {
+    format!("{}.{}.{}", version >> 16, (version >> 8) & 0xff, version & 0xff)
+}
+
+// Two-level namespace ordinals count every dependency in load command order, starting at 1.
+// LC_ID_DYLIB is the image itself and doesn't get one.
+pub fn dylib_for_ordinal(dylibs: &[ParsedDylib], ordinal: u64) -> Option<&ParsedDylib> {
+    let index = usize::try_from(ordinal).ok()?.checked_sub(1)?;
+    dylibs.iter().filter(|d| !matches!(d.kind, DylibKind::Id)).nth(index)
+}
+
+// The library ordinals that don't index a dylib command, as the report and symbol list spell them
+pub fn special_ordinal_name(ordinal: u8) -> Option<&'static str> {
+    match ordinal {
+        SELF_LIBRARY_ORDINAL => Some("<self>"),
+        DYNAMIC_LOOKUP_ORDINAL => Some("<dynamic lookup>"),
+        EXECUTABLE_ORDINAL => Some("<main executable>"),
+        _ => None,
+    }
+}
+
+// Every ordinal an import can name, in ordinal order: <self>, the dependencies, then the two lookups
+pub fn build_ordinal_map(dylibs: &[ParsedDylib]) -> Vec<DylibOrdinalReport> {
+    let dependencies = dylibs
+        .iter()
+        .filter(|d| !matches!(d.kind, DylibKind::Id))
+        .enumerate()
+        .map(|(i, d)| DylibOrdinalReport { ordinal: i as u32 + 1, path: d.path.clone() });
+
+    let special = |ordinal: u8| DylibOrdinalReport {
+        ordinal: ordinal as u32,
+        path: special_ordinal_name(ordinal).unwrap_or_default().to_string(),
+    };
+
+    std::iter::once(special(SELF_LIBRARY_ORDINAL))
+        .chain(dependencies)
+        .chain([special(DYNAMIC_LOOKUP_ORDINAL), special(EXECUTABLE_ORDINAL)])
+        .collect()
+}
+
+
+/*
+Lexical cleanup of an install name, nothing touches the filesystem:
+    /usr/lib//libz.dylib                    --> /usr/lib/libz.dylib
+    /usr/lib/./../lib/libX.dylib            --> /usr/lib/libX.dylib
+    @loader_path/../Frameworks/A.framework  --> unchanged, @loader_path is a directory we can't see into
+    /../../usr/lib/libY.dylib               --> /usr/lib/libY.dylib (can't climb above /)
+
+dyld resolves these the same way, so two install names that normalize to the same string load the
+same file. A raw path that differs from its normalized form is worth a second look, the raw path
+stays authoritative in the report.
+*/
+pub fn normalize_install_name(path: &str) -> String {
+    let is_absolute = path.starts_with('/');
+    let mut parts: Vec<&str> = Vec::new();
+
+    for component in path.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => match parts.last() {
+                // @rpath / @loader_path / @executable_path are anchors, never popped
+                Some(last) if *last != ".." && !last.starts_with('@') => {
+                    parts.pop();
+                }
+                // climbing above / stays at /
+                _ if is_absolute => {}
+                _ => parts.push(".."),
+            },
+            other => parts.push(other),
+        }
+    }
+
+    let joined = parts.join("/");
+    if is_absolute {
+        format!("/{}", joined)
+    } else if joined.is_empty() {
+        ".".to_string()
+    } else {
+        joined
+    }
+}
+
+pub fn write_dylibs_summary(w: &mut dyn Write, dylibs: &[DylibReport], canonical_names: bool) -> io::Result<()> {
+    writeln!(w, "{}", "\nDynamic Libraries".green().bold())?;
+    writeln!(w, "----------------------------------------")?;
 
     for dylib in dylibs {
-        let kind = match dylib.kind {
-            DylibKind::Id => "ID".yellow().bold(),
-            DylibKind::Load => "LOAD".yellow().bold(),
-            DylibKind::Weak => "WEAK".yellow().bold(),
-            DylibKind::Reexport => "REEXPORT".yellow().bold(),
-            DylibKind::Lazy => "LAZY".yellow().bold(),
-            DylibKind::Upward => "UPWARD".yellow().bold(),
-            DylibKind::Unknown => "UNKNOWN".red().bold(),
+        let kind = colored_kind(&dylib.kind);
+
+        if canonical_names && dylib.normalized_path != dylib.path {
+            writeln!(w, "[{:<8}] {} {}", kind, dylib.normalized_path, format!("(raw: {})", dylib.path).dimmed())?;
+        } else {
+            writeln!(w, "[{:<8}] {}", kind, dylib.path)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn write_ordinal_map(w: &mut dyn Write, ordinals: &[DylibOrdinalReport]) -> io::Result<()> {
+    writeln!(w, "{}", "\nLibrary Ordinals".green().bold())?;
+    writeln!(w, "----------------------------------------")?;
+
+    for entry in ordinals {
+        let path = if entry.path.starts_with('<') { entry.path.dimmed() } else { entry.path.normal() };
+        writeln!(w, "{:>4}  {}", entry.ordinal, path)?;
+    }
+    Ok(())
+}
+
+
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dylib_kind_strings_are_stable() {
+        let expected = [
+            (DylibKind::Id, "ID"),
+            (DylibKind::Load, "LOAD"),
+            (DylibKind::Weak, "WEAK"),
+            (DylibKind::Reexport, "REEXPORT"),
+            (DylibKind::Lazy, "LAZY"),
+            (DylibKind::Upward, "UPWARD"),
+            (DylibKind::Unknown, "UNKNOWN"),
+        ];
+
+        for (kind, name) in expected {
+            assert_eq!(kind.as_str(), name);
+        }
+    }
+
+    #[test]
+    fn ordinal_map_skips_the_id_dylib_and_brackets_the_specials() {
+        let dylib = |path: &str, kind| ParsedDylib {
+            path: path.to_string(),
+            timestamp: 0,
+            current_version: 0,
+            compatibility_version: 0,
+            kind,
+            source_lc: LoadCommand { cmd: LC_LOAD_DYLIB, cmdsize: 0, offset: 0 },
         };
+        let dylibs = [dylib("/usr/lib/libfoo.dylib", DylibKind::Id), dylib("/usr/lib/libSystem.B.dylib", DylibKind::Load), dylib("/usr/lib/libz.1.dylib", DylibKind::Weak)];
+
+        let ordinals = build_ordinal_map(&dylibs);
+        let map: Vec<(u32, &str)> = ordinals.iter().map(|e| (e.ordinal, e.path.as_str())).collect();
+        assert_eq!(map, [
+            (0, "<self>"),
+            (1, "/usr/lib/libSystem.B.dylib"),
+            (2, "/usr/lib/libz.1.dylib"),
+            (0xfe, "<dynamic lookup>"),
+            (0xff, "<main executable>"),
+        ]);
+    }
+
+    #[test]
+    fn clean_paths_are_unchanged() {
+        for path in ["/usr/lib/libSystem.B.dylib", "@rpath/Foo.framework/Foo", "libfoo.dylib"] {
+            assert_eq!(normalize_install_name(path), path);
+        }
+    }
+
+    #[test]
+    fn dot_components_and_duplicate_slashes_collapse() {
+        assert_eq!(normalize_install_name("/usr/lib/../lib/libX.dylib"), "/usr/lib/libX.dylib");
+        assert_eq!(normalize_install_name("/usr//lib/./libz.dylib"), "/usr/lib/libz.dylib");
+        assert_eq!(normalize_install_name("/System/Library/Frameworks/../../../usr/lib/libc.dylib"), "/usr/lib/libc.dylib");
+    }
+
+    #[test]
+    fn cannot_climb_above_root() {
+        assert_eq!(normalize_install_name("/../../usr/lib/libY.dylib"), "/usr/lib/libY.dylib");
+        assert_eq!(normalize_install_name("/.."), "/");
+    }
 
-        //println!("[{:<8}] {} DEBUG:{:?}", kind, dylib.path, dylib.source_lc.cmd);
-        println!("[{:<8}] {}", kind, dylib.path);
+    #[test]
+    fn at_anchors_are_never_popped() {
+        assert_eq!(normalize_install_name("@loader_path/../Frameworks/A.framework/A"), "@loader_path/../Frameworks/A.framework/A");
+        assert_eq!(normalize_install_name("@rpath/x/../../B.dylib"), "@rpath/../B.dylib");
+        assert_eq!(normalize_install_name("@executable_path//./lib/libC.dylib"), "@executable_path/lib/libC.dylib");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn relative_paths_keep_leading_parent_components() {
+        assert_eq!(normalize_install_name("../../lib/libD.dylib"), "../../lib/libD.dylib");
+        assert_eq!(normalize_install_name("a/../../libE.dylib"), "../libE.dylib");
+        assert_eq!(normalize_install_name("./"), ".");
+    }
+
+    #[test]
+    fn dylib_versions_unpack() {
+        assert_eq!(format_dylib_version(0x0516_0100), "1302.1.0");
+        assert_eq!(format_dylib_version(0x0001_0000), "1.0.0");
+    }
+}
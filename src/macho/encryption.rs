@@ -0,0 +1,138 @@
+// File Purpose: Is part of this binary encrypted? (LC_ENCRYPTION_INFO / LC_ENCRYPTION_INFO_64)
+
+use std::error::Error;
+use std::io::{self, Write};
+use colored::Colorize;
+use crate::macho::constants::*;
+use crate::macho::load_commands::{self, LoadCommand};
+use crate::macho::utils;
+use crate::reporting::encryption::EncryptionInfoReport;
+
+/*
+encryption_info_command memory layout (from loader.h)
++-----------------------------+
+| cmd (u32)                   |  LC_ENCRYPTION_INFO or LC_ENCRYPTION_INFO_64
+| cmdsize (u32)               |  20, or 24 for the _64 flavor
+| cryptoff (u32)              |  file offset of the encrypted range (slice relative)
+| cryptsize (u32)             |  size of the encrypted range
+| cryptid (u32)               |  which encryption system, 0 = not encrypted yet
+| pad (u32)                   |  _64 only, keeps the command a multiple of 8
++-----------------------------+
+
+App Store binaries ship with FairPlay encryption over most of __TEXT (cryptid 1). A binary
+decrypted on a device (or one that never was encrypted) keeps the command with cryptid 0,
+so the command being there doesn't mean anything on its own.
+*/
+
+pub const ENCRYPTION_INFO_COMMAND_SIZE: u32 = 20;
+pub const ENCRYPTION_INFO_64_COMMAND_SIZE: u32 = 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncryptionInfo {
+    pub cmd: u32,
+    pub crypt_offset: u32,
+    pub crypt_size: u32,
+    pub crypt_id: u32,
+}
+
+impl EncryptionInfo {
+    pub fn is_encrypted(&self) -> bool {
+        self.crypt_id != 0
+    }
+
+    // Whether any byte of [offset, offset + size) is in the encrypted range, offsets are slice relative
+    pub fn covers(&self, offset: u64, size: u64) -> bool {
+        let start = self.crypt_offset as u64;
+        let end = start + self.crypt_size as u64;
+        self.is_encrypted() && size > 0 && offset < end && offset + size > start
+    }
+
+    pub fn build_report(&self) -> EncryptionInfoReport {
+        EncryptionInfoReport {
+            cmd: load_commands::load_command_name(self.cmd).to_string(),
+            crypt_offset: self.crypt_offset,
+            crypt_size: self.crypt_size,
+            crypt_id: self.crypt_id,
+            is_encrypted: self.is_encrypted(),
+        }
+    }
+}
+
+pub fn parse_encryption_info(data: &[u8], lc: &LoadCommand, is_be: bool) -> Result<EncryptionInfo, Box<dyn Error>> {
+    let expected = if lc.cmd == LC_ENCRYPTION_INFO_64 { ENCRYPTION_INFO_64_COMMAND_SIZE } else { ENCRYPTION_INFO_COMMAND_SIZE };
+    if lc.cmdsize != expected {
+        return Err(format!("{} has cmdsize {} (expected {})", load_commands::load_command_name(lc.cmd), lc.cmdsize, expected).into());
+    }
+
+    let base = lc.offset as usize;
+    if base + expected as usize > data.len() {
+        return Err(format!("{} exceeds file bounds", load_commands::load_command_name(lc.cmd)).into());
+    }
+
+    Ok(EncryptionInfo {
+        cmd: lc.cmd,
+        crypt_offset: utils::bytes_to(is_be, &data[base + 8..])?,
+        crypt_size: utils::bytes_to(is_be, &data[base + 12..])?,
+        crypt_id: utils::bytes_to(is_be, &data[base + 16..])?,
+    })
+}
+
+pub fn write_encryption_info(w: &mut dyn Write, info: &EncryptionInfoReport) -> io::Result<()> {
+    let end = info.crypt_offset as u64 + info.crypt_size as u64;
+    if info.is_encrypted {
+        writeln!(w, "{} {}", "ENCRYPTED".red().bold(), format!("cryptid {}, strings in this range were not extracted", info.crypt_id).red())?;
+    }
+    writeln!(w, "{} 0x{:x}..0x{:x} ({} bytes, cryptid {})", "Encryption   :".yellow().bold(), info.crypt_offset, end, info.crypt_size, info.crypt_id)?;
+    Ok(())
+}
+
+
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(cmd: u32, cryptoff: u32, cryptsize: u32, cryptid: u32) -> (Vec<u8>, LoadCommand) {
+        let cmdsize = if cmd == LC_ENCRYPTION_INFO_64 { ENCRYPTION_INFO_64_COMMAND_SIZE } else { ENCRYPTION_INFO_COMMAND_SIZE };
+        let mut data = Vec::new();
+        for field in [cmd, cmdsize, cryptoff, cryptsize, cryptid] {
+            data.extend_from_slice(&field.to_le_bytes());
+        }
+        if cmd == LC_ENCRYPTION_INFO_64 {
+            data.extend_from_slice(&0u32.to_le_bytes());
+        }
+        (data, LoadCommand { cmd, cmdsize, offset: 0 })
+    }
+
+    #[test]
+    fn both_flavors_are_read() {
+        let (data, lc) = command(LC_ENCRYPTION_INFO_64, 0x4000, 0x8000, 1);
+        let info = parse_encryption_info(&data, &lc, false).unwrap();
+        assert_eq!((info.crypt_offset, info.crypt_size, info.crypt_id), (0x4000, 0x8000, 1));
+        assert!(info.is_encrypted());
+
+        let (data, lc) = command(LC_ENCRYPTION_INFO, 0x1000, 0x1000, 0);
+        assert!(!parse_encryption_info(&data, &lc, false).unwrap().is_encrypted());
+
+        assert!(parse_encryption_info(&data, &LoadCommand { cmdsize: 24, ..lc }, false).is_err());
+        assert!(parse_encryption_info(&data[..16], &lc, false).is_err());
+    }
+
+    #[test]
+    fn covers_only_overlapping_ranges_while_encrypted() {
+        let info = EncryptionInfo { cmd: LC_ENCRYPTION_INFO_64, crypt_offset: 0x4000, crypt_size: 0x1000, crypt_id: 1 };
+        assert!(info.covers(0x4000, 0x10));
+        assert!(info.covers(0x3ff0, 0x20)); // straddles the start
+        assert!(info.covers(0x4ff0, 0x20)); // straddles the end
+        assert!(!info.covers(0x5000, 0x10));
+        assert!(!info.covers(0x3000, 0x1000));
+        assert!(!info.covers(0x4800, 0));
+
+        assert!(!EncryptionInfo { crypt_id: 0, ..info }.covers(0x4000, 0x10));
+    }
+}
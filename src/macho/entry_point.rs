@@ -0,0 +1,278 @@
+// File Purpose: Where does execution begin? (LC_MAIN, LC_UNIXTHREAD for older binaries)
+
+use std::error::Error;
+use std::io::{self, Write};
+use colored::Colorize;
+use crate::macho::constants::*;
+use crate::macho::diagnostics::Diagnostic;
+use crate::macho::load_commands::LoadCommand;
+use crate::macho::segments::ParsedSegment;
+use crate::macho::utils;
+use crate::reporting::entry_point::EntryPointReport;
+
+/*
+entry_point_command memory layout (from loader.h)
++-----------------------------+
+| cmd (u32)                   |  LC_MAIN
+| cmdsize (u32)               |  always 24
+| entryoff (u64)              |  file offset of main() (slice relative)
+| stacksize (u64)             |  initial stack size, 0 = default
++-----------------------------+
+
+thread_command memory layout (from loader.h)
++-----------------------------+
+| cmd (u32)                   |  LC_UNIXTHREAD
+| cmdsize (u32)               |
++-----------------------------+
+| flavor (u32)                |  *_THREAD_STATE*   \
+| count (u32)                 |  in u32 units       | repeated until cmdsize
+| state[count] (u32)          |  register file     /
++-----------------------------+
+
+LC_MAIN only gives a file offset, dyld calls it after it's done with the image. Before that
+(and still for dyld itself and static binaries) LC_UNIXTHREAD hands the kernel a whole register
+file and the entry point is whatever the pc register holds, so the layout depends on the cputype.
+Either way the other half (vm address <-> file offset) comes from the segment that holds it.
+*/
+
+pub const ENTRY_POINT_COMMAND_SIZE: u32 = 24;
+
+// macOS' default RLIMIT_STACK hard limit, ld's -stack_size won't go past it on most targets either
+pub const LARGE_STACK_SIZE: u64 = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntrySource {
+    Main,
+    UnixThread,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntryPoint {
+    pub source: EntrySource,
+    pub file_offset: Option<u64>, // slice relative
+    pub vm_addr: Option<u64>,
+    pub stack_size: Option<u64>,  // LC_MAIN only
+}
+
+impl EntryPoint {
+    pub fn build_report(&self) -> EntryPointReport {
+        EntryPointReport {
+            source: match self.source {
+                EntrySource::Main => "LC_MAIN",
+                EntrySource::UnixThread => "LC_UNIXTHREAD",
+            }.to_string(),
+            file_offset: self.file_offset,
+            vm_addr: self.vm_addr,
+            stack_size: self.stack_size,
+        }
+    }
+}
+
+// Which register holds the pc in a flavor's state: (flavor, byte offset into the state, width)
+fn pc_location(cputype: i32) -> Option<(u32, usize, usize)> {
+    match cputype {
+        CPU_TYPE_X86_64 => Some((X86_THREAD_STATE64, 16 * 8, 8)), // rax ... r15, then rip
+        CPU_TYPE_X86    => Some((X86_THREAD_STATE32, 10 * 4, 4)), // eax ... esp, ss, eflags, then eip
+        CPU_TYPE_ARM64  => Some((ARM_THREAD_STATE64, 32 * 8, 8)), // x0-x28, fp, lr, sp, then pc
+        CPU_TYPE_ARM    => Some((ARM_THREAD_STATE, 15 * 4, 4)),   // r0-r12, sp, lr, then pc
+        _ => None,
+    }
+}
+
+fn segment_for_vmaddr(segments: &[ParsedSegment], vm_addr: u64) -> Option<&ParsedSegment> {
+    segments.iter().find(|seg| seg.filesize > 0 && vm_addr >= seg.vmaddr && vm_addr - seg.vmaddr < seg.filesize)
+}
+
+fn segment_for_fileoff(segments: &[ParsedSegment], file_offset: u64) -> Option<&ParsedSegment> {
+    segments.iter().find(|seg| seg.filesize > 0 && file_offset >= seg.fileoff && file_offset - seg.fileoff < seg.filesize)
+}
+
+pub fn parse_main(data: &[u8], lc: &LoadCommand, is_be: bool, segments: &[ParsedSegment]) -> Result<EntryPoint, Box<dyn Error>> {
+    if lc.cmdsize != ENTRY_POINT_COMMAND_SIZE {
+        return Err(format!("LC_MAIN has cmdsize {} (expected {})", lc.cmdsize, ENTRY_POINT_COMMAND_SIZE).into());
+    }
+
+    let base = lc.offset as usize;
+    if base + ENTRY_POINT_COMMAND_SIZE as usize > data.len() {
+        return Err("LC_MAIN exceeds file bounds".into());
+    }
+
+    let entryoff: u64 = utils::bytes_to(is_be, &data[base + 8..])?;
+    let stacksize: u64 = utils::bytes_to(is_be, &data[base + 16..])?;
+
+    // entryoff is a file offset, __TEXT (or whichever segment maps it) gives the address
+    let vm_addr = segment_for_fileoff(segments, entryoff).map(|seg| seg.vmaddr + (entryoff - seg.fileoff));
+
+    Ok(EntryPoint {
+        source: EntrySource::Main,
+        file_offset: Some(entryoff),
+        vm_addr,
+        stack_size: Some(stacksize),
+    })
+}
+
+/*
+Almost every LC_MAIN leaves stacksize at 0 and the main thread gets the default 8MB. A value means
+someone linked with -stack_size on purpose (deep recursion, big stack buffers, or something that
+wants to look unusual), past LARGE_STACK_SIZE it's bigger than the kernel will normally hand out.
+*/
+pub fn stack_size_diagnostic(entry: &EntryPoint) -> Option<Diagnostic> {
+    match entry.stack_size? {
+        0 => None,
+        size if size > LARGE_STACK_SIZE => Some(Diagnostic::warning(
+            "stack-size-oversized",
+            format!("LC_MAIN asks for a 0x{:x} byte main thread stack, over the 0x{:x} RLIMIT_STACK hard limit", size, LARGE_STACK_SIZE),
+        )),
+        size => Some(Diagnostic::warning(
+            "stack-size-custom",
+            format!("LC_MAIN sets a custom 0x{:x} byte main thread stack (0 = default)", size),
+        )),
+    }
+}
+
+pub fn parse_unixthread(data: &[u8], lc: &LoadCommand, is_be: bool, cputype: i32, segments: &[ParsedSegment]) -> Result<EntryPoint, Box<dyn Error>> {
+    let (pc_flavor, pc_offset, pc_width) = pc_location(cputype)
+        .ok_or_else(|| format!("LC_UNIXTHREAD thread state for {} is not supported", cpu_type_name(cputype)))?;
+
+    let base = lc.offset as usize;
+    let end = base + lc.cmdsize as usize;
+    if end > data.len() {
+        return Err("LC_UNIXTHREAD exceeds file bounds".into());
+    }
+
+    // Walk the (flavor, count, state) entries until the one that holds the pc
+    let mut cursor = base + 8;
+    while cursor + 8 <= end {
+        let flavor: u32 = utils::bytes_to(is_be, &data[cursor..])?;
+        let count: u32 = utils::bytes_to(is_be, &data[cursor + 4..])?;
+        let state = cursor + 8;
+        let state_end = (count as usize).checked_mul(4).and_then(|size| state.checked_add(size)).filter(|&e| e <= end)
+            .ok_or_else(|| format!("LC_UNIXTHREAD flavor {} state (count {}) runs past cmdsize {}", flavor, count, lc.cmdsize))?;
+
+        if flavor == pc_flavor {
+            if pc_offset + pc_width > state_end - state {
+                return Err(format!("LC_UNIXTHREAD flavor {} state is too short for the pc (count {})", flavor, count).into());
+            }
+            let pc = match pc_width {
+                8 => utils::bytes_to::<u64>(is_be, &data[state + pc_offset..])?,
+                _ => utils::bytes_to::<u32>(is_be, &data[state + pc_offset..])? as u64,
+            };
+
+            return Ok(EntryPoint {
+                source: EntrySource::UnixThread,
+                file_offset: segment_for_vmaddr(segments, pc).map(|seg| seg.fileoff + (pc - seg.vmaddr)),
+                vm_addr: Some(pc),
+                stack_size: None,
+            });
+        }
+        cursor = state_end;
+    }
+
+    Err(format!("LC_UNIXTHREAD has no flavor {} thread state for {}", pc_flavor, cpu_type_name(cputype)).into())
+}
+
+pub fn write_entry_point(w: &mut dyn Write, entry: &EntryPointReport) -> io::Result<()> {
+    let hex = |value: Option<u64>| value.map(|v| format!("0x{:x}", v)).unwrap_or_else(|| "-".to_string());
+
+    writeln!(w)?;
+    writeln!(w, "{} ({})", "Entry Point".green().bold(), entry.source)?;
+    writeln!(w, "----------------------------------------")?;
+    writeln!(w, "{} {}", "VM address   :".yellow().bold(), hex(entry.vm_addr))?;
+    writeln!(w, "{} {}", "File offset  :".yellow().bold(), hex(entry.file_offset))?;
+    match entry.stack_size {
+        Some(0) => writeln!(w, "{} default", "Stack size   :".yellow().bold())?,
+        Some(size) if size > LARGE_STACK_SIZE => writeln!(w, "{} 0x{:x} {}", "Stack size   :".yellow().bold(), size, "(oversized)".red().bold())?,
+        Some(size) => writeln!(w, "{} 0x{:x} {}", "Stack size   :".yellow().bold(), size, "(custom)".yellow())?,
+        None => {}
+    }
+    writeln!(w, "----------------------------------------")?;
+    Ok(())
+}
+
+
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text_segment() -> ParsedSegment {
+        ParsedSegment {
+            segname: SEG_TEXT,
+            vmaddr: 0x1_0000_0000,
+            vmsize: 0x4000,
+            fileoff: 0,
+            filesize: 0x4000,
+            maxprot: 5,
+            initprot: 5,
+            flags: 0,
+            sections: Vec::new(),
+        }
+    }
+
+    fn command(words: &[u32]) -> Vec<u8> {
+        words.iter().flat_map(|w| w.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn lc_main_offset_maps_through_text() {
+        let data = command(&[LC_MAIN, 24, 0x3f00, 0, 0x8000, 0]);
+        let lc = LoadCommand { cmd: LC_MAIN, cmdsize: 24, offset: 0 };
+
+        let entry = parse_main(&data, &lc, false, &[text_segment()]).unwrap();
+        assert_eq!(entry, EntryPoint {
+            source: EntrySource::Main,
+            file_offset: Some(0x3f00),
+            vm_addr: Some(0x1_0000_3f00),
+            stack_size: Some(0x8000),
+        });
+
+        assert!(parse_main(&data, &LoadCommand { cmdsize: 16, ..lc }, false, &[]).is_err());
+    }
+
+    #[test]
+    fn only_a_set_stack_size_is_flagged() {
+        let main = |stack_size| EntryPoint { source: EntrySource::Main, file_offset: Some(0x3f00), vm_addr: None, stack_size };
+
+        assert_eq!(stack_size_diagnostic(&main(Some(0))), None);
+        assert_eq!(stack_size_diagnostic(&main(None)), None);
+        assert_eq!(stack_size_diagnostic(&main(Some(0x100_0000))).map(|d| d.code), Some("stack-size-custom"));
+        assert_eq!(stack_size_diagnostic(&main(Some(LARGE_STACK_SIZE))).map(|d| d.code), Some("stack-size-custom"));
+        assert_eq!(stack_size_diagnostic(&main(Some(LARGE_STACK_SIZE + 0x4000))).map(|d| d.code), Some("stack-size-oversized"));
+    }
+
+    #[test]
+    fn unixthread_pc_is_read_per_cputype() {
+        // x86_64: rip is register 16 of 21
+        let mut regs = vec![0u32; 42];
+        regs[32] = 0x3f00;
+        regs[33] = 0x1;
+        let mut words = vec![LC_UNIXTHREAD, 8 + 8 + 42 * 4, X86_THREAD_STATE64, 42];
+        words.extend(&regs);
+        let data = command(&words);
+        let lc = LoadCommand { cmd: LC_UNIXTHREAD, cmdsize: data.len() as u32, offset: 0 };
+
+        let entry = parse_unixthread(&data, &lc, false, CPU_TYPE_X86_64, &[text_segment()]).unwrap();
+        assert_eq!(entry.vm_addr, Some(0x1_0000_3f00));
+        assert_eq!(entry.file_offset, Some(0x3f00));
+        assert_eq!(entry.stack_size, None);
+
+        // arm64: pc follows x0-x28, fp, lr, sp
+        let mut regs = vec![0u32; 68];
+        regs[64] = 0x1000;
+        let mut words = vec![LC_UNIXTHREAD, 8 + 8 + 68 * 4, ARM_THREAD_STATE64, 68];
+        words.extend(&regs);
+        let data = command(&words);
+        let lc = LoadCommand { cmd: LC_UNIXTHREAD, cmdsize: data.len() as u32, offset: 0 };
+
+        let entry = parse_unixthread(&data, &lc, false, CPU_TYPE_ARM64, &[]).unwrap();
+        assert_eq!((entry.vm_addr, entry.file_offset), (Some(0x1000), None));
+
+        // the wrong flavor for the cputype has no pc to give
+        assert!(parse_unixthread(&data, &lc, false, CPU_TYPE_X86_64, &[]).is_err());
+    }
+}
@@ -0,0 +1,125 @@
+// File Purpose: What can go wrong while parsing, as something a library user can match on
+
+use std::array::TryFromSliceError;
+use std::error::Error;
+use std::fmt;
+use std::str::Utf8Error;
+use crate::macho::load_commands;
+
+/*
+The core parsers (header, fat, load commands, segments, symtab, dylibs) return MoscopeError,
+so a caller can tell a truncated download from a file that simply isn't a Mach-O:
+
+    match moscope::macho::header::read_thin_header(&data, &slice) {
+        Err(MoscopeError::BadMagic(magic)) => ...,          // not a Mach-O
+        Err(MoscopeError::OutOfBounds { .. }) => ...,       // cut short
+        ...
+    }
+
+Everything else still returns Box<dyn Error>, which a MoscopeError converts into with `?`.
+Checks that don't fit a variant (yet) carry their message in Malformed, `.into()` on a
+String / &str builds one so the existing `Err(format!(...).into())` sites read the same.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoscopeError {
+    // fewer bytes than the structure being read needs
+    TooSmall { needed: usize, available: usize },
+    // first four bytes are neither a thin nor a fat Mach-O magic, as read big endian
+    BadMagic(u32),
+    // `len` bytes at `offset` run past the end of the file (or slice)
+    OutOfBounds { offset: u64, len: u64 },
+    // NUL terminator missing, `offset` is where the string starts
+    UnterminatedString { offset: u64 },
+    // a load command's cmdsize doesn't fit what it claims to be
+    InvalidCmdSize { cmd: u32, cmdsize: u32 },
+    BadUtf8(Utf8Error),
+    Malformed(String),
+}
+
+impl fmt::Display for MoscopeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoscopeError::TooSmall { needed, available } => write!(f, "buffer too small ({} bytes needed, {} available)", needed, available),
+            MoscopeError::BadMagic(magic) => write!(f, "not a Mach-O: unknown magic 0x{:08x}", magic),
+            MoscopeError::OutOfBounds { offset, len } => write!(f, "{} bytes at offset 0x{:x} exceed file bounds", len, offset),
+            MoscopeError::UnterminatedString { offset } => write!(f, "string at offset 0x{:x} is not NUL terminated", offset),
+            MoscopeError::InvalidCmdSize { cmd, cmdsize } => write!(f, "{} has invalid cmdsize {}", load_commands::load_command_name(*cmd), cmdsize),
+            MoscopeError::BadUtf8(e) => write!(f, "invalid UTF-8: {}", e),
+            MoscopeError::Malformed(message) => f.write_str(message),
+        }
+    }
+}
+
+impl Error for MoscopeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MoscopeError::BadUtf8(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+// `bytes[..4].try_into()?` with a short slice
+impl From<TryFromSliceError> for MoscopeError {
+    fn from(_: TryFromSliceError) -> Self {
+        MoscopeError::Malformed("slice has the wrong length for the value being read".into())
+    }
+}
+
+impl From<Utf8Error> for MoscopeError {
+    fn from(e: Utf8Error) -> Self {
+        MoscopeError::BadUtf8(e)
+    }
+}
+
+impl From<String> for MoscopeError {
+    fn from(message: String) -> Self {
+        MoscopeError::Malformed(message)
+    }
+}
+
+impl From<&str> for MoscopeError {
+    fn from(message: &str) -> Self {
+        MoscopeError::Malformed(message.to_string())
+    }
+}
+
+// Helpers that still return Box<dyn Error>, the message is all that survives
+impl From<Box<dyn Error>> for MoscopeError {
+    fn from(e: Box<dyn Error>) -> Self {
+        match e.downcast::<MoscopeError>() {
+            Ok(e) => *e,
+            Err(e) => MoscopeError::Malformed(e.to_string()),
+        }
+    }
+}
+
+
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn survives_a_round_trip_through_box_dyn_error() {
+        let boxed: Box<dyn Error> = MoscopeError::OutOfBounds { offset: 0x10, len: 4 }.into();
+        assert_eq!(MoscopeError::from(boxed), MoscopeError::OutOfBounds { offset: 0x10, len: 4 });
+
+        let boxed: Box<dyn Error> = "something else".into();
+        assert_eq!(MoscopeError::from(boxed), MoscopeError::Malformed("something else".into()));
+    }
+
+    #[test]
+    fn slice_conversions_still_work_with_question_mark() {
+        fn read(bytes: &[u8]) -> Result<u32, MoscopeError> {
+            Ok(u32::from_le_bytes(bytes.try_into()?))
+        }
+        assert_eq!(read(&[1, 0, 0, 0]), Ok(1));
+        assert!(matches!(read(&[1, 0]), Err(MoscopeError::Malformed(_))));
+    }
+}
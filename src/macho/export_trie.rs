@@ -0,0 +1,356 @@
+// File Purpose: what does the binary export? Walks the export trie (LC_DYLD_EXPORTS_TRIE / dyld_info export_off)
+
+use std::collections::HashSet;
+use std::error::Error;
+use std::io::{self, Write};
+use colored::Colorize;
+use crate::macho::constants::*;
+use crate::macho::diagnostics::{self, Diagnostic};
+use crate::macho::dylibs::{self, ParsedDylib};
+use crate::macho::utils;
+use crate::reporting::exports::{ExportReport, ExportsReport, ReexportsReport};
+
+
+/*
+The export trie is a prefix tree over symbol names, one node per shared prefix:
+
+    node
+    +---------------------------+
+    | terminal_size (uleb)      |  0 = no symbol ends here
+    | terminal info             |  terminal_size bytes, see below
+    | child_count (u8)          |
+    | child[0] edge  "foo\0"    |  next chunk of the name
+    | child[0] node  (uleb)     |  offset from the start of the trie
+    | ...                       |
+    +---------------------------+
+
+    terminal info
+        flags (uleb)
+        REEXPORT             ordinal (uleb), import name (cstring, "" = same name)
+        STUB_AND_RESOLVER    stub offset (uleb), resolver offset (uleb)
+        everything else      symbol offset (uleb)
+
+    ""  --"_"-->  "_"  --"foo"-->  "_foo"  (terminal)
+                       --"bar"-->  "_bar"  (terminal, REEXPORT ordinal 1 "_baz")
+
+A REEXPORT terminal has no address in this image at all, dyld looks the name up in the
+dylib at `ordinal` (1-based over the dylib load commands, same numbering as binds).
+Umbrella frameworks are mostly made of these.
+
+A STUB_AND_RESOLVER terminal's offset is a stub, the real address comes from calling the
+resolver function at the second offset the first time the symbol is bound.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExportTarget {
+    Offset(u64), // from the __TEXT base (ABSOLUTE: the value itself)
+    Reexport { ordinal: u64, import_name: Option<String> },
+    StubAndResolver { stub: u64, resolver: u64 }, // both from the __TEXT base
+}
+
+// Deeper than any real symbol name has edges, a chain longer than this is a malformed trie
+pub const MAX_TRIE_DEPTH: usize = 512;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportedSymbol {
+    pub name: String,
+    pub flags: u64,
+    pub target: ExportTarget,
+}
+
+impl ExportedSymbol {
+    pub fn kind_str(&self) -> &'static str {
+        match self.flags as u8 & EXPORT_SYMBOL_FLAGS_KIND_MASK {
+            EXPORT_SYMBOL_FLAGS_REGULAR => "REGULAR",
+            EXPORT_SYMBOL_FLAGS_THREAD_LOCAL => "THREAD_LOCAL",
+            EXPORT_SYMBOL_FLAGS_KIND_ABSOLUTE => "ABSOLUTE",
+            _ => "UNKNOWN",
+        }
+    }
+
+    // text_base is __TEXT's vmaddr, ABSOLUTE symbols don't get it added
+    pub fn build_report(&self, dylibs: &[ParsedDylib], text_base: u64) -> ExportReport {
+        let address_of = |offset: u64| {
+            if self.flags as u8 & EXPORT_SYMBOL_FLAGS_KIND_MASK == EXPORT_SYMBOL_FLAGS_KIND_ABSOLUTE {
+                offset
+            } else {
+                text_base.wrapping_add(offset)
+            }
+        };
+
+        let mut report = ExportReport {
+            name: self.name.clone(),
+            kind: self.kind_str().to_string(),
+            flags: self.flags,
+            offset: None,
+            address: None,
+            resolver_offset: None,
+            reexport_ordinal: None,
+            reexport_from: None,
+            reexport_name: None,
+        };
+
+        match &self.target {
+            ExportTarget::Offset(off) => {
+                report.offset = Some(*off);
+                report.address = Some(address_of(*off));
+            }
+            ExportTarget::StubAndResolver { stub, resolver } => {
+                report.offset = Some(*stub);
+                report.address = Some(address_of(*stub));
+                report.resolver_offset = Some(*resolver);
+            }
+            ExportTarget::Reexport { ordinal, import_name } => {
+                report.reexport_ordinal = Some(*ordinal);
+                report.reexport_from = Some(match dylibs::dylib_for_ordinal(dylibs, *ordinal) {
+                    Some(dylib) => dylib.path.clone(),
+                    None => format!("<invalid ordinal {}>", ordinal),
+                });
+                // "" in the trie means the dependency exports it under the same name
+                report.reexport_name = Some(import_name.clone().unwrap_or_else(|| self.name.clone()));
+            }
+        }
+        report
+    }
+}
+
+fn read_cstr(trie: &[u8], cursor: &mut usize) -> Result<String, Box<dyn Error>> {
+    let rest = trie.get(*cursor..).ok_or("export trie: string starts past the end")?;
+    let len = rest.iter().position(|&b| b == 0).ok_or("export trie: unterminated string")?;
+    *cursor += len + 1;
+    Ok(String::from_utf8_lossy(&rest[..len]).to_string())
+}
+
+fn read_terminal(trie: &[u8], mut cursor: usize, name: String) -> Result<ExportedSymbol, Box<dyn Error>> {
+    let flags = utils::read_uleb(trie, &mut cursor)?;
+
+    let target = if flags as u8 & EXPORT_SYMBOL_FLAGS_REEXPORT != 0 {
+        let ordinal = utils::read_uleb(trie, &mut cursor)?;
+        let import_name = read_cstr(trie, &mut cursor)?;
+        ExportTarget::Reexport { ordinal, import_name: (!import_name.is_empty()).then_some(import_name) }
+    } else if flags as u8 & EXPORT_SYMBOL_FLAGS_STUB_AND_RESOLVER != 0 {
+        let stub = utils::read_uleb(trie, &mut cursor)?;
+        let resolver = utils::read_uleb(trie, &mut cursor)?;
+        ExportTarget::StubAndResolver { stub, resolver }
+    } else {
+        ExportTarget::Offset(utils::read_uleb(trie, &mut cursor)?)
+    };
+
+    Ok(ExportedSymbol { name, flags, target })
+}
+
+pub fn parse_export_trie(trie: &[u8]) -> Result<Vec<ExportedSymbol>, Box<dyn Error>> {
+    let mut exports = Vec::new();
+    if trie.is_empty() {
+        return Ok(exports);
+    }
+
+    // Child offsets are arbitrary, a node pointing back at an ancestor would loop forever
+    let mut visited = HashSet::new();
+    let mut pending = vec![(0usize, String::new(), 0usize)];
+
+    while let Some((node, prefix, depth)) = pending.pop() {
+        if depth > MAX_TRIE_DEPTH {
+            return Err(format!("export trie: node {:#x} is more than {} edges deep", node, MAX_TRIE_DEPTH).into());
+        }
+        if node >= trie.len() {
+            return Err(format!("export trie: node offset {:#x} past the end ({:#x} bytes)", node, trie.len()).into());
+        }
+        if !visited.insert(node) {
+            return Err(format!("export trie: node {:#x} reached twice", node).into());
+        }
+
+        let mut cursor = node;
+        let terminal_size = utils::read_uleb(trie, &mut cursor)? as usize;
+        if terminal_size != 0 {
+            exports.push(read_terminal(trie, cursor, prefix.clone())?);
+        }
+        cursor = cursor.checked_add(terminal_size).filter(|&c| c < trie.len())
+            .ok_or_else(|| format!("export trie: terminal info of node {:#x} runs past the end", node))?;
+
+        let child_count = trie[cursor];
+        cursor += 1;
+        let mut children = Vec::with_capacity(child_count as usize);
+        for _ in 0..child_count {
+            let edge = read_cstr(trie, &mut cursor)?;
+            let child = utils::read_uleb(trie, &mut cursor)? as usize;
+            children.push((child, format!("{}{}", prefix, edge), depth + 1));
+        }
+        // Reversed so the stack pops them in trie order
+        pending.extend(children.into_iter().rev());
+    }
+
+    Ok(exports)
+}
+
+pub fn build_exports_report(cpu_type: &str, cpu_subtype: &str, exports: &[ExportedSymbol], dylibs: &[ParsedDylib], text_base: u64, diagnostics: &[Diagnostic]) -> ExportsReport {
+    ExportsReport {
+        cpu_type: cpu_type.to_string(),
+        cpu_subtype: cpu_subtype.to_string(),
+        exports: exports.iter().map(|e| e.build_report(dylibs, text_base)).collect(),
+        diagnostics: diagnostics.iter().map(|d| d.build_report()).collect(),
+    }
+}
+
+pub fn write_exports_summary(w: &mut dyn Write, report: &ExportsReport) -> io::Result<()> {
+    writeln!(w)?;
+    writeln!(w, "{} ({})", "Exported Symbols".green().bold(), report.cpu_subtype)?;
+    writeln!(w, "----------------------------------------")?;
+
+    if report.exports.is_empty() {
+        writeln!(w, "No exported symbols")?;
+    }
+
+    for export in &report.exports {
+        let address = export.address.map(|a| format!("0x{:016x}", a)).unwrap_or_else(|| "-".repeat(18));
+        write!(w, "{} {:<12} {}", address, export.kind, export.name)?;
+        if let Some(from) = &export.reexport_from {
+            match export.reexport_name.as_deref() {
+                Some(import) if import != export.name => write!(w, " -> {} {}", import.cyan(), from.dimmed())?,
+                _ => write!(w, " {}", from.dimmed())?,
+            }
+        }
+        if let Some(resolver) = export.resolver_offset {
+            write!(w, " {}", format!("(resolver at +{:#x})", resolver).dimmed())?;
+        }
+        writeln!(w)?;
+    }
+    diagnostics::write_titled_diagnostic_reports(w, "Diagnostics", &report.diagnostics)?;
+    Ok(())
+}
+
+pub fn write_reexports_summary(w: &mut dyn Write, ReexportsReport(report): &ReexportsReport) -> io::Result<()> {
+    writeln!(w)?;
+    writeln!(w, "{} ({})", "Re-exported Symbols".green().bold(), report.cpu_subtype)?;
+    writeln!(w, "----------------------------------------")?;
+
+    let reexports: Vec<&ExportReport> = report.exports.iter().filter(|e| e.reexport_from.is_some()).collect();
+    if reexports.is_empty() {
+        writeln!(w, "No re-exported symbols")?;
+    }
+
+    for export in reexports {
+        let from = export.reexport_from.as_deref().unwrap_or_default();
+        match export.reexport_name.as_deref() {
+            Some(import) if import != export.name => writeln!(w, "  {} -> {} {}", export.name, import.cyan(), from.dimmed())?,
+            _ => writeln!(w, "  {} {}", export.name, from.dimmed())?,
+        }
+    }
+    diagnostics::write_titled_diagnostic_reports(w, "Diagnostics", &report.diagnostics)?;
+    Ok(())
+}
+
+
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macho::dylibs::DylibKind;
+    use crate::macho::load_commands::LoadCommand;
+
+    /*
+        0x00  root       no terminal, 1 child "_" -> 0x05
+        0x05  "_"        no terminal, 2 children "foo" -> 0x11, "bar" -> 0x16
+        0x11  "_foo"     REGULAR at 0x1000
+        0x16  "_bar"     REEXPORT ordinal 2 as "_baz"
+    */
+    fn sample_trie() -> Vec<u8> {
+        let mut trie = vec![0x00, 0x01, b'_', 0x00, 0x05];
+        trie.extend_from_slice(&[0x00, 0x02, b'f', b'o', b'o', 0x00, 0x11, b'b', b'a', b'r', 0x00, 0x16]);
+        assert_eq!(trie.len(), 0x11);
+        trie.extend_from_slice(&[0x03, 0x00, 0x80, 0x20, 0x00]);
+        trie.extend_from_slice(&[0x07, EXPORT_SYMBOL_FLAGS_REEXPORT, 0x02, b'_', b'b', b'a', b'z', 0x00, 0x00]);
+        trie
+    }
+
+    fn dylib(kind: DylibKind, path: &str) -> ParsedDylib {
+        ParsedDylib {
+            path: path.to_string(),
+            timestamp: 0,
+            current_version: 0,
+            compatibility_version: 0,
+            kind,
+            source_lc: LoadCommand { cmd: 0, cmdsize: 0, offset: 0 },
+        }
+    }
+
+    #[test]
+    fn walks_trie_and_decodes_reexports() {
+        let exports = parse_export_trie(&sample_trie()).unwrap();
+
+        assert_eq!(exports, vec![
+            ExportedSymbol { name: "_foo".into(), flags: 0, target: ExportTarget::Offset(0x1000) },
+            ExportedSymbol {
+                name: "_bar".into(),
+                flags: EXPORT_SYMBOL_FLAGS_REEXPORT as u64,
+                target: ExportTarget::Reexport { ordinal: 2, import_name: Some("_baz".into()) },
+            },
+        ]);
+    }
+
+    #[test]
+    fn reexport_from_names_the_dependency() {
+        let exports = parse_export_trie(&sample_trie()).unwrap();
+        // LC_ID_DYLIB doesn't take an ordinal, ordinal 2 is the second dependency
+        let dylibs = vec![
+            dylib(DylibKind::Id, "/usr/lib/libumbrella.dylib"),
+            dylib(DylibKind::Load, "/usr/lib/libSystem.B.dylib"),
+            dylib(DylibKind::Reexport, "/usr/lib/libsub.dylib"),
+        ];
+
+        let report = build_exports_report("ARM64", "arm64", &exports, &dylibs, 0x1_0000_0000, &[]);
+        assert_eq!(report.exports[0].reexport_from, None);
+        assert_eq!(report.exports[0].offset, Some(0x1000));
+        assert_eq!(report.exports[0].address, Some(0x1_0000_1000));
+        assert_eq!(report.exports[1].address, None);
+        assert_eq!(report.exports[1].reexport_ordinal, Some(2));
+        assert_eq!(report.exports[1].reexport_from.as_deref(), Some("/usr/lib/libsub.dylib"));
+        assert_eq!(report.exports[1].reexport_name.as_deref(), Some("_baz"));
+
+        let report = build_exports_report("ARM64", "arm64", &exports, &dylibs[..2], 0, &[]);
+        assert_eq!(report.exports[1].reexport_from.as_deref(), Some("<invalid ordinal 2>"));
+    }
+
+    #[test]
+    fn stub_and_resolver_keeps_both_offsets() {
+        // root is the terminal for "", STUB_AND_RESOLVER stub 0x10 resolver 0x20, no children
+        let trie = [0x03, EXPORT_SYMBOL_FLAGS_STUB_AND_RESOLVER, 0x10, 0x20, 0x00];
+        let exports = parse_export_trie(&trie).unwrap();
+        assert_eq!(exports[0].target, ExportTarget::StubAndResolver { stub: 0x10, resolver: 0x20 });
+
+        let report = exports[0].build_report(&[], 0x4000);
+        assert_eq!((report.address, report.resolver_offset), (Some(0x4010), Some(0x20)));
+    }
+
+    #[test]
+    fn absolute_exports_skip_the_text_base() {
+        let export = ExportedSymbol { name: "_abs".into(), flags: EXPORT_SYMBOL_FLAGS_KIND_ABSOLUTE as u64, target: ExportTarget::Offset(0x42) };
+        assert_eq!(export.build_report(&[], 0x1_0000_0000).address, Some(0x42));
+    }
+
+    #[test]
+    fn rejects_tries_deeper_than_the_bound() {
+        // a straight chain of "a" edges, every node at the next offset, MAX_TRIE_DEPTH + 1 of them
+        let mut trie = Vec::new();
+        for i in 0..=MAX_TRIE_DEPTH + 1 {
+            let next = (i + 1) * 6;
+            trie.extend_from_slice(&[0x00, 0x01, b'a', 0x00, 0x80 | (next & 0x7f) as u8, (next >> 7) as u8]);
+        }
+        trie.extend_from_slice(&[0x00, 0x00]);
+        assert!(parse_export_trie(&trie).unwrap_err().to_string().contains("deep"));
+    }
+
+    #[test]
+    fn rejects_cycles_and_truncation() {
+        // root's only child points back at the root
+        assert!(parse_export_trie(&[0x00, 0x01, b'a', 0x00, 0x00]).is_err());
+
+        let trie = sample_trie();
+        assert!(parse_export_trie(&trie[..trie.len() - 3]).is_err());
+        assert!(parse_export_trie(&[]).unwrap().is_empty());
+    }
+}
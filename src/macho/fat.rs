@@ -1,6 +1,6 @@
 // File Purpose: "Which Mach-O should be parsed?"
-use std::error::Error;
 use crate::macho::constants;
+use crate::macho::error::MoscopeError;
 use crate::macho::utils;
 
 
@@ -53,7 +53,7 @@ pub struct FatArch64 {
     pub reserved: u32,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum FatArch {
     Arch32(FatArch32),
     Arch64(FatArch64),
@@ -81,24 +81,21 @@ impl FatKind {
 pub fn read_fat_archs(
     data: &[u8],            // Entire file contents
     header: &FatHeader,     // Previously-parsed fat header
-) -> Result<Vec<FatArch>, Box<dyn Error>> {
+) -> Result<Vec<FatArch>, MoscopeError> {
     let mut archs = Vec::new();
     let mut offset: usize = constants::FAT_HEADER_SIZE; // Start on the on disk fat header
 
 
-    for i in 0..header.nfat_arch {
+    for _ in 0..header.nfat_arch {
         if header.kind.is_64() {
             // ==== fat_arch_64 ====
             // Bounds check
             if offset + constants::FAT_ARCH64_SIZE > data.len() {
-                return Err(format!(
-                    "fat_arch_64[{}] extends beyond EOF",
-                    i
-                ).into());
+                return Err(MoscopeError::OutOfBounds { offset: offset as u64, len: constants::FAT_ARCH64_SIZE as u64 });
             }
 
             let base = offset;
-            let cputype: i32 = utils::bytes_to(header.kind.is_be(), &data[base + 0..])?;
+            let cputype: i32 = utils::bytes_to(header.kind.is_be(), &data[base..])?;
             let cpusubtype: i32 = utils::bytes_to(header.kind.is_be(), &data[base + 4..])?;
             let arch_offset: u64 = utils::bytes_to(header.kind.is_be(), &data[base + 8..])?;
             let size: u64 = utils::bytes_to(header.kind.is_be(), &data[base + 16..])?;
@@ -120,14 +117,11 @@ pub fn read_fat_archs(
             // ==== fat_arch_32 ====
             // bounds check
             if offset + constants::FAT_ARCH32_SIZE > data.len() {
-                return Err(format!(
-                    "fat_arch_64[{}] extends beyond EOF",
-                    i
-                ).into());
+                return Err(MoscopeError::OutOfBounds { offset: offset as u64, len: constants::FAT_ARCH32_SIZE as u64 });
             }
 
             let base = offset;
-            let cputype: i32 = utils::bytes_to(header.kind.is_be(), &data[base + 0..])?;
+            let cputype: i32 = utils::bytes_to(header.kind.is_be(), &data[base..])?;
             let cpusubtype: i32 = utils::bytes_to(header.kind.is_be(), &data[base + 4..])?;
             let arch_offset: u32 = utils::bytes_to(header.kind.is_be(), &data[base + 8..])?;
             let size: u32 = utils::bytes_to(header.kind.is_be(), &data[base + 12..])?;
@@ -151,11 +145,201 @@ pub fn read_fat_archs(
     Ok(archs)
 }
 
-pub fn read_fat_header(data: &[u8]) -> Result<FatHeader, Box<dyn Error>> {
+impl FatArch {
+    pub fn cputype(&self) -> i32 {
+        match self {
+            FatArch::Arch32(a) => a.cputype,
+            FatArch::Arch64(a) => a.cputype,
+        }
+    }
+
+    pub fn cpusubtype(&self) -> i32 {
+        match self {
+            FatArch::Arch32(a) => a.cpusubtype,
+            FatArch::Arch64(a) => a.cpusubtype,
+        }
+    }
+
+    pub fn offset(&self) -> u64 {
+        match self {
+            FatArch::Arch32(a) => a.offset as u64,
+            FatArch::Arch64(a) => a.offset,
+        }
+    }
+
+    // log2 of the slice alignment, 14 = 16K
+    pub fn align(&self) -> u32 {
+        match self {
+            FatArch::Arch32(a) => a.align,
+            FatArch::Arch64(a) => a.align,
+        }
+    }
+
+    pub fn size(&self) -> u64 {
+        match self {
+            FatArch::Arch32(a) => a.size as u64,
+            FatArch::Arch64(a) => a.size,
+        }
+    }
+
+    // "arm64", "x86_64", ... same names --identify prints
+    pub fn name(&self) -> &'static str {
+        constants::arch_name(self.cputype(), self.cpusubtype())
+    }
+}
+
+/*
+`lipo -thin`: every slice already is a complete thin Mach-O, its offsets are relative
+to the slice start, so pulling one out is just copying [offset, offset + size).
+
+    fat file                          thin output
+    +-------------+
+    | fat_header  |
+    | fat_arch[]  |
+    +-------------+
+    | arm64 bytes |  ------------->   +-------------+
+    +-------------+                   | arm64 bytes |
+    | x86_64 ...  |                   +-------------+
+    +-------------+
+*/
+pub fn extract_slice<'a>(data: &'a [u8], arch: &str) -> Result<&'a [u8], MoscopeError> {
+    if !has_fat_magic(data) {
+        return Err("input is not a fat (universal) binary".into());
+    }
+
+    let header = read_fat_header(data)?;
+    let archs = read_fat_archs(data, &header)?;
+
+    let Some(found) = archs.iter().find(|a| a.name() == arch) else {
+        let available: Vec<&str> = archs.iter().map(FatArch::name).collect();
+        return Err(format!("architecture '{}' not found in fat binary (available: {})", arch, available.join(", ")).into());
+    };
+
+    let start = found.offset();
+    let end = start.checked_add(found.size()).filter(|&end| end <= data.len() as u64)
+        .ok_or(MoscopeError::OutOfBounds { offset: start, len: found.size() })?;
+
+    Ok(&data[start as usize..end as usize])
+}
+
+/*
+`lipo -create`, the other direction. Slices are laid out in the order given, each one
+starting on its own 2^align boundary:
+
+    +-------------+  0
+    | fat_header  |
+    | fat_arch[]  |  big endian, 32-bit entries unless something lands past 4GB
+    +-------------+
+    | padding     |
+    +-------------+  fat_arch[0].offset  (multiple of 2^align)
+    | slice 0     |
+    +-------------+
+    | padding     |
+    +-------------+  fat_arch[1].offset
+    | slice 1     |
+    +-------------+
+*/
+pub fn build_fat(slices: &[&[u8]]) -> Result<Vec<u8>, MoscopeError> {
+    use crate::macho::header::{self, MachOSlice};
+
+    if slices.is_empty() {
+        return Err("need at least one thin Mach-O to build a fat binary".into());
+    }
+
+    let mut cpus: Vec<(i32, i32)> = Vec::new();
+    for (i, bytes) in slices.iter().enumerate() {
+        if has_fat_magic(bytes) {
+            return Err(format!("input #{} is already a fat binary", i + 1).into());
+        }
+        let parsed = header::read_thin_header(bytes, &MachOSlice { offset: 0, size: None })
+            .map_err(|e| format!("input #{} is not a thin Mach-O: {}", i + 1, e))?;
+        let (cputype, cpusubtype) = parsed.header.cpu();
+
+        // Capability bits (ptrauth ABI version, ...) don't make it a different architecture
+        let subtype = cpusubtype & !constants::CPU_SUBTYPE_MASK;
+        if cpus.iter().any(|&(t, s)| t == cputype && s & !constants::CPU_SUBTYPE_MASK == subtype) {
+            return Err(format!("more than one {} input", constants::arch_name(cputype, cpusubtype)).into());
+        }
+        cpus.push((cputype, cpusubtype));
+    }
+
+    let entries: Vec<(u64, u32)> = slices.iter().zip(&cpus)
+        .map(|(bytes, &(cputype, _))| (bytes.len() as u64, slice_align(cputype)))
+        .collect();
+    let (is_64, offsets) = plan_layout(&entries);
+
+    let total = offsets.last().unwrap() + entries.last().unwrap().0;
+    let mut out = Vec::with_capacity(total as usize);
+    out.extend_from_slice(if is_64 { &constants::FAT_MAGIC_64 } else { &constants::FAT_MAGIC });
+    out.extend_from_slice(&(slices.len() as u32).to_be_bytes());
+
+    for ((&(cputype, cpusubtype), &(size, align)), &offset) in cpus.iter().zip(&entries).zip(&offsets) {
+        out.extend_from_slice(&cputype.to_be_bytes());
+        out.extend_from_slice(&cpusubtype.to_be_bytes());
+        if is_64 {
+            out.extend_from_slice(&offset.to_be_bytes());
+            out.extend_from_slice(&size.to_be_bytes());
+            out.extend_from_slice(&align.to_be_bytes());
+            out.extend_from_slice(&0u32.to_be_bytes()); // reserved
+        } else {
+            out.extend_from_slice(&(offset as u32).to_be_bytes());
+            out.extend_from_slice(&(size as u32).to_be_bytes());
+            out.extend_from_slice(&align.to_be_bytes());
+        }
+    }
+
+    for (bytes, &offset) in slices.iter().zip(&offsets) {
+        out.resize(offset as usize, 0);
+        out.extend_from_slice(bytes);
+    }
+
+    Ok(out)
+}
+
+// Same alignment lipo picks: 16K pages on ARM, 4K everywhere else
+fn slice_align(cputype: i32) -> u32 {
+    match cputype {
+        constants::CPU_TYPE_ARM | constants::CPU_TYPE_ARM64 => 14,
+        _ => 12,
+    }
+}
+
+// (size, align) per slice --> (needs fat_arch_64, slice offsets)
+fn plan_layout(entries: &[(u64, u32)]) -> (bool, Vec<u64>) {
+    let place = |arch_size: usize| {
+        let mut next = (constants::FAT_HEADER_SIZE + entries.len() * arch_size) as u64;
+        entries.iter().map(|&(size, align)| {
+            let offset = next.next_multiple_of(1u64 << align);
+            next = offset + size;
+            offset
+        }).collect::<Vec<u64>>()
+    };
+
+    // fat_arch only has 32-bit offset/size fields, anything that doesn't fit needs the 64-bit table
+    let offsets = place(constants::FAT_ARCH32_SIZE);
+    let fits = entries.iter().zip(&offsets).all(|(&(size, _), &offset)| offset <= u32::MAX as u64 && size <= u32::MAX as u64);
+    if fits {
+        (false, offsets)
+    } else {
+        (true, place(constants::FAT_ARCH64_SIZE))
+    }
+}
+
+// No universal binary in the wild comes close to this many slices
+pub const MAX_PLAUSIBLE_FAT_ARCHS: u32 = 30;
+
+pub fn has_fat_magic(data: &[u8]) -> bool {
+    data.len() >= 4 && matches!(
+        [data[0], data[1], data[2], data[3]],
+        constants::FAT_MAGIC | constants::FAT_MAGIC_64 | constants::FAT_CIGAM | constants::FAT_CIGAM_64
+    )
+}
+
+pub fn read_fat_header(data: &[u8]) -> Result<FatHeader, MoscopeError> {
     use std::mem::size_of;
 
     if data.len() < size_of::<FatHeader>() {
-        return Err("File too small to be a fat binary".into());
+        return Err(MoscopeError::TooSmall { needed: size_of::<FatHeader>(), available: data.len() });
     }
 
     fn classify_fat_magic(bytes: [u8; 4]) -> Option<FatKind> {
@@ -172,7 +356,7 @@ pub fn read_fat_header(data: &[u8]) -> Result<FatHeader, Box<dyn Error>> {
 
     let kind: FatKind = match classify_fat_magic(raw_magic_bytes) {
         Some(kind) => kind,
-        None => return Err("Not a valid fat Mach-O binary".into()),
+        None => return Err(MoscopeError::BadMagic(u32::from_be_bytes(raw_magic_bytes))),
     };
 
     
@@ -182,6 +366,15 @@ pub fn read_fat_header(data: &[u8]) -> Result<FatHeader, Box<dyn Error>> {
         u32::from_le_bytes(data[4..8].try_into()?)
     };
 
+    // 0xCAFEBABE is also the Java class file magic, there the next 4 bytes are the class version
+    // (45+ for any real JDK). file(1) uses the same cutoff to tell the two apart
+    if nfat_arch == 0 || nfat_arch > MAX_PLAUSIBLE_FAT_ARCHS {
+        return Err(format!(
+            "fat magic found but nfat_arch={} is implausible (Java class file or misdetected format?)",
+            nfat_arch
+        ).into());
+    }
+
 
     Ok(FatHeader {
         kind,
@@ -294,4 +487,118 @@ mod tests {
         assert!(archs.is_err());
     }
 
-}
\ No newline at end of file
+    // misdetection guard
+    #[test]
+    fn read_fat_header_rejects_java_class_file() {
+        let data = [
+            0xCA, 0xFE, 0xBA, 0xBE,
+            // minor_version = 0, major_version = 52 (Java 8), reads as nfat_arch = 52
+            0x00, 0x00, 0x00, 0x34,
+        ];
+
+        let err = read_fat_header(&data).unwrap_err().to_string();
+        assert!(err.contains("implausible"), "unexpected error: {}", err);
+        assert!(has_fat_magic(&data));
+    }
+
+    #[test]
+    fn read_fat_header_rejects_zero_archs() {
+        let data = [
+            0xCA, 0xFE, 0xBA, 0xBE,
+            0x00, 0x00, 0x00, 0x00,
+        ];
+
+        assert!(read_fat_header(&data).is_err());
+    }
+
+
+    #[test]
+    fn extract_slice_returns_the_requested_arch() {
+        use crate::test_util::MachOBuilder;
+
+        let arm64 = MachOBuilder::new_64(CPU_TYPE_ARM64, 0).uuid([0x11; 16]).build();
+        let x86_64 = MachOBuilder::new_64(CPU_TYPE_X86_64, 3).uuid([0x22; 16]).build();
+        let fat = build_fat(&[&x86_64, &arm64]).unwrap();
+
+        assert_eq!(extract_slice(&fat, "arm64").unwrap(), &arm64[..]);
+        assert_eq!(extract_slice(&fat, "x86_64").unwrap(), &x86_64[..]);
+
+        // the extracted bytes parse as a thin Mach-O on their own
+        let thin = extract_slice(&fat, "arm64").unwrap();
+        let slice = crate::macho::header::MachOSlice { offset: 0, size: None };
+        assert!(crate::macho::header::read_thin_header(thin, &slice).is_ok());
+    }
+
+    #[test]
+    fn extract_slice_rejects_missing_arch_and_thin_input() {
+        use crate::test_util::MachOBuilder;
+
+        let arm64 = MachOBuilder::new_64(CPU_TYPE_ARM64, 0).uuid([0x11; 16]).build();
+        let fat = build_fat(&[&arm64]).unwrap();
+
+        let err = extract_slice(&fat, "x86_64").unwrap_err().to_string();
+        assert!(err.contains("available: arm64"), "{}", err);
+        assert!(extract_slice(&arm64, "arm64").is_err());
+
+        // slice running past EOF
+        let truncated = &fat[..fat.len() - 1];
+        assert!(extract_slice(truncated, "arm64").is_err());
+    }
+
+    #[test]
+    fn build_fat_round_trips_through_extract_slice() {
+        use crate::test_util::MachOBuilder;
+
+        let arm64 = MachOBuilder::new_64(CPU_TYPE_ARM64, 0).uuid([0x11; 16]).build();
+        let x86_64 = MachOBuilder::new_64(CPU_TYPE_X86_64, 3).uuid([0x22; 16]).build();
+        let fat = build_fat(&[&x86_64, &arm64]).unwrap();
+
+        let header = read_fat_header(&fat).unwrap();
+        assert_eq!(header.kind, FatKind::Fat32BE);
+        let archs = read_fat_archs(&fat, &header).unwrap();
+        assert_eq!(archs.iter().map(FatArch::name).collect::<Vec<_>>(), ["x86_64", "arm64"]);
+        assert_eq!(archs[0].offset() % 0x1000, 0);
+        assert_eq!(archs[1].offset() % 0x4000, 0);
+
+        assert_eq!(extract_slice(&fat, "x86_64").unwrap(), &x86_64[..]);
+        assert_eq!(extract_slice(&fat, "arm64").unwrap(), &arm64[..]);
+    }
+
+    #[test]
+    fn build_fat_rejects_duplicates_and_fat_inputs() {
+        use crate::test_util::MachOBuilder;
+
+        let arm64 = MachOBuilder::new_64(CPU_TYPE_ARM64, 0).uuid([0x11; 16]).build();
+        assert!(build_fat(&[]).is_err());
+        assert!(build_fat(&[&arm64, &arm64]).unwrap_err().to_string().contains("more than one arm64"));
+
+        let fat = build_fat(&[&arm64]).unwrap();
+        assert!(build_fat(&[&fat]).is_err());
+        assert!(build_fat(&[&[0u8; 64][..]]).is_err());
+    }
+
+    #[test]
+    fn plan_layout_switches_to_fat64_past_4gb() {
+        let (is_64, offsets) = plan_layout(&[(0x1000, 12), (0x2000, 14)]);
+        assert!(!is_64);
+        assert_eq!(offsets, [0x1000, 0x4000]);
+
+        // second slice starts past 4GB, the 32-bit table can't describe it
+        let (is_64, offsets) = plan_layout(&[(5 << 30, 12), (0x1000, 12)]);
+        assert!(is_64);
+        assert!(offsets[1] > u32::MAX as u64);
+        assert_eq!(offsets[1] % 0x1000, 0);
+    }
+
+    #[test]
+    fn truncated_arch_table_is_out_of_bounds() {
+        // promises two fat_archs, has none
+        let data = [0xCA, 0xFE, 0xBA, 0xBE, 0x00, 0x00, 0x00, 0x02];
+        let header = read_fat_header(&data).unwrap();
+        assert_eq!(
+            read_fat_archs(&data, &header).unwrap_err(),
+            MoscopeError::OutOfBounds { offset: constants::FAT_HEADER_SIZE as u64, len: constants::FAT_ARCH32_SIZE as u64 }
+        );
+        assert!(matches!(read_fat_header(&data[..4]), Err(MoscopeError::TooSmall { .. })));
+    }
+}
@@ -0,0 +1,198 @@
+// File Purpose: Which Mach-Os make up this kernel cache? (LC_FILESET_ENTRY in MH_FILESET files)
+
+use std::error::Error;
+use std::io::{self, Write};
+use colored::Colorize;
+use crate::macho::constants::{LC_FILESET_ENTRY, LC_REQ_DYLD, MH_FILESET};
+use crate::macho::header::{self, MachOSlice};
+use crate::macho::load_commands::{self, LoadCommand};
+use crate::macho::utils;
+use crate::reporting::fileset::FilesetEntryReport;
+
+/*
+fileset_entry_command memory layout (from loader.h)
++-----------------------------+
+| cmd (u32)                   |  LC_FILESET_ENTRY (| LC_REQ_DYLD)
+| cmdsize (u32)               |
+| vmaddr (u64)                |  where the member's __TEXT is mapped
+| fileoff (u64)               |  where the member's mach_header sits in this file
+| entry_id.offset (u32)       |  lc_str, "com.apple.kernel", "com.apple.driver.AppleARMPlatform", ...
+| reserved (u32)              |
+| "entry_id\0"                |  padded to cmdsize
++-----------------------------+
+
+An MH_FILESET (the kernelcache since macOS 11 / iOS 14) is one big file holding the kernel and
+every kext as complete Mach-Os. The members' own offsets (segments, symtab, ...) count from the
+start of the *fileset*, not from their own header, which is why a member can't be treated like
+a fat slice.
+*/
+
+pub const FILESET_ENTRY_COMMAND_SIZE: u32 = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilesetEntry {
+    pub vmaddr: u64,
+    pub fileoff: u64,
+    pub id: String,
+}
+
+impl FilesetEntry {
+    pub fn build_report(&self) -> FilesetEntryReport {
+        FilesetEntryReport {
+            id: self.id.clone(),
+            vmaddr: self.vmaddr,
+            fileoff: self.fileoff,
+        }
+    }
+}
+
+pub fn is_fileset_entry(lc: &LoadCommand) -> bool {
+    lc.cmd & !LC_REQ_DYLD == LC_FILESET_ENTRY
+}
+
+pub fn parse_fileset_entry(data: &[u8], lc: &LoadCommand, is_be: bool) -> Result<FilesetEntry, Box<dyn Error>> {
+    if lc.cmdsize < FILESET_ENTRY_COMMAND_SIZE {
+        return Err(format!("LC_FILESET_ENTRY has cmdsize {} (at least {} expected)", lc.cmdsize, FILESET_ENTRY_COMMAND_SIZE).into());
+    }
+
+    let base = lc.offset as usize;
+    if base + FILESET_ENTRY_COMMAND_SIZE as usize > data.len() {
+        return Err("LC_FILESET_ENTRY exceeds file bounds".into());
+    }
+
+    Ok(FilesetEntry {
+        vmaddr: utils::bytes_to(is_be, &data[base + 8..])?,
+        fileoff: utils::bytes_to(is_be, &data[base + 16..])?,
+        id: load_commands::read_lc_str(data, lc, 24, is_be)?,
+    })
+}
+
+/*
+--fileset-index: the member as a standalone image. Its offsets are fileset relative, so rather
+than teaching every parser about a second base offset, the member's mach_header and load commands
+are copied over the fileset's own at offset 0. Everything they point at is still where it was.
+*/
+pub fn entry_view(data: &[u8], index: usize) -> Result<Vec<u8>, Box<dyn Error>> {
+    let whole = MachOSlice { offset: 0, size: None };
+    let (thin_header, load_commands) = load_commands::read_slice_load_commands(data, &whole)?;
+    if thin_header.header.filetype() != MH_FILESET {
+        return Err("--fileset-index needs an MH_FILESET binary (a kernel cache)".into());
+    }
+
+    let is_be = thin_header.kind.is_be();
+    let entries = load_commands
+        .iter()
+        .filter(|lc| is_fileset_entry(lc))
+        .map(|lc| parse_fileset_entry(data, lc, is_be))
+        .collect::<Result<Vec<_>, _>>()?;
+    let entry = entries
+        .get(index)
+        .ok_or_else(|| format!("fileset entry {} doesn't exist ({} entries)", index, entries.len()))?;
+
+    let member = header::read_thin_header(data, &MachOSlice { offset: entry.fileoff, size: None })?;
+    let start = entry.fileoff as usize;
+    let len = (member.header.size() + member.header.sizeofcmds() as u64) as usize;
+    let commands = data
+        .get(start..start + len)
+        .ok_or_else(|| format!("load commands of fileset entry {} ({}) run past EOF", index, entry.id))?;
+
+    let mut view = data.to_vec();
+    view[..len].copy_from_slice(commands);
+    Ok(view)
+}
+
+pub fn write_fileset_entries(w: &mut dyn Write, entries: &[FilesetEntryReport]) -> io::Result<()> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(w)?;
+    writeln!(w, "{}", "Fileset Entries".green().bold())?;
+    writeln!(w, "----------------------------------------")?;
+    writeln!(w, "{:<6} {:<20} {:<12} Id", "Index", "VM address", "File offset")?;
+    for (index, entry) in entries.iter().enumerate() {
+        writeln!(w, "{:<6} 0x{:016x} 0x{:<10x} {}", index, entry.vmaddr, entry.fileoff, entry.id.cyan())?;
+    }
+    writeln!(w, "----------------------------------------")?;
+    Ok(())
+}
+
+
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macho::constants::{CPU_TYPE_ARM64, LC_UUID, MH_CIGAM_64, MH_EXECUTE};
+
+    fn entry_command(vmaddr: u64, fileoff: u64, id: &[u8]) -> (Vec<u8>, LoadCommand) {
+        let cmd = LC_FILESET_ENTRY | LC_REQ_DYLD;
+        let cmdsize = (FILESET_ENTRY_COMMAND_SIZE as usize + id.len()).next_multiple_of(8) as u32;
+        let mut data = Vec::new();
+        data.extend_from_slice(&cmd.to_le_bytes());
+        data.extend_from_slice(&cmdsize.to_le_bytes());
+        data.extend_from_slice(&vmaddr.to_le_bytes());
+        data.extend_from_slice(&fileoff.to_le_bytes());
+        data.extend_from_slice(&FILESET_ENTRY_COMMAND_SIZE.to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(id);
+        data.resize(cmdsize as usize, 0);
+        (data, LoadCommand { cmd, cmdsize, offset: 0 })
+    }
+
+    #[test]
+    fn entry_fields_and_id_are_read() {
+        let (data, lc) = entry_command(0xffff_fe00_0700_4000, 0x4000, b"com.apple.kernel\0");
+        assert!(is_fileset_entry(&lc));
+        assert_eq!(parse_fileset_entry(&data, &lc, false).unwrap(), FilesetEntry {
+            vmaddr: 0xffff_fe00_0700_4000,
+            fileoff: 0x4000,
+            id: "com.apple.kernel".into(),
+        });
+    }
+
+    #[test]
+    fn short_or_truncated_commands_are_errors() {
+        let (data, lc) = entry_command(0, 0, b"com.apple.kernel\0");
+        assert!(parse_fileset_entry(&data, &LoadCommand { cmdsize: 24, ..lc }, false).is_err());
+        assert!(parse_fileset_entry(&data[..28], &lc, false).is_err());
+        // entry_id pointing at or past cmdsize
+        assert!(parse_fileset_entry(&data, &LoadCommand { cmdsize: FILESET_ENTRY_COMMAND_SIZE, ..lc }, false).is_err());
+    }
+
+    fn mach_header_64(filetype: u32, ncmds: u32, sizeofcmds: u32) -> Vec<u8> {
+        // little endian on disk, i.e. MH_CIGAM_64's byte order
+        let mut data = MH_CIGAM_64.to_vec();
+        for value in [CPU_TYPE_ARM64 as u32, 0, filetype, ncmds, sizeofcmds, 0, 0] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn entry_view_puts_the_member_header_first() {
+        let (lc, _) = entry_command(0xffff_fe00_0000_0000, 0x100, b"com.apple.kernel\0");
+        let mut data = mach_header_64(MH_FILESET, 1, lc.len() as u32);
+        data.extend_from_slice(&lc);
+        data.resize(0x100, 0);
+        // member with a single LC_UUID
+        let mut member = mach_header_64(MH_EXECUTE, 1, 24);
+        member.extend_from_slice(&LC_UUID.to_le_bytes());
+        member.extend_from_slice(&24u32.to_le_bytes());
+        member.extend_from_slice(&[0xab; 16]);
+        data.extend_from_slice(&member);
+
+        let view = entry_view(&data, 0).unwrap();
+        assert_eq!(view.len(), data.len());
+        assert_eq!(&view[..member.len()], &member[..]);
+        assert_eq!(&view[member.len()..], &data[member.len()..]);
+
+        assert!(entry_view(&data, 1).unwrap_err().to_string().contains("1 entries"));
+        data[12] = MH_EXECUTE as u8;
+        assert!(entry_view(&data, 0).unwrap_err().to_string().contains("MH_FILESET"));
+    }
+}
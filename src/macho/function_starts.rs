@@ -0,0 +1,130 @@
+// File Purpose: LC_FUNCTION_STARTS, the linker's list of where every function begins
+
+use std::io::{self, Write};
+use colored::Colorize;
+use crate::macho::load_commands::LinkEditDataCommand;
+use crate::macho::segments::ParsedSegment;
+use crate::macho::utils;
+use crate::reporting::function_starts::FunctionStartsReport;
+
+
+/*
+LC_FUNCTION_STARTS is a plain linkedit_data_command, its payload in __LINKEDIT is a ULEB128 stream:
+
+    dataoff (relative to the slice)
+    ┌────────┬────────┬────────┬─────┬──────┬───────────┐
+    │ delta0 │ delta1 │ delta2 │ ... │ 0x00 │  padding  │
+    └────────┴────────┴────────┴─────┴──────┴───────────┘
+    ◄──────────────────────── datasize ─────────────────►
+
+    function[0] = __TEXT vmaddr + delta0
+    function[n] = function[n - 1] + deltaN
+
+A zero delta ends the list, whatever follows is alignment padding up to datasize. Nothing promises
+the stream is well formed though, a ULEB that runs past datasize (or past the end of the file) just
+ends the list, we never read outside the command's own range.
+*/
+pub fn parse_function_starts(data: &[u8], linkedit: &LinkEditDataCommand, slice_offset: u64, text_vmaddr: u64) -> Vec<u64> {
+    let start = (slice_offset as usize).saturating_add(linkedit.dataoff as usize).min(data.len());
+    let end = start.saturating_add(linkedit.datasize as usize).min(data.len());
+    let stream = &data[start..end];
+
+    let mut starts = Vec::new();
+    let mut cursor = 0;
+    let mut addr = text_vmaddr;
+    while cursor < stream.len() {
+        let Ok(delta) = utils::read_uleb(stream, &mut cursor) else { break };
+        if delta == 0 {
+            break;
+        }
+        addr = addr.wrapping_add(delta);
+        starts.push(addr);
+    }
+    starts
+}
+
+// Deltas count from the start of __TEXT, object files don't name their one segment so fall back to 0
+pub fn text_vmaddr(segments: &[ParsedSegment]) -> u64 {
+    segments
+        .iter()
+        .find(|seg| utils::byte_array_to_string(&seg.segname) == "__TEXT")
+        .map(|seg| seg.vmaddr)
+        .unwrap_or(0)
+}
+
+pub fn build_function_starts_report(cpu_type: &str, cpu_subtype: &str, starts: &[u64]) -> FunctionStartsReport {
+    FunctionStartsReport {
+        cpu_type: cpu_type.to_string(),
+        cpu_subtype: cpu_subtype.to_string(),
+        count: starts.len(),
+        function_starts: starts.to_vec(),
+    }
+}
+
+pub fn write_function_starts(w: &mut dyn Write, report: &FunctionStartsReport) -> io::Result<()> {
+    writeln!(w)?;
+    writeln!(w, "{} ({})", "Function Starts".green().bold(), report.cpu_subtype)?;
+    writeln!(w, "----------------------------------------")?;
+
+    if report.function_starts.is_empty() {
+        writeln!(w, "No function starts (no LC_FUNCTION_STARTS, or an empty one)")?;
+        return Ok(());
+    }
+
+    writeln!(w, "{} {}", "Functions:".yellow().bold(), report.count)?;
+    for addr in &report.function_starts {
+        writeln!(w, "  {}", format!("0x{:016x}", addr).cyan())?;
+    }
+    Ok(())
+}
+
+
+/*
+============================
+          UNIT TESTS
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macho::constants::LC_FUNCTION_STARTS;
+
+    const TEXT_BASE: u64 = 0x1_0000_0000;
+
+    fn linkedit(dataoff: u32, datasize: u32) -> LinkEditDataCommand {
+        LinkEditDataCommand { cmd: LC_FUNCTION_STARTS, dataoff, datasize }
+    }
+
+    #[test]
+    fn deltas_accumulate_from_text_vmaddr() {
+        // 0x460 (two byte ULEB), then +0x20, +0x100, terminator, padding
+        let data = [0xe0, 0x08, 0x20, 0x80, 0x02, 0x00, 0x00, 0x00];
+        let starts = parse_function_starts(&data, &linkedit(0, data.len() as u32), 0, TEXT_BASE);
+
+        assert_eq!(starts, vec![TEXT_BASE + 0x460, TEXT_BASE + 0x480, TEXT_BASE + 0x580]);
+    }
+
+    #[test]
+    fn dataoff_is_relative_to_the_slice() {
+        let mut data = vec![0xffu8; 0x1000];
+        data.extend_from_slice(&[0x10, 0x10, 0x00, 0x00]);
+        let starts = parse_function_starts(&data, &linkedit(0, 4), 0x1000, 0);
+
+        assert_eq!(starts, vec![0x10, 0x20]);
+    }
+
+    #[test]
+    fn truncated_streams_stop_at_datasize() {
+        // The second ULEB is cut off by datasize, its continuation byte lives outside the command
+        let data = [0x10, 0x80, 0x01, 0x00];
+        assert_eq!(parse_function_starts(&data, &linkedit(0, 2), 0, 0), vec![0x10]);
+
+        // No terminator at all, the list just ends with the stream
+        assert_eq!(parse_function_starts(&data[..1], &linkedit(0, 1), 0, 0), vec![0x10]);
+
+        // datasize running off the end of the file is clamped, not read
+        assert_eq!(parse_function_starts(&data, &linkedit(2, 0x100), 0, 0), vec![0x1]);
+        assert!(parse_function_starts(&data, &linkedit(0x100, 4), 0, 0).is_empty());
+    }
+}
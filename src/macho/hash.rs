@@ -0,0 +1,120 @@
+// File Purpose: Hash an architecture slice on its own (what `lipo -thin` + shasum would give you)
+
+use std::error::Error;
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha384};
+use crate::macho::constants::{CS_HASHTYPE_SHA1, CS_HASHTYPE_SHA256, CS_HASHTYPE_SHA256_TRUNCATED, CS_HASHTYPE_SHA384};
+use crate::macho::header::MachOSlice;
+
+/*
+A fat binary is just a table of (offset, size) pairs pointing at complete thin Mach-Os:
+
+    file:  [fat_header][fat_arch...][pad][ slice 0 bytes ][pad][ slice 1 bytes ]
+                                         ^offset          ^offset+size
+
+Hashing exactly offset..offset+size gives the same digest as the thin file lipo would extract,
+so a single slice can be checked against a known-good thin build. A thin file is one slice
+covering the whole file.
+*/
+
+pub fn slice_bytes<'a>(data: &'a [u8], slice: &MachOSlice) -> Result<&'a [u8], Box<dyn Error>> {
+    let start = slice.offset as usize;
+    let end = match slice.size {
+        Some(size) => start.checked_add(size as usize).ok_or("slice size overflows")?,
+        None => data.len(),
+    };
+
+    if start > end || end > data.len() {
+        return Err(format!("slice 0x{:x}..0x{:x} is outside the file (0x{:x} bytes)", start, end, data.len()).into());
+    }
+
+    Ok(&data[start..end])
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn slice_sha256(data: &[u8], slice: &MachOSlice) -> Result<String, Box<dyn Error>> {
+    Ok(sha256_hex(slice_bytes(data, slice)?))
+}
+
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+
+// SHA-1, only because old CodeDirectories (hashType 1) still use it and every signed binary
+// built before ~2016 carries one
+pub fn sha1(bytes: &[u8]) -> [u8; 20] {
+    Sha1::digest(bytes).into()
+}
+
+// CodeDirectory hashType --> digest, None for a type we don't know
+pub fn cs_digest(hash_type: u8, bytes: &[u8]) -> Option<Vec<u8>> {
+    match hash_type {
+        CS_HASHTYPE_SHA1 => Some(sha1(bytes).to_vec()),
+        CS_HASHTYPE_SHA256 => Some(Sha256::digest(bytes).to_vec()),
+        CS_HASHTYPE_SHA256_TRUNCATED => Some(Sha256::digest(bytes)[..20].to_vec()),
+        CS_HASHTYPE_SHA384 => Some(Sha384::digest(bytes).to_vec()),
+        _ => None,
+    }
+}
+
+
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sha256_matches_known_vector() {
+        assert_eq!(sha256_hex(b"abc"), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    }
+
+    #[test]
+    fn slice_hash_only_covers_the_slice() {
+        let mut data = vec![0xffu8; 16];
+        data.extend_from_slice(b"abc");
+        data.extend_from_slice(&[0xee; 8]);
+
+        let slice = MachOSlice { offset: 16, size: Some(3) };
+        assert_eq!(slice_sha256(&data, &slice).unwrap(), sha256_hex(b"abc"));
+
+        // thin file --> whole file
+        let thin = MachOSlice { offset: 0, size: None };
+        assert_eq!(slice_sha256(&data, &thin).unwrap(), sha256_hex(&data));
+    }
+
+    #[test]
+    fn slice_past_end_of_file_is_rejected() {
+        let data = [0u8; 8];
+        assert!(slice_sha256(&data, &MachOSlice { offset: 4, size: Some(8) }).is_err());
+    }
+
+    #[test]
+    fn sha1_matches_known_vectors() {
+        assert_eq!(hex(&sha1(b"abc")), "a9993e364706816aba3e25717850c26c9cd0d89d");
+        assert_eq!(hex(&sha1(b"")), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+        // two blocks: 56 bytes of message pushes the length into a second block
+        assert_eq!(
+            hex(&sha1(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq")),
+            "84983e441c3bd26ebaae4aa1f95129e5e54670f1"
+        );
+    }
+
+    #[test]
+    fn cs_digest_follows_hash_type() {
+        assert_eq!(cs_digest(CS_HASHTYPE_SHA256, b"abc").unwrap().len(), 32);
+        assert_eq!(cs_digest(CS_HASHTYPE_SHA256_TRUNCATED, b"abc").unwrap(), cs_digest(CS_HASHTYPE_SHA256, b"abc").unwrap()[..20]);
+        assert_eq!(cs_digest(CS_HASHTYPE_SHA384, b"abc").unwrap().len(), 48);
+        assert_eq!(cs_digest(CS_HASHTYPE_SHA1, b"abc").unwrap(), sha1(b"abc"));
+        assert!(cs_digest(0x7f, b"abc").is_none());
+    }
+}
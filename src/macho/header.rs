@@ -1,6 +1,7 @@
 // File Purpose: "what kind of Mach-O file is this?"
-use std::error::Error;
+use std::io::{self, Write};
 use crate::macho::constants::*;
+use crate::macho::error::MoscopeError;
 use crate::macho::utils;
 use crate::macho::constants;
 use colored::Colorize;
@@ -29,11 +30,13 @@ Mach-O Header
 
 */
 
+#[derive(Debug, Clone, Copy)]
 pub struct MachOSlice {
     pub offset: u64, // Where this Mach-O binary begins
     pub size: Option<u64>, // how large is the Mach-O (only really important for fat)
 }
 
+#[derive(Debug)]
 pub struct MachOFlag {
     pub mask: u32,
     pub name: &'static str,
@@ -110,7 +113,48 @@ pub enum MachOHeader {
 }
 
 impl MachOHeader {
-    pub fn build_report(&self, _is_json: bool) -> MachHeaderReport {
+    // (cputype, cpusubtype), what every per-slice report starts from
+    pub fn cpu(&self) -> (i32, i32) {
+        match self {
+            MachOHeader::Header32(h) => (h.cputype, h.cpusubtype),
+            MachOHeader::Header64(h) => (h.cputype, h.cpusubtype),
+        }
+    }
+
+    pub fn filetype(&self) -> u32 {
+        match self {
+            MachOHeader::Header32(h) => h.filetype,
+            MachOHeader::Header64(h) => h.filetype,
+        }
+    }
+
+    pub fn flags(&self) -> u32 {
+        match self {
+            MachOHeader::Header32(h) => h.flags,
+            MachOHeader::Header64(h) => h.flags,
+        }
+    }
+
+    pub fn is_64(&self) -> bool {
+        matches!(self, MachOHeader::Header64(_))
+    }
+
+    pub fn sizeofcmds(&self) -> u32 {
+        match self {
+            MachOHeader::Header32(h) => h.sizeofcmds,
+            MachOHeader::Header64(h) => h.sizeofcmds,
+        }
+    }
+
+    // Load commands start right after this
+    pub fn size(&self) -> u64 {
+        match self {
+            MachOHeader::Header32(_) => std::mem::size_of::<MachHeader32>() as u64,
+            MachOHeader::Header64(_) => std::mem::size_of::<MachHeader64>() as u64,
+        }
+    }
+
+    pub fn build_report(&self) -> MachHeaderReport {
         match self {
             MachOHeader::Header32(h32) => MachHeaderReport {
                 magic: h32.magic,
@@ -168,15 +212,33 @@ impl MachOKind {
 
 
 
-pub fn print_header_summary(header: &MachOHeader) {
-    match header {
-        MachOHeader::Header32(h) => {
-            print_common_header(32, h.magic, h.cputype, h.cpusubtype, h.filetype, h.ncmds, h.sizeofcmds, h.flags,);
-        }
-        MachOHeader::Header64(h) => {
-            print_common_header(64, h.magic, h.cputype, h.cpusubtype, h.filetype, h.ncmds, h.sizeofcmds, h.flags,);
-        }
+pub fn write_header_summary(w: &mut dyn Write, header: &MachHeaderReport, uuid: Option<&str>, two_level_namespace: bool) -> io::Result<()> {
+    // The report keeps the raw magic, that's enough to tell mach_header from mach_header_64
+    let bits = if [MH_MAGIC, MH_CIGAM].map(u32::from_be_bytes).contains(&header.magic) { 32 } else { 64 };
+
+    writeln!(w)?;
+    writeln!(w, "{}", "Mach-O Header Summary".green().bold())?;
+    writeln!(w, "----------------------------------------")?;
+
+    writeln!(w, "{} 0x{:08x}", "  Magic        :".yellow().bold(), header.magic)?;
+    writeln!(w, "{} {} ({})", "  Architecture :".yellow().bold(), header.cpu_type, header.cpu_subtype)?;
+    writeln!(w, "{} {}-bit", "  Word size    :".yellow().bold(), bits)?;
+    writeln!(w, "{} {}", "  File type    :".yellow().bold(), header.file_type)?;
+    writeln!(w, "{} {}", "  Load cmds    :".yellow().bold(), header.ncmds)?;
+    writeln!(w, "{} {} bytes", "  Cmds size    :".yellow().bold(), header.sizeofcmds)?;
+    writeln!(w, "{} {}", "  Flags        :".yellow().bold(), header.flags.join(", "))?;
+    // Changes how every import reads, so it gets its own line even though TWOLEVEL is in Flags
+    if two_level_namespace {
+        writeln!(w, "{} two-level (imports bind to a specific dylib)", "  Namespace    :".yellow().bold())?;
+    } else {
+        writeln!(w, "{} flat (imports resolve against every loaded image)", "  Namespace    :".yellow().bold())?;
     }
+    if let Some(uuid) = uuid {
+        writeln!(w, "{} {}", "  UUID         :".yellow().bold(), uuid)?;
+    }
+    writeln!(w, "----------------------------------------")?;
+    writeln!(w)?;
+    Ok(())
 }
 
 fn parse_flags(flags: u32) -> Vec<String> {
@@ -192,49 +254,47 @@ fn parse_flags(flags: u32) -> Vec<String> {
     // .collect()                       --> consume iterator and turn it into a collection (which I believe Rust infers into our return type of Vec<&'static str>)
 }
 
-fn print_common_header(
-    bits: u32,
-    magic: u32,
-    cputype: i32,
-    cpusubtype: i32,
-    filetype: u32,
-    ncmds: u32,
-    sizeofcmds: u32,
-    flags: u32,
-) {
-    let named_flags = parse_flags(flags);
-    println!();
-    println!("{}", "Mach-O Header Summary".green().bold());
-    println!("----------------------------------------");
-
-    println!("{} 0x{:08x}", "  Magic        :".yellow().bold(), magic);
-
-    println!(
-        "{} {} ({})",
-        "  Architecture :".yellow().bold(),
-        constants::cpu_type_name(cputype),
-        constants::cpu_subtype_name(cputype, cpusubtype),
-    );
-
-    println!("{} {}-bit", "  Word size    :".yellow().bold(), bits);
-    println!("{} {}", "  File type    :".yellow().bold(), constants::filetype_name(filetype));
-    println!("{} {}", "  Load cmds    :".yellow().bold(), ncmds);
-    println!("{} {} bytes", "  Cmds size    :".yellow().bold(), sizeofcmds);
-    println!("{} {}", "  Flags        :".yellow().bold(), named_flags.join(", "));
-    println!("----------------------------------------");
-    println!();
-}
-
+/*
+The magic alone only tells us which way round to read things. If we guessed wrong
+(or the file just happens to start with a Mach-O looking magic), ncmds/sizeofcmds come
+out as nonsense like 0x19000000. Catch that here instead of walking garbage load commands:
+    - a real image always has at least one load command
+    - every load command is at least 8 bytes (cmd + cmdsize)
+    - the load command area has to fit in the slice right after the header
+*/
+fn check_header_plausibility(ncmds: u32, sizeofcmds: u32, header_size: usize, data: &[u8], slice: &MachOSlice) -> Result<(), MoscopeError> {
+    let in_file = data.len().checked_sub(slice.offset as usize).ok_or("slice starts past the end of the file")?;
+    let available = match slice.size {
+        Some(size) => (size as usize).min(in_file),
+        None => in_file,
+    };
 
+    let problem = if ncmds == 0 || sizeofcmds == 0 {
+        Some("header declares no load commands")
+    } else if (ncmds as u64) * 8 > sizeofcmds as u64 {
+        Some("ncmds is too large for sizeofcmds")
+    } else if header_size as u64 + sizeofcmds as u64 > available as u64 {
+        Some("sizeofcmds runs past the end of the image")
+    } else {
+        None
+    };
 
+    match problem {
+        Some(reason) => Err(format!(
+            "implausible Mach-O header (ncmds={}, sizeofcmds={}): {}, likely an endianness or file format misdetection",
+            ncmds, sizeofcmds, reason
+        ).into()),
+        None => Ok(()),
+    }
+}
 
 
-pub fn read_thin_header(data: &[u8], slice: &MachOSlice) -> Result<ParsedMachOHeader, Box<dyn Error>> {
+pub fn read_thin_header(data: &[u8], slice: &MachOSlice) -> Result<ParsedMachOHeader, MoscopeError> {
 
     let base = slice.offset as usize;
 
     if base + constants::MACH_HEADER32_SIZE /* base + 28 */ > data.len() { 
-        return Err("File too small for Mach-O header".into());
+        return Err(MoscopeError::TooSmall { needed: base + constants::MACH_HEADER32_SIZE, available: data.len() });
     }
 
     fn classify_macho_magic(bytes: [u8; 4]) -> Option<MachOKind> {
@@ -253,34 +313,36 @@ pub fn read_thin_header(data: &[u8], slice: &MachOSlice) -> Result<ParsedMachOHe
 
     let kind:MachOKind = match classify_macho_magic(raw_magic_bytes) {
         Some(kind) => kind,
-        None => return Err("Not a valid Mach-O binary".into()),
+        None => return Err(MoscopeError::BadMagic(u32::from_be_bytes(raw_magic_bytes))),
     };
 
     if kind.is_64() {
         // Mach-O 64 Bit
         // bounds check
         if base + constants::MACH_HEADER64_SIZE > data.len() {
-            return Err("File too small for Mach-O header 64-bit".into());
+            return Err(MoscopeError::TooSmall { needed: base + constants::MACH_HEADER64_SIZE, available: data.len() });
         } 
 
         let header64 = MachHeader64 {
-            magic: utils::bytes_to(kind.is_be(), &data[base + 0..])?,
+            magic: utils::bytes_to(kind.is_be(), &data[base..])?,
             cputype: utils::bytes_to(kind.is_be(), &data[base + 4..])?,
             cpusubtype: utils::bytes_to(kind.is_be(), &data[base + 8..])?,
             filetype: utils::bytes_to(kind.is_be(), &data[base + 12..])?,
             ncmds: utils::bytes_to(kind.is_be(), &data[base + 16..])?,
             sizeofcmds: utils::bytes_to(kind.is_be(), &data[base + 20..])?,
             flags: utils::bytes_to(kind.is_be(), &data[base + 24..])?,
-            reserved: utils::bytes_to(kind.is_be(), &data[base + 38..])?,
+            reserved: utils::bytes_to(kind.is_be(), &data[base + 28..])?,
         };
 
+        check_header_plausibility(header64.ncmds, header64.sizeofcmds, constants::MACH_HEADER64_SIZE, data, slice)?;
+
         let header = MachOHeader::Header64(header64);
         //print_header_summary(&header);
 
         Ok(ParsedMachOHeader { kind, header })
     }    else {
         let header32 = MachHeader32 {
-            magic: utils::bytes_to(kind.is_be(), &data[base + 0..])?,
+            magic: utils::bytes_to(kind.is_be(), &data[base..])?,
             cputype: utils::bytes_to(kind.is_be(), &data[base + 4..])?,
             cpusubtype: utils::bytes_to(kind.is_be(), &data[base + 8..])?,
             filetype: utils::bytes_to(kind.is_be(), &data[base + 12..])?,
@@ -289,8 +351,87 @@ pub fn read_thin_header(data: &[u8], slice: &MachOSlice) -> Result<ParsedMachOHe
             flags: utils::bytes_to(kind.is_be(), &data[base + 24..])?,
         };
 
+        check_header_plausibility(header32.ncmds, header32.sizeofcmds, constants::MACH_HEADER32_SIZE, data, slice)?;
+
         let header = MachOHeader::Header32(header32);
         //print_header_summary(&header);
         Ok(ParsedMachOHeader { kind, header })
     }
 }
+
+
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn thin_header_64(ncmds: u32, sizeofcmds: u32, total_len: usize) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&constants::MH_CIGAM_64);
+        for value in [constants::CPU_TYPE_ARM64 as u32, 0, constants::MH_EXECUTE, ncmds, sizeofcmds, 0, 0] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.resize(total_len, 0);
+        data
+    }
+
+    #[test]
+    fn plausible_header_is_accepted() {
+        let data = thin_header_64(2, 48, 32 + 48);
+        let slice = MachOSlice { offset: 0, size: None };
+
+        assert!(read_thin_header(&data, &slice).is_ok());
+    }
+
+    #[test]
+    fn swapped_counts_are_reported_as_misdetection() {
+        // ncmds/sizeofcmds written big endian in a little endian file, the classic wrong-endian read
+        let data = thin_header_64(u32::from_be(18), u32::from_be(1184), 4096);
+        let slice = MachOSlice { offset: 0, size: None };
+
+        let err = read_thin_header(&data, &slice).unwrap_err().to_string();
+        assert!(err.contains("misdetection"), "unexpected error: {}", err);
+    }
+
+    #[test]
+    fn zero_load_commands_is_rejected() {
+        let data = thin_header_64(0, 0, 64);
+        let slice = MachOSlice { offset: 0, size: None };
+
+        assert!(read_thin_header(&data, &slice).is_err());
+    }
+
+    #[test]
+    fn sizeofcmds_is_checked_against_slice_size() {
+        // fits in the file, but not in the slice the fat table gave us
+        let data = thin_header_64(1, 0x100, 0x1000);
+        let slice = MachOSlice { offset: 0, size: Some(0x80) };
+
+        assert!(read_thin_header(&data, &slice).is_err());
+    }
+
+    #[test]
+    fn slice_past_the_end_is_an_error_not_an_underflow() {
+        let data = thin_header_64(1, 0x20, 0x40);
+        let slice = MachOSlice { offset: 0x1000, size: Some(0x80) };
+
+        assert!(check_header_plausibility(1, 0x20, constants::MACH_HEADER64_SIZE, &data, &slice).is_err());
+    }
+
+    #[test]
+    fn failures_are_typed() {
+        let slice = MachOSlice { offset: 0, size: None };
+
+        let err = read_thin_header(&[0x7f, b'E', b'L', b'F', 0, 0, 0, 0], &slice).unwrap_err();
+        assert_eq!(err, MoscopeError::TooSmall { needed: constants::MACH_HEADER32_SIZE, available: 8 });
+
+        let mut elf = vec![0u8; 64];
+        elf[..4].copy_from_slice(&[0x7f, b'E', b'L', b'F']);
+        assert_eq!(read_thin_header(&elf, &slice).unwrap_err(), MoscopeError::BadMagic(0x7f454c46));
+    }
+}
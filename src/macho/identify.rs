@@ -0,0 +1,95 @@
+// File Purpose: One line fingerprint per slice for logs / dedup (--identify)
+
+use crate::macho::build_version::BuildVersion;
+use crate::macho::constants::{arch_name, filetype_short_name};
+use crate::macho::uuid::ParsedUuid;
+use crate::reporting::identify::IdentityReport;
+
+/*
+Format is fixed, scripts are expected to split on spaces and then on '=':
+
+    <arch> <filetype> UUID=<uuid> platform=<platform> minos=<x.y.z> sdk=<x.y.z>
+
+    arm64e MH_EXECUTE UUID=8D953C15-7CD8-3300-A26E-F633BD9CD546 platform=macOS minos=14.0.0 sdk=14.2.0
+
+Every field is always present, a missing load command is written as '-' so the
+column count never changes. None of the values can contain a space.
+*/
+pub const MISSING: &str = "-";
+
+pub fn build_identity_report(cputype: i32, cpusubtype: i32, filetype: u32, uuid: Option<&ParsedUuid>, build_version: Option<&BuildVersion>) -> IdentityReport {
+    let arch = arch_name(cputype, cpusubtype).to_string();
+    let filetype = filetype_short_name(filetype).to_string();
+    let uuid = uuid.map(|u| u.to_canonical());
+    let platform = build_version.map(|bv| bv.platform.clone());
+    let minos = build_version.map(|bv| bv.minos.clone());
+    let sdk = build_version.map(|bv| bv.sdk.clone());
+
+    let field = |value: &Option<String>| value.clone().unwrap_or_else(|| MISSING.to_string());
+    let identity = format!(
+        "{} {} UUID={} platform={} minos={} sdk={}",
+        arch,
+        filetype,
+        field(&uuid),
+        field(&platform),
+        field(&minos),
+        field(&sdk),
+    );
+
+    IdentityReport { arch, filetype, uuid, platform, minos, sdk, identity }
+}
+
+// --filetype accepts the bare MH_* name or just its suffix, any case (dylib == MH_DYLIB)
+pub fn filetype_matches(filetype: &str, wanted: &str) -> bool {
+    let wanted = wanted.trim().to_ascii_uppercase();
+    let wanted = wanted.strip_prefix("MH_").unwrap_or(&wanted);
+    filetype.strip_prefix("MH_").unwrap_or(filetype) == wanted
+}
+
+
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macho::constants::*;
+
+    #[test]
+    fn identity_has_fixed_field_order() {
+        let uuid = ParsedUuid { bytes: [0xAB; 16] };
+        let bv = BuildVersion {
+            platform: "macOS".to_string(),
+            minos: "14.0.0".to_string(),
+            sdk: "14.2.0".to_string(),
+            tools: Vec::new(),
+        };
+
+        let report = build_identity_report(CPU_TYPE_ARM64, CPU_SUBTYPE_ARM64E | CPU_SUBTYPE_PTRAUTH_ABI, MH_EXECUTE, Some(&uuid), Some(&bv));
+
+        assert_eq!(
+            report.identity,
+            "arm64e MH_EXECUTE UUID=ABABABAB-ABAB-ABAB-ABAB-ABABABABABAB platform=macOS minos=14.0.0 sdk=14.2.0"
+        );
+    }
+
+    #[test]
+    fn missing_fields_keep_their_column() {
+        let report = build_identity_report(CPU_TYPE_X86_64, CPU_SUBTYPE_X86_ALL, MH_DYLIB, None, None);
+
+        assert_eq!(report.identity, "x86_64 MH_DYLIB UUID=- platform=- minos=- sdk=-");
+        assert_eq!(report.identity.split(' ').count(), 6);
+    }
+
+    #[test]
+    fn filetype_filter_takes_short_or_full_names() {
+        assert!(filetype_matches("MH_DYLIB", "MH_DYLIB"));
+        assert!(filetype_matches("MH_DYLIB", "dylib"));
+        assert!(filetype_matches("MH_KEXT_BUNDLE", "kext_bundle"));
+        assert!(!filetype_matches("MH_DYLIB_STUB", "dylib"));
+        assert!(!filetype_matches("MH_EXECUTE", "bundle"));
+    }
+}
@@ -0,0 +1,227 @@
+// File Purpose: static initializers (__mod_init_func), the functions dyld runs before main()
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+use colored::Colorize;
+use crate::macho::constants::*;
+use crate::macho::memory_image::{MachOMemoryImage, PointerDecoder, StoredPointer};
+use crate::macho::sections::{ParsedSection, SectionKind};
+use crate::macho::segments::ParsedSegment;
+use crate::macho::symtab::{ParsedSymbol, SymbolKind};
+use crate::macho::utils;
+use crate::reporting::initializers::{InitializerReport, InitializersReport};
+
+
+/*
+S_MOD_INIT_FUNC_POINTERS is just an array of pointer sized function addresses:
+
+    __mod_init_func
+    ┌──────────────┬──────────────┬─────
+    │ 0x100003f00  │ 0x100003f40  │ ...       dyld calls each one, in order, before main()
+    └──────────────┴──────────────┴─────
+
+Where the section lives moved over time:
+    - older ld64            __DATA,__mod_init_func
+    - ld64 with __DATA_CONST __DATA_CONST,__mod_init_func   (read only after fixups)
+So we go by the section TYPE and never by the segment name, whatever segment holds it gets read.
+(__TEXT,__init_offsets is the newer 32-bit offset flavour, same SectionKind but a different
+type, not handled here)
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedInitializer {
+    pub segname: [u8; 16],
+    pub sectname: [u8; 16],
+    pub slot: u64,
+    pub target: StoredPointer,
+    pub symbol: Option<String>,
+}
+
+impl ParsedInitializer {
+    pub fn build_report(&self) -> InitializerReport {
+        let target = match self.target {
+            StoredPointer::Address(addr) => Some(addr),
+            _ => None,
+        };
+
+        InitializerReport {
+            segment: utils::byte_array_to_string(&self.segname),
+            section: utils::byte_array_to_string(&self.sectname),
+            slot: self.slot,
+            slot_hex: format!("{:#x}", self.slot),
+            target,
+            target_hex: target.map(|t| format!("{:#x}", t)),
+            symbol: self.symbol.clone(),
+            bound: self.target == StoredPointer::Bound,
+        }
+    }
+}
+
+pub fn is_initializer_section(section: &ParsedSection) -> bool {
+    // kind alone would also pick up __mod_term_func and __init_offsets
+    section.kind == SectionKind::Init && section.flags & SECTION_TYPE == S_MOD_INIT_FUNC_POINTERS
+}
+
+pub fn parse_initializers(
+    image: &MachOMemoryImage,
+    segments: &[ParsedSegment],
+    cputype: i32,
+    cpusubtype: i32,
+    symbols: &[ParsedSymbol],
+) -> Vec<ParsedInitializer> {
+    let pointers = PointerDecoder::new(segments, cputype, cpusubtype);
+    let ptr_size = if pointers.is_64() { 8 } else { 4 };
+
+    let names: HashMap<u64, &str> = symbols
+        .iter()
+        .filter(|s| !s.is_debug && matches!(s.kind, SymbolKind::Section))
+        .map(|s| (s.addr, s.name.as_str()))
+        .collect();
+
+    let mut initializers = Vec::new();
+    for section in segments.iter().flat_map(|seg| seg.sections.iter()).filter(|s| is_initializer_section(s)) {
+        for i in 0..(section.size / ptr_size) {
+            let slot = section.addr + i * ptr_size;
+            let target = match image.read_ptr(slot, pointers.is_64()) {
+                Some(raw) => pointers.decode(raw),
+                None => StoredPointer::Invalid,
+            };
+            let symbol = match target {
+                StoredPointer::Address(addr) => names.get(&addr).map(|n| n.to_string()),
+                _ => None,
+            };
+
+            initializers.push(ParsedInitializer {
+                segname: section.segname,
+                sectname: section.sectname,
+                slot,
+                target,
+                symbol,
+            });
+        }
+    }
+
+    initializers
+}
+
+pub fn build_initializers_report(cpu_type: &str, cpu_subtype: &str, initializers: &[ParsedInitializer]) -> InitializersReport {
+    InitializersReport {
+        cpu_type: cpu_type.to_string(),
+        cpu_subtype: cpu_subtype.to_string(),
+        initializers: initializers.iter().map(|i| i.build_report()).collect(),
+    }
+}
+
+pub fn write_initializers_summary(w: &mut dyn Write, report: &InitializersReport) -> io::Result<()> {
+    writeln!(w)?;
+    writeln!(w, "{} ({})", "Initializers".green().bold(), report.cpu_subtype)?;
+    writeln!(w, "----------------------------------------")?;
+
+    if report.initializers.is_empty() {
+        writeln!(w, "No initializers (no S_MOD_INIT_FUNC_POINTERS section)")?;
+        return Ok(());
+    }
+
+    for init in &report.initializers {
+        let target = match (&init.target_hex, init.bound) {
+            (Some(hex), _) => hex.cyan().to_string(),
+            (None, true) => "<bound>".yellow().to_string(),
+            (None, false) => "<unreadable>".red().to_string(),
+        };
+        writeln!(
+            w,
+            "  {} {} {} {}",
+            format!("{},{}", init.segment, init.section).dimmed(),
+            init.slot_hex.dimmed(),
+            target,
+            init.symbol.as_deref().unwrap_or("")
+        )?;
+    }
+    Ok(())
+}
+
+
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macho::header::{MachOSlice, read_thin_header, MachOHeader};
+    use crate::macho::load_commands::read_load_commands;
+    use crate::macho::segments::parse_segment_64;
+    use crate::macho::symtab::{SymbolIter, SymtabCommand};
+    use crate::test_util::{MachOBuilder, SegmentSpec};
+
+    const TEXT_BASE: u64 = 0x1_0000_0000;
+    const DATA_CONST_BASE: u64 = 0x1_0000_4000;
+
+    fn initializers_of(data: &[u8]) -> Vec<ParsedInitializer> {
+        let macho = read_thin_header(data, &MachOSlice { offset: 0, size: None }).unwrap();
+        let MachOHeader::Header64(h) = &macho.header else { panic!("expected a 64-bit header") };
+        let lcs = read_load_commands(data, 32, h.ncmds, 64, false).unwrap();
+
+        let segments: Vec<ParsedSegment> = lcs
+            .iter()
+            .filter(|lc| lc.cmd == LC_SEGMENT_64)
+            .map(|lc| parse_segment_64(data, lc.offset as usize, false).unwrap())
+            .collect();
+
+        let mut symbols = Vec::new();
+        if let Some(lc) = lcs.iter().find(|lc| lc.cmd == LC_SYMTAB) {
+            let field = |at: usize| -> u32 { utils::bytes_to(false, &data[lc.offset as usize + at..]).unwrap() };
+            let symtab = SymtabCommand { cmd: field(0), cmdsize: field(4), symoff: field(8), nsyms: field(12), stroff: field(16), strsize: field(20) };
+            symbols = SymbolIter::new(data, &symtab, 0, true, false).map(|s| s.unwrap()).collect();
+        }
+
+        let image = MachOMemoryImage::new(&segments, data, 0);
+        parse_initializers(&image, &segments, h.cputype, h.cpusubtype, &symbols)
+    }
+
+    fn binary(init_pointers: &[u64]) -> Vec<u8> {
+        let pointers: Vec<u8> = init_pointers.iter().flat_map(|p| p.to_le_bytes()).collect();
+
+        MachOBuilder::new_64(CPU_TYPE_X86_64, 3)
+            .segment(SegmentSpec::new("__TEXT", TEXT_BASE).section("__text", S_ATTR_PURE_INSTRUCTIONS, &[0xC3; 8]))
+            .segment(
+                SegmentSpec::new("__DATA_CONST", DATA_CONST_BASE)
+                    .section("__got", S_NON_LAZY_SYMBOL_POINTERS, &[0; 8])
+                    .section("__mod_init_func", S_MOD_INIT_FUNC_POINTERS, &pointers),
+            )
+            .symbol("__GLOBAL__sub_I_main.cpp", N_SECT, 1, TEXT_BASE)
+            .symbol("_setup", N_SECT | N_EXT, 1, TEXT_BASE + 4)
+            .build()
+    }
+
+    #[test]
+    fn reads_mod_init_func_from_data_const() {
+        let inits = initializers_of(&binary(&[TEXT_BASE, TEXT_BASE + 4]));
+
+        assert_eq!(inits.len(), 2);
+        assert_eq!(inits[0].segname, SEG_DATA_CONST);
+        assert_eq!(inits[0].slot, DATA_CONST_BASE + 8); // after __got
+        assert_eq!(inits[0].target, StoredPointer::Address(TEXT_BASE));
+        assert_eq!(inits[0].symbol.as_deref(), Some("__GLOBAL__sub_I_main.cpp"));
+        assert_eq!(inits[1].symbol.as_deref(), Some("_setup"));
+    }
+
+    #[test]
+    fn chained_bind_is_reported_as_bound() {
+        let inits = initializers_of(&binary(&[1 << 63]));
+
+        assert_eq!(inits[0].target, StoredPointer::Bound);
+        assert!(inits[0].build_report().bound);
+        assert_eq!(inits[0].build_report().target, None);
+    }
+
+    #[test]
+    fn term_funcs_are_not_initializers() {
+        let data = MachOBuilder::new_64(CPU_TYPE_X86_64, 3)
+            .segment(SegmentSpec::new("__DATA", DATA_CONST_BASE).section("__mod_term_func", S_MOD_TERM_FUNC_POINTERS, &TEXT_BASE.to_le_bytes()))
+            .build();
+
+        assert!(initializers_of(&data).is_empty());
+    }
+}
@@ -0,0 +1,197 @@
+// File Purpose: Get the raw bytes to analyze, from a file or from stdin (`-`)
+
+use std::error::Error;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use crate::macho::compression;
+
+pub const STDIN_PATH: &str = "-";
+
+pub fn is_stdin(path: &Path) -> bool {
+    path.as_os_str() == STDIN_PATH
+}
+
+/*
+Reading a pipe we don't know the final size up front, so read_to_end keeps doubling the buffer
+(and copying everything read so far) as it grows. For a multi-hundred MB binary that's a lot of
+wasted copies. --expected-size lets the caller say how big it'll be so we allocate once.
+It's only a hint: a wrong value just means a few more reallocations, and if the allocation
+itself fails we fall back to growing normally instead of aborting.
+*/
+pub fn read_all<R: Read>(mut reader: R, expected_size: Option<usize>) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut data = Vec::new();
+    if let Some(size) = expected_size {
+        let _ = data.try_reserve_exact(size);
+    }
+
+    reader.read_to_end(&mut data)?;
+    Ok(data)
+}
+
+pub fn read_input(path: &Path, expected_size: Option<usize>) -> Result<Vec<u8>, Box<dyn Error>> {
+    let data = if is_stdin(path) {
+        read_all(std::io::stdin().lock(), expected_size).map_err(|e| format!("failed to read stdin: {}", e))?
+    } else {
+        std::fs::read(path).map_err(|e| format!("failed to read '{}': {}", path.display(), e))?
+    };
+
+    if data.is_empty() {
+        let source = if is_stdin(path) { "stdin".to_string() } else { format!("'{}'", path.display()) };
+        return Err(format!("no input: {} is empty", source).into());
+    }
+
+    Ok(data)
+}
+
+// Called after decompression, so a gzip'd binary on stdin still gets through
+pub fn validate_magic(data: &[u8]) -> Result<(), Box<dyn Error>> {
+    if compression::has_macho_or_fat_magic(data) {
+        return Ok(());
+    }
+
+    let shown: Vec<String> = data.iter().take(4).map(|b| format!("{:02x}", b)).collect();
+    Err(format!("input is not a Mach-O or fat binary (starts with {})", shown.join(" ")).into())
+}
+
+
+/*
+--recursive: every Mach-O / fat file under a directory, depth counted like walkdir:
+    root/a.dylib            depth 1
+    root/sub/b.dylib        depth 2
+--max-depth 1 is "just this directory", None walks everything.
+Symlinks are never followed (framework bundles are full of them, /System loops back on itself)
+and directories we can't read are skipped, a scan of /System shouldn't die on the first EPERM.
+Only the first few bytes of each file get read here. A gzip/xz file only has its first 4 bytes
+unwrapped (that needs the `compression` feature), so a .gz that isn't a Mach-O is left out
+without decompressing all of it. Sorted so two scans diff cleanly.
+*/
+pub fn find_macho_files(root: &Path, max_depth: Option<usize>) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    if !root.is_dir() {
+        return Err(format!("'{}' is not a directory", root.display()).into());
+    }
+
+    let mut found = Vec::new();
+    let mut pending = vec![(root.to_path_buf(), 1usize)];
+
+    while let Some((dir, depth)) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            let path = entry.path();
+
+            if file_type.is_dir() {
+                if max_depth.is_none_or(|max| depth < max) {
+                    pending.push((path, depth + 1));
+                }
+            } else if file_type.is_file() && is_scan_candidate(&path) {
+                found.push(path);
+            }
+        }
+    }
+
+    found.sort();
+    Ok(found)
+}
+
+// Mach-O / fat magic, or a gzip / xz stream that unwraps to one (the longest magic, xz's, is 6 bytes)
+fn is_scan_candidate(path: &Path) -> bool {
+    let mut magic = Vec::with_capacity(compression::XZ_MAGIC.len());
+    let Ok(file) = File::open(path) else {
+        return false;
+    };
+    if file.take(compression::XZ_MAGIC.len() as u64).read_to_end(&mut magic).is_err() {
+        return false;
+    }
+    if compression::has_macho_or_fat_magic(&magic) {
+        return true;
+    }
+
+    // only the first 4 bytes get unwrapped, a compressed man page or log is skipped without a word
+    match compression::detect_compression(&magic) {
+        Some(kind) => File::open(path)
+            .ok()
+            .and_then(|file| compression::decompressed_prefix(file, kind, 4))
+            .is_some_and(|prefix| compression::has_macho_or_fat_magic(&prefix)),
+        None => false,
+    }
+}
+
+
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macho::constants::MH_CIGAM_64;
+
+    #[test]
+    fn read_all_honors_size_hint() {
+        let input = vec![0x42u8; 4096];
+
+        let data = read_all(&input[..], Some(4096)).unwrap();
+        assert_eq!(data, input);
+        assert!(data.capacity() >= 4096);
+
+        // a hint that's too small (or absurdly large) is harmless
+        assert_eq!(read_all(&input[..], Some(1)).unwrap().len(), 4096);
+        assert_eq!(read_all(&input[..], Some(usize::MAX)).unwrap().len(), 4096);
+    }
+
+    #[test]
+    fn validate_magic_accepts_macho_and_rejects_others() {
+        let mut macho = MH_CIGAM_64.to_vec();
+        macho.extend_from_slice(&[0; 28]);
+        assert!(validate_magic(&macho).is_ok());
+
+        let err = validate_magic(b"\x7fELF....").unwrap_err().to_string();
+        assert!(err.contains("7f 45 4c 46"), "unexpected error: {}", err);
+    }
+
+    #[test]
+    fn stdin_path_is_a_single_dash() {
+        assert!(is_stdin(Path::new("-")));
+        assert!(!is_stdin(Path::new("./-")));
+    }
+
+    #[test]
+    fn find_macho_files_honors_max_depth() {
+        let root = std::env::temp_dir().join(format!("moscope-walk-{}", std::process::id()));
+        let nested = root.join("a").join("b");
+        std::fs::create_dir_all(&nested).unwrap();
+
+        let macho = [MH_CIGAM_64.as_slice(), &[0; 28]].concat();
+        std::fs::write(root.join("top"), &macho).unwrap();
+        std::fs::write(root.join("a").join("mid"), &macho).unwrap();
+        std::fs::write(nested.join("deep"), &macho).unwrap();
+        std::fs::write(root.join("a").join("notes.txt"), b"not a binary").unwrap();
+        std::fs::write(root.join("tiny"), b"\xcf").unwrap();
+        // compression magic alone isn't enough, these don't unwrap to anything
+        std::fs::write(root.join("a").join("packed.gz"), [compression::GZIP_MAGIC.as_slice(), &[0x08, 0x00]].concat()).unwrap();
+        std::fs::write(nested.join("packed.xz"), [compression::XZ_MAGIC.as_slice(), &[0x00, 0x04]].concat()).unwrap();
+
+        let names = |max_depth| -> Vec<String> {
+            find_macho_files(&root, max_depth)
+                .unwrap()
+                .iter()
+                .map(|p| p.strip_prefix(&root).unwrap().to_string_lossy().replace('\\', "/"))
+                .collect()
+        };
+
+        assert_eq!(names(None), ["a/b/deep", "a/mid", "top"]);
+        assert_eq!(names(Some(2)), ["a/mid", "top"]);
+        assert_eq!(names(Some(1)), ["top"]);
+        assert!(find_macho_files(&root.join("top"), None).is_err());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -1,9 +1,14 @@
 // File Purpose: "What load commands are present in a given binary?"
+use crate::macho::build_version::decode_version;
 use crate::macho::constants::*;
+use crate::macho::diagnostics::Diagnostic;
+use crate::macho::error::MoscopeError;
+use crate::macho::header::{self, MachHeader32, MachHeader64, MachOHeader, MachOSlice, ParsedMachOHeader};
+use crate::macho::segments::ParsedSegment;
 use crate::macho::utils;
-use std::error::Error;
+use std::io::{self, Write};
 use colored::Colorize;
-use crate::reporting::load_commands::LoadCommandReport;
+use crate::reporting::load_commands::{LinkEditDataReport, LinkEditOverviewReport, LoadCommandExportEntry, LoadCommandMapReport, LoadCommandRangeReport, LoadCommandReport, MinVersionReport, SymsegReport};
 
 
 
@@ -23,11 +28,12 @@ pub struct LoadCommand {
 }
 
 impl LoadCommand {
-    pub fn build_report(&self, _is_json: bool) -> LoadCommandReport {
+    pub fn build_report(&self) -> LoadCommandReport {
         LoadCommandReport {
             command: load_command_name(self.cmd).to_string(),
             cmd: self.cmd,
             size: self.cmdsize,
+            body: None,
         }
     }
 }
@@ -101,36 +107,446 @@ pub fn load_command_name(cmd: u32) -> &'static str {
         LC_FUNCTION_VARIANTS          => "LC_FUNCTION_VARIANTS",
         LC_FUNCTION_VARIANT_FIXED     => "LC_FUNCTION_VARIANT_FIXED",
         LC_TARGET_TRIPLE              => "LC_TARGET_TRIPLE",
-        _                             => "UNKNOWN_LOAD_COMMAND",
+        _                             => UNKNOWN_LOAD_COMMAND,
     }
 }
 
+pub const UNKNOWN_LOAD_COMMAND: &str = "UNKNOWN_LOAD_COMMAND";
 
+// --dump-unknown-lc: the raw body of a command we have no parser for (newer than moscope, or undocumented)
+// None for known commands and for a body that runs past the data
+pub fn unknown_load_command_body(data: &[u8], lc: &LoadCommand) -> Option<String> {
+    if load_command_name(lc.cmd) != UNKNOWN_LOAD_COMMAND {
+        return None;
+    }
+    let start = lc.offset as usize + 8;
+    let end = lc.offset as usize + lc.cmdsize as usize;
+    data.get(start..end).map(utils::hex_string)
+}
 
-pub fn print_load_commands(load_commands: &Vec<LoadCommand>) {
-    if load_commands.is_empty() {
-        return;
+
+
+/*
+linkedit_data_command memory layout (from loader.h)
++-----------------------------+
+| cmd (u32)                   |  LC_CODE_SIGNATURE, LC_FUNCTION_STARTS, LC_ATOM_INFO, ...
+| cmdsize (u32)               |  always 16
+| dataoff (u32)               |  file offset of the blob in __LINKEDIT (slice relative)
+| datasize (u32)              |  size of the blob
++-----------------------------+
+
+A bunch of commands share this exact layout and only differ in what the blob means,
+so they all go through parse_linkedit_data instead of each feature re-reading the same 8 bytes.
+*/
+pub const LINKEDIT_DATA_COMMAND_SIZE: u32 = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LinkEditDataCommand {
+    pub cmd: u32,
+    pub dataoff: u32,
+    pub datasize: u32,
+}
+
+impl LinkEditDataCommand {
+    pub fn build_report(&self) -> LinkEditDataReport {
+        LinkEditDataReport {
+            command: load_command_name(self.cmd).to_string(),
+            dataoff: self.dataoff,
+            datasize: self.datasize,
+        }
     }
-    println!();
-    println!("{} {}", "Load Commands Found: ".green().bold(), load_commands.len());
-    println!("----------------------------------------");
-    for lc in load_commands {
-        println!(" - {:<30} cmd=0x{:08x} size={}", load_command_name(lc.cmd), lc.cmd, lc.cmdsize);
+}
+
+/*
+An lc_str is a u32 offset from the start of its load command, the NUL terminated string sits
+somewhere after the fixed fields and is padded out to cmdsize:
+    dylib_command       name at +8
+    rpath_command       path at +8
+    sub_*_command       name at +8
+field_offset is where that u32 lives, the string itself has to end before cmdsize does.
+*/
+pub fn read_lc_str(data: &[u8], lc: &LoadCommand, field_offset: usize, is_be: bool) -> Result<String, MoscopeError> {
+    let base = lc.offset as usize;
+    let end = base + lc.cmdsize as usize;
+    if field_offset + 4 > lc.cmdsize as usize {
+        return Err(MoscopeError::InvalidCmdSize { cmd: lc.cmd, cmdsize: lc.cmdsize });
+    }
+    if end > data.len() {
+        return Err(MoscopeError::OutOfBounds { offset: lc.offset, len: lc.cmdsize as u64 });
+    }
+
+    let str_offset: u32 = utils::bytes_to(is_be, &data[base + field_offset..])?;
+    if str_offset >= lc.cmdsize {
+        return Err(format!("{} string offset {} exceeds cmdsize {}", load_command_name(lc.cmd), str_offset, lc.cmdsize).into());
+    }
+
+    let string_bytes = &data[base + str_offset as usize..end];
+    let len = string_bytes
+        .iter()
+        .position(|&byte| byte == 0)
+        .ok_or(MoscopeError::UnterminatedString { offset: lc.offset + str_offset as u64 })?;
+
+    Ok(String::from_utf8_lossy(&string_bytes[..len]).to_string())
+}
+
+pub fn is_linkedit_data_command(cmd: u32) -> bool {
+    matches!(
+        cmd & !LC_REQ_DYLD,
+        LC_CODE_SIGNATURE
+            | LC_SEGMENT_SPLIT_INFO
+            | LC_FUNCTION_STARTS
+            | LC_DATA_IN_CODE
+            | LC_DYLIB_CODE_SIGN_DRS
+            | LC_LINKER_OPTIMIZATION_HINT
+            | LC_DYLD_EXPORTS_TRIE
+            | LC_DYLD_CHAINED_FIXUPS
+            | LC_ATOM_INFO
+            | LC_FUNCTION_VARIANTS
+            | LC_FUNCTION_VARIANT_FIXED
+    )
+}
+
+pub fn parse_linkedit_data(data: &[u8], lc: &LoadCommand, is_be: bool) -> Result<LinkEditDataCommand, MoscopeError> {
+    if lc.cmdsize != LINKEDIT_DATA_COMMAND_SIZE {
+        return Err(MoscopeError::InvalidCmdSize { cmd: lc.cmd, cmdsize: lc.cmdsize });
+    }
+
+    let base = lc.offset as usize;
+    if base + LINKEDIT_DATA_COMMAND_SIZE as usize > data.len() {
+        return Err(MoscopeError::OutOfBounds { offset: lc.offset, len: LINKEDIT_DATA_COMMAND_SIZE as u64 });
+    }
+
+    let dataoff: u32 = utils::bytes_to(is_be, &data[base + 8..])?; // skip cmd & cmdsize
+    let datasize: u32 = utils::bytes_to(is_be, &data[base + 12..])?;
+
+    Ok(LinkEditDataCommand { cmd: lc.cmd, dataoff, datasize })
+}
+
+// A linkedit_data_command with an odd cmdsize is recorded and skipped, the rest of the slice is still worth reporting
+pub fn parse_linkedit_data_or_diagnose(data: &[u8], lc: &LoadCommand, is_be: bool, diagnostics: &mut Vec<Diagnostic>) -> Option<LinkEditDataCommand> {
+    match parse_linkedit_data(data, lc, is_be) {
+        Ok(ld) => Some(ld),
+        Err(e) => {
+            diagnostics.push(Diagnostic::error("linkedit-data-malformed", format!("{} at 0x{:x} skipped: {}", load_command_name(lc.cmd), lc.offset, e)));
+            None
+        }
+    }
+}
+
+/*
+prebind_cksum_command memory layout (from loader.h)
++-----------------------------+
+| cmd (u32)                   |  LC_PREBIND_CKSUM
+| cmdsize (u32)               |  always 12
+| cksum (u32)                 |  checksum of the prebound image, 0 = not computed yet
++-----------------------------+
+
+Prebinding has been dead since 10.4, but old system binaries and anything redo_prebinding
+touched still carry one. A zero cksum tells update_prebinding it may (re)compute it.
+*/
+pub const PREBIND_CKSUM_COMMAND_SIZE: u32 = 12;
+
+pub fn parse_prebind_cksum(data: &[u8], lc: &LoadCommand, is_be: bool) -> Result<u32, MoscopeError> {
+    if lc.cmdsize != PREBIND_CKSUM_COMMAND_SIZE {
+        return Err(MoscopeError::InvalidCmdSize { cmd: lc.cmd, cmdsize: lc.cmdsize });
+    }
+
+    let base = lc.offset as usize;
+    if base + PREBIND_CKSUM_COMMAND_SIZE as usize > data.len() {
+        return Err(MoscopeError::OutOfBounds { offset: lc.offset, len: PREBIND_CKSUM_COMMAND_SIZE as u64 });
+    }
+
+    utils::bytes_to(is_be, &data[base + 8..]) // skip cmd & cmdsize
+}
+
+// None when the slice has no LC_PREBIND_CKSUM at all (i.e. almost everything built this century)
+pub fn find_prebind_cksum(data: &[u8], load_commands: &[LoadCommand], is_be: bool) -> Result<Option<u32>, MoscopeError> {
+    match load_commands.iter().find(|lc| lc.cmd == LC_PREBIND_CKSUM) {
+        Some(lc) => Ok(Some(parse_prebind_cksum(data, lc, is_be)?)),
+        None => Ok(None),
+    }
+}
+
+
+/*
+symseg_command memory layout (from loader.h)
++-----------------------------+
+| cmd (u32)                   |  LC_SYMSEG
+| cmdsize (u32)               |  always 16
+| offset (u32)                |  file offset of the gdb symbol segment (slice relative)
+| size (u32)                  |  its size in bytes
++-----------------------------+
+
+The pre-stabs gdb symbol table, loader.h has marked it obsolete for decades. Only very old
+binaries carry one, so finding it is worth a warning and its range is all there is to show.
+*/
+pub const SYMSEG_COMMAND_SIZE: u32 = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SymsegCommand {
+    pub offset: u32,
+    pub size: u32,
+}
+
+impl SymsegCommand {
+    pub fn build_report(&self) -> SymsegReport {
+        SymsegReport { offset: self.offset, size: self.size }
     }
-    println!("----------------------------------------");
-    println!();    
+}
 
+pub fn parse_symseg(data: &[u8], lc: &LoadCommand, is_be: bool) -> Result<SymsegCommand, MoscopeError> {
+    if lc.cmdsize != SYMSEG_COMMAND_SIZE {
+        return Err(MoscopeError::InvalidCmdSize { cmd: lc.cmd, cmdsize: lc.cmdsize });
+    }
 
+    let base = lc.offset as usize;
+    if base + SYMSEG_COMMAND_SIZE as usize > data.len() {
+        return Err(MoscopeError::OutOfBounds { offset: lc.offset, len: SYMSEG_COMMAND_SIZE as u64 });
+    }
+
+    Ok(SymsegCommand {
+        offset: utils::bytes_to(is_be, &data[base + 8..])?,
+        size: utils::bytes_to(is_be, &data[base + 12..])?,
+    })
+}
+
+// The obsolete-warning always, plus an error when the range doesn't fit in the slice
+pub fn validate_symseg(symseg: &SymsegCommand, slice_len: u64) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![Diagnostic::warning(
+        "symseg-obsolete",
+        format!("LC_SYMSEG (obsolete gdb symbol segment) at 0x{:x}, {} bytes", symseg.offset, symseg.size),
+    )];
+
+    let end = symseg.offset as u64 + symseg.size as u64;
+    if end > slice_len {
+        diagnostics.push(Diagnostic::error(
+            "symseg-out-of-bounds",
+            format!("LC_SYMSEG range 0x{:x}..0x{:x} runs past the end of the slice (0x{:x} bytes)", symseg.offset, end, slice_len),
+        ));
+    }
+    diagnostics
+}
+
+pub fn write_symseg(w: &mut dyn Write, symseg: Option<&SymsegReport>) -> io::Result<()> {
+    if let Some(symseg) = symseg {
+        writeln!(w, "{} off=0x{:08x} size={:#x} (obsolete)", "Symbol Segment:".yellow().bold(), symseg.offset, symseg.size)?;
+    }
+    Ok(())
+}
+
+/*
+version_min_command memory layout (from loader.h)
++-----------------------------+
+| cmd (u32)                   |  LC_VERSION_MIN_MACOSX / _IPHONEOS / _TVOS / _WATCHOS
+| cmdsize (u32)               |  always 16
+| version (u32)               |  X.Y.Z encoded in nibbles xxxx.yy.zz
+| sdk (u32)                   |  same encoding
++-----------------------------+
+
+What older toolchains wrote before LC_BUILD_VERSION, the platform is the command itself.
+Binaries built for a range of deployment targets can carry both, they're reported side by side.
+*/
+pub const VERSION_MIN_COMMAND_SIZE: u32 = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinVersion {
+    pub platform: &'static str,
+    pub version: String,
+    pub sdk: String,
+}
+
+impl MinVersion {
+    pub fn build_report(&self) -> MinVersionReport {
+        MinVersionReport {
+            platform: self.platform.to_string(),
+            version: self.version.clone(),
+            sdk: self.sdk.clone(),
+        }
+    }
+}
+
+// Same names LC_BUILD_VERSION's platform_name() uses, None for anything that isn't a version_min_command
+pub fn version_min_platform(cmd: u32) -> Option<&'static str> {
+    match cmd {
+        LC_VERSION_MIN_MACOSX   => Some("macOS"),
+        LC_VERSION_MIN_IPHONEOS => Some("iOS"),
+        LC_VERSION_MIN_TVOS     => Some("tvOS"),
+        LC_VERSION_MIN_WATCHOS  => Some("watchOS"),
+        _ => None,
+    }
+}
+
+pub fn parse_version_min(data: &[u8], lc: &LoadCommand, is_be: bool) -> Result<MinVersion, MoscopeError> {
+    let platform = version_min_platform(lc.cmd).ok_or_else(|| format!("0x{:x} is not an LC_VERSION_MIN_* command", lc.cmd))?;
+
+    if lc.cmdsize != VERSION_MIN_COMMAND_SIZE {
+        return Err(MoscopeError::InvalidCmdSize { cmd: lc.cmd, cmdsize: lc.cmdsize });
+    }
+
+    let base = lc.offset as usize;
+    if base + VERSION_MIN_COMMAND_SIZE as usize > data.len() {
+        return Err(MoscopeError::OutOfBounds { offset: lc.offset, len: VERSION_MIN_COMMAND_SIZE as u64 });
+    }
+
+    Ok(MinVersion {
+        platform,
+        version: decode_version(utils::bytes_to(is_be, &data[base + 8..])?),
+        sdk: decode_version(utils::bytes_to(is_be, &data[base + 12..])?),
+    })
+}
+
+pub fn write_min_version(w: &mut dyn Write, min_version: &MinVersionReport) -> io::Result<()> {
+    writeln!(w, "{} {} {} (SDK {})", "Min Version:".yellow().bold(), min_version.platform, min_version.version, min_version.sdk)?;
+    Ok(())
+}
+
+pub fn write_prebind_cksum(w: &mut dyn Write, cksum: Option<u32>) -> io::Result<()> {
+    match cksum {
+        Some(0) => writeln!(w, "{} 0x00000000 (not computed, recomputable)", "Prebind Checksum:".yellow().bold())?,
+        Some(cksum) => writeln!(w, "{} 0x{:08x}", "Prebind Checksum:".yellow().bold(), cksum)?,
+        None => {}
+    }
+    Ok(())
+}
+
+pub fn write_linkedit_data_summary(w: &mut dyn Write, linkedit_data: &[LinkEditDataReport]) -> io::Result<()> {
+    if linkedit_data.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(w, "{}", "LinkEdit Data".green().bold())?;
+    writeln!(w, "----------------------------------------")?;
+    for ld in linkedit_data {
+        writeln!(w, " - {:<30} off=0x{:08x} size={:#x}", ld.command, ld.dataoff, ld.datasize)?;
+    }
+    writeln!(w, "----------------------------------------")?;
+    writeln!(w)?;
+    Ok(())
+}
+
+// Sorted by dataoff, so it reads like a map of __LINKEDIT (the code signature is normally last)
+pub fn build_linkedit_overview(cpu_type: &str, cpu_subtype: &str, slice_offset: u64, linkedit_data: &[LinkEditDataCommand], diagnostics: &[Diagnostic]) -> LinkEditOverviewReport {
+    let mut blobs: Vec<&LinkEditDataCommand> = linkedit_data.iter().collect();
+    blobs.sort_by_key(|ld| ld.dataoff);
+
+    LinkEditOverviewReport {
+        cpu_type: cpu_type.to_string(),
+        cpu_subtype: cpu_subtype.to_string(),
+        slice_offset,
+        blobs: blobs.into_iter().map(LinkEditDataCommand::build_report).collect(),
+        diagnostics: diagnostics.iter().map(|d| d.build_report()).collect(),
+    }
+}
+
+pub fn write_linkedit_overview(w: &mut dyn Write, report: &LinkEditOverviewReport) -> io::Result<()> {
+    writeln!(w)?;
+    writeln!(w, "{} ({})", "LinkEdit Overview".green().bold(), report.cpu_subtype)?;
+    writeln!(w, "----------------------------------------")?;
+
+    if report.blobs.is_empty() {
+        writeln!(w, "No linkedit_data commands")?;
+    } else {
+        writeln!(w, "{:<30} {:<12} {:<12} Size", "Command", "Offset", "File offset")?;
+        for ld in &report.blobs {
+            writeln!(
+                w,
+                "{:<30} {:<12} {:<12} {:#x} ({} bytes)",
+                ld.command,
+                format!("0x{:x}", ld.dataoff),
+                format!("0x{:x}", report.slice_offset + ld.dataoff as u64),
+                ld.datasize,
+                ld.datasize
+            )?;
+        }
+    }
+
+    for d in &report.diagnostics {
+        let severity = if d.severity == "error" { d.severity.red().bold() } else { d.severity.yellow().bold() };
+        writeln!(w, "{} [{}] {}", severity, d.code, d.message)?;
+    }
+    Ok(())
+}
+
+
+/*
+--export-load-commands: the load command table and nothing else, as one JSON array
+
+    [
+    {"arch":"x86_64","name":"LC_SEGMENT_64","cmd":25,"cmdsize":72,"offset":4128,"requires_dyld":false},
+    ...
+    ]
+
+Every command is serialized and written as soon as it's handed over, slices are fed one at a
+time, so a fat binary never has more than one slice's table in memory. One row per line keeps
+`grep -c LC_DYLD_CHAINED_FIXUPS` working on the raw file too.
+*/
+pub struct LoadCommandExporter<W: Write> {
+    out: W,
+    first: bool,
+}
+
+impl<W: Write> LoadCommandExporter<W> {
+    pub fn new(mut out: W) -> io::Result<Self> {
+        out.write_all(b"[")?;
+        Ok(LoadCommandExporter { out, first: true })
+    }
+
+    pub fn write_slice(&mut self, arch: &str, load_commands: &[LoadCommand]) -> io::Result<()> {
+        for lc in load_commands {
+            let entry = LoadCommandExportEntry {
+                arch: arch.to_string(),
+                name: load_command_name(lc.cmd).to_string(),
+                cmd: lc.cmd,
+                cmdsize: lc.cmdsize,
+                offset: lc.offset,
+                requires_dyld: lc.cmd & LC_REQ_DYLD != 0,
+            };
+
+            self.out.write_all(if self.first { b"\n" } else { b",\n" })?;
+            serde_json::to_writer(&mut self.out, &entry)?;
+            self.first = false;
+        }
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        self.out.write_all(b"\n]\n")?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+
+pub fn write_load_commands(w: &mut dyn Write, load_commands: &[LoadCommandReport]) -> io::Result<()> {
+    if load_commands.is_empty() {
+        return Ok(());
+    }
+    writeln!(w)?;
+    writeln!(w, "{} {}", "Load Commands Found: ".green().bold(), load_commands.len())?;
+    writeln!(w, "----------------------------------------")?;
+    for lc in load_commands {
+        writeln!(w, " - {:<30} cmd=0x{:08x} size={}", lc.command, lc.cmd, lc.size)?;
+        if let Some(body) = &lc.body {
+            // 16 bytes a row like xxd, offsets from the start of the command (the body starts at 8)
+            let bytes: Vec<&str> = body.split_whitespace().collect();
+            for (row, chunk) in bytes.chunks(16).enumerate() {
+                writeln!(w, "     {}  {}", format!("{:04x}", 8 + row * 16).dimmed(), chunk.join(" "))?;
+            }
+        }
+    }
+    writeln!(w, "----------------------------------------")?;
+    writeln!(w)?;
+    Ok(())
 }
 
 
 pub fn read_load_commands(
     data: &[u8],
-    offset: u32,
+    offset: u64, // absolute file offset, u64 so slices past 4GB in a fat64 file don't get truncated
     num_load_commands: u32,
     word_size: u32, // 32 or 64,
     big_endian: bool,
-) -> Result<Vec<LoadCommand>, Box<dyn Error>> {
+) -> Result<Vec<LoadCommand>, MoscopeError> {
     let mut load_commands: Vec<LoadCommand> = Vec::new();
     let mut cursor = offset as usize;
     
@@ -142,7 +558,7 @@ pub fn read_load_commands(
 
     for i in 0..num_load_commands {
         if cursor + 8 >= data.len() {
-            return Err(format!("Load command {} header exceeds file bounds", i).into());
+            return Err(MoscopeError::OutOfBounds { offset: cursor as u64, len: 8 });
         }
         
         let cmd: u32 = utils::bytes_to(big_endian, &data[cursor..])?; // Don't have to specify end index because bytes_to already knows the size
@@ -150,15 +566,15 @@ pub fn read_load_commands(
 
         // Now verify variable length data as specified by cmd_size
         if cmd_size < 8 {
-            return Err(format!("Load command {} has invalid cmdsize of {}", i, cmd_size).into());
+            return Err(MoscopeError::InvalidCmdSize { cmd, cmdsize: cmd_size });
         }
 
-        if cmd_size % alignment != 0 {
+        if !cmd_size.is_multiple_of(alignment) {
             return Err(format!("Load command {} with cmdsize {} is not {}-byte aligned", i, cmd_size, alignment).into());
         }
 
         if cursor + cmd_size as usize > data.len() {
-            return Err(format!("Load command {} exceeds file bounds", i).into());
+            return Err(MoscopeError::OutOfBounds { offset: cursor as u64, len: cmd_size as u64 });
         }
 
         // Now we can finally read it
@@ -170,4 +586,381 @@ pub fn read_load_commands(
 
     Ok(load_commands)
 
-}
\ No newline at end of file
+}
+
+
+// Header plus load command table of one slice, what every per-slice pass starts from
+pub fn read_slice_load_commands(data: &[u8], slice: &MachOSlice) -> Result<(ParsedMachOHeader, Vec<LoadCommand>), MoscopeError> {
+    let thin_header = header::read_thin_header(data, slice)?;
+
+    let (header_size, ncmds, word_size) = match &thin_header.header {
+        MachOHeader::Header32(h) => (size_of::<MachHeader32>(), h.ncmds, 32),
+        MachOHeader::Header64(h) => (size_of::<MachHeader64>(), h.ncmds, 64),
+    };
+
+    let load_command_offset = slice.offset as usize + header_size;
+    let load_commands_vec = read_load_commands(data, load_command_offset as u64, ncmds, word_size, thin_header.kind.is_be())?;
+
+    Ok((thin_header, load_commands_vec))
+}
+
+
+/*
+--lc-map: who owns which byte of the load command region
+
+    mach header | LC 0 | LC 1 | ... | LC n-1 | (padding) | first section data
+                ^                                ^
+                region_start                     region_start + sizeofcmds
+
+Every command should start exactly where the previous one ended and the last one should
+end exactly at sizeofcmds. read_load_commands walks by cmdsize so its output always tiles,
+the interesting failures are at the edges: a chain that stops short of sizeofcmds (bytes
+nobody owns, a handy hiding spot) or runs past it, and commands that spill into section data.
+*/
+pub fn validate_load_command_layout(
+    load_commands: &[LoadCommand],
+    slice_offset: u64,
+    region_start: u64, // absolute file offset of the first load command
+    sizeofcmds: u32,
+    segments: &[ParsedSegment],
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let region_end = region_start + sizeofcmds as u64;
+    let rel = |abs: u64| abs - slice_offset;
+
+    let mut cursor = region_start;
+    for (i, lc) in load_commands.iter().enumerate() {
+        if lc.offset > cursor {
+            diagnostics.push(Diagnostic::warning(
+                "lc-gap",
+                format!("0x{:x} unowned bytes at 0x{:x}..0x{:x} before load command {} ({})",
+                    lc.offset - cursor, rel(cursor), rel(lc.offset), i, load_command_name(lc.cmd)),
+            ));
+        } else if lc.offset < cursor {
+            diagnostics.push(Diagnostic::error(
+                "lc-overlap",
+                format!("load command {} ({}) at 0x{:x} overlaps the previous command, which ends at 0x{:x}",
+                    i, load_command_name(lc.cmd), rel(lc.offset), rel(cursor)),
+            ));
+        }
+        cursor = cursor.max(lc.offset + lc.cmdsize as u64);
+    }
+
+    if cursor < region_end {
+        diagnostics.push(Diagnostic::warning(
+            "lc-region-gap",
+            format!("load commands end at 0x{:x} but sizeofcmds covers up to 0x{:x} (0x{:x} unowned bytes)",
+                rel(cursor), rel(region_end), region_end - cursor),
+        ));
+    } else if cursor > region_end {
+        diagnostics.push(Diagnostic::error(
+            "lc-region-overflow",
+            format!("load commands end at 0x{:x}, past sizeofcmds at 0x{:x}", rel(cursor), rel(region_end)),
+        ));
+    }
+
+    // Section offsets are slice relative already
+    let lc_rel_end = rel(cursor.max(region_end));
+    for seg in segments {
+        for sect in &seg.sections {
+            if sect.size == 0 || sect.offset == 0 || sect.is_zerofill() {
+                continue;
+            }
+            let sect_start = sect.offset as u64;
+            if sect_start < lc_rel_end && sect_start + sect.size > rel(region_start) {
+                diagnostics.push(Diagnostic::error(
+                    "lc-overlaps-section",
+                    format!("{},{} data at 0x{:x} starts inside the load command region (ends at 0x{:x})",
+                        utils::byte_array_to_string(&sect.segname), utils::byte_array_to_string(&sect.sectname), sect_start, lc_rel_end),
+                ));
+            }
+        }
+    }
+
+    diagnostics
+}
+
+pub fn build_lc_map_report(
+    cpu_type: &str,
+    cpu_subtype: &str,
+    load_commands: &[LoadCommand],
+    slice_offset: u64,
+    region_start: u64,
+    sizeofcmds: u32,
+    diagnostics: &[Diagnostic],
+) -> LoadCommandMapReport {
+    LoadCommandMapReport {
+        cpu_type: cpu_type.to_string(),
+        cpu_subtype: cpu_subtype.to_string(),
+        region_start: region_start - slice_offset,
+        region_end: region_start - slice_offset + sizeofcmds as u64,
+        commands: load_commands
+            .iter()
+            .enumerate()
+            .map(|(index, lc)| LoadCommandRangeReport {
+                index,
+                command: load_command_name(lc.cmd).to_string(),
+                start: lc.offset - slice_offset,
+                end: lc.offset - slice_offset + lc.cmdsize as u64,
+            })
+            .collect(),
+        diagnostics: diagnostics.iter().map(|d| d.build_report()).collect(),
+    }
+}
+
+pub fn write_lc_map(w: &mut dyn Write, report: &LoadCommandMapReport) -> io::Result<()> {
+    writeln!(w)?;
+    writeln!(w, "{} ({})", "Load Command Map".green().bold(), report.cpu_subtype)?;
+    writeln!(w, "----------------------------------------")?;
+    writeln!(w, "region 0x{:x}..0x{:x} (sizeofcmds {})", report.region_start, report.region_end, report.region_end - report.region_start)?;
+    writeln!(w, "{:<5} {:<28} Range", "#", "Command")?;
+
+    for c in &report.commands {
+        writeln!(w, "{:<5} {:<28} 0x{:x}..0x{:x} ({} bytes)", c.index, c.command, c.start, c.end, c.end - c.start)?;
+    }
+
+    for d in &report.diagnostics {
+        let severity = if d.severity == "error" { d.severity.red().bold() } else { d.severity.yellow().bold() };
+        writeln!(w, "{} [{}] {}", severity, d.code, d.message)?;
+    }
+    Ok(())
+}
+
+
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn linkedit_command(cmd: u32, cmdsize: u32, dataoff: u32, datasize: u32) -> Vec<u8> {
+        let mut data = Vec::new();
+        for value in [cmd, cmdsize, dataoff, datasize] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn parse_linkedit_data_reads_offset_and_size() {
+        let data = linkedit_command(LC_ATOM_INFO, 16, 0x8000, 0x40);
+        let lc = LoadCommand { cmd: LC_ATOM_INFO, cmdsize: 16, offset: 0 };
+
+        let ld = parse_linkedit_data(&data, &lc, false).unwrap();
+
+        assert_eq!(ld, LinkEditDataCommand { cmd: LC_ATOM_INFO, dataoff: 0x8000, datasize: 0x40 });
+    }
+
+    #[test]
+    fn odd_linkedit_cmdsize_becomes_a_diagnostic() {
+        let mut data = linkedit_command(LC_CODE_SIGNATURE, 24, 0x8000, 0x40);
+        data.extend_from_slice(&[0u8; 8]);
+        let lc = LoadCommand { cmd: LC_CODE_SIGNATURE, cmdsize: 24, offset: 0 };
+        let mut diagnostics = Vec::new();
+
+        assert!(parse_linkedit_data_or_diagnose(&data, &lc, false, &mut diagnostics).is_none());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, "linkedit-data-malformed");
+    }
+
+    #[test]
+    fn linkedit_overview_is_in_file_order() {
+        let linkedit_data = [
+            LinkEditDataCommand { cmd: LC_CODE_SIGNATURE, dataoff: 0x8400, datasize: 0x200 },
+            LinkEditDataCommand { cmd: LC_DYLD_CHAINED_FIXUPS | LC_REQ_DYLD, dataoff: 0x8000, datasize: 0x100 },
+            LinkEditDataCommand { cmd: LC_FUNCTION_STARTS, dataoff: 0x8100, datasize: 0x8 },
+        ];
+        let report = build_linkedit_overview("ARM", "arm64", 0x4000, &linkedit_data, &[]);
+
+        assert_eq!(
+            report.blobs.iter().map(|b| (b.command.as_str(), b.dataoff)).collect::<Vec<_>>(),
+            [("LC_DYLD_CHAINED_FIXUPS", 0x8000), ("LC_FUNCTION_STARTS", 0x8100), ("LC_CODE_SIGNATURE", 0x8400)]
+        );
+        assert_eq!(report.slice_offset, 0x4000);
+    }
+
+    #[test]
+    fn unknown_commands_get_their_body_dumped() {
+        let mut data = linkedit_command(0x7f, 24, 0xdeadbeef, 0x40);
+        data.extend_from_slice(&[0xaa; 8]);
+        let unknown = LoadCommand { cmd: 0x7f, cmdsize: 24, offset: 0 };
+
+        assert_eq!(unknown_load_command_body(&data, &unknown).as_deref(), Some("ef be ad de 40 00 00 00 aa aa aa aa aa aa aa aa"));
+        assert_eq!(unknown_load_command_body(&data, &LoadCommand { cmdsize: 64, ..unknown }), None);
+        assert_eq!(unknown_load_command_body(&data, &LoadCommand { cmd: LC_CODE_SIGNATURE, ..unknown }), None);
+
+        let mut report = unknown.build_report();
+        report.body = unknown_load_command_body(&data, &unknown);
+        let mut out = Vec::new();
+        write_load_commands(&mut out, &[report]).unwrap();
+        let out = crate::test_util::strip_ansi(&String::from_utf8(out).unwrap());
+        assert!(out.contains("UNKNOWN_LOAD_COMMAND"));
+        assert!(out.contains("0008  ef be ad de"));
+    }
+
+    #[test]
+    fn symseg_range_is_read_and_bounds_checked() {
+        let data = linkedit_command(LC_SYMSEG, 16, 0x1000, 0x200);
+        let lc = LoadCommand { cmd: LC_SYMSEG, cmdsize: 16, offset: 0 };
+
+        let symseg = parse_symseg(&data, &lc, false).unwrap();
+        assert_eq!(symseg, SymsegCommand { offset: 0x1000, size: 0x200 });
+
+        let diagnostics = validate_symseg(&symseg, 0x2000);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, "symseg-obsolete");
+
+        let diagnostics = validate_symseg(&symseg, 0x1100);
+        assert_eq!(diagnostics[1].code, "symseg-out-of-bounds");
+
+        assert!(parse_symseg(&data, &LoadCommand { cmdsize: 24, ..lc }, false).is_err());
+        assert!(parse_symseg(&data[..12], &lc, false).is_err());
+    }
+
+    #[test]
+    fn version_min_platform_comes_from_the_command() {
+        let data = linkedit_command(LC_VERSION_MIN_IPHONEOS, 16, 0x000C_0100, 0x000D_0000);
+        let lc = LoadCommand { cmd: LC_VERSION_MIN_IPHONEOS, cmdsize: 16, offset: 0 };
+
+        let min = parse_version_min(&data, &lc, false).unwrap();
+        assert_eq!(min, MinVersion { platform: "iOS", version: "12.1.0".to_string(), sdk: "13.0.0".to_string() });
+
+        assert_eq!(version_min_platform(LC_VERSION_MIN_MACOSX), Some("macOS"));
+        assert_eq!(version_min_platform(LC_BUILD_VERSION), None);
+        assert!(parse_version_min(&data, &LoadCommand { cmdsize: 24, ..lc }, false).is_err());
+        assert!(parse_version_min(&data, &LoadCommand { cmd: LC_SYMSEG, ..lc }, false).is_err());
+    }
+
+    #[test]
+    fn prebind_cksum_reads_the_checksum() {
+        let mut data = Vec::new();
+        for value in [LC_PREBIND_CKSUM, 12u32, 0xDEADBEEF] {
+            data.extend_from_slice(&value.to_be_bytes());
+        }
+        let lcs = [LoadCommand { cmd: LC_PREBIND_CKSUM, cmdsize: 12, offset: 0 }];
+
+        assert_eq!(find_prebind_cksum(&data, &lcs, true).unwrap(), Some(0xDEADBEEF));
+        assert_eq!(find_prebind_cksum(&data, &[], true).unwrap(), None);
+    }
+
+    #[test]
+    fn prebind_cksum_rejects_bad_cmdsize() {
+        let data = [0u8; 16];
+        let lc = LoadCommand { cmd: LC_PREBIND_CKSUM, cmdsize: 16, offset: 0 };
+
+        assert!(parse_prebind_cksum(&data, &lc, false).is_err());
+    }
+
+    #[test]
+    fn parse_linkedit_data_big_endian() {
+        let mut data = Vec::new();
+        for value in [LC_CODE_SIGNATURE, 16u32, 0x1234, 0x10] {
+            data.extend_from_slice(&value.to_be_bytes());
+        }
+        let lc = LoadCommand { cmd: LC_CODE_SIGNATURE, cmdsize: 16, offset: 0 };
+
+        let ld = parse_linkedit_data(&data, &lc, true).unwrap();
+
+        assert_eq!((ld.dataoff, ld.datasize), (0x1234, 0x10));
+    }
+
+    #[test]
+    fn parse_linkedit_data_rejects_bad_cmdsize() {
+        let data = linkedit_command(LC_FUNCTION_VARIANTS, 24, 0, 0);
+        let lc = LoadCommand { cmd: LC_FUNCTION_VARIANTS, cmdsize: 24, offset: 0 };
+
+        assert!(parse_linkedit_data(&data, &lc, false).is_err());
+    }
+
+    // Commands as read_load_commands would return them, back to back from region_start
+    fn tiled(region_start: u64, sizes: &[u32]) -> Vec<LoadCommand> {
+        let mut offset = region_start;
+        sizes
+            .iter()
+            .map(|&cmdsize| {
+                let lc = LoadCommand { cmd: LC_UUID, cmdsize, offset };
+                offset += cmdsize as u64;
+                lc
+            })
+            .collect()
+    }
+
+    #[test]
+    fn tiling_commands_are_clean() {
+        let lcs = tiled(32, &[24, 72, 16]);
+        assert!(validate_load_command_layout(&lcs, 0, 32, 112, &[]).is_empty());
+    }
+
+    #[test]
+    fn short_and_long_chains_against_sizeofcmds() {
+        let lcs = tiled(32, &[24, 24]);
+
+        let short = validate_load_command_layout(&lcs, 0, 32, 64, &[]);
+        assert_eq!(short.len(), 1);
+        assert_eq!(short[0].code, "lc-region-gap");
+        assert!(short[0].message.contains("0x10 unowned bytes"), "{}", short[0].message);
+
+        let long = validate_load_command_layout(&lcs, 0, 32, 40, &[]);
+        assert_eq!(long[0].code, "lc-region-overflow");
+    }
+
+    #[test]
+    fn gaps_and_overlaps_between_commands() {
+        let mut lcs = tiled(32, &[24, 24, 24]);
+        lcs[1].offset += 8;  // gap of 8 after LC 0
+        lcs[2].offset -= 16; // now starts inside LC 1
+
+        let codes: Vec<&str> = validate_load_command_layout(&lcs, 0, 32, 56, &[])
+            .iter()
+            .map(|d| d.code)
+            .collect();
+        assert_eq!(codes, vec!["lc-gap", "lc-overlap"]);
+    }
+
+    #[test]
+    fn map_offsets_are_slice_relative() {
+        let slice_offset = 0x4000;
+        let lcs = tiled(slice_offset + 32, &[24, 16]);
+        let report = build_lc_map_report("ARM", "arm64", &lcs, slice_offset, slice_offset + 32, 40, &[]);
+
+        assert_eq!((report.region_start, report.region_end), (32, 72));
+        assert_eq!((report.commands[1].index, report.commands[1].start, report.commands[1].end), (1, 56, 72));
+        assert_eq!(report.commands[0].command, "LC_UUID");
+    }
+
+    #[test]
+    fn linkedit_data_commands_ignore_req_dyld_bit() {
+        assert!(is_linkedit_data_command(LC_DYLD_CHAINED_FIXUPS | LC_REQ_DYLD));
+        assert!(is_linkedit_data_command(LC_FUNCTION_VARIANT_FIXED));
+        assert!(!is_linkedit_data_command(LC_SYMTAB));
+    }
+
+    #[test]
+    fn exported_load_commands_are_one_json_array() {
+        let mut exporter = LoadCommandExporter::new(Vec::new()).unwrap();
+        exporter.write_slice("x86_64", &[LoadCommand { cmd: LC_UUID, cmdsize: 24, offset: 0x1020 }]).unwrap();
+        exporter.write_slice("arm64", &[
+            LoadCommand { cmd: LC_UUID, cmdsize: 24, offset: 0x4020 },
+            LoadCommand { cmd: LC_DYLD_CHAINED_FIXUPS | LC_REQ_DYLD, cmdsize: 16, offset: 0x4038 },
+        ]).unwrap();
+        let out = exporter.finish().unwrap();
+
+        let entries: Vec<LoadCommandExportEntry> = serde_json::from_slice(&out).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!((entries[1].arch.as_str(), entries[1].offset), ("arm64", 0x4020));
+        assert_eq!(entries[2].name, "LC_DYLD_CHAINED_FIXUPS");
+        assert!(entries[2].requires_dyld);
+        assert!(!entries[0].requires_dyld);
+    }
+
+    #[test]
+    fn exporting_nothing_is_still_valid_json() {
+        let out = LoadCommandExporter::new(Vec::new()).unwrap().finish().unwrap();
+
+        assert_eq!(serde_json::from_slice::<Vec<LoadCommandExportEntry>>(&out).unwrap(), vec![]);
+    }
+}
@@ -1,5 +1,6 @@
 // File purpose: strings and such are unreadable for dyld extracted binaries due to file offset issues
-use crate::macho::segments::ParsedSegment;
+use crate::macho::constants::{CPU_ARCH_ABI64, arch_name};
+use crate::macho::segments::{self, ParsedSegment};
 use crate::macho::sections::ParsedSection;
 
 
@@ -36,15 +37,7 @@ pub struct MachOMemoryImage {
 impl MachOMemoryImage {
     pub fn new(segments: &[ParsedSegment], file_data: &[u8], slice_offset: u64) -> Self {
         // Find the address range we need
-        let mut min_addr = u64::MAX; // Start with the largest possible value
-        let mut max_addr = 0u64; // Start with the smallest possible value
-        
-        for seg in segments {
-            if seg.vmsize > 0 {
-                min_addr = min_addr.min(seg.vmaddr);
-                max_addr = max_addr.max(seg.vmaddr + seg.vmsize);
-            }
-        }
+        let (min_addr, max_addr) = segments::vm_range(segments).unwrap_or((0, 0));
         
         let total_size = (max_addr - min_addr) as usize;
         let mut buffer = vec![0u8; total_size];
@@ -96,4 +89,229 @@ impl MachOMemoryImage {
             None
         }
     }
-}
\ No newline at end of file
+
+    pub fn read_u32(&self, vmaddr: u64) -> Option<u32> {
+        let offset = vmaddr.checked_sub(self.base_vmaddr)? as usize;
+        let end = offset.checked_add(4)?;
+        if end <= self.buffer.len() {
+            let bytes = &self.buffer[offset..end];
+            Some(u32::from_le_bytes(bytes.try_into().ok()?))
+        } else {
+            None
+        }
+    }
+
+    // Pointer sized read, 4 bytes for 32-bit slices and 8 for 64-bit
+    pub fn read_ptr(&self, vmaddr: u64, is_64: bool) -> Option<u64> {
+        if is_64 {
+            self.read_u64(vmaddr)
+        } else {
+            self.read_u32(vmaddr).map(u64::from)
+        }
+    }
+
+    // NUL terminated string starting at vmaddr, None if it runs off the end of the image
+    pub fn read_c_string(&self, vmaddr: u64) -> Option<String> {
+        let offset = vmaddr.checked_sub(self.base_vmaddr)? as usize;
+        let bytes = self.buffer.get(offset..)?;
+        let len = bytes.iter().position(|&b| b == 0)?;
+        Some(String::from_utf8_lossy(&bytes[..len]).into_owned())
+    }
+}
+
+
+/*
+What's sitting in a pointer slot on disk depends on how the binary was linked:
+    - classic LC_DYLD_INFO binaries store the real vmaddr (or 0 for a bind, the bind opcodes name the target)
+    - LC_DYLD_CHAINED_FIXUPS binaries store a chained fixup: target bits + next + flags
+
+We don't walk the chains here, we only need the target out of each pointer so we
+peel the encoding off directly:
+    DYLD_CHAINED_PTR_64(_OFFSET)    bit 63 = bind, target = low 36 bits | high8 << 56
+    DYLD_CHAINED_PTR_ARM64E(...)    bit 63 = auth, bit 62 = bind,
+                                    target = low 32 bits (auth) / low 43 bits (plain)
+The *_OFFSET flavours store an offset from the mach header instead of a vmaddr,
+so a target that isn't mapped gets one more try relative to __TEXT.
+
+Started life inside the ObjC parser, initializer pointers need the exact same treatment.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StoredPointer {
+    Null,
+    Address(u64),
+    Bound,      // resolved by dyld at load time, the target lives in another image
+    Invalid,
+}
+
+pub struct PointerDecoder<'a> {
+    segments: &'a [ParsedSegment],
+    is_64: bool,
+    is_arm64e: bool,
+    text_vmaddr: u64,
+}
+
+impl<'a> PointerDecoder<'a> {
+    pub fn new(segments: &'a [ParsedSegment], cputype: i32, cpusubtype: i32) -> Self {
+        // The segment mapping file offset 0 holds the mach header, that's the base for *_OFFSET pointers
+        let text_vmaddr = segments
+            .iter()
+            .find(|seg| seg.fileoff == 0 && seg.filesize > 0)
+            .map_or(0, |seg| seg.vmaddr);
+
+        Self {
+            segments,
+            is_64: cputype & CPU_ARCH_ABI64 != 0,
+            is_arm64e: arch_name(cputype, cpusubtype) == "arm64e",
+            text_vmaddr,
+        }
+    }
+
+    pub fn is_64(&self) -> bool {
+        self.is_64
+    }
+
+    // Only bytes that came from the file count, __PAGEZERO and zerofill tails never hold metadata
+    pub fn is_mapped(&self, addr: u64) -> bool {
+        self.segments
+            .iter()
+            .any(|seg| seg.filesize > 0 && addr >= seg.vmaddr && addr < seg.vmaddr + seg.filesize)
+    }
+
+    pub fn decode(&self, raw: u64) -> StoredPointer {
+        if raw == 0 {
+            return StoredPointer::Null;
+        }
+        if self.is_mapped(raw) {
+            return StoredPointer::Address(raw);
+        }
+        if !self.is_64 {
+            return StoredPointer::Invalid;
+        }
+
+        let target = if self.is_arm64e {
+            if raw & (1 << 62) != 0 {
+                return StoredPointer::Bound;
+            }
+            if raw & (1 << 63) != 0 { raw & 0xffff_ffff } else { raw & 0x7ff_ffff_ffff }
+        } else {
+            if raw & (1 << 63) != 0 {
+                return StoredPointer::Bound;
+            }
+            (raw & 0xf_ffff_ffff) | (((raw >> 36) & 0xff) << 56)
+        };
+
+        let rebased = self.text_vmaddr.wrapping_add(target);
+        if self.is_mapped(target) {
+            StoredPointer::Address(target)
+        } else if self.is_mapped(rebased) {
+            StoredPointer::Address(rebased)
+        } else {
+            StoredPointer::Invalid
+        }
+    }
+}
+
+
+/*
+File offset counterpart to MachOMemoryImage::read_section, no VM buffer involved:
+    data[slice_offset + section.offset .. + section.size]
+Fine for anything ld64 wrote to disk, where offset and addr move in lockstep inside a segment.
+NOT fine for dylibs pulled out of the dyld shared cache (MH_DYLIB_IN_CACHE), their section
+offsets still point into the cache file, which is the whole reason the VM image exists.
+*/
+pub fn read_section_from_file<'a>(file_data: &'a [u8], section: &ParsedSection, slice_offset: u64) -> Option<&'a [u8]> {
+    if section.size == 0 || section.offset == 0 {
+        return None; // empty or zerofill, nothing on disk
+    }
+
+    let start = (slice_offset as usize).checked_add(section.offset as usize)?;
+    let end = start.checked_add(section.size as usize)?;
+    file_data.get(start..end)
+}
+
+
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macho::constants::*;
+    use crate::macho::header::{MachOSlice, read_thin_header};
+    use crate::macho::load_commands::read_load_commands;
+    use crate::macho::segments::parse_segment_64;
+    use crate::test_util::{MachOBuilder, SegmentSpec};
+
+    fn segments_of(data: &[u8]) -> Vec<ParsedSegment> {
+        let macho = read_thin_header(data, &MachOSlice { offset: 0, size: None }).unwrap();
+        let ncmds = match &macho.header {
+            crate::macho::header::MachOHeader::Header64(h) => h.ncmds,
+            crate::macho::header::MachOHeader::Header32(h) => h.ncmds,
+        };
+
+        read_load_commands(data, 32, ncmds, 64, false)
+            .unwrap()
+            .iter()
+            .filter(|lc| lc.cmd == LC_SEGMENT_64)
+            .map(|lc| parse_segment_64(data, lc.offset as usize, false).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn file_offsets_match_the_vm_image_for_on_disk_binaries() {
+        let data = MachOBuilder::new_64(CPU_TYPE_ARM64, 0)
+            .segment(SegmentSpec::new("__PAGEZERO", 0).vmsize(0x1_0000_0000))
+            .segment(
+                SegmentSpec::new("__TEXT", 0x1_0000_0000)
+                    .section("__text", S_ATTR_PURE_INSTRUCTIONS, &[0xC0, 0x03, 0x5F, 0xD6])
+                    .section("__cstring", S_CSTRING_LITERALS, b"first\0second one\0"),
+            )
+            .segment(SegmentSpec::new("__DATA", 0x1_0000_4000).section("__data", S_REGULAR, &[1, 2, 3, 4, 5, 6, 7, 8]))
+            .build();
+
+        let segments = segments_of(&data);
+        let image = MachOMemoryImage::new(&segments, &data, 0);
+
+        for section in segments.iter().flat_map(|s| &s.sections) {
+            assert!(read_section_from_file(&data, section, 0).is_some());
+            assert_eq!(read_section_from_file(&data, section, 0), image.read_section(section));
+        }
+    }
+
+    #[test]
+    fn file_read_skips_zerofill_and_out_of_bounds() {
+        let data = MachOBuilder::new_64(CPU_TYPE_ARM64, 0)
+            .segment(SegmentSpec::new("__DATA", 0x1_0000_0000).section("__bss", S_ZEROFILL, &[0; 16]))
+            .build();
+        let segments = segments_of(&data);
+        let bss = &segments[0].sections[0];
+
+        assert_eq!(read_section_from_file(&data, bss, 0), None);
+
+        let mut past_end = bss.clone();
+        past_end.offset = data.len() as u32;
+        assert_eq!(read_section_from_file(&data, &past_end, 0), None);
+    }
+
+    #[test]
+    fn oslogstring_reads_through_the_vm_image() {
+        let data = MachOBuilder::new_64(CPU_TYPE_ARM64, 0)
+            .segment(
+                SegmentSpec::new("__TEXT", 0x1_0000_0000)
+                    .section("__text", S_ATTR_PURE_INSTRUCTIONS, &[0xC0, 0x03, 0x5F, 0xD6])
+                    .section("__oslogstring", S_REGULAR, b"connected to %{public}s\0retrying in %d s\0"),
+            )
+            .build();
+
+        let segments = segments_of(&data);
+        let oslog = &segments[0].sections[1];
+        let image = MachOMemoryImage::new(&segments, &data, 0);
+
+        assert_eq!(oslog.kind, crate::macho::sections::SectionKind::CString);
+        let strings = crate::macho::symtab::extract_strings(image.read_section(oslog).unwrap(), 4);
+        assert_eq!(strings, vec!["connected to %{public}s", "retrying in %d s"]);
+    }
+}
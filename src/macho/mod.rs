@@ -2,6 +2,7 @@ pub mod header;
 pub mod fat;
 pub mod constants;
 pub mod utils;
+pub mod error;
 pub mod load_commands;
 pub mod segments;
 pub mod sections;
@@ -9,4 +10,26 @@ pub mod dylibs;
 pub mod rpaths;
 pub mod symtab;
 pub mod memory_image;
-pub mod dyld;
\ No newline at end of file
+pub mod dyld;
+pub mod uuid;
+pub mod compression;
+pub mod diagnostics;
+pub mod build_version;
+pub mod source_version;
+pub mod function_starts;
+pub mod data_in_code;
+pub mod hash;
+pub mod identify;
+pub mod input;
+pub mod objc;
+pub mod search;
+pub mod initializers;
+pub mod export_trie;
+pub mod codesign;
+pub mod entry_point;
+pub mod encryption;
+pub mod notes;
+pub mod sub_images;
+pub mod fileset;
+pub mod analysis;
+pub mod parse;
\ No newline at end of file
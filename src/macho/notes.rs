@@ -0,0 +1,146 @@
+// File Purpose: What did the producer leave in LC_NOTE? (core files, custom tooling)
+
+use std::error::Error;
+use std::io::{self, Write};
+use colored::Colorize;
+use crate::macho::load_commands::LoadCommand;
+use crate::macho::utils;
+use crate::reporting::notes::NoteReport;
+
+/*
+note_command memory layout (from loader.h)
++-----------------------------+
+| cmd (u32)                   |  LC_NOTE
+| cmdsize (u32)               |  always 40
+| data_owner (char[16])       |  who the data is for, NUL padded ("addrable bits", ...)
+| offset (u64)                |  file offset of the data (slice relative)
+| size (u64)                  |  length of the data
++-----------------------------+
+
+The format of the data is up to its owner, so all we can do is show where it is and what the
+first bytes look like. Core files from lldb / the kernel are where these show up:
+"addrable bits", "all image infos", "main bin spec", "load binary", ...
+*/
+
+pub const NOTE_COMMAND_SIZE: u32 = 40;
+
+// Enough to recognise a magic or a version field without dumping the whole region
+pub const NOTE_PREVIEW_LEN: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Note {
+    pub data_owner: String,
+    pub offset: u64,
+    pub size: u64,
+}
+
+impl Note {
+    // data is the whole file, the note's offset counts from the start of its slice
+    pub fn build_report(&self, data: &[u8], slice_offset: u64) -> NoteReport {
+        let preview = slice_offset
+            .checked_add(self.offset)
+            .and_then(|start| {
+                let end = start.checked_add(self.size.min(NOTE_PREVIEW_LEN as u64))?;
+                data.get(start as usize..end as usize)
+            })
+            .map(utils::hex_string);
+
+        NoteReport {
+            data_owner: self.data_owner.clone(),
+            offset: self.offset,
+            size: self.size,
+            preview,
+        }
+    }
+}
+
+pub fn parse_note(data: &[u8], lc: &LoadCommand, is_be: bool) -> Result<Note, Box<dyn Error>> {
+    if lc.cmdsize != NOTE_COMMAND_SIZE {
+        return Err(format!("LC_NOTE has cmdsize {} (expected {})", lc.cmdsize, NOTE_COMMAND_SIZE).into());
+    }
+
+    let base = lc.offset as usize;
+    if base + NOTE_COMMAND_SIZE as usize > data.len() {
+        return Err("LC_NOTE exceeds file bounds".into());
+    }
+
+    let owner: [u8; 16] = data[base + 8..base + 24].try_into()?;
+    Ok(Note {
+        data_owner: utils::byte_array_to_string(&owner),
+        offset: utils::bytes_to(is_be, &data[base + 24..])?,
+        size: utils::bytes_to(is_be, &data[base + 32..])?,
+    })
+}
+
+pub fn write_notes(w: &mut dyn Write, notes: &[NoteReport]) -> io::Result<()> {
+    if notes.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(w)?;
+    writeln!(w, "{}", "Notes".green().bold())?;
+    writeln!(w, "----------------------------------------")?;
+    for note in notes {
+        writeln!(w, "{:<18} offset=0x{:x} size=0x{:x}", note.data_owner.cyan(), note.offset, note.size)?;
+        match &note.preview {
+            Some(preview) if !preview.is_empty() => writeln!(w, "    {}", preview.dimmed())?,
+            Some(_) => {}
+            None => writeln!(w, "    {}", "(data lies outside the file)".red())?,
+        }
+    }
+    writeln!(w, "----------------------------------------")?;
+    Ok(())
+}
+
+
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macho::constants::LC_NOTE;
+
+    fn note_command(owner: &[u8], offset: u64, size: u64) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&LC_NOTE.to_le_bytes());
+        data.extend_from_slice(&NOTE_COMMAND_SIZE.to_le_bytes());
+        let mut padded = [0u8; 16];
+        padded[..owner.len()].copy_from_slice(owner);
+        data.extend_from_slice(&padded);
+        data.extend_from_slice(&offset.to_le_bytes());
+        data.extend_from_slice(&size.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn owner_is_trimmed_and_fields_are_read() {
+        let data = note_command(b"addrable bits", 0x28, 0x10);
+        let lc = LoadCommand { cmd: LC_NOTE, cmdsize: NOTE_COMMAND_SIZE, offset: 0 };
+        let note = parse_note(&data, &lc, false).unwrap();
+
+        assert_eq!(note, Note { data_owner: "addrable bits".into(), offset: 0x28, size: 0x10 });
+        assert!(parse_note(&data, &LoadCommand { cmdsize: 32, ..lc }, false).is_err());
+        assert!(parse_note(&data[..36], &lc, false).is_err());
+    }
+
+    #[test]
+    fn preview_is_capped_and_slice_relative() {
+        let mut data = vec![0u8; 0x10];
+        data.extend((0..64).map(|b| b as u8));
+
+        let note = Note { data_owner: "all image infos".into(), offset: 0, size: 64 };
+        let preview = note.build_report(&data, 0x10).preview.unwrap();
+        assert_eq!(preview.split(' ').count(), NOTE_PREVIEW_LEN);
+        assert!(preview.starts_with("00 01 02"));
+
+        let short = Note { size: 2, ..note.clone() }.build_report(&data, 0x10);
+        assert_eq!(short.preview.as_deref(), Some("00 01"));
+
+        let outside = Note { offset: 0x1000, ..note }.build_report(&data, 0x10);
+        assert_eq!(outside.preview, None);
+    }
+}
@@ -0,0 +1,826 @@
+// File Purpose: Objective-C runtime metadata (categories and protocols) read out of the VM image
+
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+use colored::Colorize;
+use crate::macho::constants::*;
+use crate::macho::dyld::Fixup;
+use crate::macho::memory_image::{MachOMemoryImage, PointerDecoder, StoredPointer};
+use crate::macho::segments::ParsedSegment;
+use crate::macho::symtab::{ParsedSymbol, SymbolKind};
+use crate::reporting::objc::{ObjCCategoriesReport, ObjCCategoryReport, ObjCMethodReport, ObjCProtocolReport, ObjCProtocolsReport};
+
+
+/*
+From objc4 (objc-runtime-new.h), every field is a pointer sized value:
+
+struct category_t {
+    const char *name;
+    classref_t cls;                         // the class being extended
+    method_list_t *instanceMethods;
+    method_list_t *classMethods;
+    protocol_list_t *protocols;
+    property_list_t *instanceProperties;
+};
+
+struct method_list_t {
+    uint32_t entsizeAndFlags;               // low 2 bits and high 16 bits are flags
+    uint32_t count;
+    method_t first;                         // count entries of entsize bytes follow
+};
+
+Two method_t layouts exist:
+    big   --> { SEL name; const char *types; IMP imp; }         (pointers)
+    small --> { int32 name; int32 types; int32 imp; }           (offsets relative to each field)
+              name points at a selref, which in turn points at the selector string
+
+__objc_catlist itself is just an array of category_t pointers.
+
+struct protocol_t {
+    Class isa;                              // bound to Protocol, or 0
+    const char *mangledName;                // Swift protocols keep their _TtP... name
+    protocol_list_t *protocols;             // adopted protocols
+    method_list_t *instanceMethods;         // required
+    method_list_t *classMethods;            // required
+    method_list_t *optionalInstanceMethods;
+    method_list_t *optionalClassMethods;
+    property_list_t *instanceProperties;
+    uint32_t size; uint32_t flags; ...      // newer fields, not needed here
+};
+
+struct protocol_list_t {
+    uintptr_t count;                        // a plain integer, never a fixup
+    protocol_t *list[count];
+};
+
+__objc_protolist lists the protocols this image defines, __objc_protorefs the ones its code
+references (@protocol(Foo)). Both usually point at the same protocol_t, and since every image
+carries its own copy of the protocols it uses, dyld uniques them by name at load time and swaps
+the refs over to the canonical one. We do the same on the way in: one entry per address, and only
+the first definition of a name.
+*/
+
+const METHOD_LIST_SMALL_FLAG: u32 = 0x8000_0000;
+const METHOD_LIST_FLAGS_MASK: u32 = 0xffff_0003;
+const SMALL_METHOD_SIZE: u64 = 12;
+
+// class_t.data carries flag bits in the low bits, FAST_DATA_MASK in objc4
+const CLASS_DATA_MASK_64: u64 = 0x0000_7fff_ffff_fff8;
+const CLASS_DATA_MASK_32: u64 = 0xffff_fffc;
+
+const OBJC_CLASS_SYMBOL_PREFIX: &str = "_OBJC_CLASS_$_";
+const OBJC_CATEGORY_SYMBOL_PREFIX: &str = "__OBJC_$_CATEGORY_";
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedObjCMethod {
+    pub name: String,
+    pub types: Option<String>,
+    pub imp: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedObjCCategory {
+    pub addr: u64,                      // vmaddr of the category_t
+    pub name: Option<String>,
+    pub class_name: Option<String>,     // None when cls is bound and we couldn't name the import
+    pub instance_methods: Vec<ParsedObjCMethod>,
+    pub class_methods: Vec<ParsedObjCMethod>,
+}
+
+impl ParsedObjCCategory {
+    pub fn build_report(&self) -> ObjCCategoryReport {
+        ObjCCategoryReport {
+            name: self.name.clone(),
+            class_name: self.class_name.clone(),
+            addr: self.addr,
+            addr_hex: format!("{:#x}", self.addr),
+            instance_methods: self.instance_methods.iter().map(build_method_report).collect(),
+            class_methods: self.class_methods.iter().map(build_method_report).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedObjCProtocol {
+    pub addr: u64,                      // vmaddr of the protocol_t
+    pub name: Option<String>,
+    pub protocols: Vec<String>,         // adopted, by name
+    pub instance_methods: Vec<ParsedObjCMethod>,
+    pub class_methods: Vec<ParsedObjCMethod>,
+    pub optional_instance_methods: Vec<ParsedObjCMethod>,
+    pub optional_class_methods: Vec<ParsedObjCMethod>,
+}
+
+impl ParsedObjCProtocol {
+    pub fn build_report(&self) -> ObjCProtocolReport {
+        ObjCProtocolReport {
+            name: self.name.clone(),
+            addr: self.addr,
+            addr_hex: format!("{:#x}", self.addr),
+            protocols: self.protocols.clone(),
+            instance_methods: self.instance_methods.iter().map(build_method_report).collect(),
+            class_methods: self.class_methods.iter().map(build_method_report).collect(),
+            optional_instance_methods: self.optional_instance_methods.iter().map(build_method_report).collect(),
+            optional_class_methods: self.optional_class_methods.iter().map(build_method_report).collect(),
+        }
+    }
+}
+
+fn build_method_report(method: &ParsedObjCMethod) -> ObjCMethodReport {
+    ObjCMethodReport {
+        name: method.name.clone(),
+        types: method.types.clone(),
+        imp: method.imp,
+    }
+}
+
+
+pub struct ObjCContext<'a> {
+    image: &'a MachOMemoryImage,
+    segments: &'a [ParsedSegment],
+    pointers: PointerDecoder<'a>,
+    is_64: bool,
+    binds: HashMap<u64, String>,        // pointer slot vmaddr --> bound symbol (classic binds only)
+    symbols: HashMap<u64, String>,      // vmaddr --> defined symbol name
+}
+
+impl<'a> ObjCContext<'a> {
+    pub fn new(
+        image: &'a MachOMemoryImage,
+        segments: &'a [ParsedSegment],
+        cputype: i32,
+        cpusubtype: i32,
+        fixups: &[Fixup],
+        symbols: &[ParsedSymbol],
+    ) -> Self {
+        let binds = fixups
+            .iter()
+            .filter_map(|f| match f {
+                Fixup::Bind { addr, symbol, .. }
+                | Fixup::WeakBind { addr, symbol, .. }
+                | Fixup::LazyBind { addr, symbol, .. } => Some((*addr, symbol.clone())),
+                Fixup::Rebase { .. } => None,
+            })
+            .collect();
+
+        let symbols = symbols
+            .iter()
+            .filter(|s| !s.is_debug && matches!(s.kind, SymbolKind::Section))
+            .map(|s| (s.addr, s.name.clone()))
+            .collect();
+
+        let pointers = PointerDecoder::new(segments, cputype, cpusubtype);
+        Self {
+            image,
+            segments,
+            is_64: pointers.is_64(),
+            pointers,
+            binds,
+            symbols,
+        }
+    }
+
+    fn ptr_size(&self) -> u64 {
+        if self.is_64 { 8 } else { 4 }
+    }
+
+    // Read the pointer slot at vmaddr and decode it
+    fn read_pointer(&self, slot: u64) -> StoredPointer {
+        match self.image.read_ptr(slot, self.is_64) {
+            Some(raw) => self.pointers.decode(raw),
+            None => StoredPointer::Invalid,
+        }
+    }
+
+    fn read_string_at(&self, slot: u64) -> Option<String> {
+        match self.read_pointer(slot) {
+            StoredPointer::Address(addr) => self.image.read_c_string(addr),
+            _ => None,
+        }
+    }
+
+    pub fn parse_categories(&self) -> Vec<ParsedObjCCategory> {
+        // Lives in __DATA_CONST on modern toolchains, __DATA on older ones, so match on the section name only
+        let catlists = self
+            .segments
+            .iter()
+            .flat_map(|seg| seg.sections.iter())
+            .filter(|sect| sect.sectname == SECT_OBJC_CATLIST);
+
+        let mut categories = Vec::new();
+        for catlist in catlists {
+            let Some(bytes) = self.image.read_section(catlist) else {
+                continue;
+            };
+
+            for i in 0..(bytes.len() as u64 / self.ptr_size()) {
+                if let StoredPointer::Address(addr) = self.read_pointer(catlist.addr + i * self.ptr_size()) {
+                    categories.push(self.parse_category(addr));
+                }
+            }
+        }
+
+        categories
+    }
+
+    fn parse_category(&self, addr: u64) -> ParsedObjCCategory {
+        let ptr = self.ptr_size();
+
+        ParsedObjCCategory {
+            addr,
+            name: self.read_string_at(addr),
+            class_name: self.category_class_name(addr),
+            instance_methods: self.parse_method_list(addr + 2 * ptr),
+            class_methods: self.parse_method_list(addr + 3 * ptr),
+        }
+    }
+
+    pub fn parse_protocols(&self) -> Vec<ParsedObjCProtocol> {
+        // Definitions first so a protocol reached both ways is reported from its protolist entry
+        let lists = [SECT_OBJC_PROTOLIST, SECT_OBJC_PROTLIST, SECT_OBJC_PROTOREFS].into_iter().flat_map(|sectname| {
+            self.segments
+                .iter()
+                .flat_map(|seg| seg.sections.iter())
+                .filter(move |sect| sect.sectname == sectname)
+        });
+
+        let mut seen_addrs = HashSet::new();
+        let mut seen_names = HashSet::new();
+        let mut protocols = Vec::new();
+        for list in lists {
+            let Some(bytes) = self.image.read_section(list) else {
+                continue;
+            };
+
+            for i in 0..(bytes.len() as u64 / self.ptr_size()) {
+                // A bound ref, or one into another image (shared cache), has no protocol_t here to read
+                let StoredPointer::Address(addr) = self.read_pointer(list.addr + i * self.ptr_size()) else {
+                    continue;
+                };
+                if !seen_addrs.insert(addr) || self.image.read_ptr(addr, self.is_64).is_none() {
+                    continue;
+                }
+
+                let protocol = self.parse_protocol(addr);
+                if let Some(name) = &protocol.name && !seen_names.insert(name.clone()) {
+                    continue;
+                }
+                protocols.push(protocol);
+            }
+        }
+
+        protocols
+    }
+
+    fn parse_protocol(&self, addr: u64) -> ParsedObjCProtocol {
+        let ptr = self.ptr_size();
+
+        ParsedObjCProtocol {
+            addr,
+            name: self.read_string_at(addr + ptr),
+            protocols: self.parse_protocol_list(addr + 2 * ptr),
+            instance_methods: self.parse_method_list(addr + 3 * ptr),
+            class_methods: self.parse_method_list(addr + 4 * ptr),
+            optional_instance_methods: self.parse_method_list(addr + 5 * ptr),
+            optional_class_methods: self.parse_method_list(addr + 6 * ptr),
+        }
+    }
+
+    // Only the names, following adopted protocols any deeper is the reader's job
+    fn parse_protocol_list(&self, slot: u64) -> Vec<String> {
+        let StoredPointer::Address(list) = self.read_pointer(slot) else {
+            return Vec::new();
+        };
+        let Some(count) = self.image.read_ptr(list, self.is_64) else {
+            return Vec::new();
+        };
+
+        let ptr = self.ptr_size();
+        let mut names = Vec::new();
+        for i in 0..count {
+            let entry = list + ptr + i * ptr;
+            // Ran off the image, same as a corrupt method list count
+            if self.image.read_ptr(entry, self.is_64).is_none() {
+                break;
+            }
+            if let StoredPointer::Address(protocol) = self.read_pointer(entry) && let Some(name) = self.read_string_at(protocol + ptr) {
+                names.push(name);
+            }
+        }
+
+        names
+    }
+
+    fn category_class_name(&self, category: u64) -> Option<String> {
+        let slot = category + self.ptr_size();
+
+        // Classic bind on the cls slot --> the symbol names the class (_OBJC_CLASS_$_NSString)
+        if let Some(symbol) = self.binds.get(&slot) {
+            return Some(strip_class_prefix(symbol));
+        }
+
+        let from_class = match self.read_pointer(slot) {
+            StoredPointer::Address(cls) => self
+                .symbols
+                .get(&cls)
+                .map(|s| strip_class_prefix(s))
+                .or_else(|| self.class_name_from_ro(cls)),
+            _ => None,
+        };
+
+        // Chained binds only carry an import ordinal, but an unstripped binary
+        // still names the category_t itself: __OBJC_$_CATEGORY_<Class>_$_<Category>
+        from_class.or_else(|| {
+            self.symbols
+                .get(&category)
+                .and_then(|s| s.strip_prefix(OBJC_CATEGORY_SYMBOL_PREFIX))
+                .and_then(|rest| rest.split_once("_$_"))
+                .map(|(class, _)| class.to_string())
+        })
+    }
+
+    /*
+    class_t { isa; superclass; cache; vtable; data; } --> class_ro_t
+    class_ro_t 64-bit: { u32 flags, instanceStart, instanceSize, reserved; ivarLayout; name; ... }
+    class_ro_t 32-bit: { u32 flags, instanceStart, instanceSize;           ivarLayout; name; ... }
+    */
+    fn class_name_from_ro(&self, cls: u64) -> Option<String> {
+        let ptr = self.ptr_size();
+        let mask = if self.is_64 { CLASS_DATA_MASK_64 } else { CLASS_DATA_MASK_32 };
+
+        // The flag bits ride along in the target, so mask after the fixup is peeled off
+        let StoredPointer::Address(data) = self.read_pointer(cls + 4 * ptr) else {
+            return None;
+        };
+        let ro = data & mask;
+
+        let name_offset = if self.is_64 { 24 } else { 16 };
+        self.read_string_at(ro + name_offset)
+    }
+
+    fn parse_method_list(&self, slot: u64) -> Vec<ParsedObjCMethod> {
+        let StoredPointer::Address(list) = self.read_pointer(slot) else {
+            return Vec::new();
+        };
+
+        let (Some(entsize_and_flags), Some(count)) = (self.image.read_u32(list), self.image.read_u32(list + 4)) else {
+            return Vec::new();
+        };
+
+        let is_small = entsize_and_flags & METHOD_LIST_SMALL_FLAG != 0;
+        let entsize = (entsize_and_flags & !METHOD_LIST_FLAGS_MASK) as u64;
+        let min_entsize = if is_small { SMALL_METHOD_SIZE } else { 3 * self.ptr_size() };
+        if entsize < min_entsize {
+            return Vec::new();
+        }
+
+        let mut methods = Vec::new();
+        for i in 0..count as u64 {
+            let entry = list + 8 + i * entsize;
+            let method = if is_small { self.parse_small_method(entry) } else { self.parse_big_method(entry) };
+
+            // Ran off the end of the image, a corrupt count shouldn't produce garbage entries
+            let Some(method) = method else { break };
+            methods.push(method);
+        }
+
+        methods
+    }
+
+    fn parse_big_method(&self, entry: u64) -> Option<ParsedObjCMethod> {
+        let ptr = self.ptr_size();
+        let name = self.read_string_at(entry)?;
+
+        Some(ParsedObjCMethod {
+            name,
+            types: self.read_string_at(entry + ptr),
+            imp: match self.read_pointer(entry + 2 * ptr) {
+                StoredPointer::Address(imp) => Some(imp),
+                _ => None,
+            },
+        })
+    }
+
+    fn parse_small_method(&self, entry: u64) -> Option<ParsedObjCMethod> {
+        let relative = |field: u64| -> Option<u64> {
+            let off = self.image.read_u32(field)? as i32;
+            (off != 0).then(|| field.wrapping_add_signed(off as i64))
+        };
+
+        // name --> selref --> selector string
+        let selref = relative(entry)?;
+        let name = self.read_string_at(selref)?;
+
+        Some(ParsedObjCMethod {
+            name,
+            types: relative(entry + 4).and_then(|addr| self.image.read_c_string(addr)),
+            imp: relative(entry + 8),
+        })
+    }
+}
+
+fn strip_class_prefix(symbol: &str) -> String {
+    symbol.strip_prefix(OBJC_CLASS_SYMBOL_PREFIX).unwrap_or(symbol).to_string()
+}
+
+pub fn build_categories_report(cpu_type: &str, cpu_subtype: &str, categories: &[ParsedObjCCategory]) -> ObjCCategoriesReport {
+    ObjCCategoriesReport {
+        cpu_type: cpu_type.to_string(),
+        cpu_subtype: cpu_subtype.to_string(),
+        categories: categories.iter().map(|c| c.build_report()).collect(),
+    }
+}
+
+pub fn build_protocols_report(cpu_type: &str, cpu_subtype: &str, protocols: &[ParsedObjCProtocol]) -> ObjCProtocolsReport {
+    ObjCProtocolsReport {
+        cpu_type: cpu_type.to_string(),
+        cpu_subtype: cpu_subtype.to_string(),
+        protocols: protocols.iter().map(|p| p.build_report()).collect(),
+    }
+}
+
+pub fn write_categories_summary(w: &mut dyn Write, report: &ObjCCategoriesReport) -> io::Result<()> {
+    writeln!(w)?;
+    writeln!(w, "{} ({})", "Objective-C Categories".green().bold(), report.cpu_subtype)?;
+    writeln!(w, "----------------------------------------")?;
+
+    if report.categories.is_empty() {
+        writeln!(w, "No categories (no __objc_catlist section)")?;
+        return Ok(());
+    }
+
+    for cat in &report.categories {
+        let class_name = cat.class_name.as_deref().unwrap_or("?");
+        let name = cat.name.as_deref().unwrap_or("?");
+
+        writeln!(w, "{} {}", format!("{}({})", class_name, name).cyan().bold(), cat.addr_hex.dimmed())?;
+        for m in &cat.class_methods {
+            writeln!(w, "    +[{}({}) {}]", class_name, name, m.name)?;
+        }
+        for m in &cat.instance_methods {
+            writeln!(w, "    -[{}({}) {}]", class_name, name, m.name)?;
+        }
+    }
+    Ok(())
+}
+
+
+pub fn write_protocols_summary(w: &mut dyn Write, report: &ObjCProtocolsReport) -> io::Result<()> {
+    writeln!(w)?;
+    writeln!(w, "{} ({})", "Objective-C Protocols".green().bold(), report.cpu_subtype)?;
+    writeln!(w, "----------------------------------------")?;
+
+    if report.protocols.is_empty() {
+        writeln!(w, "No protocols (no __objc_protolist or __objc_protorefs section)")?;
+        return Ok(());
+    }
+
+    for proto in &report.protocols {
+        let adopts = if proto.protocols.is_empty() { String::new() } else { format!(" <{}>", proto.protocols.join(", ")) };
+        writeln!(w, "{}{} {}", format!("@protocol {}", proto.name.as_deref().unwrap_or("?")).cyan().bold(), adopts, proto.addr_hex.dimmed())?;
+
+        for (methods, prefix, optional) in [
+            (&proto.class_methods, '+', false),
+            (&proto.instance_methods, '-', false),
+            (&proto.optional_class_methods, '+', true),
+            (&proto.optional_instance_methods, '-', true),
+        ] {
+            for m in methods {
+                let line = format!("    {}{}", prefix, m.name);
+                if optional {
+                    writeln!(w, "{} {}", line, "@optional".dimmed())?;
+                } else {
+                    writeln!(w, "{}", line)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macho::sections::{ParsedSection, classify_section};
+
+    /*
+    One __TEXT segment holding strings and one __DATA segment right after it.
+    Offsets below are from the image base (the __TEXT vmaddr):
+
+        0x0100  "Foo\0"  "MyCat\0"  "bar\0"  "v16@0:8\0"
+        0x1000  __objc_catlist  [ category_t ]
+        0x1100  category_t
+        0x1200  method_list_t
+        0x1300  class_t --> 0x1400 class_ro_t
+        0x1500  selref (small method lists only)
+        0x0800  the IMP
+
+    64-bit images sit at the usual executable base so chained *_OFFSET targets
+    can't be mistaken for vmaddrs, 32-bit ones at 0x1000.
+    */
+    const BASE_64: u64 = 0x1_0000_0000;
+    const BASE_32: u64 = 0x1000;
+    const DATA: u64 = 0x1000;
+    const CLASS_NAME: u64 = 0x100;
+    const CAT_NAME: u64 = 0x104;
+    const SEL_NAME: u64 = 0x10a;
+    const TYPES: u64 = 0x10e;
+    const IMP: u64 = 0x800;
+    const CATEGORY: u64 = 0x1100;
+    const METHODS: u64 = 0x1200;
+    const CLASS: u64 = 0x1300;
+    const CLASS_RO: u64 = 0x1400;
+    const SELREF: u64 = 0x1500;
+
+    fn segment(segname: [u8; 16], vmaddr: u64, fileoff: u64, sections: Vec<ParsedSection>) -> ParsedSegment {
+        ParsedSegment {
+            segname,
+            vmaddr,
+            vmsize: 0x1000,
+            fileoff,
+            filesize: 0x1000,
+            maxprot: 3,
+            initprot: 3,
+            flags: 0,
+            sections,
+        }
+    }
+
+    fn catlist_section(addr: u64, size: u64) -> ParsedSection {
+        objc_section(SECT_OBJC_CATLIST, DATA, addr, size)
+    }
+
+    fn objc_section(sectname: [u8; 16], offset: u64, addr: u64, size: u64) -> ParsedSection {
+        ParsedSection {
+            sectname,
+            segname: SEG_DATA,
+            offset: offset as u32,
+            addr,
+            size,
+            align: 3,
+            reloff: 0,
+            nreloc: 0,
+            flags: S_REGULAR,
+            kind: classify_section(sectname, S_REGULAR, SEG_DATA),
+            reserved1: 0,
+            reserved2: 0,
+            reserved3: None,
+        }
+    }
+
+    struct TestImage {
+        base: u64,
+        is_64: bool,
+        file: Vec<u8>,
+    }
+
+    impl TestImage {
+        fn put(&mut self, off: u64, bytes: &[u8]) {
+            let at = off as usize;
+            self.file[at..at + bytes.len()].copy_from_slice(bytes);
+        }
+
+        fn put_ptr(&mut self, off: u64, value: u64) {
+            if self.is_64 {
+                self.put(off, &value.to_le_bytes());
+            } else {
+                self.put(off, &(value as u32).to_le_bytes());
+            }
+        }
+
+        fn put_rel(&mut self, field: u64, target: u64) {
+            self.put(field, &((target as i64 - field as i64) as i32).to_le_bytes());
+        }
+
+        fn segments(&self) -> Vec<ParsedSegment> {
+            let ptr = if self.is_64 { 8 } else { 4 };
+            vec![
+                segment(SEG_TEXT, self.base, 0, Vec::new()),
+                segment(SEG_DATA, self.base + DATA, DATA, vec![catlist_section(self.base + DATA, ptr)]),
+            ]
+        }
+    }
+
+    // encode turns a plain vmaddr into whatever sits on disk (itself, or a chained fixup)
+    fn build_image(is_64: bool, small_methods: bool, encode: impl Fn(u64) -> u64) -> TestImage {
+        let base = if is_64 { BASE_64 } else { BASE_32 };
+        let ptr = if is_64 { 8 } else { 4 };
+        let at = |off: u64| encode(base + off);
+        let mut img = TestImage { base, is_64, file: vec![0u8; 0x2000] };
+
+        img.put(CLASS_NAME, b"Foo\0MyCat\0bar\0v16@0:8\0");
+
+        img.put_ptr(DATA, at(CATEGORY));
+
+        img.put_ptr(CATEGORY, at(CAT_NAME));
+        img.put_ptr(CATEGORY + ptr, at(CLASS));
+        img.put_ptr(CATEGORY + 2 * ptr, at(METHODS));
+
+        if small_methods {
+            img.put(METHODS, &(METHOD_LIST_SMALL_FLAG | 12).to_le_bytes());
+            img.put(METHODS + 4, &1u32.to_le_bytes());
+            img.put_rel(METHODS + 8, SELREF);
+            img.put_rel(METHODS + 12, TYPES);
+            img.put_rel(METHODS + 16, IMP);
+            img.put_ptr(SELREF, at(SEL_NAME));
+        } else {
+            img.put(METHODS, &(3 * ptr as u32).to_le_bytes());
+            img.put(METHODS + 4, &1u32.to_le_bytes());
+            img.put_ptr(METHODS + 8, at(SEL_NAME));
+            img.put_ptr(METHODS + 8 + ptr, at(TYPES));
+            img.put_ptr(METHODS + 8 + 2 * ptr, at(IMP));
+        }
+
+        // class_t.data with a flag bit set, class_ro_t.name
+        img.put_ptr(CLASS + 4 * ptr, at(CLASS_RO) | 1);
+        img.put_ptr(CLASS_RO + if is_64 { 24 } else { 16 }, at(CLASS_NAME));
+
+        img
+    }
+
+    fn expected(base: u64) -> ParsedObjCCategory {
+        ParsedObjCCategory {
+            addr: base + CATEGORY,
+            name: Some("MyCat".into()),
+            class_name: Some("Foo".into()),
+            instance_methods: vec![ParsedObjCMethod {
+                name: "bar".into(),
+                types: Some("v16@0:8".into()),
+                imp: Some(base + IMP),
+            }],
+            class_methods: Vec::new(),
+        }
+    }
+
+    fn categories(img: &TestImage, cputype: i32, fixups: &[Fixup]) -> Vec<ParsedObjCCategory> {
+        let segments = img.segments();
+        let image = MachOMemoryImage::new(&segments, &img.file, 0);
+        ObjCContext::new(&image, &segments, cputype, 0, fixups, &[]).parse_categories()
+    }
+
+    #[test]
+    fn parses_64bit_category_with_pointer_method_list() {
+        let img = build_image(true, false, |addr| addr);
+        assert_eq!(categories(&img, CPU_TYPE_X86_64, &[]), vec![expected(BASE_64)]);
+    }
+
+    #[test]
+    fn parses_32bit_category() {
+        let img = build_image(false, false, |addr| addr);
+        assert_eq!(categories(&img, CPU_TYPE_X86, &[]), vec![expected(BASE_32)]);
+    }
+
+    #[test]
+    fn parses_relative_method_list_behind_chained_pointers() {
+        // DYLD_CHAINED_PTR_64_OFFSET rebase: target is an offset from __TEXT, next = 1 in bits 51..62
+        let img = build_image(true, true, |addr| (addr - BASE_64) | (1 << 51));
+        assert_eq!(categories(&img, CPU_TYPE_ARM64, &[]), vec![expected(BASE_64)]);
+    }
+
+    #[test]
+    fn bound_class_is_named_by_its_bind() {
+        let mut img = build_image(true, false, |addr| addr);
+        img.put_ptr(CATEGORY + 8, 0);
+
+        let binds = vec![Fixup::Bind { addr: BASE_64 + CATEGORY + 8, symbol: "_OBJC_CLASS_$_NSString".into(), addend: 0 }];
+        let found = categories(&img, CPU_TYPE_X86_64, &binds);
+
+        assert_eq!(found[0].class_name.as_deref(), Some("NSString"));
+    }
+
+    #[test]
+    fn chained_bind_falls_back_to_category_symbol() {
+        let mut img = build_image(true, false, |addr| addr);
+        img.put_ptr(CATEGORY + 8, 1 << 63); // DYLD_CHAINED_PTR_64 bind, ordinal 0
+
+        let segments = img.segments();
+        let image = MachOMemoryImage::new(&segments, &img.file, 0);
+        let mut ctx = ObjCContext::new(&image, &segments, CPU_TYPE_X86_64, 0, &[], &[]);
+        assert_eq!(ctx.parse_categories()[0].class_name, None);
+
+        ctx.symbols.insert(BASE_64 + CATEGORY, "__OBJC_$_CATEGORY_NSString_$_MyCat".into());
+        assert_eq!(ctx.parse_categories()[0].class_name.as_deref(), Some("NSString"));
+    }
+
+    #[test]
+    fn no_catlist_means_no_categories() {
+        let img = build_image(true, false, |addr| addr);
+        let mut segments = img.segments();
+        segments[1].sections.clear();
+
+        let image = MachOMemoryImage::new(&segments, &img.file, 0);
+        assert!(ObjCContext::new(&image, &segments, CPU_TYPE_X86_64, 0, &[], &[]).parse_categories().is_empty());
+    }
+
+    /*
+    Protocols reuse the category image (its strings and METHODS list) and add:
+
+        0x0120  "MyProto\0"  "NSObject\0"
+        0x1600  __objc_protolist  [ MyProto ]
+        0x1610  __objc_protorefs  [ MyProto, NSObject ]
+        0x1700  protocol_t MyProto <NSObject>, required -bar, optional -bar
+        0x1800  protocol_list_t [ NSObject ]
+        0x1a00  protocol_t NSObject
+        0x1b00  protocol_t, a second copy of MyProto
+    */
+    const PROTO_NAME: u64 = 0x120;
+    const NSOBJECT_NAME: u64 = 0x128;
+    const PROTOLIST: u64 = 0x1600;
+    const PROTOREFS: u64 = 0x1610;
+    const PROTOCOL: u64 = 0x1700;
+    const PROTOCOL_LIST: u64 = 0x1800;
+    const NSOBJECT: u64 = 0x1a00;
+    const PROTOCOL_COPY: u64 = 0x1b00;
+
+    fn protocol_image(is_64: bool, encode: impl Fn(u64) -> u64) -> (TestImage, Vec<ParsedSegment>) {
+        let mut img = build_image(is_64, false, &encode);
+        let base = img.base;
+        let ptr = if is_64 { 8 } else { 4 };
+        let at = |off: u64| encode(base + off);
+
+        img.put(PROTO_NAME, b"MyProto\0NSObject\0");
+
+        img.put_ptr(PROTOLIST, at(PROTOCOL));
+        img.put_ptr(PROTOREFS, at(PROTOCOL));
+        img.put_ptr(PROTOREFS + ptr, at(NSOBJECT));
+        img.put_ptr(PROTOREFS + 2 * ptr, at(PROTOCOL_COPY));
+
+        img.put_ptr(PROTOCOL + ptr, at(PROTO_NAME));
+        img.put_ptr(PROTOCOL + 2 * ptr, at(PROTOCOL_LIST));
+        img.put_ptr(PROTOCOL + 3 * ptr, at(METHODS));
+        img.put_ptr(PROTOCOL + 5 * ptr, at(METHODS));
+
+        img.put_ptr(PROTOCOL_LIST, 1);
+        img.put_ptr(PROTOCOL_LIST + ptr, at(NSOBJECT));
+        img.put_ptr(NSOBJECT + ptr, at(NSOBJECT_NAME));
+        img.put_ptr(PROTOCOL_COPY + ptr, at(PROTO_NAME));
+
+        let mut segments = img.segments();
+        segments[1].sections.push(objc_section(SECT_OBJC_PROTOREFS, PROTOREFS, base + PROTOREFS, 3 * ptr));
+        segments[1].sections.push(objc_section(SECT_OBJC_PROTOLIST, PROTOLIST, base + PROTOLIST, ptr));
+        (img, segments)
+    }
+
+    fn protocols(img: &TestImage, segments: &[ParsedSegment], cputype: i32) -> Vec<ParsedObjCProtocol> {
+        let image = MachOMemoryImage::new(segments, &img.file, 0);
+        ObjCContext::new(&image, segments, cputype, 0, &[], &[]).parse_protocols()
+    }
+
+    fn expected_protocols(base: u64) -> Vec<ParsedObjCProtocol> {
+        let bar = expected(base).instance_methods;
+        vec![
+            ParsedObjCProtocol {
+                addr: base + PROTOCOL,
+                name: Some("MyProto".into()),
+                protocols: vec!["NSObject".into()],
+                instance_methods: bar.clone(),
+                class_methods: Vec::new(),
+                optional_instance_methods: bar,
+                optional_class_methods: Vec::new(),
+            },
+            ParsedObjCProtocol {
+                addr: base + NSOBJECT,
+                name: Some("NSObject".into()),
+                protocols: Vec::new(),
+                instance_methods: Vec::new(),
+                class_methods: Vec::new(),
+                optional_instance_methods: Vec::new(),
+                optional_class_methods: Vec::new(),
+            },
+        ]
+    }
+
+    #[test]
+    fn protocols_are_read_from_protolist_and_protorefs() {
+        // MyProto is in both lists and has a second copy, each protocol comes out once
+        let (img, segments) = protocol_image(true, |addr| addr);
+        assert_eq!(protocols(&img, &segments, CPU_TYPE_X86_64), expected_protocols(BASE_64));
+
+        let (img, segments) = protocol_image(false, |addr| addr);
+        assert_eq!(protocols(&img, &segments, CPU_TYPE_X86), expected_protocols(BASE_32));
+    }
+
+    #[test]
+    fn protocols_behind_chained_pointers() {
+        let (img, segments) = protocol_image(true, |addr| (addr - BASE_64) | (1 << 51));
+        assert_eq!(protocols(&img, &segments, CPU_TYPE_ARM64), expected_protocols(BASE_64));
+    }
+
+    #[test]
+    fn refs_outside_the_image_are_skipped() {
+        let (mut img, segments) = protocol_image(true, |addr| addr);
+        img.put_ptr(PROTOREFS + 8, 0x7fff_0000_0000); // uniqued into some other image
+        img.put_ptr(PROTOREFS + 16, 1 << 63); // chained bind
+
+        let found = protocols(&img, &segments, CPU_TYPE_X86_64);
+        assert_eq!(found.iter().map(|p| p.name.as_deref()).collect::<Vec<_>>(), [Some("MyProto")]);
+    }
+}
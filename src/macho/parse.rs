@@ -0,0 +1,111 @@
+// File Purpose: The one-call library API, Mach-O bytes in, every slice parsed out
+
+use crate::macho::analysis::{self, ParsedSlice, SliceOptions};
+use crate::macho::compression;
+use crate::macho::error::MoscopeError;
+use crate::macho::fat::FatArch;
+use crate::macho::header::MachOSlice;
+use crate::macho::uuid::{self, ParsedUuid};
+use crate::reporting::macho::{ArchitectureReport, MachOReport, ReportOptions, build_macho_report};
+
+/*
+What a library user wants most of the time: hand over the bytes, get the parsed file back.
+
+    let data = std::fs::read("/bin/ls")?;
+    let file = MachOFile::parse(&data)?;
+    for slice in &file.slices {
+        println!("{} dylibs, {} symbols", slice.dylibs.len(), slice.symbols.len());
+    }
+
+A thin file has one slice at offset 0, a fat one a slice per fat_arch in header order. Each
+slice is the same ParsedSlice the CLI reports on (header, load commands, segments, dylibs,
+rpaths, symbols, strings, ...), report() turns the lot into the MachOReport `--format json`
+prints. The CLI goes through here too, with the slices the user picked.
+*/
+#[derive(Debug)]
+pub struct MachOFile {
+    pub fat_archs: Option<Vec<FatArch>>, // None for a thin file
+    pub slices: Vec<ParsedSlice>,
+}
+
+impl MachOFile {
+    // Every slice with the default SliceOptions (same as the CLI without any flags)
+    pub fn parse(data: &[u8]) -> Result<MachOFile, MoscopeError> {
+        Self::parse_with(data, &SliceOptions::default())
+    }
+
+    pub fn parse_with(data: &[u8], opts: &SliceOptions) -> Result<MachOFile, MoscopeError> {
+        if data.len() < 4 {
+            return Err(MoscopeError::TooSmall { needed: 4, available: data.len() });
+        }
+        if !compression::has_macho_or_fat_magic(data) {
+            return Err(MoscopeError::BadMagic(u32::from_be_bytes([data[0], data[1], data[2], data[3]])));
+        }
+
+        let fat_archs = analysis::detect_fat_archs(data)?;
+        let slices = analysis::all_slices(fat_archs.as_deref());
+        Self::parse_slices(data, fat_archs, slices, opts)
+    }
+
+    // Only the given slices, the fat archs are kept whole for fat_align and is_fat
+    pub fn parse_slices(data: &[u8], fat_archs: Option<Vec<FatArch>>, slices: Vec<MachOSlice>, opts: &SliceOptions) -> Result<MachOFile, MoscopeError> {
+        let slices = slices
+            .iter()
+            .map(|slice| analysis::parse_slice(data, slice, opts))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(MachOFile { fat_archs, slices })
+    }
+
+    pub fn is_fat(&self) -> bool {
+        self.fat_archs.is_some()
+    }
+
+    // `data` has to be the bytes this was parsed from, strings and code signatures are read again
+    pub fn report(&self, data: &[u8], opts: &ReportOptions) -> Result<MachOReport, MoscopeError> {
+        let architecture_reports = self.slices.iter().map(|parsed| self.slice_report(parsed, data, opts)).collect::<Result<Vec<_>, _>>()?;
+        Ok(self.report_from(architecture_reports, opts))
+    }
+
+    // One entry of report()'s architectures, for callers that want to time each slice on its own
+    pub fn slice_report(&self, parsed: &ParsedSlice, data: &[u8], opts: &ReportOptions) -> Result<ArchitectureReport, MoscopeError> {
+        let mut arch_report = parsed.build_report(data, opts)?;
+        arch_report.fat_align = self.fat_archs.as_deref().unwrap_or_default().iter().find(|a| a.offset() == parsed.slice.offset).map(FatArch::align);
+        Ok(arch_report)
+    }
+
+    // The rest of report(), given slice_report() of every slice in order
+    pub fn report_from(&self, architecture_reports: Vec<ArchitectureReport>, opts: &ReportOptions) -> MachOReport {
+        // Cross-slice checks only mean something when every slice was analyzed
+        let fingerprints: Vec<uuid::SliceFingerprint> = architecture_reports
+            .iter()
+            .zip(&self.slices)
+            .map(|(arch, parsed)| uuid::SliceFingerprint {
+                arch: arch.cpu_subtype.clone(),
+                uuid: parsed.uuid,
+                dylibs: &parsed.dylibs,
+            })
+            .collect();
+        let slice_consistency = uuid::check_slice_consistency(&fingerprints);
+        let uuids: Vec<ParsedUuid> = self.slices.iter().filter_map(|p| p.uuid).collect();
+
+        build_macho_report(self.is_fat(), architecture_reports, &uuids, &slice_consistency, opts)
+    }
+}
+
+
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_or_foreign_input_is_a_typed_error() {
+        assert_eq!(MachOFile::parse(b"MZ").unwrap_err(), MoscopeError::TooSmall { needed: 4, available: 2 });
+        assert_eq!(MachOFile::parse(b"\x7fELF\x02\x01\x01\x00").unwrap_err(), MoscopeError::BadMagic(0x7f454c46));
+    }
+}
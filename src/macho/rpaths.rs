@@ -1,8 +1,9 @@
 // File Purpose: Where is the dynamic loader looking for libraries at runtime?
 
 use std::error::Error;
-use crate::macho::load_commands::{LoadCommand, load_command_name};
-use crate::macho::utils;
+use std::io::{self, Write};
+use crate::macho::load_commands::{LoadCommand, load_command_name, read_lc_str};
+use crate::macho::diagnostics::Diagnostic;
 use colored::Colorize;
 use crate::reporting::rpaths::RPathsReport;
 
@@ -13,56 +14,183 @@ pub struct ParsedRPath {
 }
 
 impl ParsedRPath {
-    pub fn build_report(&self, _is_json: bool) -> RPathsReport {
+    pub fn build_report(&self) -> RPathsReport {
         RPathsReport { source_lc: load_command_name(self.source_lc.cmd).to_string(), path: self.path.clone() }
     }
 }
 
 
 pub fn parse_rpath(data: &[u8], lc: &LoadCommand, is_be: bool) -> Result<ParsedRPath, Box<dyn Error>> {
-    // Check bounds
-    let base = lc.offset as usize;
-    let end = base + lc.cmdsize as usize;
+    // Same lc_str as a dylib's name, right after cmd & cmdsize
+    let rpath = read_lc_str(data, lc, 8, is_be)?;
+    Ok(ParsedRPath { source_lc: *lc, path: rpath })
+}
+
+/*
+LC_DYLD_ENVIRONMENT is a dylinker_command (one lc_str at +8) holding "NAME=value", which dyld
+applies as if it were set in the environment, only DYLD_*_PATH variables are honored and only
+for the main executable. DYLD_LIBRARY_PATH / DYLD_FRAMEWORK_PATH baked into a binary change
+where every dependency is looked up, so they're as interesting as a writable LC_RPATH.
+A binary can carry any number of them, each command is one variable.
+*/
+pub fn parse_dyld_environment(data: &[u8], lc: &LoadCommand, is_be: bool) -> Result<String, Box<dyn Error>> {
+    Ok(read_lc_str(data, lc, 8, is_be)?)
+}
 
-    if end > data.len() {
-        return Err("LC_RPATH exceeds file bounds".into());
+
+/*
+dyld walks the LC_RPATH list in order whenever it sees an @rpath/ install name, first hit wins.
+That makes the list a classic place to plant a dylib:
+    - absolute paths outside the SIP protected system locations (/usr/local/lib, /opt/..., /tmp)
+      can often be written to by a non-root user
+    - entries not anchored at @loader_path / @executable_path (plain "lib", "../Frameworks", "@rpath/..")
+      get resolved against the current working directory or simply never resolve
+    - duplicates are harmless on their own but usually point at a sloppy build script
+*/
+const PROTECTED_RPATH_PREFIXES: [&str; 2] = ["/usr/lib/", "/System/"];
+
+pub fn validate_rpaths(rpaths: &[ParsedRPath]) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut seen: Vec<&str> = Vec::new();
+
+    for rpath in rpaths {
+        let path = rpath.path.as_str();
+
+        if seen.contains(&path) {
+            diagnostics.push(Diagnostic::warning(
+                "rpath-duplicate",
+                format!("LC_RPATH {} appears more than once", path),
+            ));
+            continue; // already judged the first copy
+        }
+        seen.push(path);
+
+        if path.starts_with('/') {
+            if !PROTECTED_RPATH_PREFIXES.iter().any(|p| path.starts_with(p)) {
+                diagnostics.push(Diagnostic::warning(
+                    "rpath-absolute",
+                    format!("LC_RPATH {} is an absolute path outside the bundle (dylib hijacking risk if writable)", path),
+                ));
+            }
+        } else if !(path.starts_with("@loader_path") || path.starts_with("@executable_path")) {
+            diagnostics.push(Diagnostic::warning(
+                "rpath-not-anchored",
+                format!("LC_RPATH {} is not relative to @loader_path or @executable_path", path),
+            ));
+        }
     }
 
+    diagnostics
+}
 
+// --dedup-rpaths, keeps the first occurrence since that's the one dyld would search first
+pub fn dedup_rpaths(rpaths: &mut Vec<ParsedRPath>) {
+    let mut seen: Vec<String> = Vec::new();
+    rpaths.retain(|rpath| {
+        if seen.contains(&rpath.path) {
+            false
+        } else {
+            seen.push(rpath.path.clone());
+            true
+        }
+    });
+}
 
-    // We can reuse pretty much all of the dylib reading code here
-    let path_offset: u32 = utils::bytes_to(is_be, &data[base + 8..])?; // start at plus 8 to skip cmd & cmdsize
-    let string_start = base + path_offset as usize;
-    let string_end = base + lc.cmdsize as usize; 
 
-    
-    if string_start >= string_end || string_end > data.len() {
-        return Err("RPATH path offset exceeds file bounds".into());
+pub fn write_rpaths_summary(w: &mut dyn Write, rpaths: &[RPathsReport]) -> io::Result<()> {
+    if rpaths.is_empty() {
+        return Ok(());
     }
 
-    let string_bytes = &data[string_start..string_end];
+    writeln!(w, "{}", "\nRPATHs".green().bold())?;
+    writeln!(w, "----------------------------------------")?;
 
-    let first_null_byte = match string_bytes.iter().position(|&byte| byte == 0) {
-        Some(pos) => pos,
-        None => return Err("Unterminated RPATH path string".into()),
-    };
+    for rpath in rpaths {
+        writeln!(w, "[{}] {}", "RPATH".yellow().bold(), rpath.path)?;
+    }
+    Ok(())
+}
 
-    let rpath = String::from_utf8_lossy(&string_bytes[..first_null_byte]).to_string();
+pub fn write_dyld_environment(w: &mut dyn Write, environment: &[String]) -> io::Result<()> {
+    if environment.is_empty() {
+        return Ok(());
+    }
 
-    Ok(ParsedRPath { source_lc: *lc, path: rpath })
+    writeln!(w, "{}", "\ndyld Environment".green().bold())?;
+    writeln!(w, "----------------------------------------")?;
 
+    // Changes library search for the whole process, hence the warning color
+    for variable in environment {
+        writeln!(w, "[{}] {}", "ENV".red().bold(), variable.red())?;
+    }
+    Ok(())
 }
 
 
-pub fn print_rpaths_summary(rpaths: &Vec<ParsedRPath>) {
-    if rpaths.is_empty() {
-        return;
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macho::constants::{LC_DYLD_ENVIRONMENT, LC_RPATH};
+
+    fn rpath(path: &str) -> ParsedRPath {
+        ParsedRPath {
+            source_lc: LoadCommand { cmd: LC_RPATH, cmdsize: 0, offset: 0 },
+            path: path.to_string(),
+        }
     }
 
-    println!("{}", "\nRPATHs".green().bold());
-    println!("----------------------------------------");
+    fn codes(rpaths: &[ParsedRPath]) -> Vec<&'static str> {
+        validate_rpaths(rpaths).iter().map(|d| d.code).collect()
+    }
 
-    for rpath in rpaths {
-        println!("[{}] {}", "RPATH".yellow().bold(), rpath.path);
+    #[test]
+    fn anchored_and_system_rpaths_are_clean() {
+        let rpaths = vec![
+            rpath("@executable_path/../Frameworks"),
+            rpath("@loader_path/Frameworks"),
+            rpath("/usr/lib/swift"),
+        ];
+        assert!(codes(&rpaths).is_empty());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn flags_duplicate_absolute_and_unanchored_rpaths() {
+        let rpaths = vec![
+            rpath("@loader_path/Frameworks"),
+            rpath("@loader_path/Frameworks"),
+            rpath("/usr/local/lib"),
+            rpath("../lib"),
+            rpath("@rpath/Frameworks"),
+        ];
+        assert_eq!(codes(&rpaths), vec!["rpath-duplicate", "rpath-absolute", "rpath-not-anchored", "rpath-not-anchored"]);
+    }
+
+    #[test]
+    fn dyld_environment_is_read_from_its_lc_str() {
+        let mut data = Vec::new();
+        for value in [LC_DYLD_ENVIRONMENT, 48, 12] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(b"DYLD_LIBRARY_PATH=/tmp/lib\0");
+        data.resize(48, 0);
+        let lc = LoadCommand { cmd: LC_DYLD_ENVIRONMENT, cmdsize: 48, offset: 0 };
+
+        assert_eq!(parse_dyld_environment(&data, &lc, false).unwrap(), "DYLD_LIBRARY_PATH=/tmp/lib");
+        assert!(parse_dyld_environment(&data, &LoadCommand { cmdsize: 12, ..lc }, false).is_err());
+    }
+
+    #[test]
+    fn dedup_keeps_first_occurrence_in_order() {
+        let mut rpaths = vec![rpath("/a"), rpath("@loader_path"), rpath("/a")];
+        dedup_rpaths(&mut rpaths);
+
+        let paths: Vec<&str> = rpaths.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths, vec!["/a", "@loader_path"]);
+    }
+}
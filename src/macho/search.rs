@@ -0,0 +1,606 @@
+// File Purpose: "Where exactly is this string / these bytes?" (--find-string, --find-hex, --bytes-at)
+use std::io::{self, Write};
+use colored::Colorize;
+use crate::macho::constants::SECT_USTRING;
+use crate::macho::sections::{ParsedSection, SectionKind};
+use crate::macho::segments::ParsedSegment;
+use crate::macho::symtab::{extract_string_entries, extract_utf16le_strings};
+use crate::macho::utils;
+use crate::reporting::search::{PeekReport, ScanMatchReport, ScanReport, SearchHitReport, SearchReport};
+
+
+/*
+Dumping every string and grepping tells you THAT something is there, not where.
+A hit here is pinned down three ways:
+    section     --> which [SEG,SECT] it lives in
+    vmaddr      --> section.addr + offset into the section (what a disassembler shows)
+    file_offset --> slice offset + section.offset + same offset (what a hex editor shows),
+                    None for zerofill sections, they have nothing on disk
+
+Section bytes come from the VM image like string extraction does, so the vmaddr is right even
+for dyld extracted binaries (their file offsets aren't, that's the caller's problem to know).
+*/
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    pub segname: [u8; 16],
+    pub sectname: [u8; 16],
+    pub vmaddr: u64,
+    pub file_offset: Option<u64>,
+    pub string: Option<String>, // the whole string the needle was found in, --find-string only
+    pub exact: bool, // the needle is the entire string, not just part of it
+}
+
+impl SearchHit {
+    pub fn build_report(&self) -> SearchHitReport {
+        SearchHitReport {
+            segment: utils::byte_array_to_string(&self.segname),
+            section: utils::byte_array_to_string(&self.sectname),
+            vmaddr: self.vmaddr,
+            vmaddr_hex: format!("0x{:016x}", self.vmaddr),
+            file_offset: self.file_offset,
+            string: self.string.clone(),
+            exact: self.exact,
+        }
+    }
+}
+
+// The sections --find-string looks at, same set string extraction uses
+pub fn is_string_section(section: &ParsedSection) -> bool {
+    section.kind == SectionKind::CString || section.sectname == SECT_USTRING
+}
+
+// Every (overlapping) position of needle in haystack
+pub fn find_all(haystack: &[u8], needle: &[u8]) -> Vec<usize> {
+    if needle.is_empty() || needle.len() > haystack.len() {
+        return Vec::new();
+    }
+
+    haystack
+        .windows(needle.len())
+        .enumerate()
+        .filter(|(_, window)| *window == needle)
+        .map(|(pos, _)| pos)
+        .collect()
+}
+
+fn hit_at(section: &ParsedSection, offset: usize, slice_offset: u64, string: Option<String>, exact: bool) -> SearchHit {
+    SearchHit {
+        segname: section.segname,
+        sectname: section.sectname,
+        vmaddr: section.addr + offset as u64,
+        file_offset: (!section.is_zerofill() && section.offset != 0).then(|| slice_offset + section.offset as u64 + offset as u64),
+        string,
+        exact,
+    }
+}
+
+/*
+Substring search inside each extracted string, matched on the raw bytes rather than the escaped
+display value (so "\n" in the needle isn't needed to find a newline). __ustring is searched with
+the needle encoded as UTF-16LE. One hit per occurrence, so "aa" in "aaaa" is three hits.
+*/
+pub fn find_string_in_section(section: &ParsedSection, section_data: &[u8], needle: &str, slice_offset: u64) -> Vec<SearchHit> {
+    let (strings, needle_bytes) = if section.sectname == SECT_USTRING {
+        let encoded: Vec<u8> = needle.encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect();
+        (extract_utf16le_strings(section_data, 1), encoded)
+    } else {
+        (extract_string_entries(section_data, 1, false), needle.as_bytes().to_vec())
+    };
+
+    let mut hits = Vec::new();
+    for s in strings {
+        let exact = s.raw == needle_bytes;
+        for pos in find_all(&s.raw, &needle_bytes) {
+            hits.push(hit_at(section, s.offset + pos, slice_offset, Some(s.value.clone()), exact));
+        }
+    }
+
+    hits
+}
+
+// --find-hex: raw bytes anywhere in the section, no string boundaries involved
+pub fn find_bytes_in_section(section: &ParsedSection, section_data: &[u8], pattern: &[u8], slice_offset: u64) -> Vec<SearchHit> {
+    find_all(section_data, pattern)
+        .into_iter()
+        .map(|pos| hit_at(section, pos, slice_offset, None, false))
+        .collect()
+}
+
+/*
+"de ad be ef", "deadbeef", "DE:AD:BE:EF" and "0xdeadbeef" all mean the same 4 bytes.
+*/
+pub fn parse_hex_bytes(spec: &str) -> Result<Vec<u8>, String> {
+    parse_byte_pattern(spec)?
+        .into_iter()
+        .map(|b| b.ok_or_else(|| format!("'{}' has a ?? wildcard, only --scan-bytes takes those", spec)))
+        .collect()
+}
+
+/*
+--scan-bytes signatures, same spellings as parse_hex_bytes plus ?? for "any byte":
+    "48 8b ?? 48 89"  -->  [Some(0x48), Some(0x8b), None, Some(0x48), Some(0x89)]
+A pattern that's nothing but wildcards would match everywhere, so that's an error.
+*/
+pub fn parse_byte_pattern(spec: &str) -> Result<Vec<Option<u8>>, String> {
+    let digits: String = spec
+        .trim()
+        .trim_start_matches("0x")
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != ':' && *c != ',')
+        .collect();
+
+    if digits.is_empty() {
+        return Err("expected at least one byte of hex".to_string());
+    }
+    // checked up front so the pairs below are always whole characters
+    if let Some(bad) = digits.chars().find(|c| !c.is_ascii_hexdigit() && *c != '?') {
+        return Err(format!("'{}' in '{}' is not a hex digit", bad, spec));
+    }
+    if !digits.len().is_multiple_of(2) {
+        return Err(format!("'{}' has an odd number of hex digits", spec));
+    }
+
+    let pattern = (0..digits.len())
+        .step_by(2)
+        .map(|i| match &digits[i..i + 2] {
+            "??" => Ok(None),
+            pair => u8::from_str_radix(pair, 16).map(Some).map_err(|_| format!("'{}' is not valid hex", pair)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if pattern.iter().all(Option::is_none) {
+        return Err(format!("'{}' is only wildcards, it would match everywhere", spec));
+    }
+
+    Ok(pattern)
+}
+
+/*
+Wildcard aware search without checking the full pattern at every byte:
+    1. anchor on the longest run of concrete bytes in the pattern (ties --> the first one)
+    2. hop between occurrences of the anchor's first byte (position() is a tight loop / memchr)
+    3. only then compare the anchor, and after that the rest of the pattern around it
+For typical signatures the anchor's first byte filters out nearly everything in step 2.
+*/
+pub fn scan_pattern(haystack: &[u8], pattern: &[Option<u8>]) -> Vec<usize> {
+    let mut matches = Vec::new();
+    if pattern.is_empty() || pattern.len() > haystack.len() {
+        return matches;
+    }
+
+    // (start, len) of the longest concrete run
+    let (mut anchor_start, mut anchor_len, mut run_start) = (0, 0, 0);
+    for (i, b) in pattern.iter().enumerate() {
+        if b.is_none() {
+            run_start = i + 1;
+        } else if i + 1 - run_start > anchor_len {
+            (anchor_start, anchor_len) = (run_start, i + 1 - run_start);
+        }
+    }
+    if anchor_len == 0 {
+        return matches; // all wildcards, parse_byte_pattern never hands us this
+    }
+
+    let anchor: Vec<u8> = pattern[anchor_start..anchor_start + anchor_len].iter().map(|b| b.unwrap()).collect();
+    let last_start = haystack.len() - pattern.len(); // last place the whole pattern still fits
+
+    // candidate = where the anchor would sit for a match starting at 0..=last_start
+    let mut pos = anchor_start;
+    while pos <= last_start + anchor_start {
+        let Some(skip) = haystack[pos..=last_start + anchor_start].iter().position(|&b| b == anchor[0]) else { break };
+        pos += skip;
+
+        let start = pos - anchor_start;
+        let fits = haystack[pos..pos + anchor_len] == anchor[..]
+            && pattern.iter().zip(&haystack[start..start + pattern.len()]).all(|(p, h)| p.is_none_or(|p| p == *h));
+        if fits {
+            matches.push(start);
+        }
+        pos += 1;
+    }
+
+    matches
+}
+
+/*
+--scan-bytes works on the raw file, not per section, so every match gets mapped back afterwards:
+    file offset --> slice (fat only) --> segment (fileoff..fileoff+filesize) --> section
+and the VM address comes from the segment, the same way dyld would map it.
+Bytes outside every segment (fat header, padding between slices) only get a file offset.
+*/
+pub struct SliceLayout {
+    pub arch: String,
+    pub slice_offset: u64,
+    pub slice_size: Option<u64>, // None for thin files, the slice is the whole file
+    pub segments: Vec<ParsedSegment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanMatch {
+    pub file_offset: u64,
+    pub arch: Option<String>,
+    pub segname: Option<[u8; 16]>,
+    pub sectname: Option<[u8; 16]>,
+    pub vmaddr: Option<u64>,
+}
+
+impl ScanMatch {
+    pub fn build_report(&self) -> ScanMatchReport {
+        ScanMatchReport {
+            file_offset: self.file_offset,
+            file_offset_hex: format!("0x{:08x}", self.file_offset),
+            arch: self.arch.clone(),
+            segment: self.segname.map(|n| utils::byte_array_to_string(&n)),
+            section: self.sectname.map(|n| utils::byte_array_to_string(&n)),
+            vmaddr: self.vmaddr,
+            vmaddr_hex: self.vmaddr.map(|a| format!("0x{:016x}", a)),
+        }
+    }
+}
+
+pub fn locate_file_offset(file_offset: u64, layouts: &[SliceLayout]) -> ScanMatch {
+    let mut found = ScanMatch { file_offset, arch: None, segname: None, sectname: None, vmaddr: None };
+
+    let in_slice = |l: &&SliceLayout| file_offset >= l.slice_offset && l.slice_size.is_none_or(|size| file_offset < l.slice_offset + size);
+    let Some(layout) = layouts.iter().find(in_slice) else { return found };
+    found.arch = Some(layout.arch.clone());
+
+    for seg in &layout.segments {
+        let seg_start = layout.slice_offset + seg.fileoff;
+        if seg.filesize == 0 || file_offset < seg_start || file_offset >= seg_start + seg.filesize {
+            continue;
+        }
+
+        found.segname = Some(seg.segname);
+        found.vmaddr = Some(seg.vmaddr + (file_offset - seg_start));
+        found.sectname = seg
+            .sections
+            .iter()
+            .filter(|sect| !sect.is_zerofill() && sect.offset != 0)
+            .find(|sect| {
+                let start = layout.slice_offset + sect.offset as u64;
+                file_offset >= start && file_offset < start + sect.size
+            })
+            .map(|sect| sect.sectname);
+        break;
+    }
+
+    found
+}
+
+/*
+--scan-in SEG or SEG,SECT narrows the scan down to those file ranges (in every slice).
+None means the whole file in one go.
+*/
+pub fn scan_ranges(layouts: &[SliceLayout], scope: Option<&str>, file_len: u64) -> Result<Vec<(u64, u64)>, String> {
+    let Some(scope) = scope else { return Ok(vec![(0, file_len)]) };
+    let (segname, sectname) = match scope.split_once(',') {
+        Some((seg, sect)) => (seg.trim(), Some(sect.trim())),
+        None => (scope.trim(), None),
+    };
+
+    let mut ranges = Vec::new();
+    for layout in layouts {
+        for seg in layout.segments.iter().filter(|seg| utils::byte_array_to_string(&seg.segname) == segname) {
+            match sectname {
+                None if seg.filesize > 0 => ranges.push((layout.slice_offset + seg.fileoff, layout.slice_offset + seg.fileoff + seg.filesize)),
+                None => {}
+                Some(sectname) => {
+                    for sect in seg.sections.iter().filter(|sect| utils::byte_array_to_string(&sect.sectname) == sectname) {
+                        if !sect.is_zerofill() && sect.offset != 0 {
+                            let start = layout.slice_offset + sect.offset as u64;
+                            ranges.push((start, start + sect.size));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if ranges.is_empty() {
+        return Err(format!("--scan-in {}: no such segment/section with bytes in the file", scope));
+    }
+
+    // clamp, a lying filesize shouldn't panic the slicing later
+    Ok(ranges.into_iter().map(|(start, end)| (start.min(file_len), end.min(file_len))).collect())
+}
+
+pub fn build_scan_report(pattern: &str, scope: Option<&str>, matches: &[ScanMatch]) -> ScanReport {
+    ScanReport {
+        pattern: pattern.to_string(),
+        scope: scope.map(str::to_string),
+        matches: matches.iter().map(|m| m.build_report()).collect(),
+    }
+}
+
+pub fn write_scan_report(w: &mut dyn Write, report: &ScanReport) -> io::Result<()> {
+    writeln!(w)?;
+    writeln!(
+        w,
+        "{} '{}' in {} ({} matches)",
+        "Byte Scan".green().bold(),
+        report.pattern,
+        report.scope.as_deref().unwrap_or("whole file"),
+        report.matches.len()
+    )?;
+    writeln!(w, "----------------------------------------")?;
+
+    for m in &report.matches {
+        let location = match (&m.segment, &m.section) {
+            (Some(seg), Some(sect)) => format!("[{},{}]", seg, sect),
+            (Some(seg), None) => format!("[{}]", seg),
+            _ => "-".to_string(),
+        };
+        let arch = m.arch.as_deref().unwrap_or("-");
+        let vmaddr = m.vmaddr_hex.as_deref().unwrap_or("-");
+
+        writeln!(w, "file={} {:<18} {:<24} {}", m.file_offset_hex, vmaddr, location, arch)?;
+    }
+
+    writeln!(w, "----------------------------------------")?;
+    Ok(())
+}
+
+pub fn build_search_report(cpu_type: &str, cpu_subtype: &str, needle: &str, hits: &[SearchHit]) -> SearchReport {
+    SearchReport {
+        cpu_type: cpu_type.to_string(),
+        cpu_subtype: cpu_subtype.to_string(),
+        needle: needle.to_string(),
+        hits: hits.iter().map(|h| h.build_report()).collect(),
+    }
+}
+
+pub fn write_search_report(w: &mut dyn Write, report: &SearchReport) -> io::Result<()> {
+    writeln!(w)?;
+    writeln!(w, "{} '{}' ({}, {} hits)", "Search".green().bold(), report.needle, report.cpu_subtype, report.hits.len())?;
+    writeln!(w, "----------------------------------------")?;
+
+    for hit in &report.hits {
+        let file_offset = hit.file_offset.map(|o| format!("0x{:08x}", o)).unwrap_or_else(|| "-".to_string());
+        let location = format!("[{},{}]", hit.segment, hit.section);
+
+        match &hit.string {
+            Some(s) => {
+                let marker = if hit.exact { "exact".green().bold() } else { "substring".normal() };
+                writeln!(w, "{:<24} {} file={} {:<9} {}", location, hit.vmaddr_hex, file_offset, marker, s)?;
+            }
+            None => writeln!(w, "{:<24} {} file={}", location, hit.vmaddr_hex, file_offset)?,
+        }
+    }
+
+    writeln!(w, "----------------------------------------")?;
+    Ok(())
+}
+
+
+/*
+--bytes-at OFFSET --len N, a look at any N bytes of the file with no parsing in the way.
+With --arch the offset is relative to that slice (like every offset inside the Mach-O),
+otherwise it is a plain file offset. The range has to fit in the slice / file either way:
+
+    region_start                          region_start + region_len
+    |------------[offset, offset + len)---|
+*/
+pub fn parse_offset(spec: &str) -> Result<u64, String> {
+    let spec = spec.trim();
+    match spec.strip_prefix("0x").or_else(|| spec.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => spec.parse(),
+    }
+    .map_err(|_| format!("'{}' is not an offset (decimal or 0x hex)", spec))
+}
+
+// Absolute [start, end) of the peeked bytes
+pub fn peek_range(region_start: u64, region_len: u64, offset: u64, len: u64) -> Result<(u64, u64), String> {
+    if len == 0 {
+        return Err("--len must be at least 1".to_string());
+    }
+    match offset.checked_add(len) {
+        Some(end) if end <= region_len => Ok((region_start + offset, region_start + end)),
+        _ => Err(format!("0x{:x}..+0x{:x} is out of bounds (size 0x{:x})", offset, len, region_len)),
+    }
+}
+
+pub fn build_peek_report(arch: Option<&str>, offset: u64, file_offset: u64, bytes: &[u8]) -> PeekReport {
+    PeekReport {
+        arch: arch.map(str::to_string),
+        offset,
+        file_offset,
+        file_offset_hex: format!("0x{:08x}", file_offset),
+        len: bytes.len() as u64,
+        bytes: utils::hex_string(bytes),
+    }
+}
+
+// xxd style, 16 bytes a row with the file offset in front and printable ASCII at the end
+pub fn write_peek_report(w: &mut dyn Write, report: &PeekReport) -> io::Result<()> {
+    writeln!(w)?;
+    let scope = report.arch.as_ref().map(|arch| format!(", {} slice offset 0x{:x}", arch, report.offset)).unwrap_or_default();
+    writeln!(w, "{} {} bytes at {}{}", "Bytes".green().bold(), report.len, report.file_offset_hex, scope)?;
+    writeln!(w, "----------------------------------------")?;
+
+    let bytes: Vec<&str> = report.bytes.split_whitespace().collect();
+    for (row, chunk) in bytes.chunks(16).enumerate() {
+        let ascii: String = chunk
+            .iter()
+            .map(|b| u8::from_str_radix(b, 16).map_or('.', |b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' }))
+            .collect();
+        writeln!(w, "{}  {:<47}  {}", format!("{:08x}", report.file_offset + row as u64 * 16).dimmed(), chunk.join(" "), ascii)?;
+    }
+
+    writeln!(w, "----------------------------------------")?;
+    Ok(())
+}
+
+
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macho::constants::*;
+
+    fn section(sectname: [u8; 16], flags: u32, addr: u64, offset: u32) -> ParsedSection {
+        ParsedSection {
+            sectname,
+            segname: SEG_TEXT,
+            offset,
+            addr,
+            size: 0x100,
+            align: 0,
+            reloff: 0,
+            nreloc: 0,
+            flags,
+            kind: crate::macho::sections::classify_section(sectname, flags, SEG_TEXT),
+            reserved1: 0,
+            reserved2: 0,
+            reserved3: None,
+        }
+    }
+
+    #[test]
+    fn find_string_reports_every_location() {
+        let cstring = section(SECT_CSTRING, S_CSTRING_LITERALS, 0x1_0000_3f00, 0x3f00);
+        let data = b"Hello world!\0say hello\0Hello\0";
+
+        let hits = find_string_in_section(&cstring, data, "Hello", 0x4000);
+
+        assert_eq!(hits.len(), 2);
+        assert_eq!((hits[0].vmaddr, hits[0].file_offset), (0x1_0000_3f00, Some(0x7f00)));
+        assert_eq!((hits[0].string.as_deref(), hits[0].exact), (Some("Hello world!"), false));
+        assert_eq!((hits[1].vmaddr, hits[1].exact), (0x1_0000_3f00 + 23, true));
+    }
+
+    #[test]
+    fn find_string_searches_ustring_as_utf16() {
+        let ustring = section(SECT_USTRING, S_REGULAR, 0x2000, 0x2000);
+        let data: Vec<u8> = "caf\u{e9}\0".encode_utf16().flat_map(|u| u.to_le_bytes()).collect();
+
+        let hits = find_string_in_section(&ustring, &data, "f\u{e9}", 0);
+
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].vmaddr, 0x2004); // 2 UTF-16 units in
+    }
+
+    #[test]
+    fn find_bytes_allows_overlaps_and_skips_zerofill_offsets() {
+        let mut bss = section(SECT_BSS, S_ZEROFILL, 0x8000, 0);
+        bss.segname = SEG_DATA;
+
+        let hits = find_bytes_in_section(&bss, &[0, 0, 0], &[0, 0], 0);
+
+        assert_eq!(hits.iter().map(|h| h.vmaddr).collect::<Vec<_>>(), vec![0x8000, 0x8001]);
+        assert!(hits.iter().all(|h| h.file_offset.is_none()));
+    }
+
+    #[test]
+    fn byte_pattern_wildcards() {
+        assert_eq!(parse_byte_pattern("48 8b ?? 48").unwrap(), vec![Some(0x48), Some(0x8b), None, Some(0x48)]);
+        assert!(parse_byte_pattern("?? ??").is_err());
+        assert!(parse_hex_bytes("48 ??").is_err());
+        assert!(parse_byte_pattern("aé1").unwrap_err().contains("'é'"));
+        assert!(parse_byte_pattern("48 ?é").is_err());
+    }
+
+    #[test]
+    fn scan_pattern_matches_wildcards_and_edges() {
+        let haystack = [0x48, 0x8b, 0x05, 0x48, 0x89, 0x00, 0x48, 0x8b, 0xff, 0x48, 0x89];
+        let pattern = parse_byte_pattern("48 8b ?? 48 89").unwrap();
+
+        assert_eq!(scan_pattern(&haystack, &pattern), vec![0, 6]); // second one ends on the last byte
+        assert_eq!(scan_pattern(&haystack, &parse_byte_pattern("?? 48").unwrap()), vec![2, 5, 8]);
+        assert_eq!(scan_pattern(&haystack[..4], &pattern), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn scan_pattern_agrees_with_find_all_without_wildcards() {
+        let haystack = b"abababcabab";
+
+        assert_eq!(scan_pattern(haystack, &parse_byte_pattern("61 62 61").unwrap()), find_all(haystack, b"aba"));
+    }
+
+    fn layout() -> SliceLayout {
+        let mut text = section(SECT_TEXT, S_REGULAR, 0x1_0000_0400, 0x400);
+        text.size = 0x100;
+        SliceLayout {
+            arch: "arm64".to_string(),
+            slice_offset: 0x4000,
+            slice_size: Some(0x1000),
+            segments: vec![ParsedSegment {
+                segname: SEG_TEXT,
+                vmaddr: 0x1_0000_0000,
+                vmsize: 0x1000,
+                fileoff: 0,
+                filesize: 0x800,
+                maxprot: 5,
+                initprot: 5,
+                flags: 0,
+                sections: vec![text],
+            }],
+        }
+    }
+
+    #[test]
+    fn file_offsets_map_back_to_slice_segment_and_section() {
+        let layouts = [layout()];
+
+        let in_text = locate_file_offset(0x4410, &layouts);
+        assert_eq!(in_text.arch.as_deref(), Some("arm64"));
+        assert_eq!((in_text.segname, in_text.sectname, in_text.vmaddr), (Some(SEG_TEXT), Some(SECT_TEXT), Some(0x1_0000_0410)));
+
+        let header = locate_file_offset(0x4010, &layouts);
+        assert_eq!((header.sectname, header.vmaddr), (None, Some(0x1_0000_0010)));
+
+        let fat_header = locate_file_offset(0x10, &layouts);
+        assert_eq!((fat_header.arch, fat_header.vmaddr), (None, None));
+    }
+
+    #[test]
+    fn scan_ranges_follow_the_scope() {
+        let layouts = [layout()];
+
+        assert_eq!(scan_ranges(&layouts, None, 0x5000).unwrap(), vec![(0, 0x5000)]);
+        assert_eq!(scan_ranges(&layouts, Some("__TEXT"), 0x5000).unwrap(), vec![(0x4000, 0x4800)]);
+        assert_eq!(scan_ranges(&layouts, Some("__TEXT,__text"), 0x5000).unwrap(), vec![(0x4400, 0x4500)]);
+        assert!(scan_ranges(&layouts, Some("__DATA"), 0x5000).is_err());
+    }
+
+    #[test]
+    fn hex_bytes_accept_common_spellings() {
+        for spec in ["de ad be ef", "deadbeef", "DE:AD:BE:EF", "0xdeadbeef"] {
+            assert_eq!(parse_hex_bytes(spec).unwrap(), vec![0xde, 0xad, 0xbe, 0xef], "{}", spec);
+        }
+
+        assert!(parse_hex_bytes("").is_err());
+        assert!(parse_hex_bytes("abc").is_err());
+        assert!(parse_hex_bytes("zz").is_err());
+        // a multi-byte character used to land a pair off a char boundary and panic
+        assert!(parse_hex_bytes("aé1").unwrap_err().contains("'é'"));
+        assert!(parse_hex_bytes("deadbeéf").is_err());
+    }
+
+    #[test]
+    fn peek_range_is_bounds_checked() {
+        // plain file offset
+        assert_eq!(peek_range(0, 0x100, 0x10, 0x20).unwrap(), (0x10, 0x30));
+        // relative to a slice starting at 0x4000
+        assert_eq!(peek_range(0x4000, 0x100, 0x10, 0x20).unwrap(), (0x4010, 0x4030));
+        assert_eq!(peek_range(0, 0x100, 0xf0, 0x10).unwrap(), (0xf0, 0x100));
+
+        assert!(peek_range(0, 0x100, 0xf0, 0x11).is_err());
+        assert!(peek_range(0, 0x100, u64::MAX, 2).is_err());
+        assert!(peek_range(0, 0x100, 0, 0).is_err());
+    }
+
+    #[test]
+    fn offsets_take_decimal_or_hex() {
+        assert_eq!(parse_offset("4096").unwrap(), 4096);
+        assert_eq!(parse_offset("0x1000").unwrap(), 4096);
+        assert!(parse_offset("0xzz").is_err());
+    }
+}
@@ -1,11 +1,15 @@
 // File Purpose: Enumerate Sections, Work with segments.rs
 use crate::macho::constants::*;
+use crate::macho::memory_image;
 use crate::macho::utils;
-use crate::reporting::sections::SectionReport;
+use crate::reporting::sections::{IndirectSymbolRangeReport, LiteralSectionReport, LiteralsReport, SectionInfoReport, SectionReport, UnknownSectionReport, UnknownSectionsReport};
+use colored::{ColoredString, Colorize};
+use std::collections::BTreeMap;
 use std::error::Error;
 use std::mem::size_of;
+use std::io::{self, Write};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)] // Ord is declaration order, handy for stable listings
 pub enum SectionKind {
     // Executable code
     Code,                       // __text
@@ -15,10 +19,11 @@ pub enum SectionKind {
     NonLazySymbolPointers,      // __DATA,__nl_symbol_ptr (S_NON_LAZY_SYMBOL_POINTERS)
     GlobalOffsetTable,          // __DATA_CONST,__got
     // Data
-    CString,                    // __cstring
+    CString,                    // __cstring, __oslogstring
     ConstData,                  // __const
     Data,                       // __data
     Bss,                        // __bss,
+    Literals,                   // __literal4/8/16 (S_*BYTE_LITERALS), S_LITERAL_POINTERS
     // OBJC
     ObjCClass,
     ObjCMetaClass,
@@ -39,6 +44,92 @@ pub enum SectionKind {
 }
 
 impl SectionKind {
+    // Declaration order, same as Ord
+    pub const ALL: [SectionKind; 22] = [
+        SectionKind::Code, SectionKind::SymbolStubs, SectionKind::LazySymbolPointers, SectionKind::NonLazySymbolPointers,
+        SectionKind::GlobalOffsetTable, SectionKind::CString, SectionKind::ConstData, SectionKind::Data, SectionKind::Bss,
+        SectionKind::Literals, SectionKind::ObjCClass, SectionKind::ObjCMetaClass, SectionKind::ObjCSelectorRefs,
+        SectionKind::ObjCMethodNames, SectionKind::ObjCMetadata, SectionKind::Exception, SectionKind::Unwind, SectionKind::Init,
+        SectionKind::Debug, SectionKind::LinkEdit, SectionKind::Other, SectionKind::Unknown,
+    ];
+
+    // What lands in the JSON "kind" field, these are part of the report contract so don't rename them
+    // along with the variants (they happen to match the Debug names today, nothing relies on that)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SectionKind::Code                   => "Code",
+            SectionKind::SymbolStubs            => "SymbolStubs",
+            SectionKind::LazySymbolPointers     => "LazySymbolPointers",
+            SectionKind::NonLazySymbolPointers  => "NonLazySymbolPointers",
+            SectionKind::GlobalOffsetTable      => "GlobalOffsetTable",
+            SectionKind::CString                => "CString",
+            SectionKind::ConstData              => "ConstData",
+            SectionKind::Data                   => "Data",
+            SectionKind::Bss                    => "Bss",
+            SectionKind::Literals               => "Literals",
+            SectionKind::ObjCClass              => "ObjCClass",
+            SectionKind::ObjCMetaClass          => "ObjCMetaClass",
+            SectionKind::ObjCSelectorRefs       => "ObjCSelectorRefs",
+            SectionKind::ObjCMethodNames        => "ObjCMethodNames",
+            SectionKind::ObjCMetadata           => "ObjCMetadata",
+            SectionKind::Exception              => "Exception",
+            SectionKind::Unwind                 => "Unwind",
+            SectionKind::Init                   => "Init",
+            SectionKind::Debug                  => "Debug",
+            SectionKind::LinkEdit               => "LinkEdit",
+            SectionKind::Other                  => "Other",
+            SectionKind::Unknown                => "Unknown",
+        }
+    }
+
+    // Text output color per kind, write_section_legend explains them
+    pub fn colored(&self) -> ColoredString {
+        match self {
+            // Executable code
+            SectionKind::Code               => self.as_str().blue().bold(),
+            
+            // Symbol stub / pointer consumers
+            SectionKind::SymbolStubs        => self.as_str().yellow().bold(),
+            SectionKind::LazySymbolPointers => self.as_str().cyan().bold(),
+            SectionKind::NonLazySymbolPointers => self.as_str().cyan().bold(),
+            SectionKind::GlobalOffsetTable  => self.as_str().cyan().bold(),
+
+            // Data
+            SectionKind::CString            => self.as_str().green().bold(),
+            SectionKind::ConstData          => self.as_str().green().bold(),
+            SectionKind::Data               => self.as_str().blue().bold(),
+            SectionKind::Bss                => self.as_str().blue().bold(),
+            SectionKind::Literals           => self.as_str().green(),
+
+            // ObjC
+            SectionKind::ObjCClass          => self.as_str().green().bold(),
+            SectionKind::ObjCMetaClass      => self.as_str().green(),
+            SectionKind::ObjCSelectorRefs   => self.as_str().green(),
+            SectionKind::ObjCMethodNames    => self.as_str().green(),
+            SectionKind::ObjCMetadata       => self.as_str().green(),
+
+            // Exceptions / unwind
+            SectionKind::Exception          => self.as_str().yellow(),
+            SectionKind::Unwind             => self.as_str().yellow(),
+
+            // Init
+            SectionKind::Init               => self.as_str().yellow().bold(),
+
+            // Debug / LinkEdit
+            SectionKind::Debug              => self.as_str().normal(),
+            SectionKind::LinkEdit           => self.as_str().magenta().bold(),
+
+            // Fallbacks
+            SectionKind::Other              => self.as_str().normal(),
+            SectionKind::Unknown            => self.as_str().red().bold(),
+        }
+    }
+
+    // Back from the report's "kind" string, the text writer only has the report to color from
+    pub fn from_name(name: &str) -> Option<SectionKind> {
+        SectionKind::ALL.iter().copied().find(|kind| kind.as_str() == name)
+    }
+
     pub fn uses_indirect_symbols(&self) -> bool {
         matches!(
             self, 
@@ -83,12 +174,16 @@ pub struct Section64 {      // For 64-bit architectures
     pub reserved3: u32,     // reserved 
 }
 
+#[derive(Debug, Clone)]
 pub struct ParsedSection {
     pub sectname: [u8; 16], 
     pub segname: [u8; 16],  
     pub offset: u32,
     pub addr: u64,          
     pub size: u64,         
+    pub align: u32,
+    pub reloff: u32,
+    pub nreloc: u32,
     pub flags: u32,        
     pub kind: SectionKind, 
     // Adding reserved1 and 2 for indirect symbols and stubs
@@ -102,11 +197,99 @@ impl ParsedSection {
         SectionReport { 
             name: utils::byte_array_to_string(&self.sectname), 
             segment: utils::byte_array_to_string(&self.segname), 
-            kind: format!("{:?}", self.kind), 
+            kind: self.kind.as_str().to_string(),
             addr: self.addr, 
             size: self.size 
         }
     }
+
+    // Zero-fill sections occupy VM but have no bytes in the file, so their offset is meaningless
+    pub fn is_zerofill(&self) -> bool {
+        matches!(self.flags & SECTION_TYPE, S_ZEROFILL | S_GB_ZEROFILL | S_THREAD_LOCAL_ZEROFILL)
+    }
+
+    // Stubs store their size in reserved2, pointer sections are one pointer per entry
+    pub fn indirect_entry_size(&self, is_64: bool) -> u64 {
+        match self.flags & SECTION_TYPE {
+            S_SYMBOL_STUBS if self.reserved2 != 0 => self.reserved2 as u64,
+            _ => if is_64 { 8 } else { 4 },
+        }
+    }
+
+    /*
+    Everything we know about one section, for --section-info
+    For indirect symbol consumers reserved1 is the first index into the indirect symbol table
+    and the section covers size / entry_size consecutive entries from there
+    */
+    pub fn build_info_report(&self, cpu_type: &str, cpu_subtype: &str, is_64: bool, nindirectsyms: Option<u32>) -> SectionInfoReport {
+        let indirect_symbols = if self.kind.uses_indirect_symbols() {
+            let entry_size = self.indirect_entry_size(is_64);
+            let count = self.size / entry_size;
+            Some(IndirectSymbolRangeReport {
+                first_index: self.reserved1,
+                count,
+                entry_size,
+                out_of_bounds: nindirectsyms.is_some_and(|n| self.reserved1 as u64 + count > n as u64),
+            })
+        } else {
+            None
+        };
+
+        SectionInfoReport {
+            cpu_type: cpu_type.to_string(),
+            cpu_subtype: cpu_subtype.to_string(),
+            segment: utils::byte_array_to_string(&self.segname),
+            name: utils::byte_array_to_string(&self.sectname),
+            kind: self.kind.as_str().to_string(),
+            addr: self.addr,
+            size: self.size,
+            offset: self.offset,
+            align: self.align,
+            reloff: self.reloff,
+            nreloc: self.nreloc,
+            section_type: section_type_name(self.flags).to_string(),
+            attributes: section_attribute_names(self.flags).iter().map(|a| a.to_string()).collect(),
+            reserved1: self.reserved1,
+            reserved2: self.reserved2,
+            reserved3: self.reserved3,
+            indirect_symbols,
+        }
+    }
+}
+
+
+pub fn write_section_info(w: &mut dyn Write, report: &SectionInfoReport) -> io::Result<()> {
+    writeln!(w)?;
+    writeln!(w, "{} {},{} ({})", "Section".green().bold(), report.segment, report.name, report.cpu_subtype)?;
+    writeln!(w, "----------------------------------------")?;
+    writeln!(w, "{} {}", "  Kind       :".yellow().bold(), report.kind)?;
+    writeln!(w, "{} 0x{:016x} - 0x{:016x} ({:#x} bytes)", "  VM range   :".yellow().bold(), report.addr, report.addr + report.size, report.size)?;
+    writeln!(w, "{} {:#x}", "  File offset:".yellow().bold(), report.offset)?;
+    writeln!(w, "{} 2^{} ({:#x})", "  Alignment  :".yellow().bold(), report.align, 1u64.checked_shl(report.align).unwrap_or(0))?;
+    writeln!(w, "{} {:#x} ({} entries)", "  Relocations:".yellow().bold(), report.reloff, report.nreloc)?;
+    writeln!(w, "{} {}", "  Type       :".yellow().bold(), report.section_type)?;
+
+    let attributes = if report.attributes.is_empty() { "none".to_string() } else { report.attributes.join(" | ") };
+    writeln!(w, "{} {}", "  Attributes :".yellow().bold(), attributes)?;
+
+    let reserved3 = report.reserved3.map(|r| format!("{:#x}", r)).unwrap_or_else(|| "-".to_string());
+    writeln!(w, "{} {:#x} / {:#x} / {}", "  Reserved   :".yellow().bold(), report.reserved1, report.reserved2, reserved3)?;
+
+    if let Some(range) = &report.indirect_symbols {
+        writeln!(
+            w,
+            "{} [{}..{}) ({} entries of {:#x} bytes)",
+            "  Indirect   :".yellow().bold(),
+            range.first_index,
+            range.first_index as u64 + range.count,
+            range.count,
+            range.entry_size
+        )?;
+        if range.out_of_bounds {
+            writeln!(w, "{}", "  Indirect range runs past the end of the indirect symbol table".red().bold())?;
+        }
+    }
+    Ok(())
 }
 pub fn classify_section(
     sect_name: [u8; 16],
@@ -123,6 +306,8 @@ pub fn classify_section(
         S_LAZY_SYMBOL_POINTERS | S_LAZY_DYLUB_SYMBOL_POINTERS   => return SectionKind::LazySymbolPointers,
         S_NON_LAZY_SYMBOL_POINTERS                              => return SectionKind::NonLazySymbolPointers,
         S_MOD_INIT_FUNC_POINTERS | S_MOD_TERM_FUNC_POINTERS     => return SectionKind::Init,
+        S_4BYTE_LITERALS | S_8BYTE_LITERALS | S_16BYTE_LITERALS
+        | S_LITERAL_POINTERS                                    => return SectionKind::Literals,
         _ => {}
     }
 
@@ -133,6 +318,7 @@ pub fn classify_section(
             (SEG_TEXT, SECT_TEXT) => SectionKind::Code,
             (SEG_TEXT, SECT_CONST) => SectionKind::ConstData,
             (SEG_TEXT, SECT_CSTRING) => SectionKind::CString,
+            (SEG_TEXT, SECT_OSLOGSTRING) => SectionKind::CString,
             (SEG_TEXT, SECT_GCC_EXCEPT_TAB) => SectionKind::Exception,
             (SEG_TEXT, SECT_EH_FRAME) => SectionKind::Exception,
             (SEG_TEXT, SECT_UNWIND_INFO) => SectionKind::Unwind,
@@ -154,6 +340,7 @@ pub fn classify_section(
             (SEG_DATA_CONST, SECT_OBJC_IMAGEINFO) => SectionKind::ObjCMetadata,
             (SEG_DATA_CONST, SECT_OBJC_CLASSLIST) => SectionKind::ObjCClass,
             (SEG_DATA_CONST, SECT_OBJC_PROTLIST) => SectionKind::ObjCMetadata,
+            (SEG_DATA_CONST, SECT_OBJC_CATLIST) => SectionKind::ObjCMetadata,
             (SEG_DATA_CONST, SECT_OBJC_SELREFS) => SectionKind::ObjCSelectorRefs,
 
             // __AUTH / __AUTH_CONST            
@@ -191,6 +378,9 @@ pub fn read_section64_from_bytes(data: &[u8], is_be: bool, sect_offset: usize )
     let sect_addr = utils::bytes_to(is_be, &data[sect_offset + 32..])?; 
     let sect_size = utils::bytes_to(is_be, &data[sect_offset + 40..])?;
     let sect_fileoff: u32 = utils::bytes_to(is_be, &data[sect_offset + 48 .. sect_offset + 52])?;
+    let sect_align: u32 = utils::bytes_to(is_be, &data[sect_offset + 52 ..])?;
+    let sect_reloff: u32 = utils::bytes_to(is_be, &data[sect_offset + 56 ..])?;
+    let sect_nreloc: u32 = utils::bytes_to(is_be, &data[sect_offset + 60 ..])?;
     let sect_flags = utils::bytes_to(is_be, &data[sect_offset + 64..])?;
     let reserved1: u32 = utils::bytes_to(is_be, &data[sect_offset + 68 ..])?;
     let reserved2: u32 = utils::bytes_to(is_be, &data[sect_offset + 72 ..])?;
@@ -207,10 +397,13 @@ pub fn read_section64_from_bytes(data: &[u8], is_be: bool, sect_offset: usize )
         offset: sect_fileoff,
         addr: sect_addr,
         size: sect_size,
+        align: sect_align,
+        reloff: sect_reloff,
+        nreloc: sect_nreloc,
         flags: sect_flags,
         kind: sect_kind,
-        reserved1: reserved1,
-        reserved2: reserved2,
+        reserved1,
+        reserved2,
         reserved3: Some(reserved3),
     })
 }
@@ -232,6 +425,10 @@ pub fn read_section32_from_bytes(
     let seg_name: [u8; 16] = data[sect_offset + 16 .. sect_offset + 32].try_into()?;
     let sect_addr_32: u32 = utils::bytes_to(is_be, &data[sect_offset + 32 ..])?;
     let sect_size_32: u32 = utils::bytes_to(is_be, &data[sect_offset + 36 ..])?;
+    let sect_fileoff: u32 = utils::bytes_to(is_be, &data[sect_offset + 40 ..])?;
+    let sect_align: u32 = utils::bytes_to(is_be, &data[sect_offset + 44 ..])?;
+    let sect_reloff: u32 = utils::bytes_to(is_be, &data[sect_offset + 48 ..])?;
+    let sect_nreloc: u32 = utils::bytes_to(is_be, &data[sect_offset + 52 ..])?;
     let sect_flags: u32 = utils::bytes_to(is_be, &data[sect_offset + 56 ..])?;
     let reserved1: u32 = utils::bytes_to(is_be, &data[sect_offset + 60 ..])?;
     let reserved2: u32 = utils::bytes_to(is_be, &data[sect_offset + 64 ..])?;
@@ -247,13 +444,299 @@ pub fn read_section32_from_bytes(
     Ok(ParsedSection {
         sectname: sect_name,
         segname: seg_name,
-        offset: sect_offset as u32,
+        offset: sect_fileoff,
         addr: sect_addr,
         size: sect_size,
+        align: sect_align,
+        reloff: sect_reloff,
+        nreloc: sect_nreloc,
         flags: sect_flags,
         kind: sect_kind,
-        reserved1: reserved1,
-        reserved2: reserved2,
+        reserved1,
+        reserved2,
         reserved3: None,
     })
 }
+
+
+/*
+--report-unknown-sections: every section classify_section gave up on (Unknown or Other),
+deduplicated on the exact name bytes + section type and counted across the whole file.
+The raw 16 byte names are shown in hex and ASCII because the interesting cases are usually
+the weird ones (names filling all 16 bytes, junk after the NUL, non-ASCII).
+Ordered by count, most common first, since those are the rules worth writing.
+*/
+type RawSectionKey = ([u8; 16], [u8; 16], u32);
+
+pub fn build_unknown_sections_report<'a>(sections: impl IntoIterator<Item = &'a ParsedSection>) -> UnknownSectionsReport {
+    // (segname, sectname, section type) --> (kind, count)
+    let mut seen: BTreeMap<RawSectionKey, (SectionKind, usize)> = BTreeMap::new();
+
+    for sect in sections {
+        if !matches!(sect.kind, SectionKind::Unknown | SectionKind::Other) {
+            continue;
+        }
+        let entry = seen.entry((sect.segname, sect.sectname, sect.flags & SECTION_TYPE)).or_insert((sect.kind, 0));
+        entry.1 += 1;
+    }
+
+    let mut sections: Vec<UnknownSectionReport> = seen
+        .into_iter()
+        .map(|((segname, sectname, stype), (kind, count))| UnknownSectionReport {
+            segment: utils::byte_array_to_string(&segname),
+            name: utils::byte_array_to_string(&sectname),
+            segname_hex: utils::hex_string(&segname),
+            sectname_hex: utils::hex_string(&sectname),
+            segname_ascii: printable_name(&segname),
+            sectname_ascii: printable_name(&sectname),
+            kind: kind.as_str().to_string(),
+            section_type: section_type_name(stype).to_string(),
+            count,
+        })
+        .collect();
+    sections.sort_by_key(|s| std::cmp::Reverse(s.count)); // stable, ties keep name order
+
+    UnknownSectionsReport { total: sections.iter().map(|s| s.count).sum(), sections }
+}
+
+fn printable_name(bytes: &[u8; 16]) -> String {
+    bytes.iter().map(|&b| if (0x20..=0x7e).contains(&b) { b as char } else { '.' }).collect()
+}
+
+pub fn write_unknown_sections(w: &mut dyn Write, report: &UnknownSectionsReport) -> io::Result<()> {
+    writeln!(w)?;
+    writeln!(w, "{} ({} sections, {} unique)", "Unclassified Sections".green().bold(), report.total, report.sections.len())?;
+    writeln!(w, "----------------------------------------")?;
+
+    if report.sections.is_empty() {
+        writeln!(w, "none, every section was classified")?;
+    }
+
+    for s in &report.sections {
+        writeln!(w, "{:>4}x  {},{}  {} {}", s.count, s.segment, s.name, s.kind.red().bold(), s.section_type)?;
+        writeln!(w, "        seg  {}  |{}|", s.segname_hex, s.segname_ascii)?;
+        writeln!(w, "        sect {}  |{}|", s.sectname_hex, s.sectname_ascii)?;
+    }
+
+    writeln!(w, "----------------------------------------")?;
+    Ok(())
+}
+
+
+/*
+--literals: the fixed size literal sections ld uniques constants into
+    S_4BYTE_LITERALS   --> __literal4,  floats and 32-bit constants
+    S_8BYTE_LITERALS   --> __literal8,  doubles and 64-bit constants
+    S_16BYTE_LITERALS  --> __literal16, vector / long double constants
+Every entry is shown as its raw bytes in hex, in file order. S_LITERAL_POINTERS sections are
+classified as Literals too but hold pointers (fixed up by dyld), there's no value to show.
+*/
+pub fn literal_size(flags: u32) -> Option<usize> {
+    match flags & SECTION_TYPE {
+        S_4BYTE_LITERALS => Some(4),
+        S_8BYTE_LITERALS => Some(8),
+        S_16BYTE_LITERALS => Some(16),
+        _ => None,
+    }
+}
+
+// None for anything that isn't a fixed size literal section
+pub fn build_literal_section_report(section: &ParsedSection, bytes: &[u8]) -> Option<LiteralSectionReport> {
+    let size = literal_size(section.flags)?;
+    Some(LiteralSectionReport {
+        segment: utils::byte_array_to_string(&section.segname),
+        name: utils::byte_array_to_string(&section.sectname),
+        addr: section.addr,
+        literal_size: size,
+        // a trailing partial entry would be a malformed section, it's left out rather than padded
+        literals: bytes.chunks_exact(size).map(|literal| literal.iter().map(|b| format!("{:02x}", b)).collect()).collect(),
+    })
+}
+
+// --literals for one slice, every fixed size literal section read straight from the file
+pub fn build_literals_report<'a>(cpu_type: &str, cpu_subtype: &str, sections: impl IntoIterator<Item = &'a ParsedSection>, data: &[u8], slice_offset: u64) -> LiteralsReport {
+    LiteralsReport {
+        cpu_type: cpu_type.to_string(),
+        cpu_subtype: cpu_subtype.to_string(),
+        sections: sections
+            .into_iter()
+            .filter_map(|sect| build_literal_section_report(sect, memory_image::read_section_from_file(data, sect, slice_offset)?))
+            .collect(),
+    }
+}
+
+pub fn write_literals(w: &mut dyn Write, report: &LiteralsReport) -> io::Result<()> {
+    writeln!(w)?;
+    writeln!(w, "{} ({})", "Literals".green().bold(), report.cpu_subtype)?;
+    writeln!(w, "----------------------------------------")?;
+
+    if report.sections.is_empty() {
+        writeln!(w, "none")?;
+    }
+
+    for section in &report.sections {
+        writeln!(w, "{},{} ({} x {} bytes)", section.segment, section.name, section.literals.len(), section.literal_size)?;
+        for (i, literal) in section.literals.iter().enumerate() {
+            writeln!(w, "  0x{:016x}  {}", section.addr + (i * section.literal_size) as u64, literal)?;
+        }
+    }
+
+    writeln!(w, "----------------------------------------")?;
+    Ok(())
+}
+
+
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn name(s: &[u8]) -> [u8; 16] {
+        let mut out = [0u8; 16];
+        out[..s.len()].copy_from_slice(s);
+        out
+    }
+
+    fn section(segname: &[u8], sectname: &[u8], flags: u32) -> ParsedSection {
+        ParsedSection {
+            sectname: name(sectname),
+            segname: name(segname),
+            offset: 0,
+            addr: 0,
+            size: 0,
+            align: 0,
+            reloff: 0,
+            nreloc: 0,
+            flags,
+            kind: classify_section(name(sectname), flags, name(segname)),
+            reserved1: 0,
+            reserved2: 0,
+            reserved3: None,
+        }
+    }
+
+    #[test]
+    fn unknown_sections_are_deduplicated_and_counted() {
+        let sections = [
+            section(b"__TEXT", b"__text", S_REGULAR), // classified, ignored
+            section(b"__DATA", b"__mystery", S_REGULAR),
+            section(b"__DATA", b"__mystery", S_REGULAR), // same thing from another slice
+            section(b"__DATA", b"__mystery", S_COALESCED), // same name, different type
+        ];
+
+        let report = build_unknown_sections_report(&sections);
+
+        assert_eq!(report.total, 3);
+        assert_eq!(report.sections.len(), 2);
+        assert_eq!((report.sections[0].kind.as_str(), report.sections[0].count), ("Other", 2));
+        assert_eq!((report.sections[1].kind.as_str(), report.sections[1].section_type.as_str()), ("Unknown", "S_COALESCED"));
+    }
+
+    #[test]
+    fn unknown_section_names_keep_raw_bytes() {
+        let mut odd = section(b"__DATA", b"__x", S_REGULAR);
+        odd.sectname[5] = 0xff; // junk after the NUL
+
+        let report = build_unknown_sections_report([&odd]);
+        let s = &report.sections[0];
+
+        assert_eq!(s.name, "__x");
+        assert_eq!(s.sectname_hex, "5f 5f 78 00 00 ff 00 00 00 00 00 00 00 00 00 00");
+        assert_eq!(s.sectname_ascii, "__x.............");
+    }
+
+    #[test]
+    fn oslogstring_is_a_string_section_either_way() {
+        assert_eq!(section(b"__TEXT", b"__oslogstring", S_CSTRING_LITERALS).kind, SectionKind::CString);
+        assert_eq!(section(b"__TEXT", b"__oslogstring", S_REGULAR).kind, SectionKind::CString);
+    }
+
+    #[test]
+    fn literal_sections_are_classified_by_type() {
+        assert_eq!(section(b"__TEXT", b"__literal4", S_4BYTE_LITERALS).kind, SectionKind::Literals);
+        assert_eq!(section(b"__TEXT", b"__literal8", S_8BYTE_LITERALS).kind, SectionKind::Literals);
+        assert_eq!(section(b"__TEXT", b"__literal16", S_16BYTE_LITERALS).kind, SectionKind::Literals);
+        assert_eq!(section(b"__DATA", b"__literal_ptrs", S_LITERAL_POINTERS).kind, SectionKind::Literals);
+        // by type, not by name
+        assert_eq!(section(b"__TEXT", b"__literal8", S_REGULAR).kind, SectionKind::Other);
+    }
+
+    #[test]
+    fn literals_are_split_by_their_size() {
+        let sect = ParsedSection { addr: 0x1000, ..section(b"__TEXT", b"__literal8", S_8BYTE_LITERALS) };
+        let one: f64 = 1.0;
+        let mut bytes = one.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&[0xff; 8]);
+        bytes.push(0); // stray byte, not a literal
+
+        let report = build_literal_section_report(&sect, &bytes).unwrap();
+        assert_eq!(report.literal_size, 8);
+        assert_eq!(report.literals, vec!["000000000000f03f".to_string(), "ffffffffffffffff".to_string()]);
+
+        let pointers = section(b"__DATA", b"__literal_ptrs", S_LITERAL_POINTERS);
+        assert!(build_literal_section_report(&pointers, &bytes).is_none());
+    }
+
+    #[test]
+    fn literals_report_skips_zerofill_and_other_sections() {
+        let mut data = vec![0u8; 0x100];
+        data[0x80..0x88].copy_from_slice(&2.0f64.to_le_bytes());
+        let on_disk = ParsedSection { offset: 0x40, size: 8, ..section(b"__TEXT", b"__literal8", S_8BYTE_LITERALS) };
+        let zerofill = ParsedSection { size: 8, ..section(b"__DATA", b"__literal8", S_8BYTE_LITERALS) };
+        let text = ParsedSection { offset: 0x40, size: 8, ..section(b"__TEXT", b"__text", S_REGULAR) };
+
+        // section offsets are slice relative
+        let report = build_literals_report("ARM", "arm64", [&on_disk, &zerofill, &text], &data, 0x40);
+        assert_eq!((report.cpu_type.as_str(), report.cpu_subtype.as_str()), ("ARM", "arm64"));
+        assert_eq!(report.sections.len(), 1);
+        assert_eq!(report.sections[0].literals, vec!["0000000000000040".to_string()]);
+    }
+
+    #[test]
+    fn mod_init_func_is_classified_by_type_in_any_segment() {
+        assert_eq!(section(b"__DATA_CONST", b"__mod_init_func", S_MOD_INIT_FUNC_POINTERS).kind, SectionKind::Init);
+        assert_eq!(section(b"__DATA", b"__mod_init_func", S_MOD_INIT_FUNC_POINTERS).kind, SectionKind::Init);
+        assert_eq!(section(b"__AUTH_CONST", b"__whatever", S_MOD_INIT_FUNC_POINTERS).kind, SectionKind::Init);
+    }
+
+    #[test]
+    fn section_kind_strings_are_stable() {
+        let expected = [
+            (SectionKind::Code, "Code"),
+            (SectionKind::SymbolStubs, "SymbolStubs"),
+            (SectionKind::LazySymbolPointers, "LazySymbolPointers"),
+            (SectionKind::NonLazySymbolPointers, "NonLazySymbolPointers"),
+            (SectionKind::GlobalOffsetTable, "GlobalOffsetTable"),
+            (SectionKind::CString, "CString"),
+            (SectionKind::ConstData, "ConstData"),
+            (SectionKind::Data, "Data"),
+            (SectionKind::Bss, "Bss"),
+            (SectionKind::Literals, "Literals"),
+            (SectionKind::ObjCClass, "ObjCClass"),
+            (SectionKind::ObjCMetaClass, "ObjCMetaClass"),
+            (SectionKind::ObjCSelectorRefs, "ObjCSelectorRefs"),
+            (SectionKind::ObjCMethodNames, "ObjCMethodNames"),
+            (SectionKind::ObjCMetadata, "ObjCMetadata"),
+            (SectionKind::Exception, "Exception"),
+            (SectionKind::Unwind, "Unwind"),
+            (SectionKind::Init, "Init"),
+            (SectionKind::Debug, "Debug"),
+            (SectionKind::LinkEdit, "LinkEdit"),
+            (SectionKind::Other, "Other"),
+            (SectionKind::Unknown, "Unknown"),
+        ];
+
+        for (kind, name) in expected {
+            assert_eq!(kind.as_str(), name);
+            assert_eq!(SectionKind::from_name(name), Some(kind));
+        }
+        assert_eq!(SectionKind::ALL.len(), expected.len());
+        assert_eq!(SectionKind::from_name("code"), None);
+        assert_eq!(section(b"__TEXT", b"__text", S_REGULAR).build_report().kind, "Code");
+    }
+}
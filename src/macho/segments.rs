@@ -1,10 +1,16 @@
 // File Purpose: Enumerate Segments, Work with sections.rs
 
-use std::error::Error;
+use std::collections::HashMap;
+use std::io::{self, Write};
 use crate::macho::sections::*;
 use crate::macho::utils;
+use crate::macho::diagnostics::Diagnostic;
+use crate::macho::error::MoscopeError;
+use crate::macho::constants::{filetype_short_name, CPU_TYPE_ARM64, LC_CODE_SIGNATURE, LC_SEGMENT, LC_SEGMENT_64, MH_EXECUTE, MH_IMPLICIT_PAGEZERO, MH_OBJECT, SEG_PAGEZERO};
+use crate::macho::header::MachOHeader;
+use crate::macho::load_commands::{LinkEditDataCommand, LoadCommand};
 use colored::Colorize;
-use crate::reporting::segments::SegmentReport;
+use crate::reporting::segments::{AppendedDataReport, SegmentReport, VmSpanReport};
 
 // https://web.archive.org/web/20260107202245/https://developer.apple.com/library/archive/documentation/Performance/Conceptual/CodeFootprint/Articles/MachOOverview.html
 // https://web.archive.org/web/20250912084041/https://medium.com/@travmath/understanding-the-mach-o-file-format-66cf0354e3f4
@@ -116,6 +122,7 @@ pub struct SegmentCommand64 {   // For 64-bit architectures
     pub flags: u32,             // flags
 }
 
+#[derive(Debug, Clone)]
 pub struct ParsedSegment {
     pub segname: [u8; 16],      
     pub vmaddr: u64,   
@@ -130,14 +137,19 @@ pub struct ParsedSegment {
 }
 
 impl ParsedSegment {
-    pub fn build_report(&self, _is_json: bool) -> SegmentReport {
+    // vmaddr 0 (__PAGEZERO, MH_OBJECT) is aligned to everything, so there's nothing to say
+    pub fn vm_alignment(&self) -> Option<u64> {
+        (self.vmaddr != 0).then(|| 1u64 << self.vmaddr.trailing_zeros())
+    }
+
+    pub fn build_report(&self) -> SegmentReport {
         
-        let max_prot_r = if self.maxprot & 0x1 != 0 { "R" } else { "-".into() }; 
-        let max_prot_w = if self.maxprot & 0x2 != 0 { "W" } else { "-".into() };
-        let max_prot_x = if self.maxprot & 0x4 != 0 { "X" } else { "-".into() };
-        let init_prot_r = if self.initprot & 0x1 != 0 { "R" } else { "-".into() }; 
-        let init_prot_w = if self.initprot & 0x2 != 0 { "W" } else { "-".into() };
-        let init_prot_x = if self.initprot & 0x4 != 0 { "X" } else { "-".into() };
+        let max_prot_r = if self.maxprot & 0x1 != 0 { "R" } else { "-" }; 
+        let max_prot_w = if self.maxprot & 0x2 != 0 { "W" } else { "-" };
+        let max_prot_x = if self.maxprot & 0x4 != 0 { "X" } else { "-" };
+        let init_prot_r = if self.initprot & 0x1 != 0 { "R" } else { "-" }; 
+        let init_prot_w = if self.initprot & 0x2 != 0 { "W" } else { "-" };
+        let init_prot_x = if self.initprot & 0x4 != 0 { "X" } else { "-" };
         
         SegmentReport { 
             name: utils::byte_array_to_string(&self.segname), 
@@ -147,6 +159,7 @@ impl ParsedSegment {
             filesize: self.filesize, 
             maxprot: format!("{}{}{}", max_prot_r, max_prot_w, max_prot_x), 
             initprot: format!("{}{}{}", init_prot_r, init_prot_w, init_prot_x), 
+            alignment: self.vm_alignment(),
             sections: self.sections.iter().map(|ps| ps.build_report()).collect(), // call the build report func for each section in the vector of parsed sections
         }
     }
@@ -164,10 +177,10 @@ impl ParsedSegment {
 
 
 
-pub fn parse_segment_32(data: &[u8], offset: usize, is_be: bool) -> Result<ParsedSegment, Box<dyn Error>> {
+pub fn parse_segment_32(data: &[u8], offset: usize, is_be: bool) -> Result<ParsedSegment, MoscopeError> {
     use std::mem::size_of;
     if offset + size_of::<SegmentCommand>() > data.len() {
-        return Err("Segment command out of bounds".into());
+        return Err(MoscopeError::OutOfBounds { offset: offset as u64, len: size_of::<SegmentCommand>() as u64 });
     }
     // start at offset + 8 because segname starts after cmd and cmdsize which are each u32
     let segname: [u8; 16] = data[offset + 8 .. offset + 24].try_into()?;
@@ -189,7 +202,7 @@ pub fn parse_segment_32(data: &[u8], offset: usize, is_be: bool) -> Result<Parse
     let mut sections = Vec::with_capacity(nsects as usize);
     let mut sect_offset = offset + size_of::<SegmentCommand>();
     for _ in 0..nsects {
-        sections.push(read_section32_from_bytes(&data, is_be, sect_offset)?);
+        sections.push(read_section32_from_bytes(data, is_be, sect_offset)?);
         sect_offset += size_of::<Section>();
     }
     //Ok(ParsedSegment { segname, vmaddr, vmsize, fileoff, filesize, maxprot, initprot, nsects, flags, sections })
@@ -197,10 +210,10 @@ pub fn parse_segment_32(data: &[u8], offset: usize, is_be: bool) -> Result<Parse
 }
 
 
-pub fn parse_segment_64(data: &[u8], offset: usize, is_be: bool) -> Result<ParsedSegment, Box<dyn Error>> {
+pub fn parse_segment_64(data: &[u8], offset: usize, is_be: bool) -> Result<ParsedSegment, MoscopeError> {
     use std::mem::size_of;
     if offset + size_of::<SegmentCommand64>() > data.len() {
-        return Err("Segment command out of bounds".into());
+        return Err(MoscopeError::OutOfBounds { offset: offset as u64, len: size_of::<SegmentCommand64>() as u64 });
     }
     // start at offset + 8 because segname starts after cmd and cmdsize which are each u32
     let segname: [u8; 16] = data[offset + 8 .. offset + 24].try_into()?;
@@ -217,98 +230,765 @@ pub fn parse_segment_64(data: &[u8], offset: usize, is_be: bool) -> Result<Parse
     let mut sections = Vec::with_capacity(nsects as usize);
     let mut sect_offset = offset + size_of::<SegmentCommand64>();
     for _ in 0..nsects {
-        sections.push(read_section64_from_bytes(&data, is_be, sect_offset)?);
+        sections.push(read_section64_from_bytes(data, is_be, sect_offset)?);
         sect_offset += size_of::<Section64>();
     }
     //Ok(ParsedSegment { segname, vmaddr, vmsize, fileoff, filesize, maxprot, initprot, nsects, flags, sections })
     Ok(ParsedSegment { segname, vmaddr, vmsize, fileoff, filesize, maxprot, initprot, flags, sections })
 }
 
+// Every LC_SEGMENT / LC_SEGMENT_64 of a slice, in load command order
+pub fn parse_segments(data: &[u8], load_commands: &[LoadCommand], is_be: bool) -> Result<Vec<ParsedSegment>, MoscopeError> {
+    let mut segments = Vec::new();
+    for lc in load_commands {
+        match lc.cmd {
+            LC_SEGMENT_64 => segments.push(parse_segment_64(data, lc.offset as usize, is_be)?),
+            LC_SEGMENT => segments.push(parse_segment_32(data, lc.offset as usize, is_be)?),
+            _ => {}
+        }
+    }
+    Ok(segments)
+}
 
-pub fn print_segments_summary(segments: &Vec<ParsedSegment>) {
-    if segments.is_empty() {
-        return;
+
+/*
+Every section has to live inside its parent segment, both in memory and in the file:
+
+    segment.vmaddr  <= section.addr    &&  section.addr + section.size    <= segment.vmaddr + segment.vmsize
+    segment.fileoff <= section.offset  &&  section.offset + section.size  <= segment.fileoff + segment.filesize
+
+The file check is skipped for zero-fill sections (__bss, __common, thread local zerofill) since they
+have no file bytes. A section escaping its segment is either corruption or someone trying to make
+tools disagree about what bytes belong to what.
+*/
+pub fn validate_section_bounds(segments: &[ParsedSegment]) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for seg in segments {
+        let seg_name = utils::byte_array_to_string(&seg.segname);
+        let seg_vm_end = seg.vmaddr.checked_add(seg.vmsize);
+        let seg_file_end = seg.fileoff.checked_add(seg.filesize);
+
+        for sect in &seg.sections {
+            let sect_name = utils::byte_array_to_string(&sect.sectname);
+
+            let sect_vm_end = sect.addr.checked_add(sect.size);
+            let vm_ok = match (sect_vm_end, seg_vm_end) {
+                (Some(sect_end), Some(seg_end)) => sect.addr >= seg.vmaddr && sect_end <= seg_end,
+                _ => false, // wrapped around u64, definitely not inside anything
+            };
+            if !vm_ok {
+                diagnostics.push(Diagnostic::error(
+                    "section-outside-segment-vm",
+                    format!(
+                        "{},{} VM range 0x{:x}+0x{:x} is outside segment {} (0x{:x}+0x{:x})",
+                        seg_name, sect_name, sect.addr, sect.size, seg_name, seg.vmaddr, seg.vmsize
+                    ),
+                ));
+            }
+
+            if sect.is_zerofill() || sect.size == 0 {
+                continue;
+            }
+
+            let sect_file_end = (sect.offset as u64).checked_add(sect.size);
+            let file_ok = match (sect_file_end, seg_file_end) {
+                (Some(sect_end), Some(seg_end)) => sect.offset as u64 >= seg.fileoff && sect_end <= seg_end,
+                _ => false,
+            };
+            if !file_ok {
+                diagnostics.push(Diagnostic::error(
+                    "section-outside-segment-file",
+                    format!(
+                        "{},{} file range 0x{:x}+0x{:x} is outside segment {} (0x{:x}+0x{:x})",
+                        seg_name, sect_name, sect.offset, sect.size, seg_name, seg.fileoff, seg.filesize
+                    ),
+                ));
+            }
+        }
     }
-    println!();
-    println!("{}", "Segments Summary".green().bold());
-    println!("----------------------------------------");
+
+    diagnostics
+}
+
+
+/*
+validate_section_bounds checks each section against its segment, this checks sections against each other.
+
+    sections in one segment should tile the VM range without sharing bytes:
+        __text   [0x1000 ........ 0x1800)
+        __stubs                  [0x1800 ... 0x1900)   <-- fine, touching isn't overlapping
+        __const          [0x1600 ....... 0x1a00)       <-- overlaps both, flagged
+
+    a zero-size section has no bytes, so a nonzero file offset points at nothing
+
+Sorted by address first, then each section is compared with the one reaching furthest so far,
+so it's O(n log n) and a section swallowing several others is reported once per victim.
+*/
+pub fn validate_section_layout(segments: &[ParsedSegment]) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
 
     for seg in segments {
         let seg_name = utils::byte_array_to_string(&seg.segname);
 
+        for sect in &seg.sections {
+            if sect.size == 0 && sect.offset != 0 && !sect.is_zerofill() {
+                diagnostics.push(Diagnostic::warning(
+                    "section-empty-with-offset",
+                    format!(
+                        "{},{} has size 0 but claims file offset 0x{:x}",
+                        seg_name, utils::byte_array_to_string(&sect.sectname), sect.offset
+                    ),
+                ));
+            }
+        }
+
+        let mut by_addr: Vec<&ParsedSection> = seg.sections.iter().filter(|s| s.size > 0).collect();
+        by_addr.sort_by_key(|s| s.addr);
+
+        let mut furthest: Option<&ParsedSection> = None;
+        for sect in by_addr {
+            let end = sect.addr.saturating_add(sect.size);
+
+            if let Some(prev) = furthest {
+                let prev_end = prev.addr.saturating_add(prev.size);
+                if sect.addr < prev_end {
+                    diagnostics.push(Diagnostic::error(
+                        "section-overlap",
+                        format!(
+                            "{},{} (0x{:x}+0x{:x}) overlaps {},{} (0x{:x}+0x{:x})",
+                            seg_name, utils::byte_array_to_string(&sect.sectname), sect.addr, sect.size,
+                            seg_name, utils::byte_array_to_string(&prev.sectname), prev.addr, prev.size
+                        ),
+                    ));
+                }
+                if end <= prev_end {
+                    continue;
+                }
+            }
+
+            furthest = Some(sect);
+        }
+    }
+
+    diagnostics
+}
+
+// --section-info lookup, names are compared the same way they're printed (NUL padding stripped)
+pub fn find_section<'a>(segments: &'a [ParsedSegment], segname: &str, sectname: &str) -> Option<&'a ParsedSection> {
+    segments
+        .iter()
+        .filter(|seg| utils::byte_array_to_string(&seg.segname) == segname)
+        .flat_map(|seg| seg.sections.iter())
+        .find(|sect| utils::byte_array_to_string(&sect.segname) == segname && utils::byte_array_to_string(&sect.sectname) == sectname)
+}
+
+
+/*
+__PAGEZERO is the no-access mapping at address 0 that turns NULL dereferences into crashes.
+ld gives executables 4GB of it on 64-bit (the whole low 32-bit address space) and one 4KB page on 32-bit.
+MH_IMPLICIT_PAGEZERO executables skip the segment and let the kernel reserve the low memory instead.
+A missing or shrunken page zero puts attacker-reachable memory where NULL-ish pointers land.
+*/
+pub const DEFAULT_PAGEZERO_SIZE_64: u64 = 0x1_0000_0000;
+pub const DEFAULT_PAGEZERO_SIZE_32: u64 = 0x1000;
+
+pub fn pagezero_size(segments: &[ParsedSegment]) -> Option<u64> {
+    segments.iter().find(|seg| seg.segname == SEG_PAGEZERO).map(|seg| seg.vmsize)
+}
+
+// [lowest vmaddr, highest vmaddr + vmsize) over the segments that take up address space, None without any
+pub fn vm_range<'a>(segments: impl IntoIterator<Item = &'a ParsedSegment>) -> Option<(u64, u64)> {
+    segments
+        .into_iter()
+        .filter(|seg| seg.vmsize > 0)
+        .map(|seg| (seg.vmaddr, seg.vmaddr.saturating_add(seg.vmsize)))
+        .reduce(|(low, high), (start, end)| (low.min(start), high.max(end)))
+}
+
+// What the image really maps, page zero reserves address space but is never loaded
+pub fn vm_span(segments: &[ParsedSegment]) -> Option<VmSpanReport> {
+    vm_range(segments.iter().filter(|seg| seg.segname != SEG_PAGEZERO))
+        .map(|(low, high)| VmSpanReport { low, high, size: high - low })
+}
+
+pub fn validate_pagezero(segments: &[ParsedSegment], header: &MachOHeader) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    // Only the main executable gets a page zero, dylibs/bundles/objects never have one
+    if header.filetype() != MH_EXECUTE {
+        return diagnostics;
+    }
+
+    let implicit = header.flags() & MH_IMPLICIT_PAGEZERO != 0;
+    let expected = if header.is_64() { DEFAULT_PAGEZERO_SIZE_64 } else { DEFAULT_PAGEZERO_SIZE_32 };
+
+    match pagezero_size(segments) {
+        None if !implicit => diagnostics.push(Diagnostic::warning(
+            "pagezero-missing",
+            "executable has no __PAGEZERO segment and does not set MH_IMPLICIT_PAGEZERO".to_string(),
+        )),
+        Some(size) if size < expected => diagnostics.push(Diagnostic::warning(
+            "pagezero-small",
+            format!("__PAGEZERO is 0x{:x} bytes (expected 0x{:x})", size, expected),
+        )),
+        _ => {}
+    }
+
+    diagnostics
+}
+
+/*
+dyld (and the kernel for the main executable) maps segments with mmap, one page at a time:
+
+    vmaddr  % page_size == 0
+    fileoff % page_size == 0     (when there are file bytes to map)
+
+ld64 always lays segments out like that, so a segment that isn't page aligned was
+hand-edited, damaged, or produced by something that isn't a real linker.
+Page size is the one the slice's architecture runs with: 16K on arm64, 4K elsewhere.
+MH_OBJECT is never mapped, its single segment is packed tight and exempt.
+*/
+pub fn page_size(cputype: i32) -> u64 {
+    match cputype {
+        CPU_TYPE_ARM64 => 0x4000,
+        _ => 0x1000,
+    }
+}
+
+pub fn validate_segment_alignment(segments: &[ParsedSegment], header: &MachOHeader, page_size: u64) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    if header.filetype() == MH_OBJECT {
+        return diagnostics;
+    }
+
+    for seg in segments {
+        let name = utils::byte_array_to_string(&seg.segname);
+        if seg.vmaddr % page_size != 0 {
+            diagnostics.push(Diagnostic::warning(
+                "segment-not-page-aligned",
+                format!("{} vmaddr 0x{:x} is not aligned to the 0x{:x} byte page size", name, seg.vmaddr, page_size),
+            ));
+        }
+        if seg.filesize > 0 && seg.fileoff % page_size != 0 {
+            diagnostics.push(Diagnostic::warning(
+                "segment-not-page-aligned",
+                format!("{} fileoff 0x{:x} is not aligned to the 0x{:x} byte page size", name, seg.fileoff, page_size),
+            ));
+        }
+    }
+
+    diagnostics
+}
+
+// Executables only, filetype is the short MH_* name from the report
+/*
+--detect-appended: where does the image the load commands describe actually end?
+    segments      --> fileoff + filesize, __LINKEDIT is normally the last one
+    linkedit data --> dataoff + datasize, the code signature is normally the very last blob
+                      and is allowed to be what the file ends with
+Anything between that end and the end of the slice is covered by no segment and no signature,
+the classic place to tack a payload onto an otherwise validly signed binary.
+Less than `threshold` bytes is treated as padding and not flagged.
+*/
+pub const DEFAULT_APPENDED_THRESHOLD: u64 = 16;
+
+// Slice relative end of everything the load commands account for
+pub fn image_file_end(segments: &[ParsedSegment], linkedit_data: &[LinkEditDataCommand]) -> u64 {
+    let segment_ends = segments.iter().map(|seg| seg.fileoff.saturating_add(seg.filesize));
+    let blob_ends = linkedit_data.iter().map(|ld| ld.dataoff as u64 + ld.datasize as u64);
+    segment_ends.chain(blob_ends).max().unwrap_or(0)
+}
+
+pub fn find_appended_data(
+    cpu_type: &str,
+    cpu_subtype: &str,
+    segments: &[ParsedSegment],
+    linkedit_data: &[LinkEditDataCommand],
+    slice_offset: u64,
+    slice_size: u64,
+    threshold: u64,
+) -> AppendedDataReport {
+    let image_end = image_file_end(segments, linkedit_data);
+    let appended_size = slice_size.saturating_sub(image_end);
+    let appended_offset = (appended_size > 0).then_some(slice_offset + image_end);
+
+    let mut diagnostics = Vec::new();
+    if appended_size > 0 && appended_size >= threshold {
+        let last = if linkedit_data.iter().any(|ld| ld.cmd == LC_CODE_SIGNATURE) { "the code signature" } else { "__LINKEDIT" };
+        diagnostics.push(Diagnostic::error(
+            "appended-data",
+            format!("{} bytes at file offset 0x{:x} past the end of {}, covered by no segment or signature", appended_size, slice_offset + image_end, last),
+        ));
+    }
+
+    AppendedDataReport {
+        cpu_type: cpu_type.to_string(),
+        cpu_subtype: cpu_subtype.to_string(),
+        image_end,
+        slice_size,
+        appended_offset,
+        appended_size,
+        diagnostics: diagnostics.iter().map(Diagnostic::build_report).collect(),
+    }
+}
+
+pub fn write_appended_data(w: &mut dyn Write, report: &AppendedDataReport) -> io::Result<()> {
+    writeln!(w)?;
+    writeln!(w, "{} ({})", "Appended Data".green().bold(), report.cpu_subtype)?;
+    writeln!(w, "----------------------------------------")?;
+    writeln!(w, "{} 0x{:x} of 0x{:x} bytes", "Image end    :".yellow().bold(), report.image_end, report.slice_size)?;
+
+    match report.appended_offset {
+        Some(offset) if !report.diagnostics.is_empty() => {
+            writeln!(w, "{} {} bytes at 0x{:x}", "APPENDED     :".red().bold(), report.appended_size, offset)?;
+            for d in &report.diagnostics {
+                writeln!(w, "{} [{}] {}", d.severity.red().bold(), d.code, d.message)?;
+            }
+        }
+        Some(offset) => writeln!(w, "{} {} bytes at 0x{:x} (padding)", "Trailing     :".yellow().bold(), report.appended_size, offset)?,
+        None => writeln!(w, "{} none", "Appended     :".yellow().bold())?,
+    }
+
+    writeln!(w, "----------------------------------------")?;
+    Ok(())
+}
+
+pub fn write_pagezero_summary(w: &mut dyn Write, filetype: &str, pagezero_size: Option<u64>, implicit_pagezero: bool) -> io::Result<()> {
+    if filetype != filetype_short_name(MH_EXECUTE) {
+        return Ok(());
+    }
+
+    let size = match pagezero_size {
+        Some(size) => format!("0x{:x} bytes", size),
+        None if implicit_pagezero => "none (MH_IMPLICIT_PAGEZERO)".to_string(),
+        None => "none".red().bold().to_string(),
+    };
+
+    writeln!(w, "{} {}", "Page zero    :".yellow().bold(), size)?;
+    Ok(())
+}
+
+pub fn write_vm_span_summary(w: &mut dyn Write, span: &VmSpanReport) -> io::Result<()> {
+    writeln!(w, "{} 0x{:x}-0x{:x} (0x{:x} bytes)", "VM span      :".yellow().bold(), span.low, span.high, span.size)?;
+    Ok(())
+}
+
+
+pub fn write_segments_summary(w: &mut dyn Write, segments: &[SegmentReport]) -> io::Result<()> {
+    if segments.is_empty() {
+        return Ok(());
+    }
+    writeln!(w)?;
+    writeln!(w, "{}", "Segments Summary".green().bold())?;
+    writeln!(w, "----------------------------------------")?;
+
+    for seg in segments {
         let vm_start = seg.vmaddr;
         let vm_end   = seg.vmaddr + seg.vmsize;
 
         let file_start = seg.fileoff;
         let file_end   = seg.fileoff + seg.filesize;
-        // rwx is just binary, mask it out below and we can apply coloring to them later if desired (I tried, it's hard to read at the moment)
-        // 001 is r -> 1
-        // 010 is w -> 2
-        // 100 is x -> 4
-        
-        let prot_r = if seg.initprot & 0x1 != 0 { "R" } else { "-".into() }; 
-        let prot_w = if seg.initprot & 0x2 != 0 { "W" } else { "-".into() };
-        let prot_x = if seg.initprot & 0x4 != 0 { "X" } else { "-".into() };
 
-        println!();
-        println!("{} {}", "Segment".yellow().bold(), seg_name.green().bold());
+        writeln!(w)?;
+        writeln!(w, "{} {}", "Segment".yellow().bold(), seg.name.green().bold())?;
 
-        println!("{} 0x{:016x} - 0x{:016x} ({:#x} bytes)", "  VM range   :".yellow().bold(), vm_start, vm_end, seg.vmsize);
+        writeln!(w, "{} 0x{:016x} - 0x{:016x} ({:#x} bytes)", "  VM range   :".yellow().bold(), vm_start, vm_end, seg.vmsize)?;
 
-        println!("{} 0x{:08x} - 0x{:08x} ({:#x} bytes)", "  File range :".yellow().bold(), file_start, file_end, seg.filesize);
+        writeln!(w, "{} 0x{:08x} - 0x{:08x} ({:#x} bytes)", "  File range :".yellow().bold(), file_start, file_end, seg.filesize)?;
 
-        println!("{} {}{}{}", "  Protections:".yellow().bold(), prot_r, prot_w, prot_x);
+        // initprot is already spelled out as "R-X" by build_report
+        writeln!(w, "{} {}", "  Protections:".yellow().bold(), seg.initprot)?;
 
-        println!("{} {}", "  Sections   :".yellow().bold(), seg.sections.len());
+        writeln!(w, "{} {}", "  Sections   :".yellow().bold(), seg.sections.len())?;
 
         for sect in &seg.sections {
-            let sect_name = utils::byte_array_to_string(&sect.sectname);
-
-            let kind_colored = match sect.kind {
-                // Executable code
-                SectionKind::Code               => format!("{:?}", sect.kind).blue().bold(),
-                
-                // Symbol stub / pointer consumers
-                SectionKind::SymbolStubs        => format!("{:?}", sect.kind).yellow().bold(),
-                SectionKind::LazySymbolPointers => format!("{:?}", sect.kind).cyan().bold(),
-                SectionKind::NonLazySymbolPointers => format!("{:?}", sect.kind).cyan().bold(),
-                SectionKind::GlobalOffsetTable  => format!("{:?}", sect.kind).cyan().bold(),
-
-                // Data
-                SectionKind::CString            => format!("{:?}", sect.kind).green().bold(),
-                SectionKind::ConstData          => format!("{:?}", sect.kind).green().bold(),
-                SectionKind::Data               => format!("{:?}", sect.kind).blue().bold(),
-                SectionKind::Bss                => format!("{:?}", sect.kind).blue().bold(),
-
-                // ObjC
-                SectionKind::ObjCClass          => format!("{:?}", sect.kind).green().bold(),
-                SectionKind::ObjCMetaClass      => format!("{:?}", sect.kind).green(),
-                SectionKind::ObjCSelectorRefs   => format!("{:?}", sect.kind).green(),
-                SectionKind::ObjCMethodNames    => format!("{:?}", sect.kind).green(),
-                SectionKind::ObjCMetadata       => format!("{:?}", sect.kind).green(),
-
-                // Exceptions / unwind
-                SectionKind::Exception          => format!("{:?}", sect.kind).yellow(),
-                SectionKind::Unwind             => format!("{:?}", sect.kind).yellow(),
-
-                // Init
-                SectionKind::Init               => format!("{:?}", sect.kind).yellow().bold(),
-
-                // Debug / LinkEdit
-                SectionKind::Debug              => format!("{:?}", sect.kind).normal(),
-                SectionKind::LinkEdit           => format!("{:?}", sect.kind).magenta().bold(),
-
-                // Fallbacks
-                SectionKind::Other              => format!("{:?}", sect.kind).normal(),
-                SectionKind::Unknown            => format!("{:?}", sect.kind).red().bold(),
+            // A kind we don't know (older/newer report) still prints, just uncolored
+            let kind_colored = match SectionKind::from_name(&sect.kind) {
+                Some(kind) => kind.colored(),
+                None => sect.kind.normal(),
             };
 
+            writeln!(w, "    - {:<16} {:<14} size={:#x}", sect.name, kind_colored, sect.size)?;
+        }
+    }
+
+    writeln!(w, "----------------------------------------")?;
+    writeln!(w)?;
+    Ok(())
+}
+
+/*
+--legend: what the section kind colors in the segments summary mean. Every kind is printed
+with SectionKind::colored itself, so the legend can't drift from the real colors.
+*/
+const SECTION_KIND_LEGEND: &[(&[SectionKind], &str)] = &[
+    (&[SectionKind::Code, SectionKind::Data, SectionKind::Bss], "code and writable data"),
+    (&[SectionKind::CString, SectionKind::ConstData, SectionKind::Literals, SectionKind::ObjCClass], "constants, strings and ObjC classes"),
+    (&[SectionKind::ObjCMetaClass, SectionKind::ObjCSelectorRefs, SectionKind::ObjCMethodNames, SectionKind::ObjCMetadata], "Objective-C metadata"),
+    (&[SectionKind::LazySymbolPointers, SectionKind::NonLazySymbolPointers, SectionKind::GlobalOffsetTable], "symbol pointers dyld fills in"),
+    (&[SectionKind::SymbolStubs, SectionKind::Init], "linker stubs and initializers"),
+    (&[SectionKind::Exception, SectionKind::Unwind], "exception and unwind tables"),
+    (&[SectionKind::LinkEdit], "__LINKEDIT metadata"),
+    (&[SectionKind::Debug, SectionKind::Other], "debug info and everything else"),
+    (&[SectionKind::Unknown], "unrecognized, worth a closer look"),
+];
+
+pub fn write_section_legend(w: &mut dyn Write) -> io::Result<()> {
+    writeln!(w)?;
+    writeln!(w, "{}", "Section Kind Legend".green().bold())?;
+    writeln!(w, "----------------------------------------")?;
+
+    for (kinds, meaning) in SECTION_KIND_LEGEND {
+        let names: Vec<String> = kinds.iter().map(|k| k.colored().to_string()).collect();
+        writeln!(w, "  {:<34}{}", meaning, names.join(" "))?;
+    }
+    Ok(())
+}
+
+/*
+--count-sections-by-kind: a one line structural fingerprint of the slice
+    Sections by kind: Code: 1, SymbolStubs: 1, CString: 1, Unwind: 1, Unknown: 2
+Kinds come out in SectionKind declaration order so two runs (or two binaries) diff cleanly,
+Unknown/Other last where the classify_section gaps are easy to spot.
+*/
+pub fn count_sections_by_kind(segments: &[SegmentReport]) -> HashMap<SectionKind, usize> {
+    segments
+        .iter()
+        .flat_map(|seg| &seg.sections)
+        .fold(HashMap::new(), |mut counts, sect| {
+            *counts.entry(SectionKind::from_name(&sect.kind).unwrap_or(SectionKind::Unknown)).or_insert(0) += 1;
+            counts
+        })
+}
+
+pub fn write_section_kind_counts(w: &mut dyn Write, counts: &HashMap<SectionKind, usize>) -> io::Result<()> {
+    if counts.is_empty() {
+        return Ok(());
+    }
+
+    let mut kinds: Vec<(&SectionKind, &usize)> = counts.iter().collect();
+    kinds.sort();
+
+    let line: Vec<String> = kinds
+        .iter()
+        .map(|(kind, count)| {
+            let entry = format!("{}: {}", kind.as_str(), count);
+            if **kind == SectionKind::Unknown { entry.red().bold().to_string() } else { entry }
+        })
+        .collect();
+
+    writeln!(w, "{} {}", "Sections by kind:".yellow().bold(), line.join(", "))?;
+    writeln!(w)?;
+    Ok(())
+}
+
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macho::constants::*;
+
+    fn section(addr: u64, offset: u32, size: u64, flags: u32) -> ParsedSection {
+        ParsedSection {
+            sectname: SECT_DATA,
+            segname: SEG_DATA,
+            offset,
+            addr,
+            size,
+            align: 3,
+            reloff: 0,
+            nreloc: 0,
+            flags,
+            kind: classify_section(SECT_DATA, flags, SEG_DATA),
+            reserved1: 0,
+            reserved2: 0,
+            reserved3: None,
+        }
+    }
+
+    fn segment(sections: Vec<ParsedSection>) -> ParsedSegment {
+        ParsedSegment {
+            segname: SEG_DATA,
+            vmaddr: 0x1000,
+            vmsize: 0x1000,
+            fileoff: 0x4000,
+            filesize: 0x800,
+            maxprot: 3,
+            initprot: 3,
+            flags: 0,
+            sections,
+        }
+    }
+
+    #[test]
+    fn section_inside_segment_is_clean() {
+        let seg = segment(vec![section(0x1000, 0x4000, 0x800, S_REGULAR)]);
+        assert!(validate_section_bounds(&[seg]).is_empty());
+    }
+
+    #[test]
+    fn section_escaping_vm_range_is_reported() {
+        let seg = segment(vec![section(0x1f00, 0x4000, 0x200, S_REGULAR)]);
+        let diags = validate_section_bounds(&[seg]);
 
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].code, "section-outside-segment-vm");
+    }
+
+    #[test]
+    fn section_escaping_file_range_is_reported() {
+        // fits in VM (vmsize 0x1000) but the segment only has 0x800 bytes in the file
+        let seg = segment(vec![section(0x1000, 0x4000, 0x900, S_REGULAR)]);
+        let diags = validate_section_bounds(&[seg]);
+
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].code, "section-outside-segment-file");
+    }
+
+    #[test]
+    fn zerofill_section_skips_file_check() {
+        // __bss lives past the file backed part of the segment and has offset 0
+        let seg = segment(vec![section(0x1800, 0, 0x800, S_ZEROFILL)]);
+        assert!(validate_section_bounds(&[seg]).is_empty());
+    }
 
-            println!("    - {:<16} {:<14} size={:#x}", sect_name, kind_colored, sect.size);
+    #[test]
+    fn wrapping_section_is_reported() {
+        let seg = segment(vec![section(u64::MAX - 4, 0x4000, 0x10, S_REGULAR)]);
+        let diags = validate_section_bounds(&[seg]);
+
+        assert!(diags.iter().any(|d| d.code == "section-outside-segment-vm"));
+    }
+
+    fn named(mut sect: ParsedSection, name: [u8; 16]) -> ParsedSection {
+        sect.sectname = name;
+        sect
+    }
+
+    #[test]
+    fn adjacent_sections_do_not_overlap() {
+        let seg = segment(vec![
+            named(section(0x1000, 0x4000, 0x400, S_REGULAR), SECT_DATA),
+            named(section(0x1400, 0x4400, 0x400, S_REGULAR), SECT_CONST),
+        ]);
+        assert!(validate_section_layout(&[seg]).is_empty());
+    }
+
+    #[test]
+    fn overlapping_sections_are_reported() {
+        // __const starts inside __data, listed out of order to make sure we sort first
+        let seg = segment(vec![
+            named(section(0x1300, 0x4300, 0x200, S_REGULAR), SECT_CONST),
+            named(section(0x1000, 0x4000, 0x400, S_REGULAR), SECT_DATA),
+        ]);
+        let diags = validate_section_layout(&[seg]);
+
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].code, "section-overlap");
+        assert!(diags[0].message.contains("__DATA,__const"), "{}", diags[0].message);
+        assert!(diags[0].message.contains("__DATA,__data"), "{}", diags[0].message);
+    }
+
+    #[test]
+    fn section_covering_several_others_is_reported_for_each() {
+        let seg = segment(vec![
+            named(section(0x1000, 0x4000, 0x800, S_REGULAR), SECT_DATA),
+            named(section(0x1100, 0x4100, 0x100, S_REGULAR), SECT_CONST),
+            named(section(0x1400, 0x4400, 0x100, S_REGULAR), SECT_CSTRING),
+        ]);
+        let diags = validate_section_layout(&[seg]);
+
+        assert_eq!(diags.len(), 2);
+        assert!(diags.iter().all(|d| d.code == "section-overlap" && d.message.contains("overlaps __DATA,__data")));
+    }
+
+    #[test]
+    fn empty_section_with_file_offset_is_reported() {
+        let seg = segment(vec![
+            named(section(0x1000, 0x4000, 0, S_REGULAR), SECT_CONST),
+            named(section(0x1000, 0, 0, S_REGULAR), SECT_DATA),       // no offset, nothing to complain about
+            named(section(0x1800, 0x4800, 0, S_ZEROFILL), SECT_BSS),  // zerofill offsets are meaningless anyway
+        ]);
+        let diags = validate_section_layout(&[seg]);
+
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].code, "section-empty-with-offset");
+        assert!(diags[0].message.contains("__DATA,__const"));
+    }
+
+    #[test]
+    fn find_section_matches_segment_and_section_name() {
+        let mut stubs = section(0x1800, 0x4800, 0x30, S_SYMBOL_STUBS | S_ATTR_PURE_INSTRUCTIONS);
+        stubs.sectname = SECT_STUBS;
+        stubs.reserved1 = 4;
+        stubs.reserved2 = 12;
+        let segments = vec![segment(vec![section(0x1000, 0x4000, 0x100, S_REGULAR), stubs])];
+
+        let found = find_section(&segments, "__DATA", "__stubs").expect("section not found");
+        assert_eq!(found.addr, 0x1800);
+        assert!(find_section(&segments, "__TEXT", "__stubs").is_none());
+        assert!(find_section(&segments, "__DATA", "__bss").is_none());
+
+        let report = found.build_info_report("ARM", "arm64", true, Some(6));
+        let range = report.indirect_symbols.expect("stubs consume indirect symbols");
+        assert_eq!((range.first_index, range.count, range.entry_size), (4, 4, 12));
+        assert!(range.out_of_bounds); // 4 + 4 > 6
+        assert_eq!(report.section_type, "S_SYMBOL_STUBS");
+    }
+
+    fn executable_header(is_64: bool, flags: u32) -> MachOHeader {
+        use crate::macho::header::{MachHeader32, MachHeader64};
+        if is_64 {
+            MachOHeader::Header64(MachHeader64 {
+                magic: 0, cputype: CPU_TYPE_ARM64, cpusubtype: 0, filetype: MH_EXECUTE,
+                ncmds: 0, sizeofcmds: 0, flags, reserved: 0,
+            })
+        } else {
+            MachOHeader::Header32(MachHeader32 {
+                magic: 0, cputype: CPU_TYPE_X86, cpusubtype: 0, filetype: MH_EXECUTE,
+                ncmds: 0, sizeofcmds: 0, flags,
+            })
         }
     }
 
-    println!("----------------------------------------");
-    println!();
-}
\ No newline at end of file
+    fn pagezero(vmsize: u64) -> ParsedSegment {
+        ParsedSegment {
+            segname: SEG_PAGEZERO,
+            vmaddr: 0,
+            vmsize,
+            fileoff: 0,
+            filesize: 0,
+            maxprot: 0,
+            initprot: 0,
+            flags: 0,
+            sections: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn default_pagezero_is_clean() {
+        let segments = vec![pagezero(DEFAULT_PAGEZERO_SIZE_64), segment(Vec::new())];
+
+        assert_eq!(pagezero_size(&segments), Some(0x1_0000_0000));
+        assert!(validate_pagezero(&segments, &executable_header(true, 0)).is_empty());
+        assert!(validate_pagezero(&[pagezero(0x1000)], &executable_header(false, 0)).is_empty());
+    }
+
+    #[test]
+    fn vm_span_leaves_out_pagezero() {
+        let text = ParsedSegment { segname: SEG_TEXT, vmaddr: 0x1_0000_0000, vmsize: 0x4000, ..segment(Vec::new()) };
+        let data = ParsedSegment { vmaddr: 0x1_0000_8000, vmsize: 0x4000, ..segment(Vec::new()) };
+        let segments = vec![pagezero(DEFAULT_PAGEZERO_SIZE_64), data, text];
+
+        assert_eq!(vm_range(&segments), Some((0, 0x1_0000_c000)));
+        assert_eq!(vm_span(&segments), Some(VmSpanReport { low: 0x1_0000_0000, high: 0x1_0000_c000, size: 0xc000 }));
+        assert_eq!(vm_span(&[pagezero(DEFAULT_PAGEZERO_SIZE_64)]), None);
+    }
+
+    #[test]
+    fn bytes_past_the_code_signature_are_appended() {
+        // __DATA ends at 0x4800, the signature sits right behind it
+        let segments = [segment(Vec::new())];
+        let signature = [LinkEditDataCommand { cmd: LC_CODE_SIGNATURE, dataoff: 0x4800, datasize: 0x200 }];
+        assert_eq!(image_file_end(&segments, &signature), 0x4a00);
+
+        let clean = find_appended_data("ARM64", "arm64", &segments, &signature, 0, 0x4a00, 16);
+        assert_eq!((clean.appended_offset, clean.appended_size), (None, 0));
+        assert!(clean.diagnostics.is_empty());
+
+        // a few bytes of padding are fine, a payload is not
+        let padded = find_appended_data("ARM64", "arm64", &segments, &signature, 0x8000, 0x4a08, 16);
+        assert_eq!((padded.appended_offset, padded.appended_size), (Some(0xca00), 8));
+        assert!(padded.diagnostics.is_empty());
+
+        let payload = find_appended_data("ARM64", "arm64", &segments, &signature, 0x8000, 0x5a00, 16);
+        assert_eq!(payload.appended_size, 0x1000);
+        assert_eq!(payload.diagnostics[0].code, "appended-data");
+    }
+
+    #[test]
+    fn small_pagezero_is_flagged() {
+        let diags = validate_pagezero(&[pagezero(0x1000)], &executable_header(true, 0));
+
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].code, "pagezero-small");
+    }
+
+    #[test]
+    fn missing_pagezero_is_only_flagged_without_implicit_flag() {
+        let segments = vec![segment(Vec::new())];
+
+        let diags = validate_pagezero(&segments, &executable_header(true, 0));
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].code, "pagezero-missing");
+
+        assert!(validate_pagezero(&segments, &executable_header(true, MH_IMPLICIT_PAGEZERO)).is_empty());
+    }
+
+    #[test]
+    fn count_sections_by_kind_tallies_every_segment() {
+        let mut text = section(0x1000, 0x1000, 0x10, S_REGULAR);
+        text.kind = SectionKind::Code;
+        let mut cstring = section(0x1010, 0x1010, 0x10, S_CSTRING_LITERALS);
+        cstring.kind = SectionKind::CString;
+        let mut odd = section(0x2000, 0x2000, 0x10, S_REGULAR);
+        odd.kind = SectionKind::Unknown;
+
+        let counts = count_sections_by_kind(&[
+            segment(vec![text, cstring, odd.clone()]).build_report(),
+            segment(vec![odd]).build_report(),
+        ]);
+
+        assert_eq!(counts.len(), 3);
+        assert_eq!(counts[&SectionKind::Code], 1);
+        assert_eq!(counts[&SectionKind::CString], 1);
+        assert_eq!(counts[&SectionKind::Unknown], 2);
+    }
+
+    #[test]
+    fn misaligned_segment_is_flagged() {
+        let mut text = pagezero(0x4000);
+        text.segname = *b"__TEXT\0\0\0\0\0\0\0\0\0\0";
+        text.vmaddr = 0x1_0000_0000;
+        text.filesize = 0x4000;
+        let mut data = text.clone();
+        data.segname = *b"__DATA\0\0\0\0\0\0\0\0\0\0";
+        data.vmaddr = 0x1_0000_5000; // 4K aligned, not 16K
+        data.fileoff = 0x5000;
+
+        let header = executable_header(true, 0);
+        assert!(validate_segment_alignment(&[pagezero(DEFAULT_PAGEZERO_SIZE_64), text.clone()], &header, page_size(CPU_TYPE_ARM64)).is_empty());
+
+        let diags = validate_segment_alignment(&[text.clone(), data.clone()], &header, page_size(CPU_TYPE_ARM64));
+        assert_eq!(diags.len(), 2); // vmaddr and fileoff
+        assert!(diags.iter().all(|d| d.code == "segment-not-page-aligned" && d.message.starts_with("__DATA")));
+
+        // fine with x86_64's 4K pages
+        assert!(validate_segment_alignment(&[text.clone(), data.clone()], &header, page_size(CPU_TYPE_X86_64)).is_empty());
+
+        assert_eq!(data.vm_alignment(), Some(0x1000));
+        assert_eq!(text.vm_alignment(), Some(0x1_0000_0000));
+        assert_eq!(pagezero(0x1000).vm_alignment(), None);
+    }
+
+    #[test]
+    fn legend_covers_every_section_kind() {
+        let mut listed: Vec<SectionKind> = SECTION_KIND_LEGEND.iter().flat_map(|(kinds, _)| kinds.iter().copied()).collect();
+        listed.sort();
+        listed.dedup();
+        assert_eq!(listed.len(), SectionKind::Unknown as usize + 1);
+
+        let mut out = Vec::new();
+        write_section_legend(&mut out).unwrap();
+        let out = crate::test_util::strip_ansi(&String::from_utf8(out).unwrap());
+        assert!(out.contains("code and writable data"));
+        assert!(out.contains("Code Data Bss"));
+    }
+}
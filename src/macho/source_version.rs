@@ -0,0 +1,112 @@
+// File Purpose: Which source tree was this built from? (LC_SOURCE_VERSION)
+
+use std::error::Error;
+use std::io::{self, Write};
+use colored::Colorize;
+use crate::macho::constants::LC_SOURCE_VERSION;
+use crate::macho::load_commands::LoadCommand;
+use crate::macho::utils;
+
+/*
+source_version_command memory layout (from loader.h)
++-----------------------------+
+| cmd (u32)                   |  LC_SOURCE_VERSION
+| cmdsize (u32)               |  always 16
+| version (u64)               |  A.B.C.D.E packed as a24.b10.c10.d10.e10
++-----------------------------+
+
+    bits 63..40  A   (24 bits)
+    bits 39..30  B   (10 bits)
+    bits 29..20  C   (10 bits)
+    bits 19..10  D   (10 bits)
+    bits  9..0   E   (10 bits)
+
+Apple's project versions (the "1234.5.6" of a B&I build), ld only copies it over from -source_version.
+*/
+
+pub const SOURCE_VERSION_COMMAND_SIZE: u32 = 16;
+
+// A.B.C.D.E, trailing zero groups dropped past A.B like otool does (1234.5.0.0.0 --> 1234.5)
+pub fn format_source_version(packed: u64) -> String {
+    let groups = [
+        packed >> 40,
+        (packed >> 30) & 0x3ff,
+        (packed >> 20) & 0x3ff,
+        (packed >> 10) & 0x3ff,
+        packed & 0x3ff,
+    ];
+    let keep = groups.iter().rposition(|&g| g != 0).map_or(2, |last| (last + 1).max(2));
+    groups[..keep].iter().map(u64::to_string).collect::<Vec<_>>().join(".")
+}
+
+pub fn parse_source_version(data: &[u8], lc: &LoadCommand, is_be: bool) -> Result<String, Box<dyn Error>> {
+    if lc.cmdsize != SOURCE_VERSION_COMMAND_SIZE {
+        return Err(format!("LC_SOURCE_VERSION has cmdsize {} (expected {})", lc.cmdsize, SOURCE_VERSION_COMMAND_SIZE).into());
+    }
+
+    let base = lc.offset as usize;
+    if base + SOURCE_VERSION_COMMAND_SIZE as usize > data.len() {
+        return Err("LC_SOURCE_VERSION exceeds file bounds".into());
+    }
+
+    Ok(format_source_version(utils::bytes_to(is_be, &data[base + 8..])?))
+}
+
+pub fn find_source_version(data: &[u8], load_commands: &[LoadCommand], is_be: bool) -> Result<Option<String>, Box<dyn Error>> {
+    match load_commands.iter().find(|lc| lc.cmd == LC_SOURCE_VERSION) {
+        Some(lc) => Ok(Some(parse_source_version(data, lc, is_be)?)),
+        None => Ok(None),
+    }
+}
+
+pub fn write_source_version(w: &mut dyn Write, version: &str) -> io::Result<()> {
+    writeln!(w, "{} {}", "Source Version:".yellow().bold(), version)?;
+    Ok(())
+}
+
+
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pack(a: u64, b: u64, c: u64, d: u64, e: u64) -> u64 {
+        (a << 40) | (b << 30) | (c << 20) | (d << 10) | e
+    }
+
+    #[test]
+    fn all_five_groups_are_extracted() {
+        assert_eq!(format_source_version(pack(1234, 5, 6, 7, 8)), "1234.5.6.7.8");
+        // every field at its maximum
+        assert_eq!(format_source_version(u64::MAX), "16777215.1023.1023.1023.1023");
+        // raw u64s as they sit in the command
+        assert_eq!(format_source_version(0x000c_8c00_8080_0000), "3212.2.8");
+        assert_eq!(format_source_version(0x0000_0000_0000_0001), "0.0.0.0.1");
+    }
+
+    #[test]
+    fn trailing_zero_groups_are_trimmed() {
+        assert_eq!(format_source_version(pack(1234, 5, 0, 0, 0)), "1234.5");
+        assert_eq!(format_source_version(pack(1234, 0, 0, 0, 0)), "1234.0");
+        assert_eq!(format_source_version(0), "0.0");
+        assert_eq!(format_source_version(pack(1, 0, 3, 0, 0)), "1.0.3");
+    }
+
+    #[test]
+    fn source_version_command_is_read() {
+        let mut data = Vec::new();
+        data.extend_from_slice(&LC_SOURCE_VERSION.to_le_bytes());
+        data.extend_from_slice(&16u32.to_le_bytes());
+        data.extend_from_slice(&pack(902, 1, 2, 0, 0).to_le_bytes());
+        let lc = LoadCommand { cmd: LC_SOURCE_VERSION, cmdsize: 16, offset: 0 };
+
+        assert_eq!(parse_source_version(&data, &lc, false).unwrap(), "902.1.2");
+        assert!(parse_source_version(&data, &LoadCommand { cmdsize: 24, ..lc }, false).is_err());
+        assert!(parse_source_version(&data[..12], &lc, false).is_err());
+    }
+}
@@ -0,0 +1,136 @@
+// File Purpose: How does this image fit into an umbrella framework? (LC_SUB_FRAMEWORK / CLIENT / UMBRELLA / LIBRARY)
+
+use std::error::Error;
+use std::io::{self, Write};
+use colored::Colorize;
+use crate::macho::constants::{LC_SUB_CLIENT, LC_SUB_FRAMEWORK, LC_SUB_LIBRARY, LC_SUB_UMBRELLA};
+use crate::macho::load_commands::{self, LoadCommand};
+use crate::reporting::sub_images::SubImageReport;
+
+/*
+All four are just cmd, cmdsize and one lc_str (from loader.h):
++-----------------------------+
+| cmd (u32)                   |
+| cmdsize (u32)               |
+| name.offset (u32)           |  from the start of the command
+| "name\0"                    |  padded to cmdsize
++-----------------------------+
+
+    LC_SUB_FRAMEWORK   this image is part of umbrella framework <name>, only it may link us directly
+    LC_SUB_CLIENT      ...except for <name>, which is allowed to link us too
+    LC_SUB_UMBRELLA    an umbrella re-exporting sub-umbrella <name>
+    LC_SUB_LIBRARY     an umbrella re-exporting sub-library <name> (install name minus path and suffix)
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubImageKind {
+    Framework,
+    Client,
+    Umbrella,
+    Library,
+}
+
+impl SubImageKind {
+    pub fn from_cmd(cmd: u32) -> Option<Self> {
+        match cmd {
+            LC_SUB_FRAMEWORK => Some(SubImageKind::Framework),
+            LC_SUB_CLIENT => Some(SubImageKind::Client),
+            LC_SUB_UMBRELLA => Some(SubImageKind::Umbrella),
+            LC_SUB_LIBRARY => Some(SubImageKind::Library),
+            _ => None,
+        }
+    }
+
+    // Shared by the text summary and the JSON "kind" field
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SubImageKind::Framework => "FRAMEWORK",
+            SubImageKind::Client => "CLIENT",
+            SubImageKind::Umbrella => "UMBRELLA",
+            SubImageKind::Library => "LIBRARY",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedSubImage {
+    pub kind: SubImageKind,
+    pub name: String,
+}
+
+impl ParsedSubImage {
+    pub fn build_report(&self) -> SubImageReport {
+        SubImageReport {
+            kind: self.kind.as_str().to_string(),
+            name: self.name.clone(),
+        }
+    }
+}
+
+pub fn parse_sub_image(data: &[u8], lc: &LoadCommand, is_be: bool) -> Result<ParsedSubImage, Box<dyn Error>> {
+    let kind = SubImageKind::from_cmd(lc.cmd).ok_or_else(|| format!("{} is not a sub-image command", load_commands::load_command_name(lc.cmd)))?;
+    Ok(ParsedSubImage { kind, name: load_commands::read_lc_str(data, lc, 8, is_be)? })
+}
+
+pub fn write_sub_images_summary(w: &mut dyn Write, sub_images: &[SubImageReport]) -> io::Result<()> {
+    if sub_images.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(w, "{}", "\nSub-images".green().bold())?;
+    writeln!(w, "----------------------------------------")?;
+    for sub in sub_images {
+        writeln!(w, "[{:<9}] {}", sub.kind.yellow().bold(), sub.name)?;
+    }
+    Ok(())
+}
+
+
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sub_command(cmd: u32, name_offset: u32, name: &[u8]) -> (Vec<u8>, LoadCommand) {
+        let mut data = Vec::new();
+        data.extend_from_slice(&cmd.to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes()); // cmdsize, patched below
+        data.extend_from_slice(&name_offset.to_le_bytes());
+        data.extend_from_slice(name);
+        data.resize(data.len().next_multiple_of(8), 0);
+        let cmdsize = data.len() as u32;
+        data[4..8].copy_from_slice(&cmdsize.to_le_bytes());
+        (data, LoadCommand { cmd, cmdsize, offset: 0 })
+    }
+
+    #[test]
+    fn each_kind_keeps_its_name() {
+        for (cmd, kind) in [
+            (LC_SUB_FRAMEWORK, SubImageKind::Framework),
+            (LC_SUB_CLIENT, SubImageKind::Client),
+            (LC_SUB_UMBRELLA, SubImageKind::Umbrella),
+            (LC_SUB_LIBRARY, SubImageKind::Library),
+        ] {
+            let (data, lc) = sub_command(cmd, 12, b"CoreServices\0");
+            assert_eq!(parse_sub_image(&data, &lc, false).unwrap(), ParsedSubImage { kind, name: "CoreServices".into() });
+        }
+    }
+
+    #[test]
+    fn name_offset_past_cmdsize_is_an_error() {
+        let (data, lc) = sub_command(LC_SUB_FRAMEWORK, 12, b"Carbon\0");
+        assert!(parse_sub_image(&data, &LoadCommand { cmdsize: 12, ..lc }, false).is_err());
+
+        let (data, lc) = sub_command(LC_SUB_FRAMEWORK, 0x100, b"Carbon\0");
+        assert!(parse_sub_image(&data, &lc, false).unwrap_err().to_string().contains("exceeds cmdsize"));
+
+        // no NUL before cmdsize runs out
+        let (mut data, lc) = sub_command(LC_SUB_UMBRELLA, 12, b"Carbon\0");
+        data.iter_mut().skip(12).for_each(|b| *b = b'x');
+        assert!(parse_sub_image(&data, &lc, false).is_err());
+    }
+}
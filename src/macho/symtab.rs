@@ -1,8 +1,11 @@
-use std::error::Error;
-use clap::parser::Indices;
+use std::collections::HashMap;
+use std::io::{self, Write};
 use colored::Colorize;
 use regex::Regex;
 use crate::macho::utils;
+use crate::macho::error::MoscopeError;
+use crate::macho::dylibs::{self, ParsedDylib};
+use crate::macho::load_commands::LoadCommand;
 use crate::macho::constants::*;
 use crate::reporting::symtab::*;
 
@@ -87,6 +90,21 @@ pub enum SymbolKind {
 }
 
 impl SymbolKind {
+    // Used for both the text table and the JSON "kind" field, part of the report contract
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SymbolKind::Undefined           => "UNDEF",
+            SymbolKind::Absolute            => "ABS",
+            SymbolKind::Section             => "SECT",
+            SymbolKind::PreboundUndefined   => "PBUD",
+            SymbolKind::Indirect            => "INDR",
+            SymbolKind::Lazy                => "LAZY",
+            SymbolKind::Stub                => "STUB",
+            SymbolKind::Got                 => "GOT",
+            SymbolKind::Unknown             => "UNKNOWN",
+        }
+    }
+
     pub fn from_n_type(n_type: u8) -> Self {
         match n_type & N_TYPE {
             N_UNDF => SymbolKind::Undefined,
@@ -101,16 +119,59 @@ impl SymbolKind {
 
 
 
+/*
+What a string looked like on disk before it became a Rust String:
+    Ascii   --> every byte < 0x80, the overwhelming majority of __cstring
+    Utf8    --> valid UTF-8 with at least one multi-byte sequence
+    Utf16Le --> __TEXT,__ustring, where clang puts CFString literals that aren't plain ASCII
+A leading BOM (EF BB BF / FF FE) is stripped from the value, it's still in the raw bytes.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StringEncoding {
+    Ascii,
+    Utf8,
+    Utf16Le,
+}
+
+impl StringEncoding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StringEncoding::Ascii => "ascii",
+            StringEncoding::Utf8 => "utf8",
+            StringEncoding::Utf16Le => "utf16le",
+        }
+    }
+}
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16LE_BOM: u16 = 0xFEFF;
+
+// One string pulled out of a section, before it gets its segment/section attached
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtractedString {
+    pub value: String,
+    pub encoding: StringEncoding,
+    pub raw: Vec<u8>, // exact bytes from the section, BOM included, terminator not
+    pub offset: usize, // where raw starts, relative to the start of the section
+}
+
+#[derive(Debug, Clone)]
 pub struct ParsedString {
     pub value: String,
+    pub encoding: StringEncoding,
+    pub raw: Option<Vec<u8>>, // only kept with --raw-strings
     pub segname: [u8; 16],
     pub sectname: [u8; 16],
+    pub section_index: u32, // 1-based over every section in the slice, like n_sect
+    pub offset: u64, // where the string starts inside its section
 }
 
 impl ParsedString {
-    pub fn build_report(&self, _is_json: bool) -> StringReport {
+    pub fn build_report(&self) -> StringReport {
         StringReport { 
             value: self.value.clone(), 
+            encoding: self.encoding.as_str().to_string(),
+            raw_hex: self.raw.as_ref().map(|raw| utils::hex_string(raw)),
             segname: String::from_utf8_lossy(&self.segname).trim_end_matches('\0').to_string(), 
             sectname: String::from_utf8_lossy(&self.sectname).trim_end_matches('\0').to_string()
         }
@@ -133,6 +194,7 @@ pub struct ParsedSymbol {
     pub n_sect: u8,
     pub indirect_addr: Option<u64>,
     pub indirect_sect: Option<String>,
+    pub library: Option<String>, // imports only, and only in two-level namespace images
 }
 
 impl ParsedSymbol {
@@ -158,6 +220,7 @@ impl ParsedSymbol {
             segname: None,
             indirect_addr: None,
             indirect_sect: None,
+            library: None,
         }
     }
 
@@ -171,7 +234,7 @@ impl ParsedSymbol {
 
         ParsedSymbol {
             name,
-            addr: nlist.n_value as u64,
+            addr: nlist.n_value,
             n_type: nlist.n_type,
             n_sect: nlist.n_sect,
             n_desc: nlist.n_desc,
@@ -184,9 +247,15 @@ impl ParsedSymbol {
             segname: None,
             indirect_addr: None,
             indirect_sect: None,
+            library: None,
         }
     }
 
+    // Decoded STAB type for debug entries (N_SO, N_OSO, N_FUN, ...), None for regular symbols
+    pub fn stab_type(&self) -> Option<&'static str> {
+        if self.is_debug { Some(stab_type_name(self.n_type)) } else { None }
+    }
+
     pub fn effective_addr(&self) -> Option<u64> {
         if let Some(indirect) = self.indirect_addr {
             Some(indirect)
@@ -197,52 +266,73 @@ impl ParsedSymbol {
         }
     }
 
-    pub fn build_report(&self, json: bool) -> SymbolReport {
+    /*
+    The two questions everyone asks of a symbol table, answered once:
+        import --> external, not defined here. After indirect symbol reclassification an import
+                   can also be Lazy / Stub / Got, it's still undefined in this binary
+        export --> external and defined here (N_SECT, N_ABS, or an N_INDR re-export)
+    Debug (STAB) entries are neither.
+    */
+    pub fn is_import(&self) -> bool {
+        self.is_external
+            && !self.is_debug
+            && matches!(
+                self.kind,
+                SymbolKind::Undefined | SymbolKind::PreboundUndefined | SymbolKind::Lazy | SymbolKind::Stub | SymbolKind::Got
+            )
+    }
+
+    pub fn is_export(&self) -> bool {
+        self.is_external && !self.is_debug && matches!(self.kind, SymbolKind::Section | SymbolKind::Absolute | SymbolKind::Indirect)
+    }
+
+    pub fn build_report(&self) -> SymbolReport {
         let eff_addr = self.effective_addr();
         SymbolReport {
             name: self.name.clone(),
+            source_name: strip_leading_underscore(&self.name).to_string(),
             value: self.value,
             addr: self.addr,
             addr_hex: eff_addr.map(|a| format!("0x{:016x}", a)).unwrap_or_else(|| "-".to_string()),
-            kind: if json {
-                self.kind_plain()
-            } else {
-                self.kind_colored()
-            },
+            kind: self.kind.as_str().to_string(),
             section: self.section.map(|s| s.0),
             external: self.is_external,
             debug: self.is_debug,
+            is_import: self.is_import(),
+            is_export: self.is_export(),
+            stab_type: self.stab_type().map(|s| s.to_string()),
             sectname: self.sectname.clone(),
             segname: self.segname.clone(),
+            library: self.library.clone(),
         }
     }
 
-    fn kind_plain(&self) -> String {
-        match self.kind {
-            SymbolKind::Undefined           => "UNDEF",
-            SymbolKind::Absolute            => "ABS",
-            SymbolKind::Section             => "SECT",
-            SymbolKind::PreboundUndefined   => "PBUD",
-            SymbolKind::Indirect            => "INDR",
-            SymbolKind::Lazy                => "LAZY",
-            SymbolKind::Stub                => "STUB",
-            SymbolKind::Got                 => "GOT",
-            SymbolKind::Unknown             => "UNKNOWN"
-        }.to_string()
+    /*
+    Two-level namespace imports name the dylib they come from in the high byte of n_desc
+    (GET_LIBRARY_ORDINAL in <mach-o/nlist.h>):
+        0x00        --> SELF_LIBRARY_ORDINAL, this image
+        0x01..0xfd  --> dependency ordinal, same numbering as dylib_for_ordinal
+        0xfe        --> DYNAMIC_LOOKUP_ORDINAL, looked up flat at runtime
+        0xff        --> EXECUTABLE_ORDINAL, the main executable
+    Flat namespace images don't set it, dyld searches every loaded image in order, so the
+    caller has to check MH_TWOLEVEL before believing any of this.
+    */
+    pub fn library_ordinal(&self) -> u8 {
+        (self.n_desc >> 8) as u8
     }
 
-    fn kind_colored(&self) -> String {
-        match self.kind {
-            SymbolKind::Undefined           => "UNDEF".yellow().bold(),
-            SymbolKind::Absolute            => "ABS".yellow().bold(),
-            SymbolKind::Section             => "SECT".green().bold(),
-            SymbolKind::PreboundUndefined   => "PBUD".yellow().bold(),
-            SymbolKind::Indirect            => "INDR".yellow().bold(),
-            SymbolKind::Lazy                => "LAZY".yellow().bold(),
-            SymbolKind::Stub                => "STUB".yellow().bold(),
-            SymbolKind::Got                 => "GOT".yellow().bold(),
-            SymbolKind::Unknown             => "UNKNOWN".red().bold(),
-        }.to_string()
+    pub fn resolve_library(&self, dylibs: &[ParsedDylib]) -> Option<String> {
+        if !self.is_import() {
+            return None;
+        }
+        let ordinal = self.library_ordinal();
+        if let Some(name) = dylibs::special_ordinal_name(ordinal) {
+            return Some(name.to_string());
+        }
+        Some(match dylibs::dylib_for_ordinal(dylibs, ordinal as u64) {
+            Some(dylib) => dylib.path.clone(),
+            None => format!("<invalid ordinal {}>", ordinal),
+        })
     }
 
     pub fn bind_str(&self) -> &'static str {
@@ -293,13 +383,65 @@ pub struct DYSymtabCommand {
     pub nlocrel: u32, // number of local relocation entries
 }
 
+pub fn read_symtab_command(data: &[u8], lc: &LoadCommand, is_be: bool) -> Result<SymtabCommand, MoscopeError> {
+    let off = lc.offset as usize;
+    if data.get(off..off + 24).is_none() {
+        return Err(MoscopeError::OutOfBounds { offset: lc.offset, len: 24 });
+    }
+    Ok(SymtabCommand {
+        cmd: lc.cmd,
+        cmdsize: lc.cmdsize,
+        symoff: utils::bytes_to(is_be, &data[off + 8 .. off + 12])?,
+        nsyms: utils::bytes_to(is_be, &data[off + 12 .. off + 16])?,
+        stroff: utils::bytes_to(is_be, &data[off + 16 .. off + 20])?,
+        strsize: utils::bytes_to(is_be, &data[off + 20 .. off + 24])?,
+    })
+}
+
+pub const DYSYMTAB_COMMAND_SIZE: u32 = 80;
+
+// Checked up front, a truncated LC_DYSYMTAB at the end of the file would otherwise panic on the slicing below
+pub fn read_dysymtab_command(data: &[u8], lc: &LoadCommand, is_be: bool) -> Result<DYSymtabCommand, MoscopeError> {
+    if lc.cmdsize < DYSYMTAB_COMMAND_SIZE {
+        return Err(MoscopeError::InvalidCmdSize { cmd: lc.cmd, cmdsize: lc.cmdsize });
+    }
+
+    let off = lc.offset as usize;
+    if data.get(off..off + DYSYMTAB_COMMAND_SIZE as usize).is_none() {
+        return Err(MoscopeError::OutOfBounds { offset: lc.offset, len: DYSYMTAB_COMMAND_SIZE as u64 });
+    }
+
+    Ok(DYSymtabCommand {
+        cmd: lc.cmd,
+        cmdsize: lc.cmdsize,
+        ilocalsym:       utils::bytes_to(is_be, &data[off +  8 .. off + 12])?,
+        nlocalsym:       utils::bytes_to(is_be, &data[off + 12 .. off + 16])?,
+        iextdefsym:      utils::bytes_to(is_be, &data[off + 16 .. off + 20])?,
+        nextdefsym:      utils::bytes_to(is_be, &data[off + 20 .. off + 24])?,
+        iundefsym:       utils::bytes_to(is_be, &data[off + 24 .. off + 28])?,
+        nundefsym:       utils::bytes_to(is_be, &data[off + 28 .. off + 32])?,
+        tocoff:          utils::bytes_to(is_be, &data[off + 32 .. off + 36])?,
+        ntoc:            utils::bytes_to(is_be, &data[off + 36 .. off + 40])?,
+        modtaboff:       utils::bytes_to(is_be, &data[off + 40 .. off + 44])?,
+        nmodtab:         utils::bytes_to(is_be, &data[off + 44 .. off + 48])?,
+        extrefsymoff:    utils::bytes_to(is_be, &data[off + 48 .. off + 52])?,
+        nextrefsyms:     utils::bytes_to(is_be, &data[off + 52 .. off + 56])?,
+        indirectsymoff:  utils::bytes_to(is_be, &data[off + 56 .. off + 60])?,
+        nindirectsyms:   utils::bytes_to(is_be, &data[off + 60 .. off + 64])?,
+        extreloff:       utils::bytes_to(is_be, &data[off + 64 .. off + 68])?,
+        nextrel:         utils::bytes_to(is_be, &data[off + 68 .. off + 72])?,
+        locreloff:       utils::bytes_to(is_be, &data[off + 72 .. off + 76])?,
+        nlocrel:         utils::bytes_to(is_be, &data[off + 76 .. off + 80])?,
+    })
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct SectionIndex(pub u8);
 
 impl NList32 {
     pub const SIZE: usize = 12;
 
-    pub fn parse(data: &[u8], offset: usize, is_be: bool) -> Result<Self, Box<dyn Error>> {
+    pub fn parse(data: &[u8], offset: usize, is_be: bool) -> Result<Self, MoscopeError> {
         let n_strx: u32 = utils::bytes_to(is_be, &data[offset .. offset + 4])?;
         let n_type: u8 = data[offset + 4];
         let n_sect: u8 = data[offset + 5];
@@ -313,7 +455,7 @@ impl NList32 {
 impl NList64 {
     pub const SIZE: usize = 16;
 
-    pub fn parse(data: &[u8], offset: usize, is_be: bool) -> Result<Self, Box<dyn Error>> {
+    pub fn parse(data: &[u8], offset: usize, is_be: bool) -> Result<Self, MoscopeError> {
         let n_strx: u32 = utils::bytes_to(is_be, &data[offset .. offset + 4])?;
         let n_type: u8 = data[offset + 4];
         let n_sect: u8 = data[offset + 5];
@@ -324,6 +466,210 @@ impl NList64 {
     }
 }
 
+/*
+Lazy reader over the nlist table of one slice. Each next() parses exactly one entry,
+so `.filter()` / `.take()` short-circuit without ever holding all nsyms in memory.
+    symoff / stroff --> relative to the slice, slice_offset gets added back here
+    item            --> Err if an entry runs past the end of the file, iteration stops after that
+*/
+pub struct SymbolIter<'a> {
+    data: &'a [u8],
+    sym_base: usize,
+    str_offset: usize,
+    str_size: usize,
+    nsyms: usize,
+    index: usize,
+    is_64: bool,
+    is_be: bool,
+}
+
+impl<'a> SymbolIter<'a> {
+    pub fn new(data: &'a [u8], symtab: &SymtabCommand, slice_offset: u64, is_64: bool, is_be: bool) -> Self {
+        let str_offset = slice_offset as usize + symtab.stroff as usize; // have to add the fat offset otherwise we just read garbage
+        // a string table running off the end of the file would otherwise panic in read_symbol_name
+        let str_size = (symtab.strsize as usize).min(data.len().saturating_sub(str_offset));
+
+        SymbolIter {
+            data,
+            sym_base: slice_offset as usize + symtab.symoff as usize,
+            str_offset,
+            str_size,
+            nsyms: symtab.nsyms as usize,
+            index: 0,
+            is_64,
+            is_be,
+        }
+    }
+
+    fn entry_size(&self) -> usize {
+        if self.is_64 { NList64::SIZE } else { NList32::SIZE }
+    }
+}
+
+impl Iterator for SymbolIter<'_> {
+    type Item = Result<ParsedSymbol, MoscopeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.nsyms {
+            return None;
+        }
+
+        let offset = self.sym_base + self.index * self.entry_size();
+        if offset + self.entry_size() > self.data.len() {
+            self.index = self.nsyms; // don't keep yielding the same error
+            return Some(Err(MoscopeError::OutOfBounds { offset: offset as u64, len: self.entry_size() as u64 }));
+        }
+        self.index += 1;
+
+        let symbol = if self.is_64 {
+            NList64::parse(self.data, offset, self.is_be)
+                .map(|nlist| ParsedSymbol::from_nlist64(nlist, self.data, self.str_offset, self.str_size))
+        } else {
+            NList32::parse(self.data, offset, self.is_be)
+                .map(|nlist| ParsedSymbol::from_nlist32(nlist, self.data, self.str_offset, self.str_size))
+        };
+
+        Some(symbol)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.nsyms - self.index))
+    }
+}
+
+/*
+Eager version of SymbolIter for callers that need the whole table at once, e.g. the
+indirect symbol table indexes straight into it. symoff/stroff in LC_SYMTAB are relative
+to the start of the slice, NOT the file, so slice_offset (the fat_arch offset, 0 for thin
+files) has to come along or every name gets read out of the wrong architecture.
+*/
+pub fn parse_symbols(data: &[u8], symtab: &SymtabCommand, slice_offset: u64, is_64: bool, is_be: bool) -> Result<Vec<ParsedSymbol>, MoscopeError> {
+    validate_symtab(data.len(), symtab, slice_offset, is_64)?;
+    SymbolIter::new(data, symtab, slice_offset, is_64, is_be).collect()
+}
+
+/*
+Check LC_SYMTAB against the file before touching a single nlist. A corrupt (or hostile)
+nsyms of 0xffffffff would otherwise have us parsing until we fall off the end.
+    symoff + nsyms * sizeof(nlist)  has to fit in the file        --> error
+    stroff                          has to be inside the file      --> error
+    stroff + strsize                running past the end is survivable, SymbolIter clamps it
+All math in u64, nsyms * 16 overflows a u32 easily.
+*/
+pub fn validate_symtab(file_len: usize, symtab: &SymtabCommand, slice_offset: u64, is_64: bool) -> Result<(), MoscopeError> {
+    let entry_size = if is_64 { NList64::SIZE } else { NList32::SIZE } as u64;
+    let file_len = file_len as u64;
+
+    let sym_start = slice_offset + symtab.symoff as u64;
+    let sym_end = sym_start + symtab.nsyms as u64 * entry_size;
+    if sym_end > file_len {
+        return Err(format!(
+            "symbol table is truncated: {} entries at {:#x} need {:#x} bytes but the file ends at {:#x}",
+            symtab.nsyms, sym_start, sym_end, file_len
+        ).into());
+    }
+
+    let str_start = slice_offset + symtab.stroff as u64;
+    if symtab.strsize > 0 && str_start >= file_len {
+        return Err(format!("string table at {:#x} starts past the end of the file ({:#x} bytes)", str_start, file_len).into());
+    }
+
+    Ok(())
+}
+
+/*
+LC_DYSYMTAB's indirect symbol table: one u32 per stub / lazy pointer / GOT slot, each an
+index into the symbol table (or INDIRECT_SYMBOL_LOCAL / INDIRECT_SYMBOL_ABS). Sections
+point into it with reserved1. Same story as parse_symbols, indirectsymoff is slice relative.
+*/
+pub fn parse_indirect_symbols(data: &[u8], dysymtab: &DYSymtabCommand, slice_offset: u64, is_be: bool) -> Result<Vec<u32>, MoscopeError> {
+    let start = slice_offset as usize + dysymtab.indirectsymoff as usize;
+    let end = start + dysymtab.nindirectsyms as usize * 4;
+    if end > data.len() {
+        return Err(format!(
+            "indirect symbol table ({} entries at {:#x}) runs past the end of the file ({:#x} bytes)",
+            dysymtab.nindirectsyms, start, data.len()
+        ).into());
+    }
+
+    data[start..end].chunks_exact(4).map(|entry| utils::bytes_to(is_be, entry)).collect()
+}
+
+/*
+LC_SYMTAB as laid out in the file, plus how much of the string table the nlists actually use.
+Every nlist's n_strx names one NUL terminated string; the bytes no string covers are slack.
+A few bytes are normal (index 0 is the empty name, ld pads the table to the pointer size),
+anything bigger is room someone could have stashed data in.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SymtabStats {
+    pub symoff: u32,
+    pub nsyms: u32,
+    pub stroff: u32,
+    pub strsize: u32,
+    pub referenced_string_bytes: u32,
+}
+
+impl SymtabStats {
+    pub fn build_report(&self) -> SymtabStatsReport {
+        SymtabStatsReport {
+            symoff: self.symoff,
+            nsyms: self.nsyms,
+            stroff: self.stroff,
+            strsize: self.strsize,
+            referenced_string_bytes: self.referenced_string_bytes,
+            unreferenced_string_bytes: self.strsize - self.referenced_string_bytes,
+        }
+    }
+}
+
+pub fn symtab_stats(data: &[u8], symtab: &SymtabCommand, slice_offset: u64, is_64: bool, is_be: bool) -> Result<SymtabStats, MoscopeError> {
+    validate_symtab(data.len(), symtab, slice_offset, is_64)?;
+
+    // Only what is actually in the file can be referenced, SymbolIter clamps the same way
+    let str_offset = slice_offset as usize + symtab.stroff as usize;
+    let str_size = (symtab.strsize as usize).min(data.len().saturating_sub(str_offset));
+    let strings = &data[str_offset..str_offset + str_size];
+
+    let entry_size = if is_64 { NList64::SIZE } else { NList32::SIZE };
+    let sym_base = slice_offset as usize + symtab.symoff as usize;
+    let mut referenced = vec![false; str_size];
+    for i in 0..symtab.nsyms as usize {
+        let strx: u32 = utils::bytes_to(is_be, &data[sym_base + i * entry_size..])?;
+        let start = strx as usize;
+        if strx == 0 || start >= str_size {
+            continue;
+        }
+        // through the NUL, ld shares tails between strings so ranges overlap
+        let end = strings[start..].iter().position(|&b| b == 0).map_or(str_size, |nul| start + nul + 1);
+        referenced[start..end].fill(true);
+    }
+
+    Ok(SymtabStats {
+        symoff: symtab.symoff,
+        nsyms: symtab.nsyms,
+        stroff: symtab.stroff,
+        strsize: symtab.strsize,
+        referenced_string_bytes: referenced.iter().filter(|&&used| used).count() as u32,
+    })
+}
+
+// No LC_SYMTAB (or an empty one) and no export trie, there is nothing left to name anything by
+pub fn is_stripped(stats: Option<&SymtabStats>, has_export_trie: bool) -> bool {
+    !has_export_trie && stats.is_none_or(|s| s.nsyms == 0)
+}
+
+pub fn write_stripped_notice(w: &mut dyn Write) -> io::Result<()> {
+    writeln!(w, "{} none, binary is stripped / no symbols", "Symbols     :".yellow().bold())?;
+    Ok(())
+}
+
+pub fn write_symtab_stats(w: &mut dyn Write, stats: &SymtabStatsReport) -> io::Result<()> {
+    writeln!(w, "{} {} symbols at 0x{:x}, string table at 0x{:x} ({} bytes)", "Symbol Table:".yellow().bold(), stats.nsyms, stats.symoff, stats.stroff, stats.strsize)?;
+    writeln!(w, "{} {} bytes referenced, {} unreferenced", "String Table:".yellow().bold(), stats.referenced_string_bytes, stats.unreferenced_string_bytes)?;
+    Ok(())
+}
+
 pub fn read_symbol_name(data: &[u8], str_offset: usize, str_size: usize, strx: u32) -> Option<String> {
     if strx == 0 {
         return None;
@@ -346,17 +692,41 @@ pub fn read_symbol_name(data: &[u8], str_offset: usize, str_size: usize, strx: u
 
 
 pub fn extract_strings(section_data: &[u8], min_len: usize) -> Vec<String> {
+    extract_string_entries(section_data, min_len, false).into_iter().map(|s| s.value).collect()
+}
+
+// --ascii-only: same as extract_strings but every byte has to be printable ASCII (0x20-0x7E) or a tab
+// Valid UTF-8 alone lets through a lot of high-plane noise when non-string data lands in a string section
+pub fn extract_ascii_strings(section_data: &[u8], min_len: usize) -> Vec<String> {
+    extract_string_entries(section_data, min_len, true).into_iter().map(|s| s.value).collect()
+}
+
+pub fn is_printable_ascii(bytes: &[u8]) -> bool {
+    bytes.iter().all(|&b| b == b'\t' || (0x20..=0x7e).contains(&b))
+}
+
+// NUL terminated UTF-8 strings with their encoding and raw bytes, what main actually uses
+pub fn extract_string_entries(section_data: &[u8], min_len: usize, ascii_only: bool) -> Vec<ExtractedString> {
     let mut strings = Vec::new();
     let mut start = 0;
 
     while start < section_data.len() {
         // just like in rpaths we check for the first null byte
         if let Some(end) = section_data[start..].iter().position(|&byte| byte == 0) {
-            let slice = &section_data[start..start + end];
-            if slice.len() >= min_len {
-                if let Ok(s) = std::str::from_utf8(slice) {
-                    strings.push(escape_string(s).to_string());
-                }
+            let raw = &section_data[start..start + end];
+            // a BOM is an encoding marker, not part of the text (it used to show up as "\u{feff}" mojibake)
+            let body = raw.strip_prefix(UTF8_BOM).unwrap_or(raw);
+            // filter before min_len is applied so a rejected string never counts towards anything
+            if body.len() >= min_len
+                && (!ascii_only || is_printable_ascii(body))
+                && let Ok(s) = std::str::from_utf8(body)
+            {
+                strings.push(ExtractedString {
+                    value: escape_string(s),
+                    encoding: if body.is_ascii() { StringEncoding::Ascii } else { StringEncoding::Utf8 },
+                    raw: raw.to_vec(),
+                    offset: start,
+                });
             }
 
             start += end + 1; // skip the null byte
@@ -368,13 +738,93 @@ pub fn extract_strings(section_data: &[u8], min_len: usize) -> Vec<String> {
     strings
 }
 
-pub fn extract_filtered_strings(section_data: &[u8], pattern: &str) -> Result<Vec<String>, regex::Error> {
-    let re = Regex::new(pattern)?;
+/*
+__TEXT,__ustring holds CFString literals as UTF-16LE, each terminated by a 0x0000 code unit.
+min_len counts UTF-16 code units like it counts bytes for C strings, ill-formed UTF-16 is skipped.
+*/
+pub fn extract_utf16le_strings(section_data: &[u8], min_len: usize) -> Vec<ExtractedString> {
+    let mut strings = Vec::new();
+    let mut units = Vec::new();
+    let mut start = 0;
+
+    for (i, pair) in section_data.chunks_exact(2).enumerate() {
+        let unit = u16::from_le_bytes([pair[0], pair[1]]);
+        if unit != 0 {
+            units.push(unit);
+            continue;
+        }
+
+        let body = units.strip_prefix(&[UTF16LE_BOM]).unwrap_or(&units);
+        if body.len() >= min_len
+            && let Ok(s) = String::from_utf16(body)
+        {
+            strings.push(ExtractedString {
+                value: escape_string(&s),
+                encoding: StringEncoding::Utf16Le,
+                raw: section_data[start..i * 2].to_vec(),
+                offset: start,
+            });
+        }
+
+        units.clear();
+        start = (i + 1) * 2;
+    }
+
+    strings
+}
+
+/*
+String order is part of the output contract (diffs, --canonical-json, --max-strings):
+    1. section_index   the order sections appear in the load commands
+    2. offset          position inside that section
+Every string has its own (section, offset) so this is a total order, nothing depends on
+extraction order, hashing or which filters happened to run.
+*/
+pub fn sort_strings(strings: &mut [ParsedString]) {
+    strings.sort_by_key(|s| (s.section_index, s.offset));
+}
+
+pub fn extract_filtered_strings(section_data: &[u8], pattern: &str, ascii_only: bool) -> Result<Vec<ExtractedString>, regex::Error> {
     // If using regex, we want all strings (min_len = 1)
-    Ok(extract_strings(section_data, 1)
-        .into_iter()
-        .filter(|s| re.is_match(s))
-        .collect())
+    filter_strings(extract_string_entries(section_data, 1, ascii_only), pattern)
+}
+
+pub fn filter_strings(strings: Vec<ExtractedString>, pattern: &str) -> Result<Vec<ExtractedString>, regex::Error> {
+    let re = Regex::new(pattern)?;
+    Ok(strings.into_iter().filter(|s| re.is_match(&s.value)).collect())
+}
+
+/*
+--section-min-length __objc_methname=2,__cstring=8
+Selectors are short, C strings are noisy, one global --min-string-length can't suit both.
+Sections not listed keep using the global minimum.
+*/
+pub fn parse_section_min_lengths(spec: &str) -> Result<HashMap<String, usize>, String> {
+    let mut lengths = HashMap::new();
+
+    for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let (sect, len) = entry
+            .split_once('=')
+            .ok_or_else(|| format!("expected SECT=N, got '{}'", entry))?;
+
+        let sect = sect.trim();
+        if sect.is_empty() {
+            return Err(format!("missing section name in '{}'", entry));
+        }
+
+        let len = len
+            .trim()
+            .parse::<usize>()
+            .map_err(|_| format!("'{}' is not a valid length for {}", len.trim(), sect))?;
+
+        lengths.insert(sect.to_string(), len);
+    }
+
+    Ok(lengths)
+}
+
+pub fn min_length_for(sectname: &str, overrides: Option<&HashMap<String, usize>>, default: usize) -> usize {
+    overrides.and_then(|m| m.get(sectname)).copied().unwrap_or(default)
 }
 
 fn escape_string(s: &str) -> String {
@@ -391,57 +841,107 @@ fn escape_string(s: &str) -> String {
 }
 
 
-fn sort_addr(sym: &ParsedSymbol) -> Option<u64> {
-    sym.indirect_addr.or_else(|| {
-        if sym.addr != 0 { Some(sym.addr) } else { None }
-    })
+/*
+N_OSO stab layout (what ld64 leaves behind in a non-stripped debug build):
+    n_strx  --> path to the original .o (or archive(member.o))
+    n_sect  --> 0 (or the cpusubtype on newer linkers)
+    n_desc  --> 1
+    n_value --> st_mtime of the .o when it was linked, 0 if ZERO_AR_DATE was set
+
+dsymutil walks these to find the object files to pull DWARF from, and refuses
+any .o whose mtime no longer matches, so a stale/missing entry here = an empty dSYM.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedOso {
+    pub path: String,
+    pub mtime: u64,
 }
 
-pub fn print_symbols_summary(symbols: &[ParsedSymbol]) {
-    if symbols.is_empty() {
-        return;
+pub fn collect_oso_entries(symbols: &[ParsedSymbol]) -> Vec<ParsedOso> {
+    symbols
+        .iter()
+        .filter(|sym| sym.is_debug && sym.n_type == N_OSO)
+        .map(|sym| ParsedOso { path: sym.name.clone(), mtime: sym.value })
+        .collect()
+}
+
+pub fn build_oso_report(cpu_type: &str, cpu_subtype: &str, entries: &[ParsedOso]) -> OsoReport {
+    OsoReport {
+        cpu_type: cpu_type.to_string(),
+        cpu_subtype: cpu_subtype.to_string(),
+        objects: entries
+            .iter()
+            .map(|e| OsoEntryReport { path: e.path.clone(), mtime: e.mtime })
+            .collect(),
     }
+}
 
-    let mut symbols = symbols.to_vec();
-    sort_symbols(&mut symbols);
-    
+pub fn write_oso_summary(w: &mut dyn Write, report: &OsoReport) -> io::Result<()> {
+    writeln!(w)?;
+    writeln!(w, "{} ({})", "N_OSO Object Files".green().bold(), report.cpu_subtype)?;
+    writeln!(w, "----------------------------------------")?;
 
-    println!();
-    println!("{}", "Symbols".green().bold());
-    println!("--------------------------------------------------------------------------------");
-    println!(
-        "{:<18} {:<6} {:<5} {:<20} {}",
-        "Address", "Type", "Bind", "Section", "Symbol"
-    );
-    println!("--------------------------------------------------------------------------------");
+    if report.objects.is_empty() {
+        writeln!(w, "No N_OSO entries (binary is stripped or was not built with debug info)")?;
+        return Ok(());
+    }
+
+    writeln!(w, "{:<12} Object", "mtime")?;
+    for obj in &report.objects {
+        writeln!(w, "{:<12} {}", obj.mtime, obj.path)?;
+    }
+    Ok(())
+}
+
+// The report is already in sort_symbols order
+pub fn write_symbols_summary(w: &mut dyn Write, symbols: &[SymbolReport], strip_underscore: bool) -> io::Result<()> {
+    if symbols.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(w)?;
+    writeln!(w, "{}", "Symbols".green().bold())?;
+    writeln!(w, "--------------------------------------------------------------------------------")?;
+    writeln!(
+        w,
+        "{:<18} {:<6} {:<5} {:<20} Symbol",
+        "Address", "Type", "Bind", "Section"
+    )?;
+    writeln!(w, "--------------------------------------------------------------------------------")?;
 
     for sym in symbols {
-        // Format address: show '-' if 0
-        let addr_str = sym.effective_addr().map(|a| format!("0x{:016x}", a)).unwrap_or_else(|| "-".to_string());
+        // Debug entries show their STAB type instead, the N_TYPE bits are meaningless for them
+        let type_str = sym.stab_type.as_deref().unwrap_or(&sym.kind);
 
-        println!(
-            "{:<18} {:<6} {:<5} {:<20} {}",
-            addr_str,
-            sym.kind_plain(),
-            sym.bind_str(),
-            sym.sect_str(),
-            sym.name
-        );
+        writeln!(
+            w,
+            "{:<18} {:<6} {:<5} {:<20} {}{}",
+            sym.addr_hex,
+            type_str,
+            if sym.external { "EXT" } else { "LOC" },
+            sym.sectname.as_deref().unwrap_or("-"),
+            if strip_underscore { &sym.source_name } else { &sym.name },
+            sym.library.as_ref().map(|lib| format!(" ({})", lib).dimmed().to_string()).unwrap_or_default()
+        )?;
     }
 
-    println!("--------------------------------------------------------------------------------");
+    writeln!(w, "--------------------------------------------------------------------------------")?;
+    Ok(())
 }
 
-pub fn print_strings_summary(strings: &Vec<ParsedString>, min_len: usize, max_count: Option<usize>) {
+pub fn write_strings_summary(w: &mut dyn Write, strings: &[StringReport], min_len: usize, section_min_lengths: Option<&HashMap<String, usize>>, max_count: Option<usize>) -> io::Result<()> {
     if strings.is_empty() {
-        return;
+        return Ok(());
     }
 
-    println!("{}", "\nStrings".green().bold());
-    println!("----------------------------------------");
+    writeln!(w, "{}", "\nStrings".green().bold())?;
+    writeln!(w, "----------------------------------------")?;
 
     // Filter by min length
-    let mut filtered: Vec<&ParsedString> = strings.iter().filter(|s| s.value.len() >= min_len).collect();
+    let mut filtered: Vec<&StringReport> = strings
+        .iter()
+        .filter(|s| s.value.len() >= min_length_for(&s.sectname, section_min_lengths, min_len))
+        .collect();
 
     // Sort or limit if max_count is provided
     if let Some(max) = max_count {
@@ -449,16 +949,30 @@ pub fn print_strings_summary(strings: &Vec<ParsedString>, min_len: usize, max_co
     }
 
     for s in filtered {
-        let segname_raw = String::from_utf8_lossy(&s.segname);
-        let segname = segname_raw.trim_end_matches('\0');
-        let sectname_raw = String::from_utf8_lossy(&s.sectname);
-        let sectname = sectname_raw.trim_end_matches('\0');
-
-        println!("[{}:{}] {}", segname, sectname, s.value);
+        if s.encoding == StringEncoding::Ascii.as_str() {
+            writeln!(w, "[{}:{}] {}", s.segname, s.sectname, s.value)?;
+        } else {
+            writeln!(w, "[{}:{}] ({}) {}", s.segname, s.sectname, s.encoding, s.value)?;
+        }
+        if let Some(raw) = &s.raw_hex {
+            writeln!(w, "    raw: {}", raw)?;
+        }
     }
+    Ok(())
 }
 
-pub fn sort_symbols(symbols: &mut Vec<ParsedSymbol>) {
+/*
+C (and Swift/ObjC runtime) symbols get an extra '_' on Darwin, `main` lives in the symtab as `_main`.
+Only ONE underscore comes off so C++ stays a valid mangled name:
+    _main       --> main
+    __Z3foov    --> _Z3foov   (what c++filt wants anyway)
+    radr://5614542 --> untouched, no underscore to strip
+*/
+pub fn strip_leading_underscore(name: &str) -> &str {
+    name.strip_prefix('_').unwrap_or(name)
+}
+
+pub fn sort_symbols(symbols: &mut [ParsedSymbol]) {
     // Sort by address that will be printed with undefined symbols last
     symbols.sort_by(|a, b| {
         match (a.effective_addr(), b.effective_addr()) {
@@ -468,4 +982,450 @@ pub fn sort_symbols(symbols: &mut Vec<ParsedSymbol>) {
             (None, None) => std::cmp::Ordering::Equal,
         }
     });
-}
\ No newline at end of file
+}
+
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macho::dylibs::DylibKind;
+
+    fn symbol(name: &str, n_type: u8, value: u64) -> ParsedSymbol {
+        ParsedSymbol {
+            name: name.to_string(),
+            addr: value,
+            value,
+            kind: SymbolKind::from_n_type(n_type),
+            section: None,
+            is_external: false,
+            is_debug: (n_type & N_STAB) != 0,
+            sectname: None,
+            segname: None,
+            n_desc: 1,
+            n_type,
+            n_sect: 0,
+            indirect_addr: None,
+            indirect_sect: None,
+            library: None,
+        }
+    }
+
+    #[test]
+    fn truncated_dysymtab_is_an_error() {
+        let mut data = Vec::new();
+        for value in [LC_DYSYMTAB, DYSYMTAB_COMMAND_SIZE] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.resize(60, 0); // cut off before nindirectsyms
+        let lc = LoadCommand { cmd: LC_DYSYMTAB, cmdsize: DYSYMTAB_COMMAND_SIZE, offset: 0 };
+
+        assert!(read_dysymtab_command(&data, &lc, false).is_err());
+
+        data.resize(DYSYMTAB_COMMAND_SIZE as usize, 0);
+        data[60..64].copy_from_slice(&7u32.to_le_bytes());
+        assert_eq!(read_dysymtab_command(&data, &lc, false).unwrap().nindirectsyms, 7);
+    }
+
+    #[test]
+    fn collect_oso_entries_only_keeps_n_oso() {
+        let symbols = vec![
+            symbol("/tmp/hello.cpp", N_SO, 0),
+            symbol("/tmp/build/hello.o", N_OSO, 1_700_000_000),
+            symbol("_main", N_SECT | N_EXT, 0x100000460),
+            symbol("/tmp/build/libfoo.a(foo.o)", N_OSO, 0),
+        ];
+
+        let entries = collect_oso_entries(&symbols);
+
+        assert_eq!(entries, vec![
+            ParsedOso { path: "/tmp/build/hello.o".to_string(), mtime: 1_700_000_000 },
+            ParsedOso { path: "/tmp/build/libfoo.a(foo.o)".to_string(), mtime: 0 },
+        ]);
+    }
+
+    #[test]
+    fn stab_type_only_set_for_debug_entries() {
+        assert_eq!(symbol("/tmp/build/hello.o", N_OSO, 0).stab_type(), Some("N_OSO"));
+        assert_eq!(symbol("_main", N_SECT | N_EXT, 0).stab_type(), None);
+    }
+
+    // nsyms nlist_64 entries followed by a string table of "\0_sym0\0_sym1\0..."
+    fn symtab_image(nsyms: u32) -> (Vec<u8>, SymtabCommand) {
+        let mut strtab = vec![0u8];
+        let mut data = Vec::new();
+        for i in 0..nsyms {
+            data.extend_from_slice(&(strtab.len() as u32).to_le_bytes());
+            data.extend_from_slice(&[N_SECT | N_EXT, 1, 0, 0]);
+            data.extend_from_slice(&(0x1000 + i as u64).to_le_bytes());
+            strtab.extend_from_slice(format!("_sym{}\0", i).as_bytes());
+        }
+        let stroff = data.len() as u32;
+        let strsize = strtab.len() as u32;
+        data.extend_from_slice(&strtab);
+
+        (data, SymtabCommand { cmd: LC_SYMTAB, cmdsize: 24, symoff: 0, nsyms, stroff, strsize })
+    }
+
+    #[test]
+    fn symbol_iter_yields_entries_in_table_order() {
+        let (data, cmd) = symtab_image(3);
+
+        let names: Vec<String> = SymbolIter::new(&data, &cmd, 0, true, false)
+            .map(|sym| sym.unwrap().name)
+            .collect();
+
+        assert_eq!(names, vec!["_sym0", "_sym1", "_sym2"]);
+    }
+
+    #[test]
+    fn symbol_iter_is_lazy() {
+        let (data, cmd) = symtab_image(1000);
+        let mut iter = SymbolIter::new(&data, &cmd, 0, true, false);
+
+        let first: Vec<ParsedSymbol> = iter.by_ref().take(2).collect::<Result<_, _>>().unwrap();
+        assert_eq!(first[1].addr, 0x1001);
+        assert_eq!(iter.size_hint(), (0, Some(998)));
+    }
+
+    #[test]
+    fn symbol_iter_reports_truncated_table_once() {
+        let (data, mut cmd) = symtab_image(2);
+        cmd.nsyms = 1_000_000; // claims far more entries than the file holds
+
+        let results: Vec<_> = SymbolIter::new(&data, &cmd, 0, true, false).collect();
+
+        // the 2 real entries, the string table misread as 2 more, then one error and done
+        assert!(results.last().unwrap().is_err());
+        assert_eq!(results.iter().filter(|r| r.is_err()).count(), 1);
+    }
+
+    #[test]
+    fn ascii_only_drops_non_ascii_strings() {
+        let data = "hello\0caf\u{e9}\0\u{4e16}\u{754c}\u{4e16}\u{754c}\0tab\there\0".as_bytes();
+
+        // Default keeps every valid UTF-8 string
+        assert_eq!(extract_strings(data, 1), vec!["hello", "caf\u{e9}", "\u{4e16}\u{754c}\u{4e16}\u{754c}", "tab\\there"]);
+        // ASCII only keeps printable ASCII (and tabs)
+        assert_eq!(extract_ascii_strings(data, 1), vec!["hello", "tab\\there"]);
+    }
+
+    #[test]
+    fn section_min_lengths_parse_and_override() {
+        let lengths = parse_section_min_lengths("__objc_methname=2, __cstring=8").unwrap();
+
+        assert_eq!(min_length_for("__objc_methname", Some(&lengths), 4), 2);
+        assert_eq!(min_length_for("__cstring", Some(&lengths), 4), 8);
+        assert_eq!(min_length_for("__const", Some(&lengths), 4), 4);
+        assert_eq!(min_length_for("__cstring", None, 4), 4);
+    }
+
+    #[test]
+    fn section_min_lengths_reject_malformed_entries() {
+        assert!(parse_section_min_lengths("__cstring").is_err());
+        assert!(parse_section_min_lengths("=3").is_err());
+        assert!(parse_section_min_lengths("__cstring=abc").is_err());
+        assert!(parse_section_min_lengths("__cstring=-1").is_err());
+    }
+
+    #[test]
+    fn ascii_only_respects_min_length() {
+        let data = b"ab\0abcdef\0\x01\x02\x03\x04\x05\0";
+
+        assert_eq!(extract_ascii_strings(data, 4), vec!["abcdef"]);
+    }
+
+    #[test]
+    fn strip_leading_underscore_removes_exactly_one() {
+        assert_eq!(strip_leading_underscore("_main"), "main");
+        assert_eq!(strip_leading_underscore("__Z3foov"), "_Z3foov");
+        assert_eq!(strip_leading_underscore("radr://5614542"), "radr://5614542");
+        assert_eq!(strip_leading_underscore(""), "");
+    }
+
+    #[test]
+    fn symbol_report_keeps_raw_name() {
+        let report = symbol("_main", N_SECT | N_EXT, 0x100000460).build_report();
+
+        assert_eq!(report.name, "_main");
+        assert_eq!(report.source_name, "main");
+    }
+
+    #[test]
+    fn utf8_bom_is_stripped_and_labelled() {
+        let data = b"\xEF\xBB\xBFcaf\xC3\xA9\0\xEF\xBB\xBFplain\0";
+        let entries = extract_string_entries(data, 4, false);
+
+        assert_eq!(entries[0].value, "caf\u{e9}");
+        assert_eq!(entries[0].encoding, StringEncoding::Utf8);
+        assert_eq!(entries[0].raw, b"\xEF\xBB\xBFcaf\xC3\xA9");
+        assert_eq!(entries[1].value, "plain");
+        assert_eq!(entries[1].offset, 9);
+        assert_eq!(entries[1].encoding, StringEncoding::Ascii);
+    }
+
+    #[test]
+    fn bom_does_not_count_towards_min_length() {
+        assert!(extract_string_entries(b"\xEF\xBB\xBFab\0", 4, false).is_empty());
+        assert_eq!(extract_ascii_strings(b"\xEF\xBB\xBFabcd\0", 4), vec!["abcd"]);
+    }
+
+    #[test]
+    fn utf16le_strings_decode() {
+        let mut data = Vec::new();
+        for text in ["\u{feff}h\u{e9}llo", "hi", "\u{4e16}\u{754c}\u{4e16}\u{754c}"] {
+            for unit in text.encode_utf16().chain([0]) {
+                data.extend_from_slice(&unit.to_le_bytes());
+            }
+        }
+        let entries = extract_utf16le_strings(&data, 3);
+        let values: Vec<&str> = entries.iter().map(|s| s.value.as_str()).collect();
+
+        assert_eq!(values, vec!["h\u{e9}llo", "\u{4e16}\u{754c}\u{4e16}\u{754c}"]);
+        assert!(entries.iter().all(|s| s.encoding == StringEncoding::Utf16Le));
+        assert_eq!(entries[0].raw.len(), 12); // BOM + 5 units, no terminator
+    }
+
+    #[test]
+    fn import_export_follow_kind_and_binding() {
+        let mut exported = symbol("_main", N_SECT | N_EXT, 0x100000460);
+        exported.is_external = true;
+        let mut imported = symbol("_printf", N_UNDF | N_EXT, 0);
+        imported.is_external = true;
+        let local = symbol("_helper", N_SECT, 0x100000500);
+        let stab = symbol("/tmp/build/hello.o", N_OSO, 0);
+
+        assert!(exported.is_export() && !exported.is_import());
+        assert!(imported.is_import() && !imported.is_export());
+        assert!(!local.is_import() && !local.is_export());
+        assert!(!stab.is_import() && !stab.is_export());
+
+        // reclassified through the indirect symbol table, still an import
+        imported.kind = SymbolKind::Stub;
+        let report = imported.build_report();
+        assert!(report.is_import && !report.is_export);
+    }
+
+    #[test]
+    fn import_library_follows_the_ordinal_byte() {
+        let dylib = |path: &str, kind| ParsedDylib {
+            path: path.to_string(),
+            timestamp: 0,
+            current_version: 0,
+            compatibility_version: 0,
+            kind,
+            source_lc: LoadCommand { cmd: LC_LOAD_DYLIB, cmdsize: 0, offset: 0 },
+        };
+        // LC_ID_DYLIB doesn't take an ordinal, libc++ is still #2
+        let dylibs = [dylib("/usr/lib/libfoo.dylib", DylibKind::Id), dylib("/usr/lib/libSystem.B.dylib", DylibKind::Load), dylib("/usr/lib/libc++.1.dylib", DylibKind::Load)];
+
+        let mut imported = symbol("_printf", N_UNDF | N_EXT, 0);
+        imported.is_external = true;
+        let library_for = |ordinal: u8| ParsedSymbol { n_desc: (ordinal as u16) << 8, ..imported.clone() }.resolve_library(&dylibs);
+
+        assert_eq!(library_for(1).as_deref(), Some("/usr/lib/libSystem.B.dylib"));
+        assert_eq!(library_for(2).as_deref(), Some("/usr/lib/libc++.1.dylib"));
+        assert_eq!(library_for(SELF_LIBRARY_ORDINAL).as_deref(), Some("<self>"));
+        assert_eq!(library_for(DYNAMIC_LOOKUP_ORDINAL).as_deref(), Some("<dynamic lookup>"));
+        assert_eq!(library_for(EXECUTABLE_ORDINAL).as_deref(), Some("<main executable>"));
+        assert_eq!(library_for(3).as_deref(), Some("<invalid ordinal 3>"));
+
+        // Only imports get one
+        assert_eq!(symbol("_main", N_SECT | N_EXT, 0x1000).resolve_library(&dylibs), None);
+    }
+
+    // Two nlist_64 entries + their string table, 0x20 bytes into a slice that starts at slice_offset
+    fn synthetic_symtab(slice_offset: usize) -> (Vec<u8>, SymtabCommand) {
+        let strtab = b"\0_main\0_printf\0";
+        let mut data = vec![0u8; slice_offset + 0x20];
+        for (strx, n_type, n_sect, value) in [(1u32, N_SECT | N_EXT, 1u8, 0x1_0000_3f00u64), (7, N_UNDF | N_EXT, 0, 0)] {
+            data.extend_from_slice(&strx.to_le_bytes());
+            data.extend_from_slice(&[n_type, n_sect, 0, 0]);
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(strtab);
+
+        let symtab = SymtabCommand { cmd: LC_SYMTAB, cmdsize: 24, symoff: 0x20, nsyms: 2, stroff: 0x40, strsize: strtab.len() as u32 };
+        (data, symtab)
+    }
+
+    #[test]
+    fn parse_symbols_reads_a_thin_symtab() {
+        let (data, symtab) = synthetic_symtab(0);
+        let symbols = parse_symbols(&data, &symtab, 0, true, false).unwrap();
+
+        assert_eq!(symbols.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(), ["_main", "_printf"]);
+        assert_eq!(symbols[0].addr, 0x1_0000_3f00);
+        assert!(symbols[1].is_import());
+    }
+
+    #[test]
+    fn symtab_stats_count_unreferenced_string_bytes() {
+        let (data, symtab) = synthetic_symtab(0x1000);
+        let stats = symtab_stats(&data, &symtab, 0x1000, true, false).unwrap();
+
+        // "\0_main\0_printf\0": everything but the empty name at index 0
+        assert_eq!(stats.build_report(), SymtabStatsReport {
+            symoff: 0x20,
+            nsyms: 2,
+            stroff: 0x40,
+            strsize: 15,
+            referenced_string_bytes: 14,
+            unreferenced_string_bytes: 1,
+        });
+
+        // bytes tacked on after the last string nobody points at
+        let mut padded = data.clone();
+        padded.extend_from_slice(b"hidden\0");
+        let stats = symtab_stats(&padded, &SymtabCommand { strsize: 22, ..symtab }, 0x1000, true, false).unwrap();
+        assert_eq!(stats.build_report().unreferenced_string_bytes, 8);
+
+        // with only _main left, _printf's bytes are slack too
+        let stats = symtab_stats(&data, &SymtabCommand { nsyms: 1, ..symtab }, 0x1000, true, false).unwrap();
+        assert_eq!(stats.build_report().unreferenced_string_bytes, 9);
+    }
+
+    #[test]
+    fn stripped_needs_no_symbols_and_no_export_trie() {
+        let (data, symtab) = synthetic_symtab(0x1000);
+        let stats = symtab_stats(&data, &symtab, 0x1000, true, false).unwrap();
+        let empty = SymtabStats { nsyms: 0, ..stats };
+
+        assert!(is_stripped(None, false));
+        assert!(is_stripped(Some(&empty), false));
+        assert!(!is_stripped(None, true));
+        assert!(!is_stripped(Some(&stats), false));
+    }
+
+    #[test]
+    fn parse_symbols_adds_the_slice_offset() {
+        let (data, symtab) = synthetic_symtab(0x1000);
+        let symbols = parse_symbols(&data, &symtab, 0x1000, true, false).unwrap();
+
+        assert_eq!(symbols[0].name, "_main");
+        assert_eq!(symbols[1].name, "_printf");
+    }
+
+    #[test]
+    fn parse_symbols_fails_when_the_table_runs_off_the_file() {
+        let (data, symtab) = synthetic_symtab(0x1000);
+
+        // forgetting the slice offset here would silently read zeros, past the end it's an error
+        assert!(parse_symbols(&data, &SymtabCommand { nsyms: 0x1000, ..symtab }, 0x1000, true, false).is_err());
+    }
+
+    fn dysymtab(indirectsymoff: u32, nindirectsyms: u32) -> DYSymtabCommand {
+        DYSymtabCommand {
+            cmd: LC_DYSYMTAB,
+            cmdsize: 80,
+            ilocalsym: 0,
+            nlocalsym: 0,
+            iextdefsym: 0,
+            nextdefsym: 0,
+            iundefsym: 0,
+            nundefsym: 0,
+            tocoff: 0,
+            ntoc: 0,
+            modtaboff: 0,
+            nmodtab: 0,
+            extrefsymoff: 0,
+            nextrefsyms: 0,
+            indirectsymoff,
+            nindirectsyms,
+            extreloff: 0,
+            nextrel: 0,
+            locreloff: 0,
+            nlocrel: 0,
+        }
+    }
+
+    #[test]
+    fn parse_indirect_symbols_reads_from_the_slice() {
+        let mut data = vec![0u8; 0x1010];
+        for (i, idx) in [3u32, INDIRECT_SYMBOL_LOCAL, 7].iter().enumerate() {
+            data[0x1004 + i * 4..][..4].copy_from_slice(&idx.to_be_bytes());
+        }
+
+        let table = parse_indirect_symbols(&data, &dysymtab(4, 3), 0x1000, true).unwrap();
+
+        assert_eq!(table, [3, INDIRECT_SYMBOL_LOCAL, 7]);
+    }
+
+    #[test]
+    fn parse_indirect_symbols_bounds_checks_the_table() {
+        let data = vec![0u8; 0x1010];
+
+        assert!(parse_indirect_symbols(&data, &dysymtab(4, 4), 0x1000, false).is_err()); // one entry too many
+        assert_eq!(parse_indirect_symbols(&data, &dysymtab(4, 0), 0x1000, false).unwrap(), Vec::<u32>::new());
+    }
+
+    #[test]
+    fn symbol_kind_strings_are_stable() {
+        let expected = [
+            (SymbolKind::Undefined, "UNDEF"),
+            (SymbolKind::Absolute, "ABS"),
+            (SymbolKind::Section, "SECT"),
+            (SymbolKind::PreboundUndefined, "PBUD"),
+            (SymbolKind::Indirect, "INDR"),
+            (SymbolKind::Lazy, "LAZY"),
+            (SymbolKind::Stub, "STUB"),
+            (SymbolKind::Got, "GOT"),
+            (SymbolKind::Unknown, "UNKNOWN"),
+        ];
+
+        for (kind, name) in expected {
+            assert_eq!(kind.as_str(), name);
+        }
+        assert_eq!(symbol("_main", N_SECT | N_EXT, 0x1000).build_report().kind, "SECT");
+    }
+
+    #[test]
+    fn inflated_nsyms_is_rejected_before_parsing() {
+        let (data, mut cmd) = symtab_image(2);
+        cmd.nsyms = u32::MAX;
+
+        let err = parse_symbols(&data, &cmd, 0, true, false).unwrap_err().to_string();
+        assert!(err.contains("symbol table is truncated"), "unexpected error: {}", err);
+
+        assert!(validate_symtab(data.len(), &SymtabCommand { nsyms: 2, ..cmd }, 0, true).is_ok());
+        // the right count in the wrong place (slice offset pushing it off the end) is caught too
+        assert!(validate_symtab(data.len(), &SymtabCommand { nsyms: 2, ..cmd }, data.len() as u64, true).is_err());
+    }
+
+    #[test]
+    fn string_table_past_the_end_is_rejected() {
+        let (data, cmd) = symtab_image(2);
+
+        assert!(validate_symtab(data.len(), &SymtabCommand { stroff: data.len() as u32, ..cmd }, 0, true).is_err());
+        // an overlong strsize is clamped by SymbolIter instead
+        assert!(validate_symtab(data.len(), &SymtabCommand { strsize: u32::MAX, ..cmd }, 0, true).is_ok());
+        assert_eq!(parse_symbols(&data, &SymtabCommand { strsize: u32::MAX, ..cmd }, 0, true, false).unwrap()[1].name, "_sym1");
+    }
+
+    #[test]
+    fn string_order_is_section_then_offset() {
+        let string = |section_index: u32, offset: u64| ParsedString {
+            value: format!("{}:{}", section_index, offset),
+            encoding: StringEncoding::Ascii,
+            raw: None,
+            segname: [0; 16],
+            sectname: [0; 16],
+            section_index,
+            offset,
+        };
+        let order = |strings: &[ParsedString]| strings.iter().map(|s| s.value.clone()).collect::<Vec<_>>();
+
+        // same strings, collected in two different orders (think dedup / per-section filters)
+        let mut a = vec![string(7, 0x40), string(3, 0x10), string(7, 0x00), string(3, 0x00)];
+        let mut b = vec![string(3, 0x00), string(7, 0x00), string(7, 0x40), string(3, 0x10)];
+        sort_strings(&mut a);
+        sort_strings(&mut b);
+
+        assert_eq!(order(&a), ["3:0", "3:16", "7:0", "7:64"]);
+        assert_eq!(order(&a), order(&b));
+    }
+}
@@ -1,6 +1,8 @@
 // File Purpose: Various functions I seem to be using a lot between files
 
-use std::error::Error;
+use crate::macho::error::MoscopeError;
+use std::path::Path;
+use std::time::{Duration, Instant};
 
 
 /*  
@@ -20,17 +22,17 @@ use std::error::Error;
 pub trait FromEndianBytes: Sized {
     const SIZE: usize;
 
-    fn from_be(bytes: &[u8]) -> Result<Self, Box<dyn Error>>;
-    fn from_le(bytes: &[u8]) -> Result<Self, Box<dyn Error>>;
+    fn from_be(bytes: &[u8]) -> Result<Self, MoscopeError>;
+    fn from_le(bytes: &[u8]) -> Result<Self, MoscopeError>;
 }
 
 impl FromEndianBytes for u16 {
     const SIZE: usize = 2;
 
-    fn from_be(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+    fn from_be(bytes: &[u8]) -> Result<Self, MoscopeError> {
         Ok(u16::from_be_bytes(bytes.try_into()?))
     }
-    fn from_le(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+    fn from_le(bytes: &[u8]) -> Result<Self, MoscopeError> {
         Ok(u16::from_le_bytes(bytes.try_into()?))
     }
 }
@@ -38,10 +40,10 @@ impl FromEndianBytes for u16 {
 impl FromEndianBytes for u32 {
     const SIZE: usize = 4;
 
-    fn from_be(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+    fn from_be(bytes: &[u8]) -> Result<Self, MoscopeError> {
         Ok(u32::from_be_bytes(bytes.try_into()?))
     }
-    fn from_le(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+    fn from_le(bytes: &[u8]) -> Result<Self, MoscopeError> {
         Ok(u32::from_le_bytes(bytes.try_into()?))
     }
 }
@@ -49,10 +51,10 @@ impl FromEndianBytes for u32 {
 impl FromEndianBytes for i32 {
     const SIZE: usize = 4;
 
-    fn from_be(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+    fn from_be(bytes: &[u8]) -> Result<Self, MoscopeError> {
         Ok(i32::from_be_bytes(bytes.try_into()?))
     }
-    fn from_le(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+    fn from_le(bytes: &[u8]) -> Result<Self, MoscopeError> {
         Ok(i32::from_le_bytes(bytes.try_into()?))
     }
 }
@@ -60,17 +62,17 @@ impl FromEndianBytes for i32 {
 impl FromEndianBytes for u64 {
     const SIZE: usize = 8;
 
-    fn from_be(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+    fn from_be(bytes: &[u8]) -> Result<Self, MoscopeError> {
         Ok(u64::from_be_bytes(bytes.try_into()?))
     }
-    fn from_le(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+    fn from_le(bytes: &[u8]) -> Result<Self, MoscopeError> {
         Ok(u64::from_le_bytes(bytes.try_into()?))
     }
 }
 
-pub fn bytes_to<T: FromEndianBytes>(is_be: bool, data: &[u8]) -> Result<T, Box<dyn Error>> {
+pub fn bytes_to<T: FromEndianBytes>(is_be: bool, data: &[u8]) -> Result<T, MoscopeError> {
     if data.len() <T::SIZE {
-        return Err("Buffer too small".into());
+        return Err(MoscopeError::TooSmall { needed: T::SIZE, available: data.len() });
     }
     if is_be {
         T::from_be(&data[..T::SIZE])
@@ -97,8 +99,13 @@ pub fn byte_array_to_string(bytes: &[u8; 16]) -> String {
     // tl;dr take byte array --> replace invalid utf --> clone the cow
 }
 
+// "ef bb bf 68 69", space separated lowercase pairs like xxd
+pub fn hex_string(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(" ")
+}
+
 
-pub fn read_uleb(data: &[u8], cursor: &mut usize) -> Result<u64, Box<dyn Error>> {
+pub fn read_uleb(data: &[u8], cursor: &mut usize) -> Result<u64, MoscopeError> {
     // uleb128 = unsigned little endian base 128
     // Using the druntime implementation of reading it
     // Since that it what a half-decade-old stack overflow post pointed me to
@@ -107,7 +114,7 @@ pub fn read_uleb(data: &[u8], cursor: &mut usize) -> Result<u64, Box<dyn Error>>
     let mut shift: u32 = 0;
     loop {
         if *cursor >= data.len() {
-            return Err("read_uleb: cursor out of bounds".into());
+            return Err(MoscopeError::OutOfBounds { offset: *cursor as u64, len: 1 });
         }
         let byte = data[*cursor];
         *cursor += 1; // dereffing to advance the func caller's cursor 
@@ -127,7 +134,7 @@ pub fn read_uleb(data: &[u8], cursor: &mut usize) -> Result<u64, Box<dyn Error>>
 
 
 
-pub fn read_sleb(data: &[u8], cursor: &mut usize) -> Result<i64, Box<dyn Error>> {
+pub fn read_sleb(data: &[u8], cursor: &mut usize) -> Result<i64, MoscopeError> {
     // sleb128 = signed little endian base 128
     let mut result: i64 = 0;
     let mut shift: u32 = 0;
@@ -136,7 +143,7 @@ pub fn read_sleb(data: &[u8], cursor: &mut usize) -> Result<i64, Box<dyn Error>>
 
     loop {
         if *cursor >= data.len() {
-            return Err("read_sleb: cursor out of bounds".into());
+            return Err(MoscopeError::OutOfBounds { offset: *cursor as u64, len: 1 });
         }
 
         byte = data[*cursor];
@@ -162,6 +169,43 @@ pub fn read_sleb(data: &[u8], cursor: &mut usize) -> Result<i64, Box<dyn Error>>
     Ok(result)
 }
 
+// Path as the user should see it: relative to `root` when it lives under it, untouched otherwise
+// Both sides are compared as given, so pass them in the same form (both absolute or both relative)
+pub fn display_path(path: &Path, root: &Path) -> String {
+    match path.strip_prefix(root) {
+        Ok(relative) if !relative.as_os_str().is_empty() => relative.display().to_string(),
+        _ => path.display().to_string(),
+    }
+}
+
+// --timings: each lap() records the time since the previous one under a phase name
+#[derive(Debug, Clone)]
+pub struct PhaseTimer {
+    last: Instant,
+    pub phases: Vec<(&'static str, Duration)>,
+}
+
+impl PhaseTimer {
+    // The lap covering every slice's parse, each ParsedSlice carries its own timer with the breakdown
+    pub const PARSE_SLICES: &'static str = "parse + report";
+
+    pub fn new() -> Self {
+        PhaseTimer { last: Instant::now(), phases: Vec::new() }
+    }
+
+    pub fn lap(&mut self, phase: &'static str) {
+        let now = Instant::now();
+        self.phases.push((phase, now - self.last));
+        self.last = now;
+    }
+}
+
+impl Default for PhaseTimer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /*
 ============================
 ======== UNIT TESTS ========
@@ -221,6 +265,7 @@ mod tests {
         assert_eq!(value, 1);
     }
 
+    #[test]
     fn bytes_to_u64_be() {
         let data = [0, 0, 0, 0, 0, 0, 0, 1];
         let value: u64 = bytes_to(true, &data).unwrap();
@@ -244,5 +289,40 @@ mod tests {
         assert_eq!(value, 0x12345678); 
         let value: u64 = bytes_to(true, &data).unwrap();
         assert_eq!(value, 0x12345678_9ABCDEFF); 
-    }    
-}
\ No newline at end of file
+    }
+
+    #[test]
+    fn leb_past_the_end_is_out_of_bounds() {
+        let mut cursor = 0;
+        assert_eq!(read_uleb(&[0xe5, 0x8e, 0x26], &mut cursor).unwrap(), 624485);
+        let mut cursor = 0;
+        assert_eq!(read_sleb(&[0xc0, 0xbb, 0x78], &mut cursor).unwrap(), -123456);
+
+        // continuation bit set on the last byte
+        let mut cursor = 0;
+        assert_eq!(read_uleb(&[0x80, 0x80], &mut cursor).unwrap_err(), MoscopeError::OutOfBounds { offset: 2, len: 1 });
+        let mut cursor = 1;
+        assert_eq!(read_sleb(&[0x00, 0xff], &mut cursor).unwrap_err(), MoscopeError::OutOfBounds { offset: 2, len: 1 });
+    }
+
+    #[test]
+    fn phase_timer_keeps_laps_in_order() {
+        let mut timer = PhaseTimer::new();
+        timer.lap("first");
+        std::thread::sleep(Duration::from_millis(2));
+        timer.lap("second");
+
+        let names: Vec<&str> = timer.phases.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, ["first", "second"]);
+        assert!(timer.phases[1].1 >= Duration::from_millis(2));
+    }
+
+    #[test]
+    fn display_path_strips_root_prefix() {
+        let root = Path::new("/Applications/Foo.app");
+
+        assert_eq!(display_path(Path::new("/Applications/Foo.app/Contents/MacOS/Foo"), root), "Contents/MacOS/Foo");
+        assert_eq!(display_path(Path::new("/usr/bin/true"), root), "/usr/bin/true");
+        assert_eq!(display_path(root, root), "/Applications/Foo.app"); // never print an empty path
+    }
+}
@@ -0,0 +1,283 @@
+// File Purpose: Which build is this? (LC_UUID)
+
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::io::{self, Write};
+use colored::Colorize;
+use crate::macho::constants::LC_UUID;
+use crate::macho::diagnostics::Diagnostic;
+use crate::macho::dylibs::{DylibKind, ParsedDylib};
+use crate::macho::load_commands::LoadCommand;
+use crate::reporting::uuid::UuidReport;
+
+/*
+uuid_command memory layout (from loader.h)
++-----------------------------+
+| cmd (u32)                   |  LC_UUID
+| cmdsize (u32)               |  always 24
+| uuid[16] (u8)               |  raw bytes, NOT endian swapped
++-----------------------------+
+
+The UUID is what dsymutil stamps into both the binary and its dSYM,
+so it's the key for matching a binary to debug info or a crash report.
+*/
+
+pub const UUID_COMMAND_SIZE: u32 = 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParsedUuid {
+    pub bytes: [u8; 16],
+}
+
+impl ParsedUuid {
+    // Canonical XXXXXXXX-XXXX-XXXX-XXXX-XXXXXXXXXXXX form (uppercase, like dwarfdump prints)
+    pub fn to_canonical(&self) -> String {
+        let b = &self.bytes;
+        format!(
+            "{:02X}{:02X}{:02X}{:02X}-{:02X}{:02X}-{:02X}{:02X}-{:02X}{:02X}-{:02X}{:02X}{:02X}{:02X}{:02X}{:02X}",
+            b[0], b[1], b[2], b[3],
+            b[4], b[5],
+            b[6], b[7],
+            b[8], b[9],
+            b[10], b[11], b[12], b[13], b[14], b[15],
+        )
+    }
+}
+
+
+pub fn parse_uuid(data: &[u8], lc: &LoadCommand) -> Result<ParsedUuid, Box<dyn Error>> {
+    // The uuid is a plain byte array so there's no endianness to worry about here
+
+    if lc.cmdsize != UUID_COMMAND_SIZE {
+        return Err(format!("LC_UUID has cmdsize {} (expected {})", lc.cmdsize, UUID_COMMAND_SIZE).into());
+    }
+
+    let base = lc.offset as usize;
+    if base + UUID_COMMAND_SIZE as usize > data.len() {
+        return Err("LC_UUID exceeds file bounds".into());
+    }
+
+    let bytes: [u8; 16] = data[base + 8 .. base + 24].try_into()?; // skip cmd & cmdsize
+
+    Ok(ParsedUuid { bytes })
+}
+
+
+// Short circuit helper for the --uuid fast path, only walks the load command list
+pub fn find_uuid(data: &[u8], load_commands: &[LoadCommand]) -> Result<Option<ParsedUuid>, Box<dyn Error>> {
+    match load_commands.iter().find(|lc| lc.cmd == LC_UUID) {
+        Some(lc) => Ok(Some(parse_uuid(data, lc)?)),
+        None => Ok(None),
+    }
+}
+
+
+pub fn build_uuid_report(cpu_type: &str, cpu_subtype: &str, uuid: Option<&ParsedUuid>) -> UuidReport {
+    UuidReport {
+        cpu_type: cpu_type.to_string(),
+        cpu_subtype: cpu_subtype.to_string(),
+        uuid: uuid.map(|u| u.to_canonical()),
+    }
+}
+
+// dwarfdump --uuid style, one line per slice with the file it came from
+pub fn write_uuid_summary(w: &mut dyn Write, report: &UuidReport, path: &str) -> io::Result<()> {
+    let value = report.uuid.as_deref().unwrap_or("<no LC_UUID>");
+    writeln!(w, "{} {} ({}) {}", "UUID:".yellow().bold(), value, report.cpu_subtype, path)
+}
+
+
+/*
+Slices of a universal binary are separate link outputs of the same sources, so:
+    - every slice has its own UUID, and they're all different       (normal)
+    - two slices sharing a UUID breaks dSYM / crash report matching (someone copied one around)
+    - slices linking different dylibs, or claiming a different
+      LC_ID_DYLIB version, usually means a lipo of mismatched builds
+*/
+pub struct SliceFingerprint<'a> {
+    pub arch: String,
+    pub uuid: Option<ParsedUuid>,
+    pub dylibs: &'a [ParsedDylib],
+}
+
+pub fn check_slice_consistency(slices: &[SliceFingerprint]) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    if slices.len() < 2 {
+        return diagnostics;
+    }
+
+    for (i, a) in slices.iter().enumerate() {
+        for b in &slices[i + 1..] {
+            if let (Some(ua), Some(ub)) = (a.uuid, b.uuid) && ua == ub {
+                diagnostics.push(Diagnostic::warning(
+                    "slice-uuid-duplicate",
+                    format!("{} and {} share UUID {}, dSYM matching can't tell them apart", a.arch, b.arch, ua.to_canonical()),
+                ));
+            }
+        }
+    }
+
+    if slices.iter().any(|s| s.uuid.is_some()) {
+        for s in slices.iter().filter(|s| s.uuid.is_none()) {
+            diagnostics.push(Diagnostic::warning(
+                "slice-uuid-missing",
+                format!("{} has no LC_UUID while other slices do", s.arch),
+            ));
+        }
+    }
+
+    // Everything is compared against the first slice
+    let first = &slices[0];
+    let first_deps = dependency_set(first.dylibs);
+    let first_version = id_dylib_version(first.dylibs);
+
+    for other in &slices[1..] {
+        let deps = dependency_set(other.dylibs);
+        if deps != first_deps {
+            let only_first: Vec<&str> = first_deps.difference(&deps).copied().collect();
+            let only_other: Vec<&str> = deps.difference(&first_deps).copied().collect();
+            diagnostics.push(Diagnostic::warning(
+                "slice-dylibs-differ",
+                format!(
+                    "{} and {} link different dylibs (only in {}: [{}], only in {}: [{}])",
+                    first.arch, other.arch, first.arch, only_first.join(", "), other.arch, only_other.join(", ")
+                ),
+            ));
+        }
+
+        let version = id_dylib_version(other.dylibs);
+        if version != first_version {
+            diagnostics.push(Diagnostic::warning(
+                "slice-version-differs",
+                format!(
+                    "LC_ID_DYLIB current version differs: {} has {}, {} has {}",
+                    first.arch, format_version(first_version), other.arch, format_version(version)
+                ),
+            ));
+        }
+    }
+
+    diagnostics
+}
+
+fn dependency_set(dylibs: &[ParsedDylib]) -> BTreeSet<&str> {
+    dylibs
+        .iter()
+        .filter(|d| !matches!(d.kind, DylibKind::Id))
+        .map(|d| d.path.as_str())
+        .collect()
+}
+
+fn id_dylib_version(dylibs: &[ParsedDylib]) -> Option<u32> {
+    dylibs.iter().find(|d| matches!(d.kind, DylibKind::Id)).map(|d| d.current_version)
+}
+
+// X.Y.Z packed as xxxx.yy.zz nibbles, same as otool -L
+fn format_version(version: Option<u32>) -> String {
+    match version {
+        Some(v) => format!("{}.{}.{}", v >> 16, (v >> 8) & 0xff, v & 0xff),
+        None => "none".to_string(),
+    }
+}
+
+
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uuid_command(cmdsize: u32) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&LC_UUID.to_le_bytes());
+        data.extend_from_slice(&cmdsize.to_le_bytes());
+        data.extend_from_slice(&[
+            0x01, 0x23, 0x45, 0x67, 0x89, 0xAB, 0xCD, 0xEF,
+            0xFE, 0xDC, 0xBA, 0x98, 0x76, 0x54, 0x32, 0x10,
+        ]);
+        data
+    }
+
+    #[test]
+    fn parse_uuid_formats_canonical_string() {
+        let data = uuid_command(24);
+        let lc = LoadCommand { cmd: LC_UUID, cmdsize: 24, offset: 0 };
+
+        let uuid = parse_uuid(&data, &lc).unwrap();
+
+        assert_eq!(uuid.to_canonical(), "01234567-89AB-CDEF-FEDC-BA9876543210");
+    }
+
+    #[test]
+    fn parse_uuid_rejects_wrong_cmdsize() {
+        let mut data = uuid_command(32);
+        data.extend_from_slice(&[0u8; 8]);
+        let lc = LoadCommand { cmd: LC_UUID, cmdsize: 32, offset: 0 };
+
+        assert!(parse_uuid(&data, &lc).is_err());
+    }
+
+    fn dylib(path: &str, kind: DylibKind, current_version: u32) -> ParsedDylib {
+        ParsedDylib {
+            path: path.to_string(),
+            timestamp: 2,
+            current_version,
+            compatibility_version: 0x10000,
+            kind,
+            source_lc: LoadCommand { cmd: 0, cmdsize: 0, offset: 0 },
+        }
+    }
+
+    fn slice<'a>(arch: &str, uuid: Option<u8>, dylibs: &'a [ParsedDylib]) -> SliceFingerprint<'a> {
+        SliceFingerprint { arch: arch.to_string(), uuid: uuid.map(|b| ParsedUuid { bytes: [b; 16] }), dylibs }
+    }
+
+    #[test]
+    fn distinct_uuids_and_matching_dylibs_are_consistent() {
+        let dylibs = vec![dylib("/usr/lib/libSystem.B.dylib", DylibKind::Load, 0x10000)];
+        let slices = [slice("x86_64", Some(1), &dylibs), slice("arm64", Some(2), &dylibs)];
+
+        assert!(check_slice_consistency(&slices).is_empty());
+        assert!(check_slice_consistency(&slices[..1]).is_empty());
+    }
+
+    #[test]
+    fn shared_or_missing_uuids_are_reported() {
+        let slices = [slice("x86_64", Some(1), &[]), slice("arm64", Some(1), &[]), slice("arm64e", None, &[])];
+        let codes: Vec<&str> = check_slice_consistency(&slices).iter().map(|d| d.code).collect();
+
+        assert_eq!(codes, vec!["slice-uuid-duplicate", "slice-uuid-missing"]);
+    }
+
+    #[test]
+    fn diverging_dylibs_and_versions_are_reported() {
+        let x86 = vec![
+            dylib("@rpath/libFoo.dylib", DylibKind::Id, 0x10203),
+            dylib("/usr/lib/libSystem.B.dylib", DylibKind::Load, 0x10000),
+        ];
+        let arm = vec![
+            dylib("@rpath/libFoo.dylib", DylibKind::Id, 0x10204),
+            dylib("/usr/lib/libSystem.B.dylib", DylibKind::Load, 0x10000),
+            dylib("/usr/lib/libobjc.A.dylib", DylibKind::Load, 0x10000),
+        ];
+        let diags = check_slice_consistency(&[slice("x86_64", Some(1), &x86), slice("arm64", Some(2), &arm)]);
+
+        assert_eq!(diags.len(), 2);
+        assert_eq!(diags[0].code, "slice-dylibs-differ");
+        assert!(diags[0].message.contains("only in arm64: [/usr/lib/libobjc.A.dylib]"), "{}", diags[0].message);
+        assert_eq!(diags[1].code, "slice-version-differs");
+        assert!(diags[1].message.contains("x86_64 has 1.2.3, arm64 has 1.2.4"), "{}", diags[1].message);
+    }
+
+    #[test]
+    fn find_uuid_returns_none_without_lc_uuid() {
+        let data = uuid_command(24);
+        let lc = LoadCommand { cmd: 0x2A, cmdsize: 24, offset: 0 };
+
+        assert_eq!(find_uuid(&data, &[lc]).unwrap(), None);
+    }
+}
@@ -1,45 +1,126 @@
 #![allow(warnings)]
 use core::arch;
 use std::error::Error;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::mem::size_of;
 
 
 use moscope::macho::constants::*;
+use moscope::macho::analysis;
 use moscope::macho::dyld;
+use moscope::macho::diagnostics;
 use moscope::macho::fat;
+use moscope::macho::fileset;
+use moscope::macho::compression;
+use moscope::macho::input;
+use moscope::macho::build_version;
 use moscope::macho::header;
 use moscope::macho::load_commands;
 use moscope::macho::rpaths::ParsedRPath;
+use moscope::macho::search;
 use moscope::macho::segments;
+use moscope::macho::sections;
 use moscope::macho::sections::SectionKind;
 use moscope::macho::dylibs;
 use moscope::macho::dyld::Fixup;
 use moscope::macho::rpaths;
 use moscope::macho::symtab;
 use moscope::macho::symtab::DYSymtabCommand;
+use moscope::macho::uuid;
+use moscope::macho::identify;
+use moscope::macho::initializers;
+use moscope::macho::export_trie;
+use moscope::macho::function_starts;
+use moscope::macho::codesign;
+use moscope::macho::objc;
+use moscope::macho::hash;
+use moscope::macho::utils;
 use moscope::macho::utils::{bytes_to,byte_array_to_string};
-use moscope::macho::memory_image::MachOMemoryImage;
-use moscope::reporting::macho::{MachOReport, ArchitectureReport, build_macho_report, build_architecture_report, ReportOptions};
+use moscope::macho::memory_image::{self, MachOMemoryImage};
+use moscope::reporting::canonical;
+use moscope::reporting::json;
+use moscope::reporting::macho::{AllReport, MachOReport, ArchitectureReport, build_macho_report, build_architecture_report, ReportOptions};
 use moscope::reporting::header::MachHeaderReport;
-use moscope::reporting::load_commands::LoadCommandReport;
-use moscope::reporting::segments::SegmentReport;
+use moscope::reporting::load_commands::{LinkEditOverviewReport, LoadCommandMapReport, LoadCommandReport};
+use moscope::reporting::codesign::{CodeSignatureReport, SignatureCheckReport};
+use moscope::reporting::exports::{ExportsReport, ReexportsReport};
+use moscope::reporting::function_starts::FunctionStartsReport;
+use moscope::reporting::initializers::InitializersReport;
+use moscope::reporting::objc::{ObjCCategoriesReport, ObjCProtocolsReport};
+use moscope::reporting::sections::{LiteralsReport, UnknownSectionsReport};
+use moscope::reporting::symtab::OsoReport;
+use moscope::reporting::segments::{AppendedDataReport, SegmentReport};
 use moscope::reporting::dylibs::DylibReport;
 use moscope::reporting::rpaths::RPathsReport;
+use moscope::reporting::diff;
+use moscope::reporting::verify;
+use moscope::reporting::csv::CsvTable;
+use moscope::reporting::writer::{CsvWriter, DotWriter, JsonWriter, MarkdownWriter, OutputWriter, PlistWriter, TextOptions, TextReport, TextWriter};
+use moscope::reporting::identify::{IdentityReport, ScannedFileReport};
 
 
 use colored::{control, Colorize};
-use serde_json::to_string_pretty;
 use std::io::IsTerminal;
 use std::collections::HashMap;
 
-use clap::{Parser, ValueEnum};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 
 
 #[derive(Clone, Debug, ValueEnum, PartialEq)]
 pub enum OutputFormat {
     Text,
     Json,
+    Plist, // XML property list, for plutil / PlistBuddy / defaults
+    Csv,   // one flat table of the report, picked with --csv-table
+    Dot,   // GraphViz graph of the dylib dependencies, for `dot -Tpng`
+    Markdown, // headings and GFM tables, for docs
+}
+
+
+// `moscope <BINARY> [flags]` is shorthand for `moscope inspect <BINARY> [flags]`
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print the report for a Mach-O (the default when no subcommand is given)
+    Inspect(InspectArgs),
+
+    /// Compare the reports of two Mach-Os, exits 1 when they differ
+    Diff {
+        /// The report filters (--no-strings, ...) and --format apply to both sides
+        #[command(flatten)]
+        args: InspectArgs,
+
+        /// Mach-O to compare BINARY against
+        #[arg(value_name = "OTHER")]
+        other: PathBuf,
+    },
+
+    /// Parse every slice and list its diagnostics, exits 1 on an error (on a warning too with --strict)
+    Verify(InspectArgs),
+
+    /// Extract one architecture from a fat binary as a standalone thin Mach-O (like `lipo -thin`)
+    Thin {
+        /// Architecture to extract, as printed by --identify (arm64, x86_64, arm64e, ...)
+        arch: String,
+
+        /// Fat binary to extract from
+        #[arg(value_name = "BINARY")]
+        input: PathBuf,
+
+        /// Where to write the thin Mach-O
+        #[arg(short, long, value_name = "OUT")]
+        output: PathBuf,
+    },
+
+    /// Combine thin Mach-Os of different architectures into one fat binary (like `lipo -create`)
+    Merge {
+        /// Thin Mach-O inputs, slices are written in this order
+        #[arg(value_name = "BINARY", required = true)]
+        inputs: Vec<PathBuf>,
+
+        /// Where to write the fat binary
+        #[arg(short, long, value_name = "OUT")]
+        output: PathBuf,
+    },
 }
 
 
@@ -47,12 +128,34 @@ pub enum OutputFormat {
 #[command(
     name = "moscope",
     version,
-    about = "Mach-O static analysis and inspection toolkit"
+    about = "Mach-O static analysis and inspection toolkit",
+    subcommand_negates_reqs = true,
+    args_conflicts_with_subcommands = true
 )]
 struct Cli {
-    /// Path to the Mach-O binary to inspect
-    #[arg(value_name = "BINARY")]
-    binary: PathBuf,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    inspect: InspectArgs,
+}
+
+
+// Everything `inspect` takes, also flattened into Cli itself so `moscope <BINARY>` keeps working
+#[derive(Args, Debug)]
+struct InspectArgs {
+    /// Path to the Mach-O binary to inspect, or `-` to read it from stdin
+    #[arg(value_name = "BINARY", required = true)]
+    binary: Option<PathBuf>,
+
+    /// Size hint in bytes when reading from stdin, so the buffer is allocated once up front
+    #[arg(long, value_name = "BYTES")]
+    expected_size: Option<usize>,
+
+    /// Analyze the Nth LC_FILESET_ENTRY member (0-based, as listed under Fileset Entries) of a kernel cache
+    /// instead of the fileset itself; every other option applies to that member
+    #[arg(long, value_name = "N")]
+    fileset_index: Option<usize>,
 
     // Disable color output
     #[arg(long)]
@@ -62,6 +165,10 @@ struct Cli {
     #[clap(value_enum, long, default_value = "text")]
     format: OutputFormat,
 
+    /// Write the report to this file instead of stdout (no colors), `-` is stdout
+    #[arg(long, value_name = "PATH")]
+    output: Option<PathBuf>,
+
     // Flags for output filtering
     #[arg(long, default_value_t = 4)]
     min_string_length: usize,
@@ -75,6 +182,66 @@ struct Cli {
     #[arg(long)]
     no_segments: bool,
 
+    /// Find every occurrence of a string in the string sections (section, vmaddr, file offset), then exit
+    #[arg(long, value_name = "NEEDLE", conflicts_with = "find_hex")]
+    find_string: Option<String>,
+
+    /// Find a raw byte sequence (e.g. "de ad be ef") anywhere in section data, then exit
+    #[arg(long, value_name = "BYTES")]
+    find_hex: Option<String>,
+
+    /// Scan the whole file for a byte signature, ?? matches any byte (e.g. "48 8b ?? 48 89"), then exit
+    #[arg(long, value_name = "PATTERN")]
+    scan_bytes: Option<String>,
+
+    /// Limit --scan-bytes to one segment or section in every slice (e.g. __TEXT or __TEXT,__text)
+    #[arg(long, value_name = "SEG[,SECT]", requires = "scan_bytes")]
+    scan_in: Option<String>,
+
+    /// Hex dump raw bytes at a file offset (decimal or 0x hex), relative to the --arch slice if given, then exit
+    #[arg(long, value_name = "OFFSET", value_parser = search::parse_offset, requires = "len")]
+    bytes_at: Option<u64>,
+
+    /// How many bytes --bytes-at dumps
+    #[arg(long, value_name = "N", requires = "bytes_at")]
+    len: Option<u64>,
+
+    /// Fat slice --bytes-at offsets are relative to (arm64, x86_64, ... as printed by --identify)
+    #[arg(long, value_name = "ARCH", requires = "bytes_at")]
+    arch: Option<String>,
+
+    /// Dump the 4, 8 and 16 byte literal sections (float, double and vector constants) as hex, then exit
+    #[arg(long)]
+    literals: bool,
+
+    /// List every linkedit_data_command blob (code signature, function starts, chained fixups, ...) with its offset and size, then exit
+    #[arg(long)]
+    linkedit: bool,
+
+    /// Decode LC_FUNCTION_STARTS and list the start address of every function, then exit
+    #[arg(long)]
+    function_starts: bool,
+
+    /// Look for bytes appended after __LINKEDIT / the code signature in every slice and flag them, then exit
+    #[arg(long)]
+    detect_appended: bool,
+
+    /// Trailing bytes below this size count as padding for --detect-appended
+    #[arg(long, value_name = "BYTES", default_value_t = segments::DEFAULT_APPENDED_THRESHOLD)]
+    appended_threshold: u64,
+
+    /// List every section classified as Unknown/Other (raw name bytes, type, count) across all slices, then exit
+    #[arg(long)]
+    report_unknown_sections: bool,
+
+    /// After the segment listing, tally sections by kind (Code, CString, ..., Unknown)
+    #[arg(long)]
+    count_sections_by_kind: bool,
+
+    /// Print what the section kind colors in the segments summary mean (skipped when color is off)
+    #[arg(long)]
+    legend: bool,
+
     #[arg(long)]
     no_loadcmds: bool,
 
@@ -87,23 +254,67 @@ struct Cli {
     #[arg(long)]
     no_dylibs: bool,
 
+    /// Show dylib install names with `.`, `..` and duplicate slashes resolved (raw path alongside when they differ)
+    #[arg(long)]
+    canonical_name: bool,
+
     #[arg(long)]
     no_rpaths: bool,
 
     #[arg(long)]
     no_fixups: bool,
 
+    /// Leave out the structural diagnostics (per slice and cross-slice); the checks still run, they just aren't shown
+    #[arg(long)]
+    no_diagnostics: bool,
+
+    /// Hex dump the body of every load command moscope doesn't recognize (UNKNOWN_LOAD_COMMAND) in the load command list
+    #[arg(long)]
+    dump_unknown_lc: bool,
+
+    /// Collapse duplicate LC_RPATH entries in the output (duplicates are still reported as diagnostics)
+    #[arg(long)]
+    dedup_rpaths: bool,
+
     #[arg(long)]
     max_symbols: Option<usize>,
 
     #[arg(long)]
     include_debug_symbols: bool,
 
+    /// Show symbol names without their single leading underscore (`_main` --> `main`), JSON keeps the raw name
+    #[arg(long)]
+    strip_underscore: bool,
+
+    /// Only keep debug (STAB) symbols, e.g. N_SO/N_OSO/N_FUN source and object mappings
+    #[arg(long)]
+    debug_symbols_only: bool,
+
     // String filtering
     /// Filter strings by regex pattern (e.g., "^http", "\.dylib$", "password")
     #[arg(long)]
     string_pattern: Option<String>,
 
+    /// Only keep strings made entirely of printable ASCII (0x20-0x7E and tab)
+    #[arg(long)]
+    ascii_only: bool,
+
+    /// Keep each string's on-disk bytes (hex, BOM included) alongside the decoded value
+    #[arg(long)]
+    raw_strings: bool,
+
+    /// Read string sections straight from their file offsets instead of building a VM image of the slice.
+    /// Only skips the image for chained-fixup binaries: LC_DYLD_INFO fixups are resolved by VM address and still
+    /// build one, and MH_DYLIB_IN_CACHE dylibs always need it
+    #[arg(long)]
+    no_vm_image: bool,
+
+    /// Per-section minimum string length, overriding --min-string-length for those sections.
+    /// Listed C string literal sections (e.g. __objc_methname) are scanned even if they aren't __cstring
+    /// Example: --section-min-length __objc_methname=2,__cstring=8
+    #[arg(long, value_name = "SECT=N,...", value_parser = symtab::parse_section_min_lengths)]
+    section_min_length: Option<HashMap<String, usize>>,
+
     /// Only extract strings from specific sections (comma-separated)
     /// Example: --string-sections __cstring,__const
     #[arg(long, value_delimiter = ',')]
@@ -114,6 +325,120 @@ struct Cli {
     #[arg(long, value_delimiter = ',')]
     skip_sections: Option<Vec<String>>,
 
+    /// Only print the LC_UUID of each architecture and exit (respects --format)
+    #[arg(long)]
+    uuid: bool,
+
+    /// Require LC_BUILD_VERSION to target this platform (e.g. macOS, iOS, macCatalyst)
+    #[arg(long, value_name = "PLATFORM")]
+    require_platform: Option<String>,
+
+    /// Require the LC_BUILD_VERSION minimum OS to be at most this version (e.g. 13.0)
+    #[arg(long, value_name = "VERSION")]
+    require_min_os: Option<String>,
+
+    /// Exit non-zero when a --require-* check fails (otherwise failures are only warnings)
+    #[arg(long)]
+    strict: bool,
+
+    /// Emit canonical JSON (sorted keys, no whitespace) so the same binary always hashes the same
+    #[arg(long)]
+    canonical_json: bool,
+
+    /// Indentation width for JSON output, 0 prints each report on a single line
+    #[arg(long, value_name = "N", default_value_t = 2)]
+    json_indent: usize,
+
+    /// Which table --format csv writes
+    #[clap(value_enum, long, default_value = "symbols")]
+    csv_table: CsvTable,
+
+    /// Developer aid: print the Debug representation of every parsed structure instead of the report
+    #[arg(long, hide = true)]
+    debug_dump: bool,
+
+    /// Only list the N_OSO object file stabs (path + mtime) used by dsymutil and exit
+    #[arg(long)]
+    oso: bool,
+
+    /// Map the load command region byte by byte (index, command, offset range) and flag gaps/overlaps, then exit
+    #[arg(long)]
+    lc_map: bool,
+
+    /// Write just the load command table (name, cmd, cmdsize, offset, requires_dyld) as JSON to PATH and exit ('-' for stdout)
+    #[arg(long, value_name = "PATH")]
+    export_load_commands: Option<PathBuf>,
+
+    /// List Objective-C categories (__objc_catlist): the class each one extends and its methods, then exit
+    #[arg(long)]
+    objc_categories: bool,
+
+    /// List Objective-C protocols (__objc_protolist / __objc_protorefs) with their required and optional methods, then exit
+    #[arg(long)]
+    objc_protocols: bool,
+
+    /// List static initializers (S_MOD_INIT_FUNC_POINTERS in __DATA or __DATA_CONST) with the functions they point at, then exit
+    #[arg(long)]
+    initializers: bool,
+
+    /// List every symbol in the export trie (LC_DYLD_EXPORTS_TRIE or LC_DYLD_INFO's export_off) with its address and kind,
+    /// re-exports with the dylib they come from and resolvers with their resolver offset, then exit
+    #[arg(long)]
+    exports: bool,
+
+    /// List symbols the export trie re-exports from another dylib, with the dylib they come from, then exit
+    #[arg(long)]
+    reexports: bool,
+
+    /// Print the code signature (identifier, CodeDirectories, CDHash) of each slice, then exit
+    #[arg(long)]
+    code_signature: bool,
+
+    /// Recompute every code page hash of the code signature and compare it with the one stored in
+    /// each CodeDirectory, then exit (status 1 when a signed slice doesn't match)
+    #[arg(long)]
+    check_signature: bool,
+
+    /// Print everything about a single section (type, attributes, reserved fields, indirect symbol range) and exit
+    /// Example: --section-info __TEXT,__stubs
+    #[arg(long, value_name = "SEG,SECT")]
+    section_info: Option<String>,
+
+    /// Analyze every architecture of a fat binary instead of prompting for one
+    /// (also the default when MOSCOPE_NO_PROMPT is set or stdin is not a terminal)
+    #[arg(long)]
+    all_archs: bool,
+
+    /// Print file paths relative to the scanned directory (always on with --recursive, JSON keeps the absolute path too);
+    /// a single BINARY is shown relative to the current directory
+    #[arg(long)]
+    relative_paths: bool,
+
+    /// Treat BINARY as a directory and print the --identify line for every Mach-O found under it
+    #[arg(long)]
+    recursive: bool,
+
+    /// Limit --recursive to N directory levels (1 = only files directly inside BINARY), unlimited by default
+    #[arg(long, value_name = "N", requires = "recursive", value_parser = clap::value_parser!(u64).range(1..))]
+    max_depth: Option<u64>,
+
+    /// Only list --recursive slices of this file type (MH_EXECUTE, MH_DYLIB, ... or just execute, dylib, ...)
+    #[arg(long, value_name = "TYPE", requires = "recursive")]
+    filetype: Option<String>,
+
+    /// Print how long each phase took (input, per-slice parsing, report build, output) to stderr
+    #[arg(long)]
+    timings: bool,
+
+    /// Everything at once: the full report for every architecture plus the load command map, linkedit overview, code signature and its page check,
+    /// initializers, exports, re-exports, ObjC categories and protocols, OSO stabs, unknown sections, literals, function starts and appended data (an analysis that can't run is skipped)
+    #[arg(long)]
+    all: bool,
+
+    /// Print one fixed-format identification line per slice (arch, filetype, UUID, build version) and exit
+    #[arg(long)]
+    identify: bool,
+
 }
 
 
@@ -145,23 +470,24 @@ fn display_arch(cputype: i32, cpusubtype: i32) -> (&'static str, &'static str) {
 
 fn fat_binary_user_decision<'a>(archs: &'a [fat::FatArch]) -> Result<&'a fat::FatArch, Box<dyn Error>> {
     // Prompt user if they want to analyze the Intel or Apple Silicon binary (or whichever of the `n`` binaries present)
-    println!("{}", "Available architectures:".green().bold());
+    // On stderr so the report alone goes down a pipe (or into --output)
+    eprintln!("{}", "Available architectures:".green().bold());
     for (i, arch) in archs.iter().enumerate() {
         match arch {
             fat::FatArch::Arch32(a) => {
                 let (cpu, sub) = display_arch(a.cputype, a.cpusubtype);
-                println!("{i}: {cpu} ({sub})");
+                eprintln!("{i}: {cpu} ({sub})");
             }
             fat::FatArch::Arch64(a) => {
                 let (cpu, sub) = display_arch(a.cputype, a.cpusubtype);
-                println!("{i}: {cpu} ({sub})");
+                eprintln!("{i}: {cpu} ({sub})");
             }
         }
     }
 
     use std::io::{self, Write};
-    print!("Select architecture index: ");
-    io::stdout().flush()?;
+    eprint!("Select architecture index: ");
+    io::stderr().flush()?;
 
     let mut input = String::new();
     io::stdin().read_line(&mut input)?;
@@ -171,492 +497,1015 @@ fn fat_binary_user_decision<'a>(archs: &'a [fat::FatArch]) -> Result<&'a fat::Fa
 }
 
 
-fn main() -> Result<(), Box<dyn Error>> {
-    // Parse CLI arguments
-    let cli = Cli::parse();
+impl InspectArgs {
+    // Only None when a subcommand ran instead, clap enforces it otherwise
+    fn binary(&self) -> &Path {
+        self.binary.as_deref().expect("BINARY is required without a subcommand")
+    }
+}
 
-    // Disable coloring if desired or if terminal isn't a TTY
-    if cli.no_color || !std::io::stdout().is_terminal() {
-        control::set_override(false);
+fn shown_path(cli: &InspectArgs) -> String {
+    if !cli.relative_paths {
+        return cli.binary().display().to_string();
     }
 
-    let report_opts = ReportOptions {
-        include_header: !cli.no_header,
-        include_segments: !cli.no_segments,
-        include_dylibs: !cli.no_dylibs,
-        include_rpaths: !cli.no_rpaths,
-        include_loadcmds: !cli.no_loadcmds,
-        include_symbols: !cli.no_symbols,
-        include_strings: !cli.no_strings,
-        include_fixups: !cli.no_fixups,
-    };
+    // A single file has no scan root, the working directory stands in for one
+    // Canonicalize both so ./x and /abs/x agree
+    match (std::fs::canonicalize(cli.binary()), std::env::current_dir().and_then(std::fs::canonicalize)) {
+        (Ok(path), Ok(root)) => utils::display_path(&path, &root),
+        _ => cli.binary().display().to_string(),
+    }
+}
+
+// The arch prompt blocks on stdin, so only show it when someone can actually answer it
+fn should_prompt_for_arch(cli: &InspectArgs) -> bool {
+    let no_prompt_env = std::env::var_os("MOSCOPE_NO_PROMPT").is_some_and(|v| !v.is_empty() && v != "0");
+
+    !cli.all_archs && !no_prompt_env && std::io::stdin().is_terminal()
+}
 
-    let min_len = cli.min_string_length;
-    let max_strings_count = cli.max_strings;
-    let max_symbols_count = cli.max_symbols;
+fn fat_arch_slice(arch: &fat::FatArch) -> header::MachOSlice {
+    match arch {
+        fat::FatArch::Arch32(a) => header::MachOSlice { offset: a.offset as u64, size: Some(a.size as u64) },
+        fat::FatArch::Arch64(a) => header::MachOSlice { offset: a.offset, size: Some(a.size) },
+    }
+}
 
-    // Read the entire file into memory
-    let data = std::fs::read(&cli.binary)
-        .map_err(|e| format!("failed to read '{}': {}", cli.binary.display(), e))?;
+// What every fast path starts from: one slice's header and load command table, nothing past it
+struct SliceContext<'a> {
+    data: &'a [u8],
+    slice: &'a header::MachOSlice,
+    header: header::ParsedMachOHeader,
+    load_commands: Vec<load_commands::LoadCommand>,
+    cputype: i32,
+    cpusubtype: i32,
+}
 
-    // Detect if fat/universal binary
-    let fat_header = fat::read_fat_header(&data).ok();
-    let is_fat = fat_header.is_some();
-    let is_json = cli.format == OutputFormat::Json;
+impl SliceContext<'_> {
+    fn is_be(&self) -> bool {
+        self.header.kind.is_be()
+    }
 
-    // Prepare architecture slices
-    let arch_slices: Vec<header::MachOSlice> = if let Some(fat_hdr) = &fat_header {
-        let archs = fat::read_fat_archs(&data, fat_hdr)?;
-        if let OutputFormat::Json = cli.format {
-            // If JSON, do all architectures automatically
-            archs.iter().map(|arch| match arch {
-                fat::FatArch::Arch32(a) => header::MachOSlice { offset: a.offset as u64, size: Some(a.size as u64) },
-                fat::FatArch::Arch64(a) => header::MachOSlice { offset: a.offset, size: Some(a.size) },
-            }).collect()
-        } else {
-            // Otherwise, prompt user for selection
-            let selected_arch = fat_binary_user_decision(&archs)?;
-            vec![match selected_arch {
-                fat::FatArch::Arch32(a) => header::MachOSlice { offset: a.offset as u64, size: Some(a.size as u64) },
-                fat::FatArch::Arch64(a) => header::MachOSlice { offset: a.offset, size: Some(a.size) },
-            }]
+    fn is_64(&self) -> bool {
+        self.header.kind.is_64()
+    }
+
+    // The cpu_type / cpu_subtype pair of the per-slice reports
+    fn cpu_type(&self) -> &'static str {
+        cpu_type_name(self.cputype)
+    }
+
+    fn cpu_subtype(&self) -> &'static str {
+        cpu_subtype_name(self.cputype, self.cpusubtype)
+    }
+
+    // The -arch spelling for one-string labels, arm64 and arm64e share a cputype
+    fn arch(&self) -> &'static str {
+        arch_name(self.cputype, self.cpusubtype)
+    }
+
+    fn find_command(&self, cmd: u32) -> Option<&load_commands::LoadCommand> {
+        self.load_commands.iter().find(|lc| lc.cmd == cmd)
+    }
+
+    fn segments(&self) -> Result<Vec<segments::ParsedSegment>, Box<dyn Error>> {
+        Ok(segments::parse_segments(self.data, &self.load_commands, self.is_be())?)
+    }
+}
+
+// Reads each slice's header + load commands and hands them to `f`, one result per slice in order
+fn for_each_slice<R>(data: &[u8], slices: &[header::MachOSlice], mut f: impl FnMut(&SliceContext) -> Result<R, Box<dyn Error>>) -> Result<Vec<R>, Box<dyn Error>> {
+    slices
+        .iter()
+        .map(|slice| {
+            let (header, load_commands) = load_commands::read_slice_load_commands(data, slice)?;
+            let (cputype, cpusubtype) = header.header.cpu();
+            f(&SliceContext { data, slice, header, load_commands, cputype, cpusubtype })
+        })
+        .collect()
+}
+
+fn print_uuids(cli: &InspectArgs, data: &[u8], slices: &[header::MachOSlice]) -> Result<(), Box<dyn Error>> {
+    // --uuid fast path: header + load command table only, no segments/symbols/strings
+    let reports = for_each_slice(data, slices, |ctx| {
+        let parsed_uuid = uuid::find_uuid(data, &ctx.load_commands)?;
+        Ok(uuid::build_uuid_report(ctx.cpu_type(), ctx.cpu_subtype(), parsed_uuid.as_ref()))
+    })?;
+
+    print_report(cli, &reports)?;
+
+    Ok(())
+}
+
+
+fn identify_slices(data: &[u8], slices: &[header::MachOSlice]) -> Result<Vec<IdentityReport>, Box<dyn Error>> {
+    // --identify fast path: header + LC_UUID + LC_BUILD_VERSION
+    for_each_slice(data, slices, |ctx| {
+        let parsed_uuid = uuid::find_uuid(data, &ctx.load_commands)?;
+        let build_version = build_version::find_build_version(data, &ctx.load_commands, ctx.is_be())?;
+
+        Ok(identify::build_identity_report(
+            ctx.cputype,
+            ctx.cpusubtype,
+            ctx.header.header.filetype(),
+            parsed_uuid.as_ref(),
+            build_version.as_ref(),
+        ))
+    })
+}
+
+fn print_identities(cli: &InspectArgs, data: &[u8], slices: &[header::MachOSlice]) -> Result<(), Box<dyn Error>> {
+    let reports = identify_slices(data, slices)?;
+
+    print_report(cli, &reports)?;
+
+    Ok(())
+}
+
+fn thin(arch: &str, input_path: &Path, output: &Path) -> Result<(), Box<dyn Error>> {
+    // `moscope thin <ARCH> <BINARY> -o <OUT>`
+    let data = compression::maybe_decompress(input::read_input(input_path, None)?)?;
+    let slice_bytes = fat::extract_slice(&data, arch)?;
+
+    // Reparse before writing, a slice that doesn't stand on its own is an error, not an output file
+    load_commands::read_slice_load_commands(slice_bytes, &header::MachOSlice { offset: 0, size: None })
+        .map_err(|e| format!("extracted {} slice is not a valid thin Mach-O: {}", arch, e))?;
+
+    std::fs::write(output, slice_bytes)?;
+    eprintln!("Wrote {} slice ({} bytes) to {}", arch, slice_bytes.len(), output.display());
+    Ok(())
+}
+
+fn merge(inputs: &[PathBuf], output: &Path) -> Result<(), Box<dyn Error>> {
+    // `moscope merge <BINARY>... -o <OUT>`
+    let mut thin_inputs = Vec::new();
+    for path in inputs {
+        let data = compression::maybe_decompress(input::read_input(path, None)?)?;
+        thin_inputs.push(data);
+    }
+    let slices: Vec<&[u8]> = thin_inputs.iter().map(Vec::as_slice).collect();
+    let fat_bytes = fat::build_fat(&slices)?;
+
+    // Reparse the whole container before writing it
+    let fat_hdr = fat::read_fat_header(&fat_bytes)?;
+    let archs = fat::read_fat_archs(&fat_bytes, &fat_hdr)?;
+    for arch in &archs {
+        load_commands::read_slice_load_commands(&fat_bytes, &fat_arch_slice(arch))
+            .map_err(|e| format!("merged {} slice does not reparse: {}", arch.name(), e))?;
+    }
+
+    std::fs::write(output, &fat_bytes)?;
+    let names: Vec<&str> = archs.iter().map(fat::FatArch::name).collect();
+    eprintln!("Wrote fat binary ({}, {} bytes) to {}", names.join(", "), fat_bytes.len(), output.display());
+    Ok(())
+}
+
+fn scan_directory(cli: &InspectArgs) -> Result<(), Box<dyn Error>> {
+    // --recursive: the --identify line for every Mach-O under BINARY, files that fail to parse are skipped with a warning
+    let mut reports = Vec::new();
+
+    // Paths come back as root.join(...), so they strip cleanly against the root as given
+    let root = cli.binary();
+    for path in input::find_macho_files(root, cli.max_depth.map(|d| d as usize))? {
+        // Same input handling as a single file, so .gz/.xz wrapped binaries are unwrapped here too
+        let identities = input::read_input(&path, None).and_then(compression::maybe_decompress).and_then(|data| {
+            let slices: Vec<header::MachOSlice> = match fat::read_fat_header(&data) {
+                Ok(fat_hdr) => fat::read_fat_archs(&data, &fat_hdr)?.iter().map(fat_arch_slice).collect(),
+                Err(e) if fat::has_fat_magic(&data) => return Err(e.into()),
+                Err(_) => vec![header::MachOSlice { offset: 0, size: None }],
+            };
+            identify_slices(&data, &slices)
+        });
+
+        // A fat file is kept if any of its slices is the wanted type, only those slices are listed
+        let identities = identities.map(|mut ids| {
+            if let Some(wanted) = &cli.filetype {
+                ids.retain(|id| identify::filetype_matches(&id.filetype, wanted));
+            }
+            ids
+        });
+
+        match identities {
+            Ok(identities) if identities.is_empty() && cli.filetype.is_some() => {}
+            Ok(identities) => reports.push(ScannedFileReport {
+                path: std::fs::canonicalize(&path).unwrap_or_else(|_| path.clone()).display().to_string(),
+                relative_path: utils::display_path(&path, root),
+                identities,
+            }),
+            Err(e) => eprintln!("{} {}: {}", "Skipping".yellow().bold(), path.display(), e),
         }
+    }
+
+    print_report(cli, &reports)?;
+
+    Ok(())
+}
+
+fn oso_reports(data: &[u8], slices: &[header::MachOSlice]) -> Result<Vec<OsoReport>, Box<dyn Error>> {
+    // --oso fast path: only LC_SYMTAB is read, everything else is skipped
+    for_each_slice(data, slices, |ctx| {
+        let mut entries = Vec::new();
+        if let Some(lc) = ctx.find_command(LC_SYMTAB) {
+            let cmd = symtab::read_symtab_command(data, lc, ctx.is_be())?;
+            symtab::validate_symtab(data.len(), &cmd, ctx.slice.offset, ctx.is_64())?;
+            // Only debug entries are kept so a huge symtab is never fully materialized here
+            let debug_symbols = symtab::SymbolIter::new(data, &cmd, ctx.slice.offset, ctx.is_64(), ctx.is_be())
+                .filter(|sym| sym.as_ref().map_or(true, |s| s.is_debug))
+                .collect::<Result<Vec<_>, _>>()?;
+            entries = symtab::collect_oso_entries(&debug_symbols);
+        }
+
+        Ok(symtab::build_oso_report(ctx.cpu_type(), ctx.cpu_subtype(), &entries))
+    })
+}
+
+fn export_load_commands(data: &[u8], slices: &[header::MachOSlice], path: &std::path::Path) -> Result<(), Box<dyn Error>> {
+    // --export-load-commands fast path: header + load command table only, streamed out one command at a time
+    let out: Box<dyn std::io::Write> = if input::is_stdin(path) {
+        Box::new(std::io::stdout().lock())
     } else {
-        vec![header::MachOSlice { offset: 0, size: None }]
+        Box::new(std::fs::File::create(path).map_err(|e| format!("failed to create {}: {}", path.display(), e))?)
     };
+    let mut exporter = load_commands::LoadCommandExporter::new(std::io::BufWriter::new(out))?;
+
+    for_each_slice(data, slices, |ctx| Ok(exporter.write_slice(ctx.arch(), &ctx.load_commands)?))?;
+
+    exporter.finish()?;
+    Ok(())
+}
+
+fn lc_map_reports(data: &[u8], slices: &[header::MachOSlice]) -> Result<Vec<LoadCommandMapReport>, Box<dyn Error>> {
+    // --lc-map fast path: the load command table plus segments (to catch commands spilling into section data)
+    for_each_slice(data, slices, |ctx| {
+        let parsed_segments = ctx.segments()?;
+
+        let region_start = ctx.slice.offset + ctx.header.header.size();
+        let sizeofcmds = ctx.header.header.sizeofcmds();
+        let diags = load_commands::validate_load_command_layout(&ctx.load_commands, ctx.slice.offset, region_start, sizeofcmds, &parsed_segments);
+
+        Ok(load_commands::build_lc_map_report(
+            ctx.cpu_type(),
+            ctx.cpu_subtype(),
+            &ctx.load_commands,
+            ctx.slice.offset,
+            region_start,
+            sizeofcmds,
+            &diags,
+        ))
+    })
+}
+
+fn objc_category_reports(data: &[u8], slices: &[header::MachOSlice]) -> Result<Vec<ObjCCategoriesReport>, Box<dyn Error>> {
+    objc_reports(data, slices, |ctx, cpu_type, cpu_subtype| objc::build_categories_report(cpu_type, cpu_subtype, &ctx.parse_categories()))
+}
+
+fn objc_protocol_reports(data: &[u8], slices: &[header::MachOSlice]) -> Result<Vec<ObjCProtocolsReport>, Box<dyn Error>> {
+    objc_reports(data, slices, |ctx, cpu_type, cpu_subtype| objc::build_protocols_report(cpu_type, cpu_subtype, &ctx.parse_protocols()))
+}
+
+fn objc_reports<R>(data: &[u8], slices: &[header::MachOSlice], build: impl Fn(&objc::ObjCContext, &str, &str) -> R) -> Result<Vec<R>, Box<dyn Error>> {
+    // --objc-* fast paths: segments for the VM image, plus symbols and classic binds to name bound classes
+    for_each_slice(data, slices, |ctx| {
+        let is_be = ctx.is_be();
+        let parsed_segments = ctx.segments()?;
+        let mut symbols = Vec::new();
+        let mut dyldinfo_cmd = None;
+        for lc in &ctx.load_commands {
+            match lc.cmd & !LC_REQ_DYLD {
+                LC_SYMTAB => symbols = symtab::parse_symbols(data, &symtab::read_symtab_command(data, lc, is_be)?, ctx.slice.offset, ctx.is_64(), is_be)?,
+                LC_DYLD_INFO => dyldinfo_cmd = Some(dyld::read_dyld_info_command(data, lc, is_be)?),
+                _ => {}
+            }
+        }
 
-    // Store ArchitectureReports and parsed structs for printing
-    // all_* is to handle the reports for BOTH slices 
-    let mut architecture_reports = Vec::new();
-    let mut all_parsed_headers = Vec::new();
-    let mut all_parsed_segments = Vec::new();
-    let mut all_parsed_dylibs = Vec::new();
-    let mut all_parsed_rpaths = Vec::new();
-    let mut all_load_commands = Vec::new();
-    let mut all_parsed_symbols: Vec<Vec<symtab::ParsedSymbol>> = Vec::new();
-    let mut all_parsed_strings: Vec<Vec<symtab::ParsedString>> = Vec::new();
-    let mut all_parsed_fixups: Vec<Vec<Fixup>> = Vec::new();
-
-    for slice in arch_slices {
-        // Read Mach-O header for this slice
-        let thin_header: header::ParsedMachOHeader = header::read_thin_header(&data, &slice)?;
-        all_parsed_headers.push(thin_header.header.clone());
-
-        // Determine header variant info
-        let (header_size, ncmds, word_size, is_be) = match &thin_header.header {
-            header::MachOHeader::Header32(h) => (
-                std::mem::size_of::<header::MachHeader32>(),
-                h.ncmds,
-                32,
-                thin_header.kind.is_be(),
-            ),
-            header::MachOHeader::Header64(h) => (
-                std::mem::size_of::<header::MachHeader64>(),
-                h.ncmds,
-                64,
-                thin_header.kind.is_be(),
-            ),
+        let vm_image = MachOMemoryImage::new(&parsed_segments, data, ctx.slice.offset);
+        let fixups = match &dyldinfo_cmd {
+            Some(dyldinfo) => Fixup::parse(dyldinfo, &parsed_segments, 0, &vm_image, data, ctx.slice.offset, ctx.is_64())?,
+            None => Vec::new(),
         };
 
-        let load_command_offset = slice.offset as usize + header_size;
-        let load_commands_vec = load_commands::read_load_commands(&data, load_command_offset as u32, ncmds, word_size, is_be)?;
+        let objc_ctx = objc::ObjCContext::new(&vm_image, &parsed_segments, ctx.cputype, ctx.cpusubtype, &fixups, &symbols);
+        Ok(build(&objc_ctx, ctx.cpu_type(), ctx.cpu_subtype()))
+    })
+}
 
-        let mut parsed_segments = Vec::new();
-        let mut parsed_dylibs = Vec::new();
-        let mut parsed_rpaths = Vec::new();
-        let mut parsed_symbols: Vec<symtab::ParsedSymbol> = Vec::new();
-        let mut parsed_strings = Vec::new();
-        let mut parsed_fixups: Vec<Fixup> = Vec::new();
-
-        // LC_SYMTAB doesn't contain symbols it just declares info
-        // So we need to keep track of it so we can get all the symbols
-        let mut symtab_cmd: Option<symtab::SymtabCommand> = None;
-        let mut dysymtab_cmd: Option<symtab::DYSymtabCommand> = None;
-        let mut dyldinfo_cmd: Option<dyld::DYLDInfoCommand> = None;
-
-        for lc in &load_commands_vec {
-            let base_cmd = lc.cmd & !LC_REQ_DYLD;
-
-            match base_cmd {
-                LC_ID_DYLIB
-                | LC_LOAD_DYLIB
-                | LC_LOAD_WEAK_DYLIB
-                | LC_REEXPORT_DYLIB
-                | LC_LAZY_LOAD_DYLIB
-                | LC_LOAD_UPWARD_DYLIB => {
-                    parsed_dylibs.push(dylibs::parse_dylib(&data, lc, is_be)?);
-                }
-                LC_RPATH => {
-                    parsed_rpaths.push(rpaths::parse_rpath(&data, lc, is_be)?);
-                }
-                LC_SEGMENT_64 => {
-                    parsed_segments.push(segments::parse_segment_64(&data, lc.offset as usize, is_be)?);
-                }
-                LC_SEGMENT => {
-                    parsed_segments.push(segments::parse_segment_32(&data, lc.offset as usize, is_be)?);
-                }
+fn initializer_reports(data: &[u8], slices: &[header::MachOSlice]) -> Result<Vec<InitializersReport>, Box<dyn Error>> {
+    // --initializers fast path: segments for the VM image, plus symbols to name the targets
+    for_each_slice(data, slices, |ctx| {
+        let parsed_segments = ctx.segments()?;
+        let mut symbols = Vec::new();
+        if let Some(lc) = ctx.find_command(LC_SYMTAB) {
+            symbols = symtab::parse_symbols(data, &symtab::read_symtab_command(data, lc, ctx.is_be())?, ctx.slice.offset, ctx.is_64(), ctx.is_be())?;
+        }
 
-                LC_SYMTAB => {
-                    let cmd = symtab::SymtabCommand {
-                        cmd: lc.cmd,
-                        cmdsize: lc.cmdsize,
-                        symoff: bytes_to(is_be, &data[lc.offset as usize + 8 .. lc.offset as usize + 12])?,
-                        nsyms: bytes_to(is_be, &data[lc.offset as usize + 12 .. lc.offset as usize + 16])?,
-                        stroff: bytes_to(is_be, &data[lc.offset as usize + 16 .. lc.offset as usize + 20])?,
-                        strsize: bytes_to(is_be, &data[lc.offset as usize + 20 .. lc.offset as usize + 24])?,
-                    };
-
-                    symtab_cmd = Some(cmd);   
-                }
+        let vm_image = MachOMemoryImage::new(&parsed_segments, data, ctx.slice.offset);
+        let inits = initializers::parse_initializers(&vm_image, &parsed_segments, ctx.cputype, ctx.cpusubtype, &symbols);
+        Ok(initializers::build_initializers_report(ctx.cpu_type(), ctx.cpu_subtype(), &inits))
+    })
+}
 
-                LC_DYSYMTAB => {
-                    let off = lc.offset as usize;
-                    let cmd = symtab::DYSymtabCommand {
-                        cmd: lc.cmd,
-                        cmdsize: lc.cmdsize,
-                        ilocalsym:       bytes_to(is_be, &data[off +  8 .. off + 12])?,
-                        nlocalsym:       bytes_to(is_be, &data[off + 12 .. off + 16])?,
-                        iextdefsym:      bytes_to(is_be, &data[off + 16 .. off + 20])?,
-                        nextdefsym:      bytes_to(is_be, &data[off + 20 .. off + 24])?,
-                        iundefsym:       bytes_to(is_be, &data[off + 24 .. off + 28])?,
-                        nundefsym:       bytes_to(is_be, &data[off + 28 .. off + 32])?,
-                        tocoff:          bytes_to(is_be, &data[off + 32 .. off + 36])?,
-                        ntoc:            bytes_to(is_be, &data[off + 36 .. off + 40])?,
-                        modtaboff:       bytes_to(is_be, &data[off + 40 .. off + 44])?,
-                        nmodtab:         bytes_to(is_be, &data[off + 44 .. off + 48])?,
-                        extrefsymoff:    bytes_to(is_be, &data[off + 48 .. off + 52])?,
-                        nextrefsyms:     bytes_to(is_be, &data[off + 52 .. off + 56])?,
-                        indirectsymoff:  bytes_to(is_be, &data[off + 56 .. off + 60])?,
-                        nindirectsyms:   bytes_to(is_be, &data[off + 60 .. off + 64])?,
-                        extreloff:       bytes_to(is_be, &data[off + 64 .. off + 68])?,
-                        nextrel:         bytes_to(is_be, &data[off + 68 .. off + 72])?,
-                        locreloff:       bytes_to(is_be, &data[off + 72 .. off + 76])?,
-                        nlocrel:         bytes_to(is_be, &data[off + 76 .. off + 80])?,
-                    };
-
-                    dysymtab_cmd = Some(cmd);
-                }
+fn export_reports(data: &[u8], slices: &[header::MachOSlice]) -> Result<Vec<ExportsReport>, Box<dyn Error>> {
+    // --exports / --reexports fast path: segments for the __TEXT base, dylib commands for the ordinals
+    // plus the export trie, nothing else
+    for_each_slice(data, slices, |ctx| {
+        let is_be = ctx.is_be();
+        let parsed_segments = ctx.segments()?;
 
+        let mut parsed_dylibs = Vec::new();
+        let mut slice_diagnostics = Vec::new();
+        let mut trie_range = None;
+        for lc in &ctx.load_commands {
+            match lc.cmd & !LC_REQ_DYLD {
+                LC_ID_DYLIB | LC_LOAD_DYLIB | LC_LOAD_WEAK_DYLIB | LC_REEXPORT_DYLIB | LC_LAZY_LOAD_DYLIB | LC_LOAD_UPWARD_DYLIB => {
+                    parsed_dylibs.push(dylibs::parse_dylib(data, lc, is_be)?);
+                }
+                LC_DYLD_EXPORTS_TRIE => {
+                    if let Some(cmd) = load_commands::parse_linkedit_data_or_diagnose(data, lc, is_be, &mut slice_diagnostics) {
+                        trie_range = Some((cmd.dataoff, cmd.datasize));
+                    }
+                }
                 LC_DYLD_INFO => {
-                    let off = lc.offset as usize;
-                    let cmd = dyld::DYLDInfoCommand {
-                        cmd: lc.cmd,
-                        cmdsize: lc.cmdsize,
-                        rebase_off: bytes_to(is_be, &data[off + 8 .. off + 12])?,
-                        rebase_size: bytes_to(is_be, &data[off + 12 .. off + 16])?,
-                        bind_off: bytes_to(is_be, &data[off + 16 .. off + 20])?,
-                        bind_size: bytes_to(is_be, &data[off + 20 .. off + 24])?,
-                        weak_bind_off: bytes_to(is_be, &data[off + 24 .. off + 28])?,
-                        weak_bind_size: bytes_to(is_be, &data[off + 28 .. off + 32])?,
-                        lazy_bind_off: bytes_to(is_be, &data[off + 32 .. off + 36])?,
-                        lazy_bind_size: bytes_to(is_be, &data[off + 36 .. off + 40])?,
-                        export_off: bytes_to(is_be, &data[off + 40 .. off + 44])?,
-                        export_size: bytes_to(is_be, &data[off + 44 .. off + 48])?,
-                    };
-
-                    dyldinfo_cmd = Some(cmd);
+                    let cmd = dyld::read_dyld_info_command(data, lc, is_be)?;
+                    trie_range = trie_range.or(Some((cmd.export_off, cmd.export_size)));
                 }
                 _ => {}
             }
         }
 
-        // now we take a look @ our symtab_cmd and parse symbols
-        if let Some(symtab) = symtab_cmd {
-            let sym_base = symtab.symoff as usize;
-            let stroff = slice.offset as usize + symtab.stroff as usize; // have to add the fat offset otherwise we just read garbage
-            let strsize = symtab.strsize as usize;
+        let exports = match trie_range {
+            Some((off, size)) => {
+                let start = ctx.slice.offset + off as u64;
+                let trie = data.get(start as usize..(start + size as u64) as usize)
+                    .ok_or_else(|| format!("export trie ({:#x}, {} bytes) runs past EOF", off, size))?;
+                export_trie::parse_export_trie(trie)?
+            }
+            None => Vec::new(),
+        };
+        let text_base = function_starts::text_vmaddr(&parsed_segments);
+        Ok(export_trie::build_exports_report(ctx.cpu_type(), ctx.cpu_subtype(), &exports, &parsed_dylibs, text_base, &slice_diagnostics))
+    })
+}
+
+fn reexport_reports(data: &[u8], slices: &[header::MachOSlice]) -> Result<Vec<ReexportsReport>, Box<dyn Error>> {
+    Ok(export_reports(data, slices)?.into_iter().map(ReexportsReport).collect())
+}
+
+// The CodeDirectories LC_CODE_SIGNATURE points at (none for an unsigned slice), plus why the command couldn't be read
+fn code_directories(ctx: &SliceContext) -> Result<(Vec<codesign::ParsedCodeDirectory>, Vec<diagnostics::Diagnostic>), Box<dyn Error>> {
+    let mut directories = Vec::new();
+    let mut slice_diagnostics = Vec::new();
+    let code_signature = ctx.find_command(LC_CODE_SIGNATURE)
+        .and_then(|lc| load_commands::parse_linkedit_data_or_diagnose(ctx.data, lc, ctx.is_be(), &mut slice_diagnostics));
+    if let Some(cmd) = code_signature {
+        directories = codesign::read_code_signature(ctx.data, ctx.slice.offset, &cmd)?;
+    }
+    Ok((directories, slice_diagnostics))
+}
 
-            // report up to N symbols where N is defined by the --max_symbols flag
-            for i in 0..symtab.nsyms {
+fn code_signature_reports(data: &[u8], slices: &[header::MachOSlice]) -> Result<Vec<CodeSignatureReport>, Box<dyn Error>> {
+    // --code-signature fast path: LC_CODE_SIGNATURE and the blob it points at, nothing else
+    for_each_slice(data, slices, |ctx| {
+        let (directories, slice_diagnostics) = code_directories(ctx)?;
+        Ok(codesign::build_code_signature_report(ctx.cpu_type(), ctx.cpu_subtype(), &directories, &slice_diagnostics))
+    })
+}
 
-                let size = if thin_header.kind.is_64() {
-                    symtab::NList64::SIZE
-                } else {
-                    symtab::NList32::SIZE
-                };
+fn signature_check_reports(data: &[u8], slices: &[header::MachOSlice]) -> Result<Vec<SignatureCheckReport>, Box<dyn Error>> {
+    // --check-signature fast path: the --code-signature parse, then the page hashes against the slice bytes
+    for_each_slice(data, slices, |ctx| {
+        let (directories, slice_diagnostics) = code_directories(ctx)?;
+        let bytes = hash::slice_bytes(data, ctx.slice)?;
+        Ok(codesign::build_signature_check_report(ctx.cpu_type(), ctx.cpu_subtype(), bytes, &directories, &slice_diagnostics))
+    })
+}
 
-                let offset = slice.offset as usize + sym_base + (i as usize) * size; // have to add the fat offset otherwise we just read garbage
+fn literal_reports(data: &[u8], slices: &[header::MachOSlice]) -> Result<Vec<LiteralsReport>, Box<dyn Error>> {
+    // --literals fast path: segments only, literal sections are read straight from the file
+    for_each_slice(data, slices, |ctx| {
+        let parsed_segments = ctx.segments()?;
+        Ok(sections::build_literals_report(ctx.cpu_type(), ctx.cpu_subtype(), parsed_segments.iter().flat_map(|seg| &seg.sections), data, ctx.slice.offset))
+    })
+}
 
-                let symbol = if thin_header.kind.is_64() {
-                    let nlist = symtab::NList64::parse(&data, offset, is_be)?;
-                    symtab::ParsedSymbol::from_nlist64(nlist, &data, stroff, strsize)
-                } else {
-                    let nlist = symtab::NList32::parse(&data, offset, is_be)?;
-                    symtab::ParsedSymbol::from_nlist32(nlist, &data, stroff, strsize)
-                };
+fn linkedit_reports(data: &[u8], slices: &[header::MachOSlice]) -> Result<Vec<LinkEditOverviewReport>, Box<dyn Error>> {
+    // --linkedit fast path: just the linkedit_data_command table, none of the blobs are read
+    for_each_slice(data, slices, |ctx| {
+        let mut slice_diagnostics = Vec::new();
+        let linkedit_data: Vec<_> = ctx.load_commands
+            .iter()
+            .filter(|lc| load_commands::is_linkedit_data_command(lc.cmd))
+            .filter_map(|lc| load_commands::parse_linkedit_data_or_diagnose(data, lc, ctx.is_be(), &mut slice_diagnostics))
+            .collect();
+
+        Ok(load_commands::build_linkedit_overview(
+            ctx.cpu_type(),
+            ctx.cpu_subtype(),
+            ctx.slice.offset,
+            &linkedit_data,
+            &slice_diagnostics,
+        ))
+    })
+}
 
-                parsed_symbols.push(symbol);
+fn function_starts_reports(data: &[u8], slices: &[header::MachOSlice]) -> Result<Vec<FunctionStartsReport>, Box<dyn Error>> {
+    // --function-starts fast path: __TEXT's vmaddr and the one linkedit_data command
+    for_each_slice(data, slices, |ctx| {
+        let parsed_segments = ctx.segments()?;
+        let starts = match ctx.find_command(LC_FUNCTION_STARTS) {
+            Some(lc) => {
+                let linkedit = load_commands::parse_linkedit_data(data, lc, ctx.is_be())?;
+                function_starts::parse_function_starts(data, &linkedit, ctx.slice.offset, function_starts::text_vmaddr(&parsed_segments))
             }
+            None => Vec::new(),
+        };
+
+        Ok(function_starts::build_function_starts_report(ctx.cpu_type(), ctx.cpu_subtype(), &starts))
+    })
+}
+
+fn appended_data_reports(data: &[u8], slices: &[header::MachOSlice], threshold: u64) -> Result<Vec<AppendedDataReport>, Box<dyn Error>> {
+    // --detect-appended fast path: segments and linkedit_data commands, only their file ranges matter
+    for_each_slice(data, slices, |ctx| {
+        let parsed_segments = ctx.segments()?;
+        let linkedit_data = ctx.load_commands
+            .iter()
+            .filter(|lc| load_commands::is_linkedit_data_command(lc.cmd))
+            .map(|lc| load_commands::parse_linkedit_data(data, lc, ctx.is_be()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(segments::find_appended_data(
+            ctx.cpu_type(),
+            ctx.cpu_subtype(),
+            &parsed_segments,
+            &linkedit_data,
+            ctx.slice.offset,
+            hash::slice_bytes(data, ctx.slice)?.len() as u64,
+            threshold,
+        ))
+    })
+}
+
+fn print_section_info(cli: &InspectArgs, data: &[u8], slices: &[header::MachOSlice], spec: &str) -> Result<(), Box<dyn Error>> {
+    // --section-info fast path: segments + LC_DYSYMTAB (for the indirect table size) only
+    let (segname, sectname) = spec
+        .split_once(',')
+        .map(|(seg, sect)| (seg.trim(), sect.trim()))
+        .filter(|(seg, sect)| !seg.is_empty() && !sect.is_empty())
+        .ok_or_else(|| format!("--section-info expects SEG,SECT (e.g. __TEXT,__text), got '{}'", spec))?;
+
+    let found = for_each_slice(data, slices, |ctx| {
+        let parsed_segments = ctx.segments()?;
+        let mut nindirectsyms = None;
+        if let Some(lc) = ctx.find_command(LC_DYSYMTAB) {
+            nindirectsyms = Some(symtab::read_dysymtab_command(data, lc, ctx.is_be())?.nindirectsyms);
         }
 
-        // now for indirect symbols ingestion
-        let mut indirect_symbols: Option<Vec<u32>> = None;
-        if let Some(dysym) = &dysymtab_cmd {
-            let base = slice.offset as usize + dysym.indirectsymoff as usize;
+        Ok(segments::find_section(&parsed_segments, segname, sectname)
+            .map(|section| section.build_info_report(ctx.cpu_type(), ctx.cpu_subtype(), ctx.is_64(), nindirectsyms)))
+    })?;
 
-            let mut table = Vec::with_capacity(dysym.nindirectsyms as usize);
+    // A fat binary only needs the section in one of its slices
+    let reports: Vec<_> = found.into_iter().flatten().collect();
+    if reports.is_empty() {
+        return Err(format!("section {},{} not found", segname, sectname).into());
+    }
 
-            for i in 0..dysym.nindirectsyms {
-                let off = base + (i as usize * 4);
-                let idx: u32 = bytes_to(is_be, &data[off..off+4])?;
-                table.push(idx);
-            }
+    print_report(cli, &reports)?;
 
-            indirect_symbols = Some(table);
-        }
+    Ok(())
+}
 
-        // Strings extraction using the vm addressing instead of file offsets
-        //      because our file offsets method fails for dyld extracted binaries
-        
-        // Build VM image once per slice
-        let vm_image = MachOMemoryImage::new(&parsed_segments, &data, slice.offset);
-
-        // Before building report grab the strings
-        // Iterate only __cstring sections; each byte is scanned once
-        // Real cost of this is not O(n^3) like I thought but it's actually roughly O(C + B + K)
-        // C = total number of sections across all segments
-        // B = total bytes scanned in __cstring
-        // K = number of extracted strings
-        for segment in &parsed_segments {
-            for section in &segment.sections {
-                // Check if we should skip this section
-                if let Some(ref skip) = cli.skip_sections {
-                    let sectname = byte_array_to_string(&section.sectname);
-                    if skip.iter().any(|s| sectname == *s) {
-                        continue;
-                    }
-                }
+fn print_search(cli: &InspectArgs, data: &[u8], slices: &[header::MachOSlice]) -> Result<(), Box<dyn Error>> {
+    // --find-string / --find-hex fast path: segments for the VM image, nothing else
+    let hex_pattern = cli.find_hex.as_deref().map(search::parse_hex_bytes).transpose()?;
+    let needle = cli.find_string.clone().or_else(|| cli.find_hex.clone()).unwrap_or_default();
 
-                // Check if we should only process specific sections
-                if let Some(ref only) = cli.string_sections {
-                    let sectname = byte_array_to_string(&section.sectname);
-                    if !only.iter().any(|s| sectname == *s) {
-                        continue;
-                    }
-                }
+    let reports = for_each_slice(data, slices, |ctx| {
+        let parsed_segments = ctx.segments()?;
 
-                if section.kind == SectionKind::CString && section.size > 0 {
-                    if let Some(sec_bytes) = vm_image.read_section(section) {
-                        // Use filtered extraction if pattern provided, otherwise normal
-                        let extracted_strings = if let Some(ref pattern) = cli.string_pattern {
-                            match symtab::extract_filtered_strings(sec_bytes, pattern) {
-                                Ok(strings) => strings,
-                                Err(e) => {
-                                    eprintln!("Invalid regex pattern '{}': {}", pattern, e);
-                                    Vec::new()
-                                }
-                            }
-                        } else {
-                            symtab::extract_strings(sec_bytes, min_len)
-                        };
-                        
-                        // Attach section info to string
-                        for s in extracted_strings {
-                            if s.is_empty() { continue; }
-                            parsed_strings.push(symtab::ParsedString {
-                                value: s,
-                                segname: segment.segname.clone(),
-                                sectname: section.sectname.clone(),
-                            });
-                        }
-                    }
-                }
+        let vm_image = MachOMemoryImage::new(&parsed_segments, data, ctx.slice.offset);
+        let mut hits = Vec::new();
+        for section in parsed_segments.iter().flat_map(|seg| &seg.sections) {
+            let Some(bytes) = vm_image.read_section(section) else { continue };
 
-                // If this section uses indirect symbols
-                if let (Some(indirect), Some(_dysym)) = (&indirect_symbols, &dysymtab_cmd) {
-                    if section.kind.uses_indirect_symbols() {
-                        let start = section.reserved1 as usize;
-                        let entry_size = if section.reserved2 != 0 {
-                            section.reserved2 as usize
-                        } else {
-                            8 // arm64 defualt pointer/stub size
-                        };
-
-                        let count = (section.size as usize) / entry_size; 
-
-                        let end = (start + count).min(indirect.len());
-
-                        if start >= indirect.len() {
-                            continue; // section is bogus? metadata incorrect? 
-                        }
-
-                        // Alright we have some new bounds checking here
-                        // When testing on our sample binaries, nothing was wrong
-                        // But one real binary on my mac panicked with:
-                        //      index out of bounds: the len is 2349 but the index is 2349
-                        // count --> What the section claims it needs (derived, anyway)
-                        // max_count --> how many entries actually exist from `start` to the end of the indirect table
-                        // safe_count --> the smaller of the two
-                        let max_count = indirect.len() - start;
-                        let safe_count = count.min(max_count);
-                        for i in 0..safe_count {
-                            let raw = indirect[start + i];
-
-                            let flags = raw & (INDIRECT_SYMBOL_ABS | INDIRECT_SYMBOL_LOCAL);
-                            if flags != 0 {
-                                continue;
-                            }
-
-                            let indirect_index = (raw & !(INDIRECT_SYMBOL_ABS | INDIRECT_SYMBOL_LOCAL)) as usize;
-
-                            if indirect_index >= parsed_symbols.len() {
-                                continue;
-                            }
-
-                            let sym = &mut parsed_symbols[indirect_index];
-
-                            sym.indirect_sect = Some(byte_array_to_string(&section.sectname));
-                            sym.segname = Some(byte_array_to_string(&section.segname));
-                            sym.indirect_addr = Some(section.addr + (i as u64) * entry_size as u64); // now the undefined symbols can have an address like otool -Iv
-                            
-                            if sym.kind == symtab::SymbolKind::Undefined && sym.is_external {
-                                sym.kind = match byte_array_to_string(&section.sectname).as_str() {
-                                    "__la_symbol_ptr" => symtab::SymbolKind::Lazy,
-                                    "__stubs"         => symtab::SymbolKind::Stub,
-                                    "__got"           => symtab::SymbolKind::Got,
-                                    _                 => sym.kind,
-                                };
-                            }
-                        }
-                    }
+            match (&cli.find_string, &hex_pattern) {
+                (Some(needle), _) if search::is_string_section(section) => {
+                    hits.extend(search::find_string_in_section(section, bytes, needle, ctx.slice.offset));
                 }
-                
+                (None, Some(pattern)) => hits.extend(search::find_bytes_in_section(section, bytes, pattern, ctx.slice.offset)),
+                _ => {}
             }
         }
 
-        
-        let mut global_sect_index: u8 = 1;
-        // Put the section data into the hashmap 
-        let mut section_map = HashMap::new();
-        for segment in &parsed_segments {
-            for section in &segment.sections {
-                section_map.insert(global_sect_index, (
-                    byte_array_to_string(&segment.segname),
-                    byte_array_to_string(&section.sectname),
-                ));
-                global_sect_index += 1;
-            }
+        Ok(search::build_search_report(ctx.cpu_type(), ctx.cpu_subtype(), &needle, &hits))
+    })?;
+
+    print_report(cli, &reports)?;
+
+    Ok(())
+}
+
+fn print_byte_scan(cli: &InspectArgs, data: &[u8], slices: &[header::MachOSlice], spec: &str) -> Result<(), Box<dyn Error>> {
+    // --scan-bytes fast path: segments of every slice, only to map matches back to a location
+    let pattern = search::parse_byte_pattern(spec)?;
+    let layouts = for_each_slice(data, slices, |ctx| {
+        Ok(search::SliceLayout {
+            arch: ctx.arch().to_string(),
+            slice_offset: ctx.slice.offset,
+            slice_size: ctx.slice.size,
+            segments: ctx.segments()?,
+        })
+    })?;
+
+    let mut matches = Vec::new();
+    for (start, end) in search::scan_ranges(&layouts, cli.scan_in.as_deref(), data.len() as u64)? {
+        for pos in search::scan_pattern(&data[start as usize..end as usize], &pattern) {
+            matches.push(search::locate_file_offset(start + pos as u64, &layouts));
         }
+    }
 
-        // Use the hashmap to map symbols to the segments/sections they live in 
-        // I am using the hashmap because the other way I first thought was going to be quadratic time complexity
-        // This should be closer to linear
-        for sym in &mut parsed_symbols {
-            if let Some(idx) = sym.section.map(|s| s.0) {
-                if let Some((segname, sectname)) = section_map.get(&idx) {
-                    sym.segname = Some(segname.clone());   // String
-                    sym.sectname = Some(sectname.clone()); // String
-                }
+    let report = search::build_scan_report(spec, cli.scan_in.as_deref(), &matches);
+    print_report(cli, &report)?;
+
+    Ok(())
+}
+
+fn print_bytes_at(cli: &InspectArgs, data: &[u8], fat_archs: Option<&[fat::FatArch]>, offset: u64, len: u64) -> Result<(), Box<dyn Error>> {
+    // --bytes-at fast path: nothing gets parsed, --arch only decides what the offset is relative to
+    let file_len = data.len() as u64;
+    let (region_start, region_len) = match (cli.arch.as_deref(), fat_archs) {
+        (None, _) => (0, file_len),
+        (Some(arch), Some(archs)) => {
+            let Some(found) = archs.iter().find(|a| a.name() == arch) else {
+                let available: Vec<&str> = archs.iter().map(fat::FatArch::name).collect();
+                return Err(format!("architecture '{}' not found in fat binary (available: {})", arch, available.join(", ")).into());
+            };
+            // a slice claiming more than the file has only gets what's actually there
+            (found.offset(), found.size().min(file_len.saturating_sub(found.offset())))
+        }
+        (Some(arch), None) => {
+            // thin file, the whole thing is the slice as long as it's the right one
+            let thin_header = header::read_thin_header(data, &header::MachOSlice { offset: 0, size: None })?;
+            let (cputype, cpusubtype) = thin_header.header.cpu();
+            if arch_name(cputype, cpusubtype) != arch {
+                return Err(format!("architecture '{}' not found (thin {} binary)", arch, arch_name(cputype, cpusubtype)).into());
             }
+            (0, file_len)
         }
+    };
+
+    let (start, end) = search::peek_range(region_start, region_len, offset, len)?;
+    let report = search::build_peek_report(cli.arch.as_deref(), offset, start, &data[start as usize..end as usize]);
+    print_report(cli, &report)
+}
+
+fn unknown_sections_report(data: &[u8], slices: &[header::MachOSlice]) -> Result<UnknownSectionsReport, Box<dyn Error>> {
+    // --report-unknown-sections fast path: segments only, one deduplicated list for the whole file
+    let parsed_segments: Vec<_> = for_each_slice(data, slices, |ctx| ctx.segments())?.into_iter().flatten().collect();
+
+    Ok(sections::build_unknown_sections_report(parsed_segments.iter().flat_map(|seg| &seg.sections)))
+}
+
+fn check_build_requirements(cli: &InspectArgs, data: &[u8], slices: &[header::MachOSlice]) -> Result<Vec<String>, Box<dyn Error>> {
+    // Runs over every slice, a fat binary only passes if all of its architectures do
+    let failures = for_each_slice(data, slices, |ctx| {
+        let build_version = build_version::find_build_version(data, &ctx.load_commands, ctx.is_be())?;
+        let slice_failures = build_version::check_requirements(
+            build_version.as_ref(),
+            cli.require_platform.as_deref(),
+            cli.require_min_os.as_deref(),
+        );
+
+        Ok(slice_failures.into_iter().map(|f| format!("{}: {}", ctx.arch(), f)).collect::<Vec<_>>())
+    })?;
 
-        // Apply fixups for this slice
-        if let Some(dyldinfo) = &dyldinfo_cmd {
-            parsed_fixups = Fixup::parse( 
-                dyldinfo,
-                &parsed_segments,
-                &parsed_symbols,
-                0, // slide
-                &vm_image,
-                &data,
-            )?;
+    Ok(failures.into_iter().flatten().collect())
+}
+
+
+fn json_writer(cli: &InspectArgs) -> JsonWriter {
+    JsonWriter { indent: cli.json_indent, canonical: cli.canonical_json }
+}
+
+fn text_writer(cli: &InspectArgs) -> TextWriter {
+    TextWriter {
+        options: TextOptions {
+            count_sections_by_kind: cli.count_sections_by_kind,
+            // the legend explains colors, without them it's just noise
+            legend: cli.legend && control::SHOULD_COLORIZE.should_colorize(),
+            canonical_names: cli.canonical_name,
+            strip_underscore: cli.strip_underscore,
+            min_string_length: cli.min_string_length,
+            section_min_lengths: cli.section_min_length.clone(),
+            max_strings: cli.max_strings,
+            path: shown_path(cli),
+        },
+    }
+}
+
+// Every report goes out through here, the full MachOReport and the fast path ones alike
+fn output_writer<R: serde::Serialize + TextReport + ?Sized>(cli: &InspectArgs) -> Result<Box<dyn OutputWriter<R>>, Box<dyn Error>> {
+    Ok(match cli.format {
+        OutputFormat::Text => Box::new(text_writer(cli)),
+        OutputFormat::Json => Box::new(json_writer(cli)),
+        OutputFormat::Plist => Box::new(PlistWriter),
+        // only the full report has tables to flatten / dylibs to draw, that one goes through print_macho_report
+        OutputFormat::Csv | OutputFormat::Dot | OutputFormat::Markdown => {
+            let name = cli.format.to_possible_value().map(|value| value.get_name().to_string()).unwrap_or_default();
+            return Err(format!("--format {} only applies to the full report, use json or plist for this output", name).into());
         }
+    })
+}
 
-        // Before building the architecture report, apply max limit if specified
-        if let Some(max) = max_strings_count {
-            parsed_strings.truncate(max);
+// stdout, or the --output file
+fn output_sink(cli: &InspectArgs) -> Result<Box<dyn std::io::Write>, Box<dyn Error>> {
+    Ok(match &cli.output {
+        Some(path) if !input::is_stdin(path) => {
+            let file = std::fs::File::create(path).map_err(|e| format!("failed to create {}: {}", path.display(), e))?;
+            Box::new(std::io::BufWriter::new(file))
         }
+        _ => Box::new(std::io::stdout().lock()),
+    })
+}
 
-        if !cli.include_debug_symbols {  // Take out debug symbols
-            parsed_symbols.retain(|sym| !sym.is_debug);
+fn print_report<R: serde::Serialize + TextReport + ?Sized>(cli: &InspectArgs, report: &R) -> Result<(), Box<dyn Error>> {
+    let writer = output_writer(cli)?;
+    let mut out = output_sink(cli)?;
+    writer.write_report(report, &mut out)?;
+    out.flush()?;
+    Ok(())
+}
+
+fn print_macho_report(cli: &InspectArgs, report: &MachOReport) -> Result<(), Box<dyn Error>> {
+    let writer: Box<dyn OutputWriter<MachOReport>> = match cli.format {
+        OutputFormat::Csv => Box::new(CsvWriter { table: cli.csv_table, options: text_writer(cli).options }),
+        OutputFormat::Dot => {
+            let name = cli.binary().file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_else(|| shown_path(cli));
+            Box::new(DotWriter { name })
         }
+        OutputFormat::Markdown => Box::new(MarkdownWriter { options: text_writer(cli).options }),
+        _ => return print_report(cli, report),
+    };
+    let mut out = output_sink(cli)?;
+    writer.write_report(report, &mut out)?;
+    out.flush()?;
+    Ok(())
+}
+
+
+fn main() -> Result<(), Box<dyn Error>> {
+    // Parse CLI arguments
+    let cli = Cli::parse();
+
+    let result = match cli.command {
+        Some(Command::Thin { arch, input, output }) => thin(&arch, &input, &output),
+        Some(Command::Merge { inputs, output }) => merge(&inputs, &output),
+        Some(Command::Inspect(args)) => inspect(args),
+        Some(Command::Diff { args, other }) => diff(args, &other),
+        Some(Command::Verify(args)) => verify(args),
+        None => inspect(cli.inspect),
+    };
 
-        if let Some(limit) = max_symbols_count {
-            parsed_symbols.truncate(limit);
+    // `Error: ...` is printed with Debug, a MoscopeError would show up as `TooSmall { .. }` instead of its message
+    result.map_err(|e| e.to_string().into())
+}
+
+// What every report printing subcommand does before it looks at the binary
+fn prepare_output(cli: &mut InspectArgs) {
+    // --canonical-json is just JSON with a stricter serializer
+    if cli.canonical_json {
+        cli.format = OutputFormat::Json;
+    }
+
+    // Disable coloring if desired, if terminal isn't a TTY or if the report goes to a file
+    let to_file = cli.output.as_deref().is_some_and(|path| !input::is_stdin(path));
+    if cli.no_color || to_file || !std::io::stdout().is_terminal() {
+        control::set_override(false);
+    }
+}
+
+fn report_options(cli: &InspectArgs) -> ReportOptions {
+    ReportOptions {
+        include_header: !cli.no_header,
+        include_segments: !cli.no_segments,
+        include_dylibs: !cli.no_dylibs,
+        include_rpaths: !cli.no_rpaths,
+        include_loadcmds: !cli.no_loadcmds,
+        include_symbols: !cli.no_symbols,
+        include_strings: !cli.no_strings,
+        include_fixups: !cli.no_fixups,
+        include_diagnostics: !cli.no_diagnostics,
+        dump_unknown_load_commands: cli.dump_unknown_lc,
+    }
+}
+
+fn slice_options(cli: &InspectArgs) -> analysis::SliceOptions {
+    analysis::SliceOptions {
+        no_vm_image: cli.no_vm_image,
+        min_string_length: cli.min_string_length,
+        section_min_lengths: cli.section_min_length.clone(),
+        string_sections: cli.string_sections.clone(),
+        skip_sections: cli.skip_sections.clone(),
+        string_pattern: cli.string_pattern.clone(),
+        ascii_only: cli.ascii_only,
+        raw_strings: cli.raw_strings,
+        dedup_rpaths: cli.dedup_rpaths,
+        max_strings: cli.max_strings,
+        debug_symbols_only: cli.debug_symbols_only,
+        include_debug_symbols: cli.include_debug_symbols,
+        max_symbols: cli.max_symbols,
+    }
+}
+
+// Unwrapped Mach-O bytes plus the fat archs (None for a thin file)
+fn load_binary(path: &Path, expected_size: Option<usize>) -> Result<(Vec<u8>, Option<Vec<fat::FatArch>>), Box<dyn Error>> {
+    // Read the entire file (or stdin for `-`) into memory
+    let data = input::read_input(path, expected_size)?;
+
+    // .gz / .xz wrapped binaries get unwrapped here, everything below sees plain Mach-O bytes
+    let data = compression::maybe_decompress(data)?;
+    input::validate_magic(&data)?;
+
+    let fat_archs = analysis::detect_fat_archs(&data)?;
+    Ok((data, fat_archs))
+}
+
+// Parse the given slices and put the full MachOReport together, with this invocation's options
+fn build_full_report(
+    cli: &InspectArgs,
+    data: &[u8],
+    fat_archs: Option<&[fat::FatArch]>,
+    arch_slices: Vec<header::MachOSlice>,
+) -> Result<(Vec<analysis::ParsedSlice>, MachOReport), Box<dyn Error>> {
+    analysis::analyze_slices(data, fat_archs, arch_slices, &slice_options(cli), &report_options(cli))
+}
+
+// --all: a failing optional analysis costs its own section, not the whole dump
+fn optional_analysis<T>(name: &str, result: Result<T, Box<dyn Error>>) -> Option<T> {
+    match result {
+        Ok(report) => Some(report),
+        Err(e) => {
+            eprintln!("{} {} skipped: {}", "Warning:".yellow().bold(), name, e);
+            None
         }
-        
-        // Build architecture report for JSON
-        let arch_report = build_architecture_report(
-            match &thin_header.header {
-                header::MachOHeader::Header32(h) => h.cputype,
-                header::MachOHeader::Header64(h) => h.cputype,
-            },
-            match &thin_header.header {
-                header::MachOHeader::Header32(h) => h.cpusubtype,
-                header::MachOHeader::Header64(h) => h.cpusubtype,
-            },
-            &thin_header.header,
-            &load_commands_vec,
-            &parsed_segments,
-            &parsed_dylibs,
-            &parsed_rpaths,
-            &parsed_symbols,
-            &parsed_strings,
-            &parsed_fixups,
-            is_json,
-            &report_opts,
-        );
+    }
+}
 
-        architecture_reports.push(arch_report);
-        all_parsed_segments.push(parsed_segments);
-        all_parsed_dylibs.push(parsed_dylibs);
-        all_parsed_rpaths.push(parsed_rpaths);
-        all_load_commands.push(load_commands_vec);
-        all_parsed_symbols.push(parsed_symbols);
-        all_parsed_strings.push(parsed_strings);
-        all_parsed_fixups.push(parsed_fixups);
-        
-        // end of this slice
-    }
-
-    // Build final MachOReport
-    let macho_report = build_macho_report(is_fat, architecture_reports);
-
-    // Now output
-    match cli.format {
-        OutputFormat::Text => {
-            println!("{}", "Mach-O Report:".green().bold());
-            for i in 0..macho_report.architectures.len() {
-                let header = &all_parsed_headers[i]; 
-                let segments = &all_parsed_segments[i];
-                let dylibs = &all_parsed_dylibs[i];
-                let rpaths = &all_parsed_rpaths[i];
-                let load_cmds = &all_load_commands[i];
-                let symbols = &all_parsed_symbols[i];
-                let strings = &all_parsed_strings[i];
-
-                if !cli.no_header {
-                    header::print_header_summary(header);
-                }
-                if !cli.no_segments {
-                    segments::print_segments_summary(segments);
-                }
-                if !cli.no_dylibs {
-                    dylibs::print_dylibs_summary(dylibs);
-                }
-                if !cli.no_rpaths {
-                    rpaths::print_rpaths_summary(rpaths);
-                }
-                if !cli.no_loadcmds {
-                    load_commands::print_load_commands(load_cmds);
-                }
-                if !cli.no_symbols {
-                    symtab::print_symbols_summary(symbols);
-                }
-                if !cli.no_strings {
-                    symtab::print_strings_summary(strings, min_len, max_strings_count);
-                }
+fn print_everything(cli: &InspectArgs, data: &[u8], fat_archs: Option<&[fat::FatArch]>, slices: Vec<header::MachOSlice>) -> Result<(), Box<dyn Error>> {
+    // Every slice, no prompt, the optional analyses each run over all of them too
+    let report = AllReport {
+        lc_maps: optional_analysis("--lc-map", lc_map_reports(data, &slices)),
+        linkedit: optional_analysis("--linkedit", linkedit_reports(data, &slices)),
+        code_signatures: optional_analysis("--code-signature", code_signature_reports(data, &slices)),
+        signature_checks: optional_analysis("--check-signature", signature_check_reports(data, &slices)),
+        initializers: optional_analysis("--initializers", initializer_reports(data, &slices)),
+        exports: optional_analysis("--exports", export_reports(data, &slices)),
+        reexports: optional_analysis("--reexports", reexport_reports(data, &slices)),
+        objc_categories: optional_analysis("--objc-categories", objc_category_reports(data, &slices)),
+        objc_protocols: optional_analysis("--objc-protocols", objc_protocol_reports(data, &slices)),
+        oso: optional_analysis("--oso", oso_reports(data, &slices)),
+        unknown_sections: optional_analysis("--report-unknown-sections", unknown_sections_report(data, &slices)),
+        literals: optional_analysis("--literals", literal_reports(data, &slices)),
+        function_starts: optional_analysis("--function-starts", function_starts_reports(data, &slices)),
+        appended_data: optional_analysis("--detect-appended", appended_data_reports(data, &slices, cli.appended_threshold)),
+        report: build_full_report(cli, data, fat_archs, slices)?.1,
+    };
 
-                if !cli.no_fixups {
-                    dyld::print_fixups_summary(&all_parsed_fixups[i]);
+    print_report(cli, &report)
+}
+
+// --timings goes to stderr so the report itself stays byte for byte the same
+fn print_timings(timer: &utils::PhaseTimer, parsed_slices: &[analysis::ParsedSlice]) {
+    let ms = |d: std::time::Duration| d.as_secs_f64() * 1000.0;
+
+    eprintln!("{}", "Timings".green().bold());
+    eprintln!("----------------------------------------");
+    for (phase, took) in &timer.phases {
+        eprintln!("{:<40} {:>10.3} ms", phase, ms(*took));
+        if *phase == utils::PhaseTimer::PARSE_SLICES {
+            // the per-slice breakdown of that line
+            for parsed in parsed_slices {
+                let (cputype, cpusubtype) = parsed.header.header.cpu();
+                eprintln!("  {}", arch_name(cputype, cpusubtype).dimmed());
+                for (slice_phase, slice_took) in &parsed.timings.phases {
+                    eprintln!("    {:<36} {:>10.3} ms", slice_phase, ms(*slice_took));
                 }
             }
         }
-        OutputFormat::Json => {
-            let json = serde_json::to_string_pretty(&macho_report)?;
-            println!("{}", json);
+    }
+    let total: std::time::Duration = timer.phases.iter().map(|(_, took)| *took).sum();
+    eprintln!("{:<40} {:>10.3} ms", "total", ms(total));
+    eprintln!("----------------------------------------");
+}
+
+// The modes that print their own report and skip the full one, Some(exit status) when one ran
+fn run_fast_path(cli: &InspectArgs, data: &[u8], fat_archs: Option<&[fat::FatArch]>, slices: &[header::MachOSlice]) -> Result<Option<i32>, Box<dyn Error>> {
+    let mut status = 0;
+    // --uuid covers every slice (no prompt) and skips everything else
+    if cli.uuid {
+        print_uuids(cli, data, slices)?;
+    } else if cli.oso {
+        // Same deal for --oso, it only needs the symbol table of each slice
+        print_report(cli, &oso_reports(data, slices)?)?;
+    } else if cli.identify {
+        print_identities(cli, data, slices)?;
+    } else if let Some(path) = &cli.export_load_commands {
+        export_load_commands(data, slices, path)?;
+    } else if cli.lc_map {
+        print_report(cli, &lc_map_reports(data, slices)?)?;
+    } else if cli.initializers {
+        print_report(cli, &initializer_reports(data, slices)?)?;
+    } else if cli.code_signature {
+        print_report(cli, &code_signature_reports(data, slices)?)?;
+    } else if cli.check_signature {
+        let reports = signature_check_reports(data, slices)?;
+        print_report(cli, &reports)?;
+        if reports.iter().any(|r| r.signed && !r.valid) {
+            status = 1;
+        }
+    } else if cli.exports {
+        print_report(cli, &export_reports(data, slices)?)?;
+    } else if cli.reexports {
+        print_report(cli, &reexport_reports(data, slices)?)?;
+    } else if cli.objc_categories {
+        print_report(cli, &objc_category_reports(data, slices)?)?;
+    } else if cli.objc_protocols {
+        print_report(cli, &objc_protocol_reports(data, slices)?)?;
+    } else if let Some(spec) = &cli.scan_bytes {
+        print_byte_scan(cli, data, slices, spec)?;
+    } else if cli.find_string.is_some() || cli.find_hex.is_some() {
+        print_search(cli, data, slices)?;
+    } else if cli.literals {
+        print_report(cli, &literal_reports(data, slices)?)?;
+    } else if cli.linkedit {
+        print_report(cli, &linkedit_reports(data, slices)?)?;
+    } else if cli.function_starts {
+        print_report(cli, &function_starts_reports(data, slices)?)?;
+    } else if cli.detect_appended {
+        print_report(cli, &appended_data_reports(data, slices, cli.appended_threshold)?)?;
+    } else if let (Some(offset), Some(len)) = (cli.bytes_at, cli.len) {
+        print_bytes_at(cli, data, fat_archs, offset, len)?;
+    } else if cli.report_unknown_sections {
+        print_report(cli, &unknown_sections_report(data, slices)?)?;
+    } else if let Some(spec) = &cli.section_info {
+        // --section-info is a targeted lookup, every slice is searched
+        print_section_info(cli, data, slices, spec)?;
+    } else if cli.all {
+        print_everything(cli, data, fat_archs, slices.to_vec())?;
+    } else {
+        return Ok(None);
+    }
+    Ok(Some(status))
+}
+
+fn inspect(mut cli: InspectArgs) -> Result<(), Box<dyn Error>> {
+    let mut timer = utils::PhaseTimer::new();
+    prepare_output(&mut cli);
+
+    if cli.recursive {
+        scan_directory(&cli)?;
+        timer.lap("directory scan");
+        if cli.timings {
+            print_timings(&timer, &[]);
+        }
+        return Ok(());
+    }
+
+    let (data, fat_archs) = load_binary(cli.binary(), cli.expected_size)?;
+    // --fileset-index: from here on the member is the binary, a thin one
+    let (data, fat_archs) = match cli.fileset_index {
+        Some(index) => (fileset::entry_view(&data, index)?, None),
+        None => (data, fat_archs),
+    };
+    timer.lap("read input");
+    let is_fat = fat_archs.is_some();
+    // JSON, plist, CSV, DOT and Markdown all want plain (uncolored) values and every architecture
+    let is_structured = cli.format != OutputFormat::Text;
+
+    let slices = analysis::all_slices(fat_archs.as_deref());
+
+    // Deployment target checks for CI, these look at every slice regardless of what gets printed
+    if cli.require_platform.is_some() || cli.require_min_os.is_some() {
+
+        let failures = check_build_requirements(&cli, &data, &slices)?;
+        for failure in &failures {
+            let label = if cli.strict { "Requirement failed:".red().bold() } else { "Requirement warning:".yellow().bold() };
+            eprintln!("{} {}", label, failure);
+        }
+
+        if cli.strict && !failures.is_empty() {
+            std::process::exit(1);
+        }
+    }
+
+    if let Some(status) = run_fast_path(&cli, &data, fat_archs.as_deref(), &slices)? {
+        timer.lap("fast path");
+        if cli.timings {
+            print_timings(&timer, &[]);
+        }
+        if status != 0 {
+            std::process::exit(status);
+        }
+        return Ok(());
+    }
+
+    // Prepare architecture slices
+    let arch_slices: Vec<header::MachOSlice> = match &fat_archs {
+        Some(archs) if !is_structured && should_prompt_for_arch(&cli) => {
+            // Text with someone at the terminal, prompt user for selection
+            let selected_arch = fat_binary_user_decision(archs)?;
+            vec![fat_arch_slice(selected_arch)]
+        }
+        // JSON/plist (or nobody is there to answer the prompt), do all architectures automatically
+        _ => slices,
+    };
+
+    let (parsed_slices, macho_report) = build_full_report(&cli, &data, fat_archs.as_deref(), arch_slices)?;
+    timer.lap(utils::PhaseTimer::PARSE_SLICES);
+
+    // --debug-dump: raw parsed state instead of the curated report (handy for bug reports)
+    if cli.debug_dump {
+        let mut out = output_sink(&cli)?;
+        for (i, parsed) in parsed_slices.iter().enumerate() {
+            writeln!(out, "==== architecture {} ====", i)?;
+            writeln!(out, "{:#?}", parsed.header)?;
+            writeln!(out, "{:#?}", parsed.load_commands)?;
+            writeln!(out, "{:#?}", parsed.linkedit_data)?;
+            writeln!(out, "{:#?}", parsed.segments)?;
+            writeln!(out, "{:#?}", parsed.dylibs)?;
+            writeln!(out, "{:#?}", parsed.rpaths)?;
+            writeln!(out, "{:#?}", parsed.symbols)?;
+            writeln!(out, "{:#?}", parsed.strings)?;
+            writeln!(out, "{:#?}", parsed.fixups)?;
+            writeln!(out, "{:#?}", parsed.diagnostics)?;
         }
+        out.flush()?;
+    } else {
+        // Now output
+        print_macho_report(&cli, &macho_report)?;
+    }
+    timer.lap("output");
+
+    if cli.timings {
+        print_timings(&timer, &parsed_slices);
+    }
+
+    Ok(())
+}
+
+fn diff(mut cli: InspectArgs, other: &Path) -> Result<(), Box<dyn Error>> {
+    // `moscope diff <BINARY> <OTHER>`: both full reports as JSON values, compared field by field
+    let mut timer = utils::PhaseTimer::new();
+    prepare_output(&mut cli);
+
+    let mut values = Vec::new();
+    for path in [cli.binary(), other] {
+        let (data, fat_archs) = load_binary(path, None)?;
+        timer.lap("read input");
+        let slices = analysis::all_slices(fat_archs.as_deref());
+        let (_, report) = build_full_report(&cli, &data, fat_archs.as_deref(), slices)?;
+        values.push(serde_json::to_value(&report)?);
+        timer.lap(utils::PhaseTimer::PARSE_SLICES);
     }
 
+    let report = diff::diff_reports(&values[0], &values[1]);
+    timer.lap("diff");
+    print_report(&cli, &report)?;
+    timer.lap("output");
+
+    // one line per binary, the per-slice breakdown would mix the two up
+    if cli.timings {
+        print_timings(&timer, &[]);
+    }
+
+    if !report.changes.is_empty() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn verify(mut cli: InspectArgs) -> Result<(), Box<dyn Error>> {
+    // `moscope verify <BINARY>`: every slice through the full parse, only the diagnostics are printed
+    let mut timer = utils::PhaseTimer::new();
+    prepare_output(&mut cli);
+    cli.no_diagnostics = false;
+
+    let (data, fat_archs) = load_binary(cli.binary(), cli.expected_size)?;
+    timer.lap("read input");
+    let slices = analysis::all_slices(fat_archs.as_deref());
+    let (parsed_slices, macho_report) = build_full_report(&cli, &data, fat_archs.as_deref(), slices)?;
+    timer.lap(utils::PhaseTimer::PARSE_SLICES);
+
+    let report = verify::build_verify_report(&macho_report);
+    print_report(&cli, &report)?;
+    timer.lap("output");
+
+    if cli.timings {
+        print_timings(&timer, &parsed_slices);
+    }
+
+    if report.errors > 0 || (cli.strict && report.warnings > 0) {
+        std::process::exit(1);
+    }
     Ok(())
 }
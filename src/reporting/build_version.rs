@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuildToolReport {
+    pub tool: String,
+    pub version: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuildVersionReport {
+    pub platform: String, // "macOS", "iOSSimulator", ... or Platform(<n>)
+    pub minos: String,
+    pub sdk: String,
+    pub tools: Vec<BuildToolReport>,
+}
@@ -0,0 +1,63 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/*
+Canonical JSON so that the same binary always produces byte-identical output:
+    - object keys sorted (done explicitly, not relying on serde_json's map feature flags)
+    - no insignificant whitespace
+    - numbers written by serde_json's integer/float formatter, which is deterministic
+Hash the result and you get a stable fingerprint of the report.
+*/
+
+pub fn to_canonical_json<T: Serialize>(value: &T) -> Result<String, serde_json::Error> {
+    let value = serde_json::to_value(value)?;
+    serde_json::to_string(&canonicalize(value))
+}
+
+fn canonicalize(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(String, Value)> = map.into_iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+            let mut sorted = Map::new();
+            for (key, v) in entries {
+                sorted.insert(key, canonicalize(v));
+            }
+            Value::Object(sorted)
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(canonicalize).collect()),
+        other => other,
+    }
+}
+
+
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Unordered {
+        zebra: u32,
+        apple: Vec<Nested>,
+    }
+
+    #[derive(Serialize)]
+    struct Nested {
+        y: &'static str,
+        b: Option<u64>,
+    }
+
+    #[test]
+    fn keys_are_sorted_and_whitespace_is_dropped() {
+        let value = Unordered { zebra: 1, apple: vec![Nested { y: "y", b: None }] };
+
+        assert_eq!(to_canonical_json(&value).unwrap(), r#"{"apple":[{"b":null,"y":"y"}],"zebra":1}"#);
+    }
+}
@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+
+use crate::reporting::diagnostics::DiagnosticReport;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CodeDirectoryReport {
+    pub slot: u32,                  // 0 = primary, 0x1000+ = alternates
+    pub version: String,
+    pub flags: Vec<String>,         // CS_* names without the prefix, e.g. RUNTIME
+    pub hash_type: String,          // sha1, sha256, sha256-truncated, sha384
+    pub page_size: u32,
+    pub code_limit: u32,
+    pub n_code_slots: u32,
+    pub n_special_slots: u32,
+    pub team_id: Option<String>,
+    pub cdhash: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CodeSignatureReport {
+    pub cpu_type: String,
+    pub cpu_subtype: String,
+    pub signed: bool,
+    pub identifier: Option<String>,
+    pub team_id: Option<String>,    // signing team, None for ad-hoc signatures
+    pub hash_type: Option<String>,  // of the CodeDirectory the cdhash comes from
+    pub cdhash: Option<String>,     // what `codesign -dvvv` prints as CDHash
+    pub flags: Vec<String>,         // of that same CodeDirectory, the booleans below are picked out of it
+    pub platform_binary: bool,
+    pub hardened_runtime: bool,     // CS_RUNTIME
+    pub library_validation: bool,   // CS_REQUIRE_LV
+    pub adhoc: bool,
+    pub get_task_allow: bool,       // debuggable, never on a shipping build
+    pub code_directories: Vec<CodeDirectoryReport>,
+    pub diagnostics: Vec<DiagnosticReport>, // a malformed LC_CODE_SIGNATURE lands here instead of failing
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageMismatchReport {
+    pub page: u32,
+    pub offset: u64,                // slice relative
+    pub expected: String,           // the code slot hash stored in the CodeDirectory
+    pub actual: Option<String>,     // what the page hashes to now, None when the page is past EOF
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CodeDirectoryCheckReport {
+    pub slot: u32,
+    pub hash_type: String,
+    pub page_size: u32,
+    pub code_limit: u32,
+    pub pages_checked: u32,         // 0 when the hash type is one we can't compute
+    pub valid: bool,
+    pub mismatches: Vec<PageMismatchReport>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignatureCheckReport {
+    pub cpu_type: String,
+    pub cpu_subtype: String,
+    pub signed: bool,
+    pub valid: bool,                // every CodeDirectory's pages match, false when unsigned
+    pub code_directories: Vec<CodeDirectoryCheckReport>,
+    pub diagnostics: Vec<DiagnosticReport>,
+}
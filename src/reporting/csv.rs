@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use clap::ValueEnum;
+
+use crate::macho::{dylibs, symtab};
+use crate::reporting::macho::MachOReport;
+
+/*
+--format csv: one flat table per run, picked with --csv-table. The report is nested
+(architectures --> segments --> sections, ...) and CSV isn't, so every row starts with the
+slice it came from and the rest of the report is left to JSON.
+
+Fields follow RFC 4180: a field holding a comma, a quote or a line break is wrapped in quotes
+and its quotes are doubled. String values were already escaped (\n, \t, \x1b, ...) by the
+extractor, so a raw line break only gets here through a symbol or dylib name.
+*/
+
+#[derive(Clone, Copy, Debug, ValueEnum, PartialEq)]
+pub enum CsvTable {
+    Symbols,
+    Strings,
+    Dylibs,
+    Segments,
+}
+
+// What the string table filters on, the same knobs the text output honours
+pub struct CsvStringFilter<'a> {
+    pub min_length: usize,
+    pub section_min_lengths: Option<&'a HashMap<String, usize>>,
+    pub max_count: Option<usize>,
+}
+
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn write_row(w: &mut dyn Write, fields: &[&str]) -> io::Result<()> {
+    let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+    writeln!(w, "{}", row.join(","))
+}
+
+pub fn write_csv_table(w: &mut dyn Write, report: &MachOReport, table: CsvTable, strip_underscore: bool, filter: &CsvStringFilter) -> io::Result<()> {
+    match table {
+        CsvTable::Symbols => write_row(w, &["arch", "address", "kind", "bind", "segment", "section", "name"])?,
+        CsvTable::Strings => write_row(w, &["arch", "segment", "section", "value"])?,
+        CsvTable::Dylibs => write_row(w, &["arch", "path", "kind", "current_version", "compatibility_version"])?,
+        CsvTable::Segments => write_row(w, &["arch", "name", "vmaddr", "vmsize", "fileoff", "filesize", "initprot", "maxprot", "nsects"])?,
+    }
+
+    for arch in &report.architectures {
+        let arch_name = arch.cpu_subtype.as_str();
+        match table {
+            CsvTable::Symbols => {
+                for sym in arch.symbols.iter().flatten() {
+                    let kind = sym.stab_type.as_deref().unwrap_or(&sym.kind);
+                    write_row(w, &[
+                        arch_name,
+                        &sym.addr_hex,
+                        kind,
+                        if sym.external { "EXT" } else { "LOC" },
+                        sym.segname.as_deref().unwrap_or(""),
+                        sym.sectname.as_deref().unwrap_or(""),
+                        if strip_underscore { &sym.source_name } else { &sym.name },
+                    ])?;
+                }
+            }
+            CsvTable::Strings => {
+                let strings = arch
+                    .strings
+                    .iter()
+                    .flatten()
+                    .filter(|s| s.value.len() >= symtab::min_length_for(&s.sectname, filter.section_min_lengths, filter.min_length))
+                    .take(filter.max_count.unwrap_or(usize::MAX));
+                for s in strings {
+                    write_row(w, &[arch_name, &s.segname, &s.sectname, &s.value])?;
+                }
+            }
+            CsvTable::Dylibs => {
+                for dylib in arch.dylibs.iter().flatten() {
+                    write_row(w, &[
+                        arch_name,
+                        &dylib.path,
+                        &dylib.kind,
+                        &dylibs::format_dylib_version(dylib.current_version),
+                        &dylibs::format_dylib_version(dylib.compatibility_version),
+                    ])?;
+                }
+            }
+            CsvTable::Segments => {
+                for seg in arch.segments.iter().flatten() {
+                    write_row(w, &[
+                        arch_name,
+                        &seg.name,
+                        &format!("0x{:x}", seg.vmaddr),
+                        &format!("0x{:x}", seg.vmsize),
+                        &seg.fileoff.to_string(),
+                        &seg.filesize.to_string(),
+                        &seg.initprot,
+                        &seg.maxprot,
+                        &seg.sections.len().to_string(),
+                    ])?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_fields_are_left_alone() {
+        assert_eq!(csv_field("_main"), "_main");
+        assert_eq!(csv_field(""), "");
+        // already escaped by the string extractor, nothing CSV cares about
+        assert_eq!(csv_field(r"line\nbreak\t"), r"line\nbreak\t");
+    }
+
+    #[test]
+    fn commas_quotes_and_line_breaks_are_quoted() {
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+        assert_eq!(csv_field("cr\r"), "\"cr\r\"");
+    }
+
+    #[test]
+    fn rows_join_quoted_fields() {
+        let mut out = Vec::new();
+        write_row(&mut out, &["arm64", "__TEXT", "hello, \"world\""]).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "arm64,__TEXT,\"hello, \"\"world\"\"\"\n");
+    }
+}
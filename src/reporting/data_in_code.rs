@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DataInCodeEntryReport {
+    pub offset: u32, // slice relative file offset, like LC_MAIN's entryoff
+    pub vm_addr: Option<u64>, // None when no segment maps the offset
+    pub length: u16,
+    pub kind: String, // DATA, JUMP_TABLE8, ... or UNKNOWN(0x..)
+}
@@ -0,0 +1,82 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/*
+`moscope diff` compares the JSON form of two MachOReports, so anything the report carries is
+compared and nothing needs a hand written comparison. Arrays are compared index by index:
+one symbol inserted near the top shows up as every later entry changing, which is noisy but
+never wrong.
+*/
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReportChange {
+    pub path: String,       // JSON pointer into the report, /architectures/0/segments/2/vmsize
+    pub old: Option<Value>, // None when only the new report has it
+    pub new: Option<Value>, // None when only the old report has it
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiffReport {
+    pub changes: Vec<ReportChange>,
+}
+
+pub fn diff_reports(old: &Value, new: &Value) -> DiffReport {
+    let mut changes = Vec::new();
+    diff_value(String::new(), Some(old), Some(new), &mut changes);
+    DiffReport { changes }
+}
+
+// A null (an Option the report left empty) counts as absent, plist has no way to write one anyway
+fn present(value: Option<&Value>) -> Option<&Value> {
+    value.filter(|v| !v.is_null())
+}
+
+fn diff_value(path: String, old: Option<&Value>, new: Option<&Value>, changes: &mut Vec<ReportChange>) {
+    match (present(old), present(new)) {
+        (Some(Value::Object(a)), Some(Value::Object(b))) => {
+            let keys: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+            for key in keys {
+                // JSON pointer escaping, section names like __TEXT/__text aren't keys but be safe
+                let child = format!("{}/{}", path, key.replace('~', "~0").replace('/', "~1"));
+                diff_value(child, a.get(key), b.get(key), changes);
+            }
+        }
+        (Some(Value::Array(a)), Some(Value::Array(b))) => {
+            for i in 0..a.len().max(b.len()) {
+                diff_value(format!("{}/{}", path, i), a.get(i), b.get(i), changes);
+            }
+        }
+        (a, b) if a != b => changes.push(ReportChange { path, old: a.cloned(), new: b.cloned() }),
+        _ => {}
+    }
+}
+
+
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn changed_added_and_removed_fields_are_listed_by_path() {
+        let old = json!({ "is_fat": false, "architectures": [{ "cpu_subtype": "arm64", "segments": [{ "name": "__TEXT" }] }], "slice_consistency": null });
+        let new = json!({ "is_fat": false, "architectures": [{ "cpu_subtype": "arm64e", "segments": [{ "name": "__TEXT" }, { "name": "__DATA" }] }], "slice_consistency": [] });
+
+        let report = diff_reports(&old, &new);
+
+        assert_eq!(report.changes, vec![
+            ReportChange { path: "/architectures/0/cpu_subtype".into(), old: Some(json!("arm64")), new: Some(json!("arm64e")) },
+            ReportChange { path: "/architectures/0/segments/1".into(), old: None, new: Some(json!({ "name": "__DATA" })) },
+            ReportChange { path: "/slice_consistency".into(), old: None, new: Some(json!([])) },
+        ]);
+        assert!(diff_reports(&old, &old).changes.is_empty());
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use crate::macho::dylibs::DylibKind;
+use crate::reporting::macho::MachOReport;
+
+/*
+--format dot: the dylib dependency declarations as a GraphViz graph, for `dot -Tpng`.
+
+    "hello" -> "libSystem.B.dylib" [label="LOAD"]
+    "hello" -> "libfoo.dylib"      [label="WEAK", dashed orange]
+
+The analyzed binary is the root (one per slice for a fat binary, the same dylib shared between
+them), every LC_*_DYLIB but LC_ID_DYLIB an edge labelled with its kind. Nodes show the install
+name's leaf, the full path goes in the tooltip. Only what this binary declares is drawn, the
+dependencies' own dependencies would need their files.
+*/
+
+// DOT string literal, only the quote and the backslash need escaping
+fn dot_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn leaf(path: &str) -> &str {
+    path.trim_end_matches('/').rsplit('/').next().filter(|leaf| !leaf.is_empty()).unwrap_or(path)
+}
+
+fn edge_style(kind: &str) -> &'static str {
+    if kind == DylibKind::Weak.as_str() {
+        "color=orange, fontcolor=orange, style=dashed"
+    } else if kind == DylibKind::Reexport.as_str() {
+        "color=blue, fontcolor=blue"
+    } else if kind == DylibKind::Upward.as_str() {
+        "color=purple, fontcolor=purple"
+    } else if kind == DylibKind::Lazy.as_str() {
+        "color=gray, fontcolor=gray, style=dotted"
+    } else if kind == DylibKind::Unknown.as_str() {
+        "color=red, fontcolor=red"
+    } else {
+        "color=black"
+    }
+}
+
+// `name` labels the root when the binary has no LC_ID_DYLIB of its own (executables, bundles)
+pub fn write_dot_graph(w: &mut dyn Write, report: &MachOReport, name: &str) -> io::Result<()> {
+    writeln!(w, "digraph dylibs {{")?;
+    writeln!(w, "    rankdir=LR;")?;
+    writeln!(w, "    node [shape=box, fontname=\"Helvetica\"];")?;
+    writeln!(w, "    edge [fontname=\"Helvetica\", fontsize=10];")?;
+
+    let mut dylib_nodes: HashMap<&str, String> = HashMap::new();
+    for (index, arch) in report.architectures.iter().enumerate() {
+        let dylibs = arch.dylibs.as_deref().unwrap_or_default();
+        let install_name = dylibs.iter().find(|dylib| dylib.kind == DylibKind::Id.as_str()).map(|dylib| dylib.path.as_str());
+
+        let root = format!("root{}", index);
+        let mut label = leaf(install_name.unwrap_or(name)).to_string();
+        if report.is_fat {
+            label = format!("{} [{}]", label, arch.cpu_subtype);
+        }
+        writeln!(w, "    {} [label={}, tooltip={}, style=bold];", root, dot_string(&label), dot_string(install_name.unwrap_or(name)))?;
+
+        for dylib in dylibs.iter().filter(|dylib| dylib.kind != DylibKind::Id.as_str()) {
+            // declare each dylib once, the first time any slice links it
+            let node = match dylib_nodes.get(dylib.path.as_str()) {
+                Some(node) => node.clone(),
+                None => {
+                    let node = format!("dylib{}", dylib_nodes.len());
+                    writeln!(w, "    {} [label={}, tooltip={}];", node, dot_string(leaf(&dylib.path)), dot_string(&dylib.path))?;
+                    dylib_nodes.insert(dylib.path.as_str(), node.clone());
+                    node
+                }
+            };
+            writeln!(w, "    {} -> {} [label={}, {}];", root, node, dot_string(&dylib.kind), edge_style(&dylib.kind))?;
+        }
+    }
+
+    writeln!(w, "}}")?;
+    Ok(())
+}
+
+
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_are_quoted_and_escaped() {
+        assert_eq!(dot_string("libSystem.B.dylib"), "\"libSystem.B.dylib\"");
+        assert_eq!(dot_string("a\"b\\c"), "\"a\\\"b\\\\c\"");
+    }
+
+    #[test]
+    fn leaf_is_the_last_path_component() {
+        assert_eq!(leaf("/usr/lib/libSystem.B.dylib"), "libSystem.B.dylib");
+        assert_eq!(leaf("@rpath/Foo.framework/Versions/A/Foo"), "Foo");
+        assert_eq!(leaf("libbare.dylib"), "libbare.dylib");
+        assert_eq!(leaf("/"), "/");
+    }
+
+    #[test]
+    fn weak_and_reexport_edges_stand_out() {
+        assert_ne!(edge_style("WEAK"), edge_style("LOAD"));
+        assert_ne!(edge_style("REEXPORT"), edge_style("LOAD"));
+        assert_ne!(edge_style("WEAK"), edge_style("REEXPORT"));
+    }
+}
@@ -1,6 +1,6 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct FixupReport {
     pub kind: String, // "rebase", "bind", etc
     pub addr: u64,
@@ -8,3 +8,32 @@ pub struct FixupReport {
     pub symbol: Option<String>,
     pub addend: Option<i64>
 }
+
+// LC_DYLD_INFO(_ONLY): where each opcode stream / the export trie lives, offsets are slice relative
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DyldInfoReport {
+    pub cmd: String, // LC_DYLD_INFO or LC_DYLD_INFO_ONLY
+    pub rebase_off: u32,
+    pub rebase_size: u32,
+    pub bind_off: u32,
+    pub bind_size: u32,
+    pub weak_bind_off: u32,
+    pub weak_bind_size: u32,
+    pub lazy_bind_off: u32,
+    pub lazy_bind_size: u32,
+    pub export_off: u32,
+    pub export_size: u32,
+}
+
+// One bind from the LC_DYLD_INFO bind opcodes, addr is None when segment_index names no segment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BoundSymbolReport {
+    pub segment: Option<String>,
+    pub segment_offset: u64,
+    pub addr: Option<u64>,
+    pub dylib_ordinal: i32,
+    pub library: String, // dylib path, or <self> / <main executable> / <flat lookup> / <weak lookup>
+    pub symbol: String,
+    pub bind_type: String, // POINTER, TEXT_ABSOLUTE32, TEXT_PCREL32
+    pub addend: i64,
+}
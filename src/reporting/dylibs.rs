@@ -1,14 +1,22 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use super::load_commands::LoadCommandReport;
 
 
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct DylibReport {
-    pub path: String,
+    pub path: String,             // raw install name, authoritative
+    pub normalized_path: String,  // advisory, `.`/`..`/`//` resolved lexically
     pub timestamp: u32,
     pub current_version: u32,
     pub compatibility_version: u32,
     pub kind: String,
     pub load_command: LoadCommandReport,
-}
\ No newline at end of file
+}
+
+// Two-level namespace library ordinal --> what it names, special ordinals spelled <like this>
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct DylibOrdinalReport {
+    pub ordinal: u32,
+    pub path: String,
+}
@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EncryptionInfoReport {
+    pub cmd: String, // LC_ENCRYPTION_INFO or LC_ENCRYPTION_INFO_64
+    pub crypt_offset: u32, // slice relative
+    pub crypt_size: u32,
+    pub crypt_id: u32, // 0 = the range is plaintext (decrypted, or never encrypted)
+    pub is_encrypted: bool,
+}
@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntryPointReport {
+    pub source: String, // LC_MAIN or LC_UNIXTHREAD
+    pub file_offset: Option<u64>, // slice relative, None when no segment maps the address
+    pub vm_addr: Option<u64>,
+    pub stack_size: Option<u64>, // LC_MAIN only, 0 = default
+}
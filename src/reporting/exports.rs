@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+use crate::reporting::diagnostics::DiagnosticReport;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportReport {
+    pub name: String,
+    pub kind: String,                       // REGULAR, THREAD_LOCAL, ABSOLUTE
+    pub flags: u64,
+    pub offset: Option<u64>,                // from the __TEXT base (the stub for resolvers), None for re-exports
+    pub address: Option<u64>,               // offset + __TEXT vmaddr, ABSOLUTE symbols are the raw value
+    pub resolver_offset: Option<u64>,       // STUB_AND_RESOLVER only, the resolver function's offset
+    pub reexport_ordinal: Option<u64>,      // REEXPORT only, the library ordinal from the trie
+    pub reexport_from: Option<String>,      // install name of the dylib that really defines it
+    pub reexport_name: Option<String>,      // name looked up in that dylib
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportsReport {
+    pub cpu_type: String,
+    pub cpu_subtype: String,
+    pub exports: Vec<ExportReport>,
+    pub diagnostics: Vec<DiagnosticReport>, // load commands that had to be skipped
+}
+
+// --reexports: the same per-slice export list, the text output only shows the re-exported ones
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ReexportsReport(pub ExportsReport);
@@ -0,0 +1,8 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FilesetEntryReport {
+    pub id: String, // bundle id of the member, e.g. com.apple.kernel
+    pub vmaddr: u64,
+    pub fileoff: u64, // where its mach_header sits, --fileset-index analyzes it from there
+}
@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunctionStartsReport {
+    pub cpu_type: String,
+    pub cpu_subtype: String,
+    pub count: usize,
+    pub function_starts: Vec<u64>, // absolute vmaddrs, in the order LC_FUNCTION_STARTS lists them
+}
@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct IdentityReport {
+    pub arch: String,
+    pub filetype: String,
+    pub uuid: Option<String>,
+    pub platform: Option<String>,
+    pub minos: Option<String>,
+    pub sdk: Option<String>,
+    pub identity: String, // the one-line form, fields in the order above
+}
+
+// --recursive: one entry per Mach-O found under the directory
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScannedFileReport {
+    pub path: String,          // absolute
+    pub relative_path: String, // relative to the scanned directory, what text output prints
+    pub identities: Vec<IdentityReport>,
+}
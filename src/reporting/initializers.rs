@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InitializerReport {
+    pub segment: String,        // __DATA_CONST on current toolchains, __DATA on older ones
+    pub section: String,
+    pub slot: u64,              // vmaddr of the pointer itself
+    pub slot_hex: String,
+    pub target: Option<u64>,    // the function dyld calls, None if bound / unreadable
+    pub target_hex: Option<String>,
+    pub symbol: Option<String>,
+    pub bound: bool,            // pointer resolved by dyld into another image
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InitializersReport {
+    pub cpu_type: String,
+    pub cpu_subtype: String,
+    pub initializers: Vec<InitializerReport>,
+}
@@ -0,0 +1,61 @@
+use serde::Serialize;
+use serde_json::ser::{PrettyFormatter, Serializer};
+
+/*
+serde_json's to_string_pretty hardcodes two spaces. Same output, but the indent
+width comes from --json-indent. 0 means no pretty printing at all (one line).
+*/
+
+pub fn to_json_with_indent<T: Serialize>(value: &T, indent: usize) -> Result<String, serde_json::Error> {
+    if indent == 0 {
+        return serde_json::to_string(value);
+    }
+
+    let indent = " ".repeat(indent);
+    let mut buf = Vec::new();
+    let mut serializer = Serializer::with_formatter(&mut buf, PrettyFormatter::with_indent(indent.as_bytes()));
+    value.serialize(&mut serializer)?;
+
+    // serde_json only ever writes valid UTF-8
+    Ok(String::from_utf8(buf).expect("serde_json produced invalid UTF-8"))
+}
+
+
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Sample {
+        name: &'static str,
+        sizes: Vec<u32>,
+    }
+
+    fn sample() -> Sample {
+        Sample { name: "x", sizes: vec![1] }
+    }
+
+    #[test]
+    fn default_width_matches_to_string_pretty() {
+        assert_eq!(to_json_with_indent(&sample(), 2).unwrap(), serde_json::to_string_pretty(&sample()).unwrap());
+    }
+
+    #[test]
+    fn indent_width_is_applied_per_level() {
+        assert_eq!(
+            to_json_with_indent(&sample(), 4).unwrap(),
+            "{\n    \"name\": \"x\",\n    \"sizes\": [\n        1\n    ]\n}"
+        );
+    }
+
+    #[test]
+    fn zero_is_compact() {
+        assert_eq!(to_json_with_indent(&sample(), 0).unwrap(), r#"{"name":"x","sizes":[1]}"#);
+    }
+}
@@ -1,8 +1,71 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use super::diagnostics::DiagnosticReport;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct LoadCommandReport {
     pub command: String,
     pub cmd: u32,
     pub size: u32,
+    pub body: Option<String>, // hex of everything after cmd/cmdsize, unknown commands with --dump-unknown-lc only
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct LoadCommandRangeReport {
+    pub index: usize,
+    pub command: String,
+    pub start: u64, // offsets are from the start of the slice, like otool -l
+    pub end: u64,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct LoadCommandMapReport {
+    pub cpu_type: String,
+    pub cpu_subtype: String,
+    pub region_start: u64, // end of the mach header
+    pub region_end: u64,   // region_start + sizeofcmds
+    pub commands: Vec<LoadCommandRangeReport>,
+    pub diagnostics: Vec<DiagnosticReport>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct LinkEditDataReport {
+    pub command: String,
+    pub dataoff: u32,
+    pub datasize: u32,
+}
+
+// --linkedit: every linkedit_data_command blob of a slice, in file order
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct LinkEditOverviewReport {
+    pub cpu_type: String,
+    pub cpu_subtype: String,
+    pub slice_offset: u64, // add to a dataoff for the offset in the file itself
+    pub blobs: Vec<LinkEditDataReport>,
+    pub diagnostics: Vec<DiagnosticReport>, // commands with a bad cmdsize, left out of blobs
+}
+
+// LC_SYMSEG, offset is slice relative
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct SymsegReport {
+    pub offset: u32,
+    pub size: u32,
+}
+
+// LC_VERSION_MIN_*, the pre LC_BUILD_VERSION deployment target
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MinVersionReport {
+    pub platform: String,
+    pub version: String,
+    pub sdk: String,
+}
+
+// One row of --export-load-commands, flat so a corpus of these is easy to jq / count
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct LoadCommandExportEntry {
+    pub arch: String,
+    pub name: String,
+    pub cmd: u32,
+    pub cmdsize: u32,
+    pub offset: u64, // file offset of the command (slice offset included)
+    pub requires_dyld: bool, // LC_REQ_DYLD bit, dyld refuses to load the image if it doesn't know the command
 }
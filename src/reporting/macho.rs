@@ -1,20 +1,36 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-use crate::reporting::dyld::FixupReport;
+use crate::reporting::build_version::BuildVersionReport;
+use crate::reporting::codesign::{CodeSignatureReport, SignatureCheckReport};
+use crate::reporting::data_in_code::DataInCodeEntryReport;
+use crate::reporting::encryption::EncryptionInfoReport;
+use crate::reporting::entry_point::EntryPointReport;
+use crate::reporting::exports::{ExportsReport, ReexportsReport};
+use crate::reporting::fileset::FilesetEntryReport;
+use crate::reporting::function_starts::FunctionStartsReport;
+use crate::reporting::initializers::InitializersReport;
+use crate::reporting::notes::NoteReport;
+use crate::reporting::objc::{ObjCCategoriesReport, ObjCProtocolsReport};
+use crate::reporting::sections::{LiteralsReport, UnknownSectionsReport};
+use crate::reporting::dyld::{BoundSymbolReport, DyldInfoReport, FixupReport};
+use crate::reporting::diagnostics::DiagnosticReport;
 use crate::reporting::header::MachHeaderReport;
-use crate::reporting::load_commands::LoadCommandReport;
-use crate::reporting::segments::SegmentReport;
-use crate::reporting::dylibs::DylibReport;
+use crate::reporting::load_commands::{LinkEditDataReport, LinkEditOverviewReport, LoadCommandMapReport, LoadCommandReport, MinVersionReport, SymsegReport};
+use crate::reporting::segments::{AppendedDataReport, SegmentReport, VmSpanReport};
+use crate::reporting::dylibs::{DylibOrdinalReport, DylibReport};
 use crate::reporting::rpaths::RPathsReport;
-use crate::reporting::symtab::{StringReport, SymbolReport};
+use crate::reporting::sub_images::SubImageReport;
+use crate::reporting::symtab::{OsoReport, StringReport, SymbolReport, SymtabStatsReport};
 use crate::macho::constants;
 use crate::macho::header::MachOHeader;
-use crate::macho::load_commands::LoadCommand;
-use crate::macho::segments::ParsedSegment;
-use crate::macho::dylibs::ParsedDylib;
+use crate::macho::load_commands::{LinkEditDataCommand, LoadCommand};
+use crate::macho::segments::{ParsedSegment, pagezero_size, vm_span};
+use crate::macho::dylibs::{ParsedDylib, build_ordinal_map};
 use crate::macho::dyld::Fixup;
+use crate::macho::diagnostics::Diagnostic;
 use crate::macho::rpaths::ParsedRPath;
 use crate::macho::symtab::{ParsedString, ParsedSymbol, sort_symbols};
+use crate::macho::uuid::ParsedUuid;
 
 pub struct ReportOptions {
     pub include_header: bool,
@@ -25,76 +41,183 @@ pub struct ReportOptions {
     pub include_symbols: bool,
     pub include_strings: bool,
     pub include_fixups: bool,
+    pub include_diagnostics: bool,
+    pub dump_unknown_load_commands: bool, // --dump-unknown-lc, hex of the commands load_command_name doesn't know
 }
 
-#[derive(Debug, Serialize)]
+// Everything in, what a plain `moscope <BINARY>` reports
+impl Default for ReportOptions {
+    fn default() -> Self {
+        ReportOptions {
+            include_header: true,
+            include_segments: true,
+            include_dylibs: true,
+            include_rpaths: true,
+            include_loadcmds: true,
+            include_symbols: true,
+            include_strings: true,
+            include_fixups: true,
+            include_diagnostics: true,
+            dump_unknown_load_commands: false,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct MachOReport {
     pub is_fat: bool,
+    pub uuids: Vec<String>, // every slice's LC_UUID in slice order, slices without one are skipped
+    pub slice_consistency: Option<Vec<DiagnosticReport>>, // cross-slice checks, fat binaries only
     pub architectures: Vec<ArchitectureReport>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct ArchitectureReport {
     pub cpu_type: String,
     pub cpu_subtype: String,
+    pub filetype: String, // short name (MH_EXECUTE, ...), there even with --no-header
     pub header: Option<MachHeaderReport>,
     pub load_commands: Option<Vec<LoadCommandReport>>,
+    pub linkedit_data: Option<Vec<LinkEditDataReport>>,
     pub segments: Option<Vec<SegmentReport>>,
     pub dylibs: Option<Vec<DylibReport>>,
+    pub ordinal_map: Option<Vec<DylibOrdinalReport>>, // library ordinal --> dylib path, included with dylibs
     pub rpaths: Option<Vec<RPathsReport>>,
+    pub dyld_environment: Vec<String>, // LC_DYLD_ENVIRONMENT "NAME=value" strings, one per command
     pub symbols: Option<Vec<SymbolReport>>,
     pub strings: Option<Vec<StringReport>>,
-    pub fixups: Option<Vec<FixupReport>>
+    pub fixups: Option<Vec<FixupReport>>,
+    pub diagnostics: Option<Vec<DiagnosticReport>>,
+    pub pagezero_size: Option<u64>, // None when there is no __PAGEZERO segment
+    pub implicit_pagezero: bool,    // MH_IMPLICIT_PAGEZERO
+    pub two_level_namespace: bool,  // MH_TWOLEVEL, imports carry library ordinals only when set
+    pub vm_span: Option<VmSpanReport>, // the loaded footprint, None when nothing but __PAGEZERO is mapped
+    pub slice_sha256: Option<String>, // hash of just this slice's bytes (offset..offset+size)
+    pub prebind_checksum: Option<u32>, // LC_PREBIND_CKSUM, legacy prebound images only (0 = recomputable)
+    pub fat_align: Option<u32>,     // fat_arch.align (power of two), None for thin files
+    pub code_signature: Option<CodeSignatureReport>, // identifier, team and cdhash, None when unsigned
+    pub symseg: Option<SymsegReport>, // LC_SYMSEG, None unless the slice has one
+    pub symtab_stats: Option<SymtabStatsReport>, // LC_SYMTAB layout and string table slack, None without one
+    pub stripped: bool, // no LC_SYMTAB symbols and no export trie
+    pub uuid: Option<String>, // this slice's LC_UUID, canonical form
+    pub build_version: Option<BuildVersionReport>, // LC_BUILD_VERSION platform, minos, sdk and tools
+    pub min_version: Option<MinVersionReport>, // LC_VERSION_MIN_*, older binaries (or both, they coexist)
+    pub entry_point: Option<EntryPointReport>, // LC_MAIN / LC_UNIXTHREAD, None for dylibs, bundles, ...
+    pub source_version: Option<String>, // LC_SOURCE_VERSION as A.B.C.D.E, trailing zero groups trimmed
+    pub target_triple: Option<String>, // LC_TARGET_TRIPLE, e.g. arm64-apple-macos14.0
+    pub data_in_code: Option<Vec<DataInCodeEntryReport>>, // LC_DATA_IN_CODE, None when there are no entries
+    pub dyld_info: Option<DyldInfoReport>, // LC_DYLD_INFO(_ONLY) table offsets and sizes, None for chained fixups
+    pub encryption: Option<EncryptionInfoReport>, // LC_ENCRYPTION_INFO(_64), None when the slice has neither
+    pub encrypted: bool, // that command with a nonzero cryptid, strings in its range are skipped
+    pub fileset_entries: Option<Vec<FilesetEntryReport>>, // LC_FILESET_ENTRY members of a kernel cache, None otherwise
+    pub sub_images: Option<Vec<SubImageReport>>, // umbrella / sub-framework declarations, None without any (or with --no-dylibs)
+    pub notes: Option<Vec<NoteReport>>, // LC_NOTE owners, offsets and a peek at their data, None without any
+    pub bound_symbols: Option<Vec<BoundSymbolReport>>, // LC_DYLD_INFO bind opcodes decoded, None without any (or with --no-fixups)
 }
 
-pub fn build_macho_report(is_fat: bool, architectures: Vec<ArchitectureReport>) -> MachOReport {
-    MachOReport {is_fat, architectures}
+// --all: the full report plus what the fast paths print, one list entry per slice.
+// An analysis that failed on this binary is None, one with nothing to find is just empty.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct AllReport {
+    pub report: MachOReport,
+    pub lc_maps: Option<Vec<LoadCommandMapReport>>,
+    pub linkedit: Option<Vec<LinkEditOverviewReport>>,
+    pub code_signatures: Option<Vec<CodeSignatureReport>>,
+    pub signature_checks: Option<Vec<SignatureCheckReport>>,
+    pub initializers: Option<Vec<InitializersReport>>,
+    pub exports: Option<Vec<ExportsReport>>,
+    pub reexports: Option<Vec<ReexportsReport>>,
+    pub objc_categories: Option<Vec<ObjCCategoriesReport>>,
+    pub objc_protocols: Option<Vec<ObjCProtocolsReport>>,
+    pub oso: Option<Vec<OsoReport>>,
+    pub unknown_sections: Option<UnknownSectionsReport>,
+    pub literals: Option<Vec<LiteralsReport>>,
+    pub function_starts: Option<Vec<FunctionStartsReport>>,
+    pub appended_data: Option<Vec<AppendedDataReport>>,
+}
+
+pub fn build_macho_report(
+    is_fat: bool,
+    architectures: Vec<ArchitectureReport>,
+    uuids: &[ParsedUuid],
+    slice_consistency: &[Diagnostic],
+    opts: &ReportOptions,
+) -> MachOReport {
+    MachOReport {
+        is_fat,
+        uuids: uuids.iter().map(|u| u.to_canonical()).collect(),
+        slice_consistency: if opts.include_diagnostics {
+            Some(slice_consistency.iter().map(|d| d.build_report()).collect())
+        } else {
+            None
+        },
+        architectures,
+    }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn build_architecture_report(
     cputype: i32,
     cpusubtype: i32,
     header: &MachOHeader,
     load_commands: &[LoadCommand],
+    linkedit_data: &[LinkEditDataCommand],
     segments: &[ParsedSegment],
     dylibs: &[ParsedDylib],
     rpaths: &[ParsedRPath],
     symbols: &[ParsedSymbol],
     strings: &[ParsedString],
     fixups: &[Fixup],
-    json: bool,
+    diagnostics: &[Diagnostic],
+    slice_sha256: Option<String>,
+    prebind_checksum: Option<u32>,
     opts: &ReportOptions
 ) -> ArchitectureReport {
     ArchitectureReport {
         cpu_type: constants::cpu_type_name(cputype).to_string(),
         cpu_subtype: constants::cpu_subtype_name(cputype, cpusubtype).to_string(),
+        filetype: constants::filetype_short_name(header.filetype()).to_string(),
 
         header: if opts.include_header {
-            Some(header.build_report(json))
+            Some(header.build_report())
         } else {
             None
         },
 
         load_commands: if opts.include_loadcmds {
-            Some(load_commands.iter().map(|lc| lc.build_report(json)).collect())
+            Some(load_commands.iter().map(|lc| lc.build_report()).collect())
+        } else {
+            None
+        },
+
+        linkedit_data: if opts.include_loadcmds {
+            Some(linkedit_data.iter().map(|ld| ld.build_report()).collect())
         } else {
             None
         },
 
         segments: if opts.include_segments {
-            Some(segments.iter().map(|s| s.build_report(json)).collect())
+            Some(segments.iter().map(|s| s.build_report()).collect())
         } else {
             None
         },
 
         dylibs: if opts.include_dylibs {
-            Some(dylibs.iter().map(|d| d.build_report(json)).collect())
+            Some(dylibs.iter().map(|d| d.build_report()).collect())
+        } else {
+            None
+        },
+
+        ordinal_map: if opts.include_dylibs {
+            Some(build_ordinal_map(dylibs))
         } else {
             None
         },
 
+        dyld_environment: Vec::new(), // LC_DYLD_ENVIRONMENT, filled from the slice's load commands
+
         rpaths: if opts.include_rpaths {
-            Some(rpaths.iter().map(|rp| rp.build_report(json)).collect())
+            Some(rpaths.iter().map(|rp| rp.build_report()).collect())
         } else {
             None
         },
@@ -102,7 +225,7 @@ pub fn build_architecture_report(
         symbols: if opts.include_symbols {
             let mut symbols = symbols.to_vec();
             sort_symbols(&mut symbols);
-            Some(symbols.iter().map(|s| s.build_report(json)).collect())
+            Some(symbols.iter().map(|s| s.build_report()).collect())
         } else {
             None
         },
@@ -114,10 +237,42 @@ pub fn build_architecture_report(
         },
 
         strings: if opts.include_strings {
-            Some(strings.iter().map(|s| s.build_report(json)).collect())
+            Some(strings.iter().map(|s| s.build_report()).collect())
         } else {
             None
         },
 
+        diagnostics: if opts.include_diagnostics {
+            Some(diagnostics.iter().map(|d| d.build_report()).collect())
+        } else {
+            None
+        },
+
+        pagezero_size: pagezero_size(segments),
+        implicit_pagezero: header.flags() & constants::MH_IMPLICIT_PAGEZERO != 0,
+        two_level_namespace: header.flags() & constants::MH_TWOLEVEL != 0,
+        vm_span: vm_span(segments),
+        slice_sha256,
+        prebind_checksum,
+        fat_align: None, // only the fat header knows, main fills it in
+        code_signature: None, // ParsedSlice::build_report fills it in from the slice's LC_CODE_SIGNATURE
+        symseg: None, // same, from its LC_SYMSEG
+        symtab_stats: None, // and from its LC_SYMTAB
+        stripped: false, // ParsedSlice knows about the export trie, this doesn't
+        uuid: None, // filled from the slice's parsed LC_UUID
+        build_version: None, // and its LC_BUILD_VERSION
+        min_version: None, // or LC_VERSION_MIN_*
+        entry_point: None, // LC_MAIN / LC_UNIXTHREAD, resolved against its segments
+        source_version: None, // LC_SOURCE_VERSION
+        target_triple: None, // LC_TARGET_TRIPLE
+        data_in_code: None, // LC_DATA_IN_CODE entries, mapped through the slice's segments
+        dyld_info: None, // LC_DYLD_INFO(_ONLY)
+        encryption: None, // LC_ENCRYPTION_INFO(_64)
+        encrypted: false, // and whether its cryptid says the range is still encrypted
+        fileset_entries: None, // LC_FILESET_ENTRY
+        sub_images: None, // LC_SUB_*, from the slice's load commands
+        notes: None, // LC_NOTE, previewed from the slice's bytes
+        bound_symbols: None, // its bind opcodes, named through the slice's dylibs
+
     }
 }
\ No newline at end of file
@@ -0,0 +1,232 @@
+use std::io::{self, Write};
+
+use crate::macho::constants::{MH_CIGAM, MH_MAGIC};
+use crate::macho::{dylibs, symtab};
+use crate::reporting::macho::{ArchitectureReport, MachOReport};
+use crate::reporting::writer::TextOptions;
+
+/*
+--format markdown: the report as headings and GFM tables, for pasting into docs.
+
+    # Mach-O Report: hello
+    ## x86 (x86_64)
+    ### Header | Segments | Dylibs | Rpaths | Symbols | Strings
+
+Built from the same report structs as JSON, only the layout differs. Names, paths and string
+values go in code spans so `__mh_execute_header` doesn't turn bold, and a `|` in any cell is
+escaped so a long symbol table can't break out of its columns. Strings were already escaped
+(\n, \t, ...) by the extractor, a raw line break can't end a row early.
+*/
+
+// Table cell text, the pipe is the only character GFM tables care about
+pub fn md_cell(value: &str) -> String {
+    value.replace('|', "\\|").replace(['\n', '\r'], " ")
+}
+
+// Code span cell, fenced with one more backtick than the longest run inside the value
+pub fn md_code(value: &str) -> String {
+    if value.is_empty() {
+        return String::new();
+    }
+    let longest_run = value.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest_run + 1);
+    // a span starting or ending in a backtick needs a space so the fence stays separate
+    let pad = if value.starts_with('`') || value.ends_with('`') { " " } else { "" };
+    md_cell(&format!("{fence}{pad}{value}{pad}{fence}"))
+}
+
+fn write_table_head(w: &mut dyn Write, columns: &[&str]) -> io::Result<()> {
+    writeln!(w)?;
+    writeln!(w, "| {} |", columns.join(" | "))?;
+    writeln!(w, "|{}", "---|".repeat(columns.len()))
+}
+
+fn write_row(w: &mut dyn Write, cells: &[String]) -> io::Result<()> {
+    writeln!(w, "| {} |", cells.join(" | "))
+}
+
+fn write_header(w: &mut dyn Write, arch: &ArchitectureReport) -> io::Result<()> {
+    let Some(header) = &arch.header else {
+        return Ok(());
+    };
+    let bits = if [MH_MAGIC, MH_CIGAM].map(u32::from_be_bytes).contains(&header.magic) { 32 } else { 64 };
+
+    writeln!(w)?;
+    writeln!(w, "### Header")?;
+    write_table_head(w, &["Field", "Value"])?;
+    write_row(w, &["Magic".into(), format!("`0x{:08x}`", header.magic)])?;
+    write_row(w, &["Architecture".into(), md_cell(&format!("{} ({})", header.cpu_type, header.cpu_subtype))])?;
+    write_row(w, &["Word size".into(), format!("{}-bit", bits)])?;
+    write_row(w, &["File type".into(), md_cell(&header.file_type)])?;
+    write_row(w, &["Load cmds".into(), header.ncmds.to_string()])?;
+    write_row(w, &["Cmds size".into(), format!("{} bytes", header.sizeofcmds)])?;
+    let flags: Vec<String> = header.flags.iter().map(|flag| md_code(flag)).collect();
+    write_row(w, &["Flags".into(), flags.join(", ")])?;
+    if let Some(uuid) = &arch.uuid {
+        write_row(w, &["UUID".into(), md_code(uuid)])?;
+    }
+    Ok(())
+}
+
+fn write_segments(w: &mut dyn Write, arch: &ArchitectureReport) -> io::Result<()> {
+    let Some(segments) = arch.segments.as_deref().filter(|segments| !segments.is_empty()) else {
+        return Ok(());
+    };
+
+    writeln!(w)?;
+    writeln!(w, "### Segments")?;
+    write_table_head(w, &["Segment", "VM address", "VM size", "File offset", "File size", "Init prot", "Max prot"])?;
+    for seg in segments {
+        write_row(w, &[
+            md_code(&seg.name),
+            format!("`0x{:x}`", seg.vmaddr),
+            format!("`0x{:x}`", seg.vmsize),
+            seg.fileoff.to_string(),
+            seg.filesize.to_string(),
+            md_code(&seg.initprot),
+            md_code(&seg.maxprot),
+        ])?;
+    }
+
+    if segments.iter().all(|seg| seg.sections.is_empty()) {
+        return Ok(());
+    }
+    writeln!(w)?;
+    writeln!(w, "### Sections")?;
+    write_table_head(w, &["Segment", "Section", "Kind", "Address", "Size"])?;
+    for sect in segments.iter().flat_map(|seg| &seg.sections) {
+        write_row(w, &[
+            md_code(&sect.segment),
+            md_code(&sect.name),
+            md_cell(&sect.kind),
+            format!("`0x{:x}`", sect.addr),
+            sect.size.to_string(),
+        ])?;
+    }
+    Ok(())
+}
+
+fn write_dylibs(w: &mut dyn Write, arch: &ArchitectureReport) -> io::Result<()> {
+    let Some(entries) = arch.dylibs.as_deref().filter(|entries| !entries.is_empty()) else {
+        return Ok(());
+    };
+
+    writeln!(w)?;
+    writeln!(w, "### Dylibs")?;
+    write_table_head(w, &["Kind", "Path", "Current version", "Compatibility version"])?;
+    for dylib in entries {
+        write_row(w, &[
+            md_cell(&dylib.kind),
+            md_code(&dylib.path),
+            dylibs::format_dylib_version(dylib.current_version),
+            dylibs::format_dylib_version(dylib.compatibility_version),
+        ])?;
+    }
+    Ok(())
+}
+
+fn write_rpaths(w: &mut dyn Write, arch: &ArchitectureReport) -> io::Result<()> {
+    let Some(rpaths) = arch.rpaths.as_deref().filter(|rpaths| !rpaths.is_empty()) else {
+        return Ok(());
+    };
+
+    writeln!(w)?;
+    writeln!(w, "### Rpaths")?;
+    write_table_head(w, &["Load command", "Path"])?;
+    for rpath in rpaths {
+        write_row(w, &[md_cell(&rpath.source_lc), md_code(&rpath.path)])?;
+    }
+    Ok(())
+}
+
+fn write_symbols(w: &mut dyn Write, arch: &ArchitectureReport, opts: &TextOptions) -> io::Result<()> {
+    let Some(symbols) = arch.symbols.as_deref().filter(|symbols| !symbols.is_empty()) else {
+        return Ok(());
+    };
+
+    writeln!(w)?;
+    writeln!(w, "### Symbols")?;
+    write_table_head(w, &["Address", "Type", "Bind", "Section", "Symbol", "Library"])?;
+    for sym in symbols {
+        write_row(w, &[
+            format!("`{}`", sym.addr_hex),
+            md_cell(sym.stab_type.as_deref().unwrap_or(&sym.kind)),
+            if sym.external { "EXT" } else { "LOC" }.to_string(),
+            sym.sectname.as_deref().map(md_code).unwrap_or_default(),
+            md_code(if opts.strip_underscore { &sym.source_name } else { &sym.name }),
+            sym.library.as_deref().map(md_code).unwrap_or_default(),
+        ])?;
+    }
+    Ok(())
+}
+
+fn write_strings(w: &mut dyn Write, arch: &ArchitectureReport, opts: &TextOptions) -> io::Result<()> {
+    let strings: Vec<_> = arch
+        .strings
+        .iter()
+        .flatten()
+        .filter(|s| s.value.len() >= symtab::min_length_for(&s.sectname, opts.section_min_lengths.as_ref(), opts.min_string_length))
+        .take(opts.max_strings.unwrap_or(usize::MAX))
+        .collect();
+    if strings.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(w)?;
+    writeln!(w, "### Strings")?;
+    write_table_head(w, &["Section", "Encoding", "Value"])?;
+    for s in strings {
+        write_row(w, &[md_code(&format!("{},{}", s.segname, s.sectname)), md_cell(&s.encoding), md_code(&s.value)])?;
+    }
+    Ok(())
+}
+
+pub fn write_markdown_report(w: &mut dyn Write, report: &MachOReport, opts: &TextOptions) -> io::Result<()> {
+    writeln!(w, "# Mach-O Report: {}", md_code(&opts.path))?;
+    for arch in &report.architectures {
+        writeln!(w)?;
+        writeln!(w, "## {} ({})", md_cell(&arch.cpu_type), md_cell(&arch.cpu_subtype))?;
+        write_header(w, arch)?;
+        write_segments(w, arch)?;
+        write_dylibs(w, arch)?;
+        write_rpaths(w, arch)?;
+        write_symbols(w, arch, opts)?;
+        write_strings(w, arch, opts)?;
+    }
+    Ok(())
+}
+
+
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pipes_are_escaped_in_cells() {
+        assert_eq!(md_cell("operator|"), "operator\\|");
+        assert_eq!(md_cell("a\nb"), "a b");
+        assert_eq!(md_code("_Z3fooRKSt8functionIFvvEE|x"), "`_Z3fooRKSt8functionIFvvEE\\|x`");
+    }
+
+    #[test]
+    fn code_spans_outgrow_backticks_inside() {
+        assert_eq!(md_code("__mh_execute_header"), "`__mh_execute_header`");
+        assert_eq!(md_code("a`b"), "``a`b``");
+        assert_eq!(md_code("`quoted`"), "`` `quoted` ``");
+        assert_eq!(md_code(""), "");
+    }
+
+    #[test]
+    fn tables_get_a_separator_row() {
+        let mut out = Vec::new();
+        write_table_head(&mut out, &["A", "B"]).unwrap();
+        write_row(&mut out, &["1".into(), md_code("x|y")]).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "\n| A | B |\n|---|---|\n| 1 | `x\\|y` |\n");
+    }
+}
@@ -6,4 +6,28 @@ pub mod sections;
 pub mod rpaths;
 pub mod dylibs;
 pub mod symtab;
-pub mod dyld;
\ No newline at end of file
+pub mod dyld;
+pub mod uuid;
+pub mod diagnostics;
+pub mod canonical;
+pub mod identify;
+pub mod objc;
+pub mod json;
+pub mod csv;
+pub mod dot;
+pub mod markdown;
+pub mod search;
+pub mod initializers;
+pub mod writer;
+pub mod exports;
+pub mod codesign;
+pub mod diff;
+pub mod verify;
+pub mod build_version;
+pub mod entry_point;
+pub mod function_starts;
+pub mod data_in_code;
+pub mod encryption;
+pub mod notes;
+pub mod sub_images;
+pub mod fileset;
\ No newline at end of file
@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NoteReport {
+    pub data_owner: String,
+    pub offset: u64, // slice relative
+    pub size: u64,
+    pub preview: Option<String>, // first 32 bytes as hex pairs, None when the region is outside the file
+}
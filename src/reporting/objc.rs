@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ObjCMethodReport {
+    pub name: String,           // selector
+    pub types: Option<String>,  // type encoding, e.g. v16@0:8
+    pub imp: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ObjCCategoryReport {
+    pub name: Option<String>,
+    pub class_name: Option<String>, // the class being extended, None if it couldn't be resolved
+    pub addr: u64,
+    pub addr_hex: String,
+    pub instance_methods: Vec<ObjCMethodReport>,
+    pub class_methods: Vec<ObjCMethodReport>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ObjCCategoriesReport {
+    pub cpu_type: String,
+    pub cpu_subtype: String,
+    pub categories: Vec<ObjCCategoryReport>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ObjCProtocolReport {
+    pub name: Option<String>,
+    pub addr: u64,
+    pub addr_hex: String,
+    pub protocols: Vec<String>, // protocols this one adopts (<NSObject>, ...)
+    pub instance_methods: Vec<ObjCMethodReport>,
+    pub class_methods: Vec<ObjCMethodReport>,
+    pub optional_instance_methods: Vec<ObjCMethodReport>,
+    pub optional_class_methods: Vec<ObjCMethodReport>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ObjCProtocolsReport {
+    pub cpu_type: String,
+    pub cpu_subtype: String,
+    pub protocols: Vec<ObjCProtocolReport>,
+}
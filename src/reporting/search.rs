@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct SearchHitReport {
+    pub segment: String,
+    pub section: String,
+    pub vmaddr: u64,
+    pub vmaddr_hex: String,
+    pub file_offset: Option<u64>, // None for zerofill sections
+    pub string: Option<String>, // containing string, --find-string only
+    pub exact: bool,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct SearchReport {
+    pub cpu_type: String,
+    pub cpu_subtype: String,
+    pub needle: String,
+    pub hits: Vec<SearchHitReport>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScanMatchReport {
+    pub file_offset: u64,
+    pub file_offset_hex: String,
+    pub arch: Option<String>, // None outside every slice (fat header, padding)
+    pub segment: Option<String>,
+    pub section: Option<String>,
+    pub vmaddr: Option<u64>,
+    pub vmaddr_hex: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScanReport {
+    pub pattern: String,
+    pub scope: Option<String>, // --scan-in, None for the whole file
+    pub matches: Vec<ScanMatchReport>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct PeekReport {
+    pub arch: Option<String>, // --arch, None for a plain file offset
+    pub offset: u64, // as given, relative to the slice with --arch
+    pub file_offset: u64,
+    pub file_offset_hex: String,
+    pub len: u64,
+    pub bytes: String, // space separated hex pairs
+}
@@ -1,6 +1,6 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct SectionReport {
     pub name: String,
     pub segment: String,
@@ -8,3 +8,68 @@ pub struct SectionReport {
     pub addr: u64,
     pub size: u64,
 }
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct IndirectSymbolRangeReport {
+    pub first_index: u32, // reserved1
+    pub count: u64,       // size / entry size
+    pub entry_size: u64,
+    pub out_of_bounds: bool, // range runs past nindirectsyms
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct SectionInfoReport {
+    pub cpu_type: String,
+    pub cpu_subtype: String,
+    pub segment: String,
+    pub name: String,
+    pub kind: String,
+    pub addr: u64,
+    pub size: u64,
+    pub offset: u32,
+    pub align: u32, // power of 2
+    pub reloff: u32,
+    pub nreloc: u32,
+    pub section_type: String,
+    pub attributes: Vec<String>,
+    pub reserved1: u32,
+    pub reserved2: u32,
+    pub reserved3: Option<u32>,
+    pub indirect_symbols: Option<IndirectSymbolRangeReport>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct UnknownSectionReport {
+    pub segment: String,
+    pub name: String,
+    pub segname_hex: String, // all 16 raw bytes, anything after the NUL included
+    pub sectname_hex: String,
+    pub segname_ascii: String, // same bytes, non-printables as '.'
+    pub sectname_ascii: String,
+    pub kind: String, // Unknown or Other
+    pub section_type: String,
+    pub count: usize, // how many times this exact (segname, sectname, type) shows up across all slices
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct UnknownSectionsReport {
+    pub total: usize,
+    pub sections: Vec<UnknownSectionReport>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct LiteralSectionReport {
+    pub segment: String,
+    pub name: String,
+    pub addr: u64,
+    pub literal_size: usize, // 4, 8 or 16
+    pub literals: Vec<String>, // raw bytes of each entry in hex, file order
+}
+
+// --literals, one per slice
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct LiteralsReport {
+    pub cpu_type: String,
+    pub cpu_subtype: String,
+    pub sections: Vec<LiteralSectionReport>,
+}
@@ -1,8 +1,9 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use super::diagnostics::DiagnosticReport;
 use super::sections::SectionReport;
 
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct SegmentReport {
     pub name: String,
     pub vmaddr: u64,
@@ -11,5 +12,26 @@ pub struct SegmentReport {
     pub filesize: u64,
     pub maxprot: String,
     pub initprot: String,
+    pub alignment: Option<u64>, // largest power of two vmaddr is a multiple of, None for vmaddr 0
     pub sections: Vec<SectionReport>,
 }
+
+// Address range the segments cover, __PAGEZERO left out
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct VmSpanReport {
+    pub low: u64,
+    pub high: u64, // exclusive
+    pub size: u64,
+}
+
+// --detect-appended, one per slice
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct AppendedDataReport {
+    pub cpu_type: String,
+    pub cpu_subtype: String,
+    pub image_end: u64, // slice relative end of the last segment / linkedit blob
+    pub slice_size: u64,
+    pub appended_offset: Option<u64>, // file offset of whatever follows, None when the image fills the slice
+    pub appended_size: u64,
+    pub diagnostics: Vec<DiagnosticReport>, // appended-data when it's more than the threshold
+}
@@ -0,0 +1,7 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubImageReport {
+    pub kind: String, // FRAMEWORK, CLIENT, UMBRELLA or LIBRARY
+    pub name: String,
+}
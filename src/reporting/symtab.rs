@@ -1,9 +1,10 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SymbolReport {
-    pub name: String,
+    pub name: String, // raw, exactly what's in the string table
+    pub source_name: String, // name with one leading '_' dropped, what --strip-underscore shows
     pub value: u64,
     pub addr: u64, // decimal version of addr/value, useful enough for maths but I would personally prefer hex 
     pub addr_hex: String, // human readable version of addr
@@ -13,11 +14,41 @@ pub struct SymbolReport {
     pub segname: Option<String>,
     pub external: bool,
     pub debug: bool,
+    pub is_import: bool, // external + undefined here (incl. stub / lazy / got reclassified)
+    pub is_export: bool, // external + defined here
+    pub stab_type: Option<String>, // only set for debug (STAB) entries
+    pub library: Option<String>, // dylib an import binds to, None in flat namespace images
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct StringReport {
     pub value: String,
+    pub encoding: String, // ascii, utf8 or utf16le
+    pub raw_hex: Option<String>, // on-disk bytes (BOM included), only with --raw-strings
     pub segname: String,
     pub sectname: String,
 }
+
+// LC_SYMTAB's raw layout, offsets are slice relative
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SymtabStatsReport {
+    pub symoff: u32,
+    pub nsyms: u32,
+    pub stroff: u32,
+    pub strsize: u32,
+    pub referenced_string_bytes: u32,   // covered by some nlist's n_strx string
+    pub unreferenced_string_bytes: u32, // slack, strsize minus the above
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OsoEntryReport {
+    pub path: String,
+    pub mtime: u64, // seconds since epoch, 0 when the linker zeroed it
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OsoReport {
+    pub cpu_type: String,
+    pub cpu_subtype: String,
+    pub objects: Vec<OsoEntryReport>,
+}
@@ -0,0 +1,8 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct UuidReport {
+    pub cpu_type: String,
+    pub cpu_subtype: String,
+    pub uuid: Option<String>,
+}
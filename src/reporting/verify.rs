@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+
+use crate::macho::diagnostics::Severity;
+use crate::reporting::diagnostics::DiagnosticReport;
+use crate::reporting::macho::MachOReport;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VerifyArchReport {
+    pub cpu_type: String,
+    pub cpu_subtype: String,
+    pub diagnostics: Vec<DiagnosticReport>,
+}
+
+// `moscope verify`: the diagnostics of a full parse and nothing else
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VerifyReport {
+    pub errors: usize,
+    pub warnings: usize,
+    pub architectures: Vec<VerifyArchReport>,
+    pub slice_consistency: Vec<DiagnosticReport>,
+}
+
+pub fn build_verify_report(report: &MachOReport) -> VerifyReport {
+    let architectures: Vec<VerifyArchReport> = report.architectures.iter().map(|arch| VerifyArchReport {
+        cpu_type: arch.cpu_type.clone(),
+        cpu_subtype: arch.cpu_subtype.clone(),
+        diagnostics: arch.diagnostics.clone().unwrap_or_default(),
+    }).collect();
+    let slice_consistency = report.slice_consistency.clone().unwrap_or_default();
+
+    let all: Vec<&DiagnosticReport> = architectures.iter().flat_map(|a| &a.diagnostics).chain(&slice_consistency).collect();
+    VerifyReport {
+        errors: all.iter().filter(|d| d.severity == Severity::Error.as_str()).count(),
+        warnings: all.iter().filter(|d| d.severity == Severity::Warning.as_str()).count(),
+        architectures,
+        slice_consistency,
+    }
+}
+
+
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macho::constants::*;
+    use crate::macho::diagnostics::Diagnostic;
+    use crate::macho::header::{MachHeader64, MachOHeader};
+    use crate::reporting::macho::{build_architecture_report, ReportOptions};
+
+    #[test]
+    fn errors_and_warnings_are_counted_across_slices() {
+        let header = MachOHeader::Header64(MachHeader64 {
+            magic: 0xfeedfacf,
+            cputype: CPU_TYPE_ARM64,
+            cpusubtype: 0,
+            filetype: MH_EXECUTE,
+            ncmds: 0,
+            sizeofcmds: 0,
+            flags: 0,
+            reserved: 0,
+        });
+        let opts = ReportOptions {
+            include_header: true,
+            include_segments: true,
+            include_dylibs: true,
+            include_rpaths: true,
+            include_loadcmds: true,
+            include_symbols: true,
+            include_strings: true,
+            include_fixups: true,
+            include_diagnostics: true,
+            dump_unknown_load_commands: false,
+        };
+        let diagnostics = [Diagnostic::error("bad", "bad".into()), Diagnostic::warning("odd", "odd".into())];
+        let arch = build_architecture_report(CPU_TYPE_ARM64, 0, &header, &[], &[], &[], &[], &[], &[], &[], &[], &diagnostics, None, None, &opts);
+
+        let consistency = Diagnostic::warning("uuid-shared", "shared".into()).build_report();
+        let report = MachOReport { is_fat: true, uuids: Vec::new(), slice_consistency: Some(vec![consistency]), architectures: vec![arch] };
+        let verify = build_verify_report(&report);
+
+        assert_eq!((verify.errors, verify.warnings), (1, 2));
+        assert_eq!(verify.architectures[0].cpu_subtype, "arm64 (ARM64_ALL)");
+        assert_eq!(verify.architectures[0].diagnostics.len(), 2);
+    }
+}
@@ -0,0 +1,635 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::{self, Write};
+
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::macho::{build_version, codesign, data_in_code, diagnostics, dyld, dylibs, encryption, entry_point, export_trie, fileset, function_starts, header, initializers, load_commands, notes, objc, rpaths, search, sections, segments, source_version, sub_images, symtab, uuid};
+use crate::reporting::canonical;
+use crate::reporting::csv::{self, CsvStringFilter, CsvTable};
+use crate::reporting::dot;
+use crate::reporting::markdown;
+use crate::reporting::codesign::{CodeSignatureReport, SignatureCheckReport};
+use crate::reporting::diff::DiffReport;
+use crate::reporting::exports::{ExportsReport, ReexportsReport};
+use crate::reporting::function_starts::FunctionStartsReport;
+use crate::reporting::identify::{IdentityReport, ScannedFileReport};
+use crate::reporting::initializers::InitializersReport;
+use crate::reporting::json;
+use crate::reporting::load_commands::{LinkEditOverviewReport, LoadCommandMapReport};
+use crate::reporting::macho::{AllReport, MachOReport};
+use crate::reporting::objc::{ObjCCategoriesReport, ObjCProtocolsReport};
+use crate::reporting::search::{PeekReport, ScanReport, SearchReport};
+use crate::reporting::sections::{LiteralsReport, SectionInfoReport, UnknownSectionsReport};
+use crate::reporting::segments::AppendedDataReport;
+use crate::reporting::symtab::OsoReport;
+use crate::reporting::uuid::UuidReport;
+use crate::reporting::verify::VerifyReport;
+
+/*
+One writer per --format. main picks one, hands it a finished report and a sink
+(stdout today, anything implementing Write tomorrow):
+
+    OutputFormat::Text  --> TextWriter      the colored summaries
+    OutputFormat::Json  --> JsonWriter      pretty / --json-indent / --canonical-json
+    OutputFormat::Plist --> PlistWriter     XML plist
+    OutputFormat::Csv   --> CsvWriter       one --csv-table of the full report
+    OutputFormat::Dot   --> DotWriter       GraphViz graph of the dylib dependencies
+    OutputFormat::Markdown --> MarkdownWriter  headings and tables, no colors
+
+The report is whatever the run produced: the full MachOReport, or the Vec of per-slice
+reports a fast path (--uuid, --lc-map, ...) builds. JSON and plist take anything Serialize,
+text anything TextReport. CSV, DOT and Markdown only exist for the full MachOReport. New formats are a new impl, not another arm in main's match.
+Tests write into a Vec<u8>.
+*/
+pub trait OutputWriter<R: ?Sized> {
+    fn write_report(&self, report: &R, w: &mut dyn Write) -> Result<(), Box<dyn Error>>;
+}
+
+
+pub struct JsonWriter {
+    pub indent: usize,
+    pub canonical: bool,
+}
+
+impl<R: Serialize + ?Sized> OutputWriter<R> for JsonWriter {
+    fn write_report(&self, report: &R, w: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+        let text = if self.canonical {
+            canonical::to_canonical_json(&report)?
+        } else {
+            json::to_json_with_indent(&report, self.indent)?
+        };
+        writeln!(w, "{}", text)?;
+        Ok(())
+    }
+}
+
+
+// Apple's XML plist flavour, same thing `plutil -convert xml1` produces
+pub struct PlistWriter;
+
+impl<R: Serialize + ?Sized> OutputWriter<R> for PlistWriter {
+    fn write_report(&self, report: &R, w: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+        let mut buf = Vec::new();
+        plist::to_writer_xml(&mut buf, &report)?;
+        w.write_all(&buf)?;
+        writeln!(w)?;
+        Ok(())
+    }
+}
+
+
+// Flat tables can't hold the whole report, --csv-table picks which one is written
+pub struct CsvWriter {
+    pub table: CsvTable,
+    pub options: TextOptions, // strings are filtered and symbols named like the text output
+}
+
+impl OutputWriter<MachOReport> for CsvWriter {
+    fn write_report(&self, report: &MachOReport, w: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+        let filter = CsvStringFilter {
+            min_length: self.options.min_string_length,
+            section_min_lengths: self.options.section_min_lengths.as_ref(),
+            max_count: self.options.max_strings,
+        };
+        csv::write_csv_table(w, report, self.table, self.options.strip_underscore, &filter)?;
+        Ok(())
+    }
+}
+
+
+// `moscope binary --format dot | dot -Tpng -o deps.png`
+pub struct DotWriter {
+    pub name: String, // root label when the binary has no install name, the input's file name
+}
+
+impl OutputWriter<MachOReport> for DotWriter {
+    fn write_report(&self, report: &MachOReport, w: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+        dot::write_dot_graph(w, report, &self.name)?;
+        Ok(())
+    }
+}
+
+
+pub struct MarkdownWriter {
+    pub options: TextOptions, // same string filters and symbol names as the text output
+}
+
+impl OutputWriter<MachOReport> for MarkdownWriter {
+    fn write_report(&self, report: &MachOReport, w: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+        markdown::write_markdown_report(w, report, &self.options)?;
+        Ok(())
+    }
+}
+
+
+/*
+Text is rendered from the report alone, the same one JSON gets, so the two can't drift.
+What's left in TextOptions only changes how it's shown (colors, names, string filters),
+never what was parsed. Sections the --no-* switches dropped are None in the report and
+simply aren't printed.
+*/
+pub struct TextOptions {
+    pub count_sections_by_kind: bool,
+    pub legend: bool,                   // --legend, main already turned it off for uncolored output
+    pub canonical_names: bool,
+    pub strip_underscore: bool,
+    pub min_string_length: usize,
+    pub section_min_lengths: Option<HashMap<String, usize>>,
+    pub max_strings: Option<usize>,
+    pub path: String,                   // the input as it should be shown, --uuid lines end with it
+}
+
+pub trait TextReport {
+    fn write_text(&self, w: &mut dyn Write, opts: &TextOptions) -> io::Result<()>;
+}
+
+pub struct TextWriter {
+    pub options: TextOptions,
+}
+
+impl<R: TextReport + ?Sized> OutputWriter<R> for TextWriter {
+    fn write_report(&self, report: &R, w: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+        report.write_text(w, &self.options)?;
+        Ok(())
+    }
+}
+
+// The fast paths produce one report per slice, printed back to back
+impl<T: TextReport> TextReport for [T] {
+    fn write_text(&self, w: &mut dyn Write, opts: &TextOptions) -> io::Result<()> {
+        for report in self {
+            report.write_text(w, opts)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: TextReport> TextReport for Vec<T> {
+    fn write_text(&self, w: &mut dyn Write, opts: &TextOptions) -> io::Result<()> {
+        self.as_slice().write_text(w, opts)
+    }
+}
+
+impl TextReport for MachOReport {
+    fn write_text(&self, w: &mut dyn Write, opts: &TextOptions) -> io::Result<()> {
+        writeln!(w, "{}", "Mach-O Report:".green().bold())?;
+        if opts.legend && self.architectures.iter().any(|arch| arch.segments.is_some()) {
+            segments::write_section_legend(w)?;
+        }
+        for arch in &self.architectures {
+            if let Some(header) = &arch.header {
+                header::write_header_summary(w, header, arch.uuid.as_deref(), arch.two_level_namespace)?;
+                if let Some(info) = &arch.encryption {
+                    encryption::write_encryption_info(w, info)?;
+                }
+                if let Some(sha) = &arch.slice_sha256 {
+                    writeln!(w, "{} {}", "Slice SHA-256:".yellow().bold(), sha)?;
+                }
+                if let Some(cdhash) = arch.code_signature.as_ref().and_then(|cs| cs.cdhash.as_ref()) {
+                    writeln!(w, "{} {}", "CDHash:".yellow().bold(), cdhash)?;
+                }
+                if let Some(align) = arch.fat_align {
+                    writeln!(w, "{} 2^{} (0x{:x})", "Fat Alignment:".yellow().bold(), align, 1u64.checked_shl(align).unwrap_or(0))?;
+                }
+                load_commands::write_prebind_cksum(w, arch.prebind_checksum)?;
+                if let Some(bv) = &arch.build_version {
+                    build_version::write_build_version(w, bv)?;
+                }
+                if let Some(min) = &arch.min_version {
+                    load_commands::write_min_version(w, min)?;
+                }
+                if let Some(version) = &arch.source_version {
+                    source_version::write_source_version(w, version)?;
+                }
+                if let Some(triple) = &arch.target_triple {
+                    build_version::write_target_triple(w, triple)?;
+                }
+            }
+            if let Some(entry) = &arch.entry_point {
+                entry_point::write_entry_point(w, entry)?;
+            }
+            if let Some(segs) = &arch.segments {
+                segments::write_segments_summary(w, segs)?;
+                if opts.count_sections_by_kind {
+                    segments::write_section_kind_counts(w, &segments::count_sections_by_kind(segs))?;
+                }
+                segments::write_pagezero_summary(w, &arch.filetype, arch.pagezero_size, arch.implicit_pagezero)?;
+                if let Some(span) = &arch.vm_span {
+                    segments::write_vm_span_summary(w, span)?;
+                }
+            }
+            if let Some(entries) = &arch.data_in_code {
+                data_in_code::write_data_in_code(w, entries)?;
+            }
+            if let Some(libs) = &arch.dylibs {
+                dylibs::write_dylibs_summary(w, libs, opts.canonical_names)?;
+            }
+            if let Some(ordinals) = &arch.ordinal_map {
+                dylibs::write_ordinal_map(w, ordinals)?;
+            }
+            if let Some(subs) = &arch.sub_images {
+                sub_images::write_sub_images_summary(w, subs)?;
+            }
+            if let Some(paths) = &arch.rpaths {
+                rpaths::write_rpaths_summary(w, paths)?;
+            }
+            rpaths::write_dyld_environment(w, &arch.dyld_environment)?;
+            if let Some(lcs) = &arch.load_commands {
+                load_commands::write_load_commands(w, lcs)?;
+                load_commands::write_symseg(w, arch.symseg.as_ref())?;
+            }
+            if let Some(linkedit) = &arch.linkedit_data {
+                load_commands::write_linkedit_data_summary(w, linkedit)?;
+            }
+            if let Some(entries) = &arch.fileset_entries {
+                fileset::write_fileset_entries(w, entries)?;
+            }
+            if let Some(entries) = &arch.notes {
+                notes::write_notes(w, entries)?;
+            }
+            if let Some(info) = &arch.dyld_info {
+                dyld::write_dyld_info(w, info)?;
+            }
+            if let Some(binds) = &arch.bound_symbols {
+                dyld::write_bound_symbols(w, binds)?;
+            }
+            if arch.stripped {
+                symtab::write_stripped_notice(w)?;
+            } else if let Some(symbols) = &arch.symbols {
+                symtab::write_symbols_summary(w, symbols, opts.strip_underscore)?;
+                if let Some(stats) = &arch.symtab_stats {
+                    symtab::write_symtab_stats(w, stats)?;
+                }
+            }
+            if let Some(strings) = &arch.strings {
+                symtab::write_strings_summary(w, strings, opts.min_string_length, opts.section_min_lengths.as_ref(), opts.max_strings)?;
+            }
+            if let Some(fixups) = &arch.fixups {
+                dyld::write_fixups_summary(w, fixups)?;
+            }
+            if let Some(diags) = &arch.diagnostics {
+                diagnostics::write_diagnostics_summary(w, diags)?;
+            }
+        }
+
+        if let Some(consistency) = &self.slice_consistency {
+            diagnostics::write_titled_diagnostic_reports(w, "Slice Consistency", consistency)?;
+        }
+        Ok(())
+    }
+}
+
+impl TextReport for AllReport {
+    fn write_text(&self, w: &mut dyn Write, opts: &TextOptions) -> io::Result<()> {
+        self.report.write_text(w, opts)?;
+        if let Some(maps) = &self.lc_maps {
+            maps.write_text(w, opts)?;
+        }
+        if let Some(linkedit) = &self.linkedit {
+            linkedit.write_text(w, opts)?;
+        }
+        if let Some(signatures) = &self.code_signatures {
+            signatures.write_text(w, opts)?;
+        }
+        if let Some(checks) = &self.signature_checks {
+            checks.write_text(w, opts)?;
+        }
+        if let Some(inits) = &self.initializers {
+            inits.write_text(w, opts)?;
+        }
+        if let Some(exports) = &self.exports {
+            exports.write_text(w, opts)?;
+        }
+        if let Some(reexports) = &self.reexports {
+            reexports.write_text(w, opts)?;
+        }
+        if let Some(categories) = &self.objc_categories {
+            categories.write_text(w, opts)?;
+        }
+        if let Some(protocols) = &self.objc_protocols {
+            protocols.write_text(w, opts)?;
+        }
+        if let Some(oso) = &self.oso {
+            oso.write_text(w, opts)?;
+        }
+        if let Some(unknown) = &self.unknown_sections {
+            unknown.write_text(w, opts)?;
+        }
+        if let Some(literals) = &self.literals {
+            literals.write_text(w, opts)?;
+        }
+        if let Some(starts) = &self.function_starts {
+            starts.write_text(w, opts)?;
+        }
+        if let Some(appended) = &self.appended_data {
+            appended.write_text(w, opts)?;
+        }
+        Ok(())
+    }
+}
+
+impl TextReport for UuidReport {
+    fn write_text(&self, w: &mut dyn Write, opts: &TextOptions) -> io::Result<()> {
+        uuid::write_uuid_summary(w, self, &opts.path)
+    }
+}
+
+impl TextReport for IdentityReport {
+    fn write_text(&self, w: &mut dyn Write, _opts: &TextOptions) -> io::Result<()> {
+        // No colors or labels, this is meant to be grepped/split
+        writeln!(w, "{}", self.identity)
+    }
+}
+
+impl TextReport for ScannedFileReport {
+    fn write_text(&self, w: &mut dyn Write, _opts: &TextOptions) -> io::Result<()> {
+        for identity in &self.identities {
+            writeln!(w, "{} {}", self.relative_path, identity.identity)?;
+        }
+        Ok(())
+    }
+}
+
+impl TextReport for OsoReport {
+    fn write_text(&self, w: &mut dyn Write, _opts: &TextOptions) -> io::Result<()> {
+        symtab::write_oso_summary(w, self)
+    }
+}
+
+impl TextReport for LinkEditOverviewReport {
+    fn write_text(&self, w: &mut dyn Write, _opts: &TextOptions) -> io::Result<()> {
+        load_commands::write_linkedit_overview(w, self)
+    }
+}
+
+impl TextReport for LoadCommandMapReport {
+    fn write_text(&self, w: &mut dyn Write, _opts: &TextOptions) -> io::Result<()> {
+        load_commands::write_lc_map(w, self)
+    }
+}
+
+impl TextReport for ObjCCategoriesReport {
+    fn write_text(&self, w: &mut dyn Write, _opts: &TextOptions) -> io::Result<()> {
+        objc::write_categories_summary(w, self)
+    }
+}
+
+impl TextReport for ObjCProtocolsReport {
+    fn write_text(&self, w: &mut dyn Write, _opts: &TextOptions) -> io::Result<()> {
+        objc::write_protocols_summary(w, self)
+    }
+}
+
+impl TextReport for InitializersReport {
+    fn write_text(&self, w: &mut dyn Write, _opts: &TextOptions) -> io::Result<()> {
+        initializers::write_initializers_summary(w, self)
+    }
+}
+
+impl TextReport for ExportsReport {
+    fn write_text(&self, w: &mut dyn Write, _opts: &TextOptions) -> io::Result<()> {
+        export_trie::write_exports_summary(w, self)
+    }
+}
+
+impl TextReport for ReexportsReport {
+    fn write_text(&self, w: &mut dyn Write, _opts: &TextOptions) -> io::Result<()> {
+        export_trie::write_reexports_summary(w, self)
+    }
+}
+
+impl TextReport for CodeSignatureReport {
+    fn write_text(&self, w: &mut dyn Write, _opts: &TextOptions) -> io::Result<()> {
+        codesign::write_code_signature_summary(w, self)
+    }
+}
+
+impl TextReport for SignatureCheckReport {
+    fn write_text(&self, w: &mut dyn Write, _opts: &TextOptions) -> io::Result<()> {
+        codesign::write_signature_check(w, self)
+    }
+}
+
+impl TextReport for SectionInfoReport {
+    fn write_text(&self, w: &mut dyn Write, _opts: &TextOptions) -> io::Result<()> {
+        sections::write_section_info(w, self)
+    }
+}
+
+impl TextReport for SearchReport {
+    fn write_text(&self, w: &mut dyn Write, _opts: &TextOptions) -> io::Result<()> {
+        search::write_search_report(w, self)
+    }
+}
+
+impl TextReport for ScanReport {
+    fn write_text(&self, w: &mut dyn Write, _opts: &TextOptions) -> io::Result<()> {
+        search::write_scan_report(w, self)
+    }
+}
+
+impl TextReport for AppendedDataReport {
+    fn write_text(&self, w: &mut dyn Write, _opts: &TextOptions) -> io::Result<()> {
+        segments::write_appended_data(w, self)
+    }
+}
+
+impl TextReport for PeekReport {
+    fn write_text(&self, w: &mut dyn Write, _opts: &TextOptions) -> io::Result<()> {
+        search::write_peek_report(w, self)
+    }
+}
+
+impl TextReport for LiteralsReport {
+    fn write_text(&self, w: &mut dyn Write, _opts: &TextOptions) -> io::Result<()> {
+        sections::write_literals(w, self)
+    }
+}
+
+impl TextReport for FunctionStartsReport {
+    fn write_text(&self, w: &mut dyn Write, _opts: &TextOptions) -> io::Result<()> {
+        function_starts::write_function_starts(w, self)
+    }
+}
+
+impl TextReport for UnknownSectionsReport {
+    fn write_text(&self, w: &mut dyn Write, _opts: &TextOptions) -> io::Result<()> {
+        sections::write_unknown_sections(w, self)
+    }
+}
+
+
+impl TextReport for DiffReport {
+    fn write_text(&self, w: &mut dyn Write, _opts: &TextOptions) -> io::Result<()> {
+        writeln!(w, "{}", "Report Diff".green().bold())?;
+        writeln!(w, "----------------------------------------")?;
+        for change in &self.changes {
+            match (&change.old, &change.new) {
+                (Some(old), Some(new)) => writeln!(w, "{} {}: {} -> {}", "~".yellow().bold(), change.path, old, new)?,
+                (None, Some(new)) => writeln!(w, "{} {}: {}", "+".green().bold(), change.path, new)?,
+                (Some(old), None) => writeln!(w, "{} {}: {}", "-".red().bold(), change.path, old)?,
+                (None, None) => {}
+            }
+        }
+        if self.changes.is_empty() {
+            writeln!(w, "No differences")?;
+        }
+        writeln!(w, "----------------------------------------")?;
+        Ok(())
+    }
+}
+
+impl TextReport for VerifyReport {
+    fn write_text(&self, w: &mut dyn Write, _opts: &TextOptions) -> io::Result<()> {
+        for arch in &self.architectures {
+            diagnostics::write_titled_diagnostic_reports(w, &format!("Diagnostics ({})", arch.cpu_subtype), &arch.diagnostics)?;
+        }
+        diagnostics::write_titled_diagnostic_reports(w, "Slice Consistency", &self.slice_consistency)?;
+
+        let summary = format!("{} error(s), {} warning(s)", self.errors, self.warnings);
+        match (self.errors, self.warnings) {
+            (0, 0) => writeln!(w, "{} {}", "Verify:".green().bold(), "OK".green())?,
+            (0, _) => writeln!(w, "{} {}", "Verify:".yellow().bold(), summary)?,
+            _ => writeln!(w, "{} {}", "Verify:".red().bold(), summary)?,
+        }
+        Ok(())
+    }
+}
+
+
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macho::header::{MachHeader64, MachOHeader};
+    use crate::macho::constants::*;
+    use crate::reporting::macho::{build_architecture_report, ReportOptions};
+    use crate::test_util::strip_ansi;
+
+    fn empty_report() -> MachOReport {
+        MachOReport { is_fat: false, uuids: vec!["11223344-5566-7788-99AA-BBCCDDEEFF00".into()], slice_consistency: None, architectures: Vec::new() }
+    }
+
+    fn render<R: ?Sized>(writer: &dyn OutputWriter<R>, report: &R) -> String {
+        let mut out = Vec::new();
+        writer.write_report(report, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn text_writer() -> TextWriter {
+        TextWriter {
+            options: TextOptions {
+                count_sections_by_kind: false,
+                legend: false,
+                canonical_names: false,
+                strip_underscore: false,
+                min_string_length: 4,
+                section_min_lengths: None,
+                max_strings: None,
+                path: "a.out".into(),
+            },
+        }
+    }
+
+    fn everything() -> ReportOptions {
+        ReportOptions {
+            include_header: true,
+            include_segments: true,
+            include_dylibs: true,
+            include_rpaths: true,
+            include_loadcmds: true,
+            include_symbols: true,
+            include_strings: true,
+            include_fixups: true,
+            include_diagnostics: true,
+            dump_unknown_load_commands: false,
+        }
+    }
+
+    #[test]
+    fn json_writer_round_trips() {
+        let report = empty_report();
+        let out = render(&JsonWriter { indent: 2, canonical: false }, &report);
+
+        assert!(out.ends_with("}\n"));
+        assert_eq!(serde_json::from_str::<MachOReport>(&out).unwrap(), report);
+    }
+
+    #[test]
+    fn canonical_json_is_one_sorted_line() {
+        let out = render(&JsonWriter { indent: 2, canonical: true }, &empty_report());
+
+        assert_eq!(out.lines().count(), 1);
+        assert!(out.find("\"architectures\"").unwrap() < out.find("\"uuids\"").unwrap());
+    }
+
+    #[test]
+    fn plist_writer_emits_xml() {
+        let out = render(&PlistWriter, &empty_report());
+
+        assert!(out.starts_with("<?xml"));
+        assert!(out.contains("<key>uuids</key>"));
+    }
+
+    #[test]
+    fn text_writer_renders_each_slice_from_the_report() {
+        let header = MachOHeader::Header64(MachHeader64 {
+            magic: 0xfeedfacf,
+            cputype: CPU_TYPE_ARM64,
+            cpusubtype: 0,
+            filetype: MH_EXECUTE,
+            ncmds: 0,
+            sizeofcmds: 0,
+            flags: 0,
+            reserved: 0,
+        });
+
+        let arch = build_architecture_report(
+            CPU_TYPE_ARM64, 0, &header, &[], &[], &[], &[], &[], &[], &[], &[], &[],
+            Some("ab".repeat(32)), Some(0), &everything(),
+        );
+        let mut report = empty_report();
+        report.architectures.push(arch);
+        report.architectures[0].fat_align = Some(14);
+
+        let out = strip_ansi(&render(&text_writer(), &report));
+        assert!(out.starts_with("Mach-O Report:\n"));
+        assert!(out.contains("Mach-O Header Summary"));
+        assert!(out.contains("Word size    : 64-bit"));
+        assert!(out.contains(&format!("Slice SHA-256: {}", "ab".repeat(32))));
+        assert!(out.contains("Prebind Checksum: 0x00000000"));
+        assert!(out.contains("Fat Alignment: 2^14 (0x4000)"));
+        // no segments at all, and no MH_IMPLICIT_PAGEZERO either
+        assert!(out.contains("Page zero    : none\n"));
+
+        // --no-header drops the block, the page zero line only needs the top-level filetype
+        report.architectures[0].header = None;
+        let out = strip_ansi(&render(&text_writer(), &report));
+        assert!(!out.contains("Mach-O Header Summary"));
+        assert!(!out.contains("Slice SHA-256"));
+        assert!(out.contains("Page zero    : none\n"));
+    }
+
+    #[test]
+    fn fast_path_reports_go_through_every_writer() {
+        let reports = vec![
+            UuidReport { cpu_type: "ARM64".into(), cpu_subtype: "arm64".into(), uuid: Some("11223344-5566-7788-99AA-BBCCDDEEFF00".into()) },
+            UuidReport { cpu_type: "X86_64".into(), cpu_subtype: "x86_64".into(), uuid: None },
+        ];
+
+        let text = strip_ansi(&render(&text_writer(), &reports));
+        assert_eq!(text, "UUID: 11223344-5566-7788-99AA-BBCCDDEEFF00 (arm64) a.out\nUUID: <no LC_UUID> (x86_64) a.out\n");
+
+        let json = render(&JsonWriter { indent: 2, canonical: false }, &reports);
+        assert_eq!(serde_json::from_str::<Vec<UuidReport>>(&json).unwrap(), reports);
+
+        assert!(render(&PlistWriter, &reports).contains("<string>x86_64</string>"));
+    }
+}
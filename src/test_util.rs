@@ -0,0 +1,464 @@
+// File Purpose: Build small, valid Mach-O images in memory so tests don't need checked-in fixtures
+// Only compiled for this crate's own tests or with the `test-util` feature (integration tests enable it)
+
+use crate::macho::constants::*;
+
+/*
+MachOBuilder lays a thin Mach-O out like ld64 would, minus everything nobody asked for:
+
+    +---------------------------+ 0
+    | mach_header(_64)          |
+    | load commands             |   in the order they were added, LC_SYMTAB last
+    +---------------------------+ aligned to 16
+    | segment 0 section data    |   sections back to back, each aligned to 8
+    | segment 1 section data    |
+    | ...                       |
+    +---------------------------+
+    | nlist table               |   only if symbols were added
+    | string table              |
+    +---------------------------+
+
+Section addresses are seg.vmaddr + (offset of the section inside the segment), so file layout and
+VM layout agree and MachOMemoryImage sees the same bytes the parsers do. Put zerofill sections last
+like ld64 does, they get an address but no file bytes (offset 0).
+
+    let data = MachOBuilder::new_64(CPU_TYPE_ARM64, 0)
+        .uuid([0xAB; 16])
+        .segment(SegmentSpec::new("__TEXT", 0x1_0000_0000).section("__cstring", S_CSTRING_LITERALS, b"hi\0"))
+        .symbol("_main", N_SECT | N_EXT, 1, 0x1_0000_0000)
+        .build();
+*/
+
+pub struct SectionSpec {
+    pub sectname: String,
+    pub flags: u32,
+    pub data: Vec<u8>,
+    pub reserved1: u32,
+    pub reserved2: u32,
+}
+
+impl SectionSpec {
+    // Zerofill sections only take up VM space, their data is just used for the size
+    fn is_zerofill(&self) -> bool {
+        matches!(self.flags & SECTION_TYPE, S_ZEROFILL | S_GB_ZEROFILL | S_THREAD_LOCAL_ZEROFILL)
+    }
+}
+
+pub struct SegmentSpec {
+    pub segname: String,
+    pub vmaddr: u64,
+    pub vmsize: Option<u64>, // defaults to the file size rounded up to a page
+    pub maxprot: i32,
+    pub initprot: i32,
+    pub sections: Vec<SectionSpec>,
+}
+
+impl SegmentSpec {
+    pub fn new(segname: &str, vmaddr: u64) -> Self {
+        SegmentSpec { segname: segname.to_string(), vmaddr, vmsize: None, maxprot: 7, initprot: 3, sections: Vec::new() }
+    }
+
+    pub fn vmsize(mut self, vmsize: u64) -> Self {
+        self.vmsize = Some(vmsize);
+        self
+    }
+
+    pub fn protection(mut self, maxprot: i32, initprot: i32) -> Self {
+        self.maxprot = maxprot;
+        self.initprot = initprot;
+        self
+    }
+
+    pub fn section(mut self, sectname: &str, flags: u32, data: &[u8]) -> Self {
+        self.sections.push(SectionSpec { sectname: sectname.to_string(), flags, data: data.to_vec(), reserved1: 0, reserved2: 0 });
+        self
+    }
+
+    fn file_size(&self) -> u64 {
+        self.sections.iter().filter(|s| !s.is_zerofill()).map(|s| align(s.data.len() as u64, 8)).sum()
+    }
+}
+
+struct SymbolSpec {
+    name: String,
+    n_type: u8,
+    n_sect: u8,
+    n_desc: u16,
+    n_value: u64,
+}
+
+enum CommandSpec {
+    Segment(SegmentSpec),
+    Uuid([u8; 16]),
+    Dylib { cmd: u32, path: String, current_version: u32, compatibility_version: u32 },
+    Rpath(String),
+    Raw { cmd: u32, payload: Vec<u8> },
+}
+
+pub struct MachOBuilder {
+    is_64: bool,
+    is_be: bool,
+    cputype: i32,
+    cpusubtype: i32,
+    filetype: u32,
+    flags: u32,
+    commands: Vec<CommandSpec>,
+    symbols: Vec<SymbolSpec>,
+}
+
+fn align(value: u64, to: u64) -> u64 {
+    value.div_ceil(to) * to
+}
+
+fn name16(name: &str) -> [u8; 16] {
+    assert!(name.len() <= 16, "segment/section name '{}' longer than 16 bytes", name);
+    let mut out = [0u8; 16];
+    out[..name.len()].copy_from_slice(name.as_bytes());
+    out
+}
+
+impl MachOBuilder {
+    pub fn new_64(cputype: i32, cpusubtype: i32) -> Self {
+        MachOBuilder {
+            is_64: true,
+            is_be: false,
+            cputype,
+            cpusubtype,
+            filetype: MH_EXECUTE,
+            flags: 0,
+            commands: Vec::new(),
+            symbols: Vec::new(),
+        }
+    }
+
+    pub fn new_32(cputype: i32, cpusubtype: i32) -> Self {
+        MachOBuilder { is_64: false, ..Self::new_64(cputype, cpusubtype) }
+    }
+
+    // PowerPC style, every multi-byte field is written big endian
+    pub fn big_endian(mut self) -> Self {
+        self.is_be = true;
+        self
+    }
+
+    pub fn filetype(mut self, filetype: u32) -> Self {
+        self.filetype = filetype;
+        self
+    }
+
+    pub fn flags(mut self, flags: u32) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    pub fn segment(mut self, segment: SegmentSpec) -> Self {
+        self.commands.push(CommandSpec::Segment(segment));
+        self
+    }
+
+    pub fn uuid(mut self, bytes: [u8; 16]) -> Self {
+        self.commands.push(CommandSpec::Uuid(bytes));
+        self
+    }
+
+    pub fn dylib(mut self, cmd: u32, path: &str) -> Self {
+        self.commands.push(CommandSpec::Dylib { cmd, path: path.to_string(), current_version: 0x10000, compatibility_version: 0x10000 });
+        self
+    }
+
+    pub fn dylib_versioned(mut self, cmd: u32, path: &str, current_version: u32, compatibility_version: u32) -> Self {
+        self.commands.push(CommandSpec::Dylib { cmd, path: path.to_string(), current_version, compatibility_version });
+        self
+    }
+
+    pub fn rpath(mut self, path: &str) -> Self {
+        self.commands.push(CommandSpec::Rpath(path.to_string()));
+        self
+    }
+
+    // Anything without a dedicated helper: payload is everything after cmd/cmdsize, padded for you
+    pub fn raw_command(mut self, cmd: u32, payload: &[u8]) -> Self {
+        self.commands.push(CommandSpec::Raw { cmd, payload: payload.to_vec() });
+        self
+    }
+
+    // Any symbol makes build() emit LC_SYMTAB, n_sect is 1-based across all sections like the real thing
+    pub fn symbol(mut self, name: &str, n_type: u8, n_sect: u8, n_value: u64) -> Self {
+        self.symbols.push(SymbolSpec { name: name.to_string(), n_type, n_sect, n_desc: 0, n_value });
+        self
+    }
+
+    fn ptr_align(&self) -> u64 {
+        if self.is_64 { 8 } else { 4 }
+    }
+
+    fn header_size(&self) -> u64 {
+        if self.is_64 { 32 } else { 28 }
+    }
+
+    fn nlist_size(&self) -> u64 {
+        if self.is_64 { 16 } else { 12 }
+    }
+
+    fn command_size(&self, command: &CommandSpec) -> u64 {
+        let size = match command {
+            CommandSpec::Segment(seg) => {
+                let (seg_size, sect_size) = if self.is_64 { (72, 80) } else { (56, 68) };
+                seg_size + sect_size * seg.sections.len() as u64
+            }
+            CommandSpec::Uuid(_) => 24,
+            CommandSpec::Dylib { path, .. } => 24 + path.len() as u64 + 1,
+            CommandSpec::Rpath(path) => 12 + path.len() as u64 + 1,
+            CommandSpec::Raw { payload, .. } => 8 + payload.len() as u64,
+        };
+        align(size, self.ptr_align())
+    }
+
+    pub fn build(&self) -> Vec<u8> {
+        let symtab_size = if self.symbols.is_empty() { 0 } else { 24 };
+        let sizeofcmds: u64 = self.commands.iter().map(|c| self.command_size(c)).sum::<u64>() + symtab_size;
+        let ncmds = self.commands.len() as u32 + u32::from(!self.symbols.is_empty());
+
+        // Where each segment's bytes land in the file
+        let mut cursor = align(self.header_size() + sizeofcmds, 16);
+        let mut segment_offsets = Vec::new();
+        for command in &self.commands {
+            if let CommandSpec::Segment(seg) = command {
+                segment_offsets.push(cursor);
+                cursor += seg.file_size();
+            }
+        }
+
+        let symoff = align(cursor, 8);
+        let stroff = symoff + self.symbols.len() as u64 * self.nlist_size();
+        let mut strtab = vec![0u8]; // index 0 is the empty string
+        let mut strx = Vec::new();
+        for sym in &self.symbols {
+            strx.push(strtab.len() as u32);
+            strtab.extend_from_slice(sym.name.as_bytes());
+            strtab.push(0);
+        }
+        let strsize = align(strtab.len() as u64, self.ptr_align());
+        let total = if self.symbols.is_empty() { cursor } else { stroff + strsize };
+
+        let mut w = Writer { data: vec![0u8; total as usize], pos: 0, is_be: self.is_be };
+
+        // Header
+        let magic = match (self.is_64, self.is_be) {
+            (true, false) => MH_CIGAM_64,
+            (true, true) => MH_MAGIC_64,
+            (false, false) => MH_CIGAM,
+            (false, true) => MH_MAGIC,
+        };
+        w.bytes(&magic);
+        w.u32(self.cputype as u32);
+        w.u32(self.cpusubtype as u32);
+        w.u32(self.filetype);
+        w.u32(ncmds);
+        w.u32(sizeofcmds as u32);
+        w.u32(self.flags);
+        if self.is_64 {
+            w.u32(0); // reserved
+        }
+
+        // Load commands
+        let mut segment_index = 0;
+        for command in &self.commands {
+            let start = w.pos;
+            let cmdsize = self.command_size(command) as u32;
+
+            match command {
+                CommandSpec::Segment(seg) => {
+                    let fileoff = segment_offsets[segment_index];
+                    segment_index += 1;
+                    self.write_segment(&mut w, seg, fileoff, cmdsize);
+                }
+                CommandSpec::Uuid(bytes) => {
+                    w.u32(LC_UUID);
+                    w.u32(cmdsize);
+                    w.bytes(bytes);
+                }
+                CommandSpec::Dylib { cmd, path, current_version, compatibility_version } => {
+                    w.u32(*cmd);
+                    w.u32(cmdsize);
+                    w.u32(24); // name offset
+                    w.u32(2); // timestamp, ld64 always writes 2
+                    w.u32(*current_version);
+                    w.u32(*compatibility_version);
+                    w.bytes(path.as_bytes());
+                }
+                CommandSpec::Rpath(path) => {
+                    w.u32(LC_RPATH | LC_REQ_DYLD);
+                    w.u32(cmdsize);
+                    w.u32(12); // path offset
+                    w.bytes(path.as_bytes());
+                }
+                CommandSpec::Raw { cmd, payload } => {
+                    w.u32(*cmd);
+                    w.u32(cmdsize);
+                    w.bytes(payload);
+                }
+            }
+
+            w.pos = start + cmdsize as usize; // padding is already zero
+        }
+
+        if !self.symbols.is_empty() {
+            w.u32(LC_SYMTAB);
+            w.u32(24);
+            w.u32(symoff as u32);
+            w.u32(self.symbols.len() as u32);
+            w.u32(stroff as u32);
+            w.u32(strsize as u32);
+
+            w.pos = symoff as usize;
+            for (sym, &index) in self.symbols.iter().zip(&strx) {
+                w.u32(index);
+                w.bytes(&[sym.n_type, sym.n_sect]);
+                w.u16(sym.n_desc);
+                if self.is_64 { w.u64(sym.n_value) } else { w.u32(sym.n_value as u32) }
+            }
+
+            w.pos = stroff as usize;
+            w.bytes(&strtab);
+        }
+
+        w.data
+    }
+
+    fn write_segment(&self, w: &mut Writer, seg: &SegmentSpec, fileoff: u64, cmdsize: u32) {
+        let filesize = seg.file_size();
+        let vmsize = seg.vmsize.unwrap_or_else(|| align(filesize.max(1), 0x4000));
+
+        w.u32(if self.is_64 { LC_SEGMENT_64 } else { LC_SEGMENT });
+        w.u32(cmdsize);
+        w.bytes(&name16(&seg.segname));
+        if self.is_64 {
+            w.u64(seg.vmaddr);
+            w.u64(vmsize);
+            w.u64(fileoff);
+            w.u64(filesize);
+        } else {
+            w.u32(seg.vmaddr as u32);
+            w.u32(vmsize as u32);
+            w.u32(fileoff as u32);
+            w.u32(filesize as u32);
+        }
+        w.u32(seg.maxprot as u32);
+        w.u32(seg.initprot as u32);
+        w.u32(seg.sections.len() as u32);
+        w.u32(0); // flags
+
+        let mut sect_off = 0u64; // VM offset into the segment
+        let mut file_off = 0u64; // same, minus any zerofill sections before this one
+        for sect in &seg.sections {
+            let size = sect.data.len() as u64;
+            let is_zerofill = sect.is_zerofill();
+
+            w.bytes(&name16(&sect.sectname));
+            w.bytes(&name16(&seg.segname));
+            if self.is_64 {
+                w.u64(seg.vmaddr + sect_off);
+                w.u64(size);
+            } else {
+                w.u32((seg.vmaddr + sect_off) as u32);
+                w.u32(size as u32);
+            }
+            w.u32(if is_zerofill { 0 } else { (fileoff + file_off) as u32 });
+            w.u32(3); // align 2^3
+            w.u32(0); // reloff
+            w.u32(0); // nreloc
+            w.u32(sect.flags);
+            w.u32(sect.reserved1);
+            w.u32(sect.reserved2);
+            if self.is_64 {
+                w.u32(0); // reserved3
+            }
+
+            // Section bytes go into the data area, then come back to the section table
+            if !is_zerofill {
+                let table_pos = w.pos;
+                w.pos = (fileoff + file_off) as usize;
+                w.bytes(&sect.data);
+                w.pos = table_pos;
+                file_off += align(size, 8);
+            }
+
+            sect_off += align(size, 8);
+        }
+    }
+}
+
+struct Writer {
+    data: Vec<u8>,
+    pos: usize,
+    is_be: bool,
+}
+
+impl Writer {
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.data[self.pos..self.pos + bytes.len()].copy_from_slice(bytes);
+        self.pos += bytes.len();
+    }
+
+    fn u16(&mut self, v: u16) {
+        if self.is_be { self.bytes(&v.to_be_bytes()) } else { self.bytes(&v.to_le_bytes()) }
+    }
+
+    fn u32(&mut self, v: u32) {
+        if self.is_be { self.bytes(&v.to_be_bytes()) } else { self.bytes(&v.to_le_bytes()) }
+    }
+
+    fn u64(&mut self, v: u64) {
+        if self.is_be { self.bytes(&v.to_be_bytes()) } else { self.bytes(&v.to_le_bytes()) }
+    }
+}
+
+
+// Text output with the color codes taken out, so tests can match it without flipping
+// colored's process-wide override (which leaks into every test running alongside)
+pub fn strip_ansi(text: &str) -> String {
+    regex::Regex::new("\x1b\\[[0-9;]*m").unwrap().replace_all(text, "").into_owned()
+}
+
+/*
+============================
+======== UNIT TESTS ========
+============================
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macho::header::{MachOHeader, MachOSlice, read_thin_header};
+    use crate::macho::load_commands::read_load_commands;
+    use crate::macho::segments::parse_segment_32;
+
+    #[test]
+    fn empty_builder_is_a_valid_header() {
+        let data = MachOBuilder::new_64(CPU_TYPE_ARM64, 0).uuid([1; 16]).build();
+        let macho = read_thin_header(&data, &MachOSlice { offset: 0, size: None }).unwrap();
+
+        assert_eq!(macho.header.filetype(), MH_EXECUTE);
+        assert_eq!(macho.header.sizeofcmds(), 24);
+    }
+
+    #[test]
+    fn big_endian_32bit_segments_parse() {
+        let data = MachOBuilder::new_32(CPU_TYPE_POWERPC, 0)
+            .big_endian()
+            .segment(SegmentSpec::new("__TEXT", 0x1000).section("__text", S_REGULAR, &[0x60, 0, 0, 0]))
+            .build();
+
+        let macho = read_thin_header(&data, &MachOSlice { offset: 0, size: None }).unwrap();
+        assert!(macho.kind.is_be());
+        let MachOHeader::Header32(h) = &macho.header else { panic!("expected a 32-bit header") };
+
+        let lcs = read_load_commands(&data, 28, h.ncmds, 32, true).unwrap();
+        let seg = parse_segment_32(&data, lcs[0].offset as usize, true).unwrap();
+        let text = &seg.sections[0];
+
+        assert_eq!((seg.vmaddr, text.addr, text.size), (0x1000, 0x1000, 4));
+        assert_eq!(&data[text.offset as usize..text.offset as usize + 4], &[0x60, 0, 0, 0]);
+    }
+}
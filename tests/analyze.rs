@@ -0,0 +1,177 @@
+use std::fs;
+
+use moscope::macho::analysis::analyze;
+use moscope::macho::constants::MH_EXECUTE;
+use moscope::macho::error::MoscopeError;
+use moscope::macho::parse::MachOFile;
+use moscope::reporting::macho::ReportOptions;
+
+// The library entry point, no CLI involved: bytes in, the whole report out
+
+#[test]
+fn analyzes_a_thin_binary() {
+    let data = fs::read("tests/samples/hello_arm64").expect("failed to read hello_arm64");
+    let report = analyze(&data, &ReportOptions::default()).unwrap();
+
+    assert!(!report.is_fat);
+    assert_eq!(report.architectures.len(), 1);
+
+    let arch = &report.architectures[0];
+    assert_eq!(arch.cpu_type, "ARM");
+    assert_eq!(arch.filetype, "MH_EXECUTE");
+    assert!(arch.segments.as_ref().unwrap().iter().any(|seg| seg.name == "__TEXT"));
+    assert!(arch.symbols.as_ref().unwrap().iter().any(|sym| sym.name == "_main"));
+    assert!(arch.dylibs.as_ref().unwrap().iter().any(|lib| lib.path.contains("libSystem")));
+    assert_eq!(arch.uuid.as_ref(), report.uuids.first());
+    assert_eq!(arch.entry_point.as_ref().unwrap().source, "LC_MAIN");
+}
+
+#[test]
+fn analyzes_every_slice_of_a_fat_binary() {
+    let data = fs::read("tests/samples/hello_fat").expect("failed to read hello_fat");
+    let report = analyze(&data, &ReportOptions::default()).unwrap();
+
+    assert!(report.is_fat);
+    let types: Vec<&str> = report.architectures.iter().map(|arch| arch.cpu_type.as_str()).collect();
+    assert_eq!(types, ["x86", "ARM"]);
+    assert!(report.architectures.iter().all(|arch| arch.fat_align.is_some()));
+    assert!(report.slice_consistency.is_some());
+}
+
+#[test]
+fn report_options_still_apply() {
+    let data = fs::read("tests/samples/hello_x86_64").expect("failed to read hello_x86_64");
+    let opts = ReportOptions { include_strings: false, include_symbols: false, ..ReportOptions::default() };
+    let report = analyze(&data, &opts).unwrap();
+
+    assert!(report.architectures[0].strings.is_none());
+    assert!(report.architectures[0].symbols.is_none());
+    assert!(report.architectures[0].header.is_some());
+}
+
+#[test]
+fn rejects_non_macho_bytes() {
+    assert!(matches!(analyze(b"definitely not a Mach-O file", &ReportOptions::default()), Err(MoscopeError::BadMagic(_))));
+    assert!(matches!(analyze(b"", &ReportOptions::default()), Err(MoscopeError::TooSmall { .. })));
+}
+
+#[test]
+fn reports_dyld_info_tables_only_without_chained_fixups() {
+    let data = fs::read("tests/samples/hello_x86_64_nochain").expect("failed to read hello_x86_64_nochain");
+    let report = analyze(&data, &ReportOptions::default()).unwrap();
+    let info = report.architectures[0].dyld_info.as_ref().expect("-no_fixup_chains keeps LC_DYLD_INFO_ONLY");
+    assert_eq!(info.cmd, "LC_DYLD_INFO_ONLY");
+    assert!(info.bind_size > 0 && info.export_size > 0);
+    let binds = report.architectures[0].bound_symbols.as_ref().expect("the bind opcodes decode without a VM image");
+    assert!(binds.iter().any(|b| b.symbol == "dyld_stub_binder" && b.library == "/usr/lib/libSystem.B.dylib"));
+
+    let data = fs::read("tests/samples/hello_arm64").expect("failed to read hello_arm64");
+    assert!(analyze(&data, &ReportOptions::default()).unwrap().architectures[0].dyld_info.is_none());
+}
+
+#[test]
+fn csv_tables_have_one_row_per_entry() {
+    use moscope::reporting::csv::{write_csv_table, CsvStringFilter, CsvTable};
+
+    let data = fs::read("tests/samples/hello_fat").expect("failed to read hello_fat");
+    let report = analyze(&data, &ReportOptions::default()).unwrap();
+    let filter = CsvStringFilter { min_length: 0, section_min_lengths: None, max_count: None };
+
+    let mut out = Vec::new();
+    write_csv_table(&mut out, &report, CsvTable::Symbols, false, &filter).unwrap();
+    let csv = String::from_utf8(out).unwrap();
+    let mut lines = csv.lines();
+    assert_eq!(lines.next(), Some("arch,address,kind,bind,segment,section,name"));
+    let symbols: usize = report.architectures.iter().map(|arch| arch.symbols.as_ref().unwrap().len()).sum();
+    assert_eq!(lines.count(), symbols);
+    assert!(csv.lines().any(|line| line.starts_with("x86_64,") && line.ends_with(",__TEXT,__text,_main")));
+
+    let mut out = Vec::new();
+    write_csv_table(&mut out, &report, CsvTable::Strings, false, &filter).unwrap();
+    assert!(String::from_utf8(out).unwrap().lines().any(|line| line.ends_with(",__TEXT,__cstring,Hello world!")));
+}
+
+#[test]
+fn dot_graph_links_every_slice_to_shared_dylib_nodes() {
+    use moscope::reporting::dot::write_dot_graph;
+
+    let data = fs::read("tests/samples/hello_fat").expect("failed to read hello_fat");
+    let report = analyze(&data, &ReportOptions::default()).unwrap();
+
+    let mut out = Vec::new();
+    write_dot_graph(&mut out, &report, "hello_fat").unwrap();
+    let dot = String::from_utf8(out).unwrap();
+
+    assert!(dot.starts_with("digraph dylibs {"));
+    assert!(dot.trim_end().ends_with('}'));
+    // declared once, linked from both slices
+    assert_eq!(dot.matches("[label=\"libSystem.B.dylib\"").count(), 1);
+    let edges: usize = report.architectures.iter().map(|arch| arch.dylibs.as_ref().unwrap().len()).sum();
+    assert_eq!(dot.matches(" -> ").count(), edges);
+    assert!(dot.contains("root1 -> dylib1 [label=\"LOAD\""));
+}
+
+#[test]
+fn markdown_report_is_tables_without_colors() {
+    use moscope::reporting::markdown::write_markdown_report;
+    use moscope::reporting::writer::TextOptions;
+
+    let data = fs::read("tests/samples/hello_arm64").expect("failed to read hello_arm64");
+    let report = analyze(&data, &ReportOptions::default()).unwrap();
+    let opts = TextOptions {
+        count_sections_by_kind: false,
+        legend: false,
+        canonical_names: false,
+        strip_underscore: false,
+        min_string_length: 4,
+        section_min_lengths: None,
+        max_strings: None,
+        path: "hello_arm64".into(),
+    };
+
+    let mut out = Vec::new();
+    write_markdown_report(&mut out, &report, &opts).unwrap();
+    let markdown = String::from_utf8(out).unwrap();
+
+    assert!(markdown.starts_with("# Mach-O Report: `hello_arm64`"));
+    assert!(!markdown.contains('\x1b'));
+    for heading in ["### Header", "### Segments", "### Sections", "### Dylibs", "### Symbols", "### Strings"] {
+        assert!(markdown.contains(heading), "{} missing", heading);
+    }
+    assert!(markdown.contains("| `0x00000001000004e8` | SECT | EXT | `__text` | `_main` |  |"));
+    // every row of a table has the same number of cells as its header
+    let symbol_rows: Vec<&str> = markdown.lines().skip_while(|line| *line != "### Symbols").skip(2).take_while(|line| line.starts_with('|')).collect();
+    assert_eq!(symbol_rows.len(), report.architectures[0].symbols.as_ref().unwrap().len() + 2);
+    assert!(symbol_rows.iter().all(|row| row.matches('|').count() - row.matches("\\|").count() == 7));
+}
+
+#[test]
+fn parses_every_slice_into_a_machofile() {
+    let data = fs::read("tests/samples/hello_fat").expect("failed to read hello_fat");
+    let file = MachOFile::parse(&data).unwrap();
+
+    assert!(file.is_fat());
+    assert_eq!(file.slices.len(), file.fat_archs.as_ref().unwrap().len());
+    for slice in &file.slices {
+        assert_eq!(slice.header.header.filetype(), MH_EXECUTE);
+        assert!(!slice.load_commands.is_empty());
+        assert!(slice.symbols.iter().any(|sym| sym.name == "_main"));
+        assert!(slice.dylibs.iter().any(|lib| lib.path.contains("libSystem")));
+    }
+
+    // the report built from it is the one analyze() returns
+    let report = file.report(&data, &ReportOptions::default()).unwrap();
+    assert_eq!(
+        serde_json::to_value(&report).unwrap(),
+        serde_json::to_value(analyze(&data, &ReportOptions::default()).unwrap()).unwrap(),
+    );
+}
+
+#[test]
+fn machofile_errors_can_be_matched_on() {
+    let data = fs::read("tests/samples/hello_arm64").expect("failed to read hello_arm64");
+    assert_eq!(MachOFile::parse(&data).unwrap().slices.len(), 1);
+
+    assert!(matches!(MachOFile::parse(b"definitely not a Mach-O file"), Err(MoscopeError::BadMagic(_))));
+    assert!(MachOFile::parse(&data[..64]).is_err());
+}
@@ -0,0 +1,92 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use moscope::macho::constants::*;
+use moscope::macho::dylibs::parse_dylib;
+use moscope::macho::header::{MachOHeader, MachOSlice, read_thin_header};
+use moscope::macho::load_commands::read_load_commands;
+use moscope::macho::rpaths::parse_rpath;
+use moscope::macho::segments::parse_segment_64;
+use moscope::macho::symtab::{SymbolIter, SymtabCommand};
+use moscope::macho::utils::bytes_to;
+use moscope::macho::uuid::find_uuid;
+use moscope::test_util::{MachOBuilder, SegmentSpec};
+
+/*
+Round trips through MachOBuilder: build a thin arm64 executable with the usual suspects,
+then make sure both the library parsers and the real CLI read back exactly what went in.
+New parsing features can grow a builder call here instead of another checked-in sample.
+*/
+
+const UUID: [u8; 16] = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xAA, 0xBB, 0xCC, 0xDD, 0xEE, 0xFF, 0x00];
+const TEXT_BASE: u64 = 0x1_0000_0000;
+
+fn sample() -> Vec<u8> {
+    MachOBuilder::new_64(CPU_TYPE_ARM64, 0)
+        .flags(MH_PIE)
+        .segment(SegmentSpec::new("__PAGEZERO", 0).vmsize(TEXT_BASE).protection(0, 0))
+        .segment(
+            SegmentSpec::new("__TEXT", TEXT_BASE)
+                .protection(5, 5)
+                .section("__text", S_REGULAR | S_ATTR_PURE_INSTRUCTIONS, &[0xC0, 0x03, 0x5F, 0xD6]) // ret
+                .section("__cstring", S_CSTRING_LITERALS, b"hello from the builder\0"),
+        )
+        .uuid(UUID)
+        .dylib(LC_LOAD_DYLIB, "/usr/lib/libSystem.B.dylib")
+        .rpath("@executable_path/../Frameworks")
+        .symbol("_main", N_SECT | N_EXT, 1, TEXT_BASE)
+        .symbol("_printf", N_UNDF | N_EXT, 0, 0)
+        .build()
+}
+
+#[test]
+fn library_parsers_read_back_the_builder_output() {
+    let data = sample();
+    let macho = read_thin_header(&data, &MachOSlice { offset: 0, size: None }).expect("failed to read header");
+    let MachOHeader::Header64(h) = &macho.header else { panic!("expected a 64-bit header") };
+    assert_eq!(h.cputype, CPU_TYPE_ARM64);
+    assert_eq!(h.flags, MH_PIE);
+
+    let lcs = read_load_commands(&data, 32, h.ncmds, 64, false).expect("failed to read load commands");
+    assert_eq!(lcs.len(), 6);
+    assert_eq!(lcs.iter().filter(|lc| lc.cmd == LC_UUID).count(), 1);
+    assert_eq!(find_uuid(&data, &lcs).unwrap().unwrap().bytes, UUID);
+
+    let text = parse_segment_64(&data, lcs[1].offset as usize, false).unwrap();
+    let cstring = &text.sections[1];
+    assert_eq!(text.sections.len(), 2);
+    assert_eq!(cstring.addr - text.vmaddr, cstring.offset as u64 - text.fileoff);
+    assert_eq!(&data[cstring.offset as usize..][..cstring.size as usize], b"hello from the builder\0");
+
+    let dylib = parse_dylib(&data, &lcs[3], false).unwrap();
+    assert_eq!(dylib.path, "/usr/lib/libSystem.B.dylib");
+    assert_eq!(parse_rpath(&data, &lcs[4], false).unwrap().path, "@executable_path/../Frameworks");
+
+    let off = lcs[5].offset as usize;
+    assert_eq!(lcs[5].cmd, LC_SYMTAB);
+    let field = |at: usize| -> u32 { bytes_to(false, &data[off + at..]).unwrap() };
+    let symtab = SymtabCommand { cmd: field(0), cmdsize: field(4), symoff: field(8), nsyms: field(12), stroff: field(16), strsize: field(20) };
+
+    let names: Vec<String> = SymbolIter::new(&data, &symtab, 0, true, false).map(|s| s.unwrap().name).collect();
+    assert_eq!(names, ["_main", "_printf"]);
+}
+
+#[test]
+fn cli_reports_the_builder_output() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_moscope"))
+        .args(["-", "--format", "json"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to run moscope");
+
+    child.stdin.take().unwrap().write_all(&sample()).expect("failed to write stdin");
+    let output = child.wait_with_output().expect("failed to wait for moscope");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "moscope failed: {}", String::from_utf8_lossy(&output.stderr));
+    for expected in ["11223344-5566-7788-99AA-BBCCDDEEFF00", "/usr/lib/libSystem.B.dylib", "@executable_path/../Frameworks", "_main"] {
+        assert!(stdout.contains(expected), "'{}' missing from report:\n{}", expected, stdout);
+    }
+}
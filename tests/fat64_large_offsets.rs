@@ -0,0 +1,134 @@
+use moscope::macho::constants::*;
+use moscope::macho::fat::{FatArch, FatKind, read_fat_archs, read_fat_header};
+use moscope::macho::header::{MachHeader64, MachOHeader, MachOSlice, read_thin_header};
+use moscope::macho::load_commands::read_load_commands;
+use moscope::macho::symtab::{NList64, ParsedSymbol};
+use moscope::macho::utils::bytes_to;
+use moscope::macho::uuid::find_uuid;
+
+/*
+A FAT_MAGIC_64 universal binary whose only slice starts past the 4GB mark.
+
+    0x000000000  fat_header (FAT_MAGIC_64, nfat_arch = 1)
+    0x000000008  fat_arch_64 { offset = 4GB + 0x1000 }
+    ...          zeroes
+    SLICE_OFFSET mach_header_64 + LC_UUID + LC_SYMTAB
+    +0x100       nlist_64[1]
+    +0x200       string table
+
+The buffer is allocated zeroed so on Linux/macOS the OS only backs the pages we actually
+touch, but it is still a >4GB allocation: 32-bit targets, Windows and memory-capped CI
+runners can't make it. The parsers all take &[u8], so there is no sparse reader to swap in,
+and the test is opt-in instead: `cargo test -- --ignored`.
+Anything on the path that squeezes an offset through a u32 ends up reading the zeroes
+at (offset mod 4GB) and fails.
+*/
+
+const SLICE_OFFSET: u64 = (1 << 32) + 0x1000;
+const SLICE_SIZE: u64 = 0x1000;
+const SYMOFF: u32 = 0x100;
+const STROFF: u32 = 0x200;
+const UUID: [u8; 16] = [0xAB; 16];
+
+fn put_u32_le(data: &mut [u8], at: u64, value: u32) {
+    let at = at as usize;
+    data[at..at + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+fn build_fat64_with_huge_offset() -> Vec<u8> {
+    let mut data = vec![0u8; (SLICE_OFFSET + SLICE_SIZE) as usize];
+
+    // fat header + fat_arch_64, always big endian on disk
+    data[0..4].copy_from_slice(&FAT_MAGIC_64);
+    data[4..8].copy_from_slice(&1u32.to_be_bytes());
+    data[8..12].copy_from_slice(&CPU_TYPE_ARM64.to_be_bytes());
+    data[12..16].copy_from_slice(&0i32.to_be_bytes());
+    data[16..24].copy_from_slice(&SLICE_OFFSET.to_be_bytes());
+    data[24..32].copy_from_slice(&SLICE_SIZE.to_be_bytes());
+    data[32..36].copy_from_slice(&14u32.to_be_bytes());
+
+    // mach_header_64 (little endian slice)
+    let base = SLICE_OFFSET;
+    data[base as usize..base as usize + 4].copy_from_slice(&MH_CIGAM_64);
+    put_u32_le(&mut data, base + 4, CPU_TYPE_ARM64 as u32);
+    put_u32_le(&mut data, base + 8, 0);
+    put_u32_le(&mut data, base + 12, MH_EXECUTE);
+    put_u32_le(&mut data, base + 16, 2); // ncmds
+    put_u32_le(&mut data, base + 20, 48); // sizeofcmds
+
+    // LC_UUID
+    let lc = base + 32;
+    put_u32_le(&mut data, lc, LC_UUID);
+    put_u32_le(&mut data, lc + 4, 24);
+    data[lc as usize + 8..lc as usize + 24].copy_from_slice(&UUID);
+
+    // LC_SYMTAB, offsets are relative to the slice like a real linker writes them
+    let lc = base + 56;
+    put_u32_le(&mut data, lc, LC_SYMTAB);
+    put_u32_le(&mut data, lc + 4, 24);
+    put_u32_le(&mut data, lc + 8, SYMOFF);
+    put_u32_le(&mut data, lc + 12, 1); // nsyms
+    put_u32_le(&mut data, lc + 16, STROFF);
+    put_u32_le(&mut data, lc + 20, 16); // strsize
+
+    // nlist_64 { n_strx = 1, N_SECT | N_EXT, n_sect = 1, n_value }
+    let nlist = base + SYMOFF as u64;
+    put_u32_le(&mut data, nlist, 1);
+    data[nlist as usize + 4] = N_SECT | N_EXT;
+    data[nlist as usize + 5] = 1;
+    data[nlist as usize + 8..nlist as usize + 16].copy_from_slice(&0x1_0000_0460u64.to_le_bytes());
+
+    let strtab = (base + STROFF as u64) as usize;
+    data[strtab..strtab + 12].copy_from_slice(b"\0_big_slice\0");
+
+    data
+}
+
+#[test]
+#[ignore = "allocates a >4GB buffer, run with --ignored"]
+fn fat64_slice_offsets_past_4gb_are_not_truncated() {
+    let data = build_fat64_with_huge_offset();
+
+    let fat_header = read_fat_header(&data).expect("failed to parse fat header");
+    assert_eq!(fat_header.kind, FatKind::Fat64BE);
+
+    let archs = read_fat_archs(&data, &fat_header).expect("failed to parse fat_arch_64");
+    let slice = match &archs[0] {
+        FatArch::Arch64(a) => MachOSlice { offset: a.offset, size: Some(a.size) },
+        FatArch::Arch32(_) => panic!("expected a fat_arch_64 entry"),
+    };
+    assert_eq!(slice.offset, SLICE_OFFSET);
+    assert!(slice.offset > u32::MAX as u64);
+
+    // Header
+    let macho = read_thin_header(&data, &slice).expect("failed to read slice header past 4GB");
+    let ncmds = match &macho.header {
+        MachOHeader::Header64(h) => {
+            assert_eq!(h.cputype, CPU_TYPE_ARM64);
+            h.ncmds
+        }
+        MachOHeader::Header32(_) => panic!("expected a 64-bit slice"),
+    };
+
+    // Load commands
+    let lc_offset = slice.offset + std::mem::size_of::<MachHeader64>() as u64;
+    let load_commands = read_load_commands(&data, lc_offset, ncmds, 64, false).expect("failed to read load commands");
+    assert_eq!(load_commands.len(), 2);
+    assert!(load_commands.iter().all(|lc| lc.offset > u32::MAX as u64));
+
+    let uuid = find_uuid(&data, &load_commands).unwrap().expect("LC_UUID missing");
+    assert_eq!(uuid.bytes, UUID);
+
+    // Symbols, the symtab/strtab offsets only make sense once slice.offset is added back
+    let symtab = load_commands.iter().find(|lc| lc.cmd == LC_SYMTAB).unwrap();
+    let off = symtab.offset as usize;
+    let symoff: u32 = bytes_to(false, &data[off + 8..]).unwrap();
+    let stroff: u32 = bytes_to(false, &data[off + 16..]).unwrap();
+    let strsize: u32 = bytes_to(false, &data[off + 20..]).unwrap();
+
+    let nlist = NList64::parse(&data, slice.offset as usize + symoff as usize, false).unwrap();
+    let symbol = ParsedSymbol::from_nlist64(nlist, &data, slice.offset as usize + stroff as usize, strsize as usize);
+
+    assert_eq!(symbol.name, "_big_slice");
+    assert_eq!(symbol.addr, 0x1_0000_0460);
+}
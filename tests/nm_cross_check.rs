@@ -0,0 +1,141 @@
+// Uses the system nm as an oracle for our nlist / string table parsing.
+// Only meaningful where Apple's nm exists, so the whole file is macOS only
+// and each test quietly skips if nm can't be run (e.g. no Xcode CLT installed).
+#![cfg(target_os = "macos")]
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use moscope::macho::constants::*;
+use moscope::macho::fat::{FatArch, read_fat_archs, read_fat_header};
+use moscope::macho::header::{MachHeader32, MachHeader64, MachOHeader, MachOSlice, read_thin_header};
+use moscope::macho::load_commands::read_load_commands;
+use moscope::macho::symtab::{NList32, NList64, ParsedSymbol, SymbolKind};
+use moscope::macho::utils::bytes_to;
+
+
+// nm -g -U -j --> external, defined, names only. None when nm isn't usable
+fn nm_external_defined(path: &Path, arch: &str) -> Option<BTreeSet<String>> {
+    let output = Command::new("nm")
+        .args(["-arch", arch, "-g", "-U", "-j"])
+        .arg(path)
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    Some(
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(normalize)
+            .collect()
+    )
+}
+
+// C symbols carry a leading underscore in the symbol table, compare without it
+fn normalize(name: &str) -> String {
+    name.strip_prefix('_').unwrap_or(name).to_string()
+}
+
+fn nm_arch_name(cputype: i32) -> &'static str {
+    match cputype {
+        CPU_TYPE_ARM64 => "arm64",
+        CPU_TYPE_X86_64 => "x86_64",
+        CPU_TYPE_X86 => "i386",
+        other => panic!("no nm -arch name for cputype {}", other),
+    }
+}
+
+fn slices_for(data: &[u8]) -> Vec<MachOSlice> {
+    match read_fat_header(data) {
+        Ok(fat_header) => read_fat_archs(data, &fat_header)
+            .expect("failed to parse fat archs")
+            .into_iter()
+            .map(|arch| match arch {
+                FatArch::Arch32(a) => MachOSlice { offset: a.offset as u64, size: Some(a.size as u64) },
+                FatArch::Arch64(a) => MachOSlice { offset: a.offset, size: Some(a.size) },
+            })
+            .collect(),
+        Err(_) => vec![MachOSlice { offset: 0, size: None }],
+    }
+}
+
+// (nm arch name, external defined symbol names) for one slice, parsed by moscope
+fn moscope_external_defined(data: &[u8], slice: &MachOSlice) -> (&'static str, BTreeSet<String>) {
+    let macho = read_thin_header(data, slice).expect("failed to read Mach-O header");
+    let is_be = macho.kind.is_be();
+    let is_64 = macho.kind.is_64();
+
+    let (cputype, ncmds, header_size) = match &macho.header {
+        MachOHeader::Header32(h) => (h.cputype, h.ncmds, std::mem::size_of::<MachHeader32>()),
+        MachOHeader::Header64(h) => (h.cputype, h.ncmds, std::mem::size_of::<MachHeader64>()),
+    };
+
+    let load_commands = read_load_commands(data, slice.offset + header_size as u64, ncmds, if is_64 { 64 } else { 32 }, is_be)
+        .expect("failed to read load commands");
+
+    let symtab = load_commands.iter().find(|lc| lc.cmd == LC_SYMTAB).expect("LC_SYMTAB missing");
+    let off = symtab.offset as usize;
+    let symoff: u32 = bytes_to(is_be, &data[off + 8..]).unwrap();
+    let nsyms: u32 = bytes_to(is_be, &data[off + 12..]).unwrap();
+    let stroff: u32 = bytes_to(is_be, &data[off + 16..]).unwrap();
+    let strsize: u32 = bytes_to(is_be, &data[off + 20..]).unwrap();
+
+    let str_base = slice.offset as usize + stroff as usize;
+    let entry_size = if is_64 { NList64::SIZE } else { NList32::SIZE };
+
+    let mut names = BTreeSet::new();
+    for i in 0..nsyms as usize {
+        let offset = slice.offset as usize + symoff as usize + i * entry_size;
+        let symbol: ParsedSymbol = if is_64 {
+            ParsedSymbol::from_nlist64(NList64::parse(data, offset, is_be).unwrap(), data, str_base, strsize as usize)
+        } else {
+            ParsedSymbol::from_nlist32(NList32::parse(data, offset, is_be).unwrap(), data, str_base, strsize as usize)
+        };
+
+        if symbol.is_external && !symbol.is_debug && !matches!(symbol.kind, SymbolKind::Undefined) {
+            names.insert(normalize(&symbol.name));
+        }
+    }
+
+    (nm_arch_name(cputype), names)
+}
+
+fn cross_check(sample: &str) {
+    let path = Path::new("tests/samples").join(sample);
+    let data = fs::read(&path).expect("failed to read sample");
+
+    for slice in slices_for(&data) {
+        let (arch, ours) = moscope_external_defined(&data, &slice);
+
+        let Some(theirs) = nm_external_defined(&path, arch) else {
+            eprintln!("skipping {} ({}): nm not available", sample, arch);
+            return;
+        };
+
+        assert!(!theirs.is_empty(), "nm found no external symbols in {} ({})", sample, arch);
+        assert_eq!(ours, theirs, "external defined symbols differ from nm for {} ({})", sample, arch);
+    }
+}
+
+
+#[test]
+fn thin_arm64_symbols_match_nm() {
+    cross_check("hello_arm64");
+}
+
+#[test]
+fn thin_x86_64_symbols_match_nm() {
+    cross_check("hello_x86_64");
+}
+
+#[test]
+fn fat_symbols_match_nm_per_slice() {
+    cross_check("hello_fat");
+}
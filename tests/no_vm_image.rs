@@ -0,0 +1,43 @@
+use std::process::Command;
+
+/*
+--no-vm-image swaps MachOMemoryImage for plain file offset reads when extracting strings.
+For anything straight out of ld64 the two have to agree byte for byte, so the whole JSON
+report (strings included) should come out identical either way.
+*/
+
+fn report(sample: &str, extra: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_moscope"))
+        .arg(format!("tests/samples/{}", sample))
+        .args(["--format", "json"])
+        .args(extra)
+        .env("MOSCOPE_NO_PROMPT", "1")
+        .output()
+        .expect("failed to run moscope");
+
+    assert!(output.status.success(), "moscope failed: {}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+fn assert_same_report(sample: &str) {
+    let vm = report(sample, &[]);
+    let file = report(sample, &["--no-vm-image"]);
+
+    assert!(vm.contains("\"strings\": ["), "no strings section in {} report", sample);
+    assert_eq!(vm, file, "--no-vm-image changed the report for {}", sample);
+}
+
+#[test]
+fn thin_arm64_matches_vm_image() {
+    assert_same_report("hello_arm64");
+}
+
+#[test]
+fn thin_x86_64_matches_vm_image() {
+    assert_same_report("hello_x86_64");
+}
+
+#[test]
+fn fat_matches_vm_image() {
+    assert_same_report("hello_fat");
+}
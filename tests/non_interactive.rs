@@ -0,0 +1,49 @@
+use std::process::{Command, Stdio};
+
+/*
+The fat binary arch prompt reads from stdin. In CI stdin is usually /dev/null or a pipe,
+which used to mean a failed (or hung) read instead of a report. These run the real
+binary with a non-TTY stdin and expect every slice to be analyzed without any input.
+*/
+
+fn run_moscope(args: &[&str], envs: &[(&str, &str)]) -> (bool, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_moscope"))
+        .args(args)
+        .env_remove("MOSCOPE_NO_PROMPT")
+        .envs(envs.iter().copied())
+        .stdin(Stdio::null())
+        .output()
+        .expect("failed to run moscope");
+
+    (output.status.success(), String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn assert_all_archs(stdout: &str) {
+    assert!(!stdout.contains("Select architecture index"), "prompted for an architecture:\n{}", stdout);
+    assert!(stdout.contains("x86 (x86_64)"), "x86_64 slice missing:\n{}", stdout);
+    assert!(stdout.contains("ARM (arm64"), "arm64 slice missing:\n{}", stdout);
+}
+
+#[test]
+fn non_tty_stdin_analyzes_all_archs() {
+    let (ok, stdout) = run_moscope(&["tests/samples/hello_fat"], &[]);
+
+    assert!(ok);
+    assert_all_archs(&stdout);
+}
+
+#[test]
+fn all_archs_flag_skips_prompt() {
+    let (ok, stdout) = run_moscope(&["tests/samples/hello_fat", "--all-archs"], &[]);
+
+    assert!(ok);
+    assert_all_archs(&stdout);
+}
+
+#[test]
+fn no_prompt_env_skips_prompt() {
+    let (ok, stdout) = run_moscope(&["tests/samples/hello_fat"], &[("MOSCOPE_NO_PROMPT", "1")]);
+
+    assert!(ok);
+    assert_all_archs(&stdout);
+}
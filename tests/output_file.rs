@@ -0,0 +1,102 @@
+use std::fs;
+use std::process::Command;
+
+/*
+--output sends the report to a file instead of stdout. These run the real binary and check
+that the file gets the whole report and stdout stays empty, so a script can keep its own
+output separate from moscope's.
+*/
+
+fn run_moscope(args: &[&str]) -> (bool, String, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_moscope"))
+        .args(args)
+        .env("MOSCOPE_NO_PROMPT", "1")
+        .output()
+        .expect("failed to run moscope");
+
+    (
+        output.status.success(),
+        String::from_utf8_lossy(&output.stdout).to_string(),
+        String::from_utf8_lossy(&output.stderr).to_string(),
+    )
+}
+
+fn temp_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("moscope-{}-{}", std::process::id(), name))
+}
+
+#[test]
+fn json_report_goes_to_the_file() {
+    let path = temp_path("report.json");
+    let (ok, stdout, stderr) = run_moscope(&["tests/samples/hello_fat", "--format", "json", "--output", path.to_str().unwrap()]);
+
+    assert!(ok, "{}", stderr);
+    assert!(stdout.is_empty(), "report leaked to stdout:\n{}", stdout);
+    let report: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(report["architectures"].as_array().unwrap().len(), 2);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn text_report_in_a_file_has_no_colors() {
+    let path = temp_path("report.txt");
+    let (ok, stdout, stderr) = run_moscope(&["tests/samples/hello_arm64", "--output", path.to_str().unwrap()]);
+
+    assert!(ok, "{}", stderr);
+    assert!(stdout.is_empty());
+    let text = fs::read_to_string(&path).unwrap();
+    assert!(text.contains("Mach-O Report:"));
+    assert!(!text.contains('\x1b'));
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn fast_paths_write_the_file_too() {
+    let path = temp_path("uuid.txt");
+    let (ok, stdout, _) = run_moscope(&["tests/samples/hello_arm64", "--uuid", "--output", path.to_str().unwrap()]);
+
+    assert!(ok);
+    assert!(stdout.is_empty());
+    assert!(!fs::read_to_string(&path).unwrap().is_empty());
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn debug_dump_goes_to_the_file() {
+    let path = temp_path("dump.txt");
+    let (ok, stdout, stderr) = run_moscope(&["tests/samples/hello_fat", "--debug-dump", "--all-archs", "--output", path.to_str().unwrap()]);
+
+    assert!(ok, "{}", stderr);
+    assert!(stdout.is_empty(), "dump leaked to stdout:\n{}", stdout);
+    let dump = fs::read_to_string(&path).unwrap();
+    assert_eq!(dump.matches("==== architecture ").count(), 2);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn timings_cover_fast_paths_too() {
+    for args in [&["--uuid"][..], &["--lc-map"], &["--all", "--format", "json"]] {
+        let path = temp_path("timed.txt");
+        let mut full = vec!["tests/samples/hello_arm64", "--timings", "--output", path.to_str().unwrap()];
+        full.extend_from_slice(args);
+        let (ok, stdout, stderr) = run_moscope(&full);
+
+        assert!(ok, "{}", stderr);
+        assert!(stdout.is_empty());
+        assert!(stderr.contains("fast path") && stderr.contains("total"), "{:?} printed no timings:\n{}", args, stderr);
+        assert!(!fs::read_to_string(&path).unwrap().contains("Timings"));
+        fs::remove_file(&path).unwrap();
+    }
+
+    let (ok, _, stderr) = run_moscope(&["verify", "tests/samples/hello_fat", "--timings"]);
+    assert!(ok, "{}", stderr);
+    assert!(stderr.contains("parse + report"), "{}", stderr);
+}
+
+#[test]
+fn unwritable_path_is_an_error() {
+    let (ok, _, stderr) = run_moscope(&["tests/samples/hello_arm64", "--output", "/nonexistent-dir/report.txt"]);
+
+    assert!(!ok);
+    assert!(stderr.contains("failed to create"), "{}", stderr);
+}
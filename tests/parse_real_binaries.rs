@@ -3,12 +3,23 @@ use std::path::Path;
 
 use moscope::macho::fat::{FatArch, FatKind, read_fat_archs, read_fat_header};
 use moscope::macho::header::{MachHeader32, MachHeader64, MachOHeader, MachOSlice, read_thin_header};
-use moscope::macho::load_commands::{LoadCommand, read_load_commands};
+use moscope::macho::load_commands::{parse_linkedit_data, read_load_commands, read_slice_load_commands};
+use moscope::macho::analysis::all_slices;
+use moscope::macho::codesign::{build_signature_check_report, read_code_signature};
+use moscope::macho::export_trie::{build_exports_report, parse_export_trie};
+use moscope::macho::function_starts::{parse_function_starts, text_vmaddr};
+use moscope::macho::symtab::{parse_symbols, read_symtab_command};
+use moscope::macho::segments::parse_segments;
 use moscope::macho::constants::{
     cpu_type_name,
     cpu_subtype_name,
-    filetype_name,
     MH_EXECUTE, 
+    CPU_TYPE_ARM64,
+    LC_FUNCTION_STARTS,
+    LC_CODE_SIGNATURE,
+    LC_DYLD_EXPORTS_TRIE,
+    LC_REQ_DYLD,
+    LC_SYMTAB,
 };
 
 
@@ -206,10 +217,82 @@ fn fat_binary_has_load_commands() {
         };
 
         let load_command_offset = slice.offset as usize + header_size;
-        let load_commands = read_load_commands(&data, load_command_offset as u32, ncmds, word_size, is_be).unwrap();
+        let load_commands = read_load_commands(&data, load_command_offset as u64, ncmds, word_size, is_be).unwrap();
         assert!(!load_commands.is_empty(), "No load commands found");
         assert_eq!(ncmds, load_commands.len() as u32);
             
     }
 }
 
+
+#[test]
+fn fat_function_starts_match_the_thin_binary() {
+    // LC_FUNCTION_STARTS' dataoff is slice relative, the fat copy has to decode to the same list
+    let function_starts_of = |data: &[u8], slice: &MachOSlice| {
+        let (thin_header, load_commands) = read_slice_load_commands(data, slice).unwrap();
+        let is_be = thin_header.kind.is_be();
+        let segments = parse_segments(data, &load_commands, is_be).unwrap();
+        let lc = load_commands.iter().find(|lc| lc.cmd == LC_FUNCTION_STARTS).expect("ld always emits LC_FUNCTION_STARTS");
+        parse_function_starts(data, &parse_linkedit_data(data, lc, is_be).unwrap(), slice.offset, text_vmaddr(&segments))
+    };
+
+    let thin = fs::read("tests/samples/hello_arm64").expect("failed to read hello_arm64");
+    let thin_starts = function_starts_of(&thin, &MachOSlice { offset: 0, size: None });
+
+    let fat = fs::read("tests/samples/hello_fat").expect("failed to read hello_fat");
+    let archs = read_fat_archs(&fat, &read_fat_header(&fat).unwrap()).unwrap();
+    let arm64 = all_slices(Some(&archs))
+        .into_iter()
+        .find(|slice| read_thin_header(&fat, slice).unwrap().header.cpu().0 == CPU_TYPE_ARM64)
+        .expect("hello_fat should have an arm64 slice");
+
+    assert!(!thin_starts.is_empty());
+    assert!(arm64.offset > 0);
+    assert_eq!(function_starts_of(&fat, &arm64), thin_starts);
+}
+
+
+#[test]
+fn signed_sample_pages_match_until_patched() {
+    // hello_arm64 carries the linker's ad-hoc signature, every page of it has to hash back
+    let check = |data: &[u8]| {
+        let slice = MachOSlice { offset: 0, size: None };
+        let (thin_header, load_commands) = read_slice_load_commands(data, &slice).unwrap();
+        let lc = load_commands.iter().find(|lc| lc.cmd == LC_CODE_SIGNATURE).expect("arm64 binaries are always signed");
+        let cmd = parse_linkedit_data(data, lc, thin_header.kind.is_be()).unwrap();
+        let directories = read_code_signature(data, 0, &cmd).unwrap();
+        build_signature_check_report("ARM64", "arm64", data, &directories, &[])
+    };
+
+    let mut data = fs::read("tests/samples/hello_arm64").expect("failed to read hello_arm64");
+    let report = check(&data);
+    assert!(report.valid, "{:?}", report);
+    assert!(report.code_directories[0].pages_checked > 1);
+
+    data[0x1000] ^= 0xff;
+    let report = check(&data);
+    assert!(!report.valid);
+    assert_eq!(report.code_directories[0].mismatches.iter().map(|m| m.page).collect::<Vec<_>>(), vec![1]);
+}
+
+
+#[test]
+fn export_trie_addresses_match_the_symbol_table() {
+    let data = fs::read("tests/samples/hello_arm64").expect("failed to read hello_arm64");
+    let slice = MachOSlice { offset: 0, size: None };
+    let (thin_header, load_commands) = read_slice_load_commands(&data, &slice).unwrap();
+    let is_be = thin_header.kind.is_be();
+    let segments = parse_segments(&data, &load_commands, is_be).unwrap();
+
+    let lc = load_commands.iter().find(|lc| lc.cmd & !LC_REQ_DYLD == LC_DYLD_EXPORTS_TRIE).expect("chained fixup binaries use LC_DYLD_EXPORTS_TRIE");
+    let cmd = parse_linkedit_data(&data, lc, is_be).unwrap();
+    let trie = &data[cmd.dataoff as usize..(cmd.dataoff + cmd.datasize) as usize];
+    let report = build_exports_report("ARM64", "arm64", &parse_export_trie(trie).unwrap(), &[], text_vmaddr(&segments), &[]);
+
+    let lc = load_commands.iter().find(|lc| lc.cmd == LC_SYMTAB).unwrap();
+    let symbols = parse_symbols(&data, &read_symtab_command(&data, lc, is_be).unwrap(), 0, true, is_be).unwrap();
+    let main = symbols.iter().find(|sym| sym.name == "_main").unwrap();
+
+    let exported = report.exports.iter().find(|e| e.name == "_main").expect("_main is exported");
+    assert_eq!(exported.address, Some(main.value));
+}
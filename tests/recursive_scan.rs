@@ -0,0 +1,42 @@
+#![cfg(feature = "compression")]
+
+use std::fs;
+use std::io::Write;
+use std::process::Command;
+
+/*
+--recursive lists every Mach-O under a directory, gzip wrapped ones included: the walk unwraps
+the first few bytes of each .gz, and only one that turns out to be a Mach-O is read in full.
+*/
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[test]
+fn compressed_binaries_are_scanned_too() {
+    let root = std::env::temp_dir().join(format!("moscope-scan-{}", std::process::id()));
+    fs::create_dir_all(&root).unwrap();
+
+    let sample = fs::read("tests/samples/hello_arm64").expect("failed to read hello_arm64");
+    fs::write(root.join("a"), &sample).unwrap();
+    fs::write(root.join("b.gz"), gzip(&sample)).unwrap();
+    fs::write(root.join("notes.txt"), b"not a binary").unwrap();
+    // a compressed file that isn't a Mach-O is left out without a "Skipping" line
+    fs::write(root.join("ls.1.gz"), gzip(b".TH LS 1\n.SH NAME\nls - list directory contents\n")).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_moscope"))
+        .args([root.to_str().unwrap(), "--recursive", "--format", "json"])
+        .output()
+        .expect("failed to run moscope");
+    fs::remove_dir_all(&root).unwrap();
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(output.stderr.is_empty(), "{}", String::from_utf8_lossy(&output.stderr));
+    let reports: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let paths: Vec<&str> = reports.as_array().unwrap().iter().map(|r| r["relative_path"].as_str().unwrap()).collect();
+    assert_eq!(paths, ["a", "b.gz"]);
+    assert_eq!(reports[0]["identities"], reports[1]["identities"]);
+}
@@ -0,0 +1,159 @@
+use std::fs;
+use std::path::Path;
+
+use moscope::macho::analysis::{parse_slice, SliceOptions};
+use moscope::macho::fat::{FatArch, read_fat_archs, read_fat_header};
+use moscope::macho::header::MachOSlice;
+use moscope::macho::uuid::ParsedUuid;
+use moscope::reporting::canonical::to_canonical_json;
+use moscope::reporting::macho::{build_macho_report, MachOReport, ReportOptions};
+
+/*
+These tests lock in the JSON contract:
+    parse sample --> MachOReport --> JSON --> MachOReport
+and the two reports must be structurally equal. If a report field is renamed,
+retyped or dropped without updating Deserialize, this is where it shows up.
+*/
+
+fn all_sections() -> ReportOptions {
+    ReportOptions {
+        include_header: true,
+        include_segments: true,
+        include_dylibs: true,
+        include_rpaths: true,
+        include_loadcmds: true,
+        include_symbols: true,
+        include_strings: true,
+        include_fixups: true,
+        include_diagnostics: true,
+        dump_unknown_load_commands: false,
+    }
+}
+
+// Same per-slice pipeline as the CLI, raw bytes kept and debug symbols left in so both get round tripped too
+fn build_report(path: &str) -> MachOReport {
+    let data = fs::read(Path::new(path)).expect("failed to read sample");
+    let opts = all_sections();
+    let slice_opts = SliceOptions { raw_strings: true, include_debug_symbols: true, ..SliceOptions::default() };
+
+    let (is_fat, slices) = match read_fat_header(&data) {
+        Ok(fat_header) => {
+            let archs = read_fat_archs(&data, &fat_header).unwrap();
+            let slices = archs
+                .iter()
+                .map(|arch| match arch {
+                    FatArch::Arch32(a) => MachOSlice { offset: a.offset as u64, size: Some(a.size as u64) },
+                    FatArch::Arch64(a) => MachOSlice { offset: a.offset, size: Some(a.size) },
+                })
+                .collect();
+            (true, slices)
+        }
+        Err(_) => (false, vec![MachOSlice { offset: 0, size: None }]),
+    };
+
+    let parsed: Vec<_> = slices.iter().map(|slice| parse_slice(&data, slice, &slice_opts).unwrap()).collect();
+    let reports = parsed.iter().map(|p| p.build_report(&data, &opts).unwrap()).collect();
+    let uuids: Vec<ParsedUuid> = parsed.iter().filter_map(|p| p.uuid).collect();
+    build_macho_report(is_fat, reports, &uuids, &[], &opts)
+}
+
+fn assert_round_trips(path: &str) {
+    let report = build_report(path);
+
+    let json = serde_json::to_string_pretty(&report).expect("failed to serialize report");
+    let reparsed: MachOReport = serde_json::from_str(&json).expect("failed to deserialize report");
+
+    assert_eq!(report, reparsed, "{} did not survive a JSON round trip", path);
+
+    // And serializing the reparsed report must give back the exact same document
+    let json_again = serde_json::to_string_pretty(&reparsed).unwrap();
+    assert_eq!(json, json_again);
+}
+
+#[test]
+fn thin_arm64_report_round_trips() {
+    assert_round_trips("tests/samples/hello_arm64");
+}
+
+#[test]
+fn thin_x86_64_report_round_trips() {
+    assert_round_trips("tests/samples/hello_x86_64");
+}
+
+#[test]
+fn fat_report_round_trips() {
+    let report = build_report("tests/samples/hello_fat");
+    assert!(report.is_fat);
+    assert_eq!(report.architectures.len(), 2);
+    assert_eq!(report.uuids.len(), 2);
+    assert_ne!(report.uuids[0], report.uuids[1]);
+    // and every slice carries its own
+    let per_arch: Vec<_> = report.architectures.iter().filter_map(|arch| arch.uuid.clone()).collect();
+    assert_eq!(per_arch, report.uuids);
+
+    assert_round_trips("tests/samples/hello_fat");
+}
+
+#[test]
+fn report_round_trips_through_xml_plist() {
+    // --format plist has to produce something plist readers (and we) can load back
+    let report = build_report("tests/samples/hello_fat");
+
+    let mut xml = Vec::new();
+    plist::to_writer_xml(&mut xml, &report).expect("failed to serialize report as plist");
+    let reparsed: MachOReport = plist::from_bytes(&xml).expect("failed to parse plist back");
+
+    assert_eq!(report, reparsed);
+}
+
+#[test]
+fn canonical_json_hashes_identically_across_runs() {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    // Two independent parses of the same file must give the exact same canonical bytes
+    let hash = |path: &str| {
+        let json = to_canonical_json(&build_report(path)).expect("failed to canonicalize report");
+        let mut hasher = DefaultHasher::new();
+        json.hash(&mut hasher);
+        hasher.finish()
+    };
+
+    assert_eq!(hash("tests/samples/hello_fat"), hash("tests/samples/hello_fat"));
+    assert_ne!(hash("tests/samples/hello_arm64"), hash("tests/samples/hello_x86_64"));
+}
+
+#[test]
+fn round_trip_keeps_parsed_content() {
+    // Guard against a vacuous pass where everything is None/empty on both sides
+    let report = build_report("tests/samples/hello_arm64");
+    let arch = &report.architectures[0];
+
+    assert!(arch.header.is_some());
+    assert!(!arch.segments.as_ref().unwrap().is_empty());
+    assert!(!arch.dylibs.as_ref().unwrap().is_empty());
+    assert!(!arch.symbols.as_ref().unwrap().is_empty());
+    assert!(arch.strings.as_ref().unwrap().iter().any(|s| s.value == "Hello world!"));
+
+    // A well formed linker output shouldn't trip any structural checks
+    assert!(arch.diagnostics.as_ref().unwrap().is_empty());
+
+    // ld ad-hoc signs every arm64 output, the cdhash travels with the report
+    let signature = arch.code_signature.as_ref().expect("arm64 sample should be signed");
+    assert_eq!(signature.cdhash.as_ref().map(String::len), Some(40));
+
+    // ld's default 4GB page zero for 64-bit executables
+    assert_eq!(arch.pagezero_size, Some(0x1_0000_0000));
+    assert!(!arch.implicit_pagezero);
+
+    // ld links two-level by default, so imports know which dylib they come from
+    assert!(arch.two_level_namespace);
+    let symbols = arch.symbols.as_ref().unwrap();
+    assert!(symbols.iter().filter(|s| s.is_import).all(|s| s.library.as_deref().is_some_and(|lib| lib.starts_with("/usr/lib/"))));
+    assert!(symbols.iter().filter(|s| !s.is_import).all(|s| s.library.is_none()));
+
+    // Each import's library is whatever its ordinal names in the map
+    let ordinals = arch.ordinal_map.as_ref().unwrap();
+    assert_eq!(ordinals.first().map(|e| e.path.as_str()), Some("<self>"));
+    assert!(symbols.iter().filter_map(|s| s.library.as_ref()).all(|lib| ordinals.iter().any(|e| &e.path == lib)));
+}
@@ -0,0 +1,59 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/*
+`moscope -` reads the binary from stdin. These pipe bytes into the real binary and
+check that a Mach-O comes through intact, and that empty or non Mach-O input fails
+with a readable error instead of a parse error from deep inside the header code.
+*/
+
+fn run_with_stdin(args: &[&str], input: &[u8]) -> (bool, String, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_moscope"))
+        .args(args)
+        .env("MOSCOPE_NO_PROMPT", "1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to run moscope");
+
+    child.stdin.take().unwrap().write_all(input).expect("failed to write stdin");
+    let output = child.wait_with_output().expect("failed to wait for moscope");
+
+    (
+        output.status.success(),
+        String::from_utf8_lossy(&output.stdout).to_string(),
+        String::from_utf8_lossy(&output.stderr).to_string(),
+    )
+}
+
+#[test]
+fn stdin_matches_reading_the_file() {
+    let data = std::fs::read("tests/samples/hello_arm64").expect("failed to read hello_arm64");
+    let from_file = Command::new(env!("CARGO_BIN_EXE_moscope"))
+        .args(["tests/samples/hello_arm64", "--identify"])
+        .output()
+        .expect("failed to run moscope");
+
+    let hint = data.len().to_string();
+    let (ok, stdout, stderr) = run_with_stdin(&["-", "--identify", "--expected-size", &hint], &data);
+
+    assert!(ok, "stdin read failed: {}", stderr);
+    assert_eq!(stdout, String::from_utf8_lossy(&from_file.stdout));
+}
+
+#[test]
+fn empty_stdin_is_a_clear_error() {
+    let (ok, _, stderr) = run_with_stdin(&["-"], b"");
+
+    assert!(!ok);
+    assert!(stderr.contains("stdin is empty"), "unexpected error: {}", stderr);
+}
+
+#[test]
+fn non_macho_stdin_is_rejected() {
+    let (ok, _, stderr) = run_with_stdin(&["-"], b"#!/bin/sh\necho hi\n");
+
+    assert!(!ok);
+    assert!(stderr.contains("not a Mach-O or fat binary"), "unexpected error: {}", stderr);
+}
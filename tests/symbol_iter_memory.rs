@@ -0,0 +1,99 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use moscope::macho::constants::*;
+use moscope::macho::symtab::{parse_symbols, SymbolIter, SymtabCommand};
+
+/*
+Memory benchmark for SymbolIter vs the eager parse_symbols on a synthetic symbol table.
+
+    0x0            nlist_64[NSYMS]   every 1000th one an N_OSO stab, the rest N_SECT | N_EXT
+    NSYMS * 16     string table      "_sym<i>\0" per entry
+
+A counting global allocator (this test binary only) records the peak number of live bytes
+while each reader runs. The input buffer is built before the measurement starts, so the
+numbers are just what the reader itself holds on to. Run with --nocapture to see them.
+*/
+
+const NSYMS: usize = 300_000;
+
+struct PeakAlloc;
+
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for PeakAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            let live = LIVE.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+            PEAK.fetch_max(live, Ordering::SeqCst);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+        LIVE.fetch_sub(layout.size(), Ordering::SeqCst);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: PeakAlloc = PeakAlloc;
+
+// Peak bytes allocated on top of what was live when f started
+fn peak_during<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let start = LIVE.load(Ordering::SeqCst);
+    PEAK.store(start, Ordering::SeqCst);
+    let value = f();
+    (value, PEAK.load(Ordering::SeqCst) - start)
+}
+
+fn build_symtab() -> (Vec<u8>, SymtabCommand) {
+    let mut strtab = vec![0u8];
+    let mut nlists = Vec::with_capacity(NSYMS * 16);
+
+    for i in 0..NSYMS {
+        let n_strx = strtab.len() as u32;
+        strtab.extend_from_slice(format!("_sym{}\0", i).as_bytes());
+
+        let (n_type, n_sect) = if i % 1000 == 0 { (N_OSO, 0u8) } else { (N_SECT | N_EXT, 1u8) };
+        nlists.extend_from_slice(&n_strx.to_le_bytes());
+        nlists.push(n_type);
+        nlists.push(n_sect);
+        nlists.extend_from_slice(&0u16.to_le_bytes());
+        nlists.extend_from_slice(&(0x1_0000_0000u64 + i as u64 * 4).to_le_bytes());
+    }
+
+    let symtab = SymtabCommand {
+        cmd: LC_SYMTAB,
+        cmdsize: 24,
+        symoff: 0,
+        nsyms: NSYMS as u32,
+        stroff: nlists.len() as u32,
+        strsize: strtab.len() as u32,
+    };
+    nlists.extend_from_slice(&strtab);
+    (nlists, symtab)
+}
+
+#[test]
+fn symbol_iter_keeps_memory_bounded_on_a_large_symtab() {
+    let (data, symtab) = build_symtab();
+
+    let (all, eager_peak) = peak_during(|| parse_symbols(&data, &symtab, 0, true, false).unwrap().len());
+    let (debug, lazy_peak) = peak_during(|| {
+        SymbolIter::new(&data, &symtab, 0, true, false)
+            .filter(|sym| sym.as_ref().map_or(true, |s| s.is_debug))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+            .len()
+    });
+
+    println!("{} symbols: parse_symbols peak {} KiB, SymbolIter (debug only) peak {} KiB", NSYMS, eager_peak / 1024, lazy_peak / 1024);
+
+    assert_eq!(all, NSYMS);
+    assert_eq!(debug, NSYMS / 1000);
+    // Only the kept 0.1% should stay resident, give it plenty of slack
+    assert!(lazy_peak * 20 < eager_peak, "lazy {} vs eager {}", lazy_peak, eager_peak);
+}